@@ -0,0 +1,76 @@
+// THIS FILE IS AUTO-GENERATED BY ts-bindgen! DO NOT EDIT!
+
+import { invoke as invokeRaw } from "@tauri-apps/api";
+import { listen as listenRaw, type EventCallback, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type AppError =
+    | { code: "EmitFail", message: string, hint: string | null }
+    | { code: "TitleTooLongFail", message: string, hint: string | null, max_length: number };
+
+export function isAppError(error: unknown): error is AppError {
+    return typeof error === "object" && error !== null && "code" in error && "message" in error;
+}
+
+type TauriCommands = {
+    /** Returns nothing. */
+    ping: {
+        returns: void,
+        args: {  },
+        error: AppError
+    },
+    /** Takes primitives and borrowed strings. */
+    rename_conversation: {
+        returns: void,
+        args: { conversation_id: string, new_title: string, notify: boolean, retries: number, temperature: number },
+        error: AppError
+    },
+    /** State and app handle parameters are left out. */
+    list_titles: {
+        returns: Array<string>,
+        args: {  },
+        error: AppError
+    },
+    /** Nests collections. */
+    count_words: {
+        returns: Record<string, Array<number>>,
+        args: { texts: Array<Array<string>> },
+        error: AppError
+    },
+    undocumented: {
+        returns: number,
+        args: { count: number },
+        error: never
+    },
+    /** Errors hidden behind a `Result<T>` alias can't be typed. */
+    reload: {
+        returns: void,
+        args: {  },
+        error: unknown
+    }
+};
+
+/** Rejects with the command's `error`. */
+export function invoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T]["args"]): Promise<TauriCommands[T]["returns"]> {
+    return invokeRaw(cmd, args);
+}
+
+export type InvokeResult<T extends keyof TauriCommands> =
+    | { ok: true, value: TauriCommands[T]["returns"] }
+    | { ok: false, error: TauriCommands[T]["error"] };
+
+/** Resolves with the command's result or its `error`, so callers have to handle both. */
+export async function tryInvoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T]["args"]): Promise<InvokeResult<T>> {
+    try {
+        return { ok: true, value: await invokeRaw(cmd, args) };
+    } catch (error) {
+        return { ok: false, error: error as TauriCommands[T]["error"] };
+    }
+}
+
+type TauriEvents = {
+
+};
+
+export function listen<E extends keyof TauriEvents>(event: E, handler: EventCallback<TauriEvents[E]>): Promise<UnlistenFn> {
+    return listenRaw(event, handler);
+}
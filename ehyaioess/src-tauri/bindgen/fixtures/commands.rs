@@ -0,0 +1,64 @@
+// Input for the bindings generator's golden-file test; never compiled. Regenerate
+// `commands.d.ts` with `UPDATE_BINDINGS=1 cargo test -p ehyaioess-bindgen`.
+
+use std::collections::HashMap;
+use tauri::{async_runtime::RwLock, Runtime, State};
+
+/// Errors serialize by hand as `{ code, message, hint }` plus their fields.
+#[derive(Debug, Clone)]
+pub enum MyError {
+    EmitFail,
+    TitleTooLongFail { max_length: usize },
+}
+
+/// Returns nothing.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn ping() -> Result<(), MyError> {
+    Ok(())
+}
+
+/// Takes primitives and borrowed strings.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn rename_conversation(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    conversation_id: String,
+    new_title: &str,
+    notify: bool,
+    retries: u32,
+    temperature: f64,
+) -> Result<(), MyError> {
+    Ok(())
+}
+
+/// State and app handle parameters are left out.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_titles<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+) -> Result<Vec<String>, MyError> {
+    Ok(Vec::new())
+}
+
+/// Nests collections.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn count_words(
+    texts: Vec<Vec<String>>,
+) -> Result<HashMap<String, Vec<usize>>, MyError> {
+    Ok(HashMap::new())
+}
+
+#[tauri::command]
+pub fn undocumented(count: i64) -> usize {
+    0
+}
+
+// Not a command, so not in the bindings.
+pub fn helper(value: Option<String>) -> Result<(), MyError> {
+    Ok(())
+}
+
+/// Errors hidden behind a `Result<T>` alias can't be typed.
+#[tauri::command]
+pub fn reload() -> tauri::Result<()> {
+    Ok(())
+}
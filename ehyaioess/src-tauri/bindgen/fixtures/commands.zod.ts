@@ -0,0 +1,47 @@
+// THIS FILE IS AUTO-GENERATED BY ts-bindgen! DO NOT EDIT!
+
+import { z } from "zod";
+
+export const AppErrorSchema = z.union([
+    z.object({ code: z.literal("EmitFail"), message: z.string(), hint: z.string().nullable() }),
+    z.object({ code: z.literal("TitleTooLongFail"), message: z.string(), hint: z.string().nullable(), max_length: z.number() }),
+]);
+
+/** What each command takes, resolves with and rejects with. */
+export const commandSchemas = {
+    ping: {
+        args: z.object({}),
+        returns: z.null(),
+        error: z.lazy(() => AppErrorSchema)
+    },
+    rename_conversation: {
+        args: z.object({ conversation_id: z.string(), new_title: z.string(), notify: z.boolean(), retries: z.number(), temperature: z.number() }),
+        returns: z.null(),
+        error: z.lazy(() => AppErrorSchema)
+    },
+    list_titles: {
+        args: z.object({}),
+        returns: z.array(z.string()),
+        error: z.lazy(() => AppErrorSchema)
+    },
+    count_words: {
+        args: z.object({ texts: z.array(z.array(z.string())) }),
+        returns: z.record(z.string(), z.array(z.number())),
+        error: z.lazy(() => AppErrorSchema)
+    },
+    undocumented: {
+        args: z.object({ count: z.number() }),
+        returns: z.number(),
+        error: z.never()
+    },
+    reload: {
+        args: z.object({}),
+        returns: z.null(),
+        error: z.unknown()
+    }
+};
+
+/** The payload each event carries. */
+export const eventSchemas = {
+
+};
@@ -0,0 +1,64 @@
+// THIS FILE IS AUTO-GENERATED BY ts-bindgen! DO NOT EDIT!
+
+import { invoke as invokeRaw } from "@tauri-apps/api";
+import { listen as listenRaw, type EventCallback, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type Shortcuts = Record<string, string>;
+
+export interface TaskPayload {
+    name: string,
+}
+
+export interface TitleChangedEventPayload {
+    conversation_id: string,
+    title: string,
+}
+
+export type AppError =
+    | { code: "EmitFail", message: string, hint: string | null };
+
+export function isAppError(error: unknown): error is AppError {
+    return typeof error === "object" && error !== null && "code" in error && "message" in error;
+}
+
+type TauriCommands = {
+    /** Payloads written out in the call. */
+    rename: {
+        returns: void,
+        args: { conversation_id: string, title: string },
+        error: AppError
+    }
+};
+
+/** Rejects with the command's `error`. */
+export function invoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T]["args"]): Promise<TauriCommands[T]["returns"]> {
+    return invokeRaw(cmd, args);
+}
+
+export type InvokeResult<T extends keyof TauriCommands> =
+    | { ok: true, value: TauriCommands[T]["returns"] }
+    | { ok: false, error: TauriCommands[T]["error"] };
+
+/** Resolves with the command's result or its `error`, so callers have to handle both. */
+export async function tryInvoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T]["args"]): Promise<InvokeResult<T>> {
+    try {
+        return { ok: true, value: await invokeRaw(cmd, args) };
+    } catch (error) {
+        return { ok: false, error: error as TauriCommands[T]["error"] };
+    }
+}
+
+type TauriEvents = {
+    enabled_changed: boolean,
+    everything_deleted: null,
+    names_listed: Array<string>,
+    renamed: string,
+    shortcuts_changed: Shortcuts,
+    task_finished: TaskPayload,
+    task_started: TaskPayload,
+    title_changed: TitleChangedEventPayload
+};
+
+export function listen<E extends keyof TauriEvents>(event: E, handler: EventCallback<TauriEvents[E]>): Promise<UnlistenFn> {
+    return listenRaw(event, handler);
+}
@@ -0,0 +1,66 @@
+// Input for the bindings generator's golden-file test; never compiled. Regenerate
+// `events.d.ts` with `UPDATE_BINDINGS=1 cargo test -p ehyaioess-bindgen`.
+
+use serde::Serialize;
+use tauri::{AppHandle, Runtime};
+
+use crate::events;
+
+#[derive(Debug, Clone)]
+pub enum MyError {
+    EmitFail,
+}
+
+pub type Shortcuts = std::collections::BTreeMap<String, String>;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TitleChangedEventPayload {
+    pub conversation_id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskPayload {
+    pub name: String,
+}
+
+impl From<&str> for TaskPayload {
+    fn from(name: &str) -> Self {
+        Self { name: name.to_owned() }
+    }
+}
+
+/// Payloads written out in the call.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn rename<R: Runtime>(app_handle: AppHandle<R>, conversation_id: String, title: String) -> Result<(), MyError> {
+    events::emit_all(
+        &app_handle,
+        "title_changed",
+        TitleChangedEventPayload { conversation_id: conversation_id.clone(), title },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    events::emit_all(&app_handle, "task_started", TaskPayload::from("rename")).map_err(|_| MyError::EmitFail)?;
+    events::emit_all(&app_handle, "renamed", conversation_id.to_string()).map_err(|_| MyError::EmitFail)?;
+    events::emit_all(&app_handle, "everything_deleted", ()).map_err(|_| MyError::EmitFail)
+}
+
+/// Payloads whose types only show where the same events are sent elsewhere.
+pub fn restore<R: Runtime>(app_handle: &AppHandle<R>, settings: &Settings) {
+    let enabled = settings.enabled;
+    let _ = events::emit_all(app_handle, "enabled_changed", enabled);
+}
+
+/// Payloads bound beforehand.
+pub fn announce<R: Runtime>(app_handle: &AppHandle<R>, enabled: bool, shortcuts: &Shortcuts) {
+    let payload = TaskPayload { name: "announce".to_owned() };
+    let _ = events::emit_all(app_handle, "task_finished", payload);
+    let _ = events::emit_all(app_handle, "enabled_changed", enabled);
+    let copied: Shortcuts = shortcuts.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    let _ = events::emit_all(app_handle, "shortcuts_changed", copied);
+    let tasks: Vec<TaskPayload> = vec![TaskPayload::from("a"), TaskPayload::from("b")];
+    for task in tasks {
+        let _ = events::emit_all(app_handle, "task_started", task);
+    }
+    let names = ["a", "b"].iter().map(|name| name.to_string()).collect::<Vec<String>>();
+    let _ = events::emit_all(app_handle, "names_listed", names);
+}
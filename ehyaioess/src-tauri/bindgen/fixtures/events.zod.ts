@@ -0,0 +1,32 @@
+// THIS FILE IS AUTO-GENERATED BY ts-bindgen! DO NOT EDIT!
+
+import { z } from "zod";
+
+export const ShortcutsSchema = z.record(z.string(), z.string());
+
+export const TaskPayloadSchema = z.object({ name: z.string() });
+
+export const TitleChangedEventPayloadSchema = z.object({ conversation_id: z.string(), title: z.string() });
+
+export const AppErrorSchema = z.object({ code: z.literal("EmitFail"), message: z.string(), hint: z.string().nullable() });
+
+/** What each command takes, resolves with and rejects with. */
+export const commandSchemas = {
+    rename: {
+        args: z.object({ conversation_id: z.string(), title: z.string() }),
+        returns: z.null(),
+        error: z.lazy(() => AppErrorSchema)
+    }
+};
+
+/** The payload each event carries. */
+export const eventSchemas = {
+    enabled_changed: z.boolean(),
+    everything_deleted: z.null(),
+    names_listed: z.array(z.string()),
+    renamed: z.string(),
+    shortcuts_changed: z.lazy(() => ShortcutsSchema),
+    task_finished: z.lazy(() => TaskPayloadSchema),
+    task_started: z.lazy(() => TaskPayloadSchema),
+    title_changed: z.lazy(() => TitleChangedEventPayloadSchema)
+};
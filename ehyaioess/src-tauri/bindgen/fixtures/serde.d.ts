@@ -0,0 +1,73 @@
+// THIS FILE IS AUTO-GENERATED BY ts-bindgen! DO NOT EDIT!
+
+import { invoke as invokeRaw } from "@tauri-apps/api";
+import { listen as listenRaw, type EventCallback, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type Backend = { kind: "web_dav", url: string, user_name: string } | { kind: "s3_bucket", "bucket-name": string } | { kind: "local" } & Metadata | { kind: "disabled" };
+
+export type Limit = number | { min: number, max: number } | null;
+
+export type Message = { t: "Text", c: string } | { t: "Pair", c: [number, number] } | { t: "Empty" };
+
+export interface Metadata {
+    created: number,
+}
+
+export interface Profile extends Metadata {
+    displayName: string,
+    type: ProfileKind,
+    ref: string,
+    avatarUrl?: string | null,
+}
+
+export type ProfileKind = "API_KEY" | "O_AUTH_TOKEN" | "none";
+
+export type Tag = string;
+
+export type AppError =
+    | { code: "EmitFail", message: string, hint: string | null };
+
+export function isAppError(error: unknown): error is AppError {
+    return typeof error === "object" && error !== null && "code" in error && "message" in error;
+}
+
+type TauriCommands = {
+    /** Tauri takes arguments in camelCase by default. */
+    save_profile: {
+        returns: Backend,
+        args: { profileId: string, profile: Profile, tags: Array<Tag> },
+        error: AppError
+    },
+    /** Unless told otherwise. */
+    send_message: {
+        returns: Limit,
+        args: { conversation_id: string, message: Message },
+        error: AppError
+    }
+};
+
+/** Rejects with the command's `error`. */
+export function invoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T]["args"]): Promise<TauriCommands[T]["returns"]> {
+    return invokeRaw(cmd, args);
+}
+
+export type InvokeResult<T extends keyof TauriCommands> =
+    | { ok: true, value: TauriCommands[T]["returns"] }
+    | { ok: false, error: TauriCommands[T]["error"] };
+
+/** Resolves with the command's result or its `error`, so callers have to handle both. */
+export async function tryInvoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T]["args"]): Promise<InvokeResult<T>> {
+    try {
+        return { ok: true, value: await invokeRaw(cmd, args) };
+    } catch (error) {
+        return { ok: false, error: error as TauriCommands[T]["error"] };
+    }
+}
+
+type TauriEvents = {
+
+};
+
+export function listen<E extends keyof TauriEvents>(event: E, handler: EventCallback<TauriEvents[E]>): Promise<UnlistenFn> {
+    return listenRaw(event, handler);
+}
@@ -0,0 +1,85 @@
+// Input for the bindings generator's golden-file test; never compiled. Regenerate
+// `serde.d.ts` with `UPDATE_BINDINGS=1 cargo test -p ehyaioess-bindgen`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone)]
+pub enum MyError {
+    EmitFail,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub display_name: String,
+    #[serde(rename = "type")]
+    pub kind: ProfileKind,
+    pub r#ref: String,
+    #[serde(skip)]
+    pub cached_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar_url: Option<String>,
+    #[serde(flatten)]
+    pub extra: Metadata,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    #[serde(rename(serialize = "created", deserialize = "created_at"))]
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProfileKind {
+    ApiKey,
+    OAuthToken,
+    #[serde(rename = "none")]
+    Anonymous,
+    #[serde(skip)]
+    Internal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Backend {
+    WebDav { url: String, user_name: String },
+    #[serde(rename_all = "kebab-case")]
+    S3Bucket { bucket_name: String },
+    Local(Metadata),
+    Disabled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "t", content = "c")]
+pub enum Message {
+    Text(String),
+    Pair(u8, u8),
+    Empty,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Limit {
+    Count(usize),
+    Range { min: usize, max: usize },
+    Unlimited,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Tag {
+    name: String,
+}
+
+/// Tauri takes arguments in camelCase by default.
+#[tauri::command]
+pub async fn save_profile(profile_id: String, profile: Profile, tags: Vec<Tag>) -> Result<Backend, MyError> {
+    Ok(Backend::Disabled)
+}
+
+/// Unless told otherwise.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn send_message(conversation_id: String, message: Message) -> Result<Limit, MyError> {
+    Ok(Limit::Unlimited)
+}
@@ -0,0 +1,38 @@
+// THIS FILE IS AUTO-GENERATED BY ts-bindgen! DO NOT EDIT!
+
+import { z } from "zod";
+
+export const BackendSchema = z.union([z.object({ kind: z.literal("web_dav"), url: z.string(), user_name: z.string() }), z.object({ kind: z.literal("s3_bucket"), "bucket-name": z.string() }), z.object({ kind: z.literal("local") }).and(z.lazy(() => MetadataSchema)), z.object({ kind: z.literal("disabled") })]);
+
+export const LimitSchema = z.union([z.number(), z.object({ min: z.number(), max: z.number() }), z.null()]);
+
+export const MessageSchema = z.union([z.object({ t: z.literal("Text"), c: z.string() }), z.object({ t: z.literal("Pair"), c: z.tuple([z.number(), z.number()]) }), z.object({ t: z.literal("Empty") })]);
+
+export const MetadataSchema = z.object({ created: z.number() });
+
+export const ProfileSchema = z.object({ displayName: z.string(), type: z.lazy(() => ProfileKindSchema), ref: z.string(), avatarUrl: z.string().nullable().optional() }).and(z.lazy(() => MetadataSchema));
+
+export const ProfileKindSchema = z.union([z.literal("API_KEY"), z.literal("O_AUTH_TOKEN"), z.literal("none")]);
+
+export const TagSchema = z.string();
+
+export const AppErrorSchema = z.object({ code: z.literal("EmitFail"), message: z.string(), hint: z.string().nullable() });
+
+/** What each command takes, resolves with and rejects with. */
+export const commandSchemas = {
+    save_profile: {
+        args: z.object({ profileId: z.string(), profile: z.lazy(() => ProfileSchema), tags: z.array(z.lazy(() => TagSchema)) }),
+        returns: z.lazy(() => BackendSchema),
+        error: z.lazy(() => AppErrorSchema)
+    },
+    send_message: {
+        args: z.object({ conversation_id: z.string(), message: z.lazy(() => MessageSchema) }),
+        returns: z.lazy(() => LimitSchema),
+        error: z.lazy(() => AppErrorSchema)
+    }
+};
+
+/** The payload each event carries. */
+export const eventSchemas = {
+
+};
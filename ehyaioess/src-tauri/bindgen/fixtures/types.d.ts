@@ -0,0 +1,76 @@
+// THIS FILE IS AUTO-GENERATED BY ts-bindgen! DO NOT EDIT!
+
+import { invoke as invokeRaw } from "@tauri-apps/api";
+import { listen as listenRaw, type EventCallback, type UnlistenFn } from "@tauri-apps/api/event";
+
+/**
+ * A file attached to a conversation.
+ *
+ * Doc comments carry over as TSDoc, *\/ and all.
+ */
+export interface Attachment {
+    id: string,
+    /** Where the file was copied to. */
+    path: string,
+    /** In bytes, when known. */
+    size: number | null,
+}
+
+export type Change = { Renamed: string } | { Moved: [string, string] } | { Attached: { attachment: Attachment, visibility: Visibility } } | "Cleared";
+
+export type Checksum = string;
+
+export interface Page<T> {
+    items: Array<T>,
+    next: number | null,
+}
+
+export type Visibility = "Private" | "Shared";
+
+export type AppError =
+    | { code: "EmitFail", message: string, hint: string | null };
+
+export function isAppError(error: unknown): error is AppError {
+    return typeof error === "object" && error !== null && "code" in error && "message" in error;
+}
+
+type TauriCommands = {
+    /** Takes optional and custom types. */
+    attach: {
+        returns: Checksum | null,
+        args: { conversation_id: string, attachment: Attachment, visibility: Visibility | null },
+        error: AppError
+    },
+    /** Returns a generic type, declaring what it's used with. */
+    list_changes: {
+        returns: Page<Change>,
+        args: { conversation_id: string, after: number | null },
+        error: AppError
+    }
+};
+
+/** Rejects with the command's `error`. */
+export function invoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T]["args"]): Promise<TauriCommands[T]["returns"]> {
+    return invokeRaw(cmd, args);
+}
+
+export type InvokeResult<T extends keyof TauriCommands> =
+    | { ok: true, value: TauriCommands[T]["returns"] }
+    | { ok: false, error: TauriCommands[T]["error"] };
+
+/** Resolves with the command's result or its `error`, so callers have to handle both. */
+export async function tryInvoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T]["args"]): Promise<InvokeResult<T>> {
+    try {
+        return { ok: true, value: await invokeRaw(cmd, args) };
+    } catch (error) {
+        return { ok: false, error: error as TauriCommands[T]["error"] };
+    }
+}
+
+type TauriEvents = {
+
+};
+
+export function listen<E extends keyof TauriEvents>(event: E, handler: EventCallback<TauriEvents[E]>): Promise<UnlistenFn> {
+    return listenRaw(event, handler);
+}
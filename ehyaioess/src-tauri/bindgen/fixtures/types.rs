@@ -0,0 +1,71 @@
+// Input for the bindings generator's golden-file test; never compiled. Regenerate
+// `types.d.ts` with `UPDATE_BINDINGS=1 cargo test -p ehyaioess-bindgen`.
+
+use serde::{Deserialize, Serialize};
+use tauri::{async_runtime::RwLock, State};
+
+#[derive(Debug, Clone)]
+pub enum MyError {
+    EmitFail,
+}
+
+/// A file attached to a conversation.
+///
+/// Doc comments carry over as TSDoc, */ and all.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: uuid::Uuid,
+    /// Where the file was copied to.
+    pub path: std::path::PathBuf,
+    /// In bytes, when known.
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Visibility {
+    Private,
+    Shared,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Change {
+    Renamed(String),
+    Moved(String, String),
+    Attached { attachment: Attachment, visibility: Visibility },
+    Cleared,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checksum(pub String);
+
+/// Never referenced by a command, so never declared.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Unused {
+    pub value: String,
+}
+
+/// Takes optional and custom types.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn attach(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    conversation_id: uuid::Uuid,
+    attachment: Attachment,
+    visibility: Option<Visibility>,
+) -> Result<Option<Checksum>, MyError> {
+    Ok(None)
+}
+
+/// Returns a generic type, declaring what it's used with.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_changes(
+    conversation_id: uuid::Uuid,
+    after: Option<usize>,
+) -> Result<Page<Change>, MyError> {
+    Ok(Page { items: Vec::new(), next: None })
+}
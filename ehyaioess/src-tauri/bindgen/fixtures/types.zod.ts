@@ -0,0 +1,34 @@
+// THIS FILE IS AUTO-GENERATED BY ts-bindgen! DO NOT EDIT!
+
+import { z } from "zod";
+
+export const AttachmentSchema = z.object({ id: z.string(), path: z.string(), size: z.number().nullable() });
+
+export const ChangeSchema = z.union([z.object({ Renamed: z.string() }), z.object({ Moved: z.tuple([z.string(), z.string()]) }), z.object({ Attached: z.object({ attachment: z.lazy(() => AttachmentSchema), visibility: z.lazy(() => VisibilitySchema) }) }), z.literal("Cleared")]);
+
+export const ChecksumSchema = z.string();
+
+export const PageSchema = <T extends z.ZodTypeAny>(T: T) => z.object({ items: z.array(T), next: z.number().nullable() });
+
+export const VisibilitySchema = z.union([z.literal("Private"), z.literal("Shared")]);
+
+export const AppErrorSchema = z.object({ code: z.literal("EmitFail"), message: z.string(), hint: z.string().nullable() });
+
+/** What each command takes, resolves with and rejects with. */
+export const commandSchemas = {
+    attach: {
+        args: z.object({ conversation_id: z.string(), attachment: z.lazy(() => AttachmentSchema), visibility: z.lazy(() => VisibilitySchema).nullable() }),
+        returns: z.lazy(() => ChecksumSchema).nullable(),
+        error: z.lazy(() => AppErrorSchema)
+    },
+    list_changes: {
+        args: z.object({ conversation_id: z.string(), after: z.number().nullable() }),
+        returns: z.lazy(() => PageSchema(z.lazy(() => ChangeSchema))),
+        error: z.lazy(() => AppErrorSchema)
+    }
+};
+
+/** The payload each event carries. */
+export const eventSchemas = {
+
+};
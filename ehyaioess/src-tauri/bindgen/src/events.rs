@@ -0,0 +1,198 @@
+//! Events sent to the frontend, found where they're sent.
+
+use crate::types::type_args;
+
+/// Finds the events sent with `events::emit_all(app_handle, "name", payload)` and the
+/// types of their payloads, as far as the code around each call shows them.
+#[derive(Default)]
+pub(crate) struct EventCollector {
+    /// Parameters and `let` bindings seen so far in the current function, with their
+    /// types when known.
+    bindings: std::collections::HashMap<String, Option<syn::Type>>,
+    function: String,
+    /// Payload types by event name.
+    events: std::collections::BTreeMap<String, syn::Type>,
+    /// Events sent with a payload of no obvious type, and the functions sending them.
+    untyped: Vec<(String, String)>,
+}
+
+impl EventCollector {
+    /// Payload types by event name, once every source has been visited. An event only needs
+    /// its payload type to show where it's sent once.
+    pub(crate) fn events(self) -> std::collections::BTreeMap<String, syn::Type> {
+        for (event, function) in &self.untyped {
+            assert!(
+                self.events.contains_key(event),
+                "Can't tell the payload type of the {} event sent in {}; give the payload a type annotation",
+                event,
+                function,
+            );
+        }
+        self.events
+    }
+
+    fn visit_function(&mut self, sig: &syn::Signature, visit_body: impl FnOnce(&mut Self)) {
+        let outer = std::mem::take(&mut self.bindings);
+        let outer_function = std::mem::replace(&mut self.function, sig.ident.to_string());
+        for input in &sig.inputs {
+            if let syn::FnArg::Typed(pat_type) = input {
+                if let syn::Pat::Ident(pat_ident) = &*pat_type.pat {
+                    self.bindings
+                        .insert(pat_ident.ident.to_string(), Some((*pat_type.ty).clone()));
+                }
+            }
+        }
+        visit_body(self);
+        self.bindings = outer;
+        self.function = outer_function;
+    }
+
+    /// The type of `expr`, if it's spelled out or obvious.
+    fn type_of(&self, expr: &syn::Expr) -> Option<syn::Type> {
+        match expr {
+            syn::Expr::Struct(expr_struct) => Some(syn::Type::Path(syn::TypePath {
+                qself: None,
+                path: expr_struct.path.clone(),
+            })),
+            // `Type::from(...)`, `Type::new(...)` and the like
+            syn::Expr::Call(expr_call) => match &*expr_call.func {
+                syn::Expr::Path(expr_path) if expr_path.path.segments.len() >= 2 => {
+                    let mut path = expr_path.path.clone();
+                    path.segments.pop();
+                    path.segments.pop_punct();
+                    let is_type = path.segments.last().is_some_and(|segment| {
+                        segment.ident.to_string().starts_with(char::is_uppercase)
+                    });
+                    is_type.then(|| syn::Type::Path(syn::TypePath { qself: None, path }))
+                }
+                _ => None,
+            },
+            syn::Expr::MethodCall(expr_method_call) if expr_method_call.method == "to_string" => {
+                Some(syn::parse_quote!(String))
+            }
+            syn::Expr::MethodCall(expr_method_call) if expr_method_call.method == "clone" => {
+                self.type_of(&expr_method_call.receiver)
+            }
+            syn::Expr::MethodCall(syn::ExprMethodCall {
+                method,
+                turbofish: Some(turbofish),
+                ..
+            }) if method == "collect" => match turbofish.args.first() {
+                Some(syn::GenericArgument::Type(ty)) => Some(ty.clone()),
+                _ => None,
+            },
+            syn::Expr::Macro(expr_macro) if expr_macro.mac.path.is_ident("format") => {
+                Some(syn::parse_quote!(String))
+            }
+            syn::Expr::Reference(expr_reference) => self.type_of(&expr_reference.expr),
+            syn::Expr::Paren(expr_paren) => self.type_of(&expr_paren.expr),
+            syn::Expr::Tuple(expr_tuple) if expr_tuple.elems.is_empty() => {
+                Some(syn::parse_quote!(()))
+            }
+            syn::Expr::Lit(expr_lit) => match &expr_lit.lit {
+                syn::Lit::Str(_) => Some(syn::parse_quote!(String)),
+                syn::Lit::Bool(_) => Some(syn::parse_quote!(bool)),
+                syn::Lit::Int(_) | syn::Lit::Float(_) => Some(syn::parse_quote!(f64)),
+                _ => None,
+            },
+            syn::Expr::Path(expr_path) => expr_path
+                .path
+                .get_ident()
+                .and_then(|ident| self.bindings.get(&ident.to_string()).cloned().flatten()),
+            _ => None,
+        }
+    }
+}
+
+impl<'ast> syn::visit::Visit<'ast> for EventCollector {
+    fn visit_item_fn(&mut self, item_fn: &'ast syn::ItemFn) {
+        self.visit_function(&item_fn.sig, |collector| {
+            syn::visit::visit_item_fn(collector, item_fn)
+        });
+    }
+
+    fn visit_impl_item_fn(&mut self, impl_item_fn: &'ast syn::ImplItemFn) {
+        self.visit_function(&impl_item_fn.sig, |collector| {
+            syn::visit::visit_impl_item_fn(collector, impl_item_fn)
+        });
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        syn::visit::visit_local(self, local);
+        match &local.pat {
+            syn::Pat::Type(pat_type) => {
+                if let syn::Pat::Ident(pat_ident) = &*pat_type.pat {
+                    self.bindings
+                        .insert(pat_ident.ident.to_string(), Some((*pat_type.ty).clone()));
+                }
+            }
+            syn::Pat::Ident(pat_ident) => {
+                let ty = local
+                    .init
+                    .as_ref()
+                    .and_then(|init| self.type_of(&init.expr));
+                self.bindings.insert(pat_ident.ident.to_string(), ty);
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_expr_for_loop(&mut self, expr_for_loop: &'ast syn::ExprForLoop) {
+        if let syn::Pat::Ident(pat_ident) = &*expr_for_loop.pat {
+            let item_type = match self.type_of(&expr_for_loop.expr) {
+                Some(syn::Type::Path(type_path)) => type_path
+                    .path
+                    .segments
+                    .last()
+                    .filter(|segment| segment.ident == "Vec")
+                    .and_then(|segment| type_args(segment).first().map(|ty| (*ty).clone())),
+                _ => None,
+            };
+            self.bindings.insert(pat_ident.ident.to_string(), item_type);
+        }
+        syn::visit::visit_expr_for_loop(self, expr_for_loop);
+    }
+
+    fn visit_expr_call(&mut self, expr_call: &'ast syn::ExprCall) {
+        syn::visit::visit_expr_call(self, expr_call);
+        let syn::Expr::Path(func) = &*expr_call.func else {
+            return;
+        };
+        if func
+            .path
+            .segments
+            .last()
+            .is_none_or(|segment| segment.ident != "emit_all")
+            || expr_call.args.len() != 3
+        {
+            return;
+        }
+        let syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(event),
+            ..
+        }) = &expr_call.args[1]
+        else {
+            return;
+        };
+        let event = event.value();
+        let Some(ty) = self.type_of(&expr_call.args[2]) else {
+            self.untyped.push((event, self.function.clone()));
+            return;
+        };
+        match self.events.get(&event) {
+            Some(known)
+                if quote::quote! {#known}.to_string() != quote::quote! {#ty}.to_string() =>
+            {
+                panic!(
+                    "The {} event is sent with both {} and {} payloads",
+                    event,
+                    quote::quote! {#known},
+                    quote::quote! {#ty},
+                )
+            }
+            _ => {
+                self.events.insert(event, ty);
+            }
+        }
+    }
+}
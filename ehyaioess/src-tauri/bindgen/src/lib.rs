@@ -0,0 +1,470 @@
+//! Generates the frontend's TypeScript bindings for the app's Tauri commands and events
+//! from its Rust sources. Run by the `ts-bindgen` binary; see its `--help`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::{
+    events::EventCollector,
+    types::{
+        collect_type_definitions, doc_comment, error_variants, rename_case, rust_type_to_shape,
+        ts_key, tsdoc, type_args, type_definition, Definition, Member, Shape,
+    },
+    zod::{definition_to_zod, object_to_zod, shape_to_zod},
+};
+
+mod events;
+mod types;
+mod zod;
+
+/// What the generator emits for a file of commands.
+pub struct Bindings {
+    /// The `tauri_commands.d.ts` the frontend imports `invoke` and `listen` from.
+    pub definitions: String,
+    /// The action catalog served by `list_app_actions`, as JSON.
+    pub actions: String,
+    /// Zod schemas for the same commands, events and types, for validating what crosses IPC
+    /// at runtime. Only written when asked for, as they need `zod` installed.
+    pub schemas: String,
+}
+
+struct Command {
+    name: String,
+    args: Vec<Member>,
+    returns: Shape,
+    error: Shape,
+    doc: Option<String>,
+}
+
+// Fields in alphabetical order, the catalog's order whether or not serde_json preserves
+// insertion order.
+#[derive(Serialize)]
+struct Action {
+    args: Vec<ActionArg>,
+    description: String,
+    name: String,
+    returns: String,
+}
+
+#[derive(Serialize)]
+struct ActionArg {
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+/// Reads the `.rs` files directly in `dirs`, in a stable order.
+pub fn read_sources(dirs: &[impl AsRef<Path>]) -> io::Result<Vec<String>> {
+    let mut sources = Vec::new();
+    for dir in dirs {
+        let mut paths = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        paths.sort();
+        for path in paths {
+            if path.extension().and_then(|ext| ext.to_str()) == Some("rs") {
+                sources.push(std::fs::read_to_string(path)?);
+            }
+        }
+    }
+    Ok(sources)
+}
+
+/// Where `actual` first differs from `expected`, ignoring line endings, or `None` when they
+/// match.
+pub fn difference(expected: &str, actual: &str) -> Option<String> {
+    let expected = expected.replace("\r\n", "\n");
+    if expected == actual {
+        return None;
+    }
+    let (line, (expected_line, actual_line)) = expected
+        .lines()
+        .chain(std::iter::repeat("<end of file>"))
+        .zip(actual.lines().chain(std::iter::repeat("<end of file>")))
+        .enumerate()
+        .find(|(_, (expected, actual))| expected != actual)
+        .unwrap_or((0, ("", "")));
+    Some(format!(
+        "first difference on line {}:\n  expected: {}\n  actual:   {}",
+        line + 1,
+        expected_line,
+        actual_line,
+    ))
+}
+
+/// The name of `E` in a `Result<T, E>` return type, or `Err` for a `Result<T>` alias that
+/// hides it.
+fn result_error(return_type: &syn::Type) -> Option<Result<String, ()>> {
+    let syn::Type::Path(type_path) = return_type else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+    Some(match type_args(segment).get(1) {
+        Some(syn::Type::Path(error_path)) => {
+            Ok(error_path.path.segments.last().unwrap().ident.to_string())
+        }
+        _ => Err(()),
+    })
+}
+
+/// Generates bindings for the commands in `contents` and the events sent in `sources`,
+/// declaring the types they use from the structs and enums in `sources`.
+pub fn generate(contents: &str, sources: &[String]) -> Bindings {
+    let ast = syn::parse_file(contents).unwrap();
+
+    let mut commands = Vec::new();
+    let mut actions = Vec::new();
+    let mut referenced = BTreeSet::new();
+    let mut error_types = BTreeSet::new();
+
+    for item in ast.items {
+        if let syn::Item::Fn(item_fn) = item {
+            let tauri_command_attr = item_fn.attrs.iter().find(|attr| {
+                attr.path()
+                    .segments
+                    .iter()
+                    .map(|seg| seg.ident.to_string())
+                    .collect::<Vec<_>>()
+                    == ["tauri", "command"]
+            });
+
+            if let Some(tauri_command_attr) = tauri_command_attr {
+                let command_name = item_fn.sig.ident.to_string();
+
+                // Tauri takes arguments in camelCase unless told otherwise
+                let mut rename_all = "camelCase".to_owned();
+                if let syn::Meta::List(_) = &tauri_command_attr.meta {
+                    tauri_command_attr
+                        .parse_nested_meta(|meta| {
+                            if meta.path.is_ident("rename_all") {
+                                rename_all = meta.value()?.parse::<syn::LitStr>()?.value();
+                            }
+                            Ok(())
+                        })
+                        .unwrap();
+                }
+
+                let mut args = Vec::new();
+                let mut action_args = Vec::new();
+                for arg in &item_fn.sig.inputs {
+                    if let syn::FnArg::Typed(pat_type) = arg {
+                        if let syn::Pat::Ident(pat_ident) = &*pat_type.pat {
+                            // Filter out State, AppHandle and Window parameters
+                            let ty_string = quote::quote! {#pat_type.ty}.to_string();
+                            if !ty_string.contains("State")
+                                && !ty_string.contains("AppHandle")
+                                && !ty_string.contains("Window <")
+                            {
+                                let shape = rust_type_to_shape(&pat_type.ty, &[], &mut referenced);
+                                let arg_name = rename_case(
+                                    &syn::ext::IdentExt::unraw(&pat_ident.ident).to_string(),
+                                    &rename_all,
+                                    false,
+                                );
+                                action_args.push(ActionArg {
+                                    name: arg_name.clone(),
+                                    ty: shape.to_ts(),
+                                });
+                                args.push(Member::new(arg_name, shape));
+                            }
+                        }
+                    }
+                }
+
+                let (returns, error) = match &item_fn.sig.output {
+                    syn::ReturnType::Type(_, ty) => {
+                        let error = match result_error(ty) {
+                            Some(Ok(error_type)) => {
+                                error_types.insert(error_type);
+                                Shape::Named("AppError".to_owned(), Vec::new())
+                            }
+                            Some(Err(())) => Shape::Unknown,
+                            None => Shape::Never,
+                        };
+                        (rust_type_to_shape(ty, &[], &mut referenced), error)
+                    }
+                    syn::ReturnType::Default => (Shape::Void, Shape::Never),
+                };
+
+                let doc = doc_comment(&item_fn.attrs);
+                let description = doc
+                    .iter()
+                    .flat_map(|doc| doc.lines())
+                    .map(str::trim)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                actions.push(Action {
+                    args: action_args,
+                    description,
+                    name: command_name.clone(),
+                    returns: returns.to_ts(),
+                });
+                commands.push(Command {
+                    name: command_name,
+                    args,
+                    returns,
+                    error,
+                    doc,
+                });
+            }
+        }
+    }
+
+    // events, typed by their payloads
+    let mut collector = EventCollector::default();
+    for source in sources {
+        syn::visit::visit_file(&mut collector, &syn::parse_file(source).unwrap());
+    }
+    let events: Vec<_> = collector
+        .events()
+        .iter()
+        .map(|(event, ty)| {
+            let shape = match ty {
+                syn::Type::Tuple(tuple_type) if tuple_type.elems.is_empty() => Shape::Null,
+                ty => rust_type_to_shape(ty, &[], &mut referenced),
+            };
+            Member::new(event.clone(), shape)
+        })
+        .collect();
+
+    // declare the types the commands and events use, and the types those use in turn
+    let type_definitions = collect_type_definitions(sources);
+    let mut app_error = Vec::new();
+    for name in &error_types {
+        let item = type_definitions
+            .get(name)
+            .unwrap_or_else(|| panic!("No enum named {} to declare errors from", name));
+        app_error.extend(error_variants(item, &mut referenced));
+    }
+    let mut declarations = BTreeMap::new();
+    while let Some(name) = referenced
+        .iter()
+        .find(|name| !declarations.contains_key(*name))
+        .cloned()
+    {
+        let item = type_definitions
+            .get(&name)
+            .unwrap_or_else(|| panic!("No struct, enum or type alias named {} to declare", name));
+        let definition = type_definition(item, &mut referenced);
+        declarations.insert(name, definition);
+    }
+
+    Bindings {
+        definitions: definitions_file(&declarations, &app_error, &commands, &events),
+        actions: serde_json::to_string_pretty(&actions).unwrap(),
+        schemas: schemas_file(&declarations, &app_error, &commands, &events),
+    }
+}
+
+/// The `.d.ts` declaring `definitions` and typing `invoke` and `listen` by `commands` and
+/// `events`.
+fn definitions_file(
+    definitions: &BTreeMap<String, Definition>,
+    app_error: &[Shape],
+    commands: &[Command],
+    events: &[Member],
+) -> String {
+    // build file contents
+    let warning_header = "// THIS FILE IS AUTO-GENERATED BY ts-bindgen! DO NOT EDIT!";
+    let invoke_import = "import { invoke as invokeRaw } from \"@tauri-apps/api\";";
+    let listen_import = "import { listen as listenRaw, type EventCallback, type UnlistenFn } from \"@tauri-apps/api/event\";";
+    let app_error = match app_error.is_empty() {
+        true => "export type AppError = never;".to_owned(),
+        false => format!(
+            "export type AppError =\n    | {};",
+            app_error
+                .iter()
+                .map(Shape::to_ts)
+                .collect::<Vec<_>>()
+                .join("\n    | ")
+        ),
+    };
+    let is_app_error_fn = indoc::indoc! {"
+        export function isAppError(error: unknown): error is AppError {
+            return typeof error === \"object\" && error !== null && \"code\" in error && \"message\" in error;
+        }
+    "};
+    let commands: Vec<_> = commands
+        .iter()
+        .map(|command| {
+            let args: Vec<_> = command.args.iter().map(Member::to_ts).collect();
+            format!(
+                "{}    {}: {{\n        returns: {},\n        args: {{ {} }},\n        error: {}\n    }}",
+                tsdoc(command.doc.as_deref(), "    "),
+                command.name,
+                command.returns.to_ts(),
+                args.join(", "),
+                command.error.to_ts()
+            )
+        })
+        .collect();
+    let tauri_commands = format!("type TauriCommands = {{\n{}\n}};", commands.join(",\n"));
+    let invoke_fn = indoc::indoc! {"
+        /** Rejects with the command's `error`. */
+        export function invoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T][\"args\"]): Promise<TauriCommands[T][\"returns\"]> {
+            return invokeRaw(cmd, args);
+        }
+
+        export type InvokeResult<T extends keyof TauriCommands> =
+            | { ok: true, value: TauriCommands[T][\"returns\"] }
+            | { ok: false, error: TauriCommands[T][\"error\"] };
+
+        /** Resolves with the command's result or its `error`, so callers have to handle both. */
+        export async function tryInvoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T][\"args\"]): Promise<InvokeResult<T>> {
+            try {
+                return { ok: true, value: await invokeRaw(cmd, args) };
+            } catch (error) {
+                return { ok: false, error: error as TauriCommands[T][\"error\"] };
+            }
+        }
+    "};
+    let events: Vec<_> = events
+        .iter()
+        .map(|event| format!("    {}", event.to_ts()))
+        .collect();
+    let tauri_events = format!("type TauriEvents = {{\n{}\n}};", events.join(",\n"));
+    let listen_fn = indoc::indoc! {"
+        export function listen<E extends keyof TauriEvents>(event: E, handler: EventCallback<TauriEvents[E]>): Promise<UnlistenFn> {
+            return listenRaw(event, handler);
+        }
+    "};
+    let mut sections = vec![
+        warning_header.to_owned(),
+        format!("{}\n{}", invoke_import, listen_import),
+    ];
+    sections.extend(definitions.values().map(Definition::to_ts));
+    sections.push(app_error);
+    sections.push(is_app_error_fn.trim_end().to_owned());
+    sections.push(tauri_commands);
+    sections.push(invoke_fn.trim_end().to_owned());
+    sections.push(tauri_events);
+    sections.push(listen_fn.to_owned());
+    sections.join("\n\n")
+}
+
+/// Zod schemas for `definitions`, and for what `commands` take and return and `events`
+/// carry.
+fn schemas_file(
+    definitions: &BTreeMap<String, Definition>,
+    app_error: &[Shape],
+    commands: &[Command],
+    events: &[Member],
+) -> String {
+    let warning_header = "// THIS FILE IS AUTO-GENERATED BY ts-bindgen! DO NOT EDIT!";
+    let zod_import = "import { z } from \"zod\";";
+    let app_error = match app_error {
+        [] => "z.never()".to_owned(),
+        [variant] => shape_to_zod(variant),
+        variants => format!(
+            "z.union([\n    {},\n])",
+            variants
+                .iter()
+                .map(shape_to_zod)
+                .collect::<Vec<_>>()
+                .join(",\n    ")
+        ),
+    };
+    let app_error = format!("export const AppErrorSchema = {};", app_error);
+    let commands: Vec<_> = commands
+        .iter()
+        .map(|command| {
+            format!(
+                "    {}: {{\n        args: {},\n        returns: {},\n        error: {}\n    }}",
+                command.name,
+                object_to_zod(&command.args),
+                shape_to_zod(&command.returns),
+                shape_to_zod(&command.error)
+            )
+        })
+        .collect();
+    let command_schemas = format!(
+        "/** What each command takes, resolves with and rejects with. */\nexport const commandSchemas = {{\n{}\n}};",
+        commands.join(",\n")
+    );
+    let events: Vec<_> = events
+        .iter()
+        .map(|event| format!("    {}: {}", ts_key(&event.key), shape_to_zod(&event.shape)))
+        .collect();
+    let event_schemas = format!(
+        "/** The payload each event carries. */\nexport const eventSchemas = {{\n{}\n}};",
+        events.join(",\n")
+    );
+    let mut sections = vec![warning_header.to_owned(), zod_import.to_owned()];
+    sections.extend(definitions.values().map(definition_to_zod));
+    sections.push(app_error);
+    sections.push(command_schemas);
+    sections.push(event_schemas);
+    sections.join("\n\n") + "\n"
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Compares `actual` with the committed file at `path`, or writes it there when
+    /// `UPDATE_BINDINGS` is set.
+    fn assert_golden(path: &Path, actual: &str) {
+        if std::env::var_os("UPDATE_BINDINGS").is_some() {
+            std::fs::write(path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(path).unwrap_or_default();
+        if let Some(difference) = difference(&expected, actual) {
+            panic!(
+                "{} is out of date, {}\nRerun with UPDATE_BINDINGS=1 to regenerate it.",
+                path.display(),
+                difference
+            );
+        }
+    }
+
+    /// Each `fixtures/*.rs` file must generate its committed `.d.ts` and `.zod.ts` neighbours.
+    #[test]
+    fn test_bindings_match_golden_files() {
+        let mut fixtures = 0;
+        for entry in std::fs::read_dir("fixtures").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("rs") {
+                continue;
+            }
+            let contents = std::fs::read_to_string(&path).unwrap();
+            let bindings = generate(&contents, std::slice::from_ref(&contents));
+            assert_golden(&path.with_extension("d.ts"), &bindings.definitions);
+            assert_golden(&path.with_extension("zod.ts"), &bindings.schemas);
+            fixtures += 1;
+        }
+        assert!(fixtures > 0, "no fixtures in fixtures");
+    }
+
+    /// The app's committed bindings are what `ts-bindgen` generates from its sources now,
+    /// as `ts-bindgen --check` would tell.
+    #[test]
+    fn test_app_bindings_are_up_to_date() {
+        let contents = std::fs::read_to_string("../src/commands.rs").unwrap();
+        let sources = read_sources(&["../src", "../core/src"]).unwrap();
+        let bindings = generate(&contents, &sources);
+        for (path, actual) in [
+            (
+                "../../src/lib/bindings/tauri_commands.d.ts",
+                &bindings.definitions,
+            ),
+            ("../src/app_actions.json", &bindings.actions),
+        ] {
+            let expected = std::fs::read_to_string(path).unwrap();
+            if let Some(difference) = difference(&expected, actual) {
+                panic!(
+                    "{} is out of date, {}\nRegenerate it with `cargo run -p ehyaioess-bindgen`.",
+                    path, difference
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Writes the frontend's bindings for the app's commands and events, or with `--check`,
+//! fails when the committed ones are out of date. Paths default to the app's, relative to
+//! `src-tauri`:
+//!
+//! ```sh
+//! cargo run -p ehyaioess-bindgen
+//! cargo run -p ehyaioess-bindgen -- --check
+//! cargo run -p ehyaioess-bindgen -- --schemas ../src/lib/bindings/tauri_schemas.ts
+//! ```
+
+use std::{fs, io, path::PathBuf};
+
+use clap::Parser;
+
+#[derive(Parser)]
+#[command(
+    name = "ts-bindgen",
+    about = "Generates TypeScript bindings for the app's Tauri commands and events"
+)]
+struct Cli {
+    /// File declaring the Tauri commands
+    #[arg(long, default_value = "src/commands.rs")]
+    commands: PathBuf,
+    /// Directories whose `.rs` files declare the types and send the events the bindings use
+    #[arg(long = "sources", default_values = ["src", "core/src"])]
+    sources: Vec<PathBuf>,
+    /// TypeScript definitions to write
+    #[arg(long, default_value = "../src/lib/bindings/tauri_commands.d.ts")]
+    definitions: PathBuf,
+    /// Action catalog to write, served by `list_app_actions`
+    #[arg(long, default_value = "src/app_actions.json")]
+    actions: PathBuf,
+    /// Zod schemas to write as well, for validating IPC data at runtime; needs `zod`
+    #[arg(long)]
+    schemas: Option<PathBuf>,
+    /// Check that the files are up to date instead of writing them
+    #[arg(long)]
+    check: bool,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Generates the bindings, returning whether the files on disk were up to date (always, when
+/// writing them).
+fn run(cli: &Cli) -> io::Result<bool> {
+    let contents = fs::read_to_string(&cli.commands)?;
+    let sources = ehyaioess_bindgen::read_sources(&cli.sources)?;
+    let bindings = ehyaioess_bindgen::generate(&contents, &sources);
+
+    let mut files = vec![
+        (&cli.definitions, bindings.definitions),
+        (&cli.actions, bindings.actions),
+    ];
+    if let Some(schemas) = &cli.schemas {
+        files.push((schemas, bindings.schemas));
+    }
+
+    let mut up_to_date = true;
+    for (path, actual) in files {
+        if !cli.check {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, actual)?;
+            continue;
+        }
+        let expected = fs::read_to_string(path).unwrap_or_default();
+        if let Some(difference) = ehyaioess_bindgen::difference(&expected, &actual) {
+            eprintln!("{} is out of date, {}", path.display(), difference);
+            up_to_date = false;
+        }
+    }
+    if !up_to_date {
+        eprintln!("Regenerate the bindings with `cargo run -p ehyaioess-bindgen`.");
+    }
+    Ok(up_to_date)
+}
@@ -0,0 +1,629 @@
+//! Rust types the way serde puts them on the wire, and as TypeScript.
+
+use std::collections::BTreeSet;
+
+/// Struct, enum and type alias definitions by name, for the types commands refer to.
+pub(crate) type TypeDefinitions = std::collections::HashMap<String, syn::Item>;
+
+/// Collects the top-level structs, enums and type aliases of `sources`. Where two share a
+/// name, the first one wins.
+pub(crate) fn collect_type_definitions(sources: &[String]) -> TypeDefinitions {
+    let mut type_definitions = TypeDefinitions::new();
+    for source in sources {
+        for item in syn::parse_file(source).unwrap().items {
+            let name = match &item {
+                syn::Item::Struct(item_struct) => item_struct.ident.to_string(),
+                syn::Item::Enum(item_enum) => item_enum.ident.to_string(),
+                syn::Item::Type(item_type) => item_type.ident.to_string(),
+                _ => continue,
+            };
+            type_definitions.entry(name).or_insert(item);
+        }
+    }
+    type_definitions
+}
+
+/// The type arguments of a path segment, like `A` and `B` in `HashMap<A, B>`.
+pub(crate) fn type_args(segment: &syn::PathSegment) -> Vec<&syn::Type> {
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(angle_bracketed_data) => angle_bracketed_data
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A type as it goes over the wire, for rendering as TypeScript or a Zod schema.
+pub(crate) enum Shape {
+    String,
+    Number,
+    Boolean,
+    Unknown,
+    Null,
+    /// What a command returning `()` resolves with.
+    Void,
+    Never,
+    Literal(String),
+    Nullable(Box<Shape>),
+    Array(Box<Shape>),
+    Record(Box<Shape>, Box<Shape>),
+    Tuple(Vec<Shape>),
+    /// A declared type, with its type arguments.
+    Named(String, Vec<Shape>),
+    /// A type parameter in scope.
+    Param(String),
+    Object(Vec<Member>),
+    Intersection(Vec<Shape>),
+    Union(Vec<Shape>),
+}
+
+/// A member of an object, like `name: string` or `note?: string | null`.
+pub(crate) struct Member {
+    pub(crate) key: String,
+    /// Left out when empty, by `skip_serializing_if`.
+    pub(crate) optional: bool,
+    pub(crate) shape: Shape,
+    /// Shown where the member is declared on its own line, as in an interface.
+    pub(crate) doc: Option<String>,
+}
+
+impl Member {
+    pub(crate) fn new(key: impl Into<String>, shape: Shape) -> Self {
+        Member {
+            key: key.into(),
+            optional: false,
+            shape,
+            doc: None,
+        }
+    }
+
+    pub(crate) fn to_ts(&self) -> String {
+        format!(
+            "{}{}: {}",
+            ts_key(&self.key),
+            if self.optional { "?" } else { "" },
+            self.shape.to_ts()
+        )
+    }
+}
+
+impl Shape {
+    /// `parts` joined with `&`, or the only one.
+    fn intersection(mut parts: Vec<Shape>) -> Shape {
+        match parts.len() {
+            1 => parts.pop().unwrap(),
+            _ => Shape::Intersection(parts),
+        }
+    }
+
+    pub(crate) fn to_ts(&self) -> String {
+        let join = |shapes: &[Shape], separator: &str| {
+            shapes
+                .iter()
+                .map(Shape::to_ts)
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+        match self {
+            Shape::String => "string".to_owned(),
+            Shape::Number => "number".to_owned(),
+            Shape::Boolean => "boolean".to_owned(),
+            Shape::Unknown => "unknown".to_owned(),
+            Shape::Null => "null".to_owned(),
+            Shape::Void => "void".to_owned(),
+            Shape::Never => "never".to_owned(),
+            Shape::Literal(value) => format!("{:?}", value),
+            Shape::Nullable(shape) => format!("{} | null", shape.to_ts()),
+            Shape::Array(shape) => format!("Array<{}>", shape.to_ts()),
+            Shape::Record(key, value) => format!("Record<{}, {}>", key.to_ts(), value.to_ts()),
+            Shape::Tuple(shapes) => format!("[{}]", join(shapes, ", ")),
+            Shape::Named(name, args) if args.is_empty() => name.clone(),
+            Shape::Named(name, args) => format!("{}<{}>", name, join(args, ", ")),
+            Shape::Param(name) => name.clone(),
+            Shape::Object(members) => {
+                let members: Vec<_> = members.iter().map(Member::to_ts).collect();
+                format!("{{ {} }}", members.join(", "))
+            }
+            Shape::Intersection(shapes) => join(shapes, " & "),
+            Shape::Union(shapes) => join(shapes, " | "),
+        }
+    }
+}
+
+/// A struct, enum or type alias to declare.
+pub(crate) struct Definition {
+    pub(crate) name: String,
+    pub(crate) generics: Vec<String>,
+    pub(crate) shape: Shape,
+    pub(crate) doc: Option<String>,
+    /// For structs with named fields, declared as an interface extending the types of
+    /// their flattened fields, with `shape` being the rest.
+    pub(crate) extends: Option<Vec<Shape>>,
+}
+
+impl Definition {
+    pub(crate) fn to_ts(&self) -> String {
+        let name = if self.generics.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}<{}>", self.name, self.generics.join(", "))
+        };
+        match (&self.extends, &self.shape) {
+            (Some(extends), Shape::Object(members)) => {
+                let extends = if extends.is_empty() {
+                    String::new()
+                } else {
+                    let extends: Vec<_> = extends.iter().map(Shape::to_ts).collect();
+                    format!(" extends {}", extends.join(", "))
+                };
+                let members: Vec<_> = members
+                    .iter()
+                    .map(|member| {
+                        format!(
+                            "{}    {},",
+                            tsdoc(member.doc.as_deref(), "    "),
+                            member.to_ts()
+                        )
+                    })
+                    .collect();
+                format!(
+                    "{}export interface {}{} {{\n{}\n}}",
+                    tsdoc(self.doc.as_deref(), ""),
+                    name,
+                    extends,
+                    members.join("\n")
+                )
+            }
+            _ => format!(
+                "{}export type {} = {};",
+                tsdoc(self.doc.as_deref(), ""),
+                name,
+                self.shape.to_ts()
+            ),
+        }
+    }
+}
+
+/// Converts `rust_type` to the shape serde gives it. Types that need a definition of their
+/// own are added to `referenced`, except for `generics`, the type parameters in scope.
+pub(crate) fn rust_type_to_shape(
+    rust_type: &syn::Type,
+    generics: &[String],
+    referenced: &mut BTreeSet<String>,
+) -> Shape {
+    match rust_type {
+        syn::Type::Path(type_path) if type_path.qself.is_none() => {
+            let segment = type_path.path.segments.last().unwrap();
+            let ident = segment.ident.to_string();
+            let args = type_args(segment);
+            let mut arg = |index: usize| match args.get(index) {
+                Some(ty) => rust_type_to_shape(ty, generics, referenced),
+                None => panic!("{} without type argument {}", ident, index),
+            };
+            match ident.as_str() {
+                "str" | "String" | "char" | "Uuid" | "PathBuf" => Shape::String,
+                "bool" => Shape::Boolean,
+                "u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize"
+                | "f32" | "f64" => Shape::Number,
+                "Value" => Shape::Unknown,
+                // chatgpt_rs serializes roles in lowercase
+                "Role" => Shape::Union(
+                    ["system", "user", "assistant"]
+                        .map(|role| Shape::Literal(role.to_owned()))
+                        .into(),
+                ),
+                "Result" => arg(0),
+                "Option" => Shape::Nullable(Box::new(arg(0))),
+                "Box" | "Arc" | "Rc" | "Cow" => arg(0),
+                "Vec" | "VecDeque" | "HashSet" | "BTreeSet" => Shape::Array(Box::new(arg(0))),
+                "HashMap" | "BTreeMap" => {
+                    let key = arg(0);
+                    Shape::Record(Box::new(key), Box::new(arg(1)))
+                }
+                _ if generics.contains(&ident) => Shape::Param(ident),
+                _ => {
+                    referenced.insert(ident.clone());
+                    let args = args
+                        .iter()
+                        .map(|ty| rust_type_to_shape(ty, generics, referenced))
+                        .collect();
+                    Shape::Named(ident, args)
+                }
+            }
+        }
+        syn::Type::Reference(type_reference) => {
+            rust_type_to_shape(&type_reference.elem, generics, referenced)
+        }
+        syn::Type::Slice(type_slice) => Shape::Array(Box::new(rust_type_to_shape(
+            &type_slice.elem,
+            generics,
+            referenced,
+        ))),
+        syn::Type::Tuple(tuple_type) if tuple_type.elems.is_empty() => Shape::Void,
+        syn::Type::Tuple(tuple_type) => Shape::Tuple(
+            tuple_type
+                .elems
+                .iter()
+                .map(|ty| rust_type_to_shape(ty, generics, referenced))
+                .collect(),
+        ),
+        _ => panic!("Unsupported type: {}", quote::quote! {#rust_type}),
+    }
+}
+
+/// The serde attributes that change what a type, field or variant looks like on the wire.
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    tag: Option<String>,
+    content: Option<String>,
+    untagged: bool,
+    transparent: bool,
+    flatten: bool,
+    skip: bool,
+    /// Left out when empty, by `skip_serializing_if`.
+    optional: bool,
+}
+
+/// Reads the `#[serde(...)]` attributes in `attrs`. For those set differently for
+/// serializing and deserializing, the serialized form wins, being what commands return.
+fn serde_attrs(attrs: &[syn::Attribute]) -> SerdeAttrs {
+    let mut serde = SerdeAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            let name = meta
+                .path
+                .get_ident()
+                .map(|ident| ident.to_string())
+                .unwrap_or_default();
+            let value = if meta.input.peek(syn::token::Paren) {
+                let mut serialized = None;
+                meta.parse_nested_meta(|nested| {
+                    let value: syn::LitStr = nested.value()?.parse()?;
+                    if nested.path.is_ident("serialize") {
+                        serialized = Some(value.value());
+                    }
+                    Ok(())
+                })?;
+                serialized
+            } else if meta.input.peek(syn::Token![=]) {
+                Some(meta.value()?.parse::<syn::LitStr>()?.value())
+            } else {
+                None
+            };
+            match (name.as_str(), value) {
+                ("rename", value @ Some(_)) => serde.rename = value,
+                ("rename_all", value @ Some(_)) => serde.rename_all = value,
+                ("tag", value @ Some(_)) => serde.tag = value,
+                ("content", value @ Some(_)) => serde.content = value,
+                ("untagged", None) => serde.untagged = true,
+                ("transparent", None) => serde.transparent = true,
+                ("flatten", None) => serde.flatten = true,
+                ("skip" | "skip_serializing", None) => serde.skip = true,
+                ("skip_serializing_if", Some(_)) => serde.optional = true,
+                _ => {}
+            }
+            Ok(())
+        })
+        .unwrap();
+    }
+    serde
+}
+
+/// Renames `ident` the way `rename_all = "<rule>"` does, given a field (snake_case) or a
+/// variant (PascalCase).
+pub(crate) fn rename_case(ident: &str, rule: &str, variant: bool) -> String {
+    let words: Vec<String> = if variant {
+        let mut words: Vec<String> = Vec::new();
+        for c in ident.chars() {
+            match words.last_mut() {
+                Some(word) if !c.is_uppercase() => word.push(c),
+                _ => words.push(c.to_lowercase().collect()),
+            }
+        }
+        words
+    } else {
+        ident.split('_').map(str::to_owned).collect()
+    };
+    let capitalize = |word: &String| {
+        let mut chars = word.chars();
+        chars.next().map_or_else(String::new, |first| {
+            first.to_uppercase().chain(chars).collect()
+        })
+    };
+    match rule {
+        "lowercase" => ident.to_lowercase(),
+        "UPPERCASE" => ident.to_uppercase(),
+        "PascalCase" => words.iter().map(capitalize).collect(),
+        "camelCase" => words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                if i == 0 {
+                    word.clone()
+                } else {
+                    capitalize(word)
+                }
+            })
+            .collect(),
+        "snake_case" => words.join("_"),
+        "SCREAMING_SNAKE_CASE" => words.join("_").to_uppercase(),
+        "kebab-case" => words.join("-"),
+        "SCREAMING-KEBAB-CASE" => words.join("-").to_uppercase(),
+        _ => panic!("Unsupported rename_all rule: {}", rule),
+    }
+}
+
+/// The name a field or variant goes by on the wire.
+fn wire_name(
+    ident: &syn::Ident,
+    serde: &SerdeAttrs,
+    rename_all: Option<&str>,
+    variant: bool,
+) -> String {
+    let ident = syn::ext::IdentExt::unraw(ident).to_string();
+    match (&serde.rename, rename_all) {
+        (Some(rename), _) => rename.clone(),
+        (None, Some(rule)) => rename_case(&ident, rule, variant),
+        (None, None) => ident,
+    }
+}
+
+/// The `///` comments in `attrs`, one line each without the space after the slashes.
+pub(crate) fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<_> = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(name_value) if name_value.path.is_ident("doc") => {
+                match &name_value.value {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(doc),
+                        ..
+                    }) => Some(doc.value()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .flat_map(|doc| {
+            doc.split('\n')
+                .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end().to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// `doc` as a TSDoc comment for something declared at `indent`, or nothing without a doc
+/// comment.
+pub(crate) fn tsdoc(doc: Option<&str>, indent: &str) -> String {
+    let Some(doc) = doc else {
+        return String::new();
+    };
+    let doc = doc.replace("*/", "*\\/");
+    if !doc.contains('\n') {
+        return format!("{}/** {} */\n", indent, doc);
+    }
+    let lines: Vec<_> = doc
+        .lines()
+        .map(|line| {
+            format!(
+                "{} *{}{}",
+                indent,
+                if line.is_empty() { "" } else { " " },
+                line
+            )
+        })
+        .collect();
+    format!("{}/**\n{}\n{} */\n", indent, lines.join("\n"), indent)
+}
+
+/// `name` as an object key, quoted unless it's a valid identifier.
+pub(crate) fn ts_key(name: &str) -> String {
+    let is_identifier = name.chars().enumerate().all(|(i, c)| {
+        c == '_' || c == '$' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit())
+    });
+    if is_identifier && !name.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{:?}", name)
+    }
+}
+
+/// The members of an object with `fields`, and the types of its flattened fields.
+fn named_fields_to_shape(
+    fields: &syn::FieldsNamed,
+    rename_all: Option<&str>,
+    generics: &[String],
+    referenced: &mut BTreeSet<String>,
+) -> (Vec<Member>, Vec<Shape>) {
+    let mut members = Vec::new();
+    let mut flattened = Vec::new();
+    for field in &fields.named {
+        let serde = serde_attrs(&field.attrs);
+        if serde.skip {
+            continue;
+        }
+        let shape = rust_type_to_shape(&field.ty, generics, referenced);
+        if serde.flatten {
+            flattened.push(shape);
+            continue;
+        }
+        members.push(Member {
+            key: wire_name(field.ident.as_ref().unwrap(), &serde, rename_all, false),
+            optional: serde.optional,
+            shape,
+            doc: doc_comment(&field.attrs),
+        });
+    }
+    (members, flattened)
+}
+
+/// `{ a: A, b: B }` for named fields, `[A, B]` for several unnamed ones and `A` for one.
+fn fields_to_shape(
+    fields: &syn::Fields,
+    rename_all: Option<&str>,
+    generics: &[String],
+    referenced: &mut BTreeSet<String>,
+) -> Shape {
+    match fields {
+        syn::Fields::Named(named) => {
+            let (members, flattened) =
+                named_fields_to_shape(named, rename_all, generics, referenced);
+            Shape::intersection(
+                std::iter::once(Shape::Object(members))
+                    .chain(flattened)
+                    .collect(),
+            )
+        }
+        syn::Fields::Unnamed(unnamed) => {
+            let mut fields: Vec<_> = unnamed
+                .unnamed
+                .iter()
+                .filter(|field| !serde_attrs(&field.attrs).skip)
+                .map(|field| rust_type_to_shape(&field.ty, generics, referenced))
+                .collect();
+            match fields.len() {
+                1 => fields.pop().unwrap(),
+                _ => Shape::Tuple(fields),
+            }
+        }
+        syn::Fields::Unit => Shape::Null,
+    }
+}
+
+/// Defines a struct, enum or type alias the way serde serializes it.
+pub(crate) fn type_definition(item: &syn::Item, referenced: &mut BTreeSet<String>) -> Definition {
+    let (ident, item_generics, attrs) = match item {
+        syn::Item::Struct(item_struct) => (
+            &item_struct.ident,
+            &item_struct.generics,
+            &item_struct.attrs,
+        ),
+        syn::Item::Enum(item_enum) => (&item_enum.ident, &item_enum.generics, &item_enum.attrs),
+        syn::Item::Type(item_type) => (&item_type.ident, &item_type.generics, &item_type.attrs),
+        _ => unreachable!("only structs, enums and type aliases are collected"),
+    };
+    let serde = serde_attrs(attrs);
+    let rename_all = serde.rename_all.as_deref();
+    let generics: Vec<_> = item_generics
+        .type_params()
+        .map(|param| param.ident.to_string())
+        .collect();
+    let mut extends = None;
+    let shape = match item {
+        syn::Item::Struct(syn::ItemStruct {
+            fields: syn::Fields::Named(named),
+            ..
+        }) if !serde.transparent => {
+            let (members, flattened) =
+                named_fields_to_shape(named, rename_all, &generics, referenced);
+            extends = Some(flattened);
+            Shape::Object(members)
+        }
+        syn::Item::Struct(item_struct) => match &item_struct.fields {
+            // transparent structs are their only field
+            syn::Fields::Named(named) => {
+                rust_type_to_shape(&named.named[0].ty, &generics, referenced)
+            }
+            fields => fields_to_shape(fields, rename_all, &generics, referenced),
+        },
+        syn::Item::Enum(item_enum) => {
+            let mut variants = Vec::new();
+            for variant in &item_enum.variants {
+                let variant_serde = serde_attrs(&variant.attrs);
+                if variant_serde.skip {
+                    continue;
+                }
+                let variant_name = wire_name(&variant.ident, &variant_serde, rename_all, true);
+                let literal = || Shape::Literal(variant_name.clone());
+                let fields_rename_all = variant_serde.rename_all.as_deref();
+                let shape = match (&serde.tag, &serde.content, &variant.fields) {
+                    _ if serde.untagged => {
+                        fields_to_shape(&variant.fields, fields_rename_all, &generics, referenced)
+                    }
+                    (None, _, syn::Fields::Unit) => literal(),
+                    (None, _, fields) => Shape::Object(vec![Member::new(
+                        variant_name.clone(),
+                        fields_to_shape(fields, fields_rename_all, &generics, referenced),
+                    )]),
+                    (Some(tag), _, syn::Fields::Unit) => {
+                        Shape::Object(vec![Member::new(tag, literal())])
+                    }
+                    (Some(tag), Some(content), fields) => Shape::Object(vec![
+                        Member::new(tag, literal()),
+                        Member::new(
+                            content,
+                            fields_to_shape(fields, fields_rename_all, &generics, referenced),
+                        ),
+                    ]),
+                    (Some(tag), None, syn::Fields::Named(named)) => {
+                        let (members, flattened) =
+                            named_fields_to_shape(named, fields_rename_all, &generics, referenced);
+                        let object = Shape::Object(
+                            std::iter::once(Member::new(tag, literal()))
+                                .chain(members)
+                                .collect(),
+                        );
+                        Shape::intersection(std::iter::once(object).chain(flattened).collect())
+                    }
+                    (Some(tag), None, fields) => Shape::Intersection(vec![
+                        Shape::Object(vec![Member::new(tag, literal())]),
+                        fields_to_shape(fields, fields_rename_all, &generics, referenced),
+                    ]),
+                };
+                variants.push(shape);
+            }
+            match variants.len() {
+                0 => Shape::Never,
+                1 => variants.pop().unwrap(),
+                _ => Shape::Union(variants),
+            }
+        }
+        syn::Item::Type(item_type) => rust_type_to_shape(&item_type.ty, &generics, referenced),
+        _ => unreachable!("only structs, enums and type aliases are collected"),
+    };
+    Definition {
+        name: ident.to_string(),
+        generics,
+        shape,
+        doc: doc_comment(attrs),
+        extends,
+    }
+}
+
+/// The variants of a command error enum as the frontend gets them, `{ code, message, hint }`
+/// with the variant's name as the code, plus the fields of variants that have any. Errors
+/// serialize themselves that way by hand rather than deriving it.
+pub(crate) fn error_variants(item: &syn::Item, referenced: &mut BTreeSet<String>) -> Vec<Shape> {
+    let syn::Item::Enum(item_enum) = item else {
+        panic!("Command errors must be enums");
+    };
+    item_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            let mut members = vec![
+                Member::new("code", Shape::Literal(variant.ident.to_string())),
+                Member::new("message", Shape::String),
+                Member::new("hint", Shape::Nullable(Box::new(Shape::String))),
+            ];
+            match &variant.fields {
+                syn::Fields::Named(named) => {
+                    members.extend(named_fields_to_shape(named, None, &[], referenced).0)
+                }
+                syn::Fields::Unit => {}
+                syn::Fields::Unnamed(_) => {
+                    panic!("Command error {} has unnamed fields", variant.ident)
+                }
+            }
+            Shape::Object(members)
+        })
+        .collect()
+}
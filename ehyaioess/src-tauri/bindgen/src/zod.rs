@@ -0,0 +1,102 @@
+//! Zod schemas for the shapes the bindings declare, for checking IPC data at runtime.
+
+use crate::types::{ts_key, Definition, Member, Shape};
+
+/// The schema a type's definition is exported as.
+fn schema_name(name: &str) -> String {
+    format!("{}Schema", name)
+}
+
+/// A schema for `shape`. Declared types are referred to lazily, so schemas can be declared in
+/// any order and refer to themselves.
+pub(crate) fn shape_to_zod(shape: &Shape) -> String {
+    let join = |shapes: &[Shape]| {
+        shapes
+            .iter()
+            .map(shape_to_zod)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match shape {
+        Shape::String => "z.string()".to_owned(),
+        Shape::Number => "z.number()".to_owned(),
+        Shape::Boolean => "z.boolean()".to_owned(),
+        Shape::Unknown => "z.unknown()".to_owned(),
+        // serde serializes `()` as null
+        Shape::Null | Shape::Void => "z.null()".to_owned(),
+        Shape::Never => "z.never()".to_owned(),
+        Shape::Literal(value) => format!("z.literal({:?})", value),
+        Shape::Nullable(shape) => format!("{}.nullable()", shape_to_zod(shape)),
+        Shape::Array(shape) => format!("z.array({})", shape_to_zod(shape)),
+        Shape::Record(key, value) => {
+            format!("z.record({}, {})", shape_to_zod(key), shape_to_zod(value))
+        }
+        Shape::Tuple(shapes) => format!("z.tuple([{}])", join(shapes)),
+        Shape::Named(name, args) if args.is_empty() => {
+            format!("z.lazy(() => {})", schema_name(name))
+        }
+        Shape::Named(name, args) => {
+            format!("z.lazy(() => {}({}))", schema_name(name), join(args))
+        }
+        Shape::Param(name) => name.clone(),
+        Shape::Object(members) => object_to_zod(members),
+        Shape::Intersection(shapes) => {
+            let mut shapes = shapes.iter().map(shape_to_zod);
+            let first = shapes.next().unwrap();
+            shapes.fold(first, |schema, shape| format!("{}.and({})", schema, shape))
+        }
+        Shape::Union(shapes) => format!("z.union([{}])", join(shapes)),
+    }
+}
+
+/// `z.object({ ... })` with `members`.
+pub(crate) fn object_to_zod(members: &[Member]) -> String {
+    let members: Vec<_> = members
+        .iter()
+        .map(|member| {
+            format!(
+                "{}: {}{}",
+                ts_key(&member.key),
+                shape_to_zod(&member.shape),
+                if member.optional { ".optional()" } else { "" }
+            )
+        })
+        .collect();
+    match members.is_empty() {
+        true => "z.object({})".to_owned(),
+        false => format!("z.object({{ {} }})", members.join(", ")),
+    }
+}
+
+/// Declares the schema for a definition, a function of the schemas of its type parameters
+/// when it has any.
+pub(crate) fn definition_to_zod(definition: &Definition) -> String {
+    let schema = definition
+        .extends
+        .iter()
+        .flatten()
+        .fold(shape_to_zod(&definition.shape), |schema, shape| {
+            format!("{}.and({})", schema, shape_to_zod(shape))
+        });
+    let name = schema_name(&definition.name);
+    if definition.generics.is_empty() {
+        return format!("export const {} = {};", name, schema);
+    }
+    let params: Vec<_> = definition
+        .generics
+        .iter()
+        .map(|param| format!("{} extends z.ZodTypeAny", param))
+        .collect();
+    let args: Vec<_> = definition
+        .generics
+        .iter()
+        .map(|param| format!("{}: {}", param, param))
+        .collect();
+    format!(
+        "export const {} = <{}>({}) => {};",
+        name,
+        params.join(", "),
+        args.join(", "),
+        schema
+    )
+}
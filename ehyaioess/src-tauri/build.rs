@@ -1,3 +1,14 @@
 fn main() {
+  // Reported by `get_app_info`; left unset when building outside a git checkout.
+  let output = std::process::Command::new("git")
+    .args(["rev-parse", "--short", "HEAD"])
+    .output()
+    .ok()
+    .filter(|output| output.status.success());
+  if let Some(output) = output {
+    let hash = String::from_utf8_lossy(&output.stdout);
+    println!("cargo:rustc-env=EHYAIOESS_BUILD_HASH={}", hash.trim());
+  }
+  println!("cargo:rerun-if-changed=../../.git/logs/HEAD");
   tauri_build::build()
 }
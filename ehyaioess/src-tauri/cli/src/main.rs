@@ -0,0 +1,278 @@
+use std::{
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use ehyaioess_core::{
+    analytics::{UsageLog, UsageRecord},
+    catalog::ModelCatalog,
+    config::Config,
+    context::ContextLimit,
+    export,
+    guardrails::Guardrails,
+    knowledge,
+    models::{
+        Conversation, ConversationManager, ConversationMessageAddedEvent,
+        ConversationMessageFilteredEvent, MyError,
+    },
+    overrides::ConfigOverrides,
+    profiles::ProfileClients,
+    provider,
+    recorder::RequestRecorder,
+    workspaces,
+};
+use uuid::Uuid;
+
+#[derive(Parser)]
+#[command(name = "ehyaioess-cli", about = "Headless access to ehyaioess conversations")]
+struct Cli {
+    /// Workspace profile to use instead of the one last opened in the app
+    #[arg(long, global = true)]
+    workspace: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List conversations as `<id>\t<title>`
+    List,
+    /// Send a message and print the assistant's reply
+    Chat {
+        /// Conversation to continue; a new one is started when omitted
+        #[arg(long)]
+        conversation: Option<Uuid>,
+        /// Message to send; messages are read from stdin line by line when omitted
+        message: Option<String>,
+    },
+    /// Print or save a conversation transcript
+    Export {
+        conversation: Uuid,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Markdown)]
+        format: ExportFormat,
+        /// File to write to instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli).await {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let base = workspaces::base_dir().ok_or(MyError::NoConfigDirFail)?;
+    match &cli.workspace {
+        Some(name) => workspaces::select(&base, name)?,
+        None => workspaces::load_active(&base),
+    }
+    // Flags are handled by clap, so only environment overrides apply here.
+    let env_overrides = ConfigOverrides::collect(|name| std::env::var(name).ok(), &[]);
+    let config = Config::from_disk()?.with_overrides(env_overrides)?;
+    config.apply_locale();
+    let mut mgr = ConversationManager::from_disk(&config.conversation_history_save_path)
+        .unwrap_or_else(|_| ConversationManager::new());
+    mgr.compressed = config.compress_history;
+
+    match cli.command {
+        Command::List => {
+            for (id, conv) in mgr.conversations.iter() {
+                println!("{}\t{}", id, conv.get_title());
+            }
+        }
+        Command::Chat {
+            conversation,
+            message,
+        } => {
+            let recorder = Arc::new(RequestRecorder::new(
+                Config::get_request_log_path()?,
+                config.record_requests,
+            ));
+            let profile_clients = ProfileClients::from_config(&config, recorder)?;
+            let usage_log = UsageLog::new(Config::get_usage_log_path()?);
+            let conversation_id = match conversation {
+                Some(id) if mgr.conversations.contains_key(&id) => id,
+                Some(_) => return Err(Box::new(MyError::ConversationNotFoundFail)),
+                None => {
+                    let conv = Conversation::new();
+                    let id = conv.id;
+                    mgr.conversations.insert(id, conv);
+                    eprintln!("Started conversation {}", id);
+                    id
+                }
+            };
+            match message {
+                Some(message) => {
+                    chat(
+                        &mut mgr,
+                        &config,
+                        &profile_clients,
+                        &usage_log,
+                        conversation_id,
+                        &message,
+                    )
+                    .await?
+                }
+                None => {
+                    let stdin = io::stdin();
+                    loop {
+                        eprint!("> ");
+                        io::stderr().flush()?;
+                        let mut line = String::new();
+                        if stdin.lock().read_line(&mut line)? == 0 {
+                            break;
+                        }
+                        if line.trim().is_empty() {
+                            continue;
+                        }
+                        chat(
+                            &mut mgr,
+                            &config,
+                            &profile_clients,
+                            &usage_log,
+                            conversation_id,
+                            line.trim(),
+                        )
+                        .await?;
+                    }
+                }
+            }
+        }
+        Command::Export {
+            conversation,
+            format,
+            output,
+        } => {
+            let conv = mgr.conversation(&conversation)?;
+            let contents = match format {
+                ExportFormat::Markdown => export::conversation_to_markdown(&conv),
+                ExportFormat::Json => export::conversation_to_json(&conv)?,
+            };
+            match output {
+                Some(path) => std::fs::write(path, contents)?,
+                None => println!("{}", contents),
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn chat(
+    mgr: &mut ConversationManager,
+    config: &Config,
+    profile_clients: &ProfileClients,
+    usage_log: &UsageLog,
+    conversation_id: Uuid,
+    message: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let conv = mgr.conversation_mut(&conversation_id)?;
+    conv.try_add_event(ConversationMessageAddedEvent {
+        author: chatgpt::types::Role::User,
+        content: message.to_string(),
+        model: None,
+        language: None,
+        citations: Vec::new(),
+    })?;
+    let preferred_profile = conv
+        .get_pinned_profile()
+        .unwrap_or(&config.active_profile)
+        .to_string();
+    let models = conv.model_chain(config.model_chain());
+    let catalog = ModelCatalog::open(&Config::get_model_catalog_path()?, &config.model_pricing);
+    let limit = catalog
+        .info(&models[0], &config.model_pricing)
+        .context_window
+        .map(|context_window| ContextLimit {
+            model: &models[0],
+            context_window,
+        });
+    let mut history = conv.to_request_messages(&config.system_prompt, limit.as_ref());
+    let stop_sequences = conv.stop_sequences();
+    let sampling = conv.sampling();
+    let retrieval = conv.repository().zip(knowledge::retrieval_query(conv));
+    // Read-only mode answers with the mock provider and saves nothing, like the app.
+    if !config.read_only {
+        mgr.write_to_disk(&config.conversation_history_save_path)?;
+    }
+
+    if let (Some((repository_id, query)), false) = (retrieval, config.read_only) {
+        // Like the app, the reply goes without the repository's code when it can't be searched.
+        let context = knowledge::retrieve_context(
+            &Config::get_knowledge_dir()?,
+            repository_id,
+            &query,
+            profile_clients,
+            &preferred_profile,
+        )
+        .await;
+        match context {
+            Ok(Some(context)) => knowledge::add_context(&mut history, context),
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to search the linked repository: {}", e),
+        }
+    }
+
+    let ai_response = if config.read_only {
+        provider::mock_completion()
+    } else {
+        profile_clients
+            .send_with_failover(
+                &preferred_profile,
+                &models,
+                &history,
+                &stop_sequences,
+                sampling,
+            )
+            .await?
+    };
+    if let Some(usage) = UsageRecord::from_response(conversation_id, &ai_response) {
+        usage_log.record(usage);
+    }
+    let conv = mgr.conversation_mut(&conversation_id)?;
+    let filtered = Guardrails::new(&config.guardrails).filter(
+        &conv.complete_reply(
+            &ai_response
+                .message()
+                .ok_or(MyError::ConversationAIResponseFail)?
+                .content,
+        ),
+    );
+    println!("{}", filtered.content);
+    for hit in &filtered.hits {
+        eprintln!("Banned content found: {} ({} times)", hit.rule, hit.count);
+    }
+
+    let message_id = conv
+        .try_add_event(ConversationMessageAddedEvent {
+            author: chatgpt::types::Role::Assistant,
+            content: filtered.content,
+            model: Some(ai_response.model.clone()),
+            language: None,
+            citations: Vec::new(),
+        })?
+        .id;
+    if !filtered.hits.is_empty() {
+        conv.add_event(ConversationMessageFilteredEvent {
+            message_id,
+            hits: filtered.hits,
+            redacted: filtered.redacted,
+        });
+    }
+    if !config.read_only {
+        mgr.write_to_disk(&config.conversation_history_save_path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Benchmarks of the event log and its persistence, for weighing storage redesigns with
+//! numbers. Run with `cargo bench -p ehyaioess-core`.
+
+use chatgpt::types::Role;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use ehyaioess_core::{
+    models::{Conversation, ConversationManager, ConversationMessageAddedEvent},
+    search,
+};
+
+const MESSAGES: usize = 10_000;
+
+fn message(index: usize) -> ConversationMessageAddedEvent {
+    ConversationMessageAddedEvent {
+        author: if index.is_multiple_of(2) {
+            Role::User
+        } else {
+            Role::Assistant
+        },
+        content: format!(
+            "Message {} about the quarterly report, with enough text to resemble a real reply.",
+            index
+        ),
+        model: None,
+        language: None,
+        citations: Vec::new(),
+    }
+}
+
+fn long_conversation() -> Conversation {
+    let mut conv = Conversation::new();
+    for index in 0..MESSAGES {
+        conv.add_event(message(index));
+    }
+    conv
+}
+
+/// A history of `conversations` conversations holding `MESSAGES` messages between them.
+fn history(conversations: usize) -> ConversationManager {
+    let mut mgr = ConversationManager::new();
+    for _ in 0..conversations {
+        let mut conv = Conversation::new();
+        for index in 0..MESSAGES / conversations {
+            conv.add_event(message(index));
+        }
+        mgr.conversations.insert(conv.id, conv);
+    }
+    mgr
+}
+
+fn event_append(c: &mut Criterion) {
+    let conv = long_conversation();
+    c.bench_function("append event to 10k-message conversation", |b| {
+        b.iter_batched_ref(
+            || conv.clone(),
+            |conv| conv.try_add_event(message(MESSAGES)).is_ok(),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn replay(c: &mut Criterion) {
+    let conv = long_conversation();
+    c.bench_function("replay 10k-message conversation", |b| {
+        b.iter(|| {
+            (
+                black_box(&conv).to_chat_history().len(),
+                conv.get_title().len(),
+                conv.unread_count(),
+            )
+        })
+    });
+}
+
+fn save_and_load(c: &mut Criterion) {
+    let dir = std::env::temp_dir().join(format!("bench-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut mgr = ConversationManager::new();
+    let conv = long_conversation();
+    mgr.conversations.insert(conv.id, conv);
+
+    for compressed in [false, true] {
+        mgr.compressed = compressed;
+        let kind = if compressed { "compressed" } else { "plain" };
+        let path = dir.join(format!("{}.json", kind));
+        let path = path.to_str().unwrap();
+        c.bench_function(&format!("save 10k-message history ({})", kind), |b| {
+            b.iter(|| mgr.write_to_disk(path).unwrap())
+        });
+        c.bench_function(&format!("load 10k-message history ({})", kind), |b| {
+            b.iter(|| ConversationManager::from_disk(path).unwrap())
+        });
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+fn search(c: &mut Criterion) {
+    let mgr = history(100);
+    c.bench_function("search 10k messages in 100 conversations", |b| {
+        b.iter(|| search::search_messages(&mgr, black_box("message 42"), "").len())
+    });
+}
+
+criterion_group!(benches, event_append, replay, save_and_load, search);
+criterion_main!(benches);
@@ -0,0 +1,254 @@
+//! Action items the model pulls out of conversations, kept as a small to-do list with whether
+//! each one is done.
+
+use std::{io, path::Path};
+
+use chatgpt::types::{ChatMessage, Role};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{bookmarks::snippet, compression, models::Conversation, validation};
+
+/// Per-message limit on what the model sees, keeping extraction requests affordable.
+const EXTRACTION_MESSAGE_CHARS: usize = 1_000;
+/// What the model replies when the conversation has no action items.
+const NO_ITEMS: &str = "NONE";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ActionItem {
+    pub id: Uuid,
+    /// The conversation the item was extracted from.
+    pub conversation_id: Uuid,
+    pub text: String,
+    pub created_at: i64,
+    /// None while it's still to do.
+    pub done_at: Option<i64>,
+    /// When to remind the user of the item; cleared once the reminder is shown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remind_at: Option<i64>,
+}
+
+/// The request asking for the conversation's action items, answered one per line.
+pub fn extraction_messages(conv: &Conversation) -> Vec<ChatMessage> {
+    let transcript = conv
+        .to_chat_history()
+        .iter()
+        .map(|message| {
+            format!(
+                "{:?}: {}",
+                message.role,
+                snippet(&message.content, EXTRACTION_MESSAGE_CHARS)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    vec![
+        ChatMessage {
+            role: Role::System,
+            content: format!(
+                "List the action items the user should follow up on from the following \
+                 conversation, each as a short imperative sentence on its own line. Reply with \
+                 only the action items, or {} when there are none.",
+                NO_ITEMS
+            ),
+        },
+        ChatMessage {
+            role: Role::User,
+            content: transcript,
+        },
+    ]
+}
+
+/// Action items from the model's reply to `extraction_messages`, without list markers or
+/// duplicates.
+pub fn parse_action_items(reply: &str) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+    for line in reply.lines() {
+        let line = line.trim();
+        let line = match line.split_once(['.', ')']) {
+            Some((number, rest))
+                if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                rest
+            }
+            _ => line,
+        };
+        let line = line.trim_start_matches(['-', '*', '•']).trim_start();
+        let line = line
+            .strip_prefix("[ ]")
+            .or_else(|| line.strip_prefix("[x]"))
+            .unwrap_or(line);
+        let item = validation::imported_title(line);
+        if item.is_empty() || item.eq_ignore_ascii_case(NO_ITEMS) {
+            continue;
+        }
+        if !items
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(&item))
+        {
+            items.push(item);
+        }
+    }
+    items
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ActionItems {
+    /// In the order they were extracted.
+    pub items: Vec<ActionItem>,
+}
+
+impl ActionItems {
+    /// The action items at `path`, none when there's no file yet. A file that can't be read is
+    /// an error rather than an empty list, so saving doesn't overwrite the items in it.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        match compression::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path, compress: bool) -> io::Result<()> {
+        compression::write_with(path, compress, |file| {
+            serde_json::to_writer(file, self).map_err(io::Error::from)
+        })
+    }
+
+    /// Adds the items of `texts` the conversation doesn't have yet, so extracting again only
+    /// adds what's new. Returns the added items.
+    pub fn add(&mut self, conversation_id: Uuid, texts: Vec<String>, now: i64) -> Vec<ActionItem> {
+        let mut added = Vec::new();
+        for text in texts {
+            let exists = self.items.iter().any(|item| {
+                item.conversation_id == conversation_id && item.text.eq_ignore_ascii_case(&text)
+            });
+            if exists {
+                continue;
+            }
+            let item = ActionItem {
+                id: Uuid::new_v4(),
+                conversation_id,
+                text,
+                created_at: now,
+                done_at: None,
+                remind_at: None,
+            };
+            self.items.push(item.clone());
+            added.push(item);
+        }
+        added
+    }
+
+    /// Marks the item done at `now`, which drops its reminder, or to do again. None when
+    /// there's no such item.
+    pub fn complete(&mut self, id: &Uuid, done: bool, now: i64) -> Option<&ActionItem> {
+        let item = self.items.iter_mut().find(|item| item.id == *id)?;
+        item.done_at = match (done, item.done_at) {
+            (true, Some(done_at)) => Some(done_at),
+            (true, None) => Some(now),
+            (false, _) => None,
+        };
+        if done {
+            item.remind_at = None;
+        }
+        Some(item)
+    }
+
+    /// Sets or, with None, cancels the item's reminder. None when there's no such item.
+    pub fn set_reminder(&mut self, id: &Uuid, remind_at: Option<i64>) -> Option<&ActionItem> {
+        let item = self.items.iter_mut().find(|item| item.id == *id)?;
+        item.remind_at = remind_at;
+        Some(item)
+    }
+
+    /// Takes the reminders due at `now`, returning their items.
+    pub fn take_due_reminders(&mut self, now: i64) -> Vec<ActionItem> {
+        let mut due = Vec::new();
+        for item in &mut self.items {
+            if item.remind_at.is_some_and(|remind_at| remind_at <= now) {
+                due.push(item.clone());
+                item.remind_at = None;
+            }
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_action_items() {
+        assert_eq!(
+            parse_action_items(
+                "1. Book the flights.\n- [ ] Renew the passport\n\n* book the flights.\n2) Call Sam"
+            ),
+            ["Book the flights.", "Renew the passport", "Call Sam"]
+        );
+        assert!(parse_action_items("NONE").is_empty());
+        assert!(parse_action_items("").is_empty());
+    }
+
+    #[test]
+    fn test_add_and_complete() {
+        let path = std::env::temp_dir().join(format!("action_items-{}.json", Uuid::new_v4()));
+        let conversation_id = Uuid::new_v4();
+        let mut items = ActionItems::open(&path).unwrap();
+        let added = items.add(conversation_id, vec!["Call Sam".to_string()], 10);
+        assert_eq!(added.len(), 1);
+        // extracting again only adds the new ones
+        let again = items.add(
+            conversation_id,
+            vec!["call sam".to_string(), "Email Kim".to_string()],
+            20,
+        );
+        assert_eq!(again.len(), 1);
+        assert_eq!(items.items.len(), 2);
+
+        assert_eq!(
+            items.complete(&added[0].id, true, 30).unwrap().done_at,
+            Some(30)
+        );
+        // completing again keeps when it was done
+        assert_eq!(
+            items.complete(&added[0].id, true, 40).unwrap().done_at,
+            Some(30)
+        );
+        items.save(&path, false).unwrap();
+        let mut reopened = ActionItems::open(&path).unwrap();
+        assert_eq!(reopened.items, items.items);
+        assert_eq!(
+            reopened.complete(&added[0].id, false, 50).unwrap().done_at,
+            None
+        );
+        assert!(reopened.complete(&Uuid::new_v4(), true, 50).is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_reminders() {
+        let mut items = ActionItems::default();
+        let added = items.add(
+            Uuid::new_v4(),
+            vec!["Call Sam".to_string(), "Email Kim".to_string()],
+            0,
+        );
+        items.set_reminder(&added[0].id, Some(100));
+        items.set_reminder(&added[1].id, Some(200));
+        assert!(items.take_due_reminders(99).is_empty());
+        let due = items.take_due_reminders(150);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, added[0].id);
+        // shown once
+        assert!(items.take_due_reminders(150).is_empty());
+
+        // done or cancelled items aren't reminded of
+        items.complete(&added[1].id, true, 160);
+        items.set_reminder(&added[0].id, Some(170));
+        items.set_reminder(&added[0].id, None);
+        assert!(items.take_due_reminders(300).is_empty());
+        assert!(items.set_reminder(&Uuid::new_v4(), Some(0)).is_none());
+    }
+}
@@ -0,0 +1,330 @@
+//! Local usage analytics. Records stay in the config directory and are never sent anywhere.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    models::ConversationManager,
+    pricing::{self, PriceOverrides},
+    provider::CompletionResponse,
+};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UsageRecord {
+    pub timestamp: i64,
+    pub conversation_id: Uuid,
+    pub model: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+impl UsageRecord {
+    /// None when the provider didn't report token usage.
+    pub fn from_response(conversation_id: Uuid, response: &CompletionResponse) -> Option<Self> {
+        let usage = response.usage?;
+        Some(Self {
+            timestamp: chrono::Utc::now().timestamp(),
+            conversation_id,
+            model: response.model.clone(),
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportPeriod {
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl ReportPeriod {
+    pub fn parse(period: &str) -> Option<Self> {
+        match period {
+            "week" => Some(ReportPeriod::Week),
+            "month" => Some(ReportPeriod::Month),
+            "year" => Some(ReportPeriod::Year),
+            "all" => Some(ReportPeriod::All),
+            _ => None,
+        }
+    }
+
+    /// Start of the period ending at `now`, or None for all time.
+    pub fn start(&self, now: i64) -> Option<i64> {
+        let days = match self {
+            ReportPeriod::Week => 7,
+            ReportPeriod::Month => 30,
+            ReportPeriod::Year => 365,
+            ReportPeriod::All => return None,
+        };
+        Some(now - days * SECONDS_PER_DAY)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WeeklyChatCount {
+    /// Monday 00:00 UTC of the week, in unix seconds.
+    pub week_start: i64,
+    pub chats: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModelTokenUsage {
+    pub model: String,
+    pub requests: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Estimated from the current prices; None for a model without a known price.
+    pub cost_usd: Option<f64>,
+}
+
+/// How many of the period's new conversations used a template or style preset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedUsage {
+    pub name: String,
+    pub conversations: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct FailedGeneration {
+    pub conversation_id: Uuid,
+    pub timestamp: i64,
+    pub error: String,
+    /// Whether a reply was generated afterwards, by retrying or otherwise.
+    pub recovered: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageReport {
+    pub period_start: Option<i64>,
+    pub chats_per_week: Vec<WeeklyChatCount>,
+    pub tokens_per_model: Vec<ModelTokenUsage>,
+    /// Most used first.
+    pub top_templates: Vec<NamedUsage>,
+    /// Most used first, by the preset each conversation uses now.
+    pub top_style_presets: Vec<NamedUsage>,
+    /// Newest first.
+    pub failed_generations: Vec<FailedGeneration>,
+}
+
+fn week_start(timestamp: i64) -> i64 {
+    let day = timestamp.div_euclid(SECONDS_PER_DAY);
+    // The unix epoch was a Thursday, three days after a Monday.
+    (day - (day + 3).rem_euclid(7)) * SECONDS_PER_DAY
+}
+
+/// Most used first, ties by name.
+fn most_used(counts: HashMap<String, usize>) -> Vec<NamedUsage> {
+    let mut usage: Vec<NamedUsage> = counts
+        .into_iter()
+        .map(|(name, conversations)| NamedUsage {
+            name,
+            conversations,
+        })
+        .collect();
+    usage.sort_by(|a, b| {
+        b.conversations
+            .cmp(&a.conversations)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    usage
+}
+
+/// Append-only log of token usage per generation.
+pub struct UsageLog {
+    path: Mutex<PathBuf>,
+    records: Mutex<Vec<UsageRecord>>,
+}
+
+impl UsageLog {
+    pub fn new(path: PathBuf) -> Self {
+        let records = Self::read_records(&path).unwrap_or_default();
+        Self {
+            path: Mutex::new(path),
+            records: Mutex::new(records),
+        }
+    }
+
+    /// Switches to the log at `path`, e.g. after changing workspace profile.
+    pub fn reopen(&self, path: PathBuf) {
+        let mut records = self.records.lock().unwrap();
+        *records = Self::read_records(&path).unwrap_or_default();
+        *self.path.lock().unwrap() = path;
+    }
+
+    fn read_records(path: &PathBuf) -> Result<Vec<UsageRecord>, std::io::Error> {
+        let file = File::open(path)?;
+        let records = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect();
+        Ok(records)
+    }
+
+    pub fn record(&self, record: UsageRecord) {
+        let line = match serde_json::to_string(&record) {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("Failed to serialize usage record: {}", e);
+                return;
+            }
+        };
+        let mut records = self.records.lock().unwrap();
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&*self.path.lock().unwrap())
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = written {
+            eprintln!("Failed to write usage log: {}", e);
+        }
+        records.push(record);
+    }
+
+    pub fn report(
+        &self,
+        mgr: &ConversationManager,
+        period: ReportPeriod,
+        now: i64,
+        prices: &PriceOverrides,
+    ) -> UsageReport {
+        let period_start = period.start(now);
+        let in_period = |timestamp: i64| match period_start {
+            Some(start) => timestamp >= start,
+            None => true,
+        };
+
+        let mut chats_per_week: BTreeMap<i64, usize> = BTreeMap::new();
+        let mut templates: HashMap<String, usize> = HashMap::new();
+        let mut style_presets: HashMap<String, usize> = HashMap::new();
+        let mut failed_generations = Vec::new();
+        for conv in mgr.conversations.values() {
+            if let Some(created) = conv.history.first().map(|record| record.timestamp) {
+                if in_period(created) {
+                    *chats_per_week.entry(week_start(created)).or_default() += 1;
+                    if let Some(template) = conv.template() {
+                        *templates.entry(template.to_string()).or_default() += 1;
+                    }
+                    if let Some(preset) = conv.style_preset() {
+                        *style_presets.entry(preset.name).or_default() += 1;
+                    }
+                }
+            }
+            for (record, failure, recovered) in conv.generation_failures() {
+                if in_period(record.timestamp) {
+                    failed_generations.push(FailedGeneration {
+                        conversation_id: conv.id,
+                        timestamp: record.timestamp,
+                        error: failure.error.clone(),
+                        recovered,
+                    });
+                }
+            }
+        }
+        failed_generations.sort_by_key(|failure| std::cmp::Reverse(failure.timestamp));
+
+        let mut tokens_per_model: HashMap<&str, ModelTokenUsage> = HashMap::new();
+        let records = self.records.lock().unwrap();
+        for record in records.iter().filter(|record| in_period(record.timestamp)) {
+            let usage = tokens_per_model
+                .entry(&record.model)
+                .or_insert_with(|| ModelTokenUsage {
+                    model: record.model.clone(),
+                    ..Default::default()
+                });
+            usage.requests += 1;
+            usage.prompt_tokens += u64::from(record.prompt_tokens);
+            usage.completion_tokens += u64::from(record.completion_tokens);
+        }
+        let mut tokens_per_model: Vec<ModelTokenUsage> = tokens_per_model.into_values().collect();
+        tokens_per_model.sort_by(|a, b| a.model.cmp(&b.model));
+        for usage in &mut tokens_per_model {
+            usage.cost_usd = pricing::price(prices, &usage.model).map(|price| {
+                (usage.prompt_tokens as f64 * price.prompt
+                    + usage.completion_tokens as f64 * price.completion)
+                    / 1_000_000.0
+            });
+        }
+
+        UsageReport {
+            period_start,
+            chats_per_week: chats_per_week
+                .into_iter()
+                .map(|(week_start, chats)| WeeklyChatCount { week_start, chats })
+                .collect(),
+            tokens_per_model,
+            top_templates: most_used(templates),
+            top_style_presets: most_used(style_presets),
+            failed_generations,
+        }
+    }
+
+    /// Drops every record, both in memory and on disk.
+    pub fn clear(&self) -> Result<(), std::io::Error> {
+        self.records.lock().unwrap().clear();
+        let path = self.path.lock().unwrap();
+        if path.exists() {
+            std::fs::remove_file(&*path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::{Conversation, ConversationSetting, ConversationSettingChangedEvent};
+
+    #[test]
+    fn test_week_start() {
+        // 1970-01-05 was a Monday.
+        assert_eq!(week_start(4 * SECONDS_PER_DAY), 4 * SECONDS_PER_DAY);
+        assert_eq!(week_start(10 * SECONDS_PER_DAY + 5), 4 * SECONDS_PER_DAY);
+        assert_eq!(week_start(3 * SECONDS_PER_DAY), -3 * SECONDS_PER_DAY);
+    }
+
+    #[test]
+    fn test_top_templates_and_presets() {
+        let mut mgr = ConversationManager::new();
+        let concise = crate::presets::built_in_presets().remove(0);
+        for template in ["Standup", "Standup", "Review"] {
+            let mut conv = Conversation::from_template(template);
+            conv.add_event(ConversationSettingChangedEvent {
+                setting: ConversationSetting::StylePreset(Some(concise.clone())),
+            });
+            mgr.conversations.insert(conv.id, conv);
+        }
+        let conv = Conversation::new();
+        mgr.conversations.insert(conv.id, conv);
+
+        let log = UsageLog::new(PathBuf::from("missing-usage.jsonl"));
+        let now = chrono::Utc::now().timestamp();
+        let report = log.report(&mgr, ReportPeriod::All, now, &PriceOverrides::new());
+        let usage = |name: &str, conversations| NamedUsage {
+            name: name.to_string(),
+            conversations,
+        };
+        assert_eq!(
+            report.top_templates,
+            [usage("Standup", 2), usage("Review", 1)]
+        );
+        assert_eq!(report.top_style_presets, [usage(&concise.name, 3)]);
+
+        let later = now + 8 * SECONDS_PER_DAY;
+        let report = log.report(&mgr, ReportPeriod::Week, later, &PriceOverrides::new());
+        assert!(report.top_templates.is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+//! Appearance settings shared by every window.
+
+use serde::{Deserialize, Serialize};
+
+pub const MIN_FONT_SIZE: u8 = 10;
+pub const MAX_FONT_SIZE: u8 = 32;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// Follow the operating system.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Density {
+    Compact,
+    #[default]
+    Comfortable,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AppearanceConfig {
+    #[serde(default)]
+    pub theme: Theme,
+    /// Base font size in pixels.
+    #[serde(default = "default_font_size")]
+    pub font_size: u8,
+    #[serde(default)]
+    pub density: Density,
+    /// Name of a syntect theme used to highlight code blocks.
+    #[serde(default = "default_code_theme")]
+    pub code_theme: String,
+}
+
+fn default_font_size() -> u8 {
+    14
+}
+
+fn default_code_theme() -> String {
+    "InspiredGitHub".to_string()
+}
+
+impl Default for AppearanceConfig {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            font_size: default_font_size(),
+            density: Density::default(),
+            code_theme: default_code_theme(),
+        }
+    }
+}
+
+/// Names of the bundled code themes.
+pub fn code_themes() -> Vec<String> {
+    syntect::highlighting::ThemeSet::load_defaults()
+        .themes
+        .into_keys()
+        .collect()
+}
+
+impl AppearanceConfig {
+    pub fn is_valid(&self) -> bool {
+        (MIN_FONT_SIZE..=MAX_FONT_SIZE).contains(&self.font_size)
+            && code_themes().contains(&self.code_theme)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_valid() {
+        assert!(AppearanceConfig::default().is_valid());
+        let too_small = AppearanceConfig {
+            font_size: 4,
+            ..Default::default()
+        };
+        assert!(!too_small.is_valid());
+        let unknown_theme = AppearanceConfig {
+            code_theme: "Neon".to_string(),
+            ..Default::default()
+        };
+        assert!(!unknown_theme.is_valid());
+    }
+}
@@ -0,0 +1,412 @@
+//! `.ehya` archives: a single conversation with its full event log and the attachments its
+//! messages show, for moving it to another machine without losing anything.
+//!
+//! An archive is a zip holding `manifest.json`, the events as `events.jsonl`, one record per
+//! line in the order they were added, and each attachment under `attachments/`.
+
+use std::{
+    collections::BTreeSet,
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, Cursor, Read, Seek, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    compression, html,
+    models::{history_checksum, Conversation, ConversationEvent, ConversationEventRecord},
+};
+
+pub const ARCHIVE_EXTENSION: &str = "ehya";
+/// For several archives bundled together by `write_bundle`.
+pub const BUNDLE_EXTENSION: &str = "zip";
+/// Identifies the manifest as ours.
+const FORMAT: &str = "ehyaioess.conversation";
+/// Raised whenever a change to the layout would be misread by older versions.
+pub const FORMAT_VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+const EVENTS: &str = "events.jsonl";
+const ATTACHMENTS: &str = "attachments/";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    pub conversation_id: Uuid,
+    pub title: String,
+    /// Unix seconds.
+    pub exported_at: i64,
+    pub event_count: usize,
+    /// `history_checksum` of the events, so a damaged archive is refused rather than imported.
+    pub checksum: String,
+    /// Names of the attachments included; ones already missing when exporting are left out.
+    pub attachments: Vec<String>,
+}
+
+/// A conversation read back from an archive.
+#[derive(Debug)]
+pub struct Archive {
+    pub manifest: Manifest,
+    pub conversation: Conversation,
+    /// Name and contents, decompressed.
+    pub attachments: Vec<(String, Vec<u8>)>,
+}
+
+#[derive(Debug)]
+pub enum ArchiveError {
+    /// Written by a newer version of the app, in a format this one can't read.
+    UnsupportedVersion(u32),
+    /// Not an archive, or one that's incomplete or damaged.
+    Invalid(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::UnsupportedVersion(version) => write!(
+                f,
+                "archive format version {} is newer than {}",
+                version, FORMAT_VERSION
+            ),
+            ArchiveError::Invalid(reason) => write!(f, "not a valid archive: {}", reason),
+            ArchiveError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+impl From<io::Error> for ArchiveError {
+    fn from(e: io::Error) -> Self {
+        ArchiveError::Io(e)
+    }
+}
+
+impl From<zip::result::ZipError> for ArchiveError {
+    fn from(e: zip::result::ZipError) -> Self {
+        match e {
+            zip::result::ZipError::Io(e) => ArchiveError::Io(e),
+            e => ArchiveError::Invalid(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ArchiveError {
+    fn from(e: serde_json::Error) -> Self {
+        ArchiveError::Invalid(e.to_string())
+    }
+}
+
+/// Names of the attachments the conversation's messages show, each once.
+fn referenced_attachments(conversation: &Conversation) -> BTreeSet<String> {
+    conversation
+        .history
+        .iter()
+        .filter_map(|record| match &record.event {
+            ConversationEvent::MessageAdded(msg) => Some(html::attachment_references(&msg.content)),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// Writes `conversation` and its attachments from `attachments_dir` as an archive at `path`.
+pub fn write(
+    conversation: &Conversation,
+    attachments_dir: &Path,
+    path: &Path,
+    now: i64,
+) -> Result<Manifest, ArchiveError> {
+    write_to(conversation, attachments_dir, File::create(path)?, now)
+}
+
+/// Writes one archive per conversation into a zip at `path`, each named `<id>.ehya` so it
+/// can be unpacked and imported on its own. `on_progress(done, total)` is called after each.
+pub fn write_bundle(
+    conversations: &[Arc<Conversation>],
+    attachments_dir: &Path,
+    path: &Path,
+    now: i64,
+    mut on_progress: impl FnMut(usize, usize),
+) -> Result<Vec<Manifest>, ArchiveError> {
+    let mut bundle = ZipWriter::new(File::create(path)?);
+    // The archives are compressed already.
+    let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    let mut manifests = Vec::new();
+    for (done, conversation) in conversations.iter().enumerate() {
+        let mut archive = Cursor::new(Vec::new());
+        manifests.push(write_to(conversation, attachments_dir, &mut archive, now)?);
+        bundle.start_file(format!("{}.{}", conversation.id, ARCHIVE_EXTENSION), options)?;
+        bundle.write_all(archive.get_ref())?;
+        on_progress(done + 1, conversations.len());
+    }
+    bundle.finish()?;
+    Ok(manifests)
+}
+
+/// Writes `conversation` and its attachments from `attachments_dir` as an archive to `writer`.
+pub fn write_to(
+    conversation: &Conversation,
+    attachments_dir: &Path,
+    writer: impl Write + Seek,
+    now: i64,
+) -> Result<Manifest, ArchiveError> {
+    let mut zip = ZipWriter::new(writer);
+    let options = FileOptions::default();
+
+    zip.start_file(EVENTS, options)?;
+    for record in &conversation.history {
+        serde_json::to_writer(&mut zip, record)?;
+        zip.write_all(b"\n")?;
+    }
+
+    let mut attachments = Vec::new();
+    for name in referenced_attachments(conversation) {
+        let bytes = match compression::read_attachment(attachments_dir, &name) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        zip.start_file(format!("{}{}", ATTACHMENTS, name), options)?;
+        zip.write_all(&bytes)?;
+        attachments.push(name);
+    }
+
+    let manifest = Manifest {
+        format: FORMAT.to_string(),
+        version: FORMAT_VERSION,
+        conversation_id: conversation.id,
+        title: conversation.get_title().into_owned(),
+        exported_at: now,
+        event_count: conversation.history.len(),
+        checksum: history_checksum(&conversation.history),
+        attachments,
+    };
+    zip.start_file(MANIFEST, options)?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+    zip.finish()?;
+    Ok(manifest)
+}
+
+fn read_entry<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<Vec<u8>, ArchiveError> {
+    let mut entry = zip.by_name(name).map_err(|e| match e {
+        zip::result::ZipError::FileNotFound => {
+            ArchiveError::Invalid(format!("{} is missing", name))
+        }
+        e => e.into(),
+    })?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Reads the archive at `path`, checking its events against the manifest. Attachments are
+/// only taken for the images the messages show, so an archive can't plant other files.
+pub fn read(path: &Path) -> Result<Archive, ArchiveError> {
+    read_from(File::open(path)?)
+}
+
+/// Reads an archive from `reader` the way `read` does.
+pub fn read_from(reader: impl Read + Seek) -> Result<Archive, ArchiveError> {
+    let mut zip = ZipArchive::new(reader)?;
+    let manifest: Manifest = serde_json::from_slice(&read_entry(&mut zip, MANIFEST)?)?;
+    if manifest.format != FORMAT {
+        return Err(ArchiveError::Invalid(format!(
+            "unknown format {}",
+            manifest.format
+        )));
+    }
+    if manifest.version > FORMAT_VERSION {
+        return Err(ArchiveError::UnsupportedVersion(manifest.version));
+    }
+
+    let history = BufReader::new(&read_entry(&mut zip, EVENTS)?[..])
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| Ok(serde_json::from_str::<ConversationEventRecord>(&line?)?))
+        .collect::<Result<Vec<_>, ArchiveError>>()?;
+    if history.len() != manifest.event_count || history_checksum(&history) != manifest.checksum {
+        return Err(ArchiveError::Invalid(
+            "events don't match the manifest".to_string(),
+        ));
+    }
+    if let Some(record) = history
+        .iter()
+        .find(|record| record.conversation_id != manifest.conversation_id)
+    {
+        return Err(ArchiveError::Invalid(format!(
+            "event {} belongs to another conversation",
+            record.id
+        )));
+    }
+    let conversation = Conversation {
+        id: manifest.conversation_id,
+        history,
+    };
+
+    let mut attachments = Vec::new();
+    for name in referenced_attachments(&conversation) {
+        if manifest.attachments.contains(&name) {
+            let bytes = read_entry(&mut zip, &format!("{}{}", ATTACHMENTS, name))?;
+            attachments.push((name, bytes));
+        }
+    }
+    Ok(Archive {
+        manifest,
+        conversation,
+        attachments,
+    })
+}
+
+/// Stores the archive's attachments in `attachments_dir`, leaving any already there alone.
+/// Returns how many were added.
+pub fn restore_attachments(
+    archive: &Archive,
+    attachments_dir: &Path,
+    compress: bool,
+) -> io::Result<usize> {
+    let mut restored = 0;
+    for (name, bytes) in &archive.attachments {
+        if compression::attachment_path(attachments_dir, name).is_some() {
+            continue;
+        }
+        compression::write_attachment(attachments_dir, name, bytes, compress)?;
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::{ConversationMessageAddedEvent, ConversationTitleChangedEvent};
+
+    fn temp_dir() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("archive-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn conversation() -> Conversation {
+        let mut conv = Conversation::new();
+        conv.add_event(ConversationTitleChangedEvent {
+            new_title: "Floor plan".to_string(),
+        });
+        conv.add_event(ConversationMessageAddedEvent {
+            author: chatgpt::types::Role::User,
+            content: "![plan](attachment:plan.png) ![gone](attachment:gone.png)".to_string(),
+            model: None,
+            language: None,
+            citations: Vec::new(),
+        });
+        conv
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = temp_dir();
+        let attachments_dir = dir.join("attachments");
+        std::fs::create_dir_all(&attachments_dir).unwrap();
+        std::fs::write(attachments_dir.join("plan.png"), b"png bytes").unwrap();
+        std::fs::write(attachments_dir.join("unrelated.png"), b"other").unwrap();
+        let conv = conversation();
+        let path = dir.join("plan.ehya");
+
+        let manifest = write(&conv, &attachments_dir, &path, 1_700_000_000).unwrap();
+        assert_eq!(manifest.title, "Floor plan");
+        assert_eq!(manifest.attachments, ["plan.png"]);
+
+        let archive = read(&path).unwrap();
+        assert_eq!(archive.manifest, manifest);
+        assert_eq!(archive.conversation.id, conv.id);
+        assert_eq!(
+            serde_json::to_value(&archive.conversation.history).unwrap(),
+            serde_json::to_value(&conv.history).unwrap()
+        );
+        assert_eq!(
+            archive.attachments,
+            [("plan.png".to_string(), b"png bytes".to_vec())]
+        );
+
+        let other_dir = dir.join("other");
+        assert_eq!(restore_attachments(&archive, &other_dir, true).unwrap(), 1);
+        assert_eq!(
+            compression::read_attachment(&other_dir, "plan.png").unwrap(),
+            b"png bytes"
+        );
+        assert_eq!(restore_attachments(&archive, &other_dir, true).unwrap(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_bundle() {
+        let dir = temp_dir();
+        let conversations = [
+            Arc::new(conversation()),
+            Arc::new(Conversation::new()),
+        ];
+        let path = dir.join("bundle.zip");
+        let mut progress = Vec::new();
+        let manifests = write_bundle(&conversations, &dir, &path, 0, |done, total| {
+            progress.push((done, total))
+        })
+        .unwrap();
+        assert_eq!(manifests.len(), 2);
+        assert_eq!(progress, [(1, 2), (2, 2)]);
+
+        let mut bundle = ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        for conv in &conversations {
+            let name = format!("{}.{}", conv.id, ARCHIVE_EXTENSION);
+            let unpacked = dir.join(&name);
+            std::fs::write(&unpacked, read_entry(&mut bundle, &name).unwrap()).unwrap();
+            assert_eq!(read(&unpacked).unwrap().conversation.id, conv.id);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn rewrite_manifest(path: &Path, edit: impl FnOnce(&mut Manifest)) {
+        let mut zip = ZipArchive::new(File::open(path).unwrap()).unwrap();
+        let events = read_entry(&mut zip, EVENTS).unwrap();
+        let mut manifest: Manifest =
+            serde_json::from_slice(&read_entry(&mut zip, MANIFEST).unwrap()).unwrap();
+        edit(&mut manifest);
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        zip.start_file(EVENTS, FileOptions::default()).unwrap();
+        zip.write_all(&events).unwrap();
+        zip.start_file(MANIFEST, FileOptions::default()).unwrap();
+        zip.write_all(&serde_json::to_vec(&manifest).unwrap())
+            .unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_refuses_newer_and_damaged_archives() {
+        let dir = temp_dir();
+        let path = dir.join("plan.ehya");
+        write(&conversation(), &dir, &path, 0).unwrap();
+
+        rewrite_manifest(&path, |manifest| manifest.version = FORMAT_VERSION + 1);
+        assert!(matches!(
+            read(&path),
+            Err(ArchiveError::UnsupportedVersion(version)) if version == FORMAT_VERSION + 1
+        ));
+        rewrite_manifest(&path, |manifest| {
+            manifest.version = FORMAT_VERSION;
+            manifest.event_count += 1;
+        });
+        assert!(matches!(read(&path), Err(ArchiveError::Invalid(_))));
+
+        std::fs::write(&path, b"not a zip").unwrap();
+        assert!(matches!(read(&path), Err(ArchiveError::Invalid(_))));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use chatgpt::types::Role;
+use uuid::Uuid;
+
+use crate::models::{ConversationEvent, ConversationManager};
+
+const SNIPPET_CHARS: usize = 240;
+
+#[derive(Debug, Clone)]
+pub struct Bookmark {
+    pub conversation_id: Uuid,
+    pub conversation_title: String,
+    pub message_id: Uuid,
+    pub author: Role,
+    pub snippet: String,
+    /// Snippet of the message that preceded the bookmarked one, usually the question it answers.
+    pub context: Option<String>,
+    pub bookmarked_at: i64,
+}
+
+/// Cuts `text` down to `max_chars` characters on a word boundary, marking the cut with an ellipsis.
+pub fn snippet(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(boundary) if boundary > 0 => &cut[..boundary],
+        _ => &cut,
+    };
+    format!("{}…", cut.trim_end())
+}
+
+/// Every bookmarked message across all conversations, most recently bookmarked first.
+pub fn list_bookmarks(mgr: &ConversationManager) -> Vec<Bookmark> {
+    let mut bookmarks = Vec::new();
+    for conv in mgr.conversations.values() {
+        // One pass for the latest bookmark state of each message, rather than a search of the
+        // history per message.
+        let mut bookmarked_at = HashMap::new();
+        for record in &conv.history {
+            if let ConversationEvent::MessageBookmarked(event) = &record.event {
+                bookmarked_at.insert(
+                    event.message_id,
+                    event.bookmarked.then_some(record.timestamp),
+                );
+            }
+        }
+        if !bookmarked_at.values().any(Option::is_some) {
+            continue;
+        }
+        let title = conv.get_title().into_owned();
+        let mut previous_message = None;
+        for record in &conv.history {
+            let ConversationEvent::MessageAdded(msg) = &record.event else {
+                continue;
+            };
+            if let Some(&Some(bookmarked_at)) = bookmarked_at.get(&record.id) {
+                bookmarks.push(Bookmark {
+                    conversation_id: conv.id,
+                    conversation_title: title.clone(),
+                    message_id: record.id,
+                    author: msg.author,
+                    snippet: snippet(&msg.content, SNIPPET_CHARS),
+                    context: previous_message.map(|content| snippet(content, SNIPPET_CHARS)),
+                    bookmarked_at,
+                });
+            }
+            previous_message = Some(msg.content.as_str());
+        }
+    }
+    bookmarks.sort_by_key(|bookmark| std::cmp::Reverse(bookmark.bookmarked_at));
+    bookmarks
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_snippet() {
+        assert_eq!(snippet("  short  ", 10), "short");
+        assert_eq!(snippet("hello wonderful world", 12), "hello…");
+        assert_eq!(snippet("abcdefghij", 4), "abcd…");
+    }
+
+    #[test]
+    fn test_list_bookmarks() {
+        use crate::models::{
+            Conversation, ConversationMessageAddedEvent, ConversationMessageBookmarkedEvent,
+        };
+
+        let message = |author, content: &str| ConversationMessageAddedEvent {
+            author,
+            content: content.to_string(),
+            model: None,
+            language: None,
+            citations: Vec::new(),
+        };
+        let mut conv = Conversation::new();
+        let question = conv.add_event(message(Role::User, "question")).id;
+        let answer = conv.add_event(message(Role::Assistant, "answer")).id;
+        for (message_id, bookmarked) in [(question, true), (answer, true), (question, false)] {
+            conv.add_event(ConversationMessageBookmarkedEvent {
+                message_id,
+                bookmarked,
+            });
+        }
+        let mut mgr = ConversationManager::new();
+        mgr.conversations.insert(conv.id, conv);
+        let unbookmarked = Conversation::new();
+        mgr.conversations.insert(unbookmarked.id, unbookmarked);
+
+        let bookmarks = list_bookmarks(&mgr);
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].message_id, answer);
+        assert_eq!(bookmarks[0].snippet, "answer");
+        assert_eq!(bookmarks[0].context.as_deref(), Some("question"));
+    }
+}
@@ -0,0 +1,168 @@
+//! The models the provider offers, with what's known of each, cached between runs so the
+//! model pickers and token budgets work without asking the provider every time.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::pricing::{self, PriceOverrides};
+
+/// Context window in tokens and whether images are accepted, by model name prefix. The
+/// models endpoint lists names only. More specific prefixes must come before the ones they
+/// extend.
+const CAPABILITIES: &[(&str, usize, bool)] = &[
+    ("gpt-4o", 128_000, true),
+    ("gpt-4-turbo", 128_000, true),
+    ("gpt-4-vision", 128_000, true),
+    ("gpt-4-0125", 128_000, false),
+    ("gpt-4-1106", 128_000, false),
+    ("gpt-4-32k", 32_768, false),
+    ("gpt-4", 8_192, false),
+    ("gpt-3.5-turbo-instruct", 4_096, false),
+    ("gpt-3.5-turbo", 16_385, false),
+    ("text-embedding", 8_192, false),
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ModelInfo {
+    pub id: String,
+    pub owned_by: String,
+    /// Tokens of prompt and reply together; None for a model we know nothing about.
+    pub context_window: Option<usize>,
+    pub vision: bool,
+    /// USD per million tokens, from `pricing` with the config's overrides; None without a
+    /// known price.
+    pub prompt_price: Option<f64>,
+    pub completion_price: Option<f64>,
+}
+
+impl ModelInfo {
+    pub fn new(id: &str, owned_by: &str, prices: &PriceOverrides) -> Self {
+        let capabilities = CAPABILITIES
+            .iter()
+            .find(|(prefix, _, _)| id.starts_with(prefix));
+        let mut info = Self {
+            id: id.to_string(),
+            owned_by: owned_by.to_string(),
+            context_window: capabilities.map(|(_, context_window, _)| *context_window),
+            vision: capabilities.is_some_and(|(_, _, vision)| *vision),
+            prompt_price: None,
+            completion_price: None,
+        };
+        info.reprice(prices);
+        info
+    }
+
+    fn reprice(&mut self, prices: &PriceOverrides) {
+        let price = pricing::price(prices, &self.id);
+        self.prompt_price = price.map(|price| price.prompt);
+        self.completion_price = price.map(|price| price.completion);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ModelCatalog {
+    /// Unix seconds of the last refresh; None until the provider was first asked.
+    pub fetched_at: Option<i64>,
+    /// Sorted by id.
+    pub models: Vec<ModelInfo>,
+}
+
+impl ModelCatalog {
+    /// The catalog `(id, owned_by)` pairs from the models endpoint make at `now`.
+    pub fn from_listing(listing: &[(String, String)], now: i64, prices: &PriceOverrides) -> Self {
+        let mut models: Vec<ModelInfo> = listing
+            .iter()
+            .map(|(id, owned_by)| ModelInfo::new(id, owned_by, prices))
+            .collect();
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+        models.dedup_by(|a, b| a.id == b.id);
+        Self {
+            fetched_at: Some(now),
+            models,
+        }
+    }
+
+    /// The catalog at `path`, priced as of now; empty when there's none yet or it can't be
+    /// read, since it can always be fetched again.
+    pub fn open(path: &Path, prices: &PriceOverrides) -> Self {
+        let mut catalog: Self = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        for model in &mut catalog.models {
+            model.reprice(prices);
+        }
+        catalog
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ModelInfo> {
+        self.models.iter().find(|model| model.id == id)
+    }
+
+    /// What's known of `model`, from the catalog or, for a model it doesn't list, from its name.
+    pub fn info(&self, model: &str, prices: &PriceOverrides) -> ModelInfo {
+        self.get(model)
+            .cloned()
+            .unwrap_or_else(|| ModelInfo::new(model, "", prices))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_model_info() {
+        let prices = PriceOverrides::new();
+        let mini = ModelInfo::new("gpt-4o-mini-2024-07-18", "system", &prices);
+        assert_eq!(mini.context_window, Some(128_000));
+        assert!(mini.vision);
+        assert_eq!(mini.prompt_price, Some(0.15));
+        let gpt4 = ModelInfo::new("gpt-4-0613", "openai", &prices);
+        assert_eq!((gpt4.context_window, gpt4.vision), (Some(8_192), false));
+        let local = ModelInfo::new("llama3", "", &prices);
+        assert_eq!((local.context_window, local.prompt_price), (None, None));
+    }
+
+    #[test]
+    fn test_catalog_round_trip() {
+        let listing = [
+            ("gpt-4o".to_string(), "system".to_string()),
+            ("gpt-3.5-turbo".to_string(), "openai".to_string()),
+            ("gpt-4o".to_string(), "system".to_string()),
+        ];
+        let prices = PriceOverrides::new();
+        let catalog = ModelCatalog::from_listing(&listing, 1_700_000_000, &prices);
+        let ids: Vec<&str> = catalog.models.iter().map(|model| model.id.as_str()).collect();
+        assert_eq!(ids, ["gpt-3.5-turbo", "gpt-4o"]);
+
+        let path = std::env::temp_dir().join(format!("catalog-{}.json", uuid::Uuid::new_v4()));
+        catalog.save(&path).unwrap();
+        let reopened = ModelCatalog::open(&path, &prices);
+        assert_eq!(reopened.fetched_at, Some(1_700_000_000));
+        assert_eq!(reopened.models, catalog.models);
+        assert_eq!(reopened.info("gpt-4o", &prices).owned_by, "system");
+        assert_eq!(reopened.info("gpt-4-32k", &prices).context_window, Some(32_768));
+        // prices changed since the refresh apply to the cached models
+        let overrides = PriceOverrides::from([(
+            "gpt-4o".to_string(),
+            pricing::ModelPrice {
+                prompt: 1.0,
+                completion: 4.0,
+            },
+        )]);
+        let repriced = ModelCatalog::open(&path, &overrides);
+        assert_eq!(repriced.get("gpt-4o").unwrap().prompt_price, Some(1.0));
+        std::fs::remove_file(&path).unwrap();
+        assert!(ModelCatalog::open(&path, &prices).models.is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+//! Sources cited in assistant replies: markdown links, footnote-style references such as
+//! `[1]: https://…` and bare URLs, in the order they appear.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Citation {
+    pub url: String,
+    /// The footnote marker, e.g. "1" for `[1]` or `[^1]`, or the text of a markdown link.
+    /// None for a bare URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+struct Patterns {
+    footnote: Regex,
+    link: Regex,
+    url: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        footnote: Regex::new(r"(?m)^[ \t]*\[\^?([^\]\n]+)\]:[^\n]*?(https?://[^\s<>]+)").unwrap(),
+        link: Regex::new(r"\[([^\]\n]+)\]\((https?://[^\s)]+)\)").unwrap(),
+        url: Regex::new(r#"https?://[^\s<>"'`\[\]]+"#).unwrap(),
+    })
+}
+
+/// Drops the punctuation a URL at the end of a sentence or inside parentheses picks up.
+fn trim_url(url: &str) -> &str {
+    let mut url = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '"', '\'']);
+    while url.ends_with(')') && url.matches(')').count() > url.matches('(').count() {
+        url = url[..url.len() - 1].trim_end_matches(['.', ',', ';', ':', '!', '?']);
+    }
+    url
+}
+
+/// The sources `content` cites, each URL once. A URL cited both with a label and bare keeps
+/// the label.
+pub fn extract(content: &str) -> Vec<Citation> {
+    let patterns = patterns();
+    let mut found: Vec<(usize, &str, Option<&str>)> = Vec::new();
+    for pattern in [&patterns.footnote, &patterns.link] {
+        for captures in pattern.captures_iter(content) {
+            let (label, url) = (captures.get(1).unwrap(), captures.get(2).unwrap());
+            found.push((
+                url.start(),
+                trim_url(url.as_str()),
+                Some(label.as_str().trim()),
+            ));
+        }
+    }
+    for url in patterns.url.find_iter(content) {
+        found.push((url.start(), trim_url(url.as_str()), None));
+    }
+    found.sort_by_key(|(start, _, label)| (*start, label.is_none()));
+
+    let mut citations: Vec<Citation> = Vec::new();
+    for (_, url, label) in found {
+        match citations.iter_mut().find(|citation| citation.url == url) {
+            Some(citation) => {
+                if citation.label.is_none() {
+                    citation.label = label.map(str::to_string);
+                }
+            }
+            None => citations.push(Citation {
+                url: url.to_string(),
+                label: label.map(str::to_string),
+            }),
+        }
+    }
+    citations
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn citation(url: &str, label: Option<&str>) -> Citation {
+        Citation {
+            url: url.to_string(),
+            label: label.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_extract() {
+        let reply = "Rust is memory safe [1], see [the book](https://doc.rust-lang.org/book/).\n\
+            More at https://www.rust-lang.org. Also (https://en.wikipedia.org/wiki/Rust_(programming_language)).\n\
+            \n\
+            [1]: https://example.com/safety \"Safety\"\n\
+            [^2]: Smith, 2021, https://example.com/paper, p. 4\n\
+            Again: https://doc.rust-lang.org/book/";
+        assert_eq!(
+            extract(reply),
+            [
+                citation("https://doc.rust-lang.org/book/", Some("the book")),
+                citation("https://www.rust-lang.org", None),
+                citation(
+                    "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+                    None
+                ),
+                citation("https://example.com/safety", Some("1")),
+                citation("https://example.com/paper", Some("2")),
+            ]
+        );
+        assert!(extract("No sources, just [brackets] and (parentheses).").is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+//! Grouping conversations by topic: k-means over their embeddings (see `embeddings`), with
+//! each group then named by the model.
+
+use chatgpt::types::{ChatMessage, Role};
+
+const MAX_ITERATIONS: usize = 50;
+/// Titles of a cluster shown to the model when asking for its label.
+const LABEL_SAMPLE_TITLES: usize = 10;
+const MAX_LABEL_CHARS: usize = 40;
+
+pub(crate) fn normalized(vector: &[f32]) -> Vec<f32> {
+    let length = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if length == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|x| x / length).collect()
+}
+
+pub(crate) fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn nearest(centroids: &[Vec<f32>], point: &[f32]) -> usize {
+    (0..centroids.len())
+        .max_by(|&a, &b| {
+            similarity(&centroids[a], point).total_cmp(&similarity(&centroids[b], point))
+        })
+        .unwrap_or(0)
+}
+
+/// The cluster of each vector, by cosine similarity, with `k` capped at the number of vectors.
+/// Deterministic for the same input, so the groups don't shuffle between calls.
+pub fn kmeans(vectors: &[Vec<f32>], k: usize) -> Vec<usize> {
+    let points: Vec<Vec<f32>> = vectors.iter().map(|vector| normalized(vector)).collect();
+    let k = k.min(points.len());
+    if k == 0 {
+        return Vec::new();
+    }
+    // Farthest-point initialization: start from the first point, then keep adding the point
+    // least similar to every centroid so far.
+    let mut centroids = vec![points[0].clone()];
+    while centroids.len() < k {
+        let closeness = |point: &Vec<f32>| {
+            centroids
+                .iter()
+                .map(|centroid| similarity(centroid, point))
+                .fold(f32::MIN, f32::max)
+        };
+        let farthest = points
+            .iter()
+            .min_by(|a, b| closeness(a).total_cmp(&closeness(b)))
+            .unwrap();
+        centroids.push(farthest.clone());
+    }
+
+    let mut assignments: Vec<usize> = Vec::new();
+    for _ in 0..MAX_ITERATIONS {
+        let next: Vec<usize> = points
+            .iter()
+            .map(|point| nearest(&centroids, point))
+            .collect();
+        if next == assignments {
+            break;
+        }
+        assignments = next;
+        for (cluster, centroid) in centroids.iter_mut().enumerate() {
+            let mut sum = vec![0.0; centroid.len()];
+            let mut members = 0;
+            for (point, _) in points
+                .iter()
+                .zip(&assignments)
+                .filter(|(_, assigned)| **assigned == cluster)
+            {
+                for (total, x) in sum.iter_mut().zip(point) {
+                    *total += x;
+                }
+                members += 1;
+            }
+            // An emptied cluster keeps its centroid and may win points back.
+            if members > 0 {
+                *centroid = normalized(&sum);
+            }
+        }
+    }
+    assignments
+}
+
+/// The items of each non-empty cluster, largest cluster first.
+pub fn group<T: Clone>(items: &[T], assignments: &[usize]) -> Vec<Vec<T>> {
+    let clusters = assignments.iter().max().map_or(0, |max| max + 1);
+    let mut groups: Vec<Vec<T>> = vec![Vec::new(); clusters];
+    for (item, cluster) in items.iter().zip(assignments) {
+        groups[*cluster].push(item.clone());
+    }
+    groups.retain(|group| !group.is_empty());
+    groups.sort_by_key(|group| std::cmp::Reverse(group.len()));
+    groups
+}
+
+/// The request for a short label summing up the conversations with the given titles.
+pub fn label_messages(titles: &[String]) -> Vec<ChatMessage> {
+    let titles: Vec<&str> = titles
+        .iter()
+        .take(LABEL_SAMPLE_TITLES)
+        .map(String::as_str)
+        .collect();
+    vec![
+        ChatMessage {
+            role: Role::System,
+            content: "Name the common topic of the conversations with the following titles in \
+                      at most four words. Reply with only the name."
+                .to_string(),
+        },
+        ChatMessage {
+            role: Role::User,
+            content: titles.join("\n"),
+        },
+    ]
+}
+
+/// The label from the model's reply to `label_messages`.
+pub fn parse_label(reply: &str) -> String {
+    let label = reply
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'' || c == '.');
+    label.chars().take(MAX_LABEL_CHARS).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_kmeans() {
+        let vectors = vec![
+            vec![1.0, 0.1, 0.0],
+            vec![0.0, 1.0, 0.1],
+            vec![0.9, 0.0, 0.1],
+            vec![0.1, 0.9, 0.0],
+            vec![1.0, 0.0, 0.0],
+        ];
+        let assignments = kmeans(&vectors, 2);
+        assert_eq!(assignments[0], assignments[2]);
+        assert_eq!(assignments[0], assignments[4]);
+        assert_eq!(assignments[1], assignments[3]);
+        assert_ne!(assignments[0], assignments[1]);
+        assert_eq!(
+            group(&["a", "b", "c", "d", "e"], &assignments),
+            vec![vec!["a", "c", "e"], vec!["b", "d"]]
+        );
+
+        assert_eq!(kmeans(&vectors, 10).len(), 5);
+        assert!(kmeans(&[], 3).is_empty());
+        assert_eq!(parse_label("\"Rust tooling.\"\nextra"), "Rust tooling");
+    }
+}
@@ -0,0 +1,199 @@
+//! Optional zstd compression of the history file and attachments. Reading is transparent:
+//! compressed data is recognised by its magic number, so either format loads whatever the
+//! setting, and turning the setting on or off only changes how things are written next.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// Starts every zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+/// zstd's default; higher levels save little on JSON for much more time.
+const LEVEL: i32 = 3;
+/// Added to the names of compressed attachments, so their type still shows in the original
+/// name.
+pub const ATTACHMENT_EXTENSION: &str = "zst";
+
+fn starts_with_magic(reader: &mut impl BufRead) -> io::Result<bool> {
+    Ok(reader.fill_buf()?.starts_with(&ZSTD_MAGIC))
+}
+
+/// Whether the file at `path` is zstd-compressed.
+pub fn is_compressed(path: &Path) -> io::Result<bool> {
+    starts_with_magic(&mut BufReader::new(File::open(path)?))
+}
+
+/// Opens `path` for reading, decompressing as it's read when it's compressed.
+pub fn open(path: &Path) -> io::Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    if starts_with_magic(&mut reader)? {
+        Ok(Box::new(zstd::Decoder::with_buffer(reader)?))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    open(path)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    let mut text = String::new();
+    open(path)?.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Creates `path` and hands `write` a writer for its contents, compressing them as they're
+/// written when `compress` is set.
+pub fn write_with(
+    path: &Path,
+    compress: bool,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut file = if compress {
+        let mut encoder = zstd::Encoder::new(file, LEVEL)?;
+        write(&mut encoder)?;
+        encoder.finish()?
+    } else {
+        let mut file = file;
+        write(&mut file)?;
+        file
+    };
+    file.flush()
+}
+
+/// `bytes` as `write_with` would store them.
+pub fn encode(bytes: &[u8], compress: bool) -> io::Result<Vec<u8>> {
+    if compress {
+        zstd::encode_all(bytes, LEVEL)
+    } else {
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Where the attachment `name` is stored in `attachments_dir`, compressed or not, if it's
+/// there at all.
+pub fn attachment_path(attachments_dir: &Path, name: &str) -> Option<PathBuf> {
+    let plain = attachments_dir.join(name);
+    if plain.exists() {
+        return Some(plain);
+    }
+    let compressed = attachments_dir.join(format!("{}.{}", name, ATTACHMENT_EXTENSION));
+    compressed.exists().then_some(compressed)
+}
+
+/// The contents of the attachment `name`, decompressed.
+pub fn read_attachment(attachments_dir: &Path, name: &str) -> io::Result<Vec<u8>> {
+    match attachment_path(attachments_dir, name) {
+        Some(path) => read(&path),
+        None => Err(io::ErrorKind::NotFound.into()),
+    }
+}
+
+/// Stores `bytes` as the attachment `name` in `attachments_dir`, compressed when `compress` is
+/// set.
+pub fn write_attachment(
+    attachments_dir: &Path,
+    name: &str,
+    bytes: &[u8],
+    compress: bool,
+) -> io::Result<()> {
+    std::fs::create_dir_all(attachments_dir)?;
+    let path = match compress {
+        true => attachments_dir.join(format!("{}.{}", name, ATTACHMENT_EXTENSION)),
+        false => attachments_dir.join(name),
+    };
+    write_with(&path, compress, |file| file.write_all(bytes))
+}
+
+/// The name an attachment stored at `path` is referenced by, without the compressed suffix.
+pub fn attachment_name(path: &Path) -> PathBuf {
+    match path.extension() {
+        Some(extension) if extension == ATTACHMENT_EXTENSION => path.with_extension(""),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Compresses (or decompresses) every attachment under `dir` that isn't stored that way yet,
+/// returning how many were rewritten. Each new file is complete before the old one is
+/// removed, so an interrupted migration leaves both and can be run again.
+pub fn migrate_attachments(dir: &Path, compress: bool) -> io::Result<usize> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let mut migrated = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            migrated += migrate_attachments(&path, compress)?;
+            continue;
+        }
+        let name = attachment_name(&path);
+        let stored_compressed = name != path;
+        if stored_compressed == compress {
+            continue;
+        }
+        let target = if compress {
+            let mut target = path.clone().into_os_string();
+            target.push(".");
+            target.push(ATTACHMENT_EXTENSION);
+            PathBuf::from(target)
+        } else {
+            name
+        };
+        let mut source = open(&path)?;
+        write_with(&target, compress, |writer| {
+            io::copy(&mut source, writer).map(|_| ())
+        })?;
+        std::fs::remove_file(&path)?;
+        migrated += 1;
+    }
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compression() {
+        let dir = std::env::temp_dir().join(format!("compression-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        let text = "{\"a\":1}".repeat(1000);
+
+        let file = dir.join("history.json");
+        for compress in [true, false] {
+            write_with(&file, compress, |writer| writer.write_all(text.as_bytes())).unwrap();
+            assert_eq!(is_compressed(&file).unwrap(), compress);
+            assert_eq!(read_to_string(&file).unwrap(), text);
+        }
+
+        std::fs::write(dir.join("image.png"), [1u8; 64]).unwrap();
+        std::fs::write(dir.join("nested").join("doc.pdf"), [2u8; 64]).unwrap();
+        assert_eq!(migrate_attachments(&dir.join("nested"), true).unwrap(), 1);
+        assert!(dir.join("nested").join("doc.pdf.zst").exists());
+        assert!(!dir.join("nested").join("doc.pdf").exists());
+        assert_eq!(
+            read_attachment(&dir, "nested/doc.pdf").unwrap(),
+            vec![2u8; 64]
+        );
+        assert!(read_attachment(&dir, "missing.png").is_err());
+
+        // Attachments already stored the asked way are left alone.
+        assert_eq!(migrate_attachments(&dir.join("nested"), true).unwrap(), 0);
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(migrate_attachments(&dir, false).unwrap(), 1);
+        assert_eq!(
+            std::fs::read(dir.join("nested").join("doc.pdf")).unwrap(),
+            vec![2u8; 64]
+        );
+        assert_eq!(read_attachment(&dir, "image.png").unwrap(), vec![1u8; 64]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,628 @@
+use serde::{Deserialize, Serialize};
+use dirs_next::config_dir;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    appearance::AppearanceConfig, embeddings::EmbeddingCacheConfig, guardrails::GuardrailConfig,
+    keybindings::Keybindings, models::MyError, overrides::ConfigOverrides, pricing::PriceOverrides,
+    retention::RetentionConfig, sync::SyncConfig, workspaces,
+};
+
+pub const DEFAULT_PROFILE_NAME: &str = "default";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiKeyProfile {
+    pub name: String,
+    pub openai_api_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ProxyConfig {
+    /// `http://`, `https://`, `socks5://` or `socks5h://` proxy URL; unset for a direct connection.
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Hosts that bypass the proxy, e.g. `localhost` or `.internal.example.com`.
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EventBridgeConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Address the WebSocket server binds to; keep it on loopback unless other machines need access.
+    #[serde(default = "default_event_bridge_address")]
+    pub address: String,
+}
+
+fn default_event_bridge_address() -> String {
+    "127.0.0.1:7878".to_string()
+}
+
+impl Default for EventBridgeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: default_event_bridge_address(),
+        }
+    }
+}
+
+/// Settings only read at startup, so changing them at runtime needs a restart.
+const RESTART_REQUIRED_KEYS: &[&str] = &[
+    "openai_api_key",
+    "api_key_profiles",
+    "conversation_history_save_path",
+    "proxy",
+    "event_bridge",
+];
+
+/// A top-level setting that differs between two configs, with secrets masked.
+#[derive(Debug, Clone)]
+pub struct ConfigChange {
+    pub key: String,
+    pub old_value: serde_json::Value,
+    pub new_value: serde_json::Value,
+    pub requires_restart: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Config {
+    // Single-key configs written before profiles existed; migrated into a "default" profile on load.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    openai_api_key: Option<String>,
+    #[serde(default)]
+    pub api_key_profiles: Vec<ApiKeyProfile>,
+    #[serde(default)]
+    pub active_profile: String,
+    pub conversation_history_save_path: String,
+    /// Previous history files, kept after moving or repairing the history; see
+    /// `storage::delete_backups`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history_backups: Vec<String>,
+    /// Store the history and attachments zstd-compressed; either format is read regardless.
+    #[serde(default)]
+    pub compress_history: bool,
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+    #[serde(default)]
+    pub record_requests: bool,
+    #[serde(default)]
+    pub event_bridge: EventBridgeConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub embedding_cache: EmbeddingCacheConfig,
+    #[serde(default)]
+    pub sync: SyncConfig,
+    /// Overrides of the default shortcuts; see `keybindings::effective`.
+    #[serde(default)]
+    pub keybindings: Keybindings,
+    #[serde(default)]
+    pub appearance: AppearanceConfig,
+    /// Locale code for backend strings; see `i18n::list_locales`.
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    /// Sent ahead of every conversation's messages; empty for none.
+    #[serde(default)]
+    pub system_prompt: String,
+    /// Model replies, labels and translations are requested from.
+    #[serde(default = "default_model")]
+    pub model: String,
+    /// Tried in order when `model` is unavailable or a conversation overflows its context.
+    #[serde(default)]
+    pub fallback_models: Vec<String>,
+    /// Prices replacing or adding to the built-in ones; see `pricing::price`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub model_pricing: PriceOverrides,
+    /// Guest/demo mode: changes are refused and replies come from the mock provider.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Let the model tag conversations by topic once they've had a few exchanges.
+    #[serde(default)]
+    pub auto_tag: bool,
+    /// Look for a newer release at startup and then daily.
+    #[serde(default)]
+    pub auto_update_check: bool,
+    /// Banned phrases and patterns looked for in replies; see `guardrails`.
+    #[serde(default)]
+    pub guardrails: GuardrailConfig,
+    /// Environment and flag overrides applied on load; never written to the file.
+    #[serde(skip)]
+    pub overrides: ConfigOverrides,
+    /// File values of the overridden settings, written back in their place.
+    #[serde(skip)]
+    overridden_file_values: Vec<(String, serde_json::Value)>,
+    /// The history path in use and the one edited into the file while running, which is
+    /// written back in its place until the restart; see `defer_history_path`.
+    #[serde(skip)]
+    deferred_history_path: Option<(String, String)>,
+}
+
+fn default_locale() -> String {
+    crate::i18n::DEFAULT_LOCALE.to_string()
+}
+
+fn default_model() -> String {
+    crate::provider::DEFAULT_MODEL.to_string()
+}
+
+impl Config {
+    /// Directory of the active workspace profile; see `workspaces`.
+    pub fn get_config_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        if let Some(base) = workspaces::base_dir() {
+            let path = workspaces::dir_of(&base, &workspaces::active());
+            if !Path::new(&path).exists() {
+                std::fs::create_dir_all(&path)?;
+            }
+            return Ok(path);
+        } else {
+            return Err(Box::new(MyError::NoConfigDirFail));
+        }
+    }
+    pub fn get_config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("config.json");
+        Ok(path)
+    }
+    pub fn get_request_log_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("request_log.jsonl");
+        Ok(path)
+    }
+    pub fn get_usage_log_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("usage.jsonl");
+        Ok(path)
+    }
+    pub fn get_drafts_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("drafts.json");
+        Ok(path)
+    }
+    pub fn get_embeddings_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("embeddings.json");
+        Ok(path)
+    }
+    pub fn get_model_catalog_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("models.json");
+        Ok(path)
+    }
+    pub fn get_trash_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("trash.json");
+        Ok(path)
+    }
+    pub fn get_link_previews_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("link_previews.json");
+        Ok(path)
+    }
+    pub fn get_action_items_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("action_items.json");
+        Ok(path)
+    }
+    pub fn get_templates_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("templates.json");
+        Ok(path)
+    }
+    pub fn get_style_presets_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("style_presets.json");
+        Ok(path)
+    }
+    pub fn get_sync_conflicts_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("sync_conflicts.json");
+        Ok(path)
+    }
+    pub fn get_window_layout_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("windows.json");
+        Ok(path)
+    }
+    pub fn get_pending_generations_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("pending_generations.json");
+        Ok(path)
+    }
+    pub fn get_crash_report_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("crash_report.json");
+        Ok(path)
+    }
+    /// Where `share_conversation_encrypted` writes the bundles it makes.
+    pub fn get_shared_bundles_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("shared");
+        Ok(path)
+    }
+    pub fn get_attachments_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("attachments");
+        Ok(path)
+    }
+    /// Indexed repositories; see `knowledge`.
+    pub fn get_knowledge_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let mut path = Config::get_config_dir()?;
+        path.push("knowledge");
+        Ok(path)
+    }
+    pub fn from_disk() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Config::get_config_path()?;
+        println!("Config path: {:?}", path);
+        if path.exists() {
+            let mut file = File::open(path)?;
+            let mut contents = String::new();
+            file.read_to_string(&mut contents)?;
+            let mut config: Config = serde_json::from_str(&contents)?;
+            if config.migrate_legacy_api_key() {
+                config.write_to_disk()?;
+            }
+            Ok(config)
+        } else {
+            let config = Config::from_user()?;
+            config.write_to_disk()?;
+            Ok(config)
+        }
+    }
+
+    /// Reads a config without creating or rewriting it.
+    pub fn read_from(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut config: Config = serde_json::from_str(&contents)?;
+        config.migrate_legacy_api_key();
+        Ok(config)
+    }
+
+    /// Layers environment and flag overrides over the loaded settings.
+    pub fn with_overrides(
+        self,
+        overrides: ConfigOverrides,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut value = serde_json::to_value(&self)?;
+        let file_values = overrides
+            .pointers()
+            .filter_map(|pointer| {
+                let file_value = value.pointer(&pointer)?.clone();
+                Some((pointer, file_value))
+            })
+            .collect();
+        overrides.apply(&mut value)?;
+        let mut config: Config = serde_json::from_value(value)?;
+        config.overrides = overrides;
+        config.overridden_file_values = file_values;
+        Ok(config)
+    }
+
+    /// Settings that changed from `self` to `other`.
+    pub fn diff(&self, other: &Config) -> Vec<ConfigChange> {
+        let (old, new) = (serde_json::to_value(self), serde_json::to_value(other));
+        let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) = (old, new)
+        else {
+            return Vec::new();
+        };
+        let (old_redacted, new_redacted) = (self.redacted(), other.redacted());
+        let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        keys.into_iter()
+            .filter(|key| old.get(*key) != new.get(*key))
+            .map(|key| ConfigChange {
+                key: key.clone(),
+                old_value: old_redacted.get(key).cloned().unwrap_or_default(),
+                new_value: new_redacted.get(key).cloned().unwrap_or_default(),
+                requires_restart: RESTART_REQUIRED_KEYS.contains(&key.as_str()),
+            })
+            .collect()
+    }
+
+    /// `model` followed by the fallback models, each once, in the order to try them.
+    pub fn model_chain(&self) -> Vec<String> {
+        let mut chain: Vec<String> = Vec::new();
+        for model in std::iter::once(&self.model).chain(&self.fallback_models) {
+            let model = model.trim();
+            if !model.is_empty() && !chain.iter().any(|m| m == model) {
+                chain.push(model.to_string());
+            }
+        }
+        if chain.is_empty() {
+            chain.push(default_model());
+        }
+        chain
+    }
+
+    /// Switches backend strings to the configured locale, keeping the default if it's unknown.
+    pub fn apply_locale(&self) {
+        if !crate::i18n::set_locale(&self.locale) {
+            eprintln!(
+                "Unknown locale {:?}, using {}",
+                self.locale,
+                crate::i18n::DEFAULT_LOCALE
+            );
+        }
+    }
+
+    pub fn write_to_disk(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.write_to(&Config::get_config_path()?)
+    }
+
+    pub fn write_to(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let deferred_history_path = self.deferred_history_path();
+        let json = if self.overridden_file_values.is_empty() && deferred_history_path.is_none() {
+            serde_json::to_string_pretty(self)?
+        } else {
+            let mut value = serde_json::to_value(self)?;
+            for (pointer, file_value) in &self.overridden_file_values {
+                if let Some(target) = value.pointer_mut(pointer) {
+                    *target = file_value.clone();
+                }
+            }
+            if let Some(edited) = deferred_history_path {
+                value["conversation_history_save_path"] = edited.into();
+            }
+            serde_json::to_string_pretty(&value)?
+        };
+        let mut file = File::create(path)?;
+        file.write_all(json.as_bytes())?;
+        Ok(())
+    }
+
+    /// Goes on using `path_in_use` for the history when the loaded config names another, which
+    /// is kept to write back to the file so it takes effect on the next start. Switching
+    /// while running would write the loaded conversations over another history file.
+    pub fn defer_history_path(&mut self, path_in_use: &str) {
+        if self.conversation_history_save_path != path_in_use {
+            let edited = std::mem::replace(
+                &mut self.conversation_history_save_path,
+                path_in_use.to_string(),
+            );
+            self.deferred_history_path = Some((path_in_use.to_string(), edited));
+        }
+    }
+
+    /// The history path waiting for a restart; see `defer_history_path`. None once the
+    /// history has been moved in the app since.
+    pub fn deferred_history_path(&self) -> Option<&str> {
+        self.deferred_history_path
+            .as_ref()
+            .filter(|(in_use, _)| *in_use == self.conversation_history_save_path)
+            .map(|(_, edited)| edited.as_str())
+    }
+
+    /// The config as JSON with API keys and passwords masked, safe to share.
+    pub fn redacted(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        for profile in value["api_key_profiles"].as_array_mut().into_iter().flatten() {
+            profile["openai_api_key"] = "[redacted]".into();
+        }
+        if let Some(legacy_key) = value.get_mut("openai_api_key") {
+            *legacy_key = "[redacted]".into();
+        }
+        if !value["proxy"]["password"].is_null() {
+            value["proxy"]["password"] = "[redacted]".into();
+        }
+        if !value["sync"]["backend"]["password"].is_null() {
+            value["sync"]["backend"]["password"] = "[redacted]".into();
+        }
+        value
+    }
+
+    /// Moves a pre-profiles `openai_api_key` into a profile, returning true if anything changed.
+    fn migrate_legacy_api_key(&mut self) -> bool {
+        let Some(openai_api_key) = self.openai_api_key.take() else {
+            return false;
+        };
+        if !self.api_key_profiles.iter().any(|p| p.name == DEFAULT_PROFILE_NAME) {
+            self.api_key_profiles.push(ApiKeyProfile {
+                name: DEFAULT_PROFILE_NAME.to_string(),
+                openai_api_key,
+            });
+        }
+        if self.active_profile.is_empty() {
+            self.active_profile = DEFAULT_PROFILE_NAME.to_string();
+        }
+        true
+    }
+
+    /// Settings for a new workspace profile in `dir`, with one API key profile unless
+    /// `openai_api_key` is empty.
+    pub fn for_workspace(dir: &Path, openai_api_key: &str) -> Self {
+        let api_key_profiles = if openai_api_key.trim().is_empty() {
+            Vec::new()
+        } else {
+            vec![ApiKeyProfile {
+                name: DEFAULT_PROFILE_NAME.to_string(),
+                openai_api_key: openai_api_key.trim().to_string(),
+            }]
+        };
+        Config {
+            openai_api_key: None,
+            api_key_profiles,
+            active_profile: DEFAULT_PROFILE_NAME.to_string(),
+            conversation_history_save_path: dir.join("conversations.json").display().to_string(),
+            history_backups: Vec::new(),
+            compress_history: false,
+            proxy: ProxyConfig::default(),
+            record_requests: false,
+            event_bridge: EventBridgeConfig::default(),
+            retention: RetentionConfig::default(),
+            embedding_cache: EmbeddingCacheConfig::default(),
+            sync: SyncConfig::default(),
+            keybindings: Keybindings::new(),
+            appearance: AppearanceConfig::default(),
+            locale: default_locale(),
+            system_prompt: String::new(),
+            model: default_model(),
+            fallback_models: Vec::new(),
+            model_pricing: PriceOverrides::new(),
+            read_only: false,
+            auto_tag: false,
+            auto_update_check: false,
+            guardrails: GuardrailConfig::default(),
+            overrides: ConfigOverrides::default(),
+            overridden_file_values: Vec::new(),
+            deferred_history_path: None,
+        }
+    }
+
+    fn from_user() -> Result<Self, Box<dyn std::error::Error>> {
+        println!("Please enter your OpenAI API Key: ");
+        let mut openai_api_key = String::new();
+        io::stdin().read_line(&mut openai_api_key)?;
+
+        let mut conversation_history_save_path = match config_dir() {
+            Some(mut path) => {
+                path.push("ehyaioess");
+                path.push("conversations.json");
+                path.to_str().unwrap().to_string()
+            }
+            None => {
+                String::new()
+            }
+        };
+        println!("Please enter the path to save conversation history to (leave blank for default): ");
+        let mut user_input = String::new();
+        io::stdin().read_line(&mut user_input)?;
+        if user_input.trim().len() > 0 {
+            conversation_history_save_path = user_input.trim().to_string();
+        }
+
+        Ok(Config {
+            openai_api_key: None,
+            api_key_profiles: vec![ApiKeyProfile {
+                name: DEFAULT_PROFILE_NAME.to_string(),
+                openai_api_key: openai_api_key.trim().to_string(),
+            }],
+            active_profile: DEFAULT_PROFILE_NAME.to_string(),
+            conversation_history_save_path,
+            history_backups: Vec::new(),
+            compress_history: false,
+            proxy: ProxyConfig::default(),
+            record_requests: false,
+            event_bridge: EventBridgeConfig::default(),
+            retention: RetentionConfig::default(),
+            embedding_cache: EmbeddingCacheConfig::default(),
+            sync: SyncConfig::default(),
+            keybindings: Keybindings::new(),
+            appearance: AppearanceConfig::default(),
+            locale: default_locale(),
+            system_prompt: String::new(),
+            model: default_model(),
+            fallback_models: Vec::new(),
+            model_pricing: PriceOverrides::new(),
+            read_only: false,
+            auto_tag: false,
+            auto_update_check: false,
+            guardrails: GuardrailConfig::default(),
+            overrides: ConfigOverrides::default(),
+            overridden_file_values: Vec::new(),
+            deferred_history_path: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let config = |json: serde_json::Value| -> Config { serde_json::from_value(json).unwrap() };
+        let old = config(serde_json::json!({
+            "conversation_history_save_path": "history.json",
+            "api_key_profiles": [{"name": "default", "openai_api_key": "sk-old"}],
+        }));
+        let new = config(serde_json::json!({
+            "conversation_history_save_path": "history.json",
+            "api_key_profiles": [{"name": "default", "openai_api_key": "sk-new"}],
+            "record_requests": true,
+        }));
+        let changes = old.diff(&new);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].key, "api_key_profiles");
+        assert!(changes[0].requires_restart);
+        assert!(!changes[0].new_value.to_string().contains("sk-new"));
+        assert_eq!(changes[1].key, "record_requests");
+        assert!(!changes[1].requires_restart);
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn test_model_chain() {
+        let config = |json: serde_json::Value| -> Config { serde_json::from_value(json).unwrap() };
+        let defaults = config(serde_json::json!({ "conversation_history_save_path": "h.json" }));
+        assert_eq!(defaults.model_chain(), [crate::provider::DEFAULT_MODEL]);
+        let chained = config(serde_json::json!({
+            "conversation_history_save_path": "h.json",
+            "model": "gpt-4o",
+            "fallback_models": ["gpt-4o-mini", " gpt-4o ", "", "gpt-3.5-turbo"],
+        }));
+        assert_eq!(chained.model_chain(), ["gpt-4o", "gpt-4o-mini", "gpt-3.5-turbo"]);
+    }
+
+    #[test]
+    fn test_defer_history_path() {
+        let json = serde_json::json!({ "conversation_history_save_path": "new.json" });
+        let mut edited: Config = serde_json::from_value(json).unwrap();
+        edited.defer_history_path("new.json");
+        assert!(edited.deferred_history_path().is_none());
+        edited.defer_history_path("old.json");
+        assert_eq!(edited.conversation_history_save_path, "old.json");
+        assert_eq!(edited.deferred_history_path(), Some("new.json"));
+
+        let path = std::env::temp_dir().join(format!("config-{}.json", uuid::Uuid::new_v4()));
+        edited.write_to(&path).unwrap();
+        let written = Config::read_from(&path).unwrap();
+        assert_eq!(written.conversation_history_save_path, "new.json");
+
+        // Moving the history in the app since wins over the edit.
+        edited.conversation_history_save_path = "moved.json".to_string();
+        assert!(edited.deferred_history_path().is_none());
+        edited.write_to(&path).unwrap();
+        let written = Config::read_from(&path).unwrap();
+        assert_eq!(written.conversation_history_save_path, "moved.json");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_legacy_api_key() {
+        let config = |json: serde_json::Value| -> Config { serde_json::from_value(json).unwrap() };
+        let mut legacy = config(serde_json::json!({
+            "conversation_history_save_path": "h.json",
+            "openai_api_key": "sk-legacy",
+        }));
+        assert!(legacy.migrate_legacy_api_key());
+        assert!(legacy.openai_api_key.is_none());
+        assert_eq!(legacy.api_key_profiles.len(), 1);
+        assert_eq!(legacy.api_key_profiles[0].name, DEFAULT_PROFILE_NAME);
+        assert_eq!(legacy.api_key_profiles[0].openai_api_key, "sk-legacy");
+        assert_eq!(legacy.active_profile, DEFAULT_PROFILE_NAME);
+        assert!(!legacy.migrate_legacy_api_key());
+
+        // A default profile that already exists is kept, as is the active profile.
+        let mut both = config(serde_json::json!({
+            "conversation_history_save_path": "h.json",
+            "openai_api_key": "sk-legacy",
+            "api_key_profiles": [
+                {"name": "work", "openai_api_key": "sk-work"},
+                {"name": "default", "openai_api_key": "sk-default"},
+            ],
+            "active_profile": "work",
+        }));
+        assert!(both.migrate_legacy_api_key());
+        assert!(both.openai_api_key.is_none());
+        assert_eq!(both.api_key_profiles.len(), 2);
+        assert_eq!(both.api_key_profiles[1].openai_api_key, "sk-default");
+        assert_eq!(both.active_profile, "work");
+    }
+}
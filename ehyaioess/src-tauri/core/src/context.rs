@@ -0,0 +1,128 @@
+//! Fitting a request into the model's context window. The oldest messages are left out
+//! first; the system prompt, pinned context and the latest message are always sent.
+
+use chatgpt::types::ChatMessage;
+
+use crate::tokens;
+
+/// Tokens kept free for the reply.
+pub const REPLY_RESERVE_TOKENS: usize = 1_024;
+/// What a message costs on top of its content, for its role and separators.
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// The model a request is for and the size of its context window in tokens.
+#[derive(Debug, Clone, Copy)]
+pub struct ContextLimit<'a> {
+    pub model: &'a str,
+    pub context_window: usize,
+}
+
+impl ContextLimit<'_> {
+    pub fn message_tokens(&self, message: &ChatMessage) -> usize {
+        tokens::count_tokens(&message.content, self.model) + MESSAGE_OVERHEAD_TOKENS
+    }
+}
+
+/// The messages of `history`, each with whether it's pinned, that fit in the context window
+/// next to `fixed` messages that are sent regardless. Pinned messages are always kept, and
+/// the rest are kept from the newest back until the first that doesn't fit, so what's sent
+/// never has holes apart from the pinned messages before it.
+pub fn fit(
+    fixed: &[ChatMessage],
+    history: Vec<(ChatMessage, bool)>,
+    limit: &ContextLimit,
+) -> Vec<ChatMessage> {
+    let budget = limit.context_window.saturating_sub(REPLY_RESERVE_TOKENS);
+    let costs: Vec<usize> = history
+        .iter()
+        .map(|(message, _)| limit.message_tokens(message))
+        .collect();
+    let mut used: usize = fixed
+        .iter()
+        .map(|message| limit.message_tokens(message))
+        .chain(
+            history
+                .iter()
+                .zip(&costs)
+                .filter(|((_, pinned), _)| *pinned)
+                .map(|(_, cost)| *cost),
+        )
+        .sum();
+    let mut keep = vec![false; history.len()];
+    let mut trimming = false;
+    for (index, (_, pinned)) in history.iter().enumerate().rev() {
+        if *pinned {
+            keep[index] = true;
+        } else if !trimming && (index + 1 == history.len() || used + costs[index] <= budget) {
+            keep[index] = true;
+            used += costs[index];
+        } else {
+            trimming = true;
+        }
+    }
+    history
+        .into_iter()
+        .zip(keep)
+        .filter_map(|((message, _), keep)| keep.then_some(message))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use chatgpt::types::Role;
+
+    use super::*;
+
+    fn message(content: &str) -> ChatMessage {
+        ChatMessage {
+            role: Role::User,
+            content: content.to_string(),
+        }
+    }
+
+    fn contents(messages: Vec<ChatMessage>) -> Vec<String> {
+        messages.into_iter().map(|message| message.content).collect()
+    }
+
+    #[test]
+    fn test_fit() {
+        let long = "word ".repeat(400);
+        let history = || {
+            vec![
+                (message("requirements"), true),
+                (message(&long), false),
+                (message(&long), false),
+                (message("latest"), false),
+            ]
+        };
+        let limit = |context_window| ContextLimit {
+            model: "gpt-4",
+            context_window,
+        };
+
+        let roomy = fit(&[], history(), &limit(8_192));
+        assert_eq!(roomy.len(), 4);
+        // room for one of the long messages only
+        let tight = fit(&[], history(), &limit(REPLY_RESERVE_TOKENS + 600));
+        assert_eq!(contents(tight), ["requirements", long.as_str(), "latest"]);
+        // the pinned and latest messages are sent even when nothing fits
+        let none = fit(&[message(&long)], history(), &limit(0));
+        assert_eq!(contents(none), ["requirements", "latest"]);
+    }
+
+    #[test]
+    fn test_no_holes() {
+        let long = "word ".repeat(400);
+        let history = vec![
+            (message("old"), false),
+            (message(&long), false),
+            (message("latest"), false),
+        ];
+        let limit = ContextLimit {
+            model: "gpt-4",
+            context_window: REPLY_RESERVE_TOKENS + 100,
+        };
+        // "old" would fit, but not without the message after it
+        assert_eq!(contents(fit(&[], history, &limit)), ["latest"]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Dictation: microphone audio, once captured, brought to the format transcription expects,
+//! cut into chunks sent off as they fill, and ended by a stretch of silence after speech.
+
+/// Samples per second of the audio sent for transcription, plenty for speech.
+pub const SAMPLE_RATE: u32 = 16_000;
+/// Audio sent for transcription at a time, so the transcript grows while the user speaks.
+pub const CHUNK_MS: u32 = 3_000;
+/// Silence after speech that ends dictation.
+pub const SILENCE_MS: u32 = 1_200;
+/// Dictation ends after this long however it goes, bounding what it costs.
+pub const MAX_DICTATION_SECS: u32 = 300;
+/// Voice activity is judged over frames of this length.
+const FRAME_MS: u32 = 30;
+/// Quieter than this is never taken for speech, however quiet the room.
+const MIN_SPEECH_LEVEL: f32 = 0.01;
+/// How far above the background noise a frame has to be to count as speech.
+const SPEECH_TO_NOISE: f32 = 3.0;
+
+/// Samples in `ms` milliseconds at `SAMPLE_RATE`.
+pub const fn samples_in(ms: u32) -> usize {
+    (SAMPLE_RATE as usize / 1000) * ms as usize
+}
+
+/// Mono audio at `from` samples per second brought to `to`. Linear interpolation is rough,
+/// but speech doesn't need better.
+pub fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let step = from as f64 / to as f64;
+    let count = (samples.len() as f64 / step).floor() as usize;
+    (0..count)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let next = samples.get(index + 1).copied().unwrap_or(samples[index]);
+            let fraction = (position - index as f64) as f32;
+            samples[index] + (next - samples[index]) * fraction
+        })
+        .collect()
+}
+
+/// Microphone samples, interleaved over `channels` at `rate`, as mono at `SAMPLE_RATE`.
+pub fn to_mono(samples: &[f32], channels: u16, rate: u32) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    let mono: Vec<f32> = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+    resample(&mono, rate, SAMPLE_RATE)
+}
+
+/// Mono `SAMPLE_RATE` audio as a 16-bit PCM WAV file.
+pub fn wav(samples: &[f32]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, one channel
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
+
+fn level(frame: &[f32]) -> f32 {
+    (frame.iter().map(|sample| sample * sample).sum::<f32>() / frame.len() as f32).sqrt()
+}
+
+/// Tells speech from silence by loudness against the background noise, which it keeps
+/// learning from the frames it takes for silence.
+#[derive(Debug, Default)]
+pub struct VoiceActivity {
+    noise_level: Option<f32>,
+    /// Samples of the frame not complete yet.
+    partial_frame: Vec<f32>,
+    speech_heard: bool,
+    silent_samples: usize,
+}
+
+impl VoiceActivity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Judges the complete frames of `samples`, mono at `SAMPLE_RATE`.
+    pub fn push(&mut self, samples: &[f32]) {
+        let frame_len = samples_in(FRAME_MS);
+        self.partial_frame.extend_from_slice(samples);
+        let complete = self.partial_frame.len() / frame_len * frame_len;
+        let frames: Vec<f32> = self.partial_frame.drain(..complete).collect();
+        for frame in frames.chunks(frame_len) {
+            let level = level(frame);
+            let noise_level = self.noise_level.unwrap_or(level);
+            if level > MIN_SPEECH_LEVEL && level > noise_level * SPEECH_TO_NOISE {
+                self.speech_heard = true;
+                self.silent_samples = 0;
+            } else {
+                self.noise_level = Some(noise_level * 0.9 + level * 0.1);
+                self.silent_samples += frame.len();
+            }
+        }
+    }
+
+    pub fn speech_heard(&self) -> bool {
+        self.speech_heard
+    }
+
+    /// Whether the speaker has been silent for `SILENCE_MS` after saying something.
+    pub fn finished(&self) -> bool {
+        self.speech_heard && self.silent_samples >= samples_in(SILENCE_MS)
+    }
+}
+
+/// `transcript` with the transcription of the next chunk added on.
+pub fn append_transcript(transcript: &str, chunk: &str) -> String {
+    match (transcript.trim(), chunk.trim()) {
+        (transcript, "") => transcript.to_string(),
+        ("", chunk) => chunk.to_string(),
+        (transcript, chunk) => format!("{} {}", transcript, chunk),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tone(ms: u32, amplitude: f32) -> Vec<f32> {
+        (0..samples_in(ms))
+            .map(|i| amplitude * (i as f32 * 0.3).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_to_mono() {
+        // stereo at twice the rate: channels are averaged and every other frame kept
+        let samples = [0.2, 0.4, 0.6, 0.6, 1.0, 0.0, 0.0, 0.0];
+        let mono = to_mono(&samples, 2, SAMPLE_RATE * 2);
+        assert_eq!(mono.len(), 2);
+        assert!((mono[0] - 0.3).abs() < 1e-6);
+        assert!((mono[1] - 0.5).abs() < 1e-6);
+        assert_eq!(to_mono(&[0.5, -0.5], 1, SAMPLE_RATE), [0.5, -0.5]);
+        // upsampling interpolates between samples
+        assert_eq!(resample(&[0.0, 1.0], 1, 2), [0.0, 0.5, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_wav() {
+        let bytes = wav(&[0.0, 1.0, -2.0]);
+        assert_eq!(bytes.len(), 44 + 6);
+        assert_eq!(&bytes[..4], b"RIFF");
+        assert_eq!(&bytes[40..44], &6u32.to_le_bytes());
+        assert_eq!(&bytes[46..48], &i16::MAX.to_le_bytes());
+        // out of range samples are clipped
+        assert_eq!(&bytes[48..50], &(-i16::MAX).to_le_bytes());
+    }
+
+    #[test]
+    fn test_voice_activity() {
+        let mut activity = VoiceActivity::new();
+        activity.push(&tone(600, 0.002));
+        assert!(!activity.speech_heard());
+        // background noise doesn't end dictation before anything was said
+        activity.push(&tone(SILENCE_MS * 2, 0.002));
+        assert!(!activity.finished());
+        activity.push(&tone(FRAME_MS * 17, 0.3));
+        assert!(activity.speech_heard());
+        activity.push(&tone(SILENCE_MS / 2, 0.002));
+        assert!(!activity.finished());
+        activity.push(&tone(SILENCE_MS / 2 + FRAME_MS, 0.002));
+        assert!(activity.finished());
+    }
+
+    #[test]
+    fn test_append_transcript() {
+        assert_eq!(append_transcript("", " Hello"), "Hello");
+        assert_eq!(append_transcript("Hello", "world. "), "Hello world.");
+        assert_eq!(append_transcript("Hello", " "), "Hello");
+    }
+}
@@ -0,0 +1,366 @@
+//! Summarizing unified diffs, as printed by `git diff` or `diff -u`: the diff split per file
+//! and the model asked for a commit-message-style summary of the whole and a line per file.
+
+use chatgpt::types::{ChatMessage, Role};
+use serde::{Deserialize, Serialize};
+
+use crate::{bookmarks::snippet, validation};
+
+/// The file types read as diffs when given a path.
+pub const DIFF_EXTENSIONS: [&str; 2] = ["diff", "patch"];
+/// Per-file limit on the changes the model sees, keeping summary requests affordable.
+const FILE_DIFF_CHARS: usize = 4_000;
+/// The most files whose changes the model sees; later ones are only listed by name.
+const MAX_DETAILED_FILES: usize = 40;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChange {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDiff {
+    /// The path after the change, or before it for a deleted file.
+    pub path: String,
+    /// The path before a rename.
+    pub old_path: Option<String>,
+    pub change: FileChange,
+    pub additions: usize,
+    pub deletions: usize,
+    /// The file's part of the diff, headers included.
+    pub text: String,
+}
+
+impl FileDiff {
+    fn new(path: String) -> Self {
+        Self {
+            path,
+            old_path: None,
+            change: FileChange::Modified,
+            additions: 0,
+            deletions: 0,
+            text: String::new(),
+        }
+    }
+}
+
+/// Whether `text` reads as a diff rather than as the path of one.
+pub fn looks_like_diff(text: &str) -> bool {
+    text.lines()
+        .any(|line| line.starts_with("diff --git ") || line.starts_with("@@ "))
+}
+
+/// A path in a `---` or `+++` header, without its `a/` or `b/` prefix or the timestamp
+/// `diff -u` adds. None for `/dev/null`.
+fn header_path(path: &str) -> Option<String> {
+    let path = path.split('\t').next().unwrap_or_default().trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// The line counts of a hunk header such as `@@ -1,4 +1,5 @@`, a missing count being 1.
+fn hunk_lengths(line: &str) -> Option<(usize, usize)> {
+    let mut ranges = line.strip_prefix("@@ ")?.split_whitespace();
+    let length = |range: &str| match range.split_once(',') {
+        Some((_, count)) => count.parse().ok(),
+        None => Some(1),
+    };
+    let old = length(ranges.next()?.strip_prefix('-')?)?;
+    let new = length(ranges.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+/// The files changed in `diff`, in the order it lists them. Lines outside of any file, such
+/// as a patch's commit message, are left out.
+pub fn parse_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files = Vec::new();
+    let mut file: Option<FileDiff> = None;
+    // Lines left in the current hunk, before and after the change.
+    let mut remaining: (usize, usize) = (0, 0);
+    // Whether the current file's `diff --git` header was read without a hunk after it yet.
+    let mut in_git_header = false;
+    for line in diff.lines() {
+        if remaining != (0, 0) {
+            if let Some(file) = file.as_mut() {
+                match line.chars().next() {
+                    Some('+') => {
+                        file.additions += 1;
+                        remaining.1 = remaining.1.saturating_sub(1);
+                    }
+                    Some('-') => {
+                        file.deletions += 1;
+                        remaining.0 = remaining.0.saturating_sub(1);
+                    }
+                    Some('\\') => {}
+                    _ => {
+                        remaining.0 = remaining.0.saturating_sub(1);
+                        remaining.1 = remaining.1.saturating_sub(1);
+                    }
+                }
+                file.text.push_str(line);
+                file.text.push('\n');
+            }
+            continue;
+        }
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            files.extend(file.take());
+            let path = paths
+                .rsplit_once(" b/")
+                .map(|(_, new)| new)
+                .unwrap_or(paths);
+            file = Some(FileDiff::new(path.to_string()));
+            in_git_header = true;
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            // Without a `diff --git` header, as from `diff -u`, each file starts here.
+            if !in_git_header {
+                files.extend(file.take());
+                file = Some(FileDiff::new(String::new()));
+            }
+            if let Some(file) = file.as_mut() {
+                match header_path(path) {
+                    Some(path) => file.path = path,
+                    None => file.change = FileChange::Added,
+                }
+            }
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            if let Some(file) = file.as_mut() {
+                match header_path(path) {
+                    Some(path) => file.path = path,
+                    None => file.change = FileChange::Deleted,
+                }
+            }
+        } else if let Some(lengths) = hunk_lengths(line) {
+            remaining = lengths;
+            in_git_header = false;
+        } else if let Some(file) = file.as_mut() {
+            if line.starts_with("new file mode") {
+                file.change = FileChange::Added;
+            } else if line.starts_with("deleted file mode") {
+                file.change = FileChange::Deleted;
+            } else if let Some(old_path) = line.strip_prefix("rename from ") {
+                file.change = FileChange::Renamed;
+                file.old_path = Some(old_path.to_string());
+            } else if let Some(path) = line.strip_prefix("rename to ") {
+                file.path = path.to_string();
+            } else if !in_git_header {
+                // Past the file's hunks: whatever follows isn't part of it.
+                continue;
+            }
+        } else {
+            continue;
+        }
+        if let Some(file) = file.as_mut() {
+            file.text.push_str(line);
+            file.text.push('\n');
+        }
+    }
+    files.extend(file);
+    files.retain(|file| !file.path.is_empty());
+    files
+}
+
+fn change_label(file: &FileDiff) -> String {
+    match (&file.change, &file.old_path) {
+        (FileChange::Added, _) => "added".to_string(),
+        (FileChange::Deleted, _) => "deleted".to_string(),
+        (FileChange::Renamed, Some(old_path)) => format!("renamed from {}", old_path),
+        _ => "modified".to_string(),
+    }
+}
+
+/// The request asking for a summary of `files`, answered with a summary line and then a line
+/// per file.
+pub fn summary_messages(files: &[FileDiff]) -> Vec<ChatMessage> {
+    let changes = files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| {
+            let heading = format!(
+                "File: {} ({}, +{} -{})",
+                file.path,
+                change_label(file),
+                file.additions,
+                file.deletions
+            );
+            match i < MAX_DETAILED_FILES {
+                true => format!("{}\n{}", heading, snippet(&file.text, FILE_DIFF_CHARS)),
+                false => heading,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    vec![
+        ChatMessage {
+            role: Role::System,
+            content: "Summarize the following diff the way a commit message would. Reply with \
+                      a one-line summary in the imperative mood, then one line per file in the \
+                      form `path: what changed`, in the order the files are given. Reply with \
+                      nothing else."
+                .to_string(),
+        },
+        ChatMessage {
+            role: Role::User,
+            content: changes,
+        },
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSummary {
+    pub path: String,
+    pub old_path: Option<String>,
+    pub change: FileChange,
+    pub additions: usize,
+    pub deletions: usize,
+    /// Empty when the reply had no line for the file.
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffSummary {
+    /// The commit-message-style summary line.
+    pub summary: String,
+    /// One per file, in the order of the diff.
+    pub files: Vec<FileSummary>,
+}
+
+/// The summaries in the model's reply to `summary_messages`. Lines naming a file that isn't in
+/// the diff are left out.
+pub fn parse_summary(reply: &str, files: &[FileDiff]) -> DiffSummary {
+    let mut summary = String::new();
+    let mut file_summaries: Vec<String> = vec![String::new(); files.len()];
+    for line in reply.lines() {
+        let line = line.trim().trim_start_matches(['-', '*', '•']).trim_start();
+        if line.is_empty() {
+            continue;
+        }
+        let named = line.split_once(": ").and_then(|(path, text)| {
+            let path = path.trim_matches(['`', '*', '"']).trim();
+            let index = files.iter().position(|file| file.path == path)?;
+            Some((index, text))
+        });
+        match named {
+            Some((index, text)) => file_summaries[index] = validation::imported_title(text),
+            None if summary.is_empty() => summary = validation::imported_title(line),
+            None => {}
+        }
+    }
+    let files = files
+        .iter()
+        .zip(file_summaries)
+        .map(|(file, summary)| FileSummary {
+            path: file.path.clone(),
+            old_path: file.old_path.clone(),
+            change: file.change,
+            additions: file.additions,
+            deletions: file.deletions,
+            summary,
+        })
+        .collect();
+    DiffSummary { summary, files }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const GIT_DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ pub mod a;
+--- removed comment
++pub mod b;
+ pub mod c;
+diff --git a/notes.txt b/notes.txt
+new file mode 100644
+--- /dev/null
++++ b/notes.txt
+@@ -0,0 +1 @@
++hello
+diff --git a/old.rs b/new.rs
+similarity index 100%
+rename from old.rs
+rename to new.rs
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+--- a/gone.txt
++++ /dev/null
+@@ -1,2 +0,0 @@
+-one
+-two
+";
+
+    #[test]
+    fn test_parse_diff() {
+        assert!(looks_like_diff(GIT_DIFF));
+        assert!(!looks_like_diff("/home/me/change.diff"));
+        let files = parse_diff(GIT_DIFF);
+        let summary: Vec<(&str, FileChange, usize, usize)> = files
+            .iter()
+            .map(|file| {
+                (
+                    file.path.as_str(),
+                    file.change,
+                    file.additions,
+                    file.deletions,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                // a removed line starting with dashes isn't taken for a new file
+                ("src/lib.rs", FileChange::Modified, 1, 1),
+                ("notes.txt", FileChange::Added, 1, 0),
+                ("new.rs", FileChange::Renamed, 0, 0),
+                ("gone.txt", FileChange::Deleted, 0, 2),
+            ]
+        );
+        assert_eq!(files[2].old_path.as_deref(), Some("old.rs"));
+        assert!(files[0].text.starts_with("diff --git a/src/lib.rs"));
+        assert!(files[0].text.ends_with(" pub mod c;\n"));
+
+        // `diff -u` output has no `diff --git` headers
+        let plain = "--- a.txt\t2024-01-01\n+++ a.txt\t2024-01-02\n@@ -1 +1 @@\n-x\n+y\n\
+                     --- b.txt\n+++ b.txt\n@@ -1 +1,2 @@\n x\n+z\n";
+        let files = parse_diff(plain);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "a.txt");
+        assert_eq!((files[1].additions, files[1].deletions), (1, 0));
+        assert!(parse_diff("not a diff").is_empty());
+    }
+
+    #[test]
+    fn test_parse_summary() {
+        let files = parse_diff(GIT_DIFF);
+        let messages = summary_messages(&files);
+        assert!(messages[1]
+            .content
+            .contains("File: new.rs (renamed from old.rs, +0 -0)"));
+        let summary = parse_summary(
+            "Split module b out of a\n\n- `src/lib.rs`: Declare module b\n\
+             notes.txt: Add notes\nother.rs: Not in the diff",
+            &files,
+        );
+        assert_eq!(summary.summary, "Split module b out of a");
+        let file_summaries: Vec<&str> = summary
+            .files
+            .iter()
+            .map(|file| file.summary.as_str())
+            .collect();
+        assert_eq!(file_summaries, ["Declare module b", "Add notes", "", ""]);
+    }
+}
@@ -0,0 +1,126 @@
+//! Unsent message drafts, kept outside the conversation history since they aren't events.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use uuid::Uuid;
+
+pub struct DraftStore {
+    path: Mutex<PathBuf>,
+    drafts: Mutex<HashMap<Uuid, String>>,
+    /// Bumped on every change so a debounced flush can tell whether it's still the latest.
+    revision: AtomicU64,
+}
+
+impl DraftStore {
+    fn read_drafts(path: &Path) -> HashMap<Uuid, String> {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            drafts: Mutex::new(Self::read_drafts(&path)),
+            path: Mutex::new(path),
+            revision: AtomicU64::new(0),
+        }
+    }
+
+    /// Switches to the drafts at `path`, e.g. after changing workspace profile.
+    /// Pending flushes of the previous drafts are dropped.
+    pub fn reopen(&self, path: PathBuf) {
+        let mut drafts = self.drafts.lock().unwrap();
+        *drafts = Self::read_drafts(&path);
+        *self.path.lock().unwrap() = path;
+        self.revision.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn get(&self, conversation_id: Uuid) -> Option<String> {
+        self.drafts.lock().unwrap().get(&conversation_id).cloned()
+    }
+
+    /// Updates the draft in memory, removing it when blank. Returns the new revision.
+    pub fn set(&self, conversation_id: Uuid, content: &str) -> u64 {
+        let mut drafts = self.drafts.lock().unwrap();
+        if content.trim().is_empty() {
+            drafts.remove(&conversation_id);
+        } else {
+            drafts.insert(conversation_id, content.to_string());
+        }
+        self.revision.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Writes to disk unless the drafts changed again after `revision`,
+    /// in which case the later change's flush will write them instead.
+    pub fn flush_if_current(&self, revision: u64) -> Result<(), Box<dyn std::error::Error>> {
+        let drafts = self.drafts.lock().unwrap();
+        if self.revision.load(Ordering::SeqCst) != revision {
+            return Ok(());
+        }
+        let path = self.path.lock().unwrap();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&*path, serde_json::to_string(&*drafts)?)?;
+        Ok(())
+    }
+
+    /// Writes to disk right away, unless the drafts are locked, e.g. by a panicking thread.
+    /// Returns whether they were written.
+    pub fn try_flush(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        let (Ok(drafts), Ok(path)) = (self.drafts.try_lock(), self.path.try_lock()) else {
+            return Ok(false);
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&*path, serde_json::to_string(&*drafts)?)?;
+        Ok(true)
+    }
+
+    /// Drops every draft, both in memory and on disk.
+    pub fn clear(&self) -> Result<(), std::io::Error> {
+        self.drafts.lock().unwrap().clear();
+        self.revision.fetch_add(1, Ordering::SeqCst);
+        let path = self.path.lock().unwrap();
+        if path.exists() {
+            std::fs::remove_file(&*path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_flush_if_current() {
+        let path = std::env::temp_dir().join(format!("drafts-{}.json", Uuid::new_v4()));
+        let store = DraftStore::new(path.clone());
+        let conversation_id = Uuid::new_v4();
+
+        let stale = store.set(conversation_id, "hel");
+        let latest = store.set(conversation_id, "hello");
+        store.flush_if_current(stale).unwrap();
+        assert!(!path.exists());
+        store.flush_if_current(latest).unwrap();
+        assert_eq!(
+            DraftStore::new(path.clone()).get(conversation_id).as_deref(),
+            Some("hello")
+        );
+
+        store.set(conversation_id, "  ");
+        assert_eq!(store.get(conversation_id), None);
+        store.clear().unwrap();
+        assert!(!path.exists());
+    }
+}
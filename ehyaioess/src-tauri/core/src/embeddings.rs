@@ -0,0 +1,216 @@
+//! Cache of embeddings keyed by model and content hash, so only text that wasn't embedded
+//! before is sent to the provider. Bounded in size, least recently used entries going first,
+//! and entries unused for too long expire.
+
+use std::{collections::HashMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::Conversation;
+
+/// Characters of each conversation that are embedded; its beginning says most about its topic.
+const EMBEDDING_TEXT_CHARS: usize = 2000;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+fn default_max_size_mb() -> u64 {
+    100
+}
+
+fn default_ttl_days() -> u32 {
+    90
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmbeddingCacheConfig {
+    /// Size the cached vectors are trimmed to, least recently used first.
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: u64,
+    /// Entries unused for this many days are dropped; 0 keeps them until trimmed for size.
+    #[serde(default = "default_ttl_days")]
+    pub ttl_days: u32,
+}
+
+impl Default for EmbeddingCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_size_mb: default_max_size_mb(),
+            ttl_days: default_ttl_days(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedEmbedding {
+    vector: Vec<f32>,
+    /// Unix seconds.
+    last_used: i64,
+}
+
+impl CachedEmbedding {
+    fn size_bytes(&self) -> u64 {
+        (self.vector.len() * std::mem::size_of::<f32>()) as u64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EmbeddingCacheStats {
+    pub entries: usize,
+    /// Size of the cached vectors, not counting the file's formatting.
+    pub size_bytes: u64,
+    /// Lookups since the cache was last cleared.
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct EmbeddingCache {
+    entries: HashMap<String, CachedEmbedding>,
+    #[serde(default)]
+    hits: u64,
+    #[serde(default)]
+    misses: u64,
+}
+
+impl EmbeddingCache {
+    /// The cache at `path`; empty when there's none yet or it can't be read, since every
+    /// embedding can be requested again.
+    pub fn open(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// The embedding `model` gave for `text`, marking it used at `now`.
+    pub fn get(&mut self, model: &str, text: &str, now: i64) -> Option<Vec<f32>> {
+        match self.entries.get_mut(&cache_key(model, text)) {
+            Some(entry) => {
+                self.hits += 1;
+                entry.last_used = now;
+                Some(entry.vector.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, model: &str, text: &str, vector: Vec<f32>, now: i64) {
+        self.entries.insert(
+            cache_key(model, text),
+            CachedEmbedding {
+                vector,
+                last_used: now,
+            },
+        );
+    }
+
+    /// Drops expired entries, then the least recently used ones until the cache fits.
+    pub fn prune(&mut self, limits: &EmbeddingCacheConfig, now: i64) {
+        if limits.ttl_days > 0 {
+            let cutoff = now - i64::from(limits.ttl_days) * SECONDS_PER_DAY;
+            self.entries.retain(|_, entry| entry.last_used >= cutoff);
+        }
+        let max_bytes = limits.max_size_mb.saturating_mul(1024 * 1024);
+        let mut size_bytes = self.stats().size_bytes;
+        if size_bytes <= max_bytes {
+            return;
+        }
+        let mut by_last_use: Vec<(String, i64, u64)> = self
+            .entries
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.last_used, entry.size_bytes()))
+            .collect();
+        by_last_use.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        for (key, _, entry_bytes) in by_last_use {
+            if size_bytes <= max_bytes {
+                break;
+            }
+            self.entries.remove(&key);
+            size_bytes -= entry_bytes;
+        }
+    }
+
+    pub fn stats(&self) -> EmbeddingCacheStats {
+        EmbeddingCacheStats {
+            entries: self.entries.len(),
+            size_bytes: self.entries.values().map(CachedEmbedding::size_bytes).sum(),
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+}
+
+/// 64-bit FNV-1a, which unlike the std hashers stays the same across Rust releases.
+fn content_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn cache_key(model: &str, text: &str) -> String {
+    format!("{}:{:016x}", model, content_hash(text))
+}
+
+/// What gets embedded for a conversation: its title followed by its messages, cut short.
+pub fn embedding_text(conv: &Conversation) -> String {
+    let mut text = conv.get_title().into_owned();
+    for message in conv.to_chat_history() {
+        text.push_str("\n\n");
+        text.push_str(&message.content);
+    }
+    text.chars().take(EMBEDDING_TEXT_CHARS).collect()
+}
+
+/// Removes the cached embeddings at `path`, if any.
+pub fn delete(path: &Path) -> Result<(), std::io::Error> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_embedding_cache() {
+        let day = SECONDS_PER_DAY;
+        let mut cache = EmbeddingCache::default();
+        cache.insert("small", "old", vec![0.0; 128], 0);
+        cache.insert("small", "recent", vec![1.0; 128], day);
+
+        assert_eq!(cache.get("small", "recent", 3 * day), Some(vec![1.0; 128]));
+        assert_eq!(cache.get("large", "recent", 3 * day), None);
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.size_bytes), (2, 1024));
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+
+        // Looking "recent" up kept it from expiring along with "old".
+        let ttl = EmbeddingCacheConfig {
+            max_size_mb: 1,
+            ttl_days: 2,
+        };
+        cache.prune(&ttl, 3 * day);
+        assert_eq!(cache.stats().entries, 1);
+
+        // Over the size limit, the least recently used entries go first.
+        cache.insert("small", "a", vec![0.0; 200_000], 3 * day + 1);
+        cache.insert("small", "b", vec![0.0; 200_000], 3 * day + 2);
+        cache.get("small", "a", 3 * day + 3);
+        cache.prune(&ttl, 3 * day + 3);
+        assert!(cache.get("small", "a", 3 * day + 4).is_some());
+        assert!(cache.get("small", "b", 3 * day + 4).is_none());
+        assert!(cache.get("small", "recent", 3 * day + 4).is_none());
+    }
+}
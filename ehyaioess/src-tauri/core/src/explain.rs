@@ -0,0 +1,122 @@
+//! Explaining pasted code: the questions asked about it, with code too long for one message
+//! split at line breaks and explained part by part.
+
+use crate::i18n;
+
+/// The most characters of code in one question.
+pub const EXPLAIN_CHUNK_CHARS: usize = 12_000;
+/// The most parts code is split into, each costing a reply.
+pub const MAX_EXPLAIN_CHUNKS: usize = 8;
+/// The longest code explained at once.
+pub const MAX_CODE_LENGTH: usize = EXPLAIN_CHUNK_CHARS * MAX_EXPLAIN_CHUNKS;
+
+/// `code` in parts of at most `max_chars` characters, split after a line break unless a
+/// single line is longer than that.
+pub fn split_code(code: &str, max_chars: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut part_chars = 0;
+    for line in code.split_inclusive('\n') {
+        let line_chars = line.chars().count();
+        if part_chars + line_chars > max_chars && !part.is_empty() {
+            parts.push(std::mem::take(&mut part));
+            part_chars = 0;
+        }
+        if line_chars <= max_chars {
+            part.push_str(line);
+            part_chars += line_chars;
+            continue;
+        }
+        let chars: Vec<char> = line.chars().collect();
+        for piece in chars.chunks(max_chars) {
+            parts.push(piece.iter().collect());
+        }
+    }
+    if !part.is_empty() {
+        parts.push(part);
+    }
+    parts
+}
+
+/// The title of a conversation started to explain code in `language`.
+pub fn explain_title(language: &str) -> String {
+    match language.trim() {
+        "" => i18n::t("explain.title"),
+        language => i18n::t("explain.title_language").replace("{language}", language),
+    }
+}
+
+/// A fence longer than any run of backticks in `code`, so the code can't close it early.
+fn fence(code: &str) -> String {
+    let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat((longest_run + 1).max(3))
+}
+
+/// The questions asked to explain `code`, one per part. `language` may be empty when it isn't
+/// known.
+pub fn explain_questions(code: &str, language: &str) -> Vec<String> {
+    let language = language.trim();
+    let subject = match language {
+        "" => "code".to_string(),
+        language => format!("{} code", language),
+    };
+    let info = language.to_lowercase().replace(char::is_whitespace, "-");
+    let parts = split_code(code.trim_end(), EXPLAIN_CHUNK_CHARS);
+    let count = parts.len();
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| {
+            let request = match count {
+                1 => format!("Explain what this {} does", subject),
+                _ => format!(
+                    "This is part {} of {} of some {}. Explain what this part does, building on \
+                     the earlier parts",
+                    i + 1,
+                    count,
+                    subject
+                ),
+            };
+            let fence = fence(part);
+            format!(
+                "{}, step by step, and point out anything surprising or likely to be a bug.\n\n\
+                 {}{}\n{}\n{}",
+                request,
+                fence,
+                info,
+                part.trim_end_matches('\n'),
+                fence
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_code() {
+        assert_eq!(split_code("a\nbb\nccc\n", 5), ["a\nbb\n", "ccc\n"]);
+        // a line longer than a part is split wherever it has to be
+        assert_eq!(split_code("a\nbbbbbbb", 3), ["a\n", "bbb", "bbb", "b"]);
+        assert!(split_code("", 3).is_empty());
+    }
+
+    #[test]
+    fn test_explain_questions() {
+        let questions = explain_questions("fn main() {}\n", "Rust");
+        assert_eq!(
+            questions,
+            ["Explain what this Rust code does, step by step, and point out anything surprising \
+              or likely to be a bug.\n\n```rust\nfn main() {}\n```"]
+        );
+        let questions = explain_questions("```\n", "");
+        assert!(questions[0].ends_with("\n\n````\n```\n````"));
+
+        let long = "x\n".repeat(EXPLAIN_CHUNK_CHARS);
+        let questions = explain_questions(&long, "Python");
+        assert_eq!(questions.len(), 2);
+        assert!(questions[1].starts_with("This is part 2 of 2 of some Python code."));
+    }
+}
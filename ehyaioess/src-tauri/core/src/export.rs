@@ -0,0 +1,26 @@
+use chatgpt::types::Role;
+
+use crate::models::{Conversation, ConversationEvent};
+
+pub fn role_label(role: Role) -> &'static str {
+    match role {
+        Role::System => "System",
+        Role::Assistant => "Assistant",
+        Role::User => "User",
+    }
+}
+
+/// Renders the conversation title and every message as a Markdown transcript.
+pub fn conversation_to_markdown(conversation: &Conversation) -> String {
+    let mut markdown = format!("# {}\n\n", conversation.get_title());
+    for record in &conversation.history {
+        if let ConversationEvent::MessageAdded(msg) = &record.event {
+            markdown.push_str(&format!("## {}\n\n{}\n\n", role_label(msg.author), msg.content));
+        }
+    }
+    markdown
+}
+
+pub fn conversation_to_json(conversation: &Conversation) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(conversation)
+}
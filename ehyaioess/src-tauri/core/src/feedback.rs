@@ -0,0 +1,120 @@
+//! Aggregates thumbs up/down ratings so users can compare how models perform for them.
+
+use std::collections::BTreeMap;
+
+use crate::models::{ConversationEvent, ConversationManager, MessageRating};
+
+/// Bucket for messages written before the model was recorded on each message.
+pub const UNKNOWN_MODEL: &str = "unknown";
+
+#[derive(Debug, Clone, Default)]
+pub struct ModelFeedback {
+    pub model: String,
+    pub thumbs_up: usize,
+    pub thumbs_down: usize,
+    pub comments: Vec<String>,
+}
+
+impl ModelFeedback {
+    /// Share of positive ratings between 0 and 1.
+    pub fn approval(&self) -> f64 {
+        let total = self.thumbs_up + self.thumbs_down;
+        if total == 0 {
+            return 0.0;
+        }
+        self.thumbs_up as f64 / total as f64
+    }
+}
+
+/// Ratings per model, sorted by model name.
+pub fn feedback_report(mgr: &ConversationManager) -> Vec<ModelFeedback> {
+    let mut per_model: BTreeMap<String, ModelFeedback> = BTreeMap::new();
+    for conv in mgr.conversations.values() {
+        for record in &conv.history {
+            let ConversationEvent::MessageAdded(msg) = &record.event else {
+                continue;
+            };
+            let Some((rating, comment)) = conv.message_rating(record.id) else {
+                continue;
+            };
+            let model = msg.model.as_deref().unwrap_or(UNKNOWN_MODEL);
+            let feedback = per_model
+                .entry(model.to_string())
+                .or_insert_with(|| ModelFeedback {
+                    model: model.to_string(),
+                    ..Default::default()
+                });
+            match rating {
+                MessageRating::Up => feedback.thumbs_up += 1,
+                MessageRating::Down => feedback.thumbs_down += 1,
+            }
+            if let Some(comment) = comment {
+                feedback.comments.push(comment.to_string());
+            }
+        }
+    }
+    per_model.into_values().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::{
+        Conversation, ConversationMessageAddedEvent, ConversationMessageRatedEvent,
+    };
+
+    fn rate(
+        conv: &mut Conversation,
+        model: Option<&str>,
+        rating: Option<MessageRating>,
+        comment: Option<&str>,
+    ) {
+        let message_id = conv
+            .add_event(ConversationMessageAddedEvent {
+                author: chatgpt::types::Role::Assistant,
+                content: "reply".to_string(),
+                model: model.map(str::to_string),
+                language: None,
+                citations: Vec::new(),
+            })
+            .id;
+        conv.add_event(ConversationMessageRatedEvent {
+            message_id,
+            rating,
+            comment: comment.map(str::to_string),
+        });
+    }
+
+    #[test]
+    fn test_feedback_report() {
+        let mut mgr = ConversationManager::new();
+        let mut first = Conversation::new();
+        rate(&mut first, Some("gpt-4o"), Some(MessageRating::Up), None);
+        rate(
+            &mut first,
+            Some("gpt-4o"),
+            Some(MessageRating::Down),
+            Some("too long"),
+        );
+        rate(&mut first, None, Some(MessageRating::Up), None);
+        // A withdrawn rating doesn't count.
+        rate(&mut first, Some("gpt-4o-mini"), None, None);
+        let mut second = Conversation::new();
+        rate(&mut second, Some("gpt-4o"), Some(MessageRating::Up), None);
+        mgr.conversations.insert(first.id, first);
+        mgr.conversations.insert(second.id, second);
+
+        let report = feedback_report(&mgr);
+        let models: Vec<_> = report
+            .iter()
+            .map(|feedback| feedback.model.as_str())
+            .collect();
+        assert_eq!(models, ["gpt-4o", UNKNOWN_MODEL]);
+        assert_eq!(report[0].thumbs_up, 2);
+        assert_eq!(report[0].thumbs_down, 1);
+        assert_eq!(report[0].comments, ["too long"]);
+        assert!((report[0].approval() - 2.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(report[1].thumbs_up, 1);
+        assert_eq!(ModelFeedback::default().approval(), 0.0);
+    }
+}
@@ -0,0 +1,123 @@
+//! Conversion of conversations into OpenAI chat fine-tuning JSONL.
+
+use std::io::Write;
+
+use chatgpt::types::Role;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Conversation, ConversationEvent};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FinetuneOptions {
+    /// Keep system messages recorded in the conversation history.
+    #[serde(default)]
+    pub include_system_messages: bool,
+    /// System prompt prepended to every example.
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Swap user and assistant roles, e.g. to train a model that writes prompts.
+    #[serde(default)]
+    pub swap_user_assistant: bool,
+}
+
+#[derive(Serialize)]
+struct FinetuneMessage<'a> {
+    role: &'static str,
+    content: &'a str,
+}
+
+#[derive(Serialize)]
+struct FinetuneExample<'a> {
+    messages: Vec<FinetuneMessage<'a>>,
+}
+
+fn role_name(role: Role, options: &FinetuneOptions) -> &'static str {
+    match (role, options.swap_user_assistant) {
+        (Role::System, _) => "system",
+        (Role::User, false) | (Role::Assistant, true) => "user",
+        (Role::Assistant, false) | (Role::User, true) => "assistant",
+    }
+}
+
+fn to_example<'a>(
+    conversation: &'a Conversation,
+    options: &'a FinetuneOptions,
+) -> FinetuneExample<'a> {
+    let mut messages = Vec::new();
+    if let Some(system_prompt) = options
+        .system_prompt
+        .as_deref()
+        .filter(|p| !p.trim().is_empty())
+    {
+        messages.push(FinetuneMessage {
+            role: "system",
+            content: system_prompt,
+        });
+    }
+    for record in &conversation.history {
+        if let ConversationEvent::MessageAdded(msg) = &record.event {
+            if matches!(msg.author, Role::System) && !options.include_system_messages {
+                continue;
+            }
+            messages.push(FinetuneMessage {
+                role: role_name(msg.author, options),
+                content: &msg.content,
+            });
+        }
+    }
+    FinetuneExample { messages }
+}
+
+/// Writes one JSONL example per conversation, skipping conversations without an assistant
+/// turn since they can't be trained on. Returns the number of examples written.
+pub fn write_dataset<'a, W: Write>(
+    writer: &mut W,
+    conversations: impl IntoIterator<Item = &'a Conversation>,
+    options: &FinetuneOptions,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut written = 0;
+    for conversation in conversations {
+        let example = to_example(conversation, options);
+        if !example.messages.iter().any(|m| m.role == "assistant") {
+            continue;
+        }
+        writeln!(writer, "{}", serde_json::to_string(&example)?)?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::ConversationMessageAddedEvent;
+
+    #[test]
+    fn test_write_dataset() {
+        let mut conv = Conversation::new();
+        for (author, content) in [(Role::User, "hi"), (Role::Assistant, "hello")] {
+            conv.add_event(ConversationMessageAddedEvent {
+                author,
+                content: content.to_string(),
+                model: None,
+                language: None,
+                citations: Vec::new(),
+            });
+        }
+        let empty = Conversation::new();
+        let options = FinetuneOptions {
+            system_prompt: Some("Be brief.".to_string()),
+            swap_user_assistant: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        assert_eq!(
+            write_dataset(&mut out, [&conv, &empty], &options).unwrap(),
+            1
+        );
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"messages\":[{\"role\":\"system\",\"content\":\"Be brief.\"},{\"role\":\"assistant\",\"content\":\"hi\"},{\"role\":\"user\",\"content\":\"hello\"}]}\n"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! fzf-style fuzzy matching for the quick switcher: the query's characters have to appear in
+//! order, and matches at word starts and in runs score higher than scattered ones.
+
+use uuid::Uuid;
+
+use crate::models::ConversationManager;
+
+pub const SCORE_MATCH: i32 = 16;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CAMEL: i32 = 7;
+pub const BONUS_CONSECUTIVE: i32 = 4;
+/// The first query character sets where the match starts, so its bonus counts double.
+const FIRST_CHAR_MULTIPLIER: i32 = 2;
+const PENALTY_GAP_START: i32 = -3;
+const PENALTY_GAP_EXTENSION: i32 = -1;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Character (not byte) indices of the matched characters, for highlighting.
+    pub indices: Vec<usize>,
+}
+
+fn bonus(previous: Option<char>, current: char) -> i32 {
+    match previous {
+        None => BONUS_BOUNDARY,
+        Some(previous) if !previous.is_alphanumeric() && current.is_alphanumeric() => {
+            BONUS_BOUNDARY
+        }
+        Some(previous) if previous.is_lowercase() && current.is_uppercase() => BONUS_CAMEL,
+        Some(previous) if !previous.is_numeric() && current.is_numeric() => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+/// The best-scoring way to match `query` in `text`, if its characters all appear in order.
+/// Case-insensitive unless the query contains an uppercase letter (smart case).
+pub fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    let text: Vec<char> = text.chars().collect();
+    if query.is_empty() || query.len() > text.len() {
+        return None;
+    }
+    let case_sensitive = query.iter().any(|c| c.is_uppercase());
+    let equal = |q: char, t: char| {
+        if case_sensitive {
+            q == t
+        } else {
+            t.to_lowercase().eq(q.to_lowercase())
+        }
+    };
+    let bonuses: Vec<i32> = (0..text.len())
+        .map(|j| bonus(j.checked_sub(1).map(|p| text[p]), text[j]))
+        .collect();
+
+    // scores[i][j]: best score with query[i] matched at text[j]; from[i][j]: where query[i - 1]
+    // was matched on that path.
+    let mut scores: Vec<Vec<Option<i32>>> = vec![vec![None; text.len()]; query.len()];
+    let mut from: Vec<Vec<usize>> = vec![vec![0; text.len()]; query.len()];
+    for (j, &t) in text.iter().enumerate() {
+        if equal(query[0], t) {
+            scores[0][j] = Some(SCORE_MATCH + bonuses[j] * FIRST_CHAR_MULTIPLIER);
+        }
+    }
+    for i in 1..query.len() {
+        // Best predecessor for a match at j after skipping at least one character.
+        let mut gapped: Option<(i32, usize)> = None;
+        for j in i..text.len() {
+            if j >= 2 {
+                let extended = gapped.map(|(score, k)| (score + PENALTY_GAP_EXTENSION, k));
+                let started = scores[i - 1][j - 2].map(|score| (score + PENALTY_GAP_START, j - 2));
+                gapped = match (extended, started) {
+                    (Some(a), Some(b)) => Some(if b.0 >= a.0 { b } else { a }),
+                    (a, b) => a.or(b),
+                };
+            }
+            if !equal(query[i], text[j]) {
+                continue;
+            }
+            let consecutive = scores[i - 1][j - 1].map(|score| (score + BONUS_CONSECUTIVE, j - 1));
+            let best = match (consecutive, gapped) {
+                (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                (a, b) => a.or(b),
+            };
+            if let Some((score, k)) = best {
+                scores[i][j] = Some(score + SCORE_MATCH + bonuses[j]);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let last = query.len() - 1;
+    let (mut j, score) = scores[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|score| (j, score)))
+        // The earliest of equally good ends, preferring tighter matches.
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))?;
+    let mut indices = vec![0; query.len()];
+    for i in (0..query.len()).rev() {
+        indices[i] = j;
+        if i > 0 {
+            j = from[i][j];
+        }
+    }
+    Some(FuzzyMatch { score, indices })
+}
+
+#[derive(Debug, Clone)]
+pub struct ConversationMatch {
+    pub conversation_id: Uuid,
+    pub title: String,
+    /// The tag that matched best, or None when it was the title.
+    pub tag: Option<String>,
+    pub matched: FuzzyMatch,
+}
+
+/// Conversations whose title or one of its tags matches `query`, best first and at most
+/// `limit`. An empty query lists the most recently active conversations instead.
+pub fn find_conversations(
+    mgr: &ConversationManager,
+    query: &str,
+    limit: usize,
+) -> Vec<ConversationMatch> {
+    let query = query.trim();
+    let mut found: Vec<(ConversationMatch, usize, i64)> = mgr
+        .conversations
+        .summaries()
+        .into_iter()
+        .filter_map(|summary| {
+            let title = summary.title();
+            let matched = if query.is_empty() {
+                (None, FuzzyMatch::default())
+            } else {
+                std::iter::once((None, fuzzy_match(query, &title)))
+                    .chain(
+                        summary
+                            .tags
+                            .iter()
+                            .map(|tag| (Some(tag.clone()), fuzzy_match(query, tag))),
+                    )
+                    .filter_map(|(tag, matched)| Some((tag, matched?)))
+                    // Ties go to the title, which comes first.
+                    .reduce(|best, next| {
+                        if next.1.score > best.1.score {
+                            next
+                        } else {
+                            best
+                        }
+                    })?
+            };
+            let length = matched.0.as_ref().unwrap_or(&title).chars().count();
+            Some((
+                ConversationMatch {
+                    conversation_id: summary.id,
+                    title,
+                    tag: matched.0,
+                    matched: matched.1,
+                },
+                length,
+                summary.last_activity,
+            ))
+        })
+        .collect();
+    // Shorter texts match more specifically; after that, recent conversations come first.
+    found.sort_by(|a, b| {
+        b.0.matched
+            .score
+            .cmp(&a.0.matched.score)
+            .then(a.1.cmp(&b.1))
+            .then(b.2.cmp(&a.2))
+    });
+    found
+        .into_iter()
+        .take(limit)
+        .map(|(matched, _, _)| matched)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert_eq!(
+            fuzzy_match("rb", "Rust borrow checker").unwrap().indices,
+            vec![0, 5]
+        );
+        // The word starts beat the earlier scattered letters.
+        assert_eq!(
+            fuzzy_match("bc", "abc borrow checker").unwrap().indices,
+            vec![4, 11]
+        );
+        assert_eq!(
+            fuzzy_match("grüß", "Grüße").unwrap().indices,
+            vec![0, 1, 2, 3]
+        );
+        assert!(fuzzy_match("Rust", "rust").is_none());
+        assert!(fuzzy_match("xyz", "Rust").is_none());
+        assert!(fuzzy_match("", "Rust").is_none());
+
+        let tight = fuzzy_match("sql", "SQL queries").unwrap();
+        let loose = fuzzy_match("sql", "some quick list").unwrap();
+        assert!(tight.score > loose.score);
+    }
+}
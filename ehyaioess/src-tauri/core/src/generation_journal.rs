@@ -0,0 +1,268 @@
+//! Replies being generated, written down while they run so that a crash, or the app being
+//! killed, mid-reply leaves a record of them. The next start offers to resume or discard each.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use chatgpt::types::Role;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{Conversation, ConversationEvent};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PendingGeneration {
+    pub conversation_id: Uuid,
+    /// The message being replied to, sent again on resuming should the history have lost it.
+    pub prompt: String,
+    /// Whether a partial reply was being continued.
+    #[serde(default)]
+    pub continue_partial: bool,
+    /// Unix seconds.
+    pub started_at: i64,
+}
+
+impl PendingGeneration {
+    /// The reply about to be generated for `conv`.
+    pub fn new(conv: &Conversation, continue_partial: bool) -> Self {
+        Self {
+            conversation_id: conv.id,
+            prompt: latest_prompt(conv).unwrap_or_default().to_string(),
+            continue_partial,
+            started_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+/// The user's latest message in `conv`.
+fn latest_prompt(conv: &Conversation) -> Option<&str> {
+    conv.history
+        .iter()
+        .rev()
+        .find_map(|record| match &record.event {
+            ConversationEvent::MessageAdded(msg) if msg.author == Role::User => {
+                Some(msg.content.as_str())
+            }
+            _ => None,
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resume {
+    /// Reply to the prompt, which is still unanswered.
+    Reply,
+    /// Continue the partial reply left behind.
+    ContinuePartial,
+    /// The prompt never made it into the history: send it again, then reply.
+    SendPrompt,
+    /// The reply was saved before the app went down; there's nothing to resume.
+    Answered,
+}
+
+/// How to pick `pending` up again in `conv` as it was loaded.
+pub fn resume(conv: &Conversation, pending: &PendingGeneration) -> Resume {
+    if pending.continue_partial && conv.pending_partial().is_some() {
+        return Resume::ContinuePartial;
+    }
+    // Nothing to send again, e.g. when replying to the assistant prefix alone.
+    if pending.prompt.is_empty() {
+        return Resume::Reply;
+    }
+    let mut answered = false;
+    for record in conv.history.iter().rev() {
+        match &record.event {
+            ConversationEvent::MessageAdded(msg) if msg.author == Role::User => {
+                return match (msg.content == pending.prompt, answered) {
+                    (true, true) => Resume::Answered,
+                    (true, false) => Resume::Reply,
+                    (false, _) => Resume::SendPrompt,
+                };
+            }
+            ConversationEvent::MessageAdded(msg) if msg.author == Role::Assistant => {
+                answered = true;
+            }
+            _ => {}
+        }
+    }
+    Resume::SendPrompt
+}
+
+#[derive(Default)]
+struct Entries {
+    /// Generating now, by conversation.
+    running: HashMap<Uuid, PendingGeneration>,
+    /// Left unfinished by an earlier run, until resumed or discarded.
+    interrupted: HashMap<Uuid, PendingGeneration>,
+}
+
+pub struct GenerationJournal {
+    path: Mutex<PathBuf>,
+    entries: Mutex<Entries>,
+}
+
+impl GenerationJournal {
+    /// Whatever is in the journal when it's opened was left by an earlier run.
+    fn read_entries(path: &Path) -> Entries {
+        let pending: Vec<PendingGeneration> = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Entries {
+            running: HashMap::new(),
+            interrupted: pending
+                .into_iter()
+                .map(|pending| (pending.conversation_id, pending))
+                .collect(),
+        }
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            entries: Mutex::new(Self::read_entries(&path)),
+            path: Mutex::new(path),
+        }
+    }
+
+    /// Switches to the journal at `path`, e.g. after changing workspace profile.
+    pub fn reopen(&self, path: PathBuf) {
+        let mut entries = self.entries.lock().unwrap();
+        *entries = Self::read_entries(&path);
+        *self.path.lock().unwrap() = path;
+    }
+
+    fn write(&self, entries: &Entries) -> Result<(), std::io::Error> {
+        let mut pending: Vec<&PendingGeneration> = entries
+            .running
+            .values()
+            .chain(entries.interrupted.values())
+            .collect();
+        pending.sort_by_key(|pending| pending.started_at);
+        let path = self.path.lock().unwrap();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&*path, serde_json::to_string(&pending)?)
+    }
+
+    /// Records the generation as running. An interrupted one for the same conversation is
+    /// dropped, the conversation having moved on.
+    pub fn begin(&self, pending: PendingGeneration) -> Result<(), std::io::Error> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.interrupted.remove(&pending.conversation_id);
+        entries.running.insert(pending.conversation_id, pending);
+        self.write(&entries)
+    }
+
+    /// Records that the conversation's generation ended, however it did.
+    pub fn finish(&self, conversation_id: Uuid) -> Result<(), std::io::Error> {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.running.remove(&conversation_id).is_none() {
+            return Ok(());
+        }
+        self.write(&entries)
+    }
+
+    /// The generations an earlier run left unfinished, oldest first.
+    pub fn interrupted(&self) -> Vec<PendingGeneration> {
+        let mut interrupted: Vec<PendingGeneration> = self
+            .entries
+            .lock()
+            .unwrap()
+            .interrupted
+            .values()
+            .cloned()
+            .collect();
+        interrupted.sort_by_key(|pending| pending.started_at);
+        interrupted
+    }
+
+    /// Removes the conversation's interrupted generation, to resume or discard it.
+    pub fn take_interrupted(
+        &self,
+        conversation_id: Uuid,
+    ) -> Result<Option<PendingGeneration>, std::io::Error> {
+        let mut entries = self.entries.lock().unwrap();
+        let pending = entries.interrupted.remove(&conversation_id);
+        if pending.is_some() {
+            self.write(&entries)?;
+        }
+        Ok(pending)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::{ConversationMessageAddedEvent, ConversationMessagePartialEvent};
+
+    fn message(author: Role, content: &str) -> ConversationMessageAddedEvent {
+        ConversationMessageAddedEvent {
+            author,
+            content: content.to_string(),
+            model: None,
+            language: None,
+            citations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_resume() {
+        let mut conv = Conversation::new();
+        conv.add_event(message(Role::User, "What's for dinner?"));
+        let pending = PendingGeneration::new(&conv, false);
+        assert_eq!(pending.prompt, "What's for dinner?");
+        assert_eq!(resume(&conv, &pending), Resume::Reply);
+        assert_eq!(resume(&Conversation::new(), &pending), Resume::SendPrompt);
+
+        conv.add_event(ConversationMessagePartialEvent {
+            content: "Pasta".to_string(),
+            model: None,
+            error: "connection reset".to_string(),
+        });
+        let continuing = PendingGeneration::new(&conv, true);
+        assert_eq!(resume(&conv, &continuing), Resume::ContinuePartial);
+
+        conv.add_event(message(Role::Assistant, "Pasta."));
+        assert_eq!(resume(&conv, &pending), Resume::Answered);
+        // the history lost the prompt that came after an earlier reply
+        let lost = PendingGeneration {
+            prompt: "And for dessert?".to_string(),
+            ..pending
+        };
+        assert_eq!(resume(&conv, &lost), Resume::SendPrompt);
+    }
+
+    #[test]
+    fn test_generation_journal() {
+        let dir = std::env::temp_dir().join(format!("generation-journal-{}", Uuid::new_v4()));
+        let path = dir.join("pending_generations.json");
+        let mut conv = Conversation::new();
+        conv.add_event(message(Role::User, "Hello"));
+        let finished = Conversation::new();
+
+        let journal = GenerationJournal::new(path.clone());
+        journal.begin(PendingGeneration::new(&conv, false)).unwrap();
+        journal
+            .begin(PendingGeneration::new(&finished, false))
+            .unwrap();
+        journal.finish(finished.id).unwrap();
+        // Running generations aren't interrupted ones.
+        assert!(journal.interrupted().is_empty());
+
+        // as though the app went down mid-reply
+        let journal = GenerationJournal::new(path.clone());
+        let interrupted = journal.interrupted();
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].conversation_id, conv.id);
+        assert_eq!(
+            journal.take_interrupted(conv.id).unwrap(),
+            Some(interrupted[0].clone())
+        );
+        assert_eq!(journal.take_interrupted(conv.id).unwrap(), None);
+        assert!(GenerationJournal::new(path).interrupted().is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,69 @@
+//! A conversation's glossary: project-specific terms and what they mean, added to the system
+//! prompt so replies use them the way the user does.
+
+use serde::{Deserialize, Serialize};
+
+/// The most terms a glossary holds, keeping the system prompt a reasonable size.
+pub const MAX_GLOSSARY_TERMS: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GlossaryEntry {
+    pub term: String,
+    pub definition: String,
+}
+
+/// Appended to the system prompt of conversations with a glossary. None for an empty one.
+pub fn glossary_instruction(glossary: &[GlossaryEntry]) -> Option<String> {
+    if glossary.is_empty() {
+        return None;
+    }
+    let terms: Vec<String> = glossary
+        .iter()
+        .map(|entry| format!("- {}: {}", entry.term, entry.definition))
+        .collect();
+    Some(format!(
+        "Use these terms consistently, with the meanings given:\n{}",
+        terms.join("\n")
+    ))
+}
+
+/// Whether two entries define the same term, compared ignoring case.
+pub fn has_duplicate_terms(glossary: &[GlossaryEntry]) -> bool {
+    glossary.iter().enumerate().any(|(i, entry)| {
+        glossary[..i]
+            .iter()
+            .any(|earlier| earlier.term.to_lowercase() == entry.term.to_lowercase())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(term: &str, definition: &str) -> GlossaryEntry {
+        GlossaryEntry {
+            term: term.to_string(),
+            definition: definition.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_glossary_instruction() {
+        assert_eq!(glossary_instruction(&[]), None);
+        let glossary = [
+            entry("Ledger", "the service storing account balances"),
+            entry("Hot path", "code run on every request"),
+        ];
+        assert_eq!(
+            glossary_instruction(&glossary).unwrap(),
+            "Use these terms consistently, with the meanings given:\n\
+             - Ledger: the service storing account balances\n\
+             - Hot path: code run on every request"
+        );
+        assert!(!has_duplicate_terms(&glossary));
+        assert!(has_duplicate_terms(&[
+            entry("Ledger", "a"),
+            entry("ledger", "b")
+        ]));
+    }
+}
@@ -0,0 +1,196 @@
+//! Guardrails on replies: banned phrases and patterns, such as internal codenames, looked for
+//! in each assistant reply once it's generated and then redacted or flagged for the user.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// What redacted content is replaced with.
+pub const REDACTED: &str = "[redacted]";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardrailAction {
+    /// Keeps the reply as it is and marks the message.
+    #[default]
+    Flag,
+    /// Replaces the banned content with `REDACTED` before the reply is stored.
+    Redact,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GuardrailConfig {
+    /// Matched ignoring case, anywhere in a reply.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub banned_phrases: Vec<String>,
+    /// Regular expressions, matched as written.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub banned_patterns: Vec<String>,
+    #[serde(default)]
+    pub action: GuardrailAction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GuardrailHit {
+    /// The phrase or pattern that matched, as configured.
+    pub rule: String,
+    pub count: usize,
+}
+
+/// A reply after the guardrails looked at it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilteredReply {
+    /// The reply, with the banned content replaced when it was redacted.
+    pub content: String,
+    /// Empty when nothing matched.
+    pub hits: Vec<GuardrailHit>,
+    pub redacted: bool,
+}
+
+/// Refuses `pattern` unless it's a valid regular expression.
+pub fn check_pattern(pattern: &str) -> Result<(), regex::Error> {
+    Regex::new(pattern).map(|_| ())
+}
+
+#[derive(Debug, Default)]
+pub struct Guardrails {
+    rules: Vec<(String, Regex)>,
+    action: GuardrailAction,
+}
+
+impl Guardrails {
+    /// The config's rules, compiled. A pattern that doesn't compile, which `check_pattern`
+    /// keeps out unless the config file was edited by hand, is left out rather than leaving
+    /// every reply unchecked.
+    pub fn new(config: &GuardrailConfig) -> Self {
+        let phrases = config.banned_phrases.iter().map(|phrase| {
+            let regex = RegexBuilder::new(&regex::escape(phrase))
+                .case_insensitive(true)
+                .build();
+            (phrase, regex)
+        });
+        let patterns = config
+            .banned_patterns
+            .iter()
+            .map(|pattern| (pattern, Regex::new(pattern)));
+        let rules = phrases
+            .chain(patterns)
+            .filter(|(rule, _)| !rule.is_empty())
+            .filter_map(|(rule, regex)| match regex {
+                Ok(regex) => Some((rule.clone(), regex)),
+                Err(e) => {
+                    eprintln!("Ignoring banned pattern {}: {}", rule, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            rules,
+            action: config.action,
+        }
+    }
+
+    pub fn filter(&self, reply: &str) -> FilteredReply {
+        let mut hits = Vec::new();
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for (rule, regex) in &self.rules {
+            let found: Vec<(usize, usize)> = regex
+                .find_iter(reply)
+                .filter(|found| !found.is_empty())
+                .map(|found| (found.start(), found.end()))
+                .collect();
+            if !found.is_empty() {
+                hits.push(GuardrailHit {
+                    rule: rule.clone(),
+                    count: found.len(),
+                });
+                ranges.extend(found);
+            }
+        }
+        let redacted = self.action == GuardrailAction::Redact && !hits.is_empty();
+        if !redacted {
+            return FilteredReply {
+                content: reply.to_string(),
+                hits,
+                redacted,
+            };
+        }
+        // Overlapping matches of different rules are redacted together.
+        ranges.sort();
+        let mut content = String::with_capacity(reply.len());
+        let mut kept_from = 0;
+        for (start, end) in ranges {
+            if end <= kept_from {
+                continue;
+            }
+            if start >= kept_from {
+                content.push_str(&reply[kept_from..start]);
+                content.push_str(REDACTED);
+            }
+            kept_from = end;
+        }
+        content.push_str(&reply[kept_from..]);
+        FilteredReply {
+            content,
+            hits,
+            redacted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn guardrails(action: GuardrailAction) -> Guardrails {
+        Guardrails::new(&GuardrailConfig {
+            banned_phrases: vec!["Project Falcon".to_string(), String::new()],
+            banned_patterns: vec![r"ACME-\d+".to_string(), "(".to_string()],
+            action,
+        })
+    }
+
+    #[test]
+    fn test_flag() {
+        let filtered = guardrails(GuardrailAction::Flag)
+            .filter("project falcon ships ACME-12 and ACME-13 next week.");
+        assert_eq!(
+            filtered.content,
+            "project falcon ships ACME-12 and ACME-13 next week."
+        );
+        assert!(!filtered.redacted);
+        assert_eq!(
+            filtered.hits,
+            [
+                GuardrailHit {
+                    rule: "Project Falcon".to_string(),
+                    count: 1,
+                },
+                GuardrailHit {
+                    rule: r"ACME-\d+".to_string(),
+                    count: 2,
+                },
+            ]
+        );
+        assert!(guardrails(GuardrailAction::Flag)
+            .filter("Nothing to see here.")
+            .hits
+            .is_empty());
+    }
+
+    #[test]
+    fn test_redact() {
+        let guardrails = Guardrails::new(&GuardrailConfig {
+            banned_phrases: vec!["Falcon".to_string()],
+            banned_patterns: vec![r"Project \w+".to_string()],
+            action: GuardrailAction::Redact,
+        });
+        let filtered = guardrails.filter("Project Falcon and Falcon.");
+        assert!(filtered.redacted);
+        // the overlapping matches become one redaction
+        assert_eq!(filtered.content, "[redacted] and [redacted].");
+        let clean = guardrails.filter("Nothing to see here.");
+        assert!(!clean.redacted);
+        assert_eq!(clean.content, "Nothing to see here.");
+        assert!(check_pattern("(").is_err());
+    }
+}
@@ -0,0 +1,192 @@
+//! Self-contained HTML transcripts: inline CSS, pre-highlighted code and images embedded as
+//! data URIs, so the single file can be shared without the app.
+
+use std::path::Path;
+
+use base64::Engine;
+use pulldown_cmark::{
+    escape::escape_html, html, CodeBlockKind, CowStr, Event, Options, Parser, Tag,
+};
+use syntect::{highlighting::ThemeSet, html::highlighted_html_for_string, parsing::SyntaxSet};
+
+use crate::{
+    compression,
+    export::role_label,
+    models::{Conversation, ConversationEvent},
+};
+
+/// Markdown images pointing at `attachment:<file name>` are embedded from the attachment store.
+const ATTACHMENT_SCHEME: &str = "attachment:";
+const THEME: &str = "InspiredGitHub";
+
+const STYLE: &str = "\
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; max-width: 860px; margin: 2rem auto; padding: 0 1rem; color: #1f2328; line-height: 1.5; }
+h1 { border-bottom: 1px solid #d0d7de; padding-bottom: 0.3em; }
+.message { border: 1px solid #d0d7de; border-radius: 8px; padding: 0.5rem 1rem; margin: 1rem 0; }
+.message.user { background: #f6f8fa; }
+.message.system { background: #fff8c5; }
+.meta { font-size: 0.85em; color: #656d76; font-weight: 600; }
+pre { padding: 0.75rem; border-radius: 6px; overflow-x: auto; border: 1px solid #d0d7de; }
+code { font-family: ui-monospace, SFMono-Regular, Consolas, monospace; font-size: 0.9em; }
+img { max-width: 100%; }
+";
+
+struct Highlighter {
+    syntaxes: SyntaxSet,
+    themes: ThemeSet,
+}
+
+impl Highlighter {
+    fn new() -> Self {
+        Self {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+            themes: ThemeSet::load_defaults(),
+        }
+    }
+
+    fn highlight(&self, code: &str, language: &str) -> String {
+        let syntax = self
+            .syntaxes
+            .find_syntax_by_token(language)
+            .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text());
+        highlighted_html_for_string(code, &self.syntaxes, syntax, &self.themes.themes[THEME])
+            .unwrap_or_else(|_| format!("<pre><code>{}</code></pre>", escape(code)))
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    escape_html(&mut escaped, text).expect("writing to a String can't fail");
+    escaped
+}
+
+/// The attachment file name an image destination points at.
+fn attachment_name(dest: &str) -> Option<&str> {
+    let name = dest.strip_prefix(ATTACHMENT_SCHEME)?;
+    // Only plain file names, so a crafted message can't embed arbitrary files from disk.
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return None;
+    }
+    Some(name)
+}
+
+/// File names of the attachments a message's images point at.
+pub fn attachment_references(content: &str) -> Vec<String> {
+    Parser::new(content)
+        .filter_map(|event| match event {
+            Event::Start(Tag::Image(_, dest, _)) => attachment_name(&dest).map(str::to_string),
+            _ => None,
+        })
+        .collect()
+}
+
+fn embed_attachment(dest: &str, attachments_dir: &Path) -> Option<String> {
+    let name = attachment_name(dest)?;
+    let bytes = compression::read_attachment(attachments_dir, name).ok()?;
+    let mime = mime_guess::from_path(name).first_or_octet_stream();
+    Some(format!(
+        "data:{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+fn is_script_url(dest: &str) -> bool {
+    let dest = dest.trim_start().to_ascii_lowercase();
+    ["javascript:", "vbscript:", "data:"]
+        .iter()
+        .any(|scheme| dest.starts_with(scheme))
+}
+
+fn render_markdown(content: &str, highlighter: &Highlighter, attachments_dir: &Path) -> String {
+    let mut events = Vec::new();
+    let mut code_block: Option<(String, String)> = None;
+    for event in Parser::new_ext(
+        content,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    ) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(language) => language.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code_block = Some((language, String::new()));
+            }
+            Event::Text(text) if code_block.is_some() => {
+                if let Some((_, code)) = code_block.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                if let Some((language, code)) = code_block.take() {
+                    events.push(Event::Html(highlighter.highlight(&code, &language).into()));
+                }
+            }
+            Event::Start(Tag::Image(link_type, dest, title)) => {
+                let dest = embed_attachment(&dest, attachments_dir)
+                    .map(CowStr::from)
+                    .unwrap_or(dest);
+                events.push(Event::Start(Tag::Image(link_type, dest, title)));
+            }
+            Event::Start(Tag::Link(link_type, dest, title)) => {
+                let dest = if is_script_url(&dest) {
+                    "#".into()
+                } else {
+                    dest
+                };
+                events.push(Event::Start(Tag::Link(link_type, dest, title)));
+            }
+            // Raw HTML from messages is shown as text so a shared file can't carry scripts.
+            Event::Html(raw) => events.push(Event::Text(raw)),
+            event => events.push(event),
+        }
+    }
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, events.into_iter());
+    rendered
+}
+
+pub fn conversation_to_html(conversation: &Conversation, attachments_dir: &Path) -> String {
+    let highlighter = Highlighter::new();
+    let title = escape(&conversation.get_title());
+    let mut body = format!("<h1>{}</h1>\n", title);
+    for record in &conversation.history {
+        if let ConversationEvent::MessageAdded(msg) = &record.event {
+            let label = role_label(msg.author);
+            body.push_str(&format!(
+                "<section class=\"message {}\">\n<div class=\"meta\">{}</div>\n{}</section>\n",
+                label.to_lowercase(),
+                label,
+                render_markdown(&msg.content, &highlighter, attachments_dir)
+            ));
+        }
+    }
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        title, STYLE, body
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_markdown() {
+        let highlighter = Highlighter::new();
+        let dir = Path::new("/nonexistent");
+        let rendered = render_markdown(
+            "Hi <script>alert(1)</script>\n\n```rust\nfn main() {}\n```\n",
+            &highlighter,
+            dir,
+        );
+        assert!(!rendered.contains("<script>"));
+        assert!(rendered.contains("&lt;script&gt;"));
+        assert!(rendered.contains("<pre style="));
+        assert_eq!(embed_attachment("attachment:../secret", dir), None);
+        assert!(
+            !render_markdown("[x](javascript:alert(1))", &highlighter, dir).contains("javascript")
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Translations for user-facing backend strings, from JSON catalogs embedded at build time.
+
+use std::{
+    collections::HashMap,
+    sync::{OnceLock, RwLock},
+};
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Locale code, display name and catalog. Every catalog must have the same keys as English.
+const CATALOGS: &[(&str, &str, &str)] = &[
+    ("en", "English", include_str!("../locales/en.json")),
+    ("de", "Deutsch", include_str!("../locales/de.json")),
+];
+
+static LOCALE: RwLock<Option<&'static str>> = RwLock::new(None);
+
+type Catalog = HashMap<String, String>;
+
+fn catalogs() -> &'static HashMap<&'static str, Catalog> {
+    static PARSED: OnceLock<HashMap<&'static str, Catalog>> = OnceLock::new();
+    PARSED.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|(code, _, json)| {
+                let catalog = serde_json::from_str(json).unwrap_or_else(|e| {
+                    eprintln!("Failed to parse {} translations: {}", code, e);
+                    Catalog::new()
+                });
+                (*code, catalog)
+            })
+            .collect()
+    })
+}
+
+/// Locale codes with their display names.
+pub fn list_locales() -> Vec<(&'static str, &'static str)> {
+    CATALOGS
+        .iter()
+        .map(|(code, name, _)| (*code, *name))
+        .collect()
+}
+
+/// Switches the locale for every later lookup. Returns false for unknown locales.
+pub fn set_locale(locale: &str) -> bool {
+    match CATALOGS.iter().find(|(code, _, _)| *code == locale) {
+        Some((code, _, _)) => {
+            *LOCALE.write().unwrap() = Some(code);
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn current_locale() -> &'static str {
+    LOCALE.read().unwrap().unwrap_or(DEFAULT_LOCALE)
+}
+
+/// Looks `key` up in `locale`, falling back to English.
+pub fn lookup(locale: &str, key: &str) -> Option<String> {
+    let catalogs = catalogs();
+    [locale, DEFAULT_LOCALE]
+        .iter()
+        .find_map(|locale| catalogs.get(locale)?.get(key))
+        .cloned()
+}
+
+/// Like `lookup`, falling back to the key itself.
+pub fn translate(locale: &str, key: &str) -> String {
+    lookup(locale, key).unwrap_or_else(|| key.to_string())
+}
+
+/// Translates `key` into the current locale.
+pub fn t(key: &str) -> String {
+    translate(current_locale(), key)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_catalogs_match_english() {
+        let catalogs = catalogs();
+        let english = &catalogs[DEFAULT_LOCALE];
+        for (code, _) in list_locales() {
+            let catalog = &catalogs[code];
+            assert_eq!(
+                catalog.len(),
+                english.len(),
+                "{} has extra or missing keys",
+                code
+            );
+            assert!(
+                english.keys().all(|key| catalog.contains_key(key)),
+                "{} is missing keys",
+                code
+            );
+        }
+        assert_eq!(
+            translate("de", "conversation.default_title"),
+            "Unbenannte Unterhaltung"
+        );
+        assert_eq!(translate("xx", "error.emit"), "Failed to emit");
+        assert_eq!(translate("de", "no.such.key"), "no.such.key");
+    }
+}
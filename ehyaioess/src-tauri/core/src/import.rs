@@ -0,0 +1,303 @@
+//! Importers normalizing other tools' exports into our event model.
+
+use chatgpt::types::Role;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::{Conversation, ConversationMessageAddedEvent, ConversationTitleChangedEvent};
+use crate::validation;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// `conversations.json` from Anthropic's Claude data export.
+    Claude,
+    /// `[{"role": ..., "content": ...}]`, optionally wrapped as `{"messages": [...]}`.
+    RoleContent,
+}
+
+fn parse_role(role: &str) -> Option<Role> {
+    match role.to_ascii_lowercase().as_str() {
+        "user" | "human" => Some(Role::User),
+        "assistant" | "ai" | "bot" | "model" => Some(Role::Assistant),
+        "system" => Some(Role::System),
+        _ => None,
+    }
+}
+
+fn parse_timestamp(value: &Value) -> Option<i64> {
+    match value {
+        Value::String(s) => chrono::DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|time| time.timestamp()),
+        Value::Number(n) => n.as_i64(),
+        _ => None,
+    }
+}
+
+/// Message text from either a plain string or a list of typed content blocks.
+fn content_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(blocks) => {
+            let text = blocks
+                .iter()
+                .filter_map(|block| block.get("text").and_then(Value::as_str))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            Some(text)
+        }
+        _ => None,
+    }
+}
+
+fn role_content_messages(json: &Value) -> Option<&Vec<Value>> {
+    match json {
+        Value::Array(messages) => Some(messages),
+        Value::Object(object) => object.get("messages").and_then(Value::as_array),
+        _ => None,
+    }
+}
+
+pub fn detect_format(json: &Value) -> Option<ImportFormat> {
+    let is_claude = json.as_array().is_some_and(|conversations| {
+        !conversations.is_empty()
+            && conversations
+                .iter()
+                .all(|conv| conv.get("chat_messages").is_some_and(Value::is_array))
+    });
+    if is_claude {
+        return Some(ImportFormat::Claude);
+    }
+    let is_role_content = role_content_messages(json).is_some_and(|messages| {
+        !messages.is_empty()
+            && messages.iter().all(|msg| {
+                msg.get("role").is_some_and(Value::is_string) && msg.get("content").is_some()
+            })
+    });
+    is_role_content.then_some(ImportFormat::RoleContent)
+}
+
+fn add_message(
+    conv: &mut Conversation,
+    role: Option<Role>,
+    content: Option<String>,
+    timestamp: Option<i64>,
+) {
+    let (Some(author), Some(content)) = (role, content) else {
+        return;
+    };
+    if content.trim().is_empty() {
+        return;
+    }
+    let event = ConversationMessageAddedEvent {
+        author,
+        content,
+        model: None,
+        language: None,
+        citations: Vec::new(),
+    };
+    match timestamp {
+        Some(timestamp) => conv.add_event_at(event, timestamp),
+        None => conv.add_event(event),
+    };
+}
+
+fn import_claude(json: &Value) -> Vec<Conversation> {
+    let mut conversations = Vec::new();
+    for claude_conv in json.as_array().into_iter().flatten() {
+        let mut conv = Conversation::new();
+        if let Some(created) = claude_conv.get("created_at").and_then(parse_timestamp) {
+            conv.history[0].timestamp = created;
+        }
+        if let Some(name) = claude_conv
+            .get("name")
+            .and_then(Value::as_str)
+            .filter(|n| !n.is_empty())
+        {
+            conv.add_event_at(
+                ConversationTitleChangedEvent {
+                    new_title: validation::imported_title(name),
+                },
+                conv.history[0].timestamp,
+            );
+        }
+        for msg in claude_conv["chat_messages"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            let role = msg
+                .get("sender")
+                .and_then(Value::as_str)
+                .and_then(parse_role);
+            let content = msg
+                .get("text")
+                .filter(|text| text.as_str().is_some_and(|text| !text.is_empty()))
+                .or_else(|| msg.get("content"))
+                .and_then(content_text);
+            let timestamp = msg.get("created_at").and_then(parse_timestamp);
+            add_message(&mut conv, role, content, timestamp);
+        }
+        conversations.push(conv);
+    }
+    conversations
+}
+
+fn import_role_content(json: &Value) -> Vec<Conversation> {
+    let mut conv = Conversation::new();
+    if let Some(title) = json.get("title").and_then(Value::as_str) {
+        conv.add_event(ConversationTitleChangedEvent {
+            new_title: validation::imported_title(title),
+        });
+    }
+    for msg in role_content_messages(json).into_iter().flatten() {
+        let role = msg.get("role").and_then(Value::as_str).and_then(parse_role);
+        let content = msg.get("content").and_then(content_text);
+        let timestamp = msg.get("timestamp").and_then(parse_timestamp);
+        add_message(&mut conv, role, content, timestamp);
+    }
+    vec![conv]
+}
+
+/// A conversation from a Markdown transcript like `export::conversation_to_markdown` writes:
+/// an optional `# Title`, then each message under a `## User`, `## Assistant` or `## System`
+/// heading. None when there are no such headings, i.e. it isn't a transcript.
+pub fn markdown_transcript(markdown: &str) -> Option<Conversation> {
+    let mut title = None;
+    let mut messages: Vec<(Role, Vec<&str>)> = Vec::new();
+    let mut in_code = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        // Headings in code blocks are part of the message.
+        let role = match in_code {
+            true => None,
+            false => line
+                .strip_prefix("## ")
+                .and_then(|heading| parse_role(heading.trim())),
+        };
+        match (role, messages.last_mut()) {
+            (Some(role), _) => messages.push((role, Vec::new())),
+            (None, Some((_, lines))) => lines.push(line),
+            (None, None) => {
+                if let Some(heading) = line.strip_prefix("# ").filter(|_| title.is_none()) {
+                    title = Some(heading);
+                }
+            }
+        }
+    }
+    if messages.is_empty() {
+        return None;
+    }
+    let mut conv = Conversation::new();
+    if let Some(title) = title
+        .map(validation::imported_title)
+        .filter(|t| !t.is_empty())
+    {
+        conv.add_event(ConversationTitleChangedEvent { new_title: title });
+    }
+    for (author, lines) in messages {
+        add_message(
+            &mut conv,
+            Some(author),
+            Some(lines.join("\n").trim().to_string()),
+            None,
+        );
+    }
+    Some(conv)
+}
+
+const UNKNOWN_FORMAT: &str = "Unrecognized import format";
+
+fn read_json(path: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    let file = std::fs::File::open(path)?;
+    Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+}
+
+pub fn detect_import_format(path: &str) -> Result<ImportFormat, Box<dyn std::error::Error>> {
+    Ok(detect_format(&read_json(path)?).ok_or(UNKNOWN_FORMAT)?)
+}
+
+/// Reads an export in any supported format as new conversations with fresh ids.
+pub fn import_conversations(path: &str) -> Result<Vec<Conversation>, Box<dyn std::error::Error>> {
+    let json = read_json(path)?;
+    match detect_format(&json).ok_or(UNKNOWN_FORMAT)? {
+        ImportFormat::Claude => Ok(import_claude(&json)),
+        ImportFormat::RoleContent => Ok(import_role_content(&json)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{export, models::ConversationEvent};
+
+    fn message_count(conv: &Conversation) -> usize {
+        conv.history
+            .iter()
+            .filter(|record| matches!(record.event, ConversationEvent::MessageAdded(_)))
+            .count()
+    }
+
+    #[test]
+    fn test_import_claude() {
+        let json = serde_json::json!([{
+            "uuid": "abc",
+            "name": "Trip planning",
+            "created_at": "2023-07-01T10:00:00.000000+00:00",
+            "chat_messages": [
+                {"sender": "human", "text": "Where to?", "created_at": "2023-07-01T10:00:01Z"},
+                {"sender": "assistant", "text": "", "content": [{"type": "text", "text": "Lisbon"}]}
+            ]
+        }]);
+        assert_eq!(detect_format(&json), Some(ImportFormat::Claude));
+        let conversations = import_claude(&json);
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0].get_title().as_str(), "Trip planning");
+        assert_eq!(message_count(&conversations[0]), 2);
+        assert_eq!(conversations[0].to_chat_history()[1].content, "Lisbon");
+    }
+
+    #[test]
+    fn test_import_role_content() {
+        let json = serde_json::json!({"messages": [
+            {"role": "system", "content": "Be terse."},
+            {"role": "user", "content": "Hi"},
+            {"role": "tool", "content": "ignored"}
+        ]});
+        assert_eq!(detect_format(&json), Some(ImportFormat::RoleContent));
+        assert_eq!(message_count(&import_role_content(&json)[0]), 2);
+        assert_eq!(detect_format(&serde_json::json!({"foo": 1})), None);
+    }
+
+    #[test]
+    fn test_markdown_transcript() {
+        let mut original = Conversation::new();
+        original.add_event(ConversationTitleChangedEvent {
+            new_title: "Shell tricks".to_string(),
+        });
+        for (author, content) in [
+            (Role::User, "How do I count lines?"),
+            (
+                Role::Assistant,
+                "Use wc:\n\n```md\n## User\n```\n\n# Not a title",
+            ),
+        ] {
+            add_message(&mut original, Some(author), Some(content.to_string()), None);
+        }
+        let markdown = export::conversation_to_markdown(&original);
+        let conv = markdown_transcript(&markdown).unwrap();
+        assert_eq!(conv.get_title().as_str(), "Shell tricks");
+        let contents = |conv: &Conversation| {
+            conv.to_chat_history()
+                .into_iter()
+                .map(|message| (export::role_label(message.role), message.content))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(contents(&conv), contents(&original));
+
+        assert!(markdown_transcript("# Notes\n\nJust some notes.").is_none());
+    }
+}
@@ -0,0 +1,476 @@
+//! Audits the stored history for damage the app would otherwise trip over, such as events it
+//! can't read, conversations that don't match their checksum or references to messages and
+//! attachments that are gone, and repairs what it can.
+//!
+//! Works on the file's JSON rather than on loaded conversations, since a single unreadable
+//! event keeps the whole file from loading.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use uuid::Uuid;
+
+use crate::{
+    compression,
+    config::Config,
+    html,
+    models::{self, Conversation, ConversationEvent, ConversationEventRecord, ConversationManager},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// Not a history file at all, so nothing in it could be loaded. Only reported when
+    /// recovering on startup; see `load_or_recover`.
+    UndecodableFile,
+    /// Not a conversation at all; repaired by dropping it.
+    UndecodableConversation,
+    /// Events changed since they were saved, as by disk corruption; repaired by dropping the
+    /// conversation, which the backup keeps.
+    ChecksumMismatch,
+    /// Stored under another id than its own; repaired by taking the id it's stored under.
+    ConversationIdMismatch,
+    /// An event the app can't read; repaired by dropping it.
+    UndecodableEvent,
+    /// An event naming another conversation; repaired by claiming it for this one.
+    ForeignEvent,
+    /// An event whose id was used before; repaired by dropping it.
+    DuplicateEvent,
+    /// An event older than one before it; repaired by ordering the events by time.
+    OutOfOrder,
+    /// A bookmark, note, rating or translation of a message that doesn't exist; repaired by
+    /// dropping it.
+    DanglingMessageReference,
+    /// An image pointing at an attachment that's gone. Can't be repaired.
+    MissingAttachment,
+}
+
+impl IssueKind {
+    pub fn is_repairable(self) -> bool {
+        !matches!(
+            self,
+            IssueKind::UndecodableFile | IssueKind::MissingAttachment
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IntegrityIssue {
+    /// The key the conversation is stored under, which may not be a valid id.
+    pub conversation_id: String,
+    /// Position of the event in the stored history.
+    pub event_index: Option<usize>,
+    pub kind: IssueKind,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    pub conversations: usize,
+    pub events: usize,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    pub fn needs_repair(&self) -> bool {
+        self.issues.iter().any(|issue| issue.kind.is_repairable())
+    }
+}
+
+pub struct Audit {
+    pub report: IntegrityReport,
+    /// The history with every repairable issue fixed.
+    pub repaired: HashMap<Uuid, Conversation>,
+}
+
+/// Audits the history file contents `json`. Fails only when it isn't a history file at all.
+pub fn audit(json: &str, attachments_dir: &Path) -> Result<Audit, serde_json::Error> {
+    // Kept as read too, since checksums are of the events exactly as stored.
+    let stored: BTreeMap<String, Box<RawValue>> = serde_json::from_str(json)?;
+    let mut report = IntegrityReport {
+        conversations: stored.len(),
+        ..Default::default()
+    };
+    let mut repaired = HashMap::new();
+    for (key, raw) in &stored {
+        let value: serde_json::Value = serde_json::from_str(raw.get())?;
+        let mut auditor = Auditor {
+            conversation_id: key,
+            issues: &mut report.issues,
+        };
+        report.events += value
+            .get("history")
+            .and_then(serde_json::Value::as_array)
+            .map_or(0, Vec::len);
+        if let Some(conv) = auditor.conversation(&value, raw, attachments_dir) {
+            repaired.insert(conv.id, conv);
+        }
+    }
+    Ok(Audit { report, repaired })
+}
+
+/// Keeps a copy of the history file and replaces it with `repaired`, returning the copy's
+/// path, which is remembered in `Config::history_backups`.
+pub fn repair(
+    config: &mut Config,
+    repaired: &ConversationManager,
+    now: i64,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let path = config.conversation_history_save_path.clone();
+    let backup_path = format!("{}.{}.bak", path, now);
+    std::fs::copy(&path, &backup_path)?;
+    config.history_backups.push(backup_path.clone());
+    config.write_to_disk()?;
+    repaired.write_to_disk(&path)?;
+    Ok(backup_path)
+}
+
+/// How a history that couldn't be loaded as it was got recovered on startup.
+#[derive(Debug, Clone)]
+pub struct HistoryRecovery {
+    pub report: IntegrityReport,
+    /// The copy kept of the damaged file; empty when none could be written, as in read-only
+    /// mode.
+    pub backup_path: String,
+}
+
+/// Loads the history, or when it's damaged, whatever of it can be repaired. Unless the config
+/// is read-only, the damaged file is backed up and replaced with the repaired history, so the
+/// next save doesn't write over what couldn't be loaded.
+pub fn load_or_recover(
+    config: &mut Config,
+    attachments_dir: &Path,
+) -> (ConversationManager, Option<HistoryRecovery>) {
+    let path = config.conversation_history_save_path.clone();
+    let error = match ConversationManager::from_disk(&path) {
+        Ok(mgr) => return (mgr, None),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return (ConversationManager::new(), None)
+        }
+        Err(e) => e,
+    };
+    eprintln!("Recovering damaged history: {}", error);
+    let audited = compression::read_to_string(Path::new(&path))
+        .map_err(|e| e.to_string())
+        .and_then(|json| audit(&json, attachments_dir).map_err(|e| e.to_string()));
+    let (report, repaired) = match audited {
+        Ok(Audit { report, repaired }) => (report, repaired),
+        Err(e) => (
+            IntegrityReport {
+                issues: vec![IntegrityIssue {
+                    conversation_id: String::new(),
+                    event_index: None,
+                    kind: IssueKind::UndecodableFile,
+                    detail: e,
+                }],
+                ..Default::default()
+            },
+            HashMap::new(),
+        ),
+    };
+    let repaired = ConversationManager {
+        conversations: repaired.into_iter().collect(),
+        compressed: config.compress_history,
+        loading: false,
+    };
+    let mut backup_path = String::new();
+    if !config.read_only {
+        match repair(config, &repaired, chrono::Utc::now().timestamp()) {
+            Ok(path) => backup_path = path,
+            Err(e) => eprintln!("Failed to back up damaged history: {}", e),
+        }
+    }
+    (
+        repaired,
+        Some(HistoryRecovery {
+            report,
+            backup_path,
+        }),
+    )
+}
+
+/// The event list of a stored conversation, exactly as read.
+#[derive(Deserialize)]
+struct StoredHistory<'a> {
+    #[serde(borrow)]
+    history: &'a RawValue,
+}
+
+struct Auditor<'a> {
+    conversation_id: &'a str,
+    issues: &'a mut Vec<IntegrityIssue>,
+}
+
+impl Auditor<'_> {
+    fn flag(&mut self, event_index: Option<usize>, kind: IssueKind, detail: String) {
+        self.issues.push(IntegrityIssue {
+            conversation_id: self.conversation_id.to_string(),
+            event_index,
+            kind,
+            detail,
+        });
+    }
+
+    /// The conversation with its repairable issues fixed, or None when it has to be dropped.
+    fn conversation(
+        &mut self,
+        value: &serde_json::Value,
+        raw: &RawValue,
+        attachments_dir: &Path,
+    ) -> Option<Conversation> {
+        let Ok(id) = Uuid::parse_str(self.conversation_id) else {
+            self.flag(
+                None,
+                IssueKind::UndecodableConversation,
+                "stored under an invalid id".to_string(),
+            );
+            return None;
+        };
+        let Some(stored_history) = value.get("history").and_then(serde_json::Value::as_array)
+        else {
+            self.flag(
+                None,
+                IssueKind::UndecodableConversation,
+                "has no event list".to_string(),
+            );
+            return None;
+        };
+        let stored_id = value.get("id").and_then(serde_json::Value::as_str);
+        if stored_id.and_then(|stored_id| Uuid::parse_str(stored_id).ok()) != Some(id) {
+            self.flag(
+                None,
+                IssueKind::ConversationIdMismatch,
+                format!("has id {}", stored_id.unwrap_or("none")),
+            );
+        }
+        if let Some(checksum) = value.get("checksum").and_then(serde_json::Value::as_str) {
+            let stored: Result<StoredHistory, _> = serde_json::from_str(raw.get());
+            if stored.is_ok_and(|stored| {
+                models::stored_history_checksum(stored.history.get()) != checksum
+            }) {
+                self.flag(
+                    None,
+                    IssueKind::ChecksumMismatch,
+                    format!("doesn't match checksum {}", checksum),
+                );
+                return None;
+            }
+        }
+
+        let mut history: Vec<(usize, ConversationEventRecord)> = Vec::new();
+        let mut seen = HashSet::new();
+        let mut latest = i64::MIN;
+        let mut out_of_order = false;
+        for (index, value) in stored_history.iter().enumerate() {
+            let mut record: ConversationEventRecord = match serde_json::from_value(value.clone()) {
+                Ok(record) => record,
+                Err(e) => {
+                    self.flag(Some(index), IssueKind::UndecodableEvent, e.to_string());
+                    continue;
+                }
+            };
+            if !seen.insert(record.id) {
+                self.flag(
+                    Some(index),
+                    IssueKind::DuplicateEvent,
+                    format!("repeats event {}", record.id),
+                );
+                continue;
+            }
+            if record.conversation_id != id {
+                self.flag(
+                    Some(index),
+                    IssueKind::ForeignEvent,
+                    format!("belongs to conversation {}", record.conversation_id),
+                );
+                record.conversation_id = id;
+            }
+            if record.timestamp < latest {
+                self.flag(
+                    Some(index),
+                    IssueKind::OutOfOrder,
+                    format!("at {}, after an event at {}", record.timestamp, latest),
+                );
+                out_of_order = true;
+            }
+            latest = latest.max(record.timestamp);
+            history.push((index, record));
+        }
+        if out_of_order {
+            // Stable, so events at the same time keep their order.
+            history.sort_by_key(|(_, record)| record.timestamp);
+        }
+
+        let message_ids: HashSet<Uuid> = history
+            .iter()
+            .filter(|(_, record)| matches!(record.event, ConversationEvent::MessageAdded(_)))
+            .map(|(_, record)| record.id)
+            .collect();
+        history.retain(|(index, record)| {
+            let referenced = match &record.event {
+                ConversationEvent::MessageBookmarked(event) => event.message_id,
+                ConversationEvent::MessageAnnotated(event) => event.message_id,
+                ConversationEvent::MessagePinned(event) => event.message_id,
+                ConversationEvent::MessageRated(event) => event.message_id,
+                ConversationEvent::MessageTranslated(event) => event.message_id,
+                ConversationEvent::MessageFiltered(event) => event.message_id,
+                _ => return true,
+            };
+            let exists = message_ids.contains(&referenced);
+            if !exists {
+                self.flag(
+                    Some(*index),
+                    IssueKind::DanglingMessageReference,
+                    format!("refers to missing message {}", referenced),
+                );
+            }
+            exists
+        });
+
+        for (index, record) in &history {
+            let ConversationEvent::MessageAdded(message) = &record.event else {
+                continue;
+            };
+            for name in html::attachment_references(&message.content) {
+                if compression::attachment_path(attachments_dir, &name).is_none() {
+                    self.flag(
+                        Some(*index),
+                        IssueKind::MissingAttachment,
+                        format!("attachment {} is missing", name),
+                    );
+                }
+            }
+        }
+
+        Some(Conversation {
+            id,
+            history: history.into_iter().map(|(_, record)| record).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::{
+        ConversationMessageAddedEvent, ConversationMessageBookmarkedEvent, ConversationReadEvent,
+    };
+
+    #[test]
+    fn test_audit() {
+        let dir = std::env::temp_dir().join(format!("integrity-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("present.png"), [0u8; 4]).unwrap();
+
+        let mut conv = Conversation::new();
+        conv.history.clear();
+        conv.add_event_at(
+            ConversationMessageAddedEvent {
+                author: chatgpt::types::Role::User,
+                content: "![a](attachment:present.png) ![b](attachment:gone.png)".to_string(),
+                model: None,
+                language: None,
+                citations: Vec::new(),
+            },
+            20,
+        );
+        conv.add_event_at(ConversationReadEvent { read_through: 1 }, 10);
+        conv.add_event_at(
+            ConversationMessageBookmarkedEvent {
+                message_id: Uuid::new_v4(),
+                bookmarked: true,
+            },
+            30,
+        );
+        let healthy = Conversation::new();
+        let mut stored = serde_json::json!({
+            conv.id.to_string(): conv,
+            healthy.id.to_string(): healthy,
+            "not-an-id": { "history": [] },
+        });
+        let events = stored[conv.id.to_string()]["history"]
+            .as_array_mut()
+            .unwrap();
+        let duplicate = events[1].clone();
+        events.push(duplicate);
+        events.push(serde_json::json!({ "id": "broken" }));
+        events[0]["conversation_id"] = serde_json::json!(healthy.id);
+
+        let audit = audit(&stored.to_string(), &dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let kinds: Vec<(Option<usize>, IssueKind)> = audit
+            .report
+            .issues
+            .iter()
+            .filter(|issue| issue.conversation_id == conv.id.to_string())
+            .map(|issue| (issue.event_index, issue.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (Some(0), IssueKind::ForeignEvent),
+                (Some(1), IssueKind::OutOfOrder),
+                (Some(3), IssueKind::DuplicateEvent),
+                (Some(4), IssueKind::UndecodableEvent),
+                (Some(2), IssueKind::DanglingMessageReference),
+                (Some(0), IssueKind::MissingAttachment),
+            ]
+        );
+        assert_eq!(audit.report.conversations, 3);
+        assert_eq!(audit.report.events, 6);
+        assert!(audit.report.needs_repair());
+        assert!(audit
+            .report
+            .issues
+            .iter()
+            .any(|issue| issue.kind == IssueKind::UndecodableConversation));
+
+        let repaired = &audit.repaired[&conv.id];
+        let timestamps: Vec<i64> = repaired
+            .history
+            .iter()
+            .map(|record| record.timestamp)
+            .collect();
+        assert_eq!(timestamps, [10, 20]);
+        assert!(repaired
+            .history
+            .iter()
+            .all(|record| record.conversation_id == conv.id));
+        assert_eq!(audit.repaired.len(), 2);
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let path = std::env::temp_dir().join(format!("integrity-{}.json", Uuid::new_v4()));
+        let mut mgr = ConversationManager::new();
+        let mut conv = Conversation::new();
+        conv.add_event(ConversationReadEvent { read_through: 7 });
+        let intact = Conversation::new();
+        mgr.conversations.insert(conv.id, conv.clone());
+        mgr.conversations.insert(intact.id, intact.clone());
+        mgr.write_to_disk(path.to_str().unwrap()).unwrap();
+        let json = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let clean = audit(&json, &path).unwrap();
+        assert!(clean.report.issues.is_empty());
+        let damaged = audit(
+            &json.replace("\"read_through\":7", "\"read_through\":9"),
+            &path,
+        )
+        .unwrap();
+        let kinds: Vec<IssueKind> = damaged
+            .report
+            .issues
+            .iter()
+            .map(|issue| issue.kind)
+            .collect();
+        assert_eq!(kinds, [IssueKind::ChecksumMismatch]);
+        assert!(!damaged.repaired.contains_key(&conv.id));
+        assert!(damaged.repaired.contains_key(&intact.id));
+    }
+}
@@ -0,0 +1,181 @@
+//! Keyboard shortcuts: built-in defaults plus the user's overrides from the config.
+
+use std::collections::BTreeMap;
+
+/// Action name to shortcut, in the accelerator format tauri uses (`CmdOrCtrl+Shift+F`).
+pub type Keybindings = BTreeMap<String, String>;
+
+pub const DEFAULT_KEYBINDINGS: &[(&str, &str)] = &[
+    ("new_conversation", "CmdOrCtrl+N"),
+    ("search_messages", "CmdOrCtrl+Shift+F"),
+    ("list_bookmarks", "CmdOrCtrl+Shift+B"),
+    ("list_app_actions", "CmdOrCtrl+Shift+P"),
+];
+
+/// Modifiers in the order they are written back out.
+const MODIFIERS: &[&str] = &["CmdOrCtrl", "Ctrl", "Alt", "Shift", "Super"];
+
+const NAMED_KEYS: &[&str] = &[
+    "Enter",
+    "Escape",
+    "Tab",
+    "Space",
+    "Backspace",
+    "Delete",
+    "Insert",
+    "Home",
+    "End",
+    "PageUp",
+    "PageDown",
+    "Up",
+    "Down",
+    "Left",
+    "Right",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum KeybindingError {
+    Invalid,
+    /// The shortcut is already bound to the named action.
+    Conflict(String),
+}
+
+impl std::fmt::Display for KeybindingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            KeybindingError::Invalid => write!(f, "Invalid shortcut"),
+            KeybindingError::Conflict(action) => {
+                write!(f, "Shortcut is already bound to {}", action)
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeybindingError {}
+
+fn normalize_modifier(modifier: &str) -> Option<&'static str> {
+    match modifier.to_ascii_lowercase().as_str() {
+        "cmdorctrl" | "commandorcontrol" => Some("CmdOrCtrl"),
+        "ctrl" | "control" => Some("Ctrl"),
+        "alt" | "option" => Some("Alt"),
+        "shift" => Some("Shift"),
+        "super" | "cmd" | "command" | "meta" => Some("Super"),
+        _ => None,
+    }
+}
+
+fn normalize_key(key: &str) -> Option<String> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return c.is_ascii_graphic().then(|| c.to_ascii_uppercase().to_string());
+    }
+    if let Some(number) = key
+        .strip_prefix(['F', 'f'])
+        .and_then(|n| n.parse::<u8>().ok())
+    {
+        return (1..=24).contains(&number).then(|| format!("F{}", number));
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|named| named.eq_ignore_ascii_case(key))
+        .map(|named| named.to_string())
+}
+
+/// Canonical form of a shortcut, or None when it isn't one. Printable keys need a modifier
+/// other than Shift, otherwise the shortcut would fire while typing.
+pub fn normalize_shortcut(shortcut: &str) -> Option<String> {
+    let mut parts: Vec<&str> = shortcut.split('+').map(str::trim).collect();
+    let key = normalize_key(parts.pop()?)?;
+    let mut modifiers = parts
+        .into_iter()
+        .map(normalize_modifier)
+        .collect::<Option<Vec<_>>>()?;
+    modifiers.sort_by_key(|modifier| MODIFIERS.iter().position(|m| m == modifier));
+    modifiers.dedup();
+    let is_printable = key.chars().count() == 1 || key == "Space";
+    if is_printable && modifiers.iter().all(|modifier| *modifier == "Shift") {
+        return None;
+    }
+    modifiers.push(&key);
+    Some(modifiers.join("+"))
+}
+
+/// Defaults overlaid with the user's overrides. An empty override unbinds the action.
+pub fn effective(overrides: &Keybindings) -> Keybindings {
+    let mut bindings: Keybindings = DEFAULT_KEYBINDINGS
+        .iter()
+        .map(|(action, shortcut)| (action.to_string(), shortcut.to_string()))
+        .collect();
+    for (action, shortcut) in overrides {
+        bindings.insert(action.clone(), shortcut.clone());
+    }
+    bindings.retain(|_, shortcut| !shortcut.is_empty());
+    bindings
+}
+
+/// Binds `action` to `shortcut`, or unbinds it when `shortcut` is empty.
+pub fn set(
+    overrides: &mut Keybindings,
+    action: &str,
+    shortcut: &str,
+) -> Result<(), KeybindingError> {
+    let shortcut = if shortcut.trim().is_empty() {
+        String::new()
+    } else {
+        normalize_shortcut(shortcut).ok_or(KeybindingError::Invalid)?
+    };
+    if !shortcut.is_empty() {
+        let conflict = effective(overrides)
+            .into_iter()
+            .find(|(other, bound)| other != action && *bound == shortcut);
+        if let Some((other, _)) = conflict {
+            return Err(KeybindingError::Conflict(other));
+        }
+    }
+    let is_default = DEFAULT_KEYBINDINGS
+        .iter()
+        .any(|(default_action, default)| *default_action == action && *default == shortcut);
+    if is_default {
+        overrides.remove(action);
+    } else {
+        overrides.insert(action.to_string(), shortcut);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize_shortcut() {
+        assert_eq!(
+            normalize_shortcut("shift + cmdorctrl + k").as_deref(),
+            Some("CmdOrCtrl+Shift+K")
+        );
+        assert_eq!(normalize_shortcut("F5").as_deref(), Some("F5"));
+        assert_eq!(normalize_shortcut("Shift+K"), None);
+        assert_eq!(normalize_shortcut("Hyper+K"), None);
+        assert_eq!(normalize_shortcut("Ctrl+"), None);
+    }
+
+    #[test]
+    fn test_set() {
+        let mut overrides = Keybindings::new();
+        assert_eq!(
+            set(&mut overrides, "list_bookmarks", "CmdOrCtrl+N"),
+            Err(KeybindingError::Conflict("new_conversation".to_string()))
+        );
+        set(&mut overrides, "new_conversation", "").unwrap();
+        set(&mut overrides, "list_bookmarks", "ctrl+n").unwrap();
+        assert_eq!(
+            effective(&overrides)
+                .get("list_bookmarks")
+                .map(String::as_str),
+            Some("Ctrl+N")
+        );
+        assert!(!effective(&overrides).contains_key("new_conversation"));
+        set(&mut overrides, "new_conversation", "CmdOrCtrl+N").unwrap();
+        assert!(!overrides.contains_key("new_conversation"));
+    }
+}
@@ -0,0 +1,437 @@
+//! The knowledge base: git repositories indexed for questions about their code. Source files
+//! the repository doesn't ignore are split into chunks of lines and embedded, and a
+//! conversation linked to a repository gets the chunks nearest its latest question added to
+//! the system prompt.
+//!
+//! `repositories.json` in the knowledge folder lists the indexed repositories; the chunks and
+//! vectors of each are in a file of their own, read only when they're searched.
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use chatgpt::types::{ChatMessage, Role};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{clustering, compression, models::Conversation, profiles::ProfileClients};
+
+/// Lines of a file in one chunk.
+pub const CHUNK_LINES: usize = 60;
+/// Characters of a chunk that are kept, for files with very long lines.
+pub const MAX_CHUNK_CHARS: usize = 3000;
+/// Larger files are skipped, being generated or data more often than source.
+pub const MAX_FILE_BYTES: u64 = 256 * 1024;
+/// The most chunks of a repository that are indexed, files past it being left out.
+pub const MAX_REPOSITORY_CHUNKS: usize = 2000;
+/// Chunks added to the system prompt for each question.
+pub const RETRIEVED_CHUNKS: usize = 5;
+/// Characters of the question that are embedded to search with.
+const QUERY_CHARS: usize = 2000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CodeChunk {
+    /// Relative to the repository, with `/` separators.
+    pub file: String,
+    /// 1-based and inclusive.
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+    /// Empty until the chunk is embedded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vector: Vec<f32>,
+}
+
+impl CodeChunk {
+    /// What gets embedded: the file's path and then its lines, so questions naming a file
+    /// find it.
+    pub fn embedding_text(&self) -> String {
+        format!("{}\n{}", self.file, self.text)
+    }
+}
+
+/// `text` of the file `file` in chunks of `CHUNK_LINES` lines.
+pub fn split_file(file: &str, text: &str) -> Vec<CodeChunk> {
+    let lines: Vec<&str> = text.lines().collect();
+    lines
+        .chunks(CHUNK_LINES)
+        .enumerate()
+        .filter(|(_, lines)| lines.iter().any(|line| !line.trim().is_empty()))
+        .map(|(i, lines)| CodeChunk {
+            file: file.to_string(),
+            start_line: i * CHUNK_LINES + 1,
+            end_line: i * CHUNK_LINES + lines.len(),
+            text: lines.join("\n").chars().take(MAX_CHUNK_CHARS).collect(),
+            vector: Vec::new(),
+        })
+        .collect()
+}
+
+/// Whether `path` is the top folder of a git repository.
+pub fn is_git_repository(path: &Path) -> bool {
+    path.join(".git").exists()
+}
+
+/// The source files of the repository at `root`, chunked.
+#[derive(Debug, Default)]
+pub struct SourceChunks {
+    pub file_count: usize,
+    pub chunks: Vec<CodeChunk>,
+    /// Whether files were left out for reaching `MAX_REPOSITORY_CHUNKS`.
+    pub truncated: bool,
+}
+
+/// Walks the repository at `root` in path order, skipping what it ignores (`.gitignore` and
+/// the like), hidden files, files over `MAX_FILE_BYTES` and binary files.
+pub fn collect_chunks(root: &Path) -> SourceChunks {
+    let mut collected = SourceChunks::default();
+    let walk = ignore::WalkBuilder::new(root)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    for entry in walk {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("Skipping part of repository: {}", e);
+                continue;
+            }
+        };
+        let small_file = entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+            && entry
+                .metadata()
+                .is_ok_and(|metadata| metadata.len() <= MAX_FILE_BYTES);
+        if !small_file {
+            continue;
+        }
+        // Files that aren't UTF-8 text are binary as far as questions go.
+        let text = match std::fs::read_to_string(entry.path()) {
+            Ok(text) if !text.contains('\0') => text,
+            _ => continue,
+        };
+        let file = entry
+            .path()
+            .strip_prefix(root)
+            .unwrap_or(entry.path())
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let chunks = split_file(&file, &text);
+        if collected.chunks.len() + chunks.len() > MAX_REPOSITORY_CHUNKS {
+            collected.truncated = true;
+            break;
+        }
+        collected.file_count += 1;
+        collected.chunks.extend(chunks);
+    }
+    collected
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RepositoryInfo {
+    pub id: Uuid,
+    /// The repository's folder name.
+    pub name: String,
+    /// Absolute, as it was indexed.
+    pub path: String,
+    pub indexed_at: i64,
+    pub file_count: usize,
+    pub chunk_count: usize,
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// The chunks of one repository with their vectors.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RepositoryIndex {
+    pub chunks: Vec<CodeChunk>,
+}
+
+impl RepositoryIndex {
+    /// Vectors of the chunks by embedding text, so indexing again only embeds what changed.
+    pub fn vectors_by_text(&self) -> HashMap<String, Vec<f32>> {
+        self.chunks
+            .iter()
+            .filter(|chunk| !chunk.vector.is_empty())
+            .map(|chunk| (chunk.embedding_text(), chunk.vector.clone()))
+            .collect()
+    }
+
+    /// The `n` chunks most similar to `query`, most similar first.
+    pub fn nearest(&self, query: &[f32], n: usize) -> Vec<&CodeChunk> {
+        let query = clustering::normalized(query);
+        let mut scored: Vec<(f32, &CodeChunk)> = self
+            .chunks
+            .iter()
+            .filter(|chunk| !chunk.vector.is_empty())
+            .map(|chunk| {
+                let vector = clustering::normalized(&chunk.vector);
+                (clustering::similarity(&query, &vector), chunk)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(n).map(|(_, chunk)| chunk).collect()
+    }
+}
+
+/// The indexed repositories, with their chunks kept in files beside the list.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct KnowledgeBase {
+    #[serde(skip)]
+    dir: PathBuf,
+    /// In the order they were first indexed.
+    pub repositories: Vec<RepositoryInfo>,
+}
+
+impl KnowledgeBase {
+    /// The knowledge base in `dir`, empty when there's nothing there yet.
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        let mut knowledge = match compression::read_to_string(&dir.join("repositories.json")) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(e),
+        };
+        knowledge.dir = dir.to_path_buf();
+        Ok(knowledge)
+    }
+
+    fn index_path(&self, id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn save(&self, compress: bool) -> io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        compression::write_with(&self.dir.join("repositories.json"), compress, |file| {
+            serde_json::to_writer(file, self).map_err(io::Error::from)
+        })
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&RepositoryInfo> {
+        self.repositories
+            .iter()
+            .find(|repository| repository.id == id)
+    }
+
+    /// The repository indexed from `path`, if any.
+    pub fn find_by_path(&self, path: &str) -> Option<&RepositoryInfo> {
+        self.repositories
+            .iter()
+            .find(|repository| repository.path == path)
+    }
+
+    pub fn read_index(&self, id: Uuid) -> io::Result<RepositoryIndex> {
+        Ok(serde_json::from_str(&compression::read_to_string(
+            &self.index_path(id),
+        )?)?)
+    }
+
+    /// Stores the index of the repository `info` describes, replacing an earlier one with the
+    /// same id.
+    pub fn put(
+        &mut self,
+        info: RepositoryInfo,
+        index: &RepositoryIndex,
+        compress: bool,
+    ) -> io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        compression::write_with(&self.index_path(info.id), compress, |file| {
+            serde_json::to_writer(file, index).map_err(io::Error::from)
+        })?;
+        match self
+            .repositories
+            .iter_mut()
+            .find(|repository| repository.id == info.id)
+        {
+            Some(existing) => *existing = info,
+            None => self.repositories.push(info),
+        }
+        self.save(compress)
+    }
+
+    /// Forgets the repository, returning it. Conversations linked to it stop getting its code.
+    pub fn remove(&mut self, id: Uuid, compress: bool) -> io::Result<Option<RepositoryInfo>> {
+        let Some(position) = self
+            .repositories
+            .iter()
+            .position(|repository| repository.id == id)
+        else {
+            return Ok(None);
+        };
+        let removed = self.repositories.remove(position);
+        match std::fs::remove_file(self.index_path(id)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        self.save(compress)?;
+        Ok(Some(removed))
+    }
+}
+
+/// Removes every indexed repository in `dir`, if any.
+pub fn delete(dir: &Path) -> io::Result<()> {
+    match std::fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// What's searched with for the reply to `conv`: its latest user message, cut short.
+pub fn retrieval_query(conv: &Conversation) -> Option<String> {
+    conv.to_chat_history()
+        .into_iter()
+        .rev()
+        .find(|message| matches!(message.role, Role::User))
+        .map(|message| message.content.chars().take(QUERY_CHARS).collect())
+        .filter(|query: &String| !query.trim().is_empty())
+}
+
+/// Added to the system prompt: the retrieved chunks of the repository named `name`.
+pub fn context_instruction(name: &str, chunks: &[&CodeChunk]) -> Option<String> {
+    if chunks.is_empty() {
+        return None;
+    }
+    let excerpts: Vec<String> = chunks
+        .iter()
+        .map(|chunk| {
+            format!(
+                "{} (lines {}-{}):\n```\n{}\n```",
+                chunk.file, chunk.start_line, chunk.end_line, chunk.text
+            )
+        })
+        .collect();
+    Some(format!(
+        "The user's question may be about the {} repository. These excerpts of it may help; \
+         mention the files you draw on.\n\n{}",
+        name,
+        excerpts.join("\n\n")
+    ))
+}
+
+/// The excerpts of the repository `repository_id` in `dir` nearest `query`, which is embedded
+/// through `profile_clients`, as `context_instruction` has them. None once the repository is
+/// removed.
+pub async fn retrieve_context(
+    dir: &Path,
+    repository_id: Uuid,
+    query: &str,
+    profile_clients: &ProfileClients,
+    profile: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let knowledge = KnowledgeBase::open(dir)?;
+    let Some(info) = knowledge.get(repository_id) else {
+        return Ok(None);
+    };
+    let index = knowledge.read_index(repository_id)?;
+    let query = profile_clients
+        .embed_with_failover(profile, &[query.to_string()])
+        .await?
+        .pop()
+        .unwrap_or_default();
+    Ok(context_instruction(
+        &info.name,
+        &index.nearest(&query, RETRIEVED_CHUNKS),
+    ))
+}
+
+/// Adds `context` to the end of the system message of `history`, or as one when it has none.
+pub fn add_context(history: &mut Vec<ChatMessage>, context: String) {
+    match history.first_mut() {
+        Some(message) if matches!(message.role, Role::System) => {
+            message.content.push_str("\n\n");
+            message.content.push_str(&context);
+        }
+        _ => history.insert(
+            0,
+            ChatMessage {
+                role: Role::System,
+                content: context,
+            },
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_collect_chunks() {
+        let root = std::env::temp_dir().join(format!("knowledge-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        std::fs::write(root.join("src").join("main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("debug.log"), "ignored").unwrap();
+        std::fs::write(root.join("image.bin"), [0u8, 1, 2]).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("target").join("out.rs"), "ignored").unwrap();
+        let long: String = (1..=70).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(root.join("notes.md"), long).unwrap();
+        assert!(is_git_repository(&root));
+
+        let collected = collect_chunks(&root);
+        let chunks: Vec<(&str, usize, usize)> = collected
+            .chunks
+            .iter()
+            .map(|chunk| (chunk.file.as_str(), chunk.start_line, chunk.end_line))
+            .collect();
+        assert_eq!(
+            chunks,
+            [
+                ("notes.md", 1, 60),
+                ("notes.md", 61, 70),
+                ("src/main.rs", 1, 1)
+            ]
+        );
+        assert_eq!(collected.file_count, 2);
+        assert!(!collected.truncated);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_knowledge_base() {
+        let dir = std::env::temp_dir().join(format!("knowledge-{}", Uuid::new_v4()));
+        let mut knowledge = KnowledgeBase::open(&dir).unwrap();
+        let mut chunks = split_file("a.rs", "fn a() {}");
+        chunks.extend(split_file("b.rs", "fn b() {}"));
+        chunks[0].vector = vec![1.0, 0.0];
+        chunks[1].vector = vec![0.0, 1.0];
+        let info = RepositoryInfo {
+            id: Uuid::new_v4(),
+            name: "repo".to_string(),
+            path: "/repo".to_string(),
+            indexed_at: 0,
+            file_count: 2,
+            chunk_count: 2,
+            truncated: false,
+        };
+        knowledge
+            .put(info.clone(), &RepositoryIndex { chunks }, false)
+            .unwrap();
+
+        let knowledge = KnowledgeBase::open(&dir).unwrap();
+        assert_eq!(knowledge.find_by_path("/repo"), Some(&info));
+        let index = knowledge.read_index(info.id).unwrap();
+        let nearest = index.nearest(&[0.1, 0.9], 1);
+        assert_eq!(nearest[0].file, "b.rs");
+        let instruction = context_instruction("repo", &nearest).unwrap();
+        assert!(instruction.ends_with("b.rs (lines 1-1):\n```\nfn b() {}\n```"));
+        assert_eq!(index.vectors_by_text().len(), 2);
+
+        let mut history = vec![ChatMessage {
+            role: Role::User,
+            content: "What does b do?".to_string(),
+        }];
+        add_context(&mut history, instruction);
+        assert!(matches!(history[0].role, Role::System));
+
+        let mut knowledge = knowledge;
+        assert_eq!(knowledge.remove(info.id, false).unwrap(), Some(info));
+        assert!(KnowledgeBase::open(&dir).unwrap().repositories.is_empty());
+        delete(&dir).unwrap();
+    }
+}
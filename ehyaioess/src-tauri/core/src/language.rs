@@ -0,0 +1,8 @@
+//! Detection of the language a message is written in, so search can be narrowed to one.
+
+/// ISO 639-3 code (e.g. `eng`, `deu`) of the language `text` is written in, when the detection
+/// is reliable; short or mixed texts usually give `None`.
+pub fn detect(text: &str) -> Option<String> {
+    let info = whatlang::detect(text)?;
+    info.is_reliable().then(|| info.lang().code().to_string())
+}
@@ -0,0 +1,59 @@
+pub mod action_items;
+pub mod analytics;
+pub mod archive;
+pub mod appearance;
+pub mod bookmarks;
+pub mod catalog;
+pub mod citations;
+pub mod clustering;
+pub mod compression;
+pub mod config;
+pub mod context;
+pub mod dictation;
+pub mod diff;
+pub mod drafts;
+pub mod embeddings;
+pub mod explain;
+pub mod export;
+pub mod feedback;
+pub mod finetune;
+pub mod fuzzy;
+pub mod generation_journal;
+pub mod glossary;
+pub mod guardrails;
+pub mod html;
+pub mod i18n;
+pub mod integrity;
+pub mod import;
+pub mod keybindings;
+pub mod knowledge;
+pub mod language;
+pub mod link_preview;
+pub mod models;
+pub mod overrides;
+pub mod pdf;
+pub mod presets;
+pub mod pricing;
+pub mod profiles;
+pub mod provider;
+pub mod quick_search;
+pub mod read_aloud;
+pub mod recorder;
+pub mod relocate;
+pub mod retention;
+pub mod search;
+pub mod sharing;
+pub mod stats;
+pub mod storage;
+pub mod store;
+pub mod sync;
+pub mod tagging;
+pub mod takeout;
+pub mod templates;
+pub mod tokens;
+pub mod translation;
+pub mod trash;
+pub mod updates;
+pub mod validation;
+pub mod window_layout;
+pub mod workspaces;
@@ -0,0 +1,288 @@
+//! Previews of linked pages from their OpenGraph and Twitter card metadata. They're fetched
+//! here so the frontend needs no cross-origin requests, and cached since pages rarely change.
+
+use std::{path::Path, sync::OnceLock, time::Duration};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{bookmarks, validation};
+
+/// How long a fetched preview is used before the page is fetched again.
+pub const CACHE_SECONDS: i64 = 7 * 24 * 60 * 60;
+/// Previews kept in the cache; the least recently fetched go first.
+const MAX_CACHED: usize = 500;
+/// Fetching gives up after this long, so one slow site doesn't hold up the rest.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// The metadata is in the head of the page; reading stops after this many bytes.
+const MAX_PAGE_BYTES: usize = 256 * 1024;
+/// In characters.
+const MAX_DESCRIPTION_LENGTH: usize = 300;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct LinkPreview {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Absolute URL of the preview image.
+    pub image: Option<String>,
+    pub site_name: Option<String>,
+    /// Unix seconds.
+    pub fetched_at: i64,
+}
+
+struct Patterns {
+    meta: Regex,
+    attribute: Regex,
+    title: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        meta: Regex::new(r"(?i)<meta\s[^>]*>").unwrap(),
+        attribute: Regex::new(r#"([\w:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap(),
+        title: Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap(),
+    })
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
+
+/// The `content` of each `<meta>` tag by its `property` or `name`, lowercased.
+fn meta_tags(html: &str) -> Vec<(String, String)> {
+    let patterns = patterns();
+    patterns
+        .meta
+        .find_iter(html)
+        .filter_map(|tag| {
+            let (mut key, mut content) = (None, None);
+            for attribute in patterns.attribute.captures_iter(tag.as_str()) {
+                let Some(value) = (2..=4).find_map(|group| attribute.get(group)) else {
+                    continue;
+                };
+                let value = value.as_str();
+                match attribute[1].to_ascii_lowercase().as_str() {
+                    "property" | "name" => key = Some(value.to_ascii_lowercase()),
+                    "content" => content = Some(decode_entities(value)),
+                    _ => {}
+                }
+            }
+            Some((key?, content?))
+        })
+        .collect()
+}
+
+/// `href` as an absolute URL, taking relative ones to be relative to `base`.
+fn resolve(base: &str, href: &str) -> String {
+    if href.starts_with("http://") || href.starts_with("https://") {
+        return href.to_string();
+    }
+    let scheme_end = base.find("://").map_or(0, |index| index + 3);
+    if let Some(rest) = href.strip_prefix("//") {
+        return format!("{}{}", &base[..scheme_end], rest);
+    }
+    let origin_end = base[scheme_end..]
+        .find('/')
+        .map_or(base.len(), |index| scheme_end + index);
+    match href.starts_with('/') {
+        true => format!("{}{}", &base[..origin_end], href),
+        false => {
+            let directory_end = base[origin_end..]
+                .rfind('/')
+                .map_or(base.len(), |index| origin_end + index);
+            format!("{}/{}", &base[..directory_end], href)
+        }
+    }
+}
+
+/// The preview of the page at `url` with the HTML of its head, preferring OpenGraph
+/// metadata to Twitter cards and those to the plain title and description.
+pub fn from_html(url: &str, html: &str, now: i64) -> LinkPreview {
+    let tags = meta_tags(html);
+    let meta = |keys: &[&str]| {
+        keys.iter().find_map(|key| {
+            tags.iter()
+                .find(|(name, content)| name == key && !content.trim().is_empty())
+                .map(|(_, content)| content.trim().to_string())
+        })
+    };
+    let title = meta(&["og:title", "twitter:title"]).or_else(|| {
+        let title = patterns().title.captures(html)?.get(1)?.as_str();
+        Some(decode_entities(title))
+    });
+    LinkPreview {
+        url: url.to_string(),
+        title: title
+            .map(|title| validation::imported_title(&title))
+            .filter(|title| !title.is_empty()),
+        description: meta(&["og:description", "twitter:description", "description"]).map(
+            |description| {
+                bookmarks::snippet(
+                    &validation::normalize_title(&description),
+                    MAX_DESCRIPTION_LENGTH,
+                )
+            },
+        ),
+        image: meta(&[
+            "og:image",
+            "og:image:url",
+            "twitter:image",
+            "twitter:image:src",
+        ])
+        .map(|image| resolve(url, &image)),
+        site_name: meta(&["og:site_name"]),
+        fetched_at: now,
+    }
+}
+
+/// Fetches the head of the page at `url` for its preview.
+pub async fn fetch(
+    http: &reqwest::Client,
+    url: &str,
+    now: i64,
+) -> Result<LinkPreview, reqwest::Error> {
+    let mut response = http
+        .get(url)
+        .header(reqwest::header::USER_AGENT, "ehyaioess")
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?;
+    let mut head = Vec::new();
+    while head.len() < MAX_PAGE_BYTES {
+        match response.chunk().await? {
+            Some(chunk) => head.extend_from_slice(&chunk),
+            None => break,
+        }
+    }
+    Ok(from_html(url, &String::from_utf8_lossy(&head), now))
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct PreviewCache {
+    /// Least recently fetched first.
+    pub previews: Vec<LinkPreview>,
+}
+
+impl PreviewCache {
+    /// The cache at `path`; empty when there's none yet or it can't be read, since previews
+    /// can always be fetched again.
+    pub fn open(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// The preview of `url` unless it's older than `CACHE_SECONDS` at `now`.
+    pub fn get(&self, url: &str, now: i64) -> Option<&LinkPreview> {
+        self.previews
+            .iter()
+            .find(|preview| preview.url == url && now - preview.fetched_at < CACHE_SECONDS)
+    }
+
+    pub fn insert(&mut self, preview: LinkPreview) {
+        self.previews.retain(|cached| cached.url != preview.url);
+        self.previews.push(preview);
+        let excess = self.previews.len().saturating_sub(MAX_CACHED);
+        self.previews.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_html() {
+        let html = r#"<html><head>
+            <title>Plain title</title>
+            <meta property="og:title" content="Rust &amp; Cargo">
+            <meta name="twitter:title" content="Twitter title" />
+            <meta name='description' content='The   Rust
+                programming language.'>
+            <meta content="/images/logo.png" property="og:image">
+            <meta property="og:site_name" content="Rust">
+        </head>"#;
+        let preview = from_html("https://www.rust-lang.org/learn/get-started", html, 100);
+        assert_eq!(
+            preview,
+            LinkPreview {
+                url: "https://www.rust-lang.org/learn/get-started".to_string(),
+                title: Some("Rust & Cargo".to_string()),
+                description: Some("The Rust programming language.".to_string()),
+                image: Some("https://www.rust-lang.org/images/logo.png".to_string()),
+                site_name: Some("Rust".to_string()),
+                fetched_at: 100,
+            }
+        );
+
+        let plain = from_html("https://example.com", "<TITLE>\n Example </TITLE>", 0);
+        assert_eq!(plain.title.as_deref(), Some("Example"));
+        assert_eq!((plain.description, plain.image), (None, None));
+        assert_eq!(
+            from_html("https://example.com", "<title> </title>", 0).title,
+            None
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let base = "https://example.com/docs/page.html";
+        assert_eq!(
+            resolve(base, "https://cdn.example.com/a.png"),
+            "https://cdn.example.com/a.png"
+        );
+        assert_eq!(
+            resolve(base, "//cdn.example.com/a.png"),
+            "https://cdn.example.com/a.png"
+        );
+        assert_eq!(resolve(base, "/a.png"), "https://example.com/a.png");
+        assert_eq!(
+            resolve(base, "img/a.png"),
+            "https://example.com/docs/img/a.png"
+        );
+        assert_eq!(
+            resolve("https://example.com", "a.png"),
+            "https://example.com/a.png"
+        );
+    }
+
+    #[test]
+    fn test_cache() {
+        let mut cache = PreviewCache::default();
+        let preview = |url: &str, fetched_at| LinkPreview {
+            url: url.to_string(),
+            fetched_at,
+            ..Default::default()
+        };
+        cache.insert(preview("https://a.example", 0));
+        cache.insert(preview("https://b.example", 10));
+        assert!(cache.get("https://a.example", CACHE_SECONDS - 1).is_some());
+        assert!(cache.get("https://a.example", CACHE_SECONDS).is_none());
+        cache.insert(preview("https://a.example", 20));
+        assert_eq!(cache.previews.len(), 2);
+        assert_eq!(cache.previews[1].fetched_at, 20);
+
+        for index in 0..MAX_CACHED {
+            cache.insert(preview(&format!("https://{}.example", index), 30));
+        }
+        assert_eq!(cache.previews.len(), MAX_CACHED);
+        assert!(cache.get("https://a.example", 30).is_none());
+    }
+}
@@ -0,0 +1,2002 @@
+use core::fmt;
+use std::{
+    any::{TypeId},
+    collections::HashMap, borrow::Cow,
+};
+
+use chatgpt::types::ChatMessage;
+use serde::{ser::SerializeStruct, Deserialize, Serialize};
+use serde_json::value::RawValue;
+use uuid::Uuid;
+
+use crate::{
+    citations::{self, Citation},
+    compression,
+    context::{self, ContextLimit},
+    glossary::{self, GlossaryEntry},
+    guardrails::GuardrailHit,
+    i18n, language,
+    presets::StylePreset,
+    provider::Sampling,
+    store::{ConversationStore, StoredEntry},
+    translation,
+    validation::ArgumentProblem,
+};
+
+/// Serialized for the frontend as `{ code, message, hint }` plus the fields of variants that
+/// have any, with the message and the optional remediation hint translated into the current
+/// locale. `ts-bindgen` declares that shape as `AppError`, so keep the two in step.
+#[derive(Debug, Clone)]
+pub enum MyError {
+    ConversationNotFoundFail,
+    EmitFail,
+    ConversationWriteToDiskFail,
+    NoConfigDirFail,
+    UserNotLatestAuthorInConversationFail,
+    ConversationEmptyFail,
+    ConversationAIResponseFail,
+    DirListFail,
+    ProfileNotFoundFail,
+    ConfigWriteToDiskFail,
+    ProxyConfigFail,
+    RequestLogExportFail,
+    DeepLinkInvalidFail,
+    DataExportFail,
+    DeleteConfirmationFail,
+    DataDeleteFail,
+    RetentionFail,
+    UsageReportPeriodFail,
+    MessageNotFoundFail,
+    MessageRatingFail,
+    FinetuneExportFail,
+    PdfExportFail,
+    HtmlExportFail,
+    ImportFail,
+    KeybindingInvalidFail,
+    KeybindingConflictFail,
+    AppearanceInvalidFail,
+    LocaleUnknownFail,
+    HistoryLocationInvalidFail,
+    HistoryMoveFail,
+    SyncNotConfiguredFail,
+    SyncFail,
+    SyncConflictNotFoundFail,
+    SyncConflictStrategyFail,
+    WorkspaceNameInvalidFail,
+    WorkspaceExistsFail,
+    WorkspaceNotFoundFail,
+    WorkspaceSwitchFail,
+    ReadOnlyModeFail,
+    ConversationLockedFail,
+    ConversationSettingInvalidFail,
+    LanguageInvalidFail,
+    ClusterCountInvalidFail,
+    SearchPatternInvalidFail,
+    TaskNotFoundFail,
+    UpdateCheckFail,
+    StorageCategoryInvalidFail,
+    GenerationInterruptedFail,
+    NoFailedGenerationFail,
+    IntegrityCheckFail,
+    IntegrityRepairFail,
+    HistoryCompressionFail,
+    ModelCatalogFail,
+    ArchiveExportFail,
+    ArchiveImportFail,
+    ArchiveVersionFail,
+    ArchiveConversationExistsFail,
+    TrashFail,
+    TemplateFail,
+    TemplateNotFoundFail,
+    LinkPreviewFail,
+    ActionItemsFail,
+    ActionItemNotFoundFail,
+    ShareFail,
+    SharedBundleFail,
+    SharedBundlePassphraseFail,
+    OpenedFileFail,
+    StylePresetFail,
+    StylePresetNotFoundFail,
+    StylePresetExistsFail,
+    StylePresetInvalidFail,
+    GlossaryInvalidFail,
+    GuardrailPatternInvalidFail,
+    RepositoryInvalidFail,
+    RepositoryNotFoundFail,
+    KnowledgeFail,
+    DiffInvalidFail,
+    DiffReadFail,
+    ScreenshotFail,
+    ScreenshotTargetNotFoundFail,
+    AttachmentWriteFail,
+    MicrophoneFail,
+    DictationActiveFail,
+    TranscriptionFail,
+    SpeakerFail,
+    SpeechFail,
+    PlaybackEmptyFail,
+    PlaybackSpeedInvalidFail,
+    WindowFail,
+    WindowLayoutWriteFail,
+    PendingGenerationNotFoundFail,
+    GenerationJournalFail,
+    /// The history is still loading, so it can't be replaced yet; see
+    /// `ConversationManager::loading`.
+    HistoryLoadingFail,
+    /// A command argument failed its `validation` check, by the name the frontend sends it as.
+    ArgumentInvalidFail {
+        field: String,
+        problem: ArgumentProblem,
+    },
+}
+impl MyError {
+    /// Key of the error's message in the translation catalogs.
+    fn message_key(&self) -> &'static str {
+        match self {
+            MyError::ConversationNotFoundFail => "error.conversation_not_found",
+            MyError::EmitFail => "error.emit",
+            MyError::ConversationWriteToDiskFail => "error.conversation_write_to_disk",
+            MyError::NoConfigDirFail => "error.no_config_dir",
+            MyError::UserNotLatestAuthorInConversationFail => "error.user_not_latest_author",
+            MyError::ConversationEmptyFail => "error.conversation_empty",
+            MyError::ConversationAIResponseFail => "error.conversation_ai_response",
+            MyError::DirListFail => "error.dir_list",
+            MyError::ProfileNotFoundFail => "error.profile_not_found",
+            MyError::ConfigWriteToDiskFail => "error.config_write_to_disk",
+            MyError::ProxyConfigFail => "error.proxy_config",
+            MyError::RequestLogExportFail => "error.request_log_export",
+            MyError::DeepLinkInvalidFail => "error.deep_link_invalid",
+            MyError::DataExportFail => "error.data_export",
+            MyError::DeleteConfirmationFail => "error.delete_confirmation",
+            MyError::DataDeleteFail => "error.data_delete",
+            MyError::RetentionFail => "error.retention",
+            MyError::UsageReportPeriodFail => "error.usage_report_period",
+            MyError::MessageNotFoundFail => "error.message_not_found",
+            MyError::MessageRatingFail => "error.message_rating",
+            MyError::FinetuneExportFail => "error.finetune_export",
+            MyError::PdfExportFail => "error.pdf_export",
+            MyError::HtmlExportFail => "error.html_export",
+            MyError::ImportFail => "error.import",
+            MyError::KeybindingInvalidFail => "error.keybinding_invalid",
+            MyError::KeybindingConflictFail => "error.keybinding_conflict",
+            MyError::AppearanceInvalidFail => "error.appearance_invalid",
+            MyError::LocaleUnknownFail => "error.locale_unknown",
+            MyError::HistoryLocationInvalidFail => "error.history_location_invalid",
+            MyError::HistoryMoveFail => "error.history_move",
+            MyError::SyncNotConfiguredFail => "error.sync_not_configured",
+            MyError::SyncFail => "error.sync",
+            MyError::SyncConflictNotFoundFail => "error.sync_conflict_not_found",
+            MyError::SyncConflictStrategyFail => "error.sync_conflict_strategy",
+            MyError::WorkspaceNameInvalidFail => "error.workspace_name_invalid",
+            MyError::WorkspaceExistsFail => "error.workspace_exists",
+            MyError::WorkspaceNotFoundFail => "error.workspace_not_found",
+            MyError::WorkspaceSwitchFail => "error.workspace_switch",
+            MyError::ReadOnlyModeFail => "error.read_only_mode",
+            MyError::ConversationLockedFail => "error.conversation_locked",
+            MyError::ConversationSettingInvalidFail => "error.conversation_setting_invalid",
+            MyError::LanguageInvalidFail => "error.language_invalid",
+            MyError::ClusterCountInvalidFail => "error.cluster_count_invalid",
+            MyError::SearchPatternInvalidFail => "error.search_pattern_invalid",
+            MyError::TaskNotFoundFail => "error.task_not_found",
+            MyError::UpdateCheckFail => "error.update_check",
+            MyError::StorageCategoryInvalidFail => "error.storage_category_invalid",
+            MyError::GenerationInterruptedFail => "error.generation_interrupted",
+            MyError::NoFailedGenerationFail => "error.no_failed_generation",
+            MyError::IntegrityCheckFail => "error.integrity_check",
+            MyError::IntegrityRepairFail => "error.integrity_repair",
+            MyError::HistoryCompressionFail => "error.history_compression",
+            MyError::ModelCatalogFail => "error.model_catalog",
+            MyError::ArchiveExportFail => "error.archive_export",
+            MyError::ArchiveImportFail => "error.archive_import",
+            MyError::ArchiveVersionFail => "error.archive_version",
+            MyError::ArchiveConversationExistsFail => "error.archive_conversation_exists",
+            MyError::TrashFail => "error.trash",
+            MyError::TemplateFail => "error.template",
+            MyError::TemplateNotFoundFail => "error.template_not_found",
+            MyError::LinkPreviewFail => "error.link_preview",
+            MyError::ActionItemsFail => "error.action_items",
+            MyError::ActionItemNotFoundFail => "error.action_item_not_found",
+            MyError::ShareFail => "error.share",
+            MyError::SharedBundleFail => "error.shared_bundle",
+            MyError::SharedBundlePassphraseFail => "error.shared_bundle_passphrase",
+            MyError::OpenedFileFail => "error.opened_file",
+            MyError::StylePresetFail => "error.style_preset",
+            MyError::StylePresetNotFoundFail => "error.style_preset_not_found",
+            MyError::StylePresetExistsFail => "error.style_preset_exists",
+            MyError::StylePresetInvalidFail => "error.style_preset_invalid",
+            MyError::GlossaryInvalidFail => "error.glossary_invalid",
+            MyError::GuardrailPatternInvalidFail => "error.guardrail_pattern_invalid",
+            MyError::RepositoryInvalidFail => "error.repository_invalid",
+            MyError::RepositoryNotFoundFail => "error.repository_not_found",
+            MyError::KnowledgeFail => "error.knowledge",
+            MyError::DiffInvalidFail => "error.diff_invalid",
+            MyError::DiffReadFail => "error.diff_read",
+            MyError::ScreenshotFail => "error.screenshot",
+            MyError::ScreenshotTargetNotFoundFail => "error.screenshot_target_not_found",
+            MyError::AttachmentWriteFail => "error.attachment_write",
+            MyError::MicrophoneFail => "error.microphone",
+            MyError::DictationActiveFail => "error.dictation_active",
+            MyError::TranscriptionFail => "error.transcription",
+            MyError::SpeakerFail => "error.speaker",
+            MyError::SpeechFail => "error.speech",
+            MyError::PlaybackEmptyFail => "error.playback_empty",
+            MyError::PlaybackSpeedInvalidFail => "error.playback_speed_invalid",
+            MyError::WindowFail => "error.window",
+            MyError::WindowLayoutWriteFail => "error.window_layout_write",
+            MyError::PendingGenerationNotFoundFail => "error.pending_generation_not_found",
+            MyError::GenerationJournalFail => "error.generation_journal",
+            MyError::HistoryLoadingFail => "error.history_loading",
+            MyError::ArgumentInvalidFail { problem, .. } => match problem {
+                ArgumentProblem::TooLong { .. } => "error.argument_too_long",
+                ArgumentProblem::InvalidId => "error.argument_invalid_id",
+                ArgumentProblem::PathNotAllowed { .. } => "error.argument_path_not_allowed",
+                ArgumentProblem::Empty => "error.argument_empty",
+                ArgumentProblem::Negative => "error.argument_negative",
+                ArgumentProblem::InvalidUrl => "error.argument_invalid_url",
+            },
+        }
+    }
+    /// The variant name, stable across locales.
+    pub fn code(&self) -> String {
+        match self {
+            MyError::ArgumentInvalidFail { .. } => "ArgumentInvalidFail".to_string(),
+            _ => format!("{:?}", self),
+        }
+    }
+    pub fn hint(&self) -> Option<String> {
+        let key = self.message_key().replacen("error.", "hint.", 1);
+        i18n::lookup(i18n::current_locale(), &key)
+    }
+}
+impl Serialize for MyError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let fields = match self {
+            MyError::ArgumentInvalidFail { .. } => 5,
+            _ => 3,
+        };
+        let mut error = serializer.serialize_struct("MyError", fields)?;
+        error.serialize_field("code", &self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.serialize_field("hint", &self.hint())?;
+        if let MyError::ArgumentInvalidFail { field, problem } = self {
+            error.serialize_field("field", field)?;
+            error.serialize_field("problem", problem)?;
+        }
+        error.end()
+    }
+}
+impl fmt::Display for MyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&i18n::t(self.message_key()))
+    }
+}
+impl std::error::Error for MyError {}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationMessageAddedEvent {
+    pub author: chatgpt::types::Role,
+    pub content: String,
+    /// Model that generated an assistant message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// ISO 639-3 code of the detected language, filled in when the message is added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Sources an assistant message cites, filled in when the message is added.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub citations: Vec<Citation>,
+}
+impl ConversationMessageAddedEvent {
+    /// The stored language, detected on the fly for messages saved before detection existed.
+    pub fn detected_language(&self) -> Option<String> {
+        self.language
+            .clone()
+            .or_else(|| language::detect(&self.content))
+    }
+    /// The stored citations, extracted on the fly for replies saved before extraction existed.
+    pub fn detected_citations(&self) -> Vec<Citation> {
+        match (&self.author, self.citations.is_empty()) {
+            (chatgpt::types::Role::Assistant, true) => citations::extract(&self.content),
+            _ => self.citations.clone(),
+        }
+    }
+}
+
+/// What arrived of an assistant reply before generating it failed. It stays pending until a
+/// message is added after it, either its continuation or a new reply.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationMessagePartialEvent {
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Why the reply broke off.
+    pub error: String,
+}
+
+/// An attempt at generating a reply that failed without any of it arriving. It stays pending
+/// until a message is added after it; see `Conversation::pending_failure`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationGenerationFailedEvent {
+    /// Redacted of API keys.
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationTitleChangedEvent {
+    pub new_title: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ConversationCreatedEvent {
+    /// Name of the template the conversation was started from, as it was then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationProfilePinnedEvent {
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationArchivedEvent {
+    pub archived: bool,
+}
+
+/// A locked conversation refuses new messages, title changes and setting changes; see
+/// `Conversation::try_add_event`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationLockedEvent {
+    pub locked: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationMessageBookmarkedEvent {
+    pub message_id: Uuid,
+    pub bookmarked: bool,
+}
+
+/// Pinned context is sent with every request, however long the conversation grows; see
+/// `context::fit`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationMessagePinnedEvent {
+    pub message_id: Uuid,
+    pub pinned: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationMessageAnnotatedEvent {
+    pub message_id: Uuid,
+    /// None clears the note.
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageRating {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationMessageRatedEvent {
+    pub message_id: Uuid,
+    /// None withdraws the rating.
+    pub rating: Option<MessageRating>,
+    pub comment: Option<String>,
+}
+
+/// Banned content the guardrails found in a reply; see `guardrails`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationMessageFilteredEvent {
+    pub message_id: Uuid,
+    pub hits: Vec<GuardrailHit>,
+    /// Whether the stored reply has the banned content redacted, rather than only flagged.
+    pub redacted: bool,
+}
+
+/// An alternate rendition of a message in another language, kept alongside the original.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationMessageTranslatedEvent {
+    pub message_id: Uuid,
+    pub language: String,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationReadEvent {
+    /// Number of messages the user had seen, counting from the start of the conversation.
+    pub read_through: usize,
+}
+
+/// A per-conversation setting. Each kind is changed independently and the latest change wins.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversationSetting {
+    /// Whether sending a message generates the reply right away. Without it, several messages
+    /// can be sent before asking for a reply.
+    AutoRespond(bool),
+    /// Sequences that end the reply early; see `provider::MAX_STOP_SEQUENCES`.
+    StopSequences(Vec<String>),
+    /// Text the reply is made to start with ("prefill"). It's sent as a final user message
+    /// asking for a continuation, since not every provider continues a trailing assistant
+    /// message. Empty for none.
+    AssistantPrefix(String),
+    /// Language replies are written in, e.g. "German". Empty for none.
+    Language(String),
+    /// Replaces the system prompt from the config. Empty to use that one.
+    SystemPrompt(String),
+    /// Tried before the models from the config. Empty to use those alone.
+    Model(String),
+    /// The response style preset as it was when applied, so editing the preset later doesn't
+    /// change the conversation. None for no preset.
+    StylePreset(Option<StylePreset>),
+    /// Terms replies should use as defined; see `glossary`. Empty for none.
+    Glossary(Vec<GlossaryEntry>),
+    /// The indexed repository whose code is searched for each question; see `knowledge`.
+    /// None for no repository.
+    Repository(Option<Uuid>),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationSettingChangedEvent {
+    pub setting: ConversationSetting,
+}
+
+/// Replaces the conversation's topic tags; see `tagging`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationTagsChangedEvent {
+    pub tags: Vec<String>,
+    /// Assigned by the model rather than the user.
+    #[serde(default)]
+    pub automatic: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ConversationEvent {
+    MessageAdded(ConversationMessageAddedEvent),
+    TitleChange(ConversationTitleChangedEvent),
+    Created(ConversationCreatedEvent),
+    ProfilePinned(ConversationProfilePinnedEvent),
+    Archived(ConversationArchivedEvent),
+    Locked(ConversationLockedEvent),
+    MessageBookmarked(ConversationMessageBookmarkedEvent),
+    MessageAnnotated(ConversationMessageAnnotatedEvent),
+    MessageRated(ConversationMessageRatedEvent),
+    MessageTranslated(ConversationMessageTranslatedEvent),
+    Read(ConversationReadEvent),
+    SettingChanged(ConversationSettingChangedEvent),
+    TagsChanged(ConversationTagsChangedEvent),
+    MessagePartial(ConversationMessagePartialEvent),
+    GenerationFailed(ConversationGenerationFailedEvent),
+    MessagePinned(ConversationMessagePinnedEvent),
+    MessageFiltered(ConversationMessageFilteredEvent),
+}
+impl From<ConversationMessageAddedEvent> for ConversationEvent {
+    fn from(event: ConversationMessageAddedEvent) -> Self {
+        ConversationEvent::MessageAdded(event)
+    }
+}
+
+impl From<ConversationTitleChangedEvent> for ConversationEvent {
+    fn from(event: ConversationTitleChangedEvent) -> Self {
+        ConversationEvent::TitleChange(event)
+    }
+}
+
+impl From<ConversationCreatedEvent> for ConversationEvent {
+    fn from(event: ConversationCreatedEvent) -> Self {
+        ConversationEvent::Created(event)
+    }
+}
+
+impl From<ConversationProfilePinnedEvent> for ConversationEvent {
+    fn from(event: ConversationProfilePinnedEvent) -> Self {
+        ConversationEvent::ProfilePinned(event)
+    }
+}
+
+impl From<ConversationArchivedEvent> for ConversationEvent {
+    fn from(event: ConversationArchivedEvent) -> Self {
+        ConversationEvent::Archived(event)
+    }
+}
+
+impl From<ConversationLockedEvent> for ConversationEvent {
+    fn from(event: ConversationLockedEvent) -> Self {
+        ConversationEvent::Locked(event)
+    }
+}
+
+impl From<ConversationMessageBookmarkedEvent> for ConversationEvent {
+    fn from(event: ConversationMessageBookmarkedEvent) -> Self {
+        ConversationEvent::MessageBookmarked(event)
+    }
+}
+
+impl From<ConversationMessagePinnedEvent> for ConversationEvent {
+    fn from(event: ConversationMessagePinnedEvent) -> Self {
+        ConversationEvent::MessagePinned(event)
+    }
+}
+
+impl From<ConversationMessageAnnotatedEvent> for ConversationEvent {
+    fn from(event: ConversationMessageAnnotatedEvent) -> Self {
+        ConversationEvent::MessageAnnotated(event)
+    }
+}
+
+impl From<ConversationMessageRatedEvent> for ConversationEvent {
+    fn from(event: ConversationMessageRatedEvent) -> Self {
+        ConversationEvent::MessageRated(event)
+    }
+}
+
+impl From<ConversationMessageTranslatedEvent> for ConversationEvent {
+    fn from(event: ConversationMessageTranslatedEvent) -> Self {
+        ConversationEvent::MessageTranslated(event)
+    }
+}
+
+impl From<ConversationMessageFilteredEvent> for ConversationEvent {
+    fn from(event: ConversationMessageFilteredEvent) -> Self {
+        ConversationEvent::MessageFiltered(event)
+    }
+}
+
+impl From<ConversationReadEvent> for ConversationEvent {
+    fn from(event: ConversationReadEvent) -> Self {
+        ConversationEvent::Read(event)
+    }
+}
+
+impl From<ConversationSettingChangedEvent> for ConversationEvent {
+    fn from(event: ConversationSettingChangedEvent) -> Self {
+        ConversationEvent::SettingChanged(event)
+    }
+}
+
+impl From<ConversationTagsChangedEvent> for ConversationEvent {
+    fn from(event: ConversationTagsChangedEvent) -> Self {
+        ConversationEvent::TagsChanged(event)
+    }
+}
+
+impl From<ConversationMessagePartialEvent> for ConversationEvent {
+    fn from(event: ConversationMessagePartialEvent) -> Self {
+        ConversationEvent::MessagePartial(event)
+    }
+}
+
+impl From<ConversationGenerationFailedEvent> for ConversationEvent {
+    fn from(event: ConversationGenerationFailedEvent) -> Self {
+        ConversationEvent::GenerationFailed(event)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationEventRecord {
+    pub id: uuid::Uuid,
+    pub conversation_id: Uuid,
+    pub timestamp: i64,
+    pub event: ConversationEvent,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Conversation {
+    pub id: uuid::Uuid,
+    pub history: Vec<ConversationEventRecord>,
+}
+
+const DEFAULT_CONVERSATION_TITLE_KEY: &str = "conversation.default_title";
+
+/// The title of a conversation that hasn't been given one, in the current locale.
+pub fn default_title() -> String {
+    i18n::t(DEFAULT_CONVERSATION_TITLE_KEY)
+}
+
+/// Sent last in a request so the reply picks up from `prefill`, which the app puts in
+/// front of the reply itself.
+fn prefill_instruction(prefill: &str) -> String {
+    format!(
+        "Your reply begins with the text below. Continue it from exactly where it stops, \
+         without repeating any of it.\n\n{}",
+        prefill
+    )
+}
+
+impl Conversation {
+    pub fn new() -> Self {
+        Self::created(ConversationCreatedEvent::default())
+    }
+    /// A new conversation started from the template named `template`.
+    pub fn from_template(template: &str) -> Self {
+        Self::created(ConversationCreatedEvent {
+            template: Some(template.to_string()),
+        })
+    }
+    fn created(event: ConversationCreatedEvent) -> Self {
+        let mut conv = Self {
+            id: uuid::Uuid::new_v4(),
+            history: Vec::new(),
+        };
+        conv.add_event(event);
+        conv
+    }
+    pub fn get_latest_event<T: 'static>(&self) -> Option<&ConversationEventRecord> {
+        self.history
+            .iter()
+            // .filter(|record| Any::type_id(&record.event as &dyn Any) == TypeId::of::<T>())
+            .filter(|record| match record.event {
+                ConversationEvent::TitleChange(_) => TypeId::of::<T>() == TypeId::of::<ConversationTitleChangedEvent>(),
+                ConversationEvent::MessageAdded(_) => TypeId::of::<T>() == TypeId::of::<ConversationMessageAddedEvent>(),
+                ConversationEvent::Created(_) => TypeId::of::<T>() == TypeId::of::<ConversationCreatedEvent>(),
+                ConversationEvent::ProfilePinned(_) => TypeId::of::<T>() == TypeId::of::<ConversationProfilePinnedEvent>(),
+                ConversationEvent::Archived(_) => TypeId::of::<T>() == TypeId::of::<ConversationArchivedEvent>(),
+                ConversationEvent::Locked(_) => TypeId::of::<T>() == TypeId::of::<ConversationLockedEvent>(),
+                ConversationEvent::MessageBookmarked(_) => TypeId::of::<T>() == TypeId::of::<ConversationMessageBookmarkedEvent>(),
+                ConversationEvent::MessageAnnotated(_) => TypeId::of::<T>() == TypeId::of::<ConversationMessageAnnotatedEvent>(),
+                ConversationEvent::MessageRated(_) => TypeId::of::<T>() == TypeId::of::<ConversationMessageRatedEvent>(),
+                ConversationEvent::MessageTranslated(_) => TypeId::of::<T>() == TypeId::of::<ConversationMessageTranslatedEvent>(),
+                ConversationEvent::Read(_) => TypeId::of::<T>() == TypeId::of::<ConversationReadEvent>(),
+                ConversationEvent::SettingChanged(_) => TypeId::of::<T>() == TypeId::of::<ConversationSettingChangedEvent>(),
+                ConversationEvent::TagsChanged(_) => TypeId::of::<T>() == TypeId::of::<ConversationTagsChangedEvent>(),
+                ConversationEvent::MessagePartial(_) => TypeId::of::<T>() == TypeId::of::<ConversationMessagePartialEvent>(),
+                ConversationEvent::GenerationFailed(_) => TypeId::of::<T>() == TypeId::of::<ConversationGenerationFailedEvent>(),
+                ConversationEvent::MessagePinned(_) => TypeId::of::<T>() == TypeId::of::<ConversationMessagePinnedEvent>(),
+                ConversationEvent::MessageFiltered(_) => TypeId::of::<T>() == TypeId::of::<ConversationMessageFilteredEvent>(),
+            })
+            .max_by_key(|record| record.timestamp)
+    }
+    /// Records an event regardless of the lock, for replaying or importing history.
+    /// User-initiated changes go through `try_add_event`.
+    pub fn add_event<E: Into<ConversationEvent>>(&mut self, event: E) -> &ConversationEventRecord {
+        self.add_event_at(event, chrono::Utc::now().timestamp())
+    }
+    /// Records an event unless the conversation is locked and the event would change its
+    /// messages, title or settings.
+    pub fn try_add_event<E: Into<ConversationEvent>>(
+        &mut self,
+        event: E,
+    ) -> Result<&ConversationEventRecord, MyError> {
+        let event = event.into();
+        if matches!(
+            event,
+            ConversationEvent::MessageAdded(_)
+                | ConversationEvent::MessagePartial(_)
+                | ConversationEvent::TitleChange(_)
+                | ConversationEvent::SettingChanged(_)
+        ) {
+            self.ensure_unlocked()?;
+        }
+        Ok(self.add_event(event))
+    }
+    pub fn is_locked(&self) -> bool {
+        self.get_latest_event::<ConversationLockedEvent>()
+            .is_some_and(|record| matches!(&record.event, ConversationEvent::Locked(event) if event.locked))
+    }
+    pub fn ensure_unlocked(&self) -> Result<(), MyError> {
+        if self.is_locked() {
+            return Err(MyError::ConversationLockedFail);
+        }
+        Ok(())
+    }
+    /// Records an event that happened at `timestamp`, e.g. when importing history.
+    pub fn add_event_at<E: Into<ConversationEvent>>(&mut self, event: E, timestamp: i64) -> &ConversationEventRecord {
+        let mut record = ConversationEventRecord {
+            id: uuid::Uuid::new_v4(),
+            conversation_id: self.id,
+            timestamp,
+            event: event.into(),
+        };
+        if let ConversationEvent::MessageAdded(message) = &mut record.event {
+            if message.language.is_none() {
+                message.language = language::detect(&message.content);
+            }
+            if message.citations.is_empty() && matches!(message.author, chatgpt::types::Role::Assistant) {
+                message.citations = citations::extract(&message.content);
+            }
+        }
+        self.history.push(record);
+        self.history.last().unwrap()
+    }
+    /// The exact messages of a completion request: the system prompt, the conversation's own
+    /// in place of `system_prompt` when it has one, and language preference, when set,
+    /// followed by the messages of the conversation in order and the instruction to continue
+    /// the assistant prefix, when set. With a `limit`, the oldest messages that aren't pinned
+    /// context are left out until the request fits.
+    pub fn to_request_messages(&self, system_prompt: &str, limit: Option<&ContextLimit>) -> Vec<ChatMessage> {
+        self.request_messages_with_prefill(system_prompt, self.assistant_prefix(), limit)
+    }
+    /// The messages asking the provider to continue the pending partial reply, which takes
+    /// the place of the assistant prefix.
+    pub fn to_continuation_messages(
+        &self,
+        system_prompt: &str,
+        limit: Option<&ContextLimit>,
+    ) -> Option<Vec<ChatMessage>> {
+        let (_, partial) = self.pending_partial()?;
+        Some(self.request_messages_with_prefill(system_prompt, partial.content.clone(), limit))
+    }
+    fn request_messages_with_prefill(
+        &self,
+        system_prompt: &str,
+        prefill: String,
+        limit: Option<&ContextLimit>,
+    ) -> Vec<ChatMessage> {
+        let own_prompt = self.system_prompt();
+        let mut system_prompt = match own_prompt.trim() {
+            "" => system_prompt.trim().to_string(),
+            own_prompt => own_prompt.to_string(),
+        };
+        if let Some(preset) = self.style_preset() {
+            if !system_prompt.is_empty() {
+                system_prompt.push_str("\n\n");
+            }
+            system_prompt.push_str(&preset.instruction);
+        }
+        if let Some(instruction) = glossary::glossary_instruction(&self.glossary()) {
+            if !system_prompt.is_empty() {
+                system_prompt.push_str("\n\n");
+            }
+            system_prompt.push_str(&instruction);
+        }
+        let language = self.language();
+        if !language.is_empty() {
+            if !system_prompt.is_empty() {
+                system_prompt.push_str("\n\n");
+            }
+            system_prompt.push_str(&translation::language_instruction(&language));
+        }
+        let system = (!system_prompt.is_empty()).then_some(ChatMessage {
+            role: chatgpt::types::Role::System,
+            content: system_prompt,
+        });
+        let prefill = (!prefill.is_empty()).then(|| ChatMessage {
+            role: chatgpt::types::Role::User,
+            content: prefill_instruction(&prefill),
+        });
+        let history = match limit {
+            Some(limit) => {
+                let fixed: Vec<ChatMessage> = system.iter().chain(&prefill).cloned().collect();
+                let history = self
+                    .history
+                    .iter()
+                    .filter_map(|record| match &record.event {
+                        ConversationEvent::MessageAdded(msg) => Some((
+                            ChatMessage {
+                                content: msg.content.clone(),
+                                role: msg.author,
+                            },
+                            self.is_message_pinned(record.id),
+                        )),
+                        _ => None,
+                    })
+                    .collect();
+                context::fit(&fixed, history, limit)
+            }
+            None => self.to_chat_history(),
+        };
+        system.into_iter().chain(history).chain(prefill).collect()
+    }
+    /// The reply as stored, with the assistant prefix the provider continued from.
+    ///
+    /// A provider may repeat the prefix despite being asked not to; that isn't undone here.
+    pub fn complete_reply(&self, continuation: &str) -> String {
+        format!("{}{}", self.assistant_prefix(), continuation)
+    }
+    pub fn to_chat_history(&self) -> Vec<ChatMessage> {
+        self
+            .history
+            .iter()
+            .filter_map(|record| {
+                if let ConversationEvent::MessageAdded(msg) = &record.event {
+                    Some(ChatMessage {
+                        content: msg.content.clone(),
+                        role: msg.author,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+    pub fn get_title(&self) -> Cow<'_, String> {
+        self.explicit_title()
+            .map(Cow::Borrowed)
+            .unwrap_or_else(|| Cow::Owned(default_title()))
+    }
+    /// The title the conversation was given, if any, rather than the default.
+    pub fn explicit_title(&self) -> Option<&String> {
+        self.get_latest_event::<ConversationTitleChangedEvent>()
+            .and_then(|record| {
+                if let ConversationEvent::TitleChange(event) = &record.event {
+                    Some(&event.new_title)
+                } else {
+                    None
+                }
+            })
+    }
+    pub fn get_pinned_profile(&self) -> Option<&str> {
+        self.get_latest_event::<ConversationProfilePinnedEvent>()
+            .and_then(|record| {
+                if let ConversationEvent::ProfilePinned(event) = &record.event {
+                    event.profile.as_deref()
+                } else {
+                    None
+                }
+            })
+    }
+    /// When the conversation was last archived, or None if it isn't archived.
+    pub fn archived_at(&self) -> Option<i64> {
+        self.get_latest_event::<ConversationArchivedEvent>()
+            .and_then(|record| match &record.event {
+                ConversationEvent::Archived(event) if event.archived => Some(record.timestamp),
+                _ => None,
+            })
+    }
+    pub fn is_archived(&self) -> bool {
+        self.archived_at().is_some()
+    }
+    /// Message records are identified by their event record id.
+    pub fn get_message(&self, message_id: Uuid) -> Option<&ConversationMessageAddedEvent> {
+        self.history.iter().find_map(|record| match &record.event {
+            ConversationEvent::MessageAdded(msg) if record.id == message_id => Some(msg),
+            _ => None,
+        })
+    }
+    /// When the message was bookmarked, or None if it currently isn't.
+    pub fn message_bookmarked_at(&self, message_id: Uuid) -> Option<i64> {
+        self.history
+            .iter()
+            .rev()
+            .find_map(|record| match &record.event {
+                ConversationEvent::MessageBookmarked(event) if event.message_id == message_id => {
+                    Some(event.bookmarked.then_some(record.timestamp))
+                }
+                _ => None,
+            })
+            .flatten()
+    }
+    pub fn is_message_pinned(&self, message_id: Uuid) -> bool {
+        self.history
+            .iter()
+            .rev()
+            .find_map(|record| match &record.event {
+                ConversationEvent::MessagePinned(event) if event.message_id == message_id => {
+                    Some(event.pinned)
+                }
+                _ => None,
+            })
+            .unwrap_or(false)
+    }
+    pub fn message_note(&self, message_id: Uuid) -> Option<&str> {
+        self.history
+            .iter()
+            .rev()
+            .find_map(|record| match &record.event {
+                ConversationEvent::MessageAnnotated(event) if event.message_id == message_id => {
+                    Some(event.note.as_deref())
+                }
+                _ => None,
+            })
+            .flatten()
+    }
+    /// The message's current rating and its comment, if it has been rated.
+    pub fn message_rating(&self, message_id: Uuid) -> Option<(MessageRating, Option<&str>)> {
+        self.history
+            .iter()
+            .rev()
+            .find_map(|record| match &record.event {
+                ConversationEvent::MessageRated(event) if event.message_id == message_id => {
+                    Some(event.rating.map(|rating| (rating, event.comment.as_deref())))
+                }
+                _ => None,
+            })
+            .flatten()
+    }
+    /// What the guardrails found in the message and whether it was redacted, if anything.
+    pub fn message_filter_hits(&self, message_id: Uuid) -> Option<(&[GuardrailHit], bool)> {
+        self.history.iter().rev().find_map(|record| match &record.event {
+            ConversationEvent::MessageFiltered(event) if event.message_id == message_id => {
+                Some((event.hits.as_slice(), event.redacted))
+            }
+            _ => None,
+        })
+    }
+    /// The latest partial reply with its record id, unless a message was added after it.
+    pub fn pending_partial(&self) -> Option<(Uuid, &ConversationMessagePartialEvent)> {
+        self.history
+            .iter()
+            .rev()
+            .find_map(|record| match &record.event {
+                ConversationEvent::MessageAdded(_) => Some(None),
+                ConversationEvent::MessagePartial(partial) => Some(Some((record.id, partial))),
+                _ => None,
+            })
+            .flatten()
+    }
+    /// The latest failed generation with its record, unless a message was added after it.
+    pub fn pending_failure(&self) -> Option<(&ConversationEventRecord, &ConversationGenerationFailedEvent)> {
+        self.history
+            .iter()
+            .rev()
+            .find_map(|record| match &record.event {
+                ConversationEvent::MessageAdded(_) => Some(None),
+                ConversationEvent::GenerationFailed(failure) => Some(Some((record, failure))),
+                _ => None,
+            })
+            .flatten()
+    }
+    /// Every failed generation with whether a reply was added after it, oldest first.
+    pub fn generation_failures(&self) -> Vec<(&ConversationEventRecord, &ConversationGenerationFailedEvent, bool)> {
+        let mut failures = Vec::new();
+        for record in &self.history {
+            match &record.event {
+                ConversationEvent::GenerationFailed(failure) => failures.push((record, failure, false)),
+                ConversationEvent::MessageAdded(msg)
+                    if matches!(msg.author, chatgpt::types::Role::Assistant) =>
+                {
+                    for (_, _, recovered) in failures.iter_mut() {
+                        *recovered = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        failures
+    }
+    pub fn message_count(&self) -> usize {
+        self.history
+            .iter()
+            .filter(|record| matches!(record.event, ConversationEvent::MessageAdded(_)))
+            .count()
+    }
+    /// Assistant messages after the last read marker. Sending a message implies
+    /// everything before it was read.
+    pub fn unread_count(&self) -> usize {
+        let mut read_through = self
+            .get_latest_event::<ConversationReadEvent>()
+            .map_or(0, |record| match &record.event {
+                ConversationEvent::Read(event) => event.read_through,
+                _ => 0,
+            });
+        let messages = self.history.iter().filter_map(|record| match &record.event {
+            ConversationEvent::MessageAdded(msg) => Some(msg),
+            _ => None,
+        });
+        for (sequence, msg) in messages.clone().enumerate() {
+            if matches!(msg.author, chatgpt::types::Role::User) {
+                read_through = read_through.max(sequence + 1);
+            }
+        }
+        messages
+            .skip(read_through)
+            .filter(|msg| matches!(msg.author, chatgpt::types::Role::Assistant))
+            .count()
+    }
+    /// The latest value of the setting `pick` selects.
+    fn latest_setting<T>(&self, pick: impl Fn(&ConversationSetting) -> Option<T>) -> Option<T> {
+        self.history.iter().rev().find_map(|record| match &record.event {
+            ConversationEvent::SettingChanged(event) => pick(&event.setting),
+            _ => None,
+        })
+    }
+    /// On unless turned off, matching conversations from before the setting existed.
+    pub fn auto_respond(&self) -> bool {
+        self.latest_setting(|setting| match setting {
+            ConversationSetting::AutoRespond(enabled) => Some(*enabled),
+            _ => None,
+        })
+        .unwrap_or(true)
+    }
+    pub fn stop_sequences(&self) -> Vec<String> {
+        self.latest_setting(|setting| match setting {
+            ConversationSetting::StopSequences(stop) => Some(stop.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+    pub fn assistant_prefix(&self) -> String {
+        self.latest_setting(|setting| match setting {
+            ConversationSetting::AssistantPrefix(prefix) => Some(prefix.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+    pub fn language(&self) -> String {
+        self.latest_setting(|setting| match setting {
+            ConversationSetting::Language(language) => Some(language.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+    pub fn system_prompt(&self) -> String {
+        self.latest_setting(|setting| match setting {
+            ConversationSetting::SystemPrompt(prompt) => Some(prompt.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+    pub fn model(&self) -> String {
+        self.latest_setting(|setting| match setting {
+            ConversationSetting::Model(model) => Some(model.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+    /// Name of the template the conversation was started from, if any.
+    pub fn template(&self) -> Option<&str> {
+        self.history.iter().find_map(|record| match &record.event {
+            ConversationEvent::Created(event) => event.template.as_deref(),
+            _ => None,
+        })
+    }
+    pub fn style_preset(&self) -> Option<StylePreset> {
+        self.latest_setting(|setting| match setting {
+            ConversationSetting::StylePreset(preset) => Some(preset.clone()),
+            _ => None,
+        })
+        .flatten()
+    }
+    pub fn glossary(&self) -> Vec<GlossaryEntry> {
+        self.latest_setting(|setting| match setting {
+            ConversationSetting::Glossary(glossary) => Some(glossary.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+    pub fn repository(&self) -> Option<Uuid> {
+        self.latest_setting(|setting| match setting {
+            ConversationSetting::Repository(repository_id) => Some(*repository_id),
+            _ => None,
+        })
+        .flatten()
+    }
+    /// The sampling parameters of the conversation's style preset, the provider's defaults
+    /// without one.
+    pub fn sampling(&self) -> Sampling {
+        self.style_preset()
+            .map(|preset| preset.sampling())
+            .unwrap_or_default()
+    }
+    /// The models to try in order: the conversation's own, when set, before `models`.
+    pub fn model_chain(&self, mut models: Vec<String>) -> Vec<String> {
+        let model = self.model();
+        let model = model.trim();
+        if !model.is_empty() {
+            models.retain(|m| m != model);
+            models.insert(0, model.to_string());
+        }
+        models
+    }
+    pub fn tags(&self) -> &[String] {
+        self.get_latest_event::<ConversationTagsChangedEvent>()
+            .and_then(|record| match &record.event {
+                ConversationEvent::TagsChanged(event) => Some(event.tags.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+    /// Whether tags were ever assigned, so automatic tagging leaves the conversation alone,
+    /// including when the user cleared them on purpose.
+    pub fn has_tag_history(&self) -> bool {
+        self.get_latest_event::<ConversationTagsChangedEvent>().is_some()
+    }
+    /// The message's translations as `(language, content)`, the latest one per language.
+    pub fn message_translations(&self, message_id: Uuid) -> Vec<(&str, &str)> {
+        let mut translations: Vec<(&str, &str)> = Vec::new();
+        for record in &self.history {
+            let ConversationEvent::MessageTranslated(event) = &record.event else {
+                continue;
+            };
+            if event.message_id != message_id {
+                continue;
+            }
+            let translation = (event.language.as_str(), event.content.as_str());
+            match translations
+                .iter_mut()
+                .find(|(language, _)| *language == event.language)
+            {
+                Some(existing) => *existing = translation,
+                None => translations.push(translation),
+            }
+        }
+        translations
+    }
+    pub fn last_activity(&self) -> i64 {
+        self.history
+            .iter()
+            .map(|record| record.timestamp)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::store::ConversationSummary;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_get_title() {
+        let mut conv = Conversation::new();
+        assert_eq!(
+            conv.get_title().as_ref(),
+            i18n::translate(i18n::DEFAULT_LOCALE, DEFAULT_CONVERSATION_TITLE_KEY).as_str()
+        );
+        let latest = conv.add_event(ConversationTitleChangedEvent {
+            new_title: "New Title".to_string(),
+        }).id;
+        assert_eq!(conv.get_latest_event::<ConversationTitleChangedEvent>().unwrap().id, latest);
+        assert_eq!(conv.get_title().as_ref(), "New Title");
+        conv.add_event(ConversationTitleChangedEvent {
+            new_title: "Newer Title".to_string(),
+        });
+        assert_eq!(conv.get_title().as_ref(), "Newer Title");
+    }
+
+    #[test]
+    fn test_error_payload() {
+        let error = serde_json::to_value(MyError::ImportFail).unwrap();
+        assert_eq!(error["code"], "ImportFail");
+        assert_eq!(error["message"], i18n::translate(i18n::DEFAULT_LOCALE, "error.import"));
+        assert!(error["hint"].is_string());
+        assert!(serde_json::to_value(MyError::EmitFail).unwrap()["hint"].is_null());
+    }
+
+    #[test]
+    fn test_conversation_not_found() {
+        let mut mgr = ConversationManager::new();
+        let conv = Conversation::new();
+        let id = conv.id;
+        mgr.conversations.insert(id, conv);
+        assert_eq!(mgr.conversation(&id).unwrap().id, id);
+        assert!(mgr.conversation_mut(&id).is_ok());
+        assert!(matches!(
+            mgr.conversation(&Uuid::new_v4()),
+            Err(MyError::ConversationNotFoundFail)
+        ));
+    }
+
+    #[test]
+    fn test_unread_count() {
+        let mut conv = Conversation::new();
+        let add_message = |conv: &mut Conversation, author| {
+            conv.add_event(ConversationMessageAddedEvent {
+                author,
+                content: String::new(),
+                model: None,
+                language: None,
+                citations: Vec::new(),
+            });
+        };
+        add_message(&mut conv, chatgpt::types::Role::User);
+        add_message(&mut conv, chatgpt::types::Role::Assistant);
+        add_message(&mut conv, chatgpt::types::Role::Assistant);
+        assert_eq!(conv.unread_count(), 2);
+        conv.add_event(ConversationReadEvent { read_through: 2 });
+        assert_eq!(conv.unread_count(), 1);
+        add_message(&mut conv, chatgpt::types::Role::User);
+        assert_eq!(conv.unread_count(), 0);
+    }
+
+    #[test]
+    fn test_locked() {
+        let mut conv = Conversation::new();
+        conv.add_event(ConversationLockedEvent { locked: true });
+        let message = || ConversationMessageAddedEvent {
+            author: chatgpt::types::Role::User,
+            content: String::new(),
+            model: None,
+            language: None,
+            citations: Vec::new(),
+        };
+        assert!(matches!(
+            conv.try_add_event(message()),
+            Err(MyError::ConversationLockedFail)
+        ));
+        assert!(conv
+            .try_add_event(ConversationTitleChangedEvent {
+                new_title: "Renamed".to_string(),
+            })
+            .is_err());
+        assert!(conv
+            .try_add_event(ConversationSettingChangedEvent {
+                setting: ConversationSetting::SystemPrompt("Talk like a pirate.".to_string()),
+            })
+            .is_err());
+        assert!(conv.try_add_event(ConversationArchivedEvent { archived: true }).is_ok());
+        conv.add_event(ConversationLockedEvent { locked: false });
+        assert!(conv.try_add_event(message()).is_ok());
+        assert_eq!(conv.message_count(), 1);
+    }
+
+    #[test]
+    fn test_to_request_messages() {
+        let mut conv = Conversation::new();
+        for (author, content) in [
+            (chatgpt::types::Role::User, "first"),
+            (chatgpt::types::Role::Assistant, "reply"),
+            (chatgpt::types::Role::User, "second"),
+        ] {
+            conv.add_event(ConversationMessageAddedEvent {
+                author,
+                content: content.to_string(),
+                model: None,
+                language: None,
+                citations: Vec::new(),
+            });
+        }
+        let contents = |messages: Vec<ChatMessage>| {
+            messages
+                .into_iter()
+                .map(|message| message.content)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            contents(conv.to_request_messages("Be brief.", None)),
+            ["Be brief.", "first", "reply", "second"]
+        );
+        assert!(matches!(
+            conv.to_request_messages("Be brief.", None)[0].role,
+            chatgpt::types::Role::System
+        ));
+        assert_eq!(
+            contents(conv.to_request_messages("  ", None)),
+            ["first", "reply", "second"]
+        );
+
+        conv.add_event(ConversationSettingChangedEvent {
+            setting: ConversationSetting::AssistantPrefix("Answer:".to_string()),
+        });
+        let messages = conv.to_request_messages("", None);
+        assert!(matches!(
+            messages.last().unwrap().role,
+            chatgpt::types::Role::User
+        ));
+        let prefill = prefill_instruction("Answer:");
+        assert_eq!(
+            contents(messages),
+            ["first", "reply", "second", prefill.as_str()]
+        );
+        assert_eq!(conv.complete_reply(" yes"), "Answer: yes");
+
+        conv.add_event(ConversationSettingChangedEvent {
+            setting: ConversationSetting::Language("German".to_string()),
+        });
+        assert_eq!(
+            conv.to_request_messages("Be brief.", None)[0].content,
+            format!(
+                "Be brief.\n\n{}",
+                translation::language_instruction("German")
+            )
+        );
+
+        conv.add_event(ConversationSettingChangedEvent {
+            setting: ConversationSetting::SystemPrompt("Be thorough.".to_string()),
+        });
+        assert!(conv.to_request_messages("Be brief.", None)[0]
+            .content
+            .starts_with("Be thorough.\n\n"));
+    }
+
+    #[test]
+    fn test_model_chain() {
+        let mut conv = Conversation::new();
+        let config_chain = || vec!["gpt-4o".to_string(), "gpt-4o-mini".to_string()];
+        assert_eq!(conv.model_chain(config_chain()), config_chain());
+        conv.add_event(ConversationSettingChangedEvent {
+            setting: ConversationSetting::Model("gpt-4o-mini".to_string()),
+        });
+        assert_eq!(conv.model_chain(config_chain()), ["gpt-4o-mini", "gpt-4o"]);
+    }
+
+    #[test]
+    fn test_style_preset() {
+        let mut conv = Conversation::new();
+        assert_eq!(conv.sampling(), Sampling::default());
+        let concise = crate::presets::built_in_presets().remove(0);
+        conv.add_event(ConversationSettingChangedEvent {
+            setting: ConversationSetting::StylePreset(Some(concise.clone())),
+        });
+        assert_eq!(conv.sampling(), concise.sampling());
+        assert_eq!(
+            conv.to_request_messages("Be kind.", None)[0].content,
+            format!("Be kind.\n\n{}", concise.instruction)
+        );
+        conv.add_event(ConversationSettingChangedEvent {
+            setting: ConversationSetting::StylePreset(None),
+        });
+        assert!(conv.style_preset().is_none());
+        assert_eq!(conv.to_request_messages("Be kind.", None)[0].content, "Be kind.");
+    }
+
+    #[test]
+    fn test_glossary() {
+        let mut conv = Conversation::new();
+        let glossary = vec![GlossaryEntry {
+            term: "Ledger".to_string(),
+            definition: "the service storing account balances".to_string(),
+        }];
+        conv.add_event(ConversationSettingChangedEvent {
+            setting: ConversationSetting::Glossary(glossary.clone()),
+        });
+        assert_eq!(conv.glossary(), glossary);
+        // without a system prompt of its own, the glossary is the whole of it
+        assert_eq!(
+            conv.to_request_messages("", None)[0].content,
+            glossary::glossary_instruction(&glossary).unwrap()
+        );
+        conv.add_event(ConversationSettingChangedEvent {
+            setting: ConversationSetting::Glossary(Vec::new()),
+        });
+        assert!(conv.to_request_messages("", None).is_empty());
+    }
+
+    #[test]
+    fn test_pinned_context_is_never_trimmed() {
+        let mut conv = Conversation::new();
+        let mut add = |content: String| {
+            conv.add_event(ConversationMessageAddedEvent {
+                author: chatgpt::types::Role::User,
+                content,
+                model: None,
+                language: None,
+                citations: Vec::new(),
+            })
+            .id
+        };
+        let requirement = add("Answer in metric units.".to_string());
+        for _ in 0..3 {
+            add("word ".repeat(400));
+        }
+        add("How far is it?".to_string());
+        conv.add_event(ConversationMessagePinnedEvent {
+            message_id: requirement,
+            pinned: true,
+        });
+        assert!(conv.is_message_pinned(requirement));
+        let limit = ContextLimit {
+            model: "gpt-4",
+            context_window: context::REPLY_RESERVE_TOKENS + 300,
+        };
+        let contents: Vec<_> = conv
+            .to_request_messages("Be brief.", Some(&limit))
+            .into_iter()
+            .map(|message| message.content)
+            .collect();
+        assert_eq!(contents, ["Be brief.", "Answer in metric units.", "How far is it?"]);
+        assert_eq!(conv.to_request_messages("Be brief.", None).len(), 6);
+
+        conv.add_event(ConversationMessagePinnedEvent {
+            message_id: requirement,
+            pinned: false,
+        });
+        assert_eq!(conv.to_request_messages("", Some(&limit)).len(), 1);
+    }
+
+    #[test]
+    fn test_pending_partial() {
+        let mut conv = Conversation::new();
+        let message = |author, content: &str| ConversationMessageAddedEvent {
+            author,
+            content: content.to_string(),
+            model: None,
+            language: None,
+            citations: Vec::new(),
+        };
+        conv.add_event(message(chatgpt::types::Role::User, "question"));
+        assert!(conv.pending_partial().is_none());
+        assert!(conv.to_continuation_messages("", None).is_none());
+
+        let partial_id = conv
+            .add_event(ConversationMessagePartialEvent {
+                content: "The answer".to_string(),
+                model: None,
+                error: "connection reset".to_string(),
+            })
+            .id;
+        assert_eq!(conv.pending_partial().map(|(id, _)| id), Some(partial_id));
+        let messages = conv.to_continuation_messages("", None).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(messages[1].role, chatgpt::types::Role::User));
+        assert_eq!(messages[1].content, prefill_instruction("The answer"));
+        // Partial replies aren't part of the conversation the provider sees otherwise.
+        assert_eq!(conv.to_request_messages("", None).len(), 1);
+
+        conv.add_event(message(chatgpt::types::Role::Assistant, "The answer is 42."));
+        assert!(conv.pending_partial().is_none());
+    }
+
+    #[test]
+    fn test_generation_failures() {
+        let mut conv = Conversation::new();
+        let message = |author| ConversationMessageAddedEvent {
+            author,
+            content: String::new(),
+            model: None,
+            language: None,
+            citations: Vec::new(),
+        };
+        let failure = |error: &str| ConversationGenerationFailedEvent {
+            error: error.to_string(),
+        };
+        conv.add_event(message(chatgpt::types::Role::User));
+        conv.add_event(failure("timed out"));
+        conv.add_event(failure("rate limited"));
+        assert_eq!(conv.pending_failure().unwrap().1.error, "rate limited");
+
+        conv.add_event(message(chatgpt::types::Role::Assistant));
+        assert!(conv.pending_failure().is_none());
+        conv.add_event(message(chatgpt::types::Role::User));
+        conv.add_event(failure("server error"));
+        let failures: Vec<(&str, bool)> = conv
+            .generation_failures()
+            .into_iter()
+            .map(|(_, failure, recovered)| (failure.error.as_str(), recovered))
+            .collect();
+        assert_eq!(
+            failures,
+            [("timed out", true), ("rate limited", true), ("server error", false)]
+        );
+    }
+
+    #[test]
+    fn test_message_translations() {
+        let mut conv = Conversation::new();
+        let message_id = conv
+            .add_event(ConversationMessageAddedEvent {
+                author: chatgpt::types::Role::User,
+                content: "Hello".to_string(),
+                model: None,
+                language: None,
+                citations: Vec::new(),
+            })
+            .id;
+        for (language, content) in [("German", "Hallo"), ("French", "Bonjour"), ("German", "Servus")] {
+            conv.add_event(ConversationMessageTranslatedEvent {
+                message_id,
+                language: language.to_string(),
+                content: content.to_string(),
+            });
+        }
+        assert_eq!(
+            conv.message_translations(message_id),
+            vec![("German", "Servus"), ("French", "Bonjour")]
+        );
+        assert_eq!(conv.get_message(message_id).unwrap().content, "Hello");
+    }
+
+    #[test]
+    fn test_auto_respond() {
+        let mut conv = Conversation::new();
+        assert!(conv.auto_respond());
+        conv.add_event(ConversationSettingChangedEvent {
+            setting: ConversationSetting::AutoRespond(false),
+        });
+        assert!(!conv.auto_respond());
+    }
+
+    #[test]
+    fn test_reload_from() {
+        let mut mgr = ConversationManager::new();
+        let kept = Conversation::new();
+        let edited = Conversation::new();
+        let removed = Conversation::new();
+        for conv in [&kept, &edited, &removed] {
+            mgr.conversations.insert(conv.id, conv.clone());
+        }
+
+        let mut external = ConversationManager::new();
+        external.conversations.insert(kept.id, kept.clone());
+        let mut edited_externally = edited.clone();
+        edited_externally.add_event(ConversationTitleChangedEvent {
+            new_title: "Synced".to_string(),
+        });
+        external.conversations.insert(edited.id, edited_externally);
+        let added = Conversation::new();
+        external.conversations.insert(added.id, added.clone());
+
+        let mut changed = mgr.reload_from(external);
+        changed.sort();
+        let mut expected = vec![(edited.id, false), (removed.id, true), (added.id, false)];
+        expected.sort();
+        assert_eq!(changed, expected);
+        assert_eq!(mgr.conversations.get(&edited.id).unwrap().get_title().as_ref(), "Synced");
+        assert!(!mgr.conversations.contains_key(&removed.id));
+    }
+
+    #[test]
+    fn test_checksum() {
+        let path = std::env::temp_dir().join(format!("history-{}.json", Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        let mut mgr = ConversationManager::new();
+        let mut conv = Conversation::new();
+        conv.add_event(ConversationMessageAddedEvent {
+            author: chatgpt::types::Role::User,
+            content: "original".to_string(),
+            model: None,
+            language: None,
+            citations: Vec::new(),
+        });
+        mgr.conversations.insert(conv.id, conv.clone());
+        mgr.write_to_disk(path).unwrap();
+        let loaded = ConversationManager::from_disk(path).unwrap();
+        assert_eq!(loaded.conversations.get(&conv.id).unwrap().message_count(), 1);
+
+        let json = std::fs::read_to_string(path).unwrap();
+        std::fs::write(path, json.replace("original", "origami!")).unwrap();
+        let error = ConversationManager::from_disk(path).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        // Files from before checksums load unverified.
+        let unchecked = serde_json::json!({ conv.id.to_string(): conv });
+        std::fs::write(path, unchecked.to_string()).unwrap();
+        assert!(ConversationManager::from_disk(path).is_ok());
+
+        // Saved before tag events had `automatic`, which is written out now even when false.
+        conv.add_event(ConversationTagsChangedEvent {
+            tags: vec!["rust".to_string()],
+            automatic: false,
+        });
+        let history = serde_json::to_string(&conv.history).unwrap();
+        let older = history.replace(",\"automatic\":false", "");
+        assert_ne!(older, history);
+        let saved = format!(
+            "{{\"{id}\":{{\"id\":\"{id}\",\"history\":{},\"checksum\":\"{}\"}}}}",
+            older,
+            stored_history_checksum(&older),
+            id = conv.id,
+        );
+        std::fs::write(path, &saved).unwrap();
+        let loaded = ConversationManager::from_disk(path).unwrap();
+        assert_eq!(loaded.conversations.get(&conv.id).unwrap().tags(), ["rust"]);
+        let audit = crate::integrity::audit(&saved, std::path::Path::new(path)).unwrap();
+        assert!(audit.report.issues.is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_batches() {
+        let path = std::env::temp_dir().join(format!("history-{}.json", Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        let mut mgr = ConversationManager::new();
+        for _ in 0..LOAD_BATCH_SIZE * 2 + 1 {
+            let conv = Conversation::new();
+            mgr.conversations.insert(conv.id, conv);
+        }
+        mgr.write_to_disk(path).unwrap();
+
+        let mut progress = Vec::new();
+        let mut loaded = ConversationManager::loading();
+        ConversationManager::load_batches(path, |batch, done, total| {
+            loaded.conversations.insert_stored(batch);
+            progress.push((done, total));
+        })
+        .unwrap();
+        let total = LOAD_BATCH_SIZE * 2 + 1;
+        assert_eq!(progress.len(), 3);
+        assert_eq!(progress.last(), Some(&(total, total)));
+        assert_eq!(loaded.conversations.len(), total);
+
+        // Saving before the whole history has loaded would lose the rest.
+        assert!(loaded.write_to_disk(path).is_err());
+        loaded.loading = false;
+        loaded.write_to_disk(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// A change made to a conversation, referring to its messages by position so any
+    /// sequence of them applies.
+    #[derive(Debug, Clone)]
+    enum Change {
+        Message(bool, String),
+        Title(String),
+        Bookmark(usize, bool),
+        Annotate(usize, Option<String>),
+        Rate(usize, Option<MessageRating>),
+        Translate(usize, String),
+        Read(usize),
+        Archive(bool),
+        Tags(Vec<String>),
+    }
+
+    fn change() -> impl Strategy<Value = Change> {
+        let rating = prop_oneof![Just(None), Just(Some(MessageRating::Up)), Just(Some(MessageRating::Down))];
+        prop_oneof![
+            3 => (any::<bool>(), ".{0,24}").prop_map(|(user, content)| Change::Message(user, content)),
+            2 => ".{0,16}".prop_map(Change::Title),
+            1 => (any::<usize>(), any::<bool>()).prop_map(|(index, on)| Change::Bookmark(index, on)),
+            1 => (any::<usize>(), proptest::option::of(".{0,16}")).prop_map(|(index, note)| Change::Annotate(index, note)),
+            1 => (any::<usize>(), rating).prop_map(|(index, rating)| Change::Rate(index, rating)),
+            1 => (any::<usize>(), "[a-z]{3}").prop_map(|(index, language)| Change::Translate(index, language)),
+            1 => (0..16usize).prop_map(Change::Read),
+            1 => any::<bool>().prop_map(Change::Archive),
+            1 => proptest::collection::vec("[a-z]{1,8}", 0..4).prop_map(Change::Tags),
+        ]
+    }
+
+    /// Changes with non-decreasing timestamps, some of them equal.
+    fn changes() -> impl Strategy<Value = Vec<(Change, i64)>> {
+        proptest::collection::vec((change(), 0..3i64), 0..40).prop_map(|changes| {
+            let mut timestamp = 1_700_000_000;
+            changes
+                .into_iter()
+                .map(|(change, step)| {
+                    timestamp += step;
+                    (change, timestamp)
+                })
+                .collect()
+        })
+    }
+
+    /// Builds a conversation from `changes`. Changes to messages that don't exist yet refer
+    /// to a made-up id, as events for deleted or foreign messages would.
+    fn build(changes: &[(Change, i64)]) -> Conversation {
+        let mut conv = Conversation {
+            id: Uuid::new_v4(),
+            history: Vec::new(),
+        };
+        conv.add_event_at(ConversationCreatedEvent::default(), changes.first().map_or(0, |(_, at)| *at));
+        let mut messages = Vec::new();
+        for (change, at) in changes {
+            let message_id = |index: &usize| {
+                if messages.is_empty() {
+                    Uuid::nil()
+                } else {
+                    messages[index % messages.len()]
+                }
+            };
+            let event: ConversationEvent = match change {
+                Change::Message(user, content) => ConversationMessageAddedEvent {
+                    author: if *user {
+                        chatgpt::types::Role::User
+                    } else {
+                        chatgpt::types::Role::Assistant
+                    },
+                    content: content.clone(),
+                    model: None,
+                    language: None,
+                    citations: Vec::new(),
+                }
+                .into(),
+                Change::Title(title) => ConversationTitleChangedEvent {
+                    new_title: title.clone(),
+                }
+                .into(),
+                Change::Bookmark(index, bookmarked) => ConversationMessageBookmarkedEvent {
+                    message_id: message_id(index),
+                    bookmarked: *bookmarked,
+                }
+                .into(),
+                Change::Annotate(index, note) => ConversationMessageAnnotatedEvent {
+                    message_id: message_id(index),
+                    note: note.clone(),
+                }
+                .into(),
+                Change::Rate(index, rating) => ConversationMessageRatedEvent {
+                    message_id: message_id(index),
+                    rating: *rating,
+                    comment: None,
+                }
+                .into(),
+                Change::Translate(index, language) => ConversationMessageTranslatedEvent {
+                    message_id: message_id(index),
+                    language: language.clone(),
+                    content: "translated".to_string(),
+                }
+                .into(),
+                Change::Read(read_through) => ConversationReadEvent {
+                    read_through: *read_through,
+                }
+                .into(),
+                Change::Archive(archived) => ConversationArchivedEvent {
+                    archived: *archived,
+                }
+                .into(),
+                Change::Tags(tags) => ConversationTagsChangedEvent {
+                    tags: tags.clone(),
+                    automatic: false,
+                }
+                .into(),
+            };
+            let record = conv.add_event_at(event, *at);
+            if matches!(record.event, ConversationEvent::MessageAdded(_)) {
+                messages.push(record.id);
+            }
+        }
+        conv
+    }
+
+    /// Everything the app derives from a conversation's events.
+    fn derived(conv: &Conversation) -> serde_json::Value {
+        let messages: Vec<_> = conv
+            .history
+            .iter()
+            .filter(|record| matches!(record.event, ConversationEvent::MessageAdded(_)))
+            .map(|record| {
+                serde_json::json!({
+                    "bookmarked_at": conv.message_bookmarked_at(record.id),
+                    "note": conv.message_note(record.id),
+                    "rating": conv.message_rating(record.id),
+                    "translations": conv.message_translations(record.id),
+                })
+            })
+            .collect();
+        let history: Vec<_> = conv
+            .to_chat_history()
+            .into_iter()
+            .map(|msg| (format!("{:?}", msg.role), msg.content))
+            .collect();
+        serde_json::json!({
+            "history": history,
+            "messages": messages,
+            "title": conv.get_title().as_ref(),
+            "message_count": conv.message_count(),
+            "unread_count": conv.unread_count(),
+            "archived_at": conv.archived_at(),
+            "tags": conv.tags(),
+            "last_activity": conv.last_activity(),
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_replay_is_deterministic(changes in changes()) {
+            let conv = build(&changes);
+            let replayed = Conversation {
+                id: conv.id,
+                history: conv.history.clone(),
+            };
+            prop_assert_eq!(derived(&conv), derived(&replayed));
+            // Record ids differ between builds, but nothing derived depends on them.
+            let rebuilt = build(&changes);
+            prop_assert_eq!(derived(&conv)["history"].clone(), derived(&rebuilt)["history"].clone());
+            prop_assert_eq!(derived(&conv)["title"].clone(), derived(&rebuilt)["title"].clone());
+            prop_assert_eq!(conv.unread_count(), rebuilt.unread_count());
+        }
+
+        #[test]
+        fn test_title_is_last_title_change(changes in changes()) {
+            let conv = build(&changes);
+            let last = changes.iter().rev().find_map(|(change, _)| match change {
+                Change::Title(title) => Some(title.clone()),
+                _ => None,
+            });
+            let title = conv.get_title().into_owned();
+            prop_assert_eq!(title, last.unwrap_or_else(|| i18n::t(DEFAULT_CONVERSATION_TITLE_KEY)));
+        }
+
+        #[test]
+        fn test_message_count_ignores_message_changes(changes in changes()) {
+            // Events about messages (bookmarks, notes, ratings and translations, the only
+            // changes to existing messages there are) never add to or remove from them.
+            let conv = build(&changes);
+            let added: Vec<_> = changes
+                .iter()
+                .filter_map(|(change, _)| match change {
+                    Change::Message(_, content) => Some(content.clone()),
+                    _ => None,
+                })
+                .collect();
+            prop_assert_eq!(conv.message_count(), added.len());
+            let history: Vec<_> = conv.to_chat_history().into_iter().map(|msg| msg.content).collect();
+            prop_assert_eq!(history, added);
+            prop_assert!(conv.unread_count() <= conv.message_count());
+        }
+
+        #[test]
+        fn test_serialization_round_trip(changes in changes()) {
+            let conv = build(&changes);
+            let json = serde_json::to_string(&conv).unwrap();
+            let decoded: Conversation = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(&serde_json::to_string(&decoded).unwrap(), &json);
+            prop_assert_eq!(history_checksum(&decoded.history), history_checksum(&conv.history));
+            prop_assert_eq!(derived(&decoded), derived(&conv));
+
+            // As the history file and the store keep it, compressed until used.
+            let stored = serde_json::to_string(&StoredConversationRef::of(&conv).unwrap()).unwrap();
+            let mut store = ConversationStore::with_limit(1);
+            store.insert_stored([decode_stored(conv.id, &stored).unwrap()]);
+            prop_assert_eq!(store.summaries(), vec![ConversationSummary::of(&conv)]);
+            let loaded = store.get(&conv.id).unwrap();
+            prop_assert_eq!(serde_json::to_string(&*loaded).unwrap(), json);
+        }
+    }
+}
+
+/// A conversation as the history file stores it, with a checksum of its events so damage to
+/// the file is noticed on load.
+#[derive(Serialize)]
+pub(crate) struct StoredConversationRef {
+    id: Uuid,
+    /// Written out as is, so the checksum is of exactly the bytes in the file.
+    history: Box<RawValue>,
+    checksum: String,
+}
+
+impl StoredConversationRef {
+    pub(crate) fn of(conv: &Conversation) -> Result<Self, serde_json::Error> {
+        let history = serde_json::value::to_raw_value(&conv.history)?;
+        Ok(Self {
+            id: conv.id,
+            checksum: stored_history_checksum(history.get()),
+            history,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct StoredConversation {
+    id: Uuid,
+    /// Kept as read until the checksum is checked, since decoding and encoding it again
+    /// wouldn't give back the same bytes once events have gained fields.
+    history: Box<RawValue>,
+    /// Missing from files saved before checksums were added.
+    #[serde(default)]
+    checksum: Option<String>,
+}
+
+impl StoredConversation {
+    pub(crate) fn into_conversation(self) -> Result<Conversation, serde_json::Error> {
+        Ok(Conversation {
+            id: self.id,
+            history: serde_json::from_str(self.history.get())?,
+        })
+    }
+}
+
+/// Decodes a conversation as the history file stores it, checking it against its checksum.
+fn decode_stored(key: Uuid, json: &str) -> Result<StoredEntry, std::io::Error> {
+    let conv: StoredConversation = serde_json::from_str(json)?;
+    if conv
+        .checksum
+        .as_ref()
+        .is_some_and(|checksum| *checksum != stored_history_checksum(conv.history.get()))
+    {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Conversation {} doesn't match its checksum", key),
+        ));
+    }
+    StoredEntry::new(key, &conv.into_conversation()?)
+}
+
+/// Conversations decoded at a time by each thread loading the history.
+const LOAD_BATCH_SIZE: usize = 64;
+
+/// Checksum of the events as they're encoded now.
+pub fn history_checksum(history: &[ConversationEventRecord]) -> String {
+    stored_history_checksum(&serde_json::to_string(history).unwrap_or_default())
+}
+
+/// Checksum of the events as the history file stores them, `json` being the stored event
+/// list exactly as read.
+pub fn stored_history_checksum(json: &str) -> String {
+    format!("crc32:{:08x}", crc32fast::hash(json.as_bytes()))
+}
+
+pub struct ConversationManager {
+    pub conversations: ConversationStore,
+    /// Whether `write_to_disk` compresses the file; follows `Config::compress_history`.
+    pub compressed: bool,
+    /// Set while the history is still being loaded into this manager, when saving would
+    /// write over the conversations not loaded yet.
+    pub loading: bool,
+}
+impl ConversationManager {
+    pub fn new() -> Self {
+        Self {
+            conversations: ConversationStore::new(),
+            compressed: false,
+            loading: false,
+        }
+    }
+    /// The conversation with `id`, telling a well-formed id that isn't in the history apart
+    /// from a malformed one, which `validation::id` refuses before the lookup.
+    pub fn conversation(&self, id: &Uuid) -> Result<std::sync::Arc<Conversation>, MyError> {
+        self.conversations
+            .get(id)
+            .ok_or(MyError::ConversationNotFoundFail)
+    }
+    pub fn conversation_mut(&mut self, id: &Uuid) -> Result<&mut Conversation, MyError> {
+        self.conversations
+            .get_mut(id)
+            .ok_or(MyError::ConversationNotFoundFail)
+    }
+    /// An empty manager the history is about to be loaded into; it refuses to save until
+    /// `loading` is cleared.
+    pub fn loading() -> Self {
+        Self {
+            loading: true,
+            ..Self::new()
+        }
+    }
+    /// Reads a plain or compressed history, remembering which it was. Conversations are
+    /// checked and kept compressed until used; see `store`. Fails with `InvalidData` when a
+    /// conversation doesn't match its checksum; see `integrity::load_or_recover`.
+    pub fn from_disk(path: &str) -> Result<Self, std::io::Error> {
+        let mut conversations = ConversationStore::new();
+        let compressed =
+            Self::load_batches(path, |batch, _, _| conversations.insert_stored(batch))?;
+        Ok(Self {
+            conversations,
+            compressed,
+            loading: false,
+        })
+    }
+    /// Reads the history at `path`, decoding its conversations on every core, and hands them
+    /// to `on_batch` as they're ready along with how many are done out of how many. Returns
+    /// whether the file was compressed; fails like `from_disk`.
+    pub fn load_batches(
+        path: &str,
+        mut on_batch: impl FnMut(Vec<StoredEntry>, usize, usize),
+    ) -> Result<bool, std::io::Error> {
+        let path = std::path::Path::new(path);
+        let compressed = compression::is_compressed(path)?;
+        // Split into each conversation's JSON first, which is quick, so decoding can be shared.
+        let stored: HashMap<Uuid, Box<RawValue>> =
+            serde_json::from_reader(compression::open(path)?)?;
+        let stored: Vec<_> = stored.into_iter().collect();
+        let chunks: Vec<_> = stored.chunks(LOAD_BATCH_SIZE).collect();
+        let next_chunk = std::sync::atomic::AtomicUsize::new(0);
+        let threads = std::thread::available_parallelism()
+            .map_or(1, std::num::NonZeroUsize::get)
+            .min(chunks.len());
+        std::thread::scope(|scope| {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            for _ in 0..threads {
+                let sender = sender.clone();
+                let (chunks, next_chunk) = (&chunks, &next_chunk);
+                scope.spawn(move || {
+                    let next = || next_chunk.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    while let Some(chunk) = chunks.get(next()) {
+                        let batch: Result<Vec<_>, _> = chunk
+                            .iter()
+                            .map(|(key, json)| decode_stored(*key, json.get()))
+                            .collect();
+                        let failed = batch.is_err();
+                        // Stops once the loader has given up after an error.
+                        if sender.send(batch).is_err() || failed {
+                            return;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+            let mut done = 0;
+            for batch in receiver {
+                let batch = batch?;
+                done += batch.len();
+                on_batch(batch, done, stored.len());
+            }
+            Ok(compressed)
+        })
+    }
+    /// Finds the conversation holding the given message.
+    pub fn find_message_conversation_mut(&mut self, message_id: Uuid) -> Option<&mut Conversation> {
+        let (id, _) = self
+            .conversations
+            .iter()
+            .find(|(_, conv)| conv.get_message(message_id).is_some())?;
+        self.conversations.get_mut(&id)
+    }
+    /// Takes over conversations that differ in `external`, such as a history file changed by
+    /// another instance or a sync tool. Returns the ids that changed and whether each was removed.
+    pub fn reload_from(&mut self, external: ConversationManager) -> Vec<(Uuid, bool)> {
+        self.conversations.reload_from(external.conversations)
+    }
+    pub fn write_to_disk(&self, path: &str) -> Result<(), std::io::Error> {
+        if self.loading {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::WouldBlock,
+                "The history is still loading",
+            ));
+        }
+        compression::write_with(std::path::Path::new(path), self.compressed, |writer| {
+            serde_json::to_writer(writer, &self.conversations).map_err(std::io::Error::from)
+        })
+    }
+}
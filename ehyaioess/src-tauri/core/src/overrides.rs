@@ -0,0 +1,295 @@
+//! Environment variable and command line overrides layered over the config file
+//! (file < environment < flags), so the app can be scripted and run in containers.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub struct OverrideSpec {
+    /// Dotted path of the setting, e.g. `proxy.url`.
+    pub key: &'static str,
+    pub env: &'static str,
+    pub flag: &'static str,
+    /// A bare boolean flag means `true` and never takes the next argument as its value, which
+    /// may be a deep link or a file to open. `--flag=false` turns it off.
+    pub boolean: bool,
+}
+
+pub const OVERRIDES: &[OverrideSpec] = &[
+    OverrideSpec {
+        key: "active_profile",
+        env: "EHYAIOESS_PROFILE",
+        flag: "--profile",
+        boolean: false,
+    },
+    OverrideSpec {
+        key: "conversation_history_save_path",
+        env: "EHYAIOESS_HISTORY_PATH",
+        flag: "--history-path",
+        boolean: false,
+    },
+    OverrideSpec {
+        key: "proxy.url",
+        env: "EHYAIOESS_PROXY_URL",
+        flag: "--proxy",
+        boolean: false,
+    },
+    OverrideSpec {
+        key: "record_requests",
+        env: "EHYAIOESS_RECORD_REQUESTS",
+        flag: "--record-requests",
+        boolean: true,
+    },
+    OverrideSpec {
+        key: "event_bridge.enabled",
+        env: "EHYAIOESS_EVENT_BRIDGE",
+        flag: "--event-bridge",
+        boolean: true,
+    },
+    OverrideSpec {
+        key: "event_bridge.address",
+        env: "EHYAIOESS_EVENT_BRIDGE_ADDRESS",
+        flag: "--event-bridge-address",
+        boolean: false,
+    },
+    OverrideSpec {
+        key: "locale",
+        env: "EHYAIOESS_LOCALE",
+        flag: "--locale",
+        boolean: false,
+    },
+    OverrideSpec {
+        key: "model",
+        env: "EHYAIOESS_MODEL",
+        flag: "--model",
+        boolean: false,
+    },
+    OverrideSpec {
+        key: "read_only",
+        env: "EHYAIOESS_READ_ONLY",
+        flag: "--read-only",
+        boolean: true,
+    },
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    Default,
+    File,
+    Env,
+    Flag,
+}
+
+#[derive(Debug, Clone)]
+struct Override {
+    key: &'static str,
+    raw: String,
+    source: ConfigSource,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    overrides: Vec<Override>,
+}
+
+/// A setting's value after layering, with secrets masked, and where it came from.
+#[derive(Debug, Clone)]
+pub struct EffectiveValue {
+    pub key: String,
+    pub value: Value,
+    pub source: ConfigSource,
+}
+
+fn pointer(key: &str) -> String {
+    format!("/{}", key.replace('.', "/"))
+}
+
+/// Value of `--flag value` or `--flag=value`. A bare boolean flag counts as `true`, as does
+/// any other flag followed by another flag (or nothing).
+fn flag_value(args: &[String], spec: &OverrideSpec) -> Option<String> {
+    let mut found = None;
+    for (i, arg) in args.iter().enumerate() {
+        if let Some(value) = arg
+            .strip_prefix(spec.flag)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            found = Some(value.to_string());
+        } else if arg == spec.flag {
+            found = Some(match args.get(i + 1) {
+                Some(next) if !spec.boolean && !next.starts_with("--") => next.clone(),
+                _ => "true".to_string(),
+            });
+        }
+    }
+    found
+}
+
+fn parse_bool(raw: &str) -> Option<bool> {
+    match raw.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+impl ConfigOverrides {
+    /// Overrides from `env` and `args`, where flags win over environment variables.
+    /// Unrelated arguments, such as deep links, are ignored.
+    pub fn collect(env: impl Fn(&str) -> Option<String>, args: &[String]) -> Self {
+        let overrides = OVERRIDES
+            .iter()
+            .filter_map(|spec| {
+                let (raw, source) = match flag_value(args, spec) {
+                    Some(raw) => (raw, ConfigSource::Flag),
+                    None => (env(spec.env)?, ConfigSource::Env),
+                };
+                Some(Override {
+                    key: spec.key,
+                    raw,
+                    source,
+                })
+            })
+            .collect();
+        Self { overrides }
+    }
+
+    /// Overrides from the process environment and command line.
+    pub fn from_process() -> Self {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        Self::collect(|name| std::env::var(name).ok(), &args)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    /// Pointers of the overridden settings.
+    pub fn pointers(&self) -> impl Iterator<Item = String> + '_ {
+        self.overrides.iter().map(|o| pointer(o.key))
+    }
+
+    /// Writes the overrides into a serialized config, converting each to the type
+    /// the setting already has.
+    pub fn apply(&self, config: &mut Value) -> Result<(), String> {
+        for o in &self.overrides {
+            let Some(target) = config.pointer_mut(&pointer(o.key)) else {
+                return Err(format!("Unknown setting {}", o.key));
+            };
+            *target = match target {
+                Value::Bool(_) => Value::Bool(
+                    parse_bool(&o.raw).ok_or_else(|| format!("{} must be true or false", o.key))?,
+                ),
+                Value::Number(_) => serde_json::from_str::<serde_json::Number>(&o.raw)
+                    .map(Value::Number)
+                    .map_err(|_| format!("{} must be a number", o.key))?,
+                _ => Value::String(o.raw.clone()),
+            };
+        }
+        Ok(())
+    }
+
+    pub fn source_of(&self, key: &str) -> Option<ConfigSource> {
+        self.overrides
+            .iter()
+            .find(|o| o.key == key)
+            .map(|o| o.source)
+    }
+}
+
+fn flatten(prefix: &str, value: &Value, leaves: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(fields) if !fields.is_empty() => {
+            for (name, field) in fields {
+                let key = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+                flatten(&key, field, leaves);
+            }
+        }
+        _ => leaves.push((prefix.to_string(), value.clone())),
+    }
+}
+
+/// Every setting of `effective` (already redacted) with its source. `file` is the config file
+/// as written, telling file values apart from defaults.
+pub fn effective_values(
+    effective: &Value,
+    file: &Value,
+    overrides: &ConfigOverrides,
+) -> Vec<EffectiveValue> {
+    let mut leaves = Vec::new();
+    flatten("", effective, &mut leaves);
+    leaves
+        .into_iter()
+        .map(|(key, value)| {
+            let source = overrides.source_of(&key).unwrap_or_else(|| {
+                if file.pointer(&pointer(&key)).is_some() {
+                    ConfigSource::File
+                } else {
+                    ConfigSource::Default
+                }
+            });
+            EffectiveValue { key, value, source }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_layering() {
+        let env = |name: &str| match name {
+            "EHYAIOESS_RECORD_REQUESTS" => Some("yes".to_string()),
+            "EHYAIOESS_LOCALE" => Some("en".to_string()),
+            _ => None,
+        };
+        let args: Vec<String> = ["ehyaioess://new", "--locale=de", "--proxy", "socks5://x:1"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let overrides = ConfigOverrides::collect(env, &args);
+
+        let file = serde_json::json!({
+            "record_requests": false,
+            "locale": "en",
+            "proxy": {"url": null},
+        });
+        let mut config = serde_json::json!({
+            "record_requests": false,
+            "locale": "en",
+            "proxy": {"url": null},
+            "active_profile": "default",
+        });
+        overrides.apply(&mut config).unwrap();
+        assert_eq!(config["record_requests"], true);
+        assert_eq!(config["locale"], "de");
+        assert_eq!(config["proxy"]["url"], "socks5://x:1");
+
+        let values = effective_values(&config, &file, &overrides);
+        let source = |key: &str| values.iter().find(|v| v.key == key).unwrap().source;
+        assert_eq!(source("record_requests"), ConfigSource::Env);
+        assert_eq!(source("locale"), ConfigSource::Flag);
+        assert_eq!(source("proxy.url"), ConfigSource::Flag);
+        assert_eq!(source("active_profile"), ConfigSource::Default);
+
+        let bad = ConfigOverrides::collect(|_| None, &["--record-requests=maybe".to_string()]);
+        assert!(bad.apply(&mut config).is_err());
+    }
+
+    #[test]
+    fn test_boolean_flag_leaves_next_argument() {
+        let args: Vec<String> = ["--record-requests", "ehyaioess://new", "--read-only=false"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let overrides = ConfigOverrides::collect(|_| None, &args);
+        let mut config = serde_json::json!({ "record_requests": false, "read_only": true });
+        overrides.apply(&mut config).unwrap();
+        assert_eq!(config["record_requests"], true);
+        assert_eq!(config["read_only"], false);
+    }
+}
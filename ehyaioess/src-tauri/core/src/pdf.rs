@@ -0,0 +1,180 @@
+//! PDF transcripts rendered with the PDF base fonts, so no font files need to ship with the app.
+//! The base fonts only cover Windows-1252; characters outside it are dropped from the output.
+
+use std::{fs::File, io::BufWriter};
+
+use chrono::TimeZone;
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference,
+};
+
+use crate::{
+    export::role_label,
+    models::{Conversation, ConversationEvent},
+};
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const PT_TO_MM: f32 = 0.3528;
+/// Conservative characters per line for the text width at each font size.
+/// Courier is monospaced (0.6 em); Helvetica averages a little narrower.
+const PROSE_CHARS_PER_LINE: usize = 92;
+const CODE_CHARS_PER_LINE: usize = 88;
+
+#[derive(Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+    Mono,
+}
+
+struct Fonts {
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    mono: IndirectFontRef,
+}
+
+struct Writer {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    fonts: Fonts,
+    y: f32,
+}
+
+impl Writer {
+    fn line(&mut self, text: &str, font: Font, size: f32) {
+        let height = size * PT_TO_MM * 1.35;
+        if self.y - height < MARGIN {
+            let (page, layer) = self
+                .doc
+                .add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+        self.y -= height;
+        let font = match font {
+            Font::Regular => &self.fonts.regular,
+            Font::Bold => &self.fonts.bold,
+            Font::Mono => &self.fonts.mono,
+        };
+        self.layer
+            .use_text(text, size, Mm(MARGIN), Mm(self.y), font);
+    }
+
+    fn gap(&mut self, mm: f32) {
+        self.y -= mm;
+    }
+}
+
+/// Greedy word wrap; words longer than a line are split.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split(' ') {
+        let mut word: Vec<char> = word.chars().collect();
+        while word.len() > width {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            lines.push(word.drain(..width).collect());
+        }
+        let word: String = word.into_iter().collect();
+        let current_len = current.chars().count();
+        if current_len > 0 && current_len + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(&word);
+    }
+    lines.push(current);
+    lines
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    chrono::Utc
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+fn write_content(writer: &mut Writer, content: &str) {
+    let mut in_code = false;
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            writer.gap(1.0);
+            continue;
+        }
+        if in_code {
+            let line = line.replace('\t', "    ");
+            for wrapped in wrap(&line, CODE_CHARS_PER_LINE) {
+                writer.line(&wrapped, Font::Mono, 9.0);
+            }
+        } else if let Some(heading) = line.strip_prefix('#') {
+            writer.line(heading.trim_start_matches('#').trim(), Font::Bold, 11.0);
+        } else {
+            for wrapped in wrap(line, PROSE_CHARS_PER_LINE) {
+                writer.line(&wrapped, Font::Regular, 10.0);
+            }
+        }
+    }
+}
+
+pub fn conversation_to_pdf(
+    conversation: &Conversation,
+    path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let title = conversation.get_title().into_owned();
+    let (doc, page, layer) =
+        PdfDocument::new(title.as_str(), Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+    let fonts = Fonts {
+        regular: doc.add_builtin_font(BuiltinFont::Helvetica)?,
+        bold: doc.add_builtin_font(BuiltinFont::HelveticaBold)?,
+        mono: doc.add_builtin_font(BuiltinFont::Courier)?,
+    };
+    let layer = doc.get_page(page).get_layer(layer);
+    let mut writer = Writer {
+        doc,
+        layer,
+        fonts,
+        y: PAGE_HEIGHT - MARGIN,
+    };
+
+    for line in wrap(&title, 60) {
+        writer.line(&line, Font::Bold, 18.0);
+    }
+    writer.gap(4.0);
+
+    for record in &conversation.history {
+        if let ConversationEvent::MessageAdded(msg) = &record.event {
+            let heading = format!(
+                "{}  -  {}",
+                role_label(msg.author),
+                format_timestamp(record.timestamp)
+            );
+            writer.line(&heading, Font::Bold, 11.0);
+            writer.gap(1.0);
+            write_content(&mut writer, &msg.content);
+            writer.gap(5.0);
+        }
+    }
+
+    writer.doc.save(&mut BufWriter::new(File::create(path)?))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("aa bb cc", 5), vec!["aa bb", "cc"]);
+        assert_eq!(wrap("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert_eq!(wrap("", 3), vec![""]);
+    }
+}
@@ -0,0 +1,178 @@
+//! Response style presets: named instructions added to the system prompt, with the sampling
+//! parameters that suit them, such as "concise" or "code-only". The built-in presets are
+//! where the list starts and can be edited or deleted like the user's own.
+
+use std::{io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{compression, provider::Sampling};
+
+/// The highest temperature the provider accepts.
+pub const MAX_TEMPERATURE: f32 = 2.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StylePreset {
+    /// Identifies the preset, compared ignoring case.
+    pub name: String,
+    /// Added to the system prompt of conversations using the preset.
+    pub instruction: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Caps the length of replies, in tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+impl StylePreset {
+    pub fn sampling(&self) -> Sampling {
+        Sampling {
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+        }
+    }
+}
+
+fn preset(
+    name: &str,
+    instruction: &str,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+) -> StylePreset {
+    StylePreset {
+        name: name.to_string(),
+        instruction: instruction.to_string(),
+        temperature,
+        max_tokens,
+    }
+}
+
+/// The presets a new install starts with.
+pub fn built_in_presets() -> Vec<StylePreset> {
+    vec![
+        preset(
+            "concise",
+            "Answer as briefly as possible: a sentence or two, or a short list. Leave out \
+             introductions, caveats and summaries.",
+            Some(0.3),
+            Some(300),
+        ),
+        preset(
+            "detailed",
+            "Answer thoroughly: explain the reasoning step by step, cover edge cases and \
+             alternatives, and give examples.",
+            Some(0.7),
+            None,
+        ),
+        preset(
+            "ELI5",
+            "Explain like I'm five: use simple words, short sentences and everyday analogies, \
+             and avoid jargon.",
+            Some(0.8),
+            None,
+        ),
+        preset(
+            "code-only",
+            "Reply with code only, in a single fenced code block, with no explanation outside \
+             of code comments.",
+            Some(0.2),
+            None,
+        ),
+    ]
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StylePresets {
+    /// Built-in presets first, then the user's in the order they were created.
+    pub presets: Vec<StylePreset>,
+}
+
+impl Default for StylePresets {
+    fn default() -> Self {
+        Self {
+            presets: built_in_presets(),
+        }
+    }
+}
+
+impl StylePresets {
+    /// The presets at `path`, the built-in ones when there's no file yet. A file that can't be
+    /// read is an error rather than the built-in presets, so saving doesn't overwrite it.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        match compression::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path, compress: bool) -> io::Result<()> {
+        compression::write_with(path, compress, |file| {
+            serde_json::to_writer(file, self).map_err(io::Error::from)
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&StylePreset> {
+        self.presets
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Replaces the preset named `name`, which `preset` may rename. Returns false when there's
+    /// none.
+    pub fn replace(&mut self, name: &str, preset: StylePreset) -> bool {
+        match self
+            .presets
+            .iter_mut()
+            .find(|existing| existing.name.eq_ignore_ascii_case(name))
+        {
+            Some(existing) => {
+                *existing = preset;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<StylePreset> {
+        let index = self
+            .presets
+            .iter()
+            .position(|preset| preset.name.eq_ignore_ascii_case(name))?;
+        Some(self.presets.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        let path = std::env::temp_dir().join(format!("presets-{}.json", uuid::Uuid::new_v4()));
+        let mut presets = StylePresets::open(&path).unwrap();
+        assert_eq!(presets.presets, built_in_presets());
+        assert_eq!(presets.get("eli5").unwrap().name, "ELI5");
+        assert_eq!(
+            presets.get("concise").unwrap().sampling(),
+            Sampling {
+                temperature: Some(0.3),
+                max_tokens: Some(300),
+            }
+        );
+
+        let mut renamed = presets.get("detailed").unwrap().clone();
+        renamed.name = "thorough".to_string();
+        assert!(presets.replace("Detailed", renamed));
+        assert!(presets.get("detailed").is_none());
+        assert!(!presets.replace("missing", built_in_presets().remove(0)));
+        assert_eq!(presets.remove("code-only").unwrap().name, "code-only");
+        presets.save(&path, false).unwrap();
+
+        // edits and deletions stick, rather than the built-in presets coming back
+        let reopened = StylePresets::open(&path).unwrap();
+        assert_eq!(reopened.presets, presets.presets);
+        assert!(reopened.get("code-only").is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
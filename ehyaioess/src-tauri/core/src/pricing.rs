@@ -0,0 +1,135 @@
+//! Estimated cost of requests from list prices. Only an estimate: prices change and the
+//! provider's bill is what counts, so any price can be overridden in the config.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::provider;
+
+/// USD per million prompt and completion tokens, by model name prefix; see `price`.
+const PRICES_PER_MILLION: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4-turbo", 10.00, 30.00),
+    ("gpt-4-32k", 60.00, 120.00),
+    ("gpt-4", 30.00, 60.00),
+    ("gpt-3.5-turbo", 0.50, 1.50),
+    ("text-embedding-ada-002", 0.10, 0.0),
+    (provider::MOCK_MODEL, 0.0, 0.0),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    /// USD per million prompt tokens.
+    pub prompt: f64,
+    /// USD per million completion tokens.
+    pub completion: f64,
+}
+
+/// Prices from the config by model name prefix. The longest prefix matching a model wins,
+/// whether built in or overridden, so overriding `gpt-4o` leaves `gpt-4o-mini` as it was.
+pub type PriceOverrides = BTreeMap<String, ModelPrice>;
+
+/// A row of the effective price table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceEntry {
+    pub prefix: String,
+    pub price: ModelPrice,
+    pub overridden: bool,
+}
+
+/// The price of `model`, or None for a model without a known price.
+pub fn price(overrides: &PriceOverrides, model: &str) -> Option<ModelPrice> {
+    table(overrides)
+        .into_iter()
+        .filter(|entry| model.starts_with(&entry.prefix))
+        .max_by_key(|entry| entry.prefix.len())
+        .map(|entry| entry.price)
+}
+
+/// Estimated cost in USD, or None for a model without a known price.
+pub fn estimate_cost(
+    overrides: &PriceOverrides,
+    model: &str,
+    prompt_tokens: u32,
+    completion_tokens: u32,
+) -> Option<f64> {
+    let price = price(overrides, model)?;
+    Some(
+        (f64::from(prompt_tokens) * price.prompt + f64::from(completion_tokens) * price.completion)
+            / 1_000_000.0,
+    )
+}
+
+/// The built-in prices with the overrides in their place, followed by overrides for models
+/// the table doesn't have, each sorted by prefix.
+pub fn table(overrides: &PriceOverrides) -> Vec<PriceEntry> {
+    let mut entries: Vec<PriceEntry> = PRICES_PER_MILLION
+        .iter()
+        .map(|(prefix, prompt, completion)| match overrides.get(*prefix) {
+            Some(price) => PriceEntry {
+                prefix: prefix.to_string(),
+                price: *price,
+                overridden: true,
+            },
+            None => PriceEntry {
+                prefix: prefix.to_string(),
+                price: ModelPrice {
+                    prompt: *prompt,
+                    completion: *completion,
+                },
+                overridden: false,
+            },
+        })
+        .collect();
+    entries.sort_by(|a, b| a.prefix.cmp(&b.prefix));
+    entries.extend(
+        overrides
+            .iter()
+            .filter(|(prefix, _)| !PRICES_PER_MILLION.iter().any(|(p, _, _)| p == *prefix))
+            .map(|(prefix, price)| PriceEntry {
+                prefix: prefix.clone(),
+                price: *price,
+                overridden: true,
+            }),
+    );
+    entries
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_estimate_cost() {
+        let cost = |model| estimate_cost(&PriceOverrides::new(), model, 1_000_000, 1_000_000);
+        assert_eq!(cost("gpt-3.5-turbo-0125"), Some(2.0));
+        assert_eq!(cost("gpt-4o-mini-2024-07-18"), Some(0.75));
+        assert_eq!(cost("gpt-4o"), Some(12.5));
+        assert_eq!(cost("gpt-4-0613"), Some(90.0));
+        assert_eq!(cost(provider::MOCK_MODEL), Some(0.0));
+        assert_eq!(cost("some-local-model"), None);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overrides = PriceOverrides::from([
+            ("gpt-4o".to_string(), ModelPrice { prompt: 2.0, completion: 8.0 }),
+            ("llama".to_string(), ModelPrice { prompt: 0.0, completion: 0.0 }),
+        ]);
+        let cost = |model| estimate_cost(&overrides, model, 1_000_000, 1_000_000);
+        assert_eq!(cost("gpt-4o-2024-08-06"), Some(10.0));
+        // the built-in price for the more specific prefix still applies
+        assert_eq!(cost("gpt-4o-mini"), Some(0.75));
+        assert_eq!(cost("llama3"), Some(0.0));
+        assert_eq!(cost("gpt-4-0613"), Some(90.0));
+
+        let table = table(&overrides);
+        let gpt4o = table.iter().find(|entry| entry.prefix == "gpt-4o").unwrap();
+        assert!(gpt4o.overridden);
+        assert_eq!(gpt4o.price.completion, 8.0);
+        assert_eq!(table.last().unwrap().prefix, "llama");
+        assert_eq!(table.iter().filter(|entry| entry.overridden).count(), 2);
+    }
+}
@@ -0,0 +1,239 @@
+use std::{future::Future, sync::Arc};
+
+use chatgpt::types::ChatMessage;
+
+use crate::{
+    config::Config,
+    provider::{self, CompletionResponse, OpenAIClient, ProviderError, Sampling},
+    recorder::RequestRecorder,
+};
+
+/// One provider client per configured API key profile, in config order.
+pub struct ProfileClients {
+    clients: Vec<(String, OpenAIClient)>,
+}
+
+impl ProfileClients {
+    pub fn from_config(
+        config: &Config,
+        recorder: Arc<RequestRecorder>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        // All profiles share one HTTP client so they go through the same proxy.
+        let http = provider::build_untimed_http_client(&config.proxy)?;
+        let clients = config
+            .api_key_profiles
+            .iter()
+            .map(|profile| {
+                (
+                    profile.name.clone(),
+                    OpenAIClient::new(http.clone(), &profile.openai_api_key, recorder.clone()),
+                )
+            })
+            .collect();
+        Ok(Self { clients })
+    }
+
+    /// Points every profile at another OpenAI-compatible API; see `OpenAIClient::with_api_base`.
+    pub fn with_api_base(self, api_base: &str) -> Self {
+        let clients = self
+            .clients
+            .into_iter()
+            .map(|(name, client)| (name, client.with_api_base(api_base)))
+            .collect();
+        Self { clients }
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.clients.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.clients.iter().any(|(n, _)| n == name)
+    }
+
+    /// The preferred profile first, followed by the rest in config order to fail over to.
+    pub fn failover_order<'a>(&'a self, preferred: &'a str) -> impl Iterator<Item = (&'a str, &'a OpenAIClient)> {
+        let preferred_client = self.clients.iter().filter(move |(name, _)| name == preferred);
+        let others = self.clients.iter().filter(move |(name, _)| name != preferred);
+        preferred_client
+            .chain(others)
+            .map(|(name, client)| (name.as_str(), client))
+    }
+
+    /// Calls the preferred profile's client, moving on to the next profile whenever the
+    /// current key is rejected or out of quota.
+    pub async fn with_failover<'a, T, F, Fut>(
+        &'a self,
+        preferred: &'a str,
+        mut call: F,
+    ) -> Result<T, ProviderError>
+    where
+        F: FnMut(&'a OpenAIClient) -> Fut,
+        Fut: Future<Output = Result<T, ProviderError>>,
+    {
+        let mut last_error = ProviderError::NoProfiles;
+        for (profile_name, client) in self.failover_order(preferred) {
+            match call(client).await {
+                Ok(value) => return Ok(value),
+                Err(e) if e.is_credential_error() => {
+                    eprintln!("Profile {} failed ({}), failing over", profile_name, e);
+                    last_error = e;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Calls `with_failover` with each of `models` in turn (see `Config::model_chain`), moving
+    /// on to the next model whenever the model is unavailable or the history overflows its
+    /// context. Once every key is refused, another model won't help. The response names the
+    /// model that answered.
+    async fn with_model_failover<'a, F, Fut>(
+        &'a self,
+        preferred: &'a str,
+        models: &'a [String],
+        mut call: F,
+    ) -> Result<CompletionResponse, ProviderError>
+    where
+        F: FnMut(&'a OpenAIClient, &'a str) -> Fut,
+        Fut: Future<Output = Result<CompletionResponse, ProviderError>>,
+    {
+        let mut last_error = ProviderError::NoProfiles;
+        for model in models {
+            match self
+                .with_failover(preferred, |client| call(client, model))
+                .await
+            {
+                Ok(response) => return Ok(answered_by(response, model)),
+                Err(e) if e.is_model_error() => {
+                    eprintln!("Model {} failed ({}), falling back", model, e);
+                    last_error = e;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Sends the history with the preferred profile, failing over to the other profiles and
+    /// to the next of `models`; see `with_model_failover`.
+    pub async fn send_with_failover(
+        &self,
+        preferred: &str,
+        models: &[String],
+        history: &[ChatMessage],
+        stop: &[String],
+        sampling: Sampling,
+    ) -> Result<CompletionResponse, ProviderError> {
+        self.with_model_failover(preferred, models, |client, model| {
+            client.send_history(model, history, stop, sampling)
+        })
+        .await
+    }
+
+    /// Streams the reply, failing over like `send_with_failover`. A reply that breaks off
+    /// midway isn't retried with another profile or model; see `ProviderError::Interrupted`.
+    pub async fn stream_with_failover(
+        &self,
+        preferred: &str,
+        models: &[String],
+        history: &[ChatMessage],
+        stop: &[String],
+        sampling: Sampling,
+    ) -> Result<CompletionResponse, ProviderError> {
+        self.with_model_failover(preferred, models, |client, model| {
+            client.stream_history(model, history, stop, sampling)
+        })
+        .await
+    }
+
+    /// Lists the models with the preferred profile, failing over like `with_failover`.
+    pub async fn list_models_with_failover(
+        &self,
+        preferred: &str,
+    ) -> Result<Vec<(String, String)>, ProviderError> {
+        self.with_failover(preferred, |client| client.list_models())
+            .await
+    }
+
+    /// Embeds the inputs, failing over like `with_failover`.
+    pub async fn embed_with_failover(
+        &self,
+        preferred: &str,
+        inputs: &[String],
+    ) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.with_failover(preferred, |client| client.embed(inputs))
+            .await
+    }
+
+    /// Speaks the text, failing over like `with_failover`.
+    pub async fn speak_with_failover(
+        &self,
+        preferred: &str,
+        text: &str,
+        speed: f32,
+    ) -> Result<Vec<u8>, ProviderError> {
+        self.with_failover(preferred, |client| client.speak(text, speed))
+            .await
+    }
+
+    /// Transcribes the audio, failing over like `with_failover`.
+    pub async fn transcribe_with_failover(
+        &self,
+        preferred: &str,
+        wav: &[u8],
+        prompt: &str,
+        language: Option<&str>,
+    ) -> Result<String, ProviderError> {
+        self.with_failover(preferred, |client| {
+            client.transcribe(wav.to_vec(), prompt, language)
+        })
+        .await
+    }
+}
+
+/// `response` with `model` as the model that answered, for APIs that don't say.
+fn answered_by(mut response: CompletionResponse, model: &str) -> CompletionResponse {
+    if response.model.is_empty() {
+        response.model = model.to_string();
+    }
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn clients(names: &[&str]) -> ProfileClients {
+        let profiles: Vec<_> = names
+            .iter()
+            .map(|name| serde_json::json!({ "name": name, "openai_api_key": format!("sk-{}", name) }))
+            .collect();
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "conversation_history_save_path": "h.json",
+            "api_key_profiles": profiles,
+        }))
+        .unwrap();
+        let log = std::env::temp_dir().join(format!("profiles-{}.jsonl", uuid::Uuid::new_v4()));
+        ProfileClients::from_config(&config, Arc::new(RequestRecorder::new(log, false))).unwrap()
+    }
+
+    fn order(clients: &ProfileClients, preferred: &str) -> Vec<String> {
+        clients
+            .failover_order(preferred)
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_failover_order() {
+        let clients = clients(&["personal", "work", "spare"]);
+        assert_eq!(order(&clients, "work"), ["work", "personal", "spare"]);
+        assert_eq!(order(&clients, "personal"), ["personal", "work", "spare"]);
+        // An unknown or missing preferred profile leaves config order.
+        assert_eq!(order(&clients, "deleted"), ["personal", "work", "spare"]);
+        assert_eq!(order(&clients, ""), ["personal", "work", "spare"]);
+        assert!(order(&self::clients(&[]), "work").is_empty());
+    }
+}
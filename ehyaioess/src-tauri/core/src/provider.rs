@@ -0,0 +1,766 @@
+use core::fmt;
+use std::{sync::Arc, time::Duration};
+
+use chatgpt::types::{ChatMessage, Role};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    config::ProxyConfig,
+    i18n,
+    recorder::{self, RequestRecord, RequestRecorder},
+};
+
+pub const OPENAI_API_BASE: &str = "https://api.openai.com/v1";
+pub const DEFAULT_MODEL: &str = "gpt-3.5-turbo";
+pub const EMBEDDING_MODEL: &str = "text-embedding-ada-002";
+pub const TRANSCRIPTION_MODEL: &str = "whisper-1";
+pub const SPEECH_MODEL: &str = "tts-1";
+pub const SPEECH_VOICE: &str = "alloy";
+/// Samples per second of the speech `speak` returns.
+pub const SPEECH_SAMPLE_RATE: u32 = 24_000;
+pub const MOCK_MODEL: &str = "mock";
+/// How long a request may take as a whole, except for streamed replies.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a streamed reply may go without sending anything before it's given up on.
+const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub enum ProviderError {
+    Http(reqwest::Error),
+    Api {
+        status: u16,
+        error_type: String,
+        /// The API's machine-readable `code`, e.g. `model_not_found`; empty when it sent none.
+        code: String,
+        message: String,
+    },
+    Parse(String),
+    NoProfiles,
+    /// A streamed reply sent nothing for `STREAM_IDLE_TIMEOUT`.
+    Stalled,
+    /// A streamed reply broke off after `partial` had already arrived.
+    Interrupted {
+        partial: String,
+        model: String,
+        error: Box<ProviderError>,
+    },
+}
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProviderError::Http(e) => write!(f, "HTTP error: {}", e),
+            ProviderError::Api {
+                status,
+                error_type,
+                message,
+                ..
+            } => write!(f, "API error {} ({}): {}", status, error_type, message),
+            ProviderError::Parse(e) => write!(f, "Failed to parse response: {}", e),
+            ProviderError::NoProfiles => write!(f, "No API key profiles are configured"),
+            ProviderError::Stalled => write!(
+                f,
+                "The provider sent nothing for {} seconds",
+                STREAM_IDLE_TIMEOUT.as_secs()
+            ),
+            ProviderError::Interrupted { partial, error, .. } => write!(
+                f,
+                "Reply interrupted after {} characters: {}",
+                partial.chars().count(),
+                error
+            ),
+        }
+    }
+}
+impl std::error::Error for ProviderError {}
+
+impl ProviderError {
+    /// Whether the error is tied to the key itself (bad credentials, exhausted quota)
+    /// so that retrying with a different profile might succeed.
+    pub fn is_credential_error(&self) -> bool {
+        match self {
+            ProviderError::Api {
+                status, error_type, ..
+            } => {
+                *status == 401
+                    || *status == 403
+                    || error_type == "insufficient_quota"
+                    || error_type == "invalid_api_key"
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the model can't take the request at all, being unavailable to the key or
+    /// given more history than fits its context, so that another model might answer.
+    pub fn is_model_error(&self) -> bool {
+        match self {
+            ProviderError::Api { code, .. } => {
+                code == "model_not_found" || code == "context_length_exceeded"
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Builds the HTTP client used for every provider request, routed through the configured proxy.
+pub fn build_http_client(proxy: &ProxyConfig) -> Result<reqwest::Client, reqwest::Error> {
+    http_client_builder(proxy)?.timeout(REQUEST_TIMEOUT).build()
+}
+
+/// Like `build_http_client`, but without a limit on how long a whole request takes, for
+/// `OpenAIClient`, which limits each request itself so streamed replies can run long.
+pub fn build_untimed_http_client(proxy: &ProxyConfig) -> Result<reqwest::Client, reqwest::Error> {
+    http_client_builder(proxy)?.build()
+}
+
+fn http_client_builder(proxy: &ProxyConfig) -> Result<reqwest::ClientBuilder, reqwest::Error> {
+    let mut builder = reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT);
+    if let Some(url) = proxy.url.as_deref().filter(|url| !url.trim().is_empty()) {
+        let mut reqwest_proxy = reqwest::Proxy::all(url.trim())?;
+        if let Some(username) = &proxy.username {
+            reqwest_proxy =
+                reqwest_proxy.basic_auth(username, proxy.password.as_deref().unwrap_or(""));
+        }
+        if !proxy.no_proxy.is_empty() {
+            reqwest_proxy =
+                reqwest_proxy.no_proxy(reqwest::NoProxy::from_string(&proxy.no_proxy.join(",")));
+        }
+        builder = builder.proxy(reqwest_proxy);
+    }
+    Ok(builder)
+}
+
+#[derive(Debug, Serialize)]
+struct CompletionRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    stop: &'a [String],
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+    #[serde(flatten)]
+    sampling: Sampling,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    /// Has the last event carry the token usage, which streamed replies otherwise lack.
+    include_usage: bool,
+}
+
+/// Sampling parameters for a completion; unset ones are left to the provider's defaults.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Default)]
+pub struct Sampling {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+/// The most stop sequences the completion API accepts.
+pub const MAX_STOP_SEQUENCES: usize = 4;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CompletionChoice {
+    pub message: ChatMessage,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CompletionResponse {
+    #[serde(default)]
+    pub model: String,
+    pub choices: Vec<CompletionChoice>,
+    pub usage: Option<TokenUsage>,
+}
+impl CompletionResponse {
+    pub fn message(&self) -> Option<&ChatMessage> {
+        self.choices.first().map(|choice| &choice.message)
+    }
+}
+
+/// The canned reply that stands in for the provider in read-only mode, so the app can be
+/// demoed without an API key or spending anything.
+pub fn mock_completion() -> CompletionResponse {
+    CompletionResponse {
+        model: MOCK_MODEL.to_string(),
+        choices: vec![CompletionChoice {
+            message: ChatMessage {
+                role: Role::Assistant,
+                content: i18n::t("provider.mock_reply"),
+            },
+        }],
+        usage: None,
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct StreamDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: StreamDelta,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    usage: Option<TokenUsage>,
+}
+
+/// Assembles a reply from the server-sent events of a streamed completion, which may arrive
+/// split at any byte.
+#[derive(Debug, Default)]
+struct StreamAccumulator {
+    /// Bytes of the line still being received.
+    pending: Vec<u8>,
+    model: String,
+    content: String,
+    usage: Option<TokenUsage>,
+    done: bool,
+}
+
+impl StreamAccumulator {
+    fn push(&mut self, bytes: &[u8]) -> Result<(), ProviderError> {
+        self.pending.extend_from_slice(bytes);
+        while let Some(end) = self.pending.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.push_line(String::from_utf8_lossy(&line).trim_end())?;
+        }
+        Ok(())
+    }
+
+    fn push_line(&mut self, line: &str) -> Result<(), ProviderError> {
+        let Some(data) = line.strip_prefix("data:") else {
+            // Blank separators, comments and other fields carry nothing of the reply.
+            return Ok(());
+        };
+        let data = data.trim();
+        if data == "[DONE]" {
+            self.done = true;
+            return Ok(());
+        }
+        let chunk: StreamChunk =
+            serde_json::from_str(data).map_err(|e| ProviderError::Parse(e.to_string()))?;
+        if self.model.is_empty() {
+            self.model = chunk.model;
+        }
+        for choice in chunk.choices {
+            self.content.push_str(choice.delta.content.as_deref().unwrap_or_default());
+        }
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
+        }
+        Ok(())
+    }
+
+    /// `error` as it stands after what arrived so far, keeping any partial reply.
+    fn interrupted(self, error: ProviderError) -> ProviderError {
+        if self.content.is_empty() {
+            return error;
+        }
+        ProviderError::Interrupted {
+            partial: self.content,
+            model: self.model,
+            error: Box::new(error),
+        }
+    }
+
+    fn finish(self) -> CompletionResponse {
+        CompletionResponse {
+            model: self.model,
+            choices: vec![CompletionChoice {
+                message: ChatMessage {
+                    role: Role::Assistant,
+                    content: self.content,
+                },
+            }],
+            usage: self.usage,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    index: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+/// What's recorded of a transcription request; the audio itself is sent as a file.
+#[derive(Debug, Serialize)]
+struct TranscriptionRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<&'a str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SpeechRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+    voice: &'a str,
+    response_format: &'a str,
+    speed: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListedModel {
+    id: String,
+    #[serde(default)]
+    owned_by: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelListing {
+    data: Vec<ListedModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorBody {
+    error: ApiErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorDetail {
+    message: String,
+    #[serde(rename = "type", default)]
+    error_type: String,
+    #[serde(default)]
+    code: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct OpenAIClient {
+    /// Built by `build_untimed_http_client`; see `exchange` and `stream_history` for the
+    /// timeouts.
+    http: reqwest::Client,
+    api_key: String,
+    recorder: Arc<RequestRecorder>,
+    api_base: String,
+}
+
+impl OpenAIClient {
+    pub fn new(http: reqwest::Client, api_key: &str, recorder: Arc<RequestRecorder>) -> Self {
+        Self {
+            http,
+            api_key: api_key.to_string(),
+            recorder,
+            api_base: OPENAI_API_BASE.to_string(),
+        }
+    }
+
+    /// Sends requests to another OpenAI-compatible API, such as a mock server in tests.
+    pub fn with_api_base(mut self, api_base: &str) -> Self {
+        self.api_base = api_base.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Generation stops before any of the `stop` sequences.
+    pub async fn send_history(
+        &self,
+        model: &str,
+        history: &[ChatMessage],
+        stop: &[String],
+        sampling: Sampling,
+    ) -> Result<CompletionResponse, ProviderError> {
+        let request = CompletionRequest {
+            model,
+            messages: history,
+            stop,
+            stream: false,
+            stream_options: None,
+            sampling,
+        };
+        self.post("chat/completions", request.model, &request).await
+    }
+
+    /// Like `send_history`, but streams the reply so that whatever arrived before the
+    /// connection broke is kept in `ProviderError::Interrupted`.
+    pub async fn stream_history(
+        &self,
+        model: &str,
+        history: &[ChatMessage],
+        stop: &[String],
+        sampling: Sampling,
+    ) -> Result<CompletionResponse, ProviderError> {
+        let request = CompletionRequest {
+            model,
+            messages: history,
+            stop,
+            stream: true,
+            stream_options: Some(StreamOptions {
+                include_usage: true,
+            }),
+            sampling,
+        };
+        let url = format!("{}/chat/completions", self.api_base);
+        let started = std::time::Instant::now();
+        let record = |status: Option<u16>, body: Option<&str>, error: Option<&reqwest::Error>| {
+            self.record_exchange(&url, request.model, &request, status, body, error, started)
+        };
+        // Not limited as a whole like other requests, since a long reply streams for minutes;
+        // only a provider that goes quiet is given up on.
+        let sent = self
+            .http
+            .post(&url)
+            .bearer_auth(&self.api_key)
+            .json(&request)
+            .send();
+        let mut response = match tokio::time::timeout(STREAM_IDLE_TIMEOUT, sent).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => {
+                record(None, None, Some(&e));
+                return Err(ProviderError::Http(e));
+            }
+            Err(_) => {
+                record(None, None, None);
+                return Err(ProviderError::Stalled);
+            }
+        };
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            record(Some(status.as_u16()), Some(&body), None);
+            return Err(api_error(status.as_u16(), body));
+        }
+        let mut stream = StreamAccumulator::default();
+        loop {
+            let error = match tokio::time::timeout(STREAM_IDLE_TIMEOUT, response.chunk()).await {
+                Ok(Ok(Some(bytes))) => match stream.push(&bytes) {
+                    Ok(()) => continue,
+                    Err(e) => e,
+                },
+                Ok(Ok(None)) if stream.done => break,
+                Ok(Ok(None)) => {
+                    ProviderError::Parse("The reply ended before it was complete".to_string())
+                }
+                Ok(Err(e)) => {
+                    record(Some(status.as_u16()), Some(&stream.content), Some(&e));
+                    return Err(stream.interrupted(ProviderError::Http(e)));
+                }
+                Err(_) => ProviderError::Stalled,
+            };
+            record(Some(status.as_u16()), Some(&stream.content), None);
+            return Err(stream.interrupted(error));
+        }
+        record(Some(status.as_u16()), Some(&stream.content), None);
+        Ok(stream.finish())
+    }
+
+    /// One embedding vector per input, in input order.
+    pub async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let request = EmbeddingRequest {
+            model: EMBEDDING_MODEL,
+            input: inputs,
+        };
+        let mut response: EmbeddingResponse =
+            self.post("embeddings", request.model, &request).await?;
+        response.data.sort_by_key(|data| data.index);
+        if response.data.len() != inputs.len() {
+            return Err(ProviderError::Parse(format!(
+                "Expected {} embeddings, got {}",
+                inputs.len(),
+                response.data.len()
+            )));
+        }
+        Ok(response.data.into_iter().map(|data| data.embedding).collect())
+    }
+
+    /// The text spoken in `wav`, a WAV file. `prompt`, such as the transcript so far, keeps
+    /// spelling and punctuation consistent from one chunk of dictation to the next. `language`
+    /// is an ISO 639-1 code, detected from the audio when not given.
+    pub async fn transcribe(
+        &self,
+        wav: Vec<u8>,
+        prompt: &str,
+        language: Option<&str>,
+    ) -> Result<String, ProviderError> {
+        let request = TranscriptionRequest {
+            model: TRANSCRIPTION_MODEL,
+            prompt,
+            language,
+        };
+        let audio = reqwest::multipart::Part::bytes(wav)
+            .file_name("dictation.wav")
+            .mime_str("audio/wav")
+            .map_err(ProviderError::Http)?;
+        let mut form = reqwest::multipart::Form::new()
+            .text("model", TRANSCRIPTION_MODEL)
+            .text("prompt", prompt.to_string())
+            .part("file", audio);
+        if let Some(language) = language {
+            form = form.text("language", language.to_string());
+        }
+        let url = format!("{}/audio/transcriptions", self.api_base);
+        let response: TranscriptionResponse = self
+            .exchange(self.http.post(&url).multipart(form), &url, request.model, &request)
+            .await?;
+        Ok(response.text)
+    }
+
+    /// `text` spoken at `speed`, as 16-bit little-endian mono PCM at `SPEECH_SAMPLE_RATE`.
+    pub async fn speak(&self, text: &str, speed: f32) -> Result<Vec<u8>, ProviderError> {
+        let request = SpeechRequest {
+            model: SPEECH_MODEL,
+            input: text,
+            voice: SPEECH_VOICE,
+            response_format: "pcm",
+            speed,
+        };
+        let url = format!("{}/audio/speech", self.api_base);
+        let started = std::time::Instant::now();
+        let result = self
+            .http
+            .post(&url)
+            .bearer_auth(&self.api_key)
+            .json(&request)
+            .send()
+            .await;
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                self.record_exchange(&url, request.model, &request, None, None, Some(&e), started);
+                return Err(ProviderError::Http(e));
+            }
+        };
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            self.record_exchange(&url, request.model, &request, Some(status.as_u16()), Some(&body), None, started);
+            return Err(api_error(status.as_u16(), body));
+        }
+        match response.bytes().await {
+            Ok(audio) => {
+                // The audio itself isn't worth keeping in the request log.
+                let summary = format!("({} bytes of audio)", audio.len());
+                self.record_exchange(&url, request.model, &request, Some(status.as_u16()), Some(&summary), None, started);
+                Ok(audio.to_vec())
+            }
+            Err(e) => {
+                self.record_exchange(&url, request.model, &request, Some(status.as_u16()), None, Some(&e), started);
+                Err(ProviderError::Http(e))
+            }
+        }
+    }
+
+    /// `(id, owned_by)` of every model the key may use; see `catalog::ModelCatalog`.
+    pub async fn list_models(&self) -> Result<Vec<(String, String)>, ProviderError> {
+        let url = format!("{}/models", self.api_base);
+        let listing: ModelListing = self.exchange(self.http.get(&url), &url, "", &()).await?;
+        Ok(listing
+            .data
+            .into_iter()
+            .map(|model| (model.id, model.owned_by))
+            .collect())
+    }
+
+    async fn post<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        model: &str,
+        request: &impl Serialize,
+    ) -> Result<T, ProviderError> {
+        let url = format!("{}/{}", self.api_base, path);
+        self.exchange(self.http.post(&url).json(request), &url, model, request).await
+    }
+
+    /// Sends `builder`'s request to `url`, recording it with `model` and the `request` body.
+    async fn exchange<T: DeserializeOwned>(
+        &self,
+        builder: reqwest::RequestBuilder,
+        url: &str,
+        model: &str,
+        request: &impl Serialize,
+    ) -> Result<T, ProviderError> {
+        let started = std::time::Instant::now();
+        let result = builder
+            .timeout(REQUEST_TIMEOUT)
+            .bearer_auth(&self.api_key)
+            .send()
+            .await;
+        let (status, body) = match result {
+            Ok(response) => {
+                let status = response.status();
+                (status, response.text().await)
+            }
+            Err(e) => {
+                self.record_exchange(url, model, request, None, None, Some(&e), started);
+                return Err(ProviderError::Http(e));
+            }
+        };
+        let body = match body {
+            Ok(body) => body,
+            Err(e) => {
+                self.record_exchange(url, model, request, Some(status.as_u16()), None, Some(&e), started);
+                return Err(ProviderError::Http(e));
+            }
+        };
+        self.record_exchange(url, model, request, Some(status.as_u16()), Some(&body), None, started);
+        if !status.is_success() {
+            return Err(api_error(status.as_u16(), body));
+        }
+        serde_json::from_str(&body).map_err(|e| ProviderError::Parse(e.to_string()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn record_exchange(
+        &self,
+        url: &str,
+        model: &str,
+        request: &impl Serialize,
+        status: Option<u16>,
+        response_body: Option<&str>,
+        error: Option<&reqwest::Error>,
+        started: std::time::Instant,
+    ) {
+        if !self.recorder.is_enabled() {
+            return;
+        }
+        let mut params = serde_json::to_value(request).unwrap_or_default();
+        // The messages, texts to embed or transcription prompt are the body; the rest are the
+        // parameters.
+        let messages = params
+            .as_object_mut()
+            .and_then(|params| {
+                params
+                    .remove("messages")
+                    .or_else(|| params.remove("input"))
+                    .or_else(|| params.remove("prompt"))
+            })
+            .unwrap_or_default();
+        self.recorder.record(RequestRecord {
+            id: uuid::Uuid::new_v4(),
+            timestamp: chrono::Utc::now().timestamp(),
+            url: url.to_string(),
+            model: Some(model.to_string()),
+            params,
+            request_body: recorder::redact_body(&messages.to_string()),
+            status,
+            response_body: response_body.map(recorder::redact_body),
+            error: error.map(|e| e.to_string()),
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+}
+
+/// The error an unsuccessful response stands for, with the API's own message when it sent one.
+fn api_error(status: u16, body: String) -> ProviderError {
+    match serde_json::from_str::<ApiErrorBody>(&body) {
+        Ok(error_body) => ProviderError::Api {
+            status,
+            error_type: error_body.error.error_type,
+            code: error_body.error.code.unwrap_or_default(),
+            message: error_body.error.message,
+        },
+        Err(_) => ProviderError::Api {
+            status,
+            error_type: String::new(),
+            code: String::new(),
+            message: body,
+        },
+    }
+}
+
+/// Makes an unauthenticated request to the provider through the given proxy settings,
+/// returning the HTTP status if the provider could be reached at all.
+pub async fn test_connection(proxy: &ProxyConfig) -> Result<u16, ProviderError> {
+    let http = build_http_client(proxy).map_err(ProviderError::Http)?;
+    let response = http
+        .get(format!("{}/models", OPENAI_API_BASE))
+        .send()
+        .await
+        .map_err(ProviderError::Http)?;
+    Ok(response.status().as_u16())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stream_accumulator() {
+        let events = concat!(
+            "data: {\"model\":\"gpt-3.5-turbo-0125\",\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"model\":\"gpt-3.5-turbo-0125\",\"choices\":[{\"delta\":{\"content\":\"Grüß\"}}]}\r\n\r\n",
+            ": keep-alive\n\n",
+            "data: {\"model\":\"gpt-3.5-turbo-0125\",\"choices\":[{\"delta\":{\"content\":\" Gott\"}}]}\n\n",
+            "data: {\"model\":\"gpt-3.5-turbo-0125\",\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":3}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let mut stream = StreamAccumulator::default();
+        // Split mid-character, as chunks may be.
+        for bytes in events.as_bytes().chunks(7) {
+            stream.push(bytes).unwrap();
+        }
+        assert!(stream.done);
+        let response = stream.finish();
+        assert_eq!(response.model, "gpt-3.5-turbo-0125");
+        assert_eq!(response.message().unwrap().content, "Grüß Gott");
+        assert_eq!(response.usage.map(|usage| usage.completion_tokens), Some(3));
+
+        let mut stream = StreamAccumulator::default();
+        stream.push(&events.as_bytes()[..200]).unwrap();
+        assert!(!stream.done);
+        match stream.interrupted(ProviderError::Parse("cut off".to_string())) {
+            ProviderError::Interrupted { partial, model, .. } => {
+                assert_eq!(partial, "Grüß");
+                assert_eq!(model, "gpt-3.5-turbo-0125");
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        let empty = StreamAccumulator::default().interrupted(ProviderError::NoProfiles);
+        assert!(matches!(empty, ProviderError::NoProfiles));
+    }
+
+    #[test]
+    fn test_model_errors() {
+        let error = |status, body: &str| api_error(status, body.to_string());
+        let overflow = error(
+            400,
+            r#"{"error":{"message":"Too long","type":"invalid_request_error","code":"context_length_exceeded"}}"#,
+        );
+        assert!(overflow.is_model_error());
+        assert!(!overflow.is_credential_error());
+        let missing = error(
+            404,
+            r#"{"error":{"message":"No such model","type":"invalid_request_error","code":"model_not_found"}}"#,
+        );
+        assert!(missing.is_model_error());
+        let quota = error(429, r#"{"error":{"message":"Out","type":"insufficient_quota","code":null}}"#);
+        assert!(!quota.is_model_error());
+        assert!(quota.is_credential_error());
+        assert!(!error(502, "Bad gateway").is_model_error());
+    }
+}
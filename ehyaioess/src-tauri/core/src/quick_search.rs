@@ -0,0 +1,238 @@
+//! The search overlay's single result list: conversation titles, message text, bookmarks,
+//! templates and commands matched against one query and ranked together.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    bookmarks::{self, snippet},
+    fuzzy::{self, fuzzy_match},
+    models::ConversationManager,
+    search,
+    templates::ConversationTemplate,
+};
+
+/// Most results returned, across every kind.
+pub const QUICK_SEARCH_LIMIT: usize = 50;
+const SNIPPET_CHARS: usize = 120;
+/// A bookmarked message outranks the same text found anywhere else.
+const BOOKMARK_BONUS: i32 = 8;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum QuickSearchKind {
+    Conversation,
+    Template,
+    Command,
+    Bookmark,
+    Message,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuickSearchResult {
+    pub kind: QuickSearchKind,
+    /// What choosing the result opens: the conversation, message or template id, or the
+    /// command name.
+    pub id: String,
+    /// The conversation a conversation, message or bookmark result is in.
+    pub conversation_id: Option<Uuid>,
+    pub title: String,
+    /// Shown under the title: the matching text, the tag that matched or what a command does.
+    pub detail: String,
+    /// Character indices into `title` that matched, for highlighting.
+    pub indices: Vec<usize>,
+    pub score: i32,
+    /// Breaks ties between equal scores, most recent first.
+    pub timestamp: i64,
+}
+
+/// What a message or bookmark containing `query` scores: the fuzzy score of one run of
+/// matches without word-start bonuses, so a title that matches as well comes first.
+fn text_score(query: &str) -> i32 {
+    let chars = query.chars().filter(|c| !c.is_whitespace()).count() as i32;
+    fuzzy::SCORE_MATCH * chars + fuzzy::BONUS_CONSECUTIVE * (chars - 1).max(0)
+}
+
+/// Results for `query` from the history, `templates` and the (name, description) pairs of
+/// `commands`, best first and at most `QUICK_SEARCH_LIMIT`. Equal scores go to conversations,
+/// then templates, commands, bookmarks and messages. An empty query lists the most recently
+/// active conversations, like the quick switcher.
+pub fn quick_search(
+    mgr: &ConversationManager,
+    templates: &[ConversationTemplate],
+    commands: &[(&str, &str)],
+    query: &str,
+) -> Vec<QuickSearchResult> {
+    let query = query.trim();
+    let mut results: Vec<QuickSearchResult> =
+        fuzzy::find_conversations(mgr, query, QUICK_SEARCH_LIMIT)
+            .into_iter()
+            .map(|found| QuickSearchResult {
+                kind: QuickSearchKind::Conversation,
+                id: found.conversation_id.to_string(),
+                conversation_id: Some(found.conversation_id),
+                indices: match found.tag {
+                    Some(_) => Vec::new(),
+                    None => found.matched.indices,
+                },
+                title: found.title,
+                detail: found.tag.unwrap_or_default(),
+                score: found.matched.score,
+                timestamp: 0,
+            })
+            .collect();
+    if query.is_empty() {
+        return results;
+    }
+
+    for template in templates {
+        if let Some(matched) = fuzzy_match(query, &template.name) {
+            results.push(QuickSearchResult {
+                kind: QuickSearchKind::Template,
+                id: template.id.to_string(),
+                conversation_id: None,
+                title: template.name.clone(),
+                detail: snippet(&template.system_prompt, SNIPPET_CHARS),
+                indices: matched.indices,
+                score: matched.score,
+                timestamp: 0,
+            });
+        }
+    }
+    for (name, description) in commands {
+        // Underscores become spaces, which keeps the indices and the word-start bonuses.
+        let title = name.replace('_', " ");
+        if let Some(matched) = fuzzy_match(query, &title) {
+            results.push(QuickSearchResult {
+                kind: QuickSearchKind::Command,
+                id: name.to_string(),
+                conversation_id: None,
+                title,
+                detail: description.to_string(),
+                indices: matched.indices,
+                score: matched.score,
+                timestamp: 0,
+            });
+        }
+    }
+
+    let text_score = text_score(query);
+    let bookmarked: HashMap<Uuid, i64> = bookmarks::list_bookmarks(mgr)
+        .into_iter()
+        .map(|bookmark| (bookmark.message_id, bookmark.bookmarked_at))
+        .collect();
+    let mut found_messages = HashSet::new();
+    for hit in search::search_messages(mgr, query, "") {
+        // A message whose content and note both match is listed once, for its content.
+        if !found_messages.insert(hit.message_id) {
+            continue;
+        }
+        let (kind, score, timestamp) = match bookmarked.get(&hit.message_id) {
+            Some(&bookmarked_at) => (
+                QuickSearchKind::Bookmark,
+                text_score + BOOKMARK_BONUS,
+                bookmarked_at,
+            ),
+            None => (QuickSearchKind::Message, text_score, hit.timestamp),
+        };
+        results.push(QuickSearchResult {
+            kind,
+            id: hit.message_id.to_string(),
+            conversation_id: Some(hit.conversation_id),
+            title: hit.conversation_title,
+            detail: snippet(&hit.snippet, SNIPPET_CHARS),
+            indices: Vec::new(),
+            score,
+            timestamp,
+        });
+    }
+
+    // The sort is stable, so conversations keep the quick switcher's order among themselves.
+    results.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.kind.cmp(&b.kind))
+            .then(b.timestamp.cmp(&a.timestamp))
+    });
+    results.truncate(QUICK_SEARCH_LIMIT);
+    results
+}
+
+#[cfg(test)]
+mod test {
+    use chatgpt::types::Role;
+
+    use super::*;
+    use crate::models::{
+        Conversation, ConversationMessageAddedEvent, ConversationMessageBookmarkedEvent,
+        ConversationTitleChangedEvent,
+    };
+
+    #[test]
+    fn test_quick_search() {
+        let mut mgr = ConversationManager::new();
+        let mut conv = Conversation::new();
+        conv.add_event(ConversationTitleChangedEvent {
+            new_title: "Rust lifetimes".to_string(),
+        });
+        for content in [
+            "Why does the borrow checker complain?",
+            "Rust lifetimes explained",
+        ] {
+            conv.add_event(ConversationMessageAddedEvent {
+                author: Role::User,
+                content: content.to_string(),
+                model: None,
+                language: None,
+                citations: Vec::new(),
+            });
+        }
+        let bookmarked = conv.history.last().unwrap().id;
+        conv.add_event(ConversationMessageBookmarkedEvent {
+            message_id: bookmarked,
+            bookmarked: true,
+        });
+        let conversation_id = conv.id;
+        mgr.conversations.insert(conv.id, conv);
+
+        let results = quick_search(
+            &mgr,
+            &[],
+            &[("rename_conversation", "Renames a conversation")],
+            "rust lifetimes",
+        );
+        let kinds: Vec<QuickSearchKind> = results.iter().map(|result| result.kind).collect();
+        // the title match first, then the bookmarked message it's also in
+        assert_eq!(
+            kinds,
+            [QuickSearchKind::Conversation, QuickSearchKind::Bookmark]
+        );
+        assert_eq!(results[1].id, bookmarked.to_string());
+
+        let results = quick_search(
+            &mgr,
+            &[],
+            &[("rename_conversation", "Renames a conversation")],
+            "borrow",
+        );
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].kind, QuickSearchKind::Message);
+        assert_eq!(results[0].conversation_id, Some(conversation_id));
+
+        let results = quick_search(
+            &mgr,
+            &[],
+            &[("rename_conversation", "Renames a conversation")],
+            "rename",
+        );
+        assert_eq!(results[0].kind, QuickSearchKind::Command);
+        assert_eq!(results[0].title, "rename conversation");
+        assert_eq!(results[0].indices, [0, 1, 2, 3, 4, 5]);
+
+        let recent = quick_search(&mgr, &[], &[], " ");
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].kind, QuickSearchKind::Conversation);
+    }
+}
@@ -0,0 +1,193 @@
+//! Reading conversations aloud: the queue of messages to speak and where playback is in it,
+//! and each message's text the way it's best heard.
+
+use std::sync::OnceLock;
+
+use chatgpt::types::Role;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    explain,
+    models::{Conversation, ConversationEvent},
+};
+
+/// The slowest and fastest speech the provider makes.
+pub const MIN_SPEED: f32 = 0.25;
+pub const MAX_SPEED: f32 = 4.0;
+/// The most characters of text spoken at once.
+pub const MAX_SPEECH_CHARS: usize = 4_096;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackState {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedMessage {
+    pub message_id: Uuid,
+    pub text: String,
+}
+
+/// `content` the way it's best heard: code blocks are mentioned rather than read out, links
+/// are read by their text and Markdown marks are left out.
+pub fn speakable_text(content: &str) -> String {
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    let link = LINK.get_or_init(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap());
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with("```") || line.starts_with("~~~") {
+            if !in_code {
+                lines.push("(code block)".to_string());
+            }
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        let line = line.trim_start_matches(['#', '>']).trim_start();
+        let line = line
+            .strip_prefix("- ")
+            .or_else(|| line.strip_prefix("* "))
+            .unwrap_or(line);
+        let line = link.replace_all(line, "$1").replace(['*', '`'], "");
+        if !line.trim().is_empty() {
+            lines.push(line.trim().to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+/// The parts `text` is spoken in, each at most `MAX_SPEECH_CHARS` long.
+pub fn speech_parts(text: &str) -> Vec<String> {
+    explain::split_code(text, MAX_SPEECH_CHARS)
+}
+
+/// Speech as the provider sends it, 16-bit little-endian PCM, as samples.
+pub fn pcm_samples(pcm: &[u8]) -> Vec<f32> {
+    pcm.chunks_exact(2)
+        .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / i16::MAX as f32)
+        .collect()
+}
+
+/// The user's and assistant's messages of `conversation` with something to say, in order.
+pub fn queue_messages(conversation: &Conversation) -> Vec<QueuedMessage> {
+    conversation
+        .history
+        .iter()
+        .filter_map(|record| match &record.event {
+            ConversationEvent::MessageAdded(msg) if msg.author != Role::System => {
+                Some(QueuedMessage {
+                    message_id: record.id,
+                    text: speakable_text(&msg.content),
+                })
+            }
+            _ => None,
+        })
+        .filter(|message| !message.text.is_empty())
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct PlaybackQueue {
+    pub conversation_id: Uuid,
+    messages: Vec<QueuedMessage>,
+    position: usize,
+    pub state: PlaybackState,
+}
+
+impl PlaybackQueue {
+    /// Playing from the message `from`, or from the start when it's not given or not queued.
+    pub fn new(conversation_id: Uuid, messages: Vec<QueuedMessage>, from: Option<Uuid>) -> Self {
+        let position = from
+            .and_then(|from| {
+                messages
+                    .iter()
+                    .position(|message| message.message_id == from)
+            })
+            .unwrap_or(0);
+        Self {
+            conversation_id,
+            messages,
+            position,
+            state: PlaybackState::Playing,
+        }
+    }
+
+    pub fn current(&self) -> Option<&QueuedMessage> {
+        match self.state {
+            PlaybackState::Stopped => None,
+            _ => self.messages.get(self.position),
+        }
+    }
+
+    /// Moves on to the next message. Returns false, having stopped, after the last one.
+    pub fn advance(&mut self) -> bool {
+        self.position += 1;
+        if self.position >= self.messages.len() {
+            self.state = PlaybackState::Stopped;
+        }
+        self.state != PlaybackState::Stopped
+    }
+
+    /// The index of the message being read.
+    pub fn position(&self) -> usize {
+        self.position.min(self.messages.len())
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn queued(text: &str) -> QueuedMessage {
+        QueuedMessage {
+            message_id: Uuid::new_v4(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_speakable_text() {
+        let content = "## Steps\n\n- Run **cargo** `build`\n```rust\nfn main() {}\n```\n\
+                       See [the docs](https://example.com) for_more.";
+        assert_eq!(
+            speakable_text(content),
+            "Steps\nRun cargo build\n(code block)\nSee the docs for_more."
+        );
+        assert_eq!(speakable_text("```\nonly code\n```"), "(code block)");
+        assert_eq!(pcm_samples(&[0xff, 0x7f, 0x00, 0x00, 0x01]), [1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_playback_queue() {
+        let messages = vec![queued("one"), queued("two"), queued("three")];
+        let second = messages[1].message_id;
+        let mut queue = PlaybackQueue::new(Uuid::new_v4(), messages.clone(), Some(second));
+        assert_eq!(queue.current().unwrap().text, "two");
+        assert!(queue.advance());
+        assert_eq!(queue.position(), 2);
+        assert!(!queue.advance());
+        assert_eq!(queue.state, PlaybackState::Stopped);
+        assert_eq!(queue.current(), None);
+        assert_eq!(queue.position(), 3);
+
+        let queue = PlaybackQueue::new(Uuid::new_v4(), messages, Some(Uuid::new_v4()));
+        assert_eq!(queue.current().unwrap().text, "one");
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of exchanges kept in the ring file; the oldest are dropped first.
+const MAX_RECORDS: usize = 200;
+/// Request and response bodies are cut down to this many bytes before being stored.
+const MAX_BODY_BYTES: usize = 4096;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RequestRecord {
+    pub id: uuid::Uuid,
+    pub timestamp: i64,
+    pub url: String,
+    pub model: Option<String>,
+    /// Every request field except the message bodies.
+    pub params: serde_json::Value,
+    pub request_body: String,
+    pub status: Option<u16>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Persists redacted provider exchanges for troubleshooting while debug recording is enabled.
+pub struct RequestRecorder {
+    enabled: AtomicBool,
+    path: Mutex<PathBuf>,
+    records: Mutex<VecDeque<RequestRecord>>,
+}
+
+impl RequestRecorder {
+    pub fn new(path: PathBuf, enabled: bool) -> Self {
+        let records = Self::read_records(&path).unwrap_or_default();
+        Self {
+            enabled: AtomicBool::new(enabled),
+            path: Mutex::new(path),
+            records: Mutex::new(records),
+        }
+    }
+
+    /// Switches to the log at `path`, e.g. after changing workspace profile.
+    pub fn reopen(&self, path: PathBuf, enabled: bool) {
+        let mut records = self.records.lock().unwrap();
+        *records = Self::read_records(&path).unwrap_or_default();
+        *self.path.lock().unwrap() = path;
+        self.set_enabled(enabled);
+    }
+
+    fn read_records(path: &PathBuf) -> Result<VecDeque<RequestRecord>, std::io::Error> {
+        let file = File::open(path)?;
+        let records = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect();
+        Ok(records)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn record(&self, record: RequestRecord) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut records = self.records.lock().unwrap();
+        records.push_back(record);
+        while records.len() > MAX_RECORDS {
+            records.pop_front();
+        }
+        if let Err(e) = self.write_records(&records) {
+            eprintln!("Failed to write request log: {}", e);
+        }
+    }
+
+    fn write_records(&self, records: &VecDeque<RequestRecord>) -> Result<(), std::io::Error> {
+        let mut file = File::create(&*self.path.lock().unwrap())?;
+        for record in records {
+            writeln!(file, "{}", serde_json::to_string(record)?)?;
+        }
+        Ok(())
+    }
+
+    /// Drops every stored exchange, both in memory and on disk.
+    pub fn clear(&self) -> Result<(), std::io::Error> {
+        self.records.lock().unwrap().clear();
+        let path = self.path.lock().unwrap();
+        if path.exists() {
+            std::fs::remove_file(&*path)?;
+        }
+        Ok(())
+    }
+
+    pub fn export(&self, target_path: &str) -> Result<usize, std::io::Error> {
+        let records = self.records.lock().unwrap();
+        let file = File::create(target_path)?;
+        serde_json::to_writer_pretty(file, &*records)?;
+        Ok(records.len())
+    }
+}
+
+/// Masks anything that looks like an OpenAI key and truncates the body to a bounded size.
+pub fn redact_body(body: &str) -> String {
+    let mut redacted = String::with_capacity(body.len().min(MAX_BODY_BYTES));
+    let mut rest = body;
+    while let Some(start) = rest.find("sk-") {
+        redacted.push_str(&rest[..start]);
+        redacted.push_str("sk-***");
+        let key_len = rest[start + 3..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(rest.len() - start - 3);
+        rest = &rest[start + 3 + key_len..];
+    }
+    redacted.push_str(rest);
+    if redacted.len() > MAX_BODY_BYTES {
+        let mut cut = MAX_BODY_BYTES;
+        while !redacted.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        redacted.truncate(cut);
+        redacted.push_str("…[truncated]");
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_redact_body() {
+        assert_eq!(
+            redact_body("{\"key\":\"sk-abc123_DEF\",\"x\":1}"),
+            "{\"key\":\"sk-***\",\"x\":1}"
+        );
+        let long = "a".repeat(MAX_BODY_BYTES * 2);
+        assert!(redact_body(&long).ends_with("…[truncated]"));
+    }
+}
@@ -0,0 +1,106 @@
+//! Moving the conversation history to another file without restarting.
+
+use std::{fs::File, io::Write, path::Path};
+
+use crate::{compression, models::ConversationManager};
+
+/// Checks that `target` can take over from `current`: a JSON file path in an existing
+/// folder, with no other history there to overwrite.
+pub fn validate_target(current: &str, target: &Path) -> Result<(), String> {
+    if target.as_os_str().is_empty() {
+        return Err("The new location is empty".to_string());
+    }
+    if target == Path::new(current) {
+        return Err("The history is already stored there".to_string());
+    }
+    if target.is_dir() {
+        return Err(format!("{} is a folder, not a file", target.display()));
+    }
+    match target.parent() {
+        Some(dir) if dir.as_os_str().is_empty() || dir.is_dir() => {}
+        _ => return Err(format!("The folder of {} doesn't exist", target.display())),
+    }
+    if target.exists() && std::fs::metadata(target).map_or(true, |meta| meta.len() > 0) {
+        return Err(format!("{} already exists", target.display()));
+    }
+    Ok(())
+}
+
+/// Writes the history to `target` through a temporary file, so the target either holds the
+/// whole history or nothing, then reads it back and compares it with what was written.
+/// The copy is compressed when the history is. Returns the history as loaded from the new
+/// location; the old file is left in place. A history that's still loading is refused, since
+/// only part of it would be moved.
+pub fn move_history(
+    mgr: &ConversationManager,
+    target: &Path,
+) -> Result<ConversationManager, Box<dyn std::error::Error>> {
+    if mgr.loading {
+        return Err("The history is still loading".into());
+    }
+    let bytes = compression::encode(&serde_json::to_vec(&mgr.conversations)?, mgr.compressed)?;
+    let mut temp_name = target.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp = Path::new(&temp_name);
+    {
+        let mut file = File::create(temp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(temp, target)?;
+
+    if std::fs::read(target)? != bytes {
+        return Err("The copied history doesn't match the original".into());
+    }
+    let target_str = target
+        .to_str()
+        .ok_or("The new location isn't valid UTF-8")?;
+    let moved = ConversationManager::from_disk(target_str)?;
+    let same_conversations = moved.conversations.len() == mgr.conversations.len()
+        && moved.conversations.iter().all(|(id, conv)| {
+            mgr.conversations
+                .get(&id)
+                .is_some_and(|original| original.history.len() == conv.history.len())
+        });
+    if !same_conversations {
+        return Err("The copied history is missing conversations".into());
+    }
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::Conversation;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_move_history() {
+        let dir = std::env::temp_dir().join(format!("relocate-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let current = dir.join("history.json");
+        let target = dir.join("moved.json");
+
+        let mut mgr = ConversationManager::new();
+        let conv = Conversation::new();
+        mgr.conversations.insert(conv.id, conv);
+        mgr.write_to_disk(current.to_str().unwrap()).unwrap();
+
+        let current = current.to_str().unwrap();
+        assert!(validate_target(current, Path::new(current)).is_err());
+        assert!(validate_target(current, &dir).is_err());
+        assert!(validate_target(current, &dir.join("missing").join("h.json")).is_err());
+        validate_target(current, &target).unwrap();
+
+        mgr.loading = true;
+        assert!(move_history(&mgr, &target).is_err());
+        assert!(!target.exists());
+        mgr.loading = false;
+        let moved = move_history(&mgr, &target).unwrap();
+        assert_eq!(moved.conversations.len(), 1);
+        assert!(Path::new(current).exists());
+        assert!(validate_target(current, &target).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+//! Retention rules deciding which conversations get removed from history.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    models::{Conversation, ConversationManager},
+    store::ConversationSummary,
+};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RetentionConfig {
+    /// Archived conversations are deleted this many days after being archived.
+    #[serde(default)]
+    pub delete_archived_after_days: Option<u32>,
+    /// Once the serialized history exceeds this size, the least recently active conversations
+    /// are deleted (archived ones first) until it fits.
+    #[serde(default)]
+    pub max_history_bytes: Option<u64>,
+    /// Deleted conversations stay in the trash this many days before they're removed for
+    /// good; 0 deletes them right away.
+    #[serde(default = "default_trash_days")]
+    pub trash_days: u32,
+}
+
+fn default_trash_days() -> u32 {
+    30
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            delete_archived_after_days: None,
+            max_history_bytes: None,
+            trash_days: default_trash_days(),
+        }
+    }
+}
+
+impl RetentionConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.delete_archived_after_days.is_some() || self.max_history_bytes.is_some()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionReason {
+    ArchivedExpired,
+    HistorySizeCap,
+}
+
+#[derive(Debug, Clone)]
+pub struct RetentionCandidate {
+    pub conversation_id: Uuid,
+    pub title: String,
+    pub reason: RetentionReason,
+    pub size_bytes: u64,
+}
+
+fn serialized_size(conv: &Conversation) -> u64 {
+    serde_json::to_vec(conv).map_or(0, |bytes| bytes.len() as u64)
+}
+
+/// Lists the conversations the rules would remove at `now` (unix seconds) without touching them.
+pub fn plan(mgr: &ConversationManager, rules: &RetentionConfig, now: i64) -> Vec<RetentionCandidate> {
+    let mut candidates = Vec::new();
+    if let Some(days) = rules.delete_archived_after_days {
+        let cutoff = now - i64::from(days) * SECONDS_PER_DAY;
+        for conv in mgr.conversations.values() {
+            if conv.archived_at().is_some_and(|archived_at| archived_at <= cutoff) {
+                candidates.push(RetentionCandidate {
+                    conversation_id: conv.id,
+                    title: conv.get_title().into_owned(),
+                    reason: RetentionReason::ArchivedExpired,
+                    size_bytes: serialized_size(&conv),
+                });
+            }
+        }
+    }
+    if let Some(max_bytes) = rules.max_history_bytes {
+        // Summaries rather than conversations, so they needn't all be decoded at once.
+        let mut remaining: Vec<(ConversationSummary, u64)> = mgr
+            .conversations
+            .values()
+            .filter(|conv| !candidates.iter().any(|c| c.conversation_id == conv.id))
+            .map(|conv| (ConversationSummary::of(&conv), serialized_size(&conv)))
+            .collect();
+        let mut total: u64 = remaining.iter().map(|(_, size)| size).sum();
+        // Archived before active, then least recently active first.
+        remaining.sort_by_key(|(summary, _)| (!summary.archived, summary.last_activity));
+        for (summary, size) in remaining {
+            if total <= max_bytes {
+                break;
+            }
+            total -= size;
+            candidates.push(RetentionCandidate {
+                conversation_id: summary.id,
+                title: summary.title(),
+                reason: RetentionReason::HistorySizeCap,
+                size_bytes: size,
+            });
+        }
+    }
+    candidates
+}
+
+pub fn apply(mgr: &mut ConversationManager, candidates: &[RetentionCandidate]) {
+    for candidate in candidates {
+        mgr.conversations.remove(&candidate.conversation_id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::ConversationArchivedEvent;
+
+    #[test]
+    fn test_plan() {
+        let mut mgr = ConversationManager::new();
+        let mut archived = Conversation::new();
+        archived.add_event(ConversationArchivedEvent { archived: true });
+        let active = Conversation::new();
+        let (archived_id, active_id) = (archived.id, active.id);
+        mgr.conversations.insert(archived_id, archived);
+        mgr.conversations.insert(active_id, active);
+        let now = mgr.conversations.get(&archived_id).unwrap().last_activity();
+
+        let rules = RetentionConfig {
+            delete_archived_after_days: Some(30),
+            ..Default::default()
+        };
+        assert!(plan(&mgr, &rules, now).is_empty());
+        let later = plan(&mgr, &rules, now + 31 * SECONDS_PER_DAY);
+        assert_eq!(later.len(), 1);
+        assert_eq!(later[0].conversation_id, archived_id);
+        assert_eq!(later[0].reason, RetentionReason::ArchivedExpired);
+
+        let rules = RetentionConfig {
+            max_history_bytes: Some(1),
+            ..Default::default()
+        };
+        let capped = plan(&mgr, &rules, now);
+        assert_eq!(capped.len(), 2);
+        assert_eq!(capped[0].conversation_id, archived_id);
+        apply(&mut mgr, &capped);
+        assert!(mgr.conversations.is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+//! Case-insensitive search over message contents and user notes across every conversation,
+//! optionally narrowed to messages written in one language, and find-in-page within one.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    bookmarks::snippet,
+    models::{Conversation, ConversationEvent, ConversationManager},
+};
+
+const SNIPPET_CHARS: usize = 240;
+/// Characters kept before the match so the snippet shows some context.
+const SNIPPET_LEAD_CHARS: usize = 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    Content,
+    Note,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub conversation_id: Uuid,
+    pub conversation_title: String,
+    pub message_id: Uuid,
+    pub field: SearchField,
+    pub snippet: String,
+    pub timestamp: i64,
+    /// ISO 639-3 code of the message's language, when it could be detected.
+    pub language: Option<String>,
+}
+
+/// Snippet of `text` starting a little before the first match of `needle` (already lowercased).
+fn match_snippet(text: &str, needle: &str) -> Option<String> {
+    let lowercase = text.to_lowercase();
+    let byte_index = lowercase.find(needle)?;
+    // Lowercasing can change byte lengths, so locate the match by character position.
+    let char_index = lowercase[..byte_index].chars().count();
+    let start = char_index.saturating_sub(SNIPPET_LEAD_CHARS);
+    let tail: String = text.chars().skip(start).collect();
+    let snippet = snippet(&tail, SNIPPET_CHARS);
+    Some(if start > 0 {
+        format!("…{}", snippet)
+    } else {
+        snippet
+    })
+}
+
+/// Matching messages, newest first. A message whose content and note both match yields two hits.
+/// A non-empty `language` (ISO 639-3) keeps only messages detected as written in it.
+pub fn search_messages(mgr: &ConversationManager, query: &str, language: &str) -> Vec<SearchHit> {
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    let mut hits = Vec::new();
+    for conv in mgr.conversations.values() {
+        for record in &conv.history {
+            let ConversationEvent::MessageAdded(msg) = &record.event else {
+                continue;
+            };
+            let message_language = msg.detected_language();
+            if !language.is_empty() && message_language.as_deref() != Some(language) {
+                continue;
+            }
+            let fields = [
+                (SearchField::Content, Some(msg.content.as_str())),
+                (SearchField::Note, conv.message_note(record.id)),
+            ];
+            for (field, text) in fields {
+                if let Some(snippet) = text.and_then(|text| match_snippet(text, &needle)) {
+                    hits.push(SearchHit {
+                        conversation_id: conv.id,
+                        conversation_title: conv.get_title().into_owned(),
+                        message_id: record.id,
+                        field,
+                        snippet,
+                        timestamp: record.timestamp,
+                        language: message_language.clone(),
+                    });
+                }
+            }
+        }
+    }
+    hits.sort_by_key(|hit| std::cmp::Reverse(hit.timestamp));
+    hits
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageMatches {
+    pub message_id: Uuid,
+    /// Byte ranges `(start, end)` of each match in the message's content.
+    pub ranges: Vec<(usize, usize)>,
+}
+
+/// The matches of `query` in each message of the conversation, in conversation order. A plain
+/// query matches case-insensitively; a `regex` one is used as written, so `(?i)` still works.
+pub fn search_in_conversation(
+    conv: &Conversation,
+    query: &str,
+    regex: bool,
+) -> Result<Vec<MessageMatches>, regex::Error> {
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Matching the escaped query keeps offsets into the original text, which lowercasing wouldn't.
+    let pattern: Regex = if regex {
+        Regex::new(query)?
+    } else {
+        RegexBuilder::new(&regex::escape(query))
+            .case_insensitive(true)
+            .build()?
+    };
+    Ok(conv
+        .history
+        .iter()
+        .filter_map(|record| {
+            let ConversationEvent::MessageAdded(msg) = &record.event else {
+                return None;
+            };
+            let ranges: Vec<(usize, usize)> = pattern
+                .find_iter(&msg.content)
+                // Patterns like `a*` also match nothing everywhere, which can't be navigated to.
+                .filter(|found| !found.is_empty())
+                .map(|found| (found.start(), found.end()))
+                .collect();
+            (!ranges.is_empty()).then_some(MessageMatches {
+                message_id: record.id,
+                ranges,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::{
+        Conversation, ConversationMessageAddedEvent, ConversationMessageAnnotatedEvent,
+    };
+
+    #[test]
+    fn test_search_messages() {
+        let mut conv = Conversation::new();
+        let message_id = conv
+            .add_event(ConversationMessageAddedEvent {
+                author: chatgpt::types::Role::Assistant,
+                content: "Use a BTreeMap for ordered keys".to_string(),
+                model: None,
+                language: None,
+                citations: Vec::new(),
+            })
+            .id;
+        conv.add_event(ConversationMessageAnnotatedEvent {
+            message_id,
+            note: Some("remember for the indexer".to_string()),
+        });
+        let mut mgr = ConversationManager::new();
+        mgr.conversations.insert(conv.id, conv);
+
+        let hits = search_messages(&mgr, "btreemap", "");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].field, SearchField::Content);
+        assert_eq!(hits[0].message_id, message_id);
+
+        let hits = search_messages(&mgr, "INDEXER", "");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].field, SearchField::Note);
+
+        assert!(search_messages(&mgr, "  ", "").is_empty());
+    }
+
+    #[test]
+    fn test_search_by_language() {
+        let mut conv = Conversation::new();
+        for content in [
+            "What is the fastest way to sort a list of numbers in Rust?",
+            "Wie sortiere ich eine Liste von Zahlen in Rust am schnellsten?",
+        ] {
+            conv.add_event(ConversationMessageAddedEvent {
+                author: chatgpt::types::Role::User,
+                content: content.to_string(),
+                model: None,
+                language: None,
+                citations: Vec::new(),
+            });
+        }
+        let mut mgr = ConversationManager::new();
+        mgr.conversations.insert(conv.id, conv);
+
+        assert_eq!(search_messages(&mgr, "rust", "").len(), 2);
+        let hits = search_messages(&mgr, "rust", "deu");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].language.as_deref(), Some("deu"));
+        assert!(hits[0].snippet.starts_with("Wie"));
+    }
+
+    #[test]
+    fn test_search_in_conversation() {
+        let mut conv = Conversation::new();
+        let mut message_ids = Vec::new();
+        for content in ["Grüße: use Vec, not vec!", "no match here", "VEC again"] {
+            message_ids.push(
+                conv.add_event(ConversationMessageAddedEvent {
+                    author: chatgpt::types::Role::User,
+                    content: content.to_string(),
+                    model: None,
+                    language: None,
+                    citations: Vec::new(),
+                })
+                .id,
+            );
+        }
+
+        let found = search_in_conversation(&conv, "vec", false).unwrap();
+        assert_eq!(
+            found,
+            vec![
+                MessageMatches {
+                    message_id: message_ids[0],
+                    ranges: vec![(13, 16), (22, 25)],
+                },
+                MessageMatches {
+                    message_id: message_ids[2],
+                    ranges: vec![(0, 3)],
+                },
+            ]
+        );
+        assert_eq!(search_in_conversation(&conv, "v.c!", false).unwrap(), vec![]);
+        let found = search_in_conversation(&conv, r"v.c!|x*", true).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].ranges, vec![(22, 26)]);
+        assert!(search_in_conversation(&conv, "(", true).is_err());
+    }
+}
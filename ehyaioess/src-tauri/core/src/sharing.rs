@@ -0,0 +1,324 @@
+//! `.ehyas` bundles: a conversation archive encrypted with a passphrase, so a transcript can
+//! be sent over email or chat without anyone along the way reading it.
+//!
+//! A bundle is a JSON envelope holding the `.ehya` archive encrypted with AES-256-GCM, under
+//! a key derived from the passphrase with Argon2id, using the envelope's random salt and cost
+//! parameters.
+
+use std::{
+    fmt,
+    io::{self, Cursor},
+    path::Path,
+};
+
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    archive::{self, Archive, ArchiveError, Manifest},
+    models::Conversation,
+};
+
+pub const SHARED_EXTENSION: &str = "ehyas";
+/// Identifies the envelope as ours.
+const FORMAT: &str = "ehyaioess.shared";
+/// Raised whenever a change to the envelope or the encryption would be misread by older
+/// versions.
+pub const FORMAT_VERSION: u32 = 1;
+const SALT_LENGTH: usize = 16;
+/// The most memory a bundle may ask key derivation for, in KiB, so opening a hostile bundle
+/// can't exhaust it.
+const MAX_M_COST: u32 = 256 * 1024;
+/// The most passes a bundle may ask for, so opening a hostile one can't take minutes.
+const MAX_T_COST: u32 = 16;
+/// The most lanes a bundle may ask for.
+const MAX_P_COST: u32 = 16;
+
+/// The Argon2id cost the key was derived with, kept in the envelope so bundles stay readable
+/// whatever the `argon2` crate's defaults become.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+struct KdfParams {
+    /// Memory, in KiB.
+    m_cost: u32,
+    /// Passes over the memory.
+    t_cost: u32,
+    /// Lanes.
+    p_cost: u32,
+}
+
+impl Default for KdfParams {
+    /// What bundles are written with, and what the ones written before the cost was recorded
+    /// used.
+    fn default() -> Self {
+        Self {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    format: String,
+    version: u32,
+    #[serde(default)]
+    kdf: KdfParams,
+    /// Base64, like the fields below.
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Debug)]
+pub enum ShareError {
+    /// Written by a newer version of the app, in a format this one can't read.
+    UnsupportedVersion(u32),
+    /// Not a bundle, or one that's incomplete.
+    Invalid(String),
+    /// The passphrase doesn't decrypt the bundle. A bundle changed after it was written
+    /// can't be told apart from a wrong passphrase, so it's this too.
+    WrongPassphrase,
+    Archive(ArchiveError),
+    Io(io::Error),
+}
+
+impl fmt::Display for ShareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShareError::UnsupportedVersion(version) => write!(
+                f,
+                "bundle format version {} is newer than {}",
+                version, FORMAT_VERSION
+            ),
+            ShareError::Invalid(reason) => write!(f, "not a valid bundle: {}", reason),
+            ShareError::WrongPassphrase => write!(f, "wrong passphrase or damaged bundle"),
+            ShareError::Archive(e) => write!(f, "{}", e),
+            ShareError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ShareError {}
+
+impl From<io::Error> for ShareError {
+    fn from(e: io::Error) -> Self {
+        ShareError::Io(e)
+    }
+}
+
+impl From<ArchiveError> for ShareError {
+    fn from(e: ArchiveError) -> Self {
+        ShareError::Archive(e)
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], kdf: KdfParams) -> Result<Key<Aes256Gcm>, ShareError> {
+    if kdf.m_cost > MAX_M_COST {
+        return Err(ShareError::Invalid(format!(
+            "key derivation asks for {} KiB of memory",
+            kdf.m_cost
+        )));
+    }
+    if kdf.t_cost > MAX_T_COST {
+        return Err(ShareError::Invalid(format!(
+            "key derivation asks for {} passes",
+            kdf.t_cost
+        )));
+    }
+    if kdf.p_cost > MAX_P_COST {
+        return Err(ShareError::Invalid(format!(
+            "key derivation asks for {} lanes",
+            kdf.p_cost
+        )));
+    }
+    let params = Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, None)
+        .map_err(|e| ShareError::Invalid(format!("kdf: {}", e)))?;
+    let mut key = Key::<Aes256Gcm>::default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| ShareError::Invalid(e.to_string()))?;
+    Ok(key)
+}
+
+/// `plaintext` encrypted with `passphrase`, as the bytes of a bundle.
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, ShareError> {
+    let mut salt = [0u8; SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let kdf = KdfParams::default();
+    let ciphertext = Aes256Gcm::new(&derive_key(passphrase, &salt, kdf)?)
+        .encrypt(&nonce, plaintext)
+        .map_err(|e| ShareError::Invalid(e.to_string()))?;
+    let base64 = base64::engine::general_purpose::STANDARD;
+    let envelope = Envelope {
+        format: FORMAT.to_string(),
+        version: FORMAT_VERSION,
+        kdf,
+        salt: base64.encode(salt),
+        nonce: base64.encode(nonce),
+        ciphertext: base64.encode(ciphertext),
+    };
+    serde_json::to_vec(&envelope).map_err(|e| ShareError::Invalid(e.to_string()))
+}
+
+/// The plaintext of the bundle in `bundle`, decrypted with `passphrase`.
+pub fn decrypt(bundle: &[u8], passphrase: &str) -> Result<Vec<u8>, ShareError> {
+    let envelope: Envelope =
+        serde_json::from_slice(bundle).map_err(|e| ShareError::Invalid(e.to_string()))?;
+    if envelope.format != FORMAT {
+        return Err(ShareError::Invalid(format!(
+            "unknown format {}",
+            envelope.format
+        )));
+    }
+    if envelope.version > FORMAT_VERSION {
+        return Err(ShareError::UnsupportedVersion(envelope.version));
+    }
+    let base64 = base64::engine::general_purpose::STANDARD;
+    let decode = |field: &str, value: &str| {
+        base64
+            .decode(value)
+            .map_err(|e| ShareError::Invalid(format!("{}: {}", field, e)))
+    };
+    let salt = decode("salt", &envelope.salt)?;
+    let nonce = decode("nonce", &envelope.nonce)?;
+    if nonce.len() != 12 {
+        return Err(ShareError::Invalid(
+            "nonce has the wrong length".to_string(),
+        ));
+    }
+    Aes256Gcm::new(&derive_key(passphrase, &salt, envelope.kdf)?)
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            &decode("ciphertext", &envelope.ciphertext)?[..],
+        )
+        .map_err(|_| ShareError::WrongPassphrase)
+}
+
+/// Writes `conversation` and its attachments from `attachments_dir` as a bundle at `path`,
+/// encrypted with `passphrase`.
+pub fn write(
+    conversation: &Conversation,
+    attachments_dir: &Path,
+    path: &Path,
+    passphrase: &str,
+    now: i64,
+) -> Result<Manifest, ShareError> {
+    let mut plaintext = Cursor::new(Vec::new());
+    let manifest = archive::write_to(conversation, attachments_dir, &mut plaintext, now)?;
+    std::fs::write(path, encrypt(plaintext.get_ref(), passphrase)?)?;
+    Ok(manifest)
+}
+
+/// Decrypts the bundle at `path` with `passphrase` and reads the archive in it, checked the
+/// way `archive::read` checks one.
+pub fn read(path: &Path, passphrase: &str) -> Result<Archive, ShareError> {
+    let plaintext = decrypt(&std::fs::read(path)?, passphrase)?;
+    Ok(archive::read_from(Cursor::new(plaintext))?)
+}
+
+#[cfg(test)]
+mod test {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::{ConversationMessageAddedEvent, ConversationTitleChangedEvent};
+
+    #[test]
+    fn test_round_trip() {
+        let mut conv = Conversation::new();
+        conv.add_event(ConversationTitleChangedEvent {
+            new_title: "Salary negotiation".to_string(),
+        });
+        conv.add_event(ConversationMessageAddedEvent {
+            author: chatgpt::types::Role::User,
+            content: "My offer is 90k".to_string(),
+            model: None,
+            language: None,
+            citations: Vec::new(),
+        });
+        let dir = std::env::temp_dir().join(format!("sharing-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("shared.{}", SHARED_EXTENSION));
+
+        write(&conv, &dir, &path, "correct horse", 100).unwrap();
+        let bundle = std::fs::read(&path).unwrap();
+        // nothing of the transcript is readable without the passphrase
+        assert!(!String::from_utf8_lossy(&bundle).contains("90k"));
+        let read_back = read(&path, "correct horse").unwrap();
+        assert_eq!(read_back.conversation.id, conv.id);
+        assert_eq!(read_back.manifest.title, "Salary negotiation");
+        assert!(matches!(
+            read(&path, "wrong horse"),
+            Err(ShareError::WrongPassphrase)
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_envelope_checks() {
+        let bundle = encrypt(b"hello", "pass").unwrap();
+        assert_eq!(decrypt(&bundle, "pass").unwrap(), b"hello");
+
+        let mut envelope: Envelope = serde_json::from_slice(&bundle).unwrap();
+        envelope.version = FORMAT_VERSION + 1;
+        let newer = serde_json::to_vec(&envelope).unwrap();
+        assert!(matches!(
+            decrypt(&newer, "pass"),
+            Err(ShareError::UnsupportedVersion(_))
+        ));
+        assert!(matches!(
+            decrypt(b"PK\x03\x04", "pass"),
+            Err(ShareError::Invalid(_))
+        ));
+
+        let greedy = [
+            KdfParams {
+                m_cost: MAX_M_COST + 1,
+                ..KdfParams::default()
+            },
+            KdfParams {
+                t_cost: MAX_T_COST + 1,
+                ..KdfParams::default()
+            },
+            KdfParams {
+                p_cost: MAX_P_COST + 1,
+                ..KdfParams::default()
+            },
+        ];
+        for kdf in greedy {
+            let mut envelope: Envelope = serde_json::from_slice(&bundle).unwrap();
+            envelope.kdf = kdf;
+            let greedy = serde_json::to_vec(&envelope).unwrap();
+            assert!(matches!(
+                decrypt(&greedy, "pass"),
+                Err(ShareError::Invalid(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_kdf_params() {
+        let bundle = encrypt(b"hello", "pass").unwrap();
+        let mut envelope: serde_json::Value = serde_json::from_slice(&bundle).unwrap();
+        assert_eq!(envelope["kdf"]["m_cost"], 19 * 1024);
+        // bundles written before the cost was recorded used the same one
+        envelope.as_object_mut().unwrap().remove("kdf");
+        let unrecorded = serde_json::to_vec(&envelope).unwrap();
+        assert_eq!(decrypt(&unrecorded, "pass").unwrap(), b"hello");
+
+        let mut envelope: Envelope = serde_json::from_slice(&bundle).unwrap();
+        envelope.kdf.t_cost = 3;
+        let other_cost = serde_json::to_vec(&envelope).unwrap();
+        assert!(matches!(
+            decrypt(&other_cost, "pass"),
+            Err(ShareError::WrongPassphrase)
+        ));
+    }
+}
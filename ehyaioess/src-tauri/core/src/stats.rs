@@ -0,0 +1,141 @@
+//! How long a conversation takes to read and how dense it is, for a "long read" indicator
+//! and for putting the heavier conversations first in digests.
+
+use crate::models::{Conversation, ConversationEvent};
+
+pub const PROSE_WORDS_PER_MINUTE: f64 = 230.0;
+/// Code is read more slowly than prose.
+pub const CODE_WORDS_PER_MINUTE: f64 = 100.0;
+/// Conversations that take at least this long to read are long reads.
+pub const LONG_READ_SECONDS: u64 = 5 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ConversationStats {
+    pub messages: usize,
+    /// Words outside code blocks. Markdown markers such as list bullets aren't words.
+    pub prose_words: usize,
+    /// Words inside fenced code blocks.
+    pub code_words: usize,
+    pub sentences: usize,
+}
+
+impl ConversationStats {
+    pub fn measure(conversation: &Conversation) -> Self {
+        let mut stats = Self::default();
+        for record in &conversation.history {
+            if let ConversationEvent::MessageAdded(message) = &record.event {
+                stats.add(&message.content);
+            }
+        }
+        stats
+    }
+
+    fn add(&mut self, content: &str) {
+        self.messages += 1;
+        let mut in_code = false;
+        for line in content.lines() {
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+                continue;
+            }
+            if in_code {
+                self.code_words += line.split_whitespace().count();
+                continue;
+            }
+            let words: Vec<&str> = line
+                .split_whitespace()
+                .filter(|word| word.chars().any(char::is_alphanumeric))
+                .collect();
+            self.prose_words += words.len();
+            // A line that doesn't end its last sentence, like a list item, is one more.
+            let ends_sentence = |word: &str| {
+                word.trim_end_matches(['"', '\'', ')', '*', '_'])
+                    .ends_with(['.', '!', '?'])
+            };
+            self.sentences += words.iter().filter(|word| ends_sentence(word)).count();
+            if words.last().is_some_and(|word| !ends_sentence(word)) {
+                self.sentences += 1;
+            }
+        }
+    }
+
+    /// Estimated time to read everything, rounded up to the second.
+    pub fn reading_seconds(&self) -> u64 {
+        let minutes = self.prose_words as f64 / PROSE_WORDS_PER_MINUTE
+            + self.code_words as f64 / CODE_WORDS_PER_MINUTE;
+        (minutes * 60.0).ceil() as u64
+    }
+
+    pub fn is_long_read(&self) -> bool {
+        self.reading_seconds() >= LONG_READ_SECONDS
+    }
+
+    /// Prose words per sentence, 0 without any prose.
+    pub fn average_sentence_words(&self) -> f64 {
+        match self.sentences {
+            0 => 0.0,
+            sentences => self.prose_words as f64 / sentences as f64,
+        }
+    }
+
+    /// Share of the words that are code, from 0 for all prose to 1 for all code.
+    pub fn code_ratio(&self) -> f64 {
+        match self.prose_words + self.code_words {
+            0 => 0.0,
+            words => self.code_words as f64 / words as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chatgpt::types::Role;
+
+    use super::*;
+    use crate::models::ConversationMessageAddedEvent;
+
+    fn conversation(messages: &[&str]) -> Conversation {
+        let mut conv = Conversation::new();
+        for content in messages {
+            conv.add_event(ConversationMessageAddedEvent {
+                author: Role::User,
+                content: content.to_string(),
+                model: None,
+                language: None,
+                citations: Vec::new(),
+            });
+        }
+        conv
+    }
+
+    #[test]
+    fn test_measure() {
+        let conv = conversation(&[
+            "How do I print in Rust? Show me.",
+            "Use the macro:\n\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n\n- it adds a newline\n- `print!` doesn't (really).",
+        ]);
+        let stats = ConversationStats::measure(&conv);
+        assert_eq!(
+            stats,
+            ConversationStats {
+                messages: 2,
+                prose_words: 18,
+                code_words: 5,
+                sentences: 5,
+            }
+        );
+        assert_eq!(stats.average_sentence_words(), 18.0 / 5.0);
+        assert_eq!(stats.code_ratio(), 5.0 / 23.0);
+        // 18 words at 230 a minute and 5 at 100 a minute
+        assert_eq!(stats.reading_seconds(), 8);
+        assert!(!stats.is_long_read());
+
+        let empty = ConversationStats::measure(&Conversation::new());
+        assert_eq!(
+            (empty.average_sentence_words(), empty.code_ratio()),
+            (0.0, 0.0)
+        );
+        let long = conversation(&[&"word ".repeat(1_150)]);
+        assert!(ConversationStats::measure(&long).is_long_read());
+    }
+}
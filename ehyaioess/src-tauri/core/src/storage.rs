@@ -0,0 +1,164 @@
+//! Disk usage of the app's data by category, and which of it can be reclaimed.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+/// Caches and logs this large are suggested for cleaning; backups are suggested at any size.
+pub const SUGGESTED_CLEANUP_BYTES: u64 = 50 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageCategory {
+    Conversations,
+    Attachments,
+    /// The embeddings cache used for topic clustering; rebuilt on demand.
+    Embeddings,
+    /// Recorded requests, usage records and crash reports.
+    Logs,
+    /// Old history files kept after moving or repairing the history; see
+    /// `Config::history_backups`.
+    Backups,
+}
+
+impl StorageCategory {
+    pub const ALL: [StorageCategory; 5] = [
+        StorageCategory::Conversations,
+        StorageCategory::Attachments,
+        StorageCategory::Embeddings,
+        StorageCategory::Logs,
+        StorageCategory::Backups,
+    ];
+
+    pub fn parse(category: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(category.to_string())).ok()
+    }
+
+    /// Whether the data can be deleted without losing conversations.
+    pub fn is_cleanable(self) -> bool {
+        !matches!(
+            self,
+            StorageCategory::Conversations | StorageCategory::Attachments
+        )
+    }
+
+    /// The files and folders holding the category's data, whether or not they exist yet.
+    pub fn paths(self, config: &Config) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        Ok(match self {
+            StorageCategory::Conversations => vec![
+                PathBuf::from(&config.conversation_history_save_path),
+                Config::get_trash_path()?,
+            ],
+            StorageCategory::Attachments => vec![Config::get_attachments_dir()?],
+            StorageCategory::Embeddings => vec![Config::get_embeddings_path()?],
+            StorageCategory::Logs => vec![
+                Config::get_request_log_path()?,
+                Config::get_usage_log_path()?,
+                Config::get_crash_report_path()?,
+            ],
+            StorageCategory::Backups => config.history_backups.iter().map(PathBuf::from).collect(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CategoryUsage {
+    pub category: StorageCategory,
+    pub bytes: u64,
+    pub files: usize,
+    pub cleanable: bool,
+    pub suggested: bool,
+}
+
+impl CategoryUsage {
+    fn new(category: StorageCategory, bytes: u64, files: usize) -> Self {
+        let suggested = match category {
+            StorageCategory::Backups => files > 0,
+            _ => category.is_cleanable() && bytes >= SUGGESTED_CLEANUP_BYTES,
+        };
+        Self {
+            category,
+            bytes,
+            files,
+            cleanable: category.is_cleanable(),
+            suggested,
+        }
+    }
+}
+
+/// Total size and number of files at `path`, counting folders recursively.
+/// Missing or unreadable paths count as empty.
+pub fn path_size(path: &Path) -> (u64, usize) {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return (0, 0);
+    };
+    if !metadata.is_dir() {
+        return (metadata.len(), 1);
+    }
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return (0, 0);
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| path_size(&entry.path()))
+        .fold((0, 0), |(bytes, files), (b, f)| (bytes + b, files + f))
+}
+
+pub fn usage(
+    config: &Config,
+    category: StorageCategory,
+) -> Result<CategoryUsage, Box<dyn std::error::Error>> {
+    let (bytes, files) = category
+        .paths(config)?
+        .iter()
+        .map(|path| path_size(path))
+        .fold((0, 0), |(bytes, files), (b, f)| (bytes + b, files + f));
+    Ok(CategoryUsage::new(category, bytes, files))
+}
+
+pub fn report(config: &Config) -> Result<Vec<CategoryUsage>, Box<dyn std::error::Error>> {
+    StorageCategory::ALL
+        .into_iter()
+        .map(|category| usage(config, category))
+        .collect()
+}
+
+/// Deletes the old history files and forgets them; the caller saves the config.
+pub fn delete_backups(config: &mut Config) -> Result<(), std::io::Error> {
+    while let Some(path) = config.history_backups.last() {
+        match std::fs::remove_file(path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        config.history_backups.pop();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_path_size() {
+        let dir = std::env::temp_dir().join(format!("storage-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("a.bin"), [0u8; 10]).unwrap();
+        std::fs::write(dir.join("nested").join("b.bin"), [0u8; 5]).unwrap();
+
+        assert_eq!(path_size(&dir), (15, 2));
+        assert_eq!(path_size(&dir.join("a.bin")), (10, 1));
+        assert_eq!(path_size(&dir.join("missing")), (0, 0));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!CategoryUsage::new(StorageCategory::Conversations, u64::MAX, 1).suggested);
+        assert!(!CategoryUsage::new(StorageCategory::Embeddings, 1024, 1).suggested);
+        assert!(CategoryUsage::new(StorageCategory::Logs, SUGGESTED_CLEANUP_BYTES, 2).suggested);
+        assert!(CategoryUsage::new(StorageCategory::Backups, 1024, 1).suggested);
+        assert_eq!(StorageCategory::parse("logs"), Some(StorageCategory::Logs));
+        assert_eq!(StorageCategory::parse("caches"), None);
+    }
+}
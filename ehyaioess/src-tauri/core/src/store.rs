@@ -0,0 +1,470 @@
+//! The conversations held in memory. Every conversation is listed from a summary, but only
+//! the most recently used are kept decoded; the rest are held as their compressed JSON and
+//! decoded again when next accessed, which bounds memory however long the history grows.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use serde_json::value::RawValue;
+use uuid::Uuid;
+
+use crate::models::{self, Conversation, StoredConversation, StoredConversationRef};
+
+/// Decoded conversations kept at most before the least recently used is compressed again.
+pub const HYDRATED_LIMIT: usize = 32;
+/// Conversations are compressed on every eviction, so speed matters more than size here.
+const LEVEL: i32 = 1;
+
+/// What lists of conversations need, available without decoding their events.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationSummary {
+    pub id: Uuid,
+    /// The title the conversation was given; see `title` for what's shown.
+    pub explicit_title: Option<String>,
+    pub tags: Vec<String>,
+    /// Unix seconds of the first event.
+    pub created_at: i64,
+    pub last_activity: i64,
+    pub message_count: usize,
+    pub archived: bool,
+}
+
+impl ConversationSummary {
+    pub fn of(conv: &Conversation) -> Self {
+        Self {
+            id: conv.id,
+            explicit_title: conv.explicit_title().cloned(),
+            tags: conv.tags().to_vec(),
+            created_at: conv.history.first().map_or(0, |record| record.timestamp),
+            last_activity: conv.last_activity(),
+            message_count: conv.message_count(),
+            archived: conv.is_archived(),
+        }
+    }
+
+    /// Like `Conversation::get_title`, with the default in the locale at the time of asking
+    /// rather than when the summary was made.
+    pub fn title(&self) -> String {
+        self.explicit_title
+            .clone()
+            .unwrap_or_else(models::default_title)
+    }
+}
+
+#[derive(Clone)]
+enum Entry {
+    Hydrated(Arc<Conversation>),
+    /// The conversation as the history file stores it, zstd-compressed.
+    Stored {
+        summary: ConversationSummary,
+        compressed: Arc<[u8]>,
+    },
+}
+
+impl Entry {
+    fn stored(conv: &Conversation) -> Result<Self, std::io::Error> {
+        let json = serde_json::to_vec(&StoredConversationRef::of(conv)?)?;
+        Ok(Entry::Stored {
+            summary: ConversationSummary::of(conv),
+            compressed: zstd::encode_all(json.as_slice(), LEVEL)?.into(),
+        })
+    }
+
+    /// The conversation as the history file stores it.
+    fn json(&self) -> Result<Vec<u8>, std::io::Error> {
+        match self {
+            Entry::Hydrated(conv) => Ok(serde_json::to_vec(&StoredConversationRef::of(conv)?)?),
+            Entry::Stored { compressed, .. } => zstd::decode_all(&compressed[..]),
+        }
+    }
+
+    fn conversation(&self) -> Result<Arc<Conversation>, std::io::Error> {
+        match self {
+            Entry::Hydrated(conv) => Ok(conv.clone()),
+            Entry::Stored { .. } => {
+                let stored: StoredConversation = serde_json::from_slice(&self.json()?)?;
+                Ok(Arc::new(stored.into_conversation()?))
+            }
+        }
+    }
+
+    fn summary(&self) -> ConversationSummary {
+        match self {
+            Entry::Hydrated(conv) => ConversationSummary::of(conv),
+            Entry::Stored { summary, .. } => summary.clone(),
+        }
+    }
+}
+
+/// A conversation compressed ahead of being added, so many can be compressed at once on
+/// other threads, as when loading the history.
+pub struct StoredEntry {
+    id: Uuid,
+    entry: Entry,
+}
+
+impl StoredEntry {
+    pub fn new(id: Uuid, conv: &Conversation) -> Result<Self, std::io::Error> {
+        Ok(Self {
+            id,
+            entry: Entry::stored(conv)?,
+        })
+    }
+}
+
+#[derive(Clone, Default)]
+struct Inner {
+    entries: HashMap<Uuid, Entry>,
+    /// Ids of the decoded entries, least recently used first.
+    recency: VecDeque<Uuid>,
+}
+
+impl Inner {
+    fn touch(&mut self, id: Uuid) {
+        if let Some(position) = self.recency.iter().position(|used| *used == id) {
+            self.recency.remove(position);
+        }
+        self.recency.push_back(id);
+    }
+
+    fn hydrate(&mut self, id: Uuid, limit: usize) -> Option<&mut Arc<Conversation>> {
+        let entry = self.entries.get(&id)?;
+        if matches!(entry, Entry::Stored { .. }) {
+            let conv = match entry.conversation() {
+                Ok(conv) => conv,
+                Err(e) => {
+                    eprintln!("Failed to decode conversation {}: {}", id, e);
+                    return None;
+                }
+            };
+            self.entries.insert(id, Entry::Hydrated(conv));
+        }
+        self.touch(id);
+        self.evict(limit);
+        match self.entries.get_mut(&id) {
+            Some(Entry::Hydrated(conv)) => Some(conv),
+            _ => None,
+        }
+    }
+
+    fn evict(&mut self, limit: usize) {
+        while self.recency.len() > limit {
+            let Some(id) = self.recency.pop_front() else {
+                return;
+            };
+            let Some(Entry::Hydrated(conv)) = self.entries.get(&id) else {
+                continue;
+            };
+            match Entry::stored(conv) {
+                Ok(stored) => {
+                    self.entries.insert(id, stored);
+                }
+                // Kept decoded; it's only memory.
+                Err(e) => eprintln!("Failed to compress conversation {}: {}", id, e),
+            }
+        }
+    }
+}
+
+pub struct ConversationStore {
+    inner: Mutex<Inner>,
+    limit: usize,
+}
+
+impl ConversationStore {
+    pub fn new() -> Self {
+        Self::with_limit(HYDRATED_LIMIT)
+    }
+
+    /// A store keeping at most `limit` conversations decoded, and always at least one.
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            inner: Mutex::default(),
+            limit: limit.max(1),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        self.inner.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains_key(&self, id: &Uuid) -> bool {
+        self.lock().entries.contains_key(id)
+    }
+
+    pub fn keys(&self) -> Vec<Uuid> {
+        self.lock().entries.keys().copied().collect()
+    }
+
+    /// How many conversations are decoded at the moment.
+    pub fn hydrated_count(&self) -> usize {
+        self.lock().recency.len()
+    }
+
+    /// The conversation, decoding it and keeping it decoded when it isn't already.
+    pub fn get(&self, id: &Uuid) -> Option<Arc<Conversation>> {
+        let limit = self.limit;
+        self.lock().hydrate(*id, limit).map(|conv| conv.clone())
+    }
+
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Conversation> {
+        let limit = self.limit;
+        self.inner_mut().hydrate(*id, limit).map(Arc::make_mut)
+    }
+
+    pub fn insert(&mut self, id: Uuid, conv: Conversation) {
+        let limit = self.limit;
+        let inner = self.inner_mut();
+        inner.entries.insert(id, Entry::Hydrated(Arc::new(conv)));
+        inner.touch(id);
+        inner.evict(limit);
+    }
+
+    /// Adds conversations without keeping them decoded.
+    pub fn insert_stored(&mut self, stored: impl IntoIterator<Item = StoredEntry>) {
+        let inner = self.inner_mut();
+        for StoredEntry { id, entry } in stored {
+            inner.recency.retain(|used| *used != id);
+            inner.entries.insert(id, entry);
+        }
+    }
+
+    pub fn remove(&mut self, id: &Uuid) -> bool {
+        let inner = self.inner_mut();
+        inner.recency.retain(|used| used != id);
+        inner.entries.remove(id).is_some()
+    }
+
+    /// Keeps only the conversations whose id `keep` accepts.
+    pub fn retain_ids(&mut self, mut keep: impl FnMut(&Uuid) -> bool) {
+        let inner = self.inner_mut();
+        inner.entries.retain(|id, _| keep(id));
+        let Inner { entries, recency } = inner;
+        recency.retain(|id| entries.contains_key(id));
+    }
+
+    pub fn clear(&mut self) {
+        *self.inner_mut() = Inner::default();
+    }
+
+    /// Every conversation, in no particular order. Conversations that aren't decoded are
+    /// decoded one at a time as the iterator reaches them and dropped again afterwards, so a
+    /// pass over the whole history doesn't keep it all in memory.
+    pub fn iter(&self) -> impl Iterator<Item = (Uuid, Arc<Conversation>)> {
+        let entries: Vec<(Uuid, Entry)> = self
+            .lock()
+            .entries
+            .iter()
+            .map(|(id, entry)| (*id, entry.clone()))
+            .collect();
+        entries
+            .into_iter()
+            .filter_map(|(id, entry)| match entry.conversation() {
+                Ok(conv) => Some((id, conv)),
+                Err(e) => {
+                    eprintln!("Failed to decode conversation {}: {}", id, e);
+                    None
+                }
+            })
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = Arc<Conversation>> {
+        self.iter().map(|(_, conv)| conv)
+    }
+
+    /// Summaries of every conversation, without decoding any.
+    pub fn summaries(&self) -> Vec<ConversationSummary> {
+        self.lock().entries.values().map(Entry::summary).collect()
+    }
+
+    /// Takes over conversations that differ in `external`, returning the ids that changed and
+    /// whether each was removed. Compares what the history file would hold for each, so
+    /// neither side needs decoding.
+    pub fn reload_from(&mut self, external: ConversationStore) -> Vec<(Uuid, bool)> {
+        let external = external
+            .inner
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut changed = Vec::new();
+        self.retain_ids(|id| {
+            let kept = external.entries.contains_key(id);
+            if !kept {
+                changed.push((*id, true));
+            }
+            kept
+        });
+        let inner = self.inner_mut();
+        for (id, entry) in external.entries {
+            let unchanged = inner
+                .entries
+                .get(&id)
+                .is_some_and(|current| current.json().ok() == entry.json().ok());
+            if !unchanged {
+                changed.push((id, false));
+                inner.recency.retain(|used| *used != id);
+                let entry = match entry {
+                    // Decoded in `external` only because it was inserted there.
+                    Entry::Hydrated(conv) => Entry::stored(&conv).unwrap_or(Entry::Hydrated(conv)),
+                    stored => stored,
+                };
+                if matches!(entry, Entry::Hydrated(_)) {
+                    inner.recency.push_back(id);
+                }
+                inner.entries.insert(id, entry);
+            }
+        }
+        let limit = self.limit;
+        self.inner_mut().evict(limit);
+        changed
+    }
+}
+
+impl Default for ConversationStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for ConversationStore {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Mutex::new(self.lock().clone()),
+            limit: self.limit,
+        }
+    }
+}
+
+impl FromIterator<(Uuid, Conversation)> for ConversationStore {
+    fn from_iter<I: IntoIterator<Item = (Uuid, Conversation)>>(iter: I) -> Self {
+        let mut store = Self::new();
+        for (id, conv) in iter {
+            store.insert(id, conv);
+        }
+        store
+    }
+}
+
+/// Serializes as the history file stores it, copying conversations that aren't decoded
+/// across without decoding them.
+impl Serialize for ConversationStore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+
+        let entries: Vec<(Uuid, Entry)> = self
+            .lock()
+            .entries
+            .iter()
+            .map(|(id, entry)| (*id, entry.clone()))
+            .collect();
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for (id, entry) in entries {
+            match entry {
+                Entry::Hydrated(conv) => {
+                    let stored = StoredConversationRef::of(&conv).map_err(S::Error::custom)?;
+                    map.serialize_entry(&id, &stored)?
+                }
+                Entry::Stored { .. } => {
+                    let json = entry.json().map_err(S::Error::custom)?;
+                    let json = String::from_utf8(json).map_err(S::Error::custom)?;
+                    let raw = RawValue::from_string(json).map_err(S::Error::custom)?;
+                    map.serialize_entry(&id, &raw)?;
+                }
+            }
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::ConversationTitleChangedEvent;
+
+    #[test]
+    fn test_conversation_store() {
+        let mut store = ConversationStore::with_limit(2);
+        let mut ids = Vec::new();
+        for title in ["a", "b", "c"] {
+            let mut conv = Conversation::new();
+            conv.add_event(ConversationTitleChangedEvent {
+                new_title: title.to_string(),
+            });
+            ids.push(conv.id);
+            store.insert(conv.id, conv);
+        }
+        // The first conversation was compressed to make room for the third.
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.hydrated_count(), 2);
+        let mut titles: Vec<String> = store.summaries().iter().map(|s| s.title()).collect();
+        titles.sort();
+        assert_eq!(titles, ["a", "b", "c"]);
+
+        // Iterating decodes without keeping anything decoded.
+        assert_eq!(store.values().count(), 3);
+        assert_eq!(store.hydrated_count(), 2);
+
+        // Using it decodes it again, in place of the least recently used.
+        store
+            .get_mut(&ids[0])
+            .unwrap()
+            .add_event(ConversationTitleChangedEvent {
+                new_title: "A".to_string(),
+            });
+        assert_eq!(store.get(&ids[0]).unwrap().get_title().as_ref(), "A");
+        assert_eq!(store.hydrated_count(), 2);
+        assert!(store.get(&Uuid::new_v4()).is_none());
+
+        // Saved and loaded, every conversation comes back whichever way it was held.
+        let json = serde_json::to_string(&store).unwrap();
+        let mut stored: HashMap<Uuid, StoredConversation> = serde_json::from_str(&json).unwrap();
+        assert_eq!(stored.len(), 3);
+        let conv = stored.remove(&ids[1]).unwrap().into_conversation().unwrap();
+        assert_eq!(conv.get_title().as_ref(), "b");
+
+        let mut external = store.clone();
+        external.remove(&ids[2]);
+        external
+            .get_mut(&ids[1])
+            .unwrap()
+            .add_event(ConversationTitleChangedEvent {
+                new_title: "B".to_string(),
+            });
+        let mut changed = store.reload_from(external);
+        changed.sort();
+        let mut expected = vec![(ids[1], false), (ids[2], true)];
+        expected.sort();
+        assert_eq!(changed, expected);
+        assert_eq!(store.get(&ids[1]).unwrap().get_title().as_ref(), "B");
+        assert!(!store.contains_key(&ids[2]));
+    }
+
+    #[test]
+    fn test_summary_title() {
+        let mut conv = Conversation::new();
+        // Without a title of its own, the default is looked up when asked for, so it follows
+        // the locale.
+        let summary = ConversationSummary::of(&conv);
+        assert_eq!(summary.explicit_title, None);
+        assert_eq!(summary.title(), models::default_title());
+
+        conv.add_event(ConversationTitleChangedEvent {
+            new_title: "a".to_string(),
+        });
+        assert_eq!(ConversationSummary::of(&conv).title(), "a");
+    }
+}
@@ -0,0 +1,465 @@
+//! Syncing conversation event logs through a remote store.
+//!
+//! Backends only store and fetch named objects: one log per conversation plus an index of how
+//! far each log goes, so a sync only downloads the logs that changed. When both sides added
+//! different events to a conversation, both branches are kept as a conflict until resolved.
+
+use core::fmt;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{config::ProxyConfig, models::Conversation, provider, store::ConversationStore};
+
+const INDEX_KEY: &str = "index.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SyncBackendConfig {
+    /// A WebDAV folder, e.g. `https://cloud.example.com/remote.php/dav/files/me/ehyaioess`.
+    WebDav {
+        url: String,
+        username: Option<String>,
+        password: Option<String>,
+    },
+    /// A folder kept in sync by other means, such as a network share.
+    Folder { path: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SyncConfig {
+    /// Unset to disable sync.
+    #[serde(default)]
+    pub backend: Option<SyncBackendConfig>,
+}
+
+#[derive(Debug)]
+pub enum SyncError {
+    Http(reqwest::Error),
+    Status(u16),
+    Io(std::io::Error),
+    Parse(String),
+}
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyncError::Http(e) => write!(f, "HTTP error: {}", e),
+            SyncError::Status(status) => write!(f, "Sync server responded with {}", status),
+            SyncError::Io(e) => write!(f, "I/O error: {}", e),
+            SyncError::Parse(e) => write!(f, "Failed to parse synced data: {}", e),
+        }
+    }
+}
+impl std::error::Error for SyncError {}
+
+impl From<reqwest::Error> for SyncError {
+    fn from(e: reqwest::Error) -> Self {
+        SyncError::Http(e)
+    }
+}
+impl From<std::io::Error> for SyncError {
+    fn from(e: std::io::Error) -> Self {
+        SyncError::Io(e)
+    }
+}
+impl From<serde_json::Error> for SyncError {
+    fn from(e: serde_json::Error) -> Self {
+        SyncError::Parse(e.to_string())
+    }
+}
+
+/// A remote store of named objects.
+#[async_trait]
+pub trait SyncBackend: Send + Sync {
+    /// The object's contents, or `None` if it doesn't exist yet.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, SyncError>;
+    async fn put(&self, key: &str, body: Vec<u8>) -> Result<(), SyncError>;
+}
+
+pub struct WebDavBackend {
+    client: reqwest::Client,
+    url: String,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl WebDavBackend {
+    fn request(&self, method: reqwest::Method, key: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}/{}", self.url.trim_end_matches('/'), key);
+        let request = self.client.request(method, url);
+        match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_deref()),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl SyncBackend for WebDavBackend {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, SyncError> {
+        let response = self.request(reqwest::Method::GET, key).send().await?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(SyncError::Status(status.as_u16()));
+        }
+        Ok(Some(response.bytes().await?.to_vec()))
+    }
+
+    async fn put(&self, key: &str, body: Vec<u8>) -> Result<(), SyncError> {
+        let response = self
+            .request(reqwest::Method::PUT, key)
+            .body(body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(SyncError::Status(response.status().as_u16()));
+        }
+        Ok(())
+    }
+}
+
+pub struct FolderBackend {
+    path: PathBuf,
+}
+
+#[async_trait]
+impl SyncBackend for FolderBackend {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, SyncError> {
+        match std::fs::read(self.path.join(key)) {
+            Ok(body) => Ok(Some(body)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put(&self, key: &str, body: Vec<u8>) -> Result<(), SyncError> {
+        std::fs::create_dir_all(&self.path)?;
+        std::fs::write(self.path.join(key), body)?;
+        Ok(())
+    }
+}
+
+/// Builds the configured backend; WebDAV requests go through the configured proxy.
+pub fn backend(
+    config: &SyncBackendConfig,
+    proxy: &ProxyConfig,
+) -> Result<Box<dyn SyncBackend>, SyncError> {
+    Ok(match config {
+        SyncBackendConfig::WebDav {
+            url,
+            username,
+            password,
+        } => Box::new(WebDavBackend {
+            client: provider::build_http_client(proxy)?,
+            url: url.clone(),
+            username: username.clone(),
+            password: password.clone(),
+        }),
+        SyncBackendConfig::Folder { path } => Box::new(FolderBackend { path: path.into() }),
+    })
+}
+
+/// How far a conversation's log goes, without downloading it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct IndexEntry {
+    events: usize,
+    last_event: Option<Uuid>,
+}
+
+impl IndexEntry {
+    fn of(conv: &Conversation) -> Self {
+        Self {
+            events: conv.history.len(),
+            last_event: conv.history.last().map(|record| record.id),
+        }
+    }
+}
+
+type Index = HashMap<Uuid, IndexEntry>;
+
+fn log_key(conversation_id: Uuid) -> String {
+    format!("{}.json", conversation_id)
+}
+
+#[derive(Debug, Default)]
+pub struct SyncOutcome {
+    /// Number of conversation logs uploaded.
+    pub pushed: usize,
+    /// Remote logs that replace the local ones.
+    pub pulled: Vec<Conversation>,
+    /// Remote branches of conversations that diverged from the local ones.
+    pub conflicts: Vec<Conversation>,
+}
+
+enum Resolution {
+    Same,
+    Push,
+    Pull(Conversation),
+    Diverged(Conversation),
+}
+
+fn resolve(local: &Conversation, remote: Conversation) -> Resolution {
+    let local_ids: Vec<Uuid> = local.history.iter().map(|record| record.id).collect();
+    let remote_ids: Vec<Uuid> = remote.history.iter().map(|record| record.id).collect();
+    if local_ids == remote_ids {
+        Resolution::Same
+    } else if remote_ids.starts_with(&local_ids) {
+        Resolution::Pull(remote)
+    } else if local_ids.starts_with(&remote_ids) {
+        Resolution::Push
+    } else {
+        Resolution::Diverged(remote)
+    }
+}
+
+async fn fetch<T: serde::de::DeserializeOwned>(
+    backend: &dyn SyncBackend,
+    key: &str,
+) -> Result<Option<T>, SyncError> {
+    match backend.get(key).await? {
+        Some(body) => Ok(Some(serde_json::from_slice(&body)?)),
+        None => Ok(None),
+    }
+}
+
+/// Pushes local changes and fetches remote ones and conflicts, which the caller applies.
+/// Conversations are never deleted by a sync.
+pub async fn sync(
+    backend: &dyn SyncBackend,
+    local: &ConversationStore,
+) -> Result<SyncOutcome, SyncError> {
+    let mut index: Index = fetch(backend, INDEX_KEY).await?.unwrap_or_default();
+    let mut outcome = SyncOutcome::default();
+
+    for (id, conv) in local.iter() {
+        let resolution = match index.get(&id) {
+            None => Resolution::Push,
+            Some(entry) if *entry == IndexEntry::of(&conv) => Resolution::Same,
+            Some(_) => match fetch(backend, &log_key(id)).await? {
+                Some(remote) => resolve(&conv, remote),
+                None => Resolution::Push,
+            },
+        };
+        match resolution {
+            Resolution::Same => {}
+            Resolution::Push => {
+                backend
+                    .put(&log_key(id), serde_json::to_vec(&*conv)?)
+                    .await?;
+                index.insert(id, IndexEntry::of(&conv));
+                outcome.pushed += 1;
+            }
+            Resolution::Pull(remote) => outcome.pulled.push(remote),
+            Resolution::Diverged(remote) => outcome.conflicts.push(remote),
+        }
+    }
+    let remote_only: Vec<Uuid> = index
+        .keys()
+        .filter(|id| !local.contains_key(id))
+        .copied()
+        .collect();
+    for id in remote_only {
+        if let Some(remote) = fetch(backend, &log_key(id)).await? {
+            outcome.pulled.push(remote);
+        }
+    }
+
+    if outcome.pushed > 0 {
+        backend.put(INDEX_KEY, serde_json::to_vec(&index)?).await?;
+    }
+    Ok(outcome)
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Keep the events of both branches, ordered by when they happened.
+    MergeByTimestamp,
+    KeepLocal,
+    KeepRemote,
+}
+
+impl ConflictStrategy {
+    pub fn parse(strategy: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(strategy.to_string())).ok()
+    }
+}
+
+/// The conversation that replaces both branches.
+pub fn resolve_conflict(
+    local: &Conversation,
+    remote: Conversation,
+    strategy: ConflictStrategy,
+) -> Conversation {
+    match strategy {
+        ConflictStrategy::KeepLocal => local.clone(),
+        ConflictStrategy::KeepRemote => remote,
+        ConflictStrategy::MergeByTimestamp => {
+            let mut history = local.history.clone();
+            for record in remote.history {
+                if !history.iter().any(|existing| existing.id == record.id) {
+                    history.push(record);
+                }
+            }
+            // Stable, so events with the same timestamp keep their order.
+            history.sort_by_key(|record| record.timestamp);
+            Conversation {
+                id: local.id,
+                history,
+            }
+        }
+    }
+}
+
+/// Uploads a resolved conversation over the remote branch.
+pub async fn push_resolved(
+    backend: &dyn SyncBackend,
+    conv: &Conversation,
+) -> Result<(), SyncError> {
+    backend
+        .put(&log_key(conv.id), serde_json::to_vec(conv)?)
+        .await?;
+    let mut index: Index = fetch(backend, INDEX_KEY).await?.unwrap_or_default();
+    index.insert(conv.id, IndexEntry::of(conv));
+    backend.put(INDEX_KEY, serde_json::to_vec(&index)?).await
+}
+
+/// Remote branches of diverged conversations, kept on disk until resolved.
+pub struct SyncConflicts {
+    path: Mutex<PathBuf>,
+    remotes: Mutex<HashMap<Uuid, Conversation>>,
+}
+
+impl SyncConflicts {
+    fn read_remotes(path: &Path) -> HashMap<Uuid, Conversation> {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            remotes: Mutex::new(Self::read_remotes(&path)),
+            path: Mutex::new(path),
+        }
+    }
+
+    /// Switches to the conflicts at `path`, e.g. after changing workspace profile.
+    pub fn reopen(&self, path: PathBuf) {
+        let mut remotes = self.remotes.lock().unwrap();
+        *remotes = Self::read_remotes(&path);
+        *self.path.lock().unwrap() = path;
+    }
+
+    fn write(&self, remotes: &HashMap<Uuid, Conversation>) -> Result<(), std::io::Error> {
+        let path = self.path.lock().unwrap();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&*path, serde_json::to_string(remotes)?)
+    }
+
+    pub fn conversation_ids(&self) -> Vec<Uuid> {
+        self.remotes.lock().unwrap().keys().copied().collect()
+    }
+
+    /// Records the remote branch, replacing an older one for the same conversation.
+    pub fn insert(&self, remote: Conversation) -> Result<(), std::io::Error> {
+        let mut remotes = self.remotes.lock().unwrap();
+        remotes.insert(remote.id, remote);
+        self.write(&remotes)
+    }
+
+    pub fn get(&self, conversation_id: Uuid) -> Option<Conversation> {
+        self.remotes.lock().unwrap().get(&conversation_id).cloned()
+    }
+
+    pub fn remove(&self, conversation_id: Uuid) -> Result<(), std::io::Error> {
+        let mut remotes = self.remotes.lock().unwrap();
+        if remotes.remove(&conversation_id).is_some() {
+            self.write(&remotes)?;
+        }
+        Ok(())
+    }
+
+    /// Drops every conflict, both in memory and on disk.
+    pub fn clear(&self) -> Result<(), std::io::Error> {
+        self.remotes.lock().unwrap().clear();
+        let path = self.path.lock().unwrap();
+        if path.exists() {
+            std::fs::remove_file(&*path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::ConversationTitleChangedEvent;
+
+    /// Retitles `conv`, `delay` seconds after it was created.
+    fn titled(conv: &mut Conversation, title: &str, delay: i64) {
+        let timestamp = conv.history[0].timestamp + delay;
+        conv.add_event_at(
+            ConversationTitleChangedEvent {
+                new_title: title.to_string(),
+            },
+            timestamp,
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync() {
+        let dir = std::env::temp_dir().join(format!("sync-{}", Uuid::new_v4()));
+        let backend = FolderBackend { path: dir.clone() };
+
+        // The first device pushes everything.
+        let shared = Conversation::new();
+        let mut first = ConversationStore::new();
+        first.insert(shared.id, shared.clone());
+        let outcome = sync(&backend, &first).await.unwrap();
+        assert_eq!((outcome.pushed, outcome.pulled.len()), (1, 0));
+
+        // The second device extends it; the first pulls the extension.
+        let mut second = first.clone();
+        titled(second.get_mut(&shared.id).unwrap(), "From second", 10);
+        assert_eq!(sync(&backend, &second).await.unwrap().pushed, 1);
+        let outcome = sync(&backend, &first).await.unwrap();
+        assert_eq!(outcome.pulled.len(), 1);
+        assert_eq!(outcome.pulled[0].get_title().as_ref(), "From second");
+
+        // Diverged logs become a conflict instead of overwriting either side.
+        titled(first.get_mut(&shared.id).unwrap(), "From first", 20);
+        let outcome = sync(&backend, &first).await.unwrap();
+        assert_eq!(outcome.pushed, 0);
+        assert_eq!(outcome.conflicts.len(), 1);
+        let remote = outcome.conflicts[0].clone();
+
+        let local = first.get(&shared.id).unwrap();
+        let merged = resolve_conflict(&local, remote.clone(), ConflictStrategy::MergeByTimestamp);
+        assert_eq!(merged.history.len(), 3);
+        assert_eq!(merged.get_title().as_ref(), "From first");
+        let kept = resolve_conflict(&local, remote, ConflictStrategy::KeepRemote);
+        assert_eq!(kept.get_title().as_ref(), "From second");
+
+        push_resolved(&backend, &merged).await.unwrap();
+        first.insert(shared.id, merged);
+        let outcome = sync(&backend, &first).await.unwrap();
+        assert!(outcome.conflicts.is_empty() && outcome.pulled.is_empty());
+        let outcome = sync(&backend, &second).await.unwrap();
+        assert_eq!(outcome.pulled[0].history.len(), 3);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+//! Topic tags on conversations, assigned by the user or, when enabled, by the model once a
+//! conversation has had a few exchanges.
+
+use chatgpt::types::{ChatMessage, Role};
+
+use crate::{bookmarks::snippet, models::Conversation};
+
+pub const MAX_TAGS: usize = 3;
+const MAX_TAG_CHARS: usize = 32;
+/// Messages a conversation needs before it's worth tagging automatically, i.e. three exchanges.
+pub const AUTO_TAG_MIN_MESSAGES: usize = 6;
+/// Per-message limit on what the model sees, keeping tagging requests cheap.
+const TAGGING_MESSAGE_CHARS: usize = 400;
+
+/// Lowercase words joined by `-`, or None when nothing usable is left.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let words: Vec<String> = tag
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let tag: String = words.join("-").chars().take(MAX_TAG_CHARS).collect();
+    let tag = tag.trim_end_matches('-');
+    (!tag.is_empty()).then(|| tag.to_string())
+}
+
+/// Normalized, without duplicates and at most `MAX_TAGS` long.
+pub fn normalize_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.into_iter().filter_map(normalize_tag) {
+        if normalized.len() == MAX_TAGS {
+            break;
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// Conversations automatic tagging should look at: long enough and never tagged before.
+pub fn needs_auto_tags(conv: &Conversation) -> bool {
+    conv.message_count() >= AUTO_TAG_MIN_MESSAGES && !conv.has_tag_history()
+}
+
+/// The request asking for the conversation's topics, answered as a comma-separated list.
+pub fn tagging_messages(conv: &Conversation) -> Vec<ChatMessage> {
+    let transcript = conv
+        .to_chat_history()
+        .iter()
+        .map(|message| {
+            format!(
+                "{:?}: {}",
+                message.role,
+                snippet(&message.content, TAGGING_MESSAGE_CHARS)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    vec![
+        ChatMessage {
+            role: Role::System,
+            content: format!(
+                "Assign 1 to {} short topic tags to the following conversation. Reply with only \
+                 the tags, separated by commas.",
+                MAX_TAGS
+            ),
+        },
+        ChatMessage {
+            role: Role::User,
+            content: transcript,
+        },
+    ]
+}
+
+/// Tags from the model's reply to `tagging_messages`.
+pub fn parse_tags(reply: &str) -> Vec<String> {
+    normalize_tags(reply.split([',', '\n']).map(|tag| tag.trim_start_matches(['#', '-', '*'])))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(
+            parse_tags("Rust, #Async Programming,rust\n- Web APIs, extra"),
+            vec!["rust", "async-programming", "web-apis"]
+        );
+        assert_eq!(normalize_tag("  C++ / Templates "), Some("c-templates".to_string()));
+        assert_eq!(normalize_tag(" -- "), None);
+        assert!(parse_tags("").is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+//! Whole-profile export ("takeout") and erasure of user data.
+
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use chatgpt::types::Role;
+use serde::Serialize;
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::{
+    compression,
+    config::Config,
+    export,
+    models::{ConversationEvent, ConversationManager},
+};
+
+/// Must be passed verbatim to `delete_all_data` so a stray call can't wipe the history.
+pub const DELETE_ALL_DATA_CONFIRMATION: &str = "DELETE ALL DATA";
+
+#[derive(Debug, Serialize, Default)]
+pub struct UsageStats {
+    pub conversation_count: usize,
+    pub message_count: usize,
+    pub user_message_count: usize,
+    pub assistant_message_count: usize,
+    pub system_message_count: usize,
+    pub total_characters: usize,
+}
+
+pub fn usage_stats(mgr: &ConversationManager) -> UsageStats {
+    let mut stats = UsageStats {
+        conversation_count: mgr.conversations.len(),
+        ..Default::default()
+    };
+    for conv in mgr.conversations.values() {
+        for record in &conv.history {
+            if let ConversationEvent::MessageAdded(msg) = &record.event {
+                stats.message_count += 1;
+                stats.total_characters += msg.content.chars().count();
+                match msg.author {
+                    Role::User => stats.user_message_count += 1,
+                    Role::Assistant => stats.assistant_message_count += 1,
+                    Role::System => stats.system_message_count += 1,
+                }
+            }
+        }
+    }
+    stats
+}
+
+/// Writes every conversation (JSON and Markdown), attachments, redacted settings and usage
+/// stats into a zip at `target_path`, returning the number of conversations exported.
+/// `on_progress(done, total)` is called after each conversation and attachment.
+pub fn export_all_data(
+    mgr: &ConversationManager,
+    config: &Config,
+    target_path: &str,
+    mut on_progress: impl FnMut(usize, usize),
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut zip = ZipWriter::new(File::create(target_path)?);
+    let options = FileOptions::default();
+    let attachments_dir = Config::get_attachments_dir()?;
+    let attachments = if attachments_dir.exists() {
+        list_files_recursive(&attachments_dir)?
+    } else {
+        Vec::new()
+    };
+    let total = mgr.conversations.len() + attachments.len();
+    let mut done = 0;
+
+    for (id, conv) in mgr.conversations.iter() {
+        zip.start_file(format!("conversations/{}.json", id), options)?;
+        zip.write_all(export::conversation_to_json(&conv)?.as_bytes())?;
+        zip.start_file(format!("conversations/{}.md", id), options)?;
+        zip.write_all(export::conversation_to_markdown(&conv).as_bytes())?;
+        done += 1;
+        on_progress(done, total);
+    }
+
+    for path in attachments {
+        // Exported as they're referenced, decompressed.
+        let name = compression::attachment_name(&path);
+        let relative = name.strip_prefix(&attachments_dir)?;
+        let name = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        zip.start_file(format!("attachments/{}", name), options)?;
+        zip.write_all(&compression::read(&path)?)?;
+        done += 1;
+        on_progress(done, total);
+    }
+
+    zip.start_file("settings.json", options)?;
+    zip.write_all(serde_json::to_string_pretty(&config.redacted())?.as_bytes())?;
+    zip.start_file("usage_stats.json", options)?;
+    zip.write_all(serde_json::to_string_pretty(&usage_stats(mgr))?.as_bytes())?;
+
+    zip.finish()?;
+    Ok(mgr.conversations.len())
+}
+
+fn list_files_recursive(dir: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(list_files_recursive(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+pub fn delete_attachments() -> Result<(), Box<dyn std::error::Error>> {
+    let attachments_dir = Config::get_attachments_dir()?;
+    if attachments_dir.exists() {
+        std::fs::remove_dir_all(attachments_dir)?;
+    }
+    Ok(())
+}
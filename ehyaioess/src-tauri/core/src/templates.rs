@@ -0,0 +1,229 @@
+//! Conversation templates: the title, settings and starter messages a new conversation starts
+//! out with, for chats that are had again and again.
+
+use std::{io, path::Path};
+
+use chatgpt::types::Role;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    compression,
+    models::{
+        Conversation, ConversationMessageAddedEvent, ConversationSetting,
+        ConversationSettingChangedEvent, ConversationTitleChangedEvent,
+    },
+    validation,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StarterMessage {
+    pub author: Role,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationTemplate {
+    pub id: Uuid,
+    pub name: String,
+    /// Title of the new conversation, with `{date}` and `{time}` replaced by the local date
+    /// and time. Empty for the default title.
+    pub title_pattern: String,
+    /// Empty to use the one from the config.
+    pub system_prompt: String,
+    /// Empty to use the one from the config.
+    pub model: String,
+    pub auto_respond: bool,
+    pub stop_sequences: Vec<String>,
+    pub assistant_prefix: String,
+    pub language: String,
+    /// Added to the new conversation in order, before anything is sent.
+    pub starter_messages: Vec<StarterMessage>,
+}
+
+impl ConversationTemplate {
+    /// The title pattern filled in for `now`.
+    pub fn title(&self, now: DateTime<Local>) -> String {
+        let title = self
+            .title_pattern
+            .replace("{date}", &now.format("%Y-%m-%d").to_string())
+            .replace("{time}", &now.format("%H:%M").to_string());
+        validation::imported_title(&title)
+    }
+
+    /// A new conversation set up the way the template says, titled for the current time.
+    pub fn instantiate(&self) -> Conversation {
+        self.instantiate_at(Local::now())
+    }
+
+    /// A new conversation set up the way the template says. Settings left at their default
+    /// aren't recorded, so the conversation follows later changes to the defaults.
+    pub fn instantiate_at(&self, now: DateTime<Local>) -> Conversation {
+        let mut conv = Conversation::from_template(&self.name);
+        let title = self.title(now);
+        if !title.is_empty() {
+            conv.add_event(ConversationTitleChangedEvent { new_title: title });
+        }
+        let settings = [
+            (!self.system_prompt.is_empty())
+                .then(|| ConversationSetting::SystemPrompt(self.system_prompt.clone())),
+            (!self.model.is_empty()).then(|| ConversationSetting::Model(self.model.clone())),
+            (!self.auto_respond).then_some(ConversationSetting::AutoRespond(false)),
+            (!self.stop_sequences.is_empty())
+                .then(|| ConversationSetting::StopSequences(self.stop_sequences.clone())),
+            (!self.assistant_prefix.is_empty())
+                .then(|| ConversationSetting::AssistantPrefix(self.assistant_prefix.clone())),
+            (!self.language.is_empty())
+                .then(|| ConversationSetting::Language(self.language.clone())),
+        ];
+        for setting in settings.into_iter().flatten() {
+            conv.add_event(ConversationSettingChangedEvent { setting });
+        }
+        for message in &self.starter_messages {
+            conv.add_event(ConversationMessageAddedEvent {
+                author: message.author,
+                content: message.content.clone(),
+                model: None,
+                language: None,
+                citations: Vec::new(),
+            });
+        }
+        conv
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct Templates {
+    /// In the order they were created.
+    pub templates: Vec<ConversationTemplate>,
+}
+
+impl Templates {
+    /// The templates at `path`, none when there's no file yet. A file that can't be read is an
+    /// error rather than an empty list, so saving doesn't overwrite the templates in it.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        match compression::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path, compress: bool) -> io::Result<()> {
+        compression::write_with(path, compress, |file| {
+            serde_json::to_writer(file, self).map_err(io::Error::from)
+        })
+    }
+
+    pub fn get(&self, id: &Uuid) -> Option<&ConversationTemplate> {
+        self.templates.iter().find(|template| template.id == *id)
+    }
+
+    /// Replaces the template with the same id, returning false when there's none.
+    pub fn replace(&mut self, template: ConversationTemplate) -> bool {
+        match self.templates.iter_mut().find(|t| t.id == template.id) {
+            Some(existing) => {
+                *existing = template;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, id: &Uuid) -> Option<ConversationTemplate> {
+        let index = self
+            .templates
+            .iter()
+            .position(|template| template.id == *id)?;
+        Some(self.templates.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn template() -> ConversationTemplate {
+        ConversationTemplate {
+            id: Uuid::new_v4(),
+            name: "Standup".to_string(),
+            title_pattern: "Standup {date} {time}".to_string(),
+            system_prompt: "Be brief.".to_string(),
+            model: String::new(),
+            auto_respond: false,
+            stop_sequences: vec![],
+            assistant_prefix: String::new(),
+            language: "German".to_string(),
+            starter_messages: vec![
+                StarterMessage {
+                    author: Role::User,
+                    content: "What did I do yesterday?".to_string(),
+                },
+                StarterMessage {
+                    author: Role::Assistant,
+                    content: "Tell me and I'll summarize it.".to_string(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_instantiate() {
+        let now = Local.with_ymd_and_hms(2024, 3, 5, 9, 7, 0).unwrap();
+        let conv = template().instantiate_at(now);
+        assert_eq!(*conv.get_title(), "Standup 2024-03-05 09:07");
+        assert_eq!(conv.system_prompt(), "Be brief.");
+        assert_eq!(conv.model(), "");
+        assert!(!conv.auto_respond());
+        assert_eq!(conv.language(), "German");
+        assert_eq!(conv.template(), Some("Standup"));
+        assert_eq!(conv.message_count(), 2);
+        // only the settings that differ from the defaults are recorded
+        let settings = conv
+            .history
+            .iter()
+            .filter(|record| {
+                matches!(
+                    record.event,
+                    crate::models::ConversationEvent::SettingChanged(_)
+                )
+            })
+            .count();
+        assert_eq!(settings, 3);
+
+        let untitled = ConversationTemplate {
+            title_pattern: " ".to_string(),
+            ..template()
+        };
+        assert_eq!(untitled.title(now), "");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join(format!("templates-{}.json", Uuid::new_v4()));
+        assert!(Templates::open(&path).unwrap().templates.is_empty());
+
+        let mut templates = Templates::default();
+        let standup = template();
+        templates.templates.push(standup.clone());
+        assert!(templates.replace(ConversationTemplate {
+            name: "Daily".to_string(),
+            ..standup.clone()
+        }));
+        assert!(!templates.replace(template()));
+        templates.save(&path, false).unwrap();
+
+        let mut reopened = Templates::open(&path).unwrap();
+        assert_eq!(reopened.get(&standup.id).unwrap().name, "Daily");
+        assert_eq!(reopened.get(&standup.id).unwrap().starter_messages.len(), 2);
+        assert!(reopened.remove(&standup.id).is_some());
+        assert!(reopened.get(&standup.id).is_none());
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(Templates::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
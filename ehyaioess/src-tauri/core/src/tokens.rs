@@ -0,0 +1,79 @@
+//! Sizing text before it's sent, so the composer can show what a message will cost.
+
+use tiktoken_rs::{
+    cl100k_base_singleton, o200k_base_singleton, p50k_base_singleton, p50k_edit_singleton,
+    r50k_base_singleton,
+    tokenizer::{get_tokenizer, Tokenizer},
+};
+
+use crate::catalog::ModelInfo;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LengthInfo {
+    pub characters: usize,
+    pub words: usize,
+    pub tokens: usize,
+    /// Estimated cost of sending `tokens` as a prompt, None for a model without a known price.
+    pub cost_usd: Option<f64>,
+    /// The model's context window, which the tokens are budgeted against; None when unknown.
+    pub context_window: Option<usize>,
+}
+
+impl LengthInfo {
+    pub fn measure(content: &str, model: &ModelInfo) -> Self {
+        let tokens = count_tokens(content, &model.id);
+        Self {
+            characters: content.chars().count(),
+            words: content.split_whitespace().count(),
+            tokens,
+            cost_usd: model.prompt_price.map(|price| tokens as f64 * price / 1_000_000.0),
+            context_window: model.context_window,
+        }
+    }
+}
+
+/// Tokens in `content` with the encoding `model` uses. Models tiktoken doesn't know, the
+/// mock and local ones included, are counted with `cl100k_base`, close enough for an estimate.
+pub fn count_tokens(content: &str, model: &str) -> usize {
+    let bpe = match get_tokenizer(model) {
+        Some(Tokenizer::O200kBase) => o200k_base_singleton(),
+        Some(Tokenizer::P50kBase) => p50k_base_singleton(),
+        Some(Tokenizer::P50kEdit) => p50k_edit_singleton(),
+        Some(Tokenizer::R50kBase | Tokenizer::Gpt2) => r50k_base_singleton(),
+        Some(Tokenizer::Cl100kBase) | None => cl100k_base_singleton(),
+    };
+    // Special tokens typed into a message are sent as plain text.
+    let tokens = bpe.lock().encode_ordinary(content).len();
+    tokens
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_measure() {
+        let prices = crate::pricing::PriceOverrides::new();
+        let model = ModelInfo::new("gpt-3.5-turbo", "openai", &prices);
+        let info = LengthInfo::measure("Grüß dich,  Welt!\n", &model);
+        assert_eq!(info.characters, 18);
+        assert_eq!(info.words, 3);
+        assert!(info.tokens > 0 && info.tokens < info.characters);
+        assert_eq!(info.cost_usd, Some(info.tokens as f64 * 0.5 / 1_000_000.0));
+        assert_eq!(info.context_window, Some(16_385));
+
+        let empty = LengthInfo::measure("", &ModelInfo::new("some-local-model", "", &prices));
+        assert_eq!((empty.characters, empty.words, empty.tokens), (0, 0, 0));
+        assert_eq!((empty.cost_usd, empty.context_window), (None, None));
+    }
+
+    #[test]
+    fn test_count_tokens_per_model() {
+        assert_eq!(count_tokens("hello world", "gpt-4"), 2);
+        assert_eq!(count_tokens("hello world", "gpt-4o"), 2);
+        assert_eq!(count_tokens("<|endoftext|>", "gpt-3.5-turbo"), 7);
+        // unknown models fall back to cl100k_base
+        let text = "Ehyaioess counts tokens";
+        assert_eq!(count_tokens(text, "llama3"), count_tokens(text, "gpt-4"));
+    }
+}
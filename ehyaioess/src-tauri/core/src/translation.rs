@@ -0,0 +1,26 @@
+//! Prompts for answering in, and translating messages into, a conversation's language.
+
+use chatgpt::types::{ChatMessage, Role};
+
+/// Appended to the system prompt of conversations with a language preference.
+pub fn language_instruction(language: &str) -> String {
+    format!("Always respond in {}.", language)
+}
+
+/// The request for translating `content`, answered with only the translation.
+pub fn translation_messages(content: &str, language: &str) -> Vec<ChatMessage> {
+    vec![
+        ChatMessage {
+            role: Role::System,
+            content: format!(
+                "Translate the user's message into {}. Reply with only the translation, keeping \
+                 its formatting.",
+                language
+            ),
+        },
+        ChatMessage {
+            role: Role::User,
+            content: content.to_string(),
+        },
+    ]
+}
@@ -0,0 +1,128 @@
+//! Deleted conversations, kept for `RetentionConfig::trash_days` so a delete can be undone
+//! before retention removes them for good.
+
+use std::{io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{compression, models::Conversation};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrashedConversation {
+    pub conversation: Conversation,
+    /// Unix seconds.
+    pub deleted_at: i64,
+}
+
+impl TrashedConversation {
+    /// When retention removes it for good, `days` after it was deleted.
+    pub fn purge_at(&self, days: u32) -> i64 {
+        self.deleted_at + i64::from(days) * SECONDS_PER_DAY
+    }
+}
+
+/// The app only opens and saves the trash while holding the `ConversationManager` write lock,
+/// so a delete can't be lost to a concurrent purge.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct Trash {
+    /// Oldest deletion first.
+    pub conversations: Vec<TrashedConversation>,
+}
+
+impl Trash {
+    /// The trash at `path`, empty when there's none. Unlike caches, a trash that can't be read
+    /// is an error, since it holds conversations that exist nowhere else.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        match compression::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the trash to `path`, or removes the file once the trash is empty.
+    pub fn save(&self, path: &Path, compress: bool) -> io::Result<()> {
+        if self.conversations.is_empty() {
+            return match std::fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+        compression::write_with(path, compress, |file| {
+            serde_json::to_writer(file, self).map_err(io::Error::from)
+        })
+    }
+
+    pub fn add(&mut self, conversation: Conversation, now: i64) {
+        self.conversations.push(TrashedConversation {
+            conversation,
+            deleted_at: now,
+        });
+    }
+
+    /// Takes the conversation out of the trash, to be restored.
+    pub fn take(&mut self, id: &Uuid) -> Option<Conversation> {
+        let index = self
+            .conversations
+            .iter()
+            .position(|trashed| trashed.conversation.id == *id)?;
+        Some(self.conversations.remove(index).conversation)
+    }
+
+    /// Removes the conversations deleted more than `days` before `now`, returning their ids.
+    pub fn purge(&mut self, days: u32, now: i64) -> Vec<Uuid> {
+        let (expired, kept) = std::mem::take(&mut self.conversations)
+            .into_iter()
+            .partition(|trashed: &TrashedConversation| trashed.purge_at(days) <= now);
+        self.conversations = kept;
+        expired
+            .into_iter()
+            .map(|trashed| trashed.conversation.id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_take_and_purge() {
+        let mut trash = Trash::default();
+        let old = Conversation::new();
+        let recent = Conversation::new();
+        let (old_id, recent_id) = (old.id, recent.id);
+        trash.add(old, 0);
+        trash.add(recent, 5 * SECONDS_PER_DAY);
+
+        assert!(trash.purge(30, 29 * SECONDS_PER_DAY).is_empty());
+        assert_eq!(trash.purge(30, 30 * SECONDS_PER_DAY), [old_id]);
+        assert!(trash.take(&old_id).is_none());
+        assert_eq!(trash.take(&recent_id).unwrap().id, recent_id);
+        assert!(trash.conversations.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join(format!("trash-{}.json", Uuid::new_v4()));
+        assert!(Trash::open(&path).unwrap().conversations.is_empty());
+
+        let mut trash = Trash::default();
+        let conv = Conversation::new();
+        trash.add(conv.clone(), 100);
+        trash.save(&path, true).unwrap();
+        assert!(compression::is_compressed(&path).unwrap());
+        let reopened = Trash::open(&path).unwrap();
+        assert_eq!(reopened.conversations[0].conversation.id, conv.id);
+        assert_eq!(reopened.conversations[0].deleted_at, 100);
+
+        Trash::default().save(&path, false).unwrap();
+        assert!(!path.exists());
+        std::fs::write(&path, "not json").unwrap();
+        assert!(Trash::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
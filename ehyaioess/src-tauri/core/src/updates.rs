@@ -0,0 +1,151 @@
+//! Checks GitHub for a newer release than the running version.
+
+use serde::Deserialize;
+
+use crate::{
+    config::ProxyConfig,
+    provider::{self, ProviderError},
+};
+
+pub const RELEASES_URL: &str = "https://api.github.com/repos/TeamDman/Ehyaiohess/releases/latest";
+
+#[derive(Debug, Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    #[serde(default)]
+    body: Option<String>,
+    html_url: String,
+    #[serde(default)]
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UpdateInfo {
+    pub current_version: String,
+    pub latest_version: String,
+    pub update_available: bool,
+    /// Markdown, as written on the release.
+    pub release_notes: String,
+    /// The installer for this platform, or the release page when there's none.
+    pub download_url: String,
+}
+
+/// Parses a version or release tag such as `v1.2.3`.
+fn parse_version(version: &str) -> Result<semver::Version, ProviderError> {
+    let version = version.trim();
+    semver::Version::parse(version.strip_prefix('v').unwrap_or(version))
+        .map_err(|e| ProviderError::Parse(format!("invalid version {:?}: {}", version, e)))
+}
+
+/// Installer file extensions for `os`, preferred first.
+fn installer_extensions(os: &str) -> &'static [&'static str] {
+    match os {
+        "windows" => &[".msi", ".exe"],
+        "macos" => &[".dmg"],
+        "linux" => &[".AppImage", ".deb"],
+        _ => &[],
+    }
+}
+
+fn download_url(release: &GithubRelease, os: &str) -> String {
+    installer_extensions(os)
+        .iter()
+        .find_map(|extension| {
+            release
+                .assets
+                .iter()
+                .find(|asset| asset.name.ends_with(extension))
+        })
+        .map_or_else(
+            || release.html_url.clone(),
+            |asset| asset.browser_download_url.clone(),
+        )
+}
+
+fn compare(release: &GithubRelease, current_version: &str) -> Result<UpdateInfo, ProviderError> {
+    let current = parse_version(current_version)?;
+    let latest = parse_version(&release.tag_name)?;
+    Ok(UpdateInfo {
+        current_version: current.to_string(),
+        latest_version: latest.to_string(),
+        update_available: latest > current,
+        release_notes: release.body.clone().unwrap_or_default(),
+        download_url: download_url(release, std::env::consts::OS),
+    })
+}
+
+/// Fetches the latest release through the given proxy settings and compares it with
+/// `current_version`.
+pub async fn check(
+    proxy: &ProxyConfig,
+    current_version: &str,
+) -> Result<UpdateInfo, ProviderError> {
+    let http = provider::build_http_client(proxy).map_err(ProviderError::Http)?;
+    let response = http
+        .get(RELEASES_URL)
+        .header(reqwest::header::USER_AGENT, "ehyaioess")
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .map_err(ProviderError::Http)?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(ProviderError::Api {
+            status: status.as_u16(),
+            error_type: "github".to_string(),
+            code: String::new(),
+            message: response.text().await.unwrap_or_default(),
+        });
+    }
+    let release: GithubRelease = response
+        .json()
+        .await
+        .map_err(|e| ProviderError::Parse(e.to_string()))?;
+    compare(&release, current_version)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let asset = |name: &str| GithubAsset {
+            name: name.to_string(),
+            browser_download_url: format!("https://example.com/{}", name),
+        };
+        let release = GithubRelease {
+            tag_name: "v1.10.0".to_string(),
+            body: Some("Fixes".to_string()),
+            html_url: "https://example.com/release".to_string(),
+            assets: vec![asset("app.exe"), asset("app.msi"), asset("app.dmg")],
+        };
+
+        let info = compare(&release, "1.9.3").unwrap();
+        assert!(info.update_available);
+        assert_eq!(info.latest_version, "1.10.0");
+        assert_eq!(info.release_notes, "Fixes");
+        assert!(!compare(&release, "1.10.0").unwrap().update_available);
+        assert!(!compare(&release, "2.0.0-beta.1").unwrap().update_available);
+        assert!(compare(&release, "not a version").is_err());
+
+        assert_eq!(
+            download_url(&release, "windows"),
+            "https://example.com/app.msi"
+        );
+        assert_eq!(
+            download_url(&release, "macos"),
+            "https://example.com/app.dmg"
+        );
+        assert_eq!(
+            download_url(&release, "linux"),
+            "https://example.com/release"
+        );
+    }
+}
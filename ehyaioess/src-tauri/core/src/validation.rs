@@ -0,0 +1,261 @@
+//! Checks on command arguments, run before a command touches any state, so bad input is
+//! refused the same way everywhere and the frontend learns which argument it was in.
+
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::models::MyError;
+
+/// In characters, after `normalize_title`.
+pub const MAX_TITLE_LENGTH: usize = 200;
+/// In characters. Far beyond any model's context, so only runaway input hits it.
+pub const MAX_MESSAGE_LENGTH: usize = 100_000;
+
+/// What was wrong with an argument.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ArgumentProblem {
+    TooLong {
+        max_length: usize,
+    },
+    InvalidId,
+    /// Not an absolute path to a file of a type the command reads or writes.
+    PathNotAllowed {
+        extensions: Vec<String>,
+    },
+    /// Empty or only whitespace.
+    Empty,
+    /// Below zero or not a number at all.
+    Negative,
+    /// Not an http or https URL.
+    InvalidUrl,
+}
+
+fn invalid(field: &str, problem: ArgumentProblem) -> MyError {
+    MyError::ArgumentInvalidFail {
+        field: field.to_string(),
+        problem,
+    }
+}
+
+/// Refuses `value` when it's longer than `max_length` characters.
+pub fn max_length(field: &str, value: &str, max_length: usize) -> Result<(), MyError> {
+    match value.chars().count() > max_length {
+        true => Err(invalid(field, ArgumentProblem::TooLong { max_length })),
+        false => Ok(()),
+    }
+}
+
+/// `title` the way titles are stored: on one line, without control characters and with
+/// every run of whitespace a single space.
+pub fn normalize_title(title: &str) -> String {
+    let title: String = title
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_whitespace() => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect();
+    title.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Normalizes a title someone typed, refusing it when it's still longer than
+/// `MAX_TITLE_LENGTH`.
+pub fn title(field: &str, value: &str) -> Result<String, MyError> {
+    let title = normalize_title(value);
+    max_length(field, &title, MAX_TITLE_LENGTH)?;
+    Ok(title)
+}
+
+/// Normalizes a title that came with a file, cutting it down to `MAX_TITLE_LENGTH` rather
+/// than refusing the file.
+pub fn imported_title(value: &str) -> String {
+    normalize_title(value)
+        .chars()
+        .take(MAX_TITLE_LENGTH)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+/// `value` trimmed, refused when nothing is left.
+pub fn not_empty(field: &str, value: &str) -> Result<String, MyError> {
+    match value.trim() {
+        "" => Err(invalid(field, ArgumentProblem::Empty)),
+        trimmed => Ok(trimmed.to_string()),
+    }
+}
+
+/// Refuses `value` unless it's a finite number of at least zero.
+pub fn non_negative(field: &str, value: f64) -> Result<f64, MyError> {
+    match value.is_finite() && value >= 0.0 {
+        true => Ok(value),
+        false => Err(invalid(field, ArgumentProblem::Negative)),
+    }
+}
+
+/// Parses `value` as the id of a conversation, message or task.
+pub fn id(field: &str, value: &str) -> Result<Uuid, MyError> {
+    Uuid::parse_str(value).map_err(|_| invalid(field, ArgumentProblem::InvalidId))
+}
+
+/// `value` trimmed, refused unless it's an http or https URL with a host.
+pub fn url(field: &str, value: &str) -> Result<String, MyError> {
+    let url = value.trim();
+    let host = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .and_then(|rest| rest.split(['/', '?', '#']).next())
+        .unwrap_or_default();
+    match host.is_empty() || url.contains(char::is_whitespace) {
+        true => Err(invalid(field, ArgumentProblem::InvalidUrl)),
+        false => Ok(url.to_string()),
+    }
+}
+
+/// Accepts `value` as an absolute path to a file with one of `extensions`, compared
+/// ignoring case, and without `..` to climb out of where it seems to point.
+pub fn path(field: &str, value: &str, extensions: &[&str]) -> Result<PathBuf, MyError> {
+    let path = Path::new(value);
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    let allowed = path.is_absolute()
+        && !path
+            .components()
+            .any(|component| component == Component::ParentDir)
+        && extensions
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(extension));
+    match allowed {
+        true => Ok(path.to_path_buf()),
+        false => Err(invalid(
+            field,
+            ArgumentProblem::PathNotAllowed {
+                extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+            },
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn problem(result: Result<impl std::fmt::Debug, MyError>) -> (String, ArgumentProblem) {
+        match result {
+            Err(MyError::ArgumentInvalidFail { field, problem }) => (field, problem),
+            other => panic!("expected an invalid argument, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_max_length_counts_characters() {
+        assert!(max_length("new_title", "äöü", 3).is_ok());
+        assert_eq!(
+            problem(max_length("new_title", "äöüß", 3)),
+            (
+                "new_title".to_string(),
+                ArgumentProblem::TooLong { max_length: 3 }
+            )
+        );
+    }
+
+    #[test]
+    fn test_normalize_title() {
+        assert_eq!(
+            normalize_title("  Trip\r\n\tto   Rome\u{0}\u{7}  "),
+            "Trip to Rome"
+        );
+        assert_eq!(normalize_title("\n \u{1b}"), "");
+        assert_eq!(title("new_title", " Trip\nplans ").unwrap(), "Trip plans");
+        // counted after collapsing whitespace
+        let padded = format!("{}{}", "a".repeat(MAX_TITLE_LENGTH), " ".repeat(10));
+        assert!(title("new_title", &padded).is_ok());
+        assert!(title("new_title", &"a".repeat(MAX_TITLE_LENGTH + 1)).is_err());
+        let long = format!("{} b", "a".repeat(MAX_TITLE_LENGTH - 1));
+        assert_eq!(imported_title(&long), "a".repeat(MAX_TITLE_LENGTH - 1));
+    }
+
+    #[test]
+    fn test_not_empty_and_non_negative() {
+        assert_eq!(not_empty("model", " gpt-4o ").unwrap(), "gpt-4o");
+        assert_eq!(
+            problem(not_empty("model", " \n")),
+            ("model".to_string(), ArgumentProblem::Empty)
+        );
+        assert_eq!(non_negative("prompt", 0.0).unwrap(), 0.0);
+        for value in [-0.5, f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                problem(non_negative("prompt", value)),
+                ("prompt".to_string(), ArgumentProblem::Negative)
+            );
+        }
+    }
+
+    #[test]
+    fn test_id() {
+        let id = Uuid::new_v4();
+        assert_eq!(super::id("message_id", &id.to_string()).unwrap(), id);
+        assert_eq!(
+            problem(super::id("message_id", "not-an-id")),
+            ("message_id".to_string(), ArgumentProblem::InvalidId)
+        );
+    }
+
+    #[test]
+    fn test_url() {
+        assert_eq!(
+            url("url", " https://example.com/a?b#c ").unwrap(),
+            "https://example.com/a?b#c"
+        );
+        assert!(url("url", "http://localhost:8080").is_ok());
+        for value in ["example.com", "ftp://example.com", "https://", "https:///a", "https://a b"] {
+            assert_eq!(
+                problem(url("url", value)),
+                ("url".to_string(), ArgumentProblem::InvalidUrl),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_path() {
+        let dir = std::env::temp_dir();
+        let pdf = dir.join("export.PDF");
+        assert_eq!(path("path", pdf.to_str().unwrap(), &["pdf"]).unwrap(), pdf);
+        let not_allowed = ArgumentProblem::PathNotAllowed {
+            extensions: vec!["pdf".to_string()],
+        };
+        for value in [
+            "export.pdf".to_string(),
+            dir.join("export.exe").display().to_string(),
+            dir.join("export").display().to_string(),
+            dir.join("..").join("export.pdf").display().to_string(),
+        ] {
+            assert_eq!(
+                problem(path("path", &value, &["pdf"])),
+                ("path".to_string(), not_allowed.clone()),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_serialized_with_field_and_problem() {
+        let error = serde_json::to_value(id("conversation_id", "nope").unwrap_err()).unwrap();
+        assert_eq!(error["code"], "ArgumentInvalidFail");
+        assert_eq!(error["field"], "conversation_id");
+        assert_eq!(
+            error["problem"],
+            serde_json::json!({ "kind": "invalid_id" })
+        );
+        assert!(error["message"].is_string());
+    }
+}
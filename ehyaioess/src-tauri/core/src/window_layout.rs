@@ -0,0 +1,226 @@
+//! Where each window was and which conversation it showed, so the next start can open the
+//! same windows and carry on where the user left off.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The window the app starts with; the others are opened by `open_window`.
+pub const MAIN_WINDOW: &str = "main";
+/// How much of a window has to be on some screen for it to be restored where it was.
+const MIN_VISIBLE: f64 = 50.0;
+
+/// A window's place on screen, in logical pixels. The size is the one it has when it isn't
+/// maximized.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct WindowBounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+impl WindowBounds {
+    /// Whether enough of the window's title bar is on one of `screens`, each given as bounds,
+    /// to grab it. Screens come and go, and a window restored off all of them is lost.
+    pub fn is_visible_on(&self, screens: &[WindowBounds]) -> bool {
+        screens.iter().any(|screen| {
+            let left = self.x.max(screen.x);
+            let right = (self.x + self.width).min(screen.x + screen.width);
+            right - left >= MIN_VISIBLE
+                && self.y >= screen.y - MIN_VISIBLE / 2.0
+                && self.y < screen.y + screen.height - MIN_VISIBLE / 2.0
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WindowState {
+    pub label: String,
+    /// None until the window is first moved or resized.
+    #[serde(default)]
+    pub bounds: Option<WindowBounds>,
+    #[serde(default)]
+    pub conversation_id: Option<Uuid>,
+}
+
+impl WindowState {
+    fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            bounds: None,
+            conversation_id: None,
+        }
+    }
+}
+
+/// The open windows, in the order they were opened, kept up to date on disk.
+pub struct WindowLayout {
+    path: Mutex<PathBuf>,
+    windows: Mutex<Vec<WindowState>>,
+}
+
+impl WindowLayout {
+    fn read_windows(path: &Path) -> Vec<WindowState> {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            windows: Mutex::new(Self::read_windows(&path)),
+            path: Mutex::new(path),
+        }
+    }
+
+    /// Switches to the layout at `path`, e.g. after changing workspace profile.
+    pub fn reopen(&self, path: PathBuf) {
+        let mut windows = self.windows.lock().unwrap();
+        *windows = Self::read_windows(&path);
+        *self.path.lock().unwrap() = path;
+    }
+
+    fn write(&self, windows: &[WindowState]) -> Result<(), std::io::Error> {
+        let path = self.path.lock().unwrap();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&*path, serde_json::to_string(windows)?)
+    }
+
+    /// The windows to restore, the main window first.
+    pub fn windows(&self) -> Vec<WindowState> {
+        let mut windows = self.windows.lock().unwrap().clone();
+        windows.sort_by_key(|window| window.label != MAIN_WINDOW);
+        windows
+    }
+
+    /// The window's state, or a new one's when it hasn't been recorded.
+    pub fn get(&self, label: &str) -> WindowState {
+        self.windows
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|window| window.label == label)
+            .cloned()
+            .unwrap_or_else(|| WindowState::new(label))
+    }
+
+    fn update(
+        &self,
+        label: &str,
+        change: impl FnOnce(&mut WindowState),
+    ) -> Result<(), std::io::Error> {
+        let mut windows = self.windows.lock().unwrap();
+        let index = match windows.iter().position(|window| window.label == label) {
+            Some(index) => index,
+            None => {
+                windows.push(WindowState::new(label));
+                windows.len() - 1
+            }
+        };
+        change(&mut windows[index]);
+        self.write(&windows)
+    }
+
+    /// Records where the window is. While it's maximized only that is recorded, keeping the
+    /// size it goes back to.
+    pub fn set_bounds(&self, label: &str, bounds: WindowBounds) -> Result<(), std::io::Error> {
+        self.update(label, |window| {
+            match (bounds.maximized, &mut window.bounds) {
+                (true, Some(known)) => known.maximized = true,
+                _ => window.bounds = Some(bounds),
+            }
+        })
+    }
+
+    pub fn set_conversation(
+        &self,
+        label: &str,
+        conversation_id: Option<Uuid>,
+    ) -> Result<(), std::io::Error> {
+        self.update(label, |window| window.conversation_id = conversation_id)
+    }
+
+    /// Forgets a window the user closed, so it isn't opened again on the next start.
+    pub fn remove(&self, label: &str) -> Result<(), std::io::Error> {
+        let mut windows = self.windows.lock().unwrap();
+        windows.retain(|window| window.label != label);
+        self.write(&windows)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bounds(x: f64, y: f64, width: f64, height: f64) -> WindowBounds {
+        WindowBounds {
+            x,
+            y,
+            width,
+            height,
+            maximized: false,
+        }
+    }
+
+    #[test]
+    fn test_is_visible_on() {
+        let screens = [
+            bounds(0.0, 0.0, 1920.0, 1080.0),
+            bounds(1920.0, 0.0, 1280.0, 1024.0),
+        ];
+        assert!(bounds(100.0, 100.0, 800.0, 600.0).is_visible_on(&screens));
+        // spanning both screens
+        assert!(bounds(1500.0, 100.0, 800.0, 600.0).is_visible_on(&screens));
+        // on a screen that's since been unplugged
+        assert!(!bounds(3400.0, 100.0, 800.0, 600.0).is_visible_on(&screens));
+        // title bar above the top of the screen
+        assert!(!bounds(100.0, -400.0, 800.0, 600.0).is_visible_on(&screens));
+        assert!(!bounds(100.0, 100.0, 800.0, 600.0).is_visible_on(&[]));
+    }
+
+    #[test]
+    fn test_window_layout() {
+        let dir = std::env::temp_dir().join(format!("window-layout-{}", Uuid::new_v4()));
+        let path = dir.join("windows.json");
+        let layout = WindowLayout::new(path.clone());
+        let conversation_id = Uuid::new_v4();
+        layout
+            .set_conversation("window-1", Some(conversation_id))
+            .unwrap();
+        layout
+            .set_bounds(MAIN_WINDOW, bounds(10.0, 20.0, 800.0, 600.0))
+            .unwrap();
+        let maximized = WindowBounds {
+            maximized: true,
+            ..bounds(0.0, 0.0, 1920.0, 1080.0)
+        };
+        layout.set_bounds(MAIN_WINDOW, maximized).unwrap();
+
+        let layout = WindowLayout::new(path);
+        let windows = layout.windows();
+        assert_eq!(windows[0].label, MAIN_WINDOW);
+        assert_eq!(
+            windows[0].bounds,
+            Some(WindowBounds {
+                maximized: true,
+                ..bounds(10.0, 20.0, 800.0, 600.0)
+            })
+        );
+        assert_eq!(windows[1].conversation_id, Some(conversation_id));
+        assert_eq!(windows[1].bounds, None);
+        layout.remove("window-1").unwrap();
+        assert_eq!(layout.windows().len(), 1);
+        assert_eq!(layout.get("window-2").conversation_id, None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+//! Named profiles (workspaces), each with its own config, API keys and history so that e.g.
+//! work and personal chats stay apart. Not to be confused with API key profiles, which live
+//! inside the config of one of these.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+pub const DEFAULT_WORKSPACE: &str = "default";
+const PROFILES_DIR: &str = "profiles";
+/// File in the base directory naming the profile to open on the next start.
+const ACTIVE_FILE: &str = "active_profile";
+const MAX_NAME_LENGTH: usize = 64;
+
+static ACTIVE: RwLock<Option<String>> = RwLock::new(None);
+static BASE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+#[derive(Debug)]
+pub enum WorkspaceError {
+    InvalidName,
+    Exists,
+    NotFound,
+    Io(std::io::Error),
+}
+impl std::fmt::Display for WorkspaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WorkspaceError::InvalidName => write!(
+                f,
+                "Profile names use up to {} letters, digits, '-' and '_'",
+                MAX_NAME_LENGTH
+            ),
+            WorkspaceError::Exists => write!(f, "A profile with that name already exists"),
+            WorkspaceError::NotFound => write!(f, "No profile with that name exists"),
+            WorkspaceError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+impl std::error::Error for WorkspaceError {}
+
+/// The directory holding the default profile, with the others in its `profiles` folder.
+pub fn base_dir() -> Option<PathBuf> {
+    if let Some(dir) = BASE_DIR.read().unwrap().clone() {
+        return Some(dir);
+    }
+    dirs_next::config_dir().map(|dir| dir.join("ehyaioess"))
+}
+
+/// Moves every profile to `dir` for this process, e.g. a temp dir in tests; None goes back
+/// to the platform's config directory.
+pub fn set_base_dir(dir: Option<PathBuf>) {
+    *BASE_DIR.write().unwrap() = dir;
+}
+
+pub fn dir_of(base: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_WORKSPACE {
+        base.to_path_buf()
+    } else {
+        base.join(PROFILES_DIR).join(name)
+    }
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The default profile followed by the others, sorted by name.
+pub fn list(base: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(base.join(PROFILES_DIR))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().into_string().ok()?;
+            (entry.path().is_dir() && is_valid_name(&name) && name != DEFAULT_WORKSPACE)
+                .then_some(name)
+        })
+        .collect();
+    names.sort();
+    names.insert(0, DEFAULT_WORKSPACE.to_string());
+    names
+}
+
+/// Creates the profile's directory, returning it.
+pub fn create(base: &Path, name: &str) -> Result<PathBuf, WorkspaceError> {
+    if !is_valid_name(name) {
+        return Err(WorkspaceError::InvalidName);
+    }
+    if exists(base, name) {
+        return Err(WorkspaceError::Exists);
+    }
+    let dir = dir_of(base, name);
+    std::fs::create_dir_all(&dir).map_err(WorkspaceError::Io)?;
+    Ok(dir)
+}
+
+pub fn active() -> String {
+    ACTIVE
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string())
+}
+
+fn exists(base: &Path, name: &str) -> bool {
+    list(base).iter().any(|existing| existing == name)
+}
+
+/// Switches the profile every later config path refers to, for this process only.
+pub fn select(base: &Path, name: &str) -> Result<(), WorkspaceError> {
+    if !exists(base, name) {
+        return Err(WorkspaceError::NotFound);
+    }
+    *ACTIVE.write().unwrap() = Some(name.to_string());
+    Ok(())
+}
+
+/// Like `select`, also remembering the profile for the next start.
+pub fn switch(base: &Path, name: &str) -> Result<(), WorkspaceError> {
+    if !exists(base, name) {
+        return Err(WorkspaceError::NotFound);
+    }
+    std::fs::write(base.join(ACTIVE_FILE), name).map_err(WorkspaceError::Io)?;
+    select(base, name)
+}
+
+/// Opens the profile that was active when the app last ran, if it still exists.
+pub fn load_active(base: &Path) {
+    let name = std::fs::read_to_string(base.join(ACTIVE_FILE)).unwrap_or_default();
+    // A removed profile falls back to the default.
+    let _ = select(base, name.trim());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_create_and_list() {
+        let base = std::env::temp_dir().join(format!("workspaces-{}", uuid::Uuid::new_v4()));
+        assert_eq!(list(&base), vec![DEFAULT_WORKSPACE]);
+
+        let work = create(&base, "work").unwrap();
+        assert_eq!(work, base.join("profiles").join("work"));
+        create(&base, "personal").unwrap();
+        assert_eq!(list(&base), vec![DEFAULT_WORKSPACE, "personal", "work"]);
+
+        assert!(matches!(create(&base, "work"), Err(WorkspaceError::Exists)));
+        assert!(matches!(
+            create(&base, DEFAULT_WORKSPACE),
+            Err(WorkspaceError::Exists)
+        ));
+        assert!(matches!(
+            create(&base, "../escape"),
+            Err(WorkspaceError::InvalidName)
+        ));
+        assert_eq!(dir_of(&base, DEFAULT_WORKSPACE), base);
+
+        std::fs::remove_dir_all(base).unwrap();
+    }
+}
@@ -0,0 +1,40 @@
+//! Catalog of invokable actions for the command palette.
+
+use crate::{keybindings::Keybindings, payloads::AppActionPayload};
+
+/// Generated from the command definitions by `ts-bindgen`.
+const CATALOG: &str = include_str!("app_actions.json");
+
+/// Every action, with its shortcut from `keybindings` (the effective bindings).
+pub fn app_actions(keybindings: &Keybindings) -> Vec<AppActionPayload> {
+    let mut actions: Vec<AppActionPayload> = match serde_json::from_str(CATALOG) {
+        Ok(actions) => actions,
+        Err(e) => {
+            eprintln!("Failed to parse action catalog: {}", e);
+            return Vec::new();
+        }
+    };
+    for action in &mut actions {
+        action.keybinding = keybindings.get(&action.name).cloned();
+    }
+    actions
+}
+
+pub fn is_action(name: &str) -> bool {
+    app_actions(&Keybindings::new())
+        .iter()
+        .any(|action| action.name == name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::keybindings::DEFAULT_KEYBINDINGS;
+
+    #[test]
+    fn test_default_keybindings_name_actions() {
+        for (name, _) in DEFAULT_KEYBINDINGS {
+            assert!(is_action(name), "keybinding for unknown action {}", name);
+        }
+    }
+}
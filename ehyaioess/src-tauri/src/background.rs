@@ -0,0 +1,243 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tauri::{async_runtime::RwLock, AppHandle, Manager, Runtime};
+
+use crate::{
+    action_items::ActionItems,
+    analytics::{UsageLog, UsageRecord},
+    config::Config,
+    events,
+    models::{ConversationManager, ConversationTagsChangedEvent, MyError},
+    payloads::{
+        ActionItemPayload, ConversationTagsChangedEventPayload, ConversationsRemovedEventPayload, UpdateInfoPayload,
+    },
+    profiles::ProfileClients,
+    provider::Sampling,
+    retention, tagging,
+    tasks::TaskSupervisor,
+    trash::Trash,
+    updates,
+};
+
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const REMINDER_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Enforces the retention rules and empties the trash of what's expired, once at startup and
+/// then hourly.
+pub async fn retention_loop<R: Runtime>(app_handle: AppHandle<R>) {
+    loop {
+        if let Err(e) = enforce_retention(&app_handle).await {
+            eprintln!("Retention pass failed: {}", e);
+        }
+        tokio::time::sleep(RETENTION_INTERVAL).await;
+    }
+}
+
+/// While `auto_update_check` is on, looks for a newer release at startup and then daily,
+/// announcing one as `update_available`.
+pub async fn update_check_loop<R: Runtime>(app_handle: AppHandle<R>) {
+    loop {
+        let config = app_handle.state::<RwLock<Config>>();
+        let config = config.read().await;
+        let (enabled, proxy) = (config.auto_update_check, config.proxy.clone());
+        drop(config);
+        if enabled {
+            let current_version = app_handle.package_info().version.to_string();
+            match updates::check(&proxy, &current_version).await {
+                Ok(info) if info.update_available => {
+                    if let Err(e) = events::emit_all(
+                        &app_handle,
+                        "update_available",
+                        UpdateInfoPayload::from(info),
+                    ) {
+                        eprintln!("Failed to announce update: {}", e);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Update check failed: {}", e),
+            }
+        }
+        tokio::time::sleep(UPDATE_CHECK_INTERVAL).await;
+    }
+}
+
+/// Announces the action items whose reminders are due as `task_reminder_due`, checking every
+/// 30 seconds. Reminders that came due while the app was closed are shown at startup.
+pub async fn reminder_loop<R: Runtime>(app_handle: AppHandle<R>) {
+    loop {
+        if let Err(e) = send_due_reminders(&app_handle).await {
+            eprintln!("Sending task reminders failed: {}", e);
+        }
+        tokio::time::sleep(REMINDER_INTERVAL).await;
+    }
+}
+
+async fn send_due_reminders<R: Runtime>(app_handle: &AppHandle<R>) -> Result<(), MyError> {
+    // Reminders are kept until they can be marked as shown.
+    if app_handle.state::<RwLock<Config>>().read().await.read_only {
+        return Ok(());
+    }
+    let path = Config::get_action_items_path().map_err(|_| MyError::NoConfigDirFail)?;
+    let mut items = ActionItems::open(&path).map_err(|_| MyError::ActionItemsFail)?;
+    let due = items.take_due_reminders(unix_now());
+    if due.is_empty() {
+        return Ok(());
+    }
+    items
+        .save(&path, false)
+        .map_err(|_| MyError::ActionItemsFail)?;
+    for item in due {
+        events::emit_all(app_handle, "task_reminder_due", ActionItemPayload::from(item))
+            .map_err(|_| MyError::EmitFail)?;
+    }
+    Ok(())
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+pub async fn enforce_retention<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Vec<uuid::Uuid>, MyError> {
+    // Copied out, so the config isn't held while waiting on the history lock.
+    let (read_only, rules, compress_history, save_path) = {
+        let config = app_handle.state::<RwLock<Config>>();
+        let config = config.read().await;
+        (
+            config.read_only,
+            config.retention.clone(),
+            config.compress_history,
+            config.conversation_history_save_path.clone(),
+        )
+    };
+    // Nothing is removed in read-only mode.
+    if read_only {
+        return Ok(Vec::new());
+    }
+    let conversation_manager = app_handle.state::<RwLock<ConversationManager>>();
+    let mut mgr = conversation_manager.write().await;
+    purge_trash(rules.trash_days, compress_history)?;
+    if !rules.is_enabled() {
+        return Ok(Vec::new());
+    }
+    let candidates = retention::plan(&mgr, &rules, unix_now());
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
+    retention::apply(&mut mgr, &candidates);
+    mgr.write_to_disk(&save_path)
+        .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    drop(mgr);
+
+    let conversation_ids: Vec<uuid::Uuid> =
+        candidates.iter().map(|c| c.conversation_id).collect();
+    events::emit_all(
+        app_handle,
+        "conversations_removed",
+        ConversationsRemovedEventPayload {
+            conversation_ids: conversation_ids.clone(),
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Ok(conversation_ids)
+}
+
+/// Removes the conversations that have been in the trash longer than `trash_days` for good.
+/// Called with the history's write lock held, since that lock guards the trash too.
+fn purge_trash(trash_days: u32, compress: bool) -> Result<(), MyError> {
+    let path = Config::get_trash_path().map_err(|_| MyError::NoConfigDirFail)?;
+    let mut trash = Trash::open(&path).map_err(|_| MyError::TrashFail)?;
+    if !trash.purge(trash_days, unix_now()).is_empty() {
+        trash
+            .save(&path, compress)
+            .map_err(|_| MyError::TrashFail)?;
+    }
+    Ok(())
+}
+
+/// Tags the conversation by topic without holding up the reply, when automatic tagging is on.
+pub fn schedule_auto_tagging<R: Runtime>(app_handle: AppHandle<R>, conversation_id: uuid::Uuid) {
+    let tasks = app_handle.state::<TaskSupervisor>().inner().clone();
+    tasks.spawn("auto_tagging", async move {
+        if let Err(e) = auto_tag(&app_handle, conversation_id).await {
+            eprintln!("Automatic tagging failed: {}", e);
+        }
+    });
+}
+
+async fn auto_tag<R: Runtime>(app_handle: &AppHandle<R>, conversation_id: uuid::Uuid) -> Result<(), MyError> {
+    let (active_profile, models) = {
+        let config = app_handle.state::<RwLock<Config>>();
+        let config = config.read().await;
+        if !config.auto_tag || config.read_only {
+            return Ok(());
+        }
+        (config.active_profile.clone(), config.model_chain())
+    };
+    let conversation_manager = app_handle.state::<RwLock<ConversationManager>>();
+    let (preferred_profile, request) = {
+        let mgr = conversation_manager.read().await;
+        let conv = mgr.conversation(&conversation_id)?;
+        if !tagging::needs_auto_tags(&conv) {
+            return Ok(());
+        }
+        (
+            conv.get_pinned_profile()
+                .unwrap_or(&active_profile)
+                .to_string(),
+            tagging::tagging_messages(&conv),
+        )
+    };
+
+    let profile_clients = app_handle.state::<RwLock<ProfileClients>>();
+    let ai_response = profile_clients
+        .read()
+        .await
+        .send_with_failover(&preferred_profile, &models, &request, &[], Sampling::default())
+        .await
+        .map_err(|_| MyError::ConversationAIResponseFail)?;
+    if let Some(usage) = UsageRecord::from_response(conversation_id, &ai_response) {
+        app_handle.state::<Arc<UsageLog>>().record(usage);
+    }
+    let tags = tagging::parse_tags(
+        &ai_response
+            .message()
+            .ok_or(MyError::ConversationAIResponseFail)?
+            .content,
+    );
+    if tags.is_empty() {
+        return Ok(());
+    }
+
+    {
+        let mut mgr = conversation_manager.write().await;
+        let conv = mgr.conversation_mut(&conversation_id)?;
+        // The user may have tagged it, or another reply finished, while the model was busy.
+        if conv.has_tag_history() {
+            return Ok(());
+        }
+        conv.add_event(ConversationTagsChangedEvent {
+            tags: tags.clone(),
+            automatic: true,
+        });
+        let config = app_handle.state::<RwLock<Config>>();
+        mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+            .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    }
+
+    events::emit_all(
+        app_handle,
+        "conversation_tags_changed",
+        ConversationTagsChangedEventPayload {
+            conversation_id,
+            tags,
+            automatic: true,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)
+}
@@ -1,157 +1,299 @@
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
 
-use chatgpt::prelude::ChatGPT;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tauri::{async_runtime::RwLock, Manager, State};
+use std::{collections::HashMap, sync::Arc};
+use tauri::{async_runtime::RwLock, Manager, Runtime, State};
 
 use crate::{
+    action_items::{self, ActionItems},
+    actions,
+    analytics::{ReportPeriod, UsageLog, UsageRecord},
+    appearance::{self, AppearanceConfig},
+    archive, background, bookmarks,
+    catalog::ModelCatalog,
+    clustering, compression,
+    context::ContextLimit,
+    crash::LastCrashReport,
+    deep_link, diff,
+    drafts::DraftStore,
+    embeddings::{self, EmbeddingCache},
+    events, explain, feedback, file_open, finetune, fuzzy,
+    generation_journal::{self, GenerationJournal, PendingGeneration, Resume},
+    glossary::{self, GlossaryEntry},
+    guardrails::{self, GuardrailConfig, Guardrails},
+    history_watcher::{self, HistoryWatcher},
+    html, i18n, import, integrity, keybindings,
+    knowledge::{self, KnowledgeBase, RepositoryIndex, RepositoryInfo},
+    link_preview::{self, LinkPreview, PreviewCache},
+    microphone::{self, ActiveDictation, DictationHandle},
     models::{
-        Conversation, ConversationEvent, ConversationManager,
-        ConversationMessageAddedEvent, ConversationTitleChangedEvent, MyError,
+        Conversation, ConversationArchivedEvent, ConversationEvent,
+        ConversationGenerationFailedEvent, ConversationLockedEvent, ConversationManager,
+        ConversationMessageAddedEvent, ConversationMessageAnnotatedEvent,
+        ConversationMessageBookmarkedEvent, ConversationMessageFilteredEvent,
+        ConversationMessagePartialEvent, ConversationMessagePinnedEvent,
+        ConversationMessageRatedEvent, ConversationMessageTranslatedEvent,
+        ConversationProfilePinnedEvent, ConversationReadEvent, ConversationSetting,
+        ConversationSettingChangedEvent, ConversationTagsChangedEvent,
+        ConversationTitleChangedEvent, MessageRating, MyError,
     },
+    overrides,
     payloads::{
-        ConversationMessageAddedEventPayload, ConversationMessagePayload,
-        ConversationTitleChangedEventPayload,
+        ActionItemPayload, AppActionPayload, AppInfoPayload, AppearancePayload,
+        AssistantTypingEventPayload, BackgroundTaskPayload, BookmarkPayload, CaptureRegionPayload,
+        CaptureTargetPayload, CitationPayload, ConversationClusterPayload,
+        ConversationLockChangedEventPayload, ConversationMessageAddedEventPayload,
+        ConversationMessagePartialEventPayload, ConversationMessagePayload,
+        ConversationReloadedEventPayload, ConversationSettingsChangedEventPayload,
+        ConversationSettingsPayload, ConversationStatsPayload, ConversationTagsChangedEventPayload,
+        ConversationTemplateEntryPayload, ConversationTemplatePayload,
+        ConversationTitleChangedEventPayload, ConversationsArchivedEventPayload,
+        ConversationsRemovedEventPayload, ConversationsTagsChangedEventPayload, CrashReportPayload,
+        DiffSummaryPayload, EffectiveConfigValuePayload, EmbeddingCacheStatsPayload,
+        FileOpenedEventPayload, FinetuneExportOptionsPayload, FuzzyConversationMatchPayload,
+        GenerationFailedEventPayload, GenerationUsageEventPayload, GlossaryEntryPayload,
+        GuardrailHitPayload, GuardrailsPayload, IntegrityIssuePayload, IntegrityReportPayload,
+        LinkPreviewPayload, LocalePayload, MessageAnnotatedEventPayload,
+        MessageBookmarkChangedEventPayload, MessageFilteredEventPayload, MessageLengthInfoPayload,
+        MessageMatchesPayload, MessagePinnedContextChangedEventPayload, MessageRatedEventPayload,
+        MessageTranslatedEventPayload, MessageTranslationPayload, ModelCatalogPayload,
+        ModelFeedbackPayload, ModelPricePayload, ModelPricingPayload, PendingGenerationPayload,
+        PlaybackStatePayload, ProxyTestResultPayload, QuickSearchResultPayload, RepositoryPayload,
+        RetentionCandidatePayload, SearchHitPayload, StorageUsagePayload, StylePresetPayload,
+        SyncConflictEventPayload, SyncStatusPayload, TrashedConversationPayload,
+        UnreadCountChangedEventPayload, UpdateInfoPayload, UsageReportPayload, WindowStatePayload,
+        WorkspaceProfilePayload,
     },
+    pdf,
+    playback::{self, ReadAloud, Session},
+    presets::{self, StylePreset, StylePresets},
+    pricing,
+    profiles::ProfileClients,
+    progress::{Progress, ProgressKind},
+    provider::{self, ProviderError, Sampling},
+    quick_search,
+    read_aloud::{self, PlaybackQueue, PlaybackState},
+    recorder::{self, RequestRecorder},
+    relocate, retention, screenshot, search, sharing,
+    stats::ConversationStats,
+    storage::{self, StorageCategory},
+    sync::{self, ConflictStrategy, SyncBackendConfig, SyncConflicts},
+    tagging, takeout,
+    tasks::TaskSupervisor,
+    templates::{ConversationTemplate, StarterMessage, Templates},
+    tokens::LengthInfo,
+    translation,
+    trash::Trash,
+    updates, validation,
+    window_layout::WindowLayout,
+    windows,
+    workspaces::{self, WorkspaceError},
 };
 
-#[cfg(test)]
-mod test {
-    
-    
-    fn rust_type_to_ts(rust_type: &syn::Type) -> String {
-        match rust_type {
-            syn::Type::Path(type_path) if type_path.qself.is_none() => {
-                let ident = &type_path.path.segments.last().unwrap().ident;
-                match ident.to_string().as_str() {
-                    "str" => "string".to_owned(),
-                    "String" => "string".to_owned(),
-                    "()" => "void".to_owned(),
-                    "Result" => {
-                        match &type_path.path.segments.last().unwrap().arguments {
-                            syn::PathArguments::AngleBracketed(angle_bracketed_data) => {
-                                let args: Vec<_> = angle_bracketed_data.args.iter().collect();
-                                if let syn::GenericArgument::Type(ty) = args[0] {
-                                    rust_type_to_ts(ty)
-                                } else {
-                                    panic!("Result without inner type")
-                                }
-                            },
-                            _ => panic!("Unsupported angle type: {}", ident.to_string()),
-                        }
-                    },
-                    "Vec" => {
-                        match &type_path.path.segments.last().unwrap().arguments {
-                            syn::PathArguments::AngleBracketed(angle_bracketed_data) => {
-                                if let Some(syn::GenericArgument::Type(ty)) = angle_bracketed_data.args.first() {
-                                    format!("Array<{}>", rust_type_to_ts(ty))
-                                } else {
-                                    panic!("Vec without inner type")
-                                }
-                            },
-                            _ => panic!("Unsupported angle type: {}", ident.to_string()),
-                        }
-                    },
-                    "HashMap" => {
-                        match &type_path.path.segments.last().unwrap().arguments {
-                            syn::PathArguments::AngleBracketed(angle_bracketed_data) => {
-                                let args: Vec<_> = angle_bracketed_data.args.iter().collect();
-                                if let syn::GenericArgument::Type(key_ty) = args[0] {
-                                    if let syn::GenericArgument::Type(value_ty) = args[1] {
-                                        format!("Record<{}, {}>", rust_type_to_ts(key_ty), rust_type_to_ts(value_ty))
-                                    } else {
-                                        panic!("HashMap without value type")
-                                    }
-                                } else {
-                                    panic!("HashMap without key type")
-                                }
-                            },
-                            _ => panic!("Unsupported angle type: {}", ident.to_string()),
-                        }
-                    },
-                    _ => ident.to_string(),
-                }
-            },
-            syn::Type::Reference(type_reference) => {
-                if let syn::Type::Path(type_path) = *type_reference.elem.clone() {
-                    let ident = &type_path.path.segments.last().unwrap().ident;
-                    match ident.to_string().as_str() {
-                        "str" => "string".to_owned(),
-                        _ => panic!("Unsupported type: &{}", ident.to_string()),
-                    }
-                } else {
-                    panic!("Unsupported ref type: {}", quote::quote! {#type_reference}.to_string())
-                }
-            },
-            syn::Type::Tuple(tuple_type) if tuple_type.elems.is_empty() => {
-                "void".to_owned()
-            },
-            _ => panic!("Unsupported type: {}", quote::quote! {#rust_type}.to_string()),
-        }
+/// Refuses changes while in read-only (guest/demo) mode.
+async fn ensure_writable(config: &RwLock<crate::config::Config>) -> Result<(), MyError> {
+    if config.read().await.read_only {
+        return Err(MyError::ReadOnlyModeFail);
     }
-    
-    #[test]
-    fn build_command_type_definitions() {
-        let contents = std::fs::read_to_string("src/commands.rs").unwrap();
-        let ast = syn::parse_file(&contents).unwrap();
-    
-        let mut commands = Vec::new();
-    
-        for item in ast.items {
-            if let syn::Item::Fn(item_fn) = item {
-                let tauri_command_attr = item_fn.attrs.iter()
-                    .find(|attr| {
-                        attr.path().segments.iter().map(|seg| seg.ident.to_string()).collect::<Vec<_>>() == ["tauri", "command"]
-                    });
-    
-                if tauri_command_attr.is_some() {
-                    let command_name = item_fn.sig.ident.to_string();
-    
-                    let mut arg_types = Vec::new();
-                    for arg in &item_fn.sig.inputs {
-                        if let syn::FnArg::Typed(pat_type) = arg {
-                            if let syn::Pat::Ident(pat_ident) = &*pat_type.pat {
-                                // Filter out State and AppHandle parameters
-                                let ty_string = quote::quote! {#pat_type.ty}.to_string();
-                                if !ty_string.contains("State") && !ty_string.contains("AppHandle") {
-                                    let ts_type = rust_type_to_ts(&pat_type.ty);
-                                    arg_types.push(format!("{}: {}", pat_ident.ident, ts_type));
-                                }
-                            }
-                        }
-                    }
-    
-                    let return_type = if let syn::ReturnType::Type(_, ty) = &item_fn.sig.output {
-                        rust_type_to_ts(ty)
-                    } else {
-                        String::new()
-                    };
-    
-                    let command_definition = format!("    {}: {{\n        returns: {},\n        args: {{ {} }}\n    }}", command_name, return_type, arg_types.join(", "));
-                    commands.push(command_definition);
-                }
-            }
-        }
-    
-        // build file contents
-        let warning_header = "// THIS FILE IS AUTO-GENERATED BY CARGO TESTS! DO NOT EDIT!";
-        let invoke_import = "import { invoke as invokeRaw } from \"@tauri-apps/api\";";
-        let tauri_commands = format!("type TauriCommands = {{\n{}\n}};", commands.join(",\n"));
-        let invoke_fn = indoc::indoc!{"
-            export function invoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T][\"args\"]): Promise<TauriCommands[T][\"returns\"]> {
-                return invokeRaw(cmd, args);
-            }
-        "};
-        let output = format!("{}\n\n{}\n\n{}\n\n{}", warning_header, invoke_import, tauri_commands, invoke_fn);
+    Ok(())
+}
 
-        // dump to file
-        std::fs::create_dir_all("../src/lib/bindings").unwrap();
-        let definitions_file = std::fs::File::create("../src/lib/bindings/tauri_commands.d.ts").unwrap();
-        std::io::Write::write_all(&mut std::io::BufWriter::new(definitions_file), output.as_bytes()).unwrap();
+/// Writes the history, except in read-only mode where chats are only kept in memory.
+async fn save_history(
+    mgr: &ConversationManager,
+    config: &RwLock<crate::config::Config>,
+) -> Result<(), MyError> {
+    let config = config.read().await;
+    if config.read_only {
+        return Ok(());
     }
-    
+    mgr.write_to_disk(&config.conversation_history_save_path)
+        .map_err(|_| MyError::ConversationWriteToDiskFail)
+}
+
+/// Lists every command with its arguments and keybinding, for the command palette.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_app_actions(
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<Vec<AppActionPayload>, MyError> {
+    let keybindings = keybindings::effective(&config.read().await.keybindings);
+    Ok(actions::app_actions(&keybindings))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_appearance(
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<AppearancePayload, MyError> {
+    Ok(config.read().await.appearance.clone().into())
+}
+
+/// Persists the appearance and broadcasts it as `appearance_changed` so every window restyles.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_appearance<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    appearance: AppearancePayload,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let appearance: AppearanceConfig = appearance.into();
+    if !appearance.is_valid() {
+        return Err(MyError::AppearanceInvalidFail);
+    }
+    {
+        let mut config = config.write().await;
+        config.appearance = appearance.clone();
+        config
+            .write_to_disk()
+            .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+    }
+
+    events::emit_all(
+        &app_handle,
+        "appearance_changed",
+        AppearancePayload::from(appearance),
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_code_themes() -> Result<Vec<String>, MyError> {
+    Ok(appearance::code_themes())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_locales() -> Result<Vec<LocalePayload>, MyError> {
+    let current = i18n::current_locale();
+    Ok(i18n::list_locales()
+        .into_iter()
+        .map(|(code, name)| LocalePayload {
+            code: code.to_string(),
+            name: name.to_string(),
+            active: code == current,
+        })
+        .collect())
+}
+
+/// Changes the language of backend strings such as error messages and default titles.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_locale<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    locale: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    if !i18n::set_locale(locale) {
+        return Err(MyError::LocaleUnknownFail);
+    }
+    {
+        let mut config = config.write().await;
+        config.locale = locale.to_string();
+        config
+            .write_to_disk()
+            .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+    }
+
+    events::emit_all(&app_handle, "locale_changed", locale.to_string())
+        .map_err(|_| MyError::EmitFail)?;
+    Ok(())
+}
+
+/// Every setting as the app currently uses it, secrets masked, with whether it came from
+/// the defaults, the config file, an environment variable or a command line flag.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_effective_config(
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<Vec<EffectiveConfigValuePayload>, MyError> {
+    let path = crate::config::Config::get_config_path().map_err(|_| MyError::NoConfigDirFail)?;
+    // A missing or unreadable file leaves everything not overridden at its default.
+    let file = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let config = config.read().await;
+    Ok(
+        overrides::effective_values(&config.redacted(), &file, &config.overrides)
+            .into_iter()
+            .map(EffectiveConfigValuePayload::from)
+            .collect(),
+    )
+}
+
+/// Version, platform, data locations and provider, for the About dialog and bug reports.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_app_info<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<AppInfoPayload, MyError> {
+    let config_dir =
+        crate::config::Config::get_config_dir().map_err(|_| MyError::NoConfigDirFail)?;
+    let config = config.read().await;
+    let sync_backend = match &config.sync.backend {
+        None => "none",
+        Some(SyncBackendConfig::WebDav { .. }) => "webdav",
+        Some(SyncBackendConfig::Folder { .. }) => "folder",
+    };
+    // Read-only mode answers with the mock provider instead of the API.
+    let (provider_name, model) = if config.read_only {
+        ("mock", provider::MOCK_MODEL.to_string())
+    } else {
+        ("openai", config.model_chain().remove(0))
+    };
+    Ok(AppInfoPayload {
+        version: app_handle.package_info().version.to_string(),
+        build_hash: option_env!("EHYAIOESS_BUILD_HASH")
+            .unwrap_or("unknown")
+            .to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        workspace: workspaces::active(),
+        config_dir: config_dir.display().to_string(),
+        history_path: config.conversation_history_save_path.clone(),
+        storage_backend: "json_file".to_string(),
+        sync_backend: sync_backend.to_string(),
+        provider: provider_name.to_string(),
+        model,
+        api_key_profile: config.active_profile.clone(),
+    })
+}
+
+/// Effective shortcuts by action name, defaults included.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_keybindings(
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<HashMap<String, String>, MyError> {
+    Ok(keybindings::effective(&config.read().await.keybindings)
+        .into_iter()
+        .collect())
+}
+
+/// An empty `shortcut` unbinds the action; binding it to its default again drops the override.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_keybinding<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    action: &str,
+    shortcut: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    if !actions::is_action(action) {
+        return Err(MyError::KeybindingInvalidFail);
+    }
+    let effective: keybindings::Keybindings = {
+        let mut config = config.write().await;
+        keybindings::set(&mut config.keybindings, action, shortcut).map_err(|e| match e {
+            keybindings::KeybindingError::Invalid => MyError::KeybindingInvalidFail,
+            keybindings::KeybindingError::Conflict(_) => MyError::KeybindingConflictFail,
+        })?;
+        config
+            .write_to_disk()
+            .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+        keybindings::effective(&config.keybindings)
+    };
 
+    events::emit_all(&app_handle, "keybindings_changed", effective)
+        .map_err(|_| MyError::EmitFail)?;
+    Ok(())
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -161,25 +303,227 @@ pub async fn list_conversation_titles(
     let mgr = conversation_manager.read().await;
     let titles_by_id = mgr
         .conversations
-        .iter()
-        .map(|(id, conv)| (id.to_string(), conv.get_title().into_owned()))
+        .summaries()
+        .into_iter()
+        .map(|summary| (summary.id.to_string(), summary.title()))
         .collect();
     Ok(titles_by_id)
 }
 
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_conversation_tags(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+) -> Result<HashMap<String, Vec<String>>, MyError> {
+    let mgr = conversation_manager.read().await;
+    let tags_by_id = mgr
+        .conversations
+        .summaries()
+        .into_iter()
+        .map(|summary| (summary.id.to_string(), summary.tags))
+        .collect();
+    Ok(tags_by_id)
+}
+
+/// Replaces the conversation's tags, normalized and capped at `tagging::MAX_TAGS`; an empty
+/// list clears them and keeps automatic tagging from adding new ones.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_conversation_tags<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    tags: Vec<String>,
+) -> Result<Vec<String>, MyError> {
+    ensure_writable(&config).await?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let tags = tagging::normalize_tags(tags.iter().map(String::as_str));
+    {
+        let mut mgr = conversation_manager.write().await;
+        let conv = mgr.conversation_mut(&conversation_id)?;
+        conv.add_event(ConversationTagsChangedEvent {
+            tags: tags.clone(),
+            automatic: false,
+        });
+        mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+            .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    }
+
+    events::emit_all(
+        &app_handle,
+        "conversation_tags_changed",
+        ConversationTagsChangedEventPayload {
+            conversation_id,
+            tags: tags.clone(),
+            automatic: false,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Ok(tags)
+}
+
+/// Embeddings are requested this many conversations at a time.
+const EMBEDDING_BATCH_SIZE: usize = 100;
+
+/// Groups the conversations with messages into at most `k` topics, each labeled by the model,
+/// for the sidebar's "by topic" view. Embeddings are cached, so only new or changed
+/// conversations are embedded again.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cluster_conversations<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    profile_clients: State<'_, RwLock<ProfileClients>>,
+    k: usize,
+) -> Result<Vec<ConversationClusterPayload>, MyError> {
+    ensure_writable(&config).await?;
+    if k == 0 {
+        return Err(MyError::ClusterCountInvalidFail);
+    }
+    let (active_profile, models, cache_limits) = {
+        let config = config.read().await;
+        (
+            config.active_profile.clone(),
+            config.model_chain(),
+            config.embedding_cache.clone(),
+        )
+    };
+    let embeddings_path =
+        crate::config::Config::get_embeddings_path().map_err(|_| MyError::NoConfigDirFail)?;
+    // Sorted so the same history always clusters the same way.
+    let mut conversations: Vec<(uuid::Uuid, String, String)> = conversation_manager
+        .read()
+        .await
+        .conversations
+        .values()
+        .filter(|conv| conv.message_count() > 0)
+        .map(|conv| {
+            (
+                conv.id,
+                conv.get_title().into_owned(),
+                embeddings::embedding_text(&conv),
+            )
+        })
+        .collect();
+    conversations.sort_by_key(|(id, _, _)| *id);
+
+    let now = background::unix_now();
+    let mut cache = EmbeddingCache::open(&embeddings_path);
+    let mut vectors: Vec<Option<Vec<f32>>> = conversations
+        .iter()
+        .map(|(_, _, text)| cache.get(provider::EMBEDDING_MODEL, text, now))
+        .collect();
+    let missing: Vec<usize> = (0..vectors.len()).filter(|&i| vectors[i].is_none()).collect();
+    let profile_clients = profile_clients.read().await;
+    let mut progress = Progress::start(&app_handle, ProgressKind::Embedding);
+    for (batch_index, batch) in missing.chunks(EMBEDDING_BATCH_SIZE).enumerate() {
+        progress.update(batch_index * EMBEDDING_BATCH_SIZE, missing.len());
+        let inputs: Vec<String> = batch.iter().map(|&i| conversations[i].2.clone()).collect();
+        let embedded = profile_clients
+            .embed_with_failover(&active_profile, &inputs)
+            .await
+            .map_err(|_| MyError::ConversationAIResponseFail)?;
+        for (&i, vector) in batch.iter().zip(embedded) {
+            cache.insert(provider::EMBEDDING_MODEL, &conversations[i].2, vector.clone(), now);
+            vectors[i] = Some(vector);
+        }
+    }
+    cache.prune(&cache_limits, now);
+    // The cache only saves requests, so failing to write it doesn't fail the grouping.
+    if let Err(e) = cache.save(&embeddings_path) {
+        eprintln!("Failed to save embeddings: {}", e);
+    }
+
+    let vectors: Vec<Vec<f32>> = vectors
+        .into_iter()
+        .collect::<Option<_>>()
+        .ok_or(MyError::ConversationAIResponseFail)?;
+    let assignments = clustering::kmeans(&vectors, k);
+    let members: Vec<(uuid::Uuid, String)> = conversations
+        .into_iter()
+        .map(|(id, title, _)| (id, title))
+        .collect();
+    let mut clusters = Vec::new();
+    for group in clustering::group(&members, &assignments) {
+        let titles: Vec<String> = group.iter().map(|(_, title)| title.clone()).collect();
+        // Labels aren't about any one conversation, so their usage isn't logged.
+        let ai_response = profile_clients
+            .send_with_failover(
+                &active_profile,
+                &models,
+                &clustering::label_messages(&titles),
+                &[],
+                Sampling::default(),
+            )
+            .await
+            .map_err(|_| MyError::ConversationAIResponseFail)?;
+        clusters.push(ConversationClusterPayload {
+            label: clustering::parse_label(
+                &ai_response
+                    .message()
+                    .ok_or(MyError::ConversationAIResponseFail)?
+                    .content,
+            ),
+            conversation_ids: group.into_iter().map(|(id, _)| id).collect(),
+        });
+    }
+    progress.finish();
+    Ok(clusters)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_unread_counts(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+) -> Result<HashMap<String, usize>, MyError> {
+    let mgr = conversation_manager.read().await;
+    let unread_by_id = mgr
+        .conversations
+        .iter()
+        .map(|(id, conv)| (id.to_string(), conv.unread_count()))
+        .collect();
+    Ok(unread_by_id)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn mark_conversation_read<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    conversation_id: &str,
+) -> Result<(), MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    {
+        let mut mgr = conversation_manager.write().await;
+        let conv = mgr.conversation_mut(&conversation_id)?;
+        // Viewing an already read conversation shouldn't grow its history.
+        if conv.unread_count() == 0 {
+            return Ok(());
+        }
+        let read_through = conv.message_count();
+        conv.add_event(ConversationReadEvent { read_through });
+        save_history(&mgr, &config).await?;
+    }
+
+    events::emit_all(
+        &app_handle,
+        "unread_count_changed",
+        UnreadCountChangedEventPayload {
+            conversation_id,
+            unread_count: 0,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Ok(())
+}
+
 #[tauri::command(rename_all = "snake_case")]
 pub async fn get_conversation(
     conversation_manager: State<'_, RwLock<ConversationManager>>,
     conversation_id: &str,
 ) -> Result<Conversation, MyError> {
-    let conversation_id =
-        uuid::Uuid::parse_str(conversation_id).map_err(|_| MyError::FindByIDFail)?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
     let mgr = conversation_manager.read().await;
-    let conversation = mgr
-        .conversations
-        .get(&conversation_id)
-        .ok_or(MyError::FindByIDFail)?;
-    Ok(conversation.clone())
+    let conversation = mgr.conversation(&conversation_id)?;
+    Ok(Conversation::clone(&conversation))
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -187,13 +531,9 @@ pub async fn get_conversation_title(
     conversation_manager: State<'_, RwLock<ConversationManager>>,
     conversation_id: &str,
 ) -> Result<String, MyError> {
-    let conversation_id =
-        uuid::Uuid::parse_str(conversation_id).map_err(|_| MyError::FindByIDFail)?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
     let mgr = conversation_manager.read().await;
-    let conversation = mgr
-        .conversations
-        .get(&conversation_id)
-        .ok_or(MyError::FindByIDFail)?;
+    let conversation = mgr.conversation(&conversation_id)?;
     Ok(conversation.get_title().into_owned())
 }
 
@@ -202,21 +542,54 @@ pub async fn get_conversation_messages(
     conversation_manager: State<'_, RwLock<ConversationManager>>,
     conversation_id: &str,
 ) -> Result<Vec<ConversationMessagePayload>, MyError> {
-    let conversation_id =
-        uuid::Uuid::parse_str(conversation_id).map_err(|_| MyError::FindByIDFail)?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
     let mgr = conversation_manager.read().await;
-    let conversation = mgr
-        .conversations
-        .get(&conversation_id)
-        .ok_or(MyError::FindByIDFail)?;
+    let conversation = mgr.conversation(&conversation_id)?;
+    let pending_partial = conversation.pending_partial().map(|(id, _)| id);
     let message_events = conversation
         .history
         .iter()
         .filter_map(|record| {
             if let ConversationEvent::MessageAdded(msg) = &record.event {
+                let (filter_hits, redacted) = conversation
+                    .message_filter_hits(record.id)
+                    .unwrap_or((&[], false));
                 Some(ConversationMessagePayload {
+                    id: record.id,
                     author: msg.author,
                     content: msg.content.clone(),
+                    bookmarked: conversation.message_bookmarked_at(record.id).is_some(),
+                    pinned_context: conversation.is_message_pinned(record.id),
+                    note: conversation.message_note(record.id).map(str::to_string),
+                    rating: conversation.message_rating(record.id).map(|(rating, _)| rating),
+                    translations: conversation
+                        .message_translations(record.id)
+                        .into_iter()
+                        .map(|(language, content)| MessageTranslationPayload {
+                            language: language.to_string(),
+                            content: content.to_string(),
+                        })
+                        .collect(),
+                    language: msg.detected_language(),
+                    partial: false,
+                    guardrail_hits: filter_hits.iter().map(GuardrailHitPayload::from).collect(),
+                    redacted,
+                })
+            } else if let ConversationEvent::MessagePartial(partial) = &record.event {
+                // Partial replies that were continued or superseded are left out.
+                (pending_partial == Some(record.id)).then(|| ConversationMessagePayload {
+                    id: record.id,
+                    author: chatgpt::types::Role::Assistant,
+                    content: partial.content.clone(),
+                    bookmarked: false,
+                    pinned_context: false,
+                    note: None,
+                    rating: None,
+                    translations: Vec::new(),
+                    language: None,
+                    partial: true,
+                    guardrail_hits: Vec::new(),
+                    redacted: false,
                 })
             } else {
                 None
@@ -232,187 +605,4042 @@ pub struct ConversationAddedEvent {
     pub title: String,
 }
 #[tauri::command(rename_all = "snake_case")]
-pub async fn new_conversation(
-    conversation_manager: State<'_, RwLock<ConversationManager>>,
-    config: State<'_, crate::config::Config>,
-    app_handle: tauri::AppHandle,
+pub async fn new_conversation<R: Runtime>(app_handle: tauri::AppHandle<R>) -> Result<Conversation, MyError> {
+    create_conversation(&app_handle).await
+}
+
+/// Shared by the `new_conversation` command and `ehyaioess://new` links.
+pub async fn create_conversation<R: Runtime>(app_handle: &tauri::AppHandle<R>) -> Result<Conversation, MyError> {
+    add_new_conversation(app_handle, Conversation::new()).await
+}
+
+/// Adds a conversation that was just made to the history and announces it.
+pub async fn add_new_conversation<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    conv: Conversation,
 ) -> Result<Conversation, MyError> {
+    let conversation_manager = app_handle.state::<RwLock<ConversationManager>>();
+    let config = app_handle.state::<RwLock<crate::config::Config>>();
     let mut mgr = conversation_manager.write().await;
-    let conv = Conversation::new();
 
     mgr.conversations.insert(conv.id, conv.clone());
-    mgr.write_to_disk(&config.conversation_history_save_path)
-        .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    save_history(&mgr, &config).await?;
 
     // Drop the lock before emitting events.
     drop(mgr);
 
-    app_handle
-        .emit_all(
-            "new_conversation",
-            ConversationAddedEvent {
-                conversation_id: conv.id,
-                title: conv.get_title().into_owned(),
-            },
-        )
-        .map_err(|_| MyError::EmitFail)?;
+    events::emit_all(
+        app_handle,
+        "new_conversation",
+        ConversationAddedEvent {
+            conversation_id: conv.id,
+            title: conv.get_title().into_owned(),
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
     Ok(conv)
 }
 
+/// Renames the conversation, returning the title as stored after `validation::title` normalized it.
 #[tauri::command(rename_all = "snake_case")]
-pub async fn set_conversation_title(
+pub async fn set_conversation_title<R: Runtime>(
     conversation_manager: State<'_, RwLock<ConversationManager>>,
-    config: State<'_, crate::config::Config>,
-    app_handle: tauri::AppHandle,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
     conversation_id: &str,
     new_title: &str,
-) -> Result<(), MyError> {
-    let conversation_id =
-        uuid::Uuid::parse_str(conversation_id).map_err(|_| MyError::UUIDParseFail)?;
-    let new_title_trimmed = new_title.trim();
+) -> Result<String, MyError> {
+    ensure_writable(&config).await?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let new_title = validation::title("new_title", new_title)?;
 
     {
         let mut mgr = conversation_manager.write().await;
-        let conv = mgr
-            .conversations
-            .get_mut(&conversation_id)
-            .ok_or(MyError::FindByIDFail)?;
+        let conv = mgr.conversation_mut(&conversation_id)?;
         let current_title = conv.get_title();
-        if current_title.as_ref() == new_title_trimmed {
-            return Ok(());
+        if current_title.as_ref() == &new_title {
+            return Ok(new_title);
         }
-        conv.add_event(ConversationTitleChangedEvent {
-            new_title: new_title_trimmed.to_string(),
-        })
-    };
+        conv.try_add_event(ConversationTitleChangedEvent {
+            new_title: new_title.clone(),
+        })?;
+    }
 
     conversation_manager
         .read()
         .await
-        .write_to_disk(&config.conversation_history_save_path)
+        .write_to_disk(&config.read().await.conversation_history_save_path)
         .map_err(|_| MyError::ConversationWriteToDiskFail)?;
 
-    app_handle
-        .emit_all(
-            "conversation_title_changed",
-            ConversationTitleChangedEventPayload {
-                conversation_id,
-                new_title: new_title_trimmed.to_string(),
-            },
-        )
-        .map_err(|_| MyError::EmitFail)?;
+    events::emit_all(
+        &app_handle,
+        "conversation_title_changed",
+        ConversationTitleChangedEventPayload {
+            conversation_id,
+            new_title: new_title.clone(),
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
 
-    Ok(())
+    Ok(new_title)
+}
+
+/// How long typing has to pause before a draft is written to disk.
+const DRAFT_SAVE_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(750);
+
+fn schedule_draft_flush(tasks: &TaskSupervisor, draft_store: Arc<DraftStore>, revision: u64) {
+    tasks.spawn("draft_save", async move {
+        tokio::time::sleep(DRAFT_SAVE_DEBOUNCE).await;
+        if let Err(e) = draft_store.flush_if_current(revision) {
+            eprintln!("Failed to save drafts: {}", e);
+        }
+    });
 }
 
+/// Called on every keystroke; the disk write is debounced.
 #[tauri::command(rename_all = "snake_case")]
-pub async fn new_conversation_user_message(
-    app_handle: tauri::AppHandle,
-    config: State<'_, crate::config::Config>,
-    conversation_manager: State<'_, RwLock<ConversationManager>>,
+pub async fn save_draft(
+    config: State<'_, RwLock<crate::config::Config>>,
+    draft_store: State<'_, Arc<DraftStore>>,
+    tasks: State<'_, TaskSupervisor>,
     conversation_id: &str,
     content: &str,
 ) -> Result<(), MyError> {
-    let conversation_id =
-        uuid::Uuid::parse_str(conversation_id).map_err(|_| MyError::UUIDParseFail)?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let revision = draft_store.set(conversation_id, content);
+    // Drafts stay in memory in read-only mode.
+    if !config.read().await.read_only {
+        schedule_draft_flush(&tasks, draft_store.inner().clone(), revision);
+    }
+    Ok(())
+}
 
-    {
-        let mut mgr = conversation_manager.write().await;
-        let conv = mgr
-            .conversations
-            .get_mut(&conversation_id)
-            .ok_or(MyError::UUIDParseFail)?;
-        conv.add_event(ConversationMessageAddedEvent {
-            author: chatgpt::types::Role::User,
-            content: content.to_string(),
-        })
-        .clone()
-    };
+/// Empty when the conversation has no draft.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_draft(
+    draft_store: State<'_, Arc<DraftStore>>,
+    conversation_id: &str,
+) -> Result<String, MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    Ok(draft_store.get(conversation_id).unwrap_or_default())
+}
 
-    conversation_manager
+/// Characters, words and tokens of `content` for `model`, for a live estimate while composing.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_message_length_info(
+    config: State<'_, RwLock<crate::config::Config>>,
+    content: &str,
+    model: &str,
+) -> Result<MessageLengthInfoPayload, MyError> {
+    validation::max_length("content", content, validation::MAX_MESSAGE_LENGTH)?;
+    let catalog_path =
+        crate::config::Config::get_model_catalog_path().map_err(|_| MyError::NoConfigDirFail)?;
+    let prices = &config.read().await.model_pricing;
+    let catalog = ModelCatalog::open(&catalog_path, prices);
+    Ok(LengthInfo::measure(content, &catalog.info(model, prices)).into())
+}
+
+/// Asks the provider which models the active profile's key may use and caches them, with
+/// their context windows, vision support and prices, for the model pickers and token budgets.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn refresh_model_catalog(
+    config: State<'_, RwLock<crate::config::Config>>,
+    profile_clients: State<'_, RwLock<ProfileClients>>,
+) -> Result<ModelCatalogPayload, MyError> {
+    ensure_writable(&config).await?;
+    let (active_profile, prices) = {
+        let config = config.read().await;
+        (config.active_profile.clone(), config.model_pricing.clone())
+    };
+    let listing = profile_clients
         .read()
         .await
-        .write_to_disk(&config.conversation_history_save_path)
-        .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+        .list_models_with_failover(&active_profile)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to list models: {}", e);
+            MyError::ModelCatalogFail
+        })?;
+    let catalog_path =
+        crate::config::Config::get_model_catalog_path().map_err(|_| MyError::NoConfigDirFail)?;
+    let catalog = ModelCatalog::from_listing(&listing, background::unix_now(), &prices);
+    catalog
+        .save(&catalog_path)
+        .map_err(|_| MyError::ModelCatalogFail)?;
+    Ok(catalog.into())
+}
 
-    app_handle
-        .emit_all(
-            "conversation_message_added",
-            ConversationMessageAddedEventPayload {
-                conversation_id,
-                author: chatgpt::types::Role::User,
-                content: content.to_string(),
-            },
-        )
-        .map_err(|_| MyError::EmitFail)?;
+/// The models as of the last `refresh_model_catalog`; none before the first.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_model_catalog(
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<ModelCatalogPayload, MyError> {
+    let catalog_path =
+        crate::config::Config::get_model_catalog_path().map_err(|_| MyError::NoConfigDirFail)?;
+    Ok(ModelCatalog::open(&catalog_path, &config.read().await.model_pricing).into())
+}
 
-    Ok(())
+#[tauri::command(rename_all = "snake_case")]
+pub async fn new_conversation_user_message<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    content: &str,
+) -> Result<(), MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    validation::max_length("content", content, validation::MAX_MESSAGE_LENGTH)?;
+    add_user_messages(&app_handle, conversation_id, &[content.to_string()]).await
 }
 
+/// Sends several messages as separate turns, e.g. a long email split into parts or a file
+/// followed by instructions, with at most one reply after the last of them.
 #[tauri::command(rename_all = "snake_case")]
-pub async fn new_conversation_assistant_message(
-    app_handle: tauri::AppHandle,
-    config: State<'_, crate::config::Config>,
-    chatgpt: State<'_, ChatGPT>,
-    conversation_manager: State<'_, RwLock<ConversationManager>>,
+pub async fn new_conversation_user_messages<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
     conversation_id: &str,
+    contents: Vec<String>,
 ) -> Result<(), MyError> {
-    let conversation_id =
-        uuid::Uuid::parse_str(conversation_id).map_err(|_| MyError::UUIDParseFail)?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    for content in &contents {
+        validation::max_length("contents", content, validation::MAX_MESSAGE_LENGTH)?;
+    }
+    add_user_messages(&app_handle, conversation_id, &contents).await
+}
 
-    let response = {
-        let mut mgr = conversation_manager.write().await;
-        let conv = mgr
-            .conversations
-            .get_mut(&conversation_id)
-            .ok_or(MyError::UUIDParseFail)?;
-
-        let mut ai_conversation = conv.into_chatgpt_conversation(chatgpt.inner().clone());
-        // remove the last message from the conversation
-        let ai_prompt = ai_conversation
-            .history
-            .pop()
-            .ok_or(MyError::ConversationEmptyFail)?;
-        let ai_response = ai_conversation
-            .send_message(ai_prompt.content)
-            .await
-            .map_err(|_| MyError::ConversationAIResponseFail)?;
+async fn add_user_messages<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    conversation_id: uuid::Uuid,
+    contents: &[String],
+) -> Result<(), MyError> {
+    if contents.is_empty() {
+        return Ok(());
+    }
+    if append_user_messages(app_handle, conversation_id, contents).await? {
+        respond(app_handle, conversation_id, false).await?;
+    }
+    Ok(())
+}
 
-        let response = ai_response.message().content.clone();
-        conv.add_event(ConversationMessageAddedEvent {
-            author: chatgpt::types::Role::Assistant,
-            content: response.clone(),
-        });
-        response
+/// Adds and announces the messages without replying to them. Returns whether the conversation
+/// replies on its own.
+async fn append_user_messages<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    conversation_id: uuid::Uuid,
+    contents: &[String],
+) -> Result<bool, MyError> {
+    let config = app_handle.state::<RwLock<crate::config::Config>>();
+    let conversation_manager = app_handle.state::<RwLock<ConversationManager>>();
+    let draft_store = app_handle.state::<Arc<DraftStore>>();
+
+    let (message_ids, auto_respond) = {
+        let mut mgr = conversation_manager.write().await;
+        let conv = mgr.conversation_mut(&conversation_id)?;
+        let mut message_ids = Vec::with_capacity(contents.len());
+        for content in contents {
+            let record = conv.try_add_event(ConversationMessageAddedEvent {
+                author: chatgpt::types::Role::User,
+                content: content.clone(),
+                model: None,
+                language: None,
+                citations: Vec::new(),
+            })?;
+            message_ids.push(record.id);
+        }
+        (message_ids, conv.auto_respond())
     };
 
-    conversation_manager
-        .read()
-        .await
-        .write_to_disk(&config.conversation_history_save_path)
-        .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    save_history(&*conversation_manager.read().await, &config).await?;
+
+    // The draft has been sent.
+    let revision = draft_store.set(conversation_id, "");
+    if !config.read().await.read_only {
+        schedule_draft_flush(
+            &app_handle.state::<TaskSupervisor>(),
+            draft_store.inner().clone(),
+            revision,
+        );
+    }
 
-    app_handle
-        .emit_all(
+    for (message_id, content) in message_ids.into_iter().zip(contents) {
+        events::emit_all(
+            app_handle,
             "conversation_message_added",
             ConversationMessageAddedEventPayload {
                 conversation_id,
-                author: chatgpt::types::Role::Assistant,
-                content: response,
+                message_id,
+                author: chatgpt::types::Role::User,
+                content: content.clone(),
             },
         )
         .map_err(|_| MyError::EmitFail)?;
-
-    Ok(())
+    }
+    Ok(auto_respond)
 }
 
+/// Asks for an explanation of pasted code in `conversation_id`, or in a new conversation
+/// titled after the language without one. Code too long for one message is explained part by
+/// part, each part a question with its own reply. Returns the conversation's id.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn explain_code<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: Option<&str>,
+    content: &str,
+    language: &str,
+) -> Result<String, MyError> {
+    let conversation_id = conversation_id
+        .map(|conversation_id| validation::id("conversation_id", conversation_id))
+        .transpose()?;
+    validation::not_empty("content", content)?;
+    validation::max_length("content", content, explain::MAX_CODE_LENGTH)?;
+    validation::max_length("language", language, validation::MAX_TITLE_LENGTH)?;
+    let questions = explain::explain_questions(content, language);
+    let conversation_id = match conversation_id {
+        Some(conversation_id) => {
+            let mgr = app_handle.state::<RwLock<ConversationManager>>();
+            mgr.read().await.conversation(&conversation_id)?;
+            conversation_id
+        }
+        None => {
+            let mut conv = Conversation::new();
+            conv.add_event(ConversationTitleChangedEvent {
+                new_title: explain::explain_title(language),
+            });
+            add_new_conversation(&app_handle, conv).await?.id
+        }
+    };
+    // Each part is answered before the next is asked, so its reply can build on the last.
+    for question in questions {
+        append_user_messages(&app_handle, conversation_id, &[question]).await?;
+        respond(&app_handle, conversation_id, false).await?;
+    }
+    Ok(conversation_id.to_string())
+}
 
-
+/// Summarizes a unified diff, pasted or read from a .diff or .patch file, the way a commit
+/// message would, with a line for each file changed so review tools can show it by the file.
 #[tauri::command(rename_all = "snake_case")]
-pub async fn list_files() -> Result<Vec<String>, MyError> {
+pub async fn summarize_diff(
+    config: State<'_, RwLock<crate::config::Config>>,
+    profile_clients: State<'_, RwLock<ProfileClients>>,
+    path_or_text: &str,
+) -> Result<DiffSummaryPayload, MyError> {
+    ensure_writable(&config).await?;
+    let text = match diff::looks_like_diff(path_or_text) {
+        true => path_or_text.to_string(),
+        false => {
+            let path =
+                validation::path("path_or_text", path_or_text.trim(), &diff::DIFF_EXTENSIONS)?;
+            std::fs::read_to_string(path).map_err(|_| MyError::DiffReadFail)?
+        }
+    };
+    let files = diff::parse_diff(&text);
+    if files.is_empty() {
+        return Err(MyError::DiffInvalidFail);
+    }
+    let (active_profile, models) = {
+        let config = config.read().await;
+        (config.active_profile.clone(), config.model_chain())
+    };
+    // A diff isn't about any one conversation, so its usage isn't logged.
+    let ai_response = profile_clients
+        .read()
+        .await
+        .send_with_failover(
+            &active_profile,
+            &models,
+            &diff::summary_messages(&files),
+            &[],
+            Sampling::default(),
+        )
+        .await
+        .map_err(|_| MyError::ConversationAIResponseFail)?;
+    let summary = diff::parse_summary(
+        &ai_response
+            .message()
+            .ok_or(MyError::ConversationAIResponseFail)?
+            .content,
+        &files,
+    );
+    Ok(summary.into())
+}
+
+/// The screens and windows `capture_screenshot` can capture.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_capture_targets() -> Result<Vec<CaptureTargetPayload>, MyError> {
+    tauri::async_runtime::spawn_blocking(screenshot::capture_targets)
+        .await
+        .map_err(|_| MyError::ScreenshotFail)?
+}
+
+/// Captures a screen or window into the attachment store and returns the attachment's name,
+/// which a message shows the image by as `![screenshot](attachment:<name>)`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn capture_screenshot(
+    config: State<'_, RwLock<crate::config::Config>>,
+    region: CaptureRegionPayload,
+) -> Result<String, MyError> {
+    ensure_writable(&config).await?;
+    let compress = config.read().await.compress_history;
+    let png = tauri::async_runtime::spawn_blocking(move || screenshot::capture_png(&region))
+        .await
+        .map_err(|_| MyError::ScreenshotFail)??;
+    let attachments_dir =
+        crate::config::Config::get_attachments_dir().map_err(|_| MyError::NoConfigDirFail)?;
+    let name = format!("screenshot-{}.png", uuid::Uuid::new_v4());
+    compression::write_attachment(&attachments_dir, &name, &png, compress)
+        .map_err(|_| MyError::AttachmentWriteFail)?;
+    Ok(name)
+}
+
+/// Starts dictating into the composer of `conversation_id`, or of whichever is open without
+/// one, transcribing in `language` (an ISO 639-1 code) or whatever is heard. Dictation ends
+/// after a pause or with `stop_dictation`; returns the id its events are sent with.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn start_dictation<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    tasks: State<'_, TaskSupervisor>,
+    active_dictation: State<'_, ActiveDictation>,
+    conversation_id: Option<&str>,
+    language: Option<&str>,
+) -> Result<String, MyError> {
+    ensure_writable(&config).await?;
+    let conversation_id = match conversation_id {
+        Some(conversation_id) => {
+            let conversation_id = validation::id("conversation_id", conversation_id)?;
+            conversation_manager
+                .read()
+                .await
+                .conversation(&conversation_id)?;
+            Some(conversation_id)
+        }
+        None => None,
+    };
+    let language = language
+        .map(str::trim)
+        .filter(|language| !language.is_empty())
+        .map(str::to_string);
+    let mut active = active_dictation.0.lock().unwrap();
+    if active.is_some() {
+        return Err(MyError::DictationActiveFail);
+    }
+    let (stop, stopped) = std::sync::mpsc::channel();
+    let recording = microphone::record(stopped)?;
+    let dictation_id = uuid::Uuid::new_v4();
+    *active = Some(DictationHandle {
+        id: dictation_id,
+        stop,
+    });
+    tasks.spawn(
+        "dictation",
+        microphone::transcribe(
+            app_handle,
+            dictation_id,
+            conversation_id,
+            language,
+            recording,
+        ),
+    );
+    Ok(dictation_id.to_string())
+}
+
+/// Stops recording the dictation; what was said is still transcribed and announced as
+/// `dictation_finished`. False when no dictation was running.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn stop_dictation(active_dictation: State<'_, ActiveDictation>) -> Result<bool, MyError> {
+    let active = active_dictation.0.lock().unwrap();
+    if let Some(handle) = active.as_ref() {
+        handle.stop();
+    }
+    Ok(active.is_some())
+}
+
+/// Reads the conversation aloud from `from_message_id`, or from the start, announcing every
+/// change as `playback_state_changed`. Resumes instead when the conversation is paused.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn play_conversation<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    tasks: State<'_, TaskSupervisor>,
+    read_aloud: State<'_, ReadAloud>,
+    conversation_id: &str,
+    from_message_id: Option<&str>,
+) -> Result<PlaybackStatePayload, MyError> {
+    ensure_writable(&config).await?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let from_message_id = from_message_id
+        .map(|id| validation::id("from_message_id", id))
+        .transpose()?;
+    let conversation = conversation_manager
+        .read()
+        .await
+        .conversation(&conversation_id)?;
+    let messages = read_aloud::queue_messages(&conversation);
+    let mut player = read_aloud.0.lock().unwrap();
+    if let Some(session) = player.session.as_mut() {
+        if session.queue.conversation_id == conversation_id
+            && session.queue.state == PlaybackState::Paused
+            && from_message_id.is_none()
+        {
+            session.resume();
+            let state = player.payload(None);
+            playback::announce(&app_handle, state.clone());
+            return Ok(state);
+        }
+    }
+    if messages.is_empty() {
+        return Err(MyError::PlaybackEmptyFail);
+    }
+    // Whatever was playing stops as its speakers close.
+    player.session = None;
+    let (stop, stopped) = std::sync::mpsc::channel();
+    let speaker = playback::speakers(stopped)?;
+    let session_id = uuid::Uuid::new_v4();
+    player.session = Some(Session {
+        id: session_id,
+        queue: PlaybackQueue::new(conversation_id, messages, from_message_id),
+        speaker,
+        skip: Default::default(),
+        stop,
+    });
+    let state = player.payload(None);
+    playback::announce(&app_handle, state.clone());
+    tasks.spawn("read_aloud", playback::read(app_handle, session_id));
+    Ok(state)
+}
+
+/// Pauses reading aloud where it is, to carry on with `play_conversation`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn pause_playback<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    read_aloud: State<'_, ReadAloud>,
+) -> Result<PlaybackStatePayload, MyError> {
+    let mut player = read_aloud.0.lock().unwrap();
+    if let Some(session) = player.session.as_mut() {
+        session.pause();
+        playback::announce(&app_handle, player.payload(None));
+    }
+    Ok(player.payload(None))
+}
+
+/// Moves on to the next message, stopping after the last one.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn skip_message(
+    read_aloud: State<'_, ReadAloud>,
+) -> Result<PlaybackStatePayload, MyError> {
+    let player = read_aloud.0.lock().unwrap();
+    // The state changes, and is announced, once the reading task has moved on.
+    if let Some(session) = player.session.as_ref() {
+        session.skip();
+    }
+    Ok(player.payload(None))
+}
+
+/// Stops reading aloud; `play_conversation` starts over.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn stop_playback<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    read_aloud: State<'_, ReadAloud>,
+) -> Result<PlaybackStatePayload, MyError> {
+    let mut player = read_aloud.0.lock().unwrap();
+    if player.session.take().is_some() {
+        playback::announce(&app_handle, player.payload(None));
+    }
+    Ok(player.payload(None))
+}
+
+/// Sets how fast messages are read, from the next part read on.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_playback_speed<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    read_aloud: State<'_, ReadAloud>,
+    speed: f32,
+) -> Result<PlaybackStatePayload, MyError> {
+    if !(read_aloud::MIN_SPEED..=read_aloud::MAX_SPEED).contains(&speed) {
+        return Err(MyError::PlaybackSpeedInvalidFail);
+    }
+    let mut player = read_aloud.0.lock().unwrap();
+    player.speed = speed;
+    let state = player.payload(None);
+    playback::announce(&app_handle, state.clone());
+    Ok(state)
+}
+
+/// What is being read aloud and how far along it is.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_playback_state(
+    read_aloud: State<'_, ReadAloud>,
+) -> Result<PlaybackStatePayload, MyError> {
+    Ok(read_aloud.0.lock().unwrap().payload(None))
+}
+
+/// Opens another window, showing `conversation_id` when given; returns the window's label.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn open_window<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    window_layout: State<'_, Arc<WindowLayout>>,
+    conversation_id: Option<&str>,
+) -> Result<String, MyError> {
+    let conversation_id = match conversation_id {
+        Some(conversation_id) => {
+            let conversation_id = validation::id("conversation_id", conversation_id)?;
+            conversation_manager
+                .read()
+                .await
+                .conversation(&conversation_id)?;
+            Some(conversation_id)
+        }
+        None => None,
+    };
+    let label = windows::new_label();
+    window_layout
+        .set_conversation(&label, conversation_id)
+        .map_err(|_| MyError::WindowLayoutWriteFail)?;
+    if let Err(e) = windows::open(&app_handle, &window_layout.get(&label)) {
+        let _ = window_layout.remove(&label);
+        return Err(e);
+    }
+    Ok(label)
+}
+
+/// Records the conversation the calling window shows, to show it again on the next start.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_window_conversation<R: Runtime>(
+    window: tauri::Window<R>,
+    window_layout: State<'_, Arc<WindowLayout>>,
+    conversation_id: Option<&str>,
+) -> Result<(), MyError> {
+    let conversation_id = conversation_id
+        .map(|id| validation::id("conversation_id", id))
+        .transpose()?;
+    window_layout
+        .set_conversation(window.label(), conversation_id)
+        .map_err(|_| MyError::WindowLayoutWriteFail)
+}
+
+/// What the calling window showed when the app was last closed, as `restore_window_state`
+/// announces once its page has loaded.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_window_state<R: Runtime>(
+    window: tauri::Window<R>,
+    window_layout: State<'_, Arc<WindowLayout>>,
+) -> Result<WindowStatePayload, MyError> {
+    Ok(windows::payload(&window_layout.get(window.label())))
+}
+
+/// Replies to every message since the last reply at once, e.g. after sending several
+/// messages with auto-respond off.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn generate_response<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+) -> Result<(), MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    respond(&app_handle, conversation_id, false).await
+}
+
+/// Continues the partial reply `message_id`, left by a generation that broke off, from where
+/// it stopped. The finished reply replaces the partial one.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn continue_generation<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    message_id: &str,
+) -> Result<(), MyError> {
+    let message_id = validation::id("message_id", message_id)?;
+    let conversation_id = app_handle
+        .state::<RwLock<ConversationManager>>()
+        .read()
+        .await
+        .conversations
+        .values()
+        .find(|conv| conv.pending_partial().is_some_and(|(id, _)| id == message_id))
+        .map(|conv| conv.id)
+        .ok_or(MyError::MessageNotFoundFail)?;
+    respond(&app_handle, conversation_id, true).await
+}
+
+/// Generates the reply whose last attempt failed, sending the same messages again.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn retry_failed_generation<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+) -> Result<(), MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    {
+        let mgr = app_handle.state::<RwLock<ConversationManager>>();
+        let mgr = mgr.read().await;
+        let conv = mgr.conversation(&conversation_id)?;
+        if conv.pending_failure().is_none() {
+            return Err(MyError::NoFailedGenerationFail);
+        }
+    }
+    respond(&app_handle, conversation_id, false).await
+}
+
+/// Replies an earlier run was generating when it crashed or was closed, as announced by
+/// `pending_generation_found` once the history has loaded. Oldest first.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_pending_generations(
+    generation_journal: State<'_, Arc<GenerationJournal>>,
+) -> Result<Vec<PendingGenerationPayload>, MyError> {
+    Ok(generation_journal
+        .interrupted()
+        .into_iter()
+        .map(Into::into)
+        .collect())
+}
+
+/// Generates the reply an earlier run was interrupted in, first sending its prompt again if
+/// the history lost it. Nothing is generated when the reply turns out to have been saved.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn resume_pending_generation<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    generation_journal: State<'_, Arc<GenerationJournal>>,
+    conversation_id: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let conv = app_handle
+        .state::<RwLock<ConversationManager>>()
+        .read()
+        .await
+        .conversation(&conversation_id)?;
+    let pending = generation_journal
+        .take_interrupted(conversation_id)
+        .map_err(|_| MyError::GenerationJournalFail)?
+        .ok_or(MyError::PendingGenerationNotFoundFail)?;
+    match generation_journal::resume(&conv, &pending) {
+        Resume::Reply => respond(&app_handle, conversation_id, false).await,
+        Resume::ContinuePartial => respond(&app_handle, conversation_id, true).await,
+        Resume::SendPrompt => {
+            append_user_messages(&app_handle, conversation_id, &[pending.prompt]).await?;
+            respond(&app_handle, conversation_id, false).await
+        }
+        Resume::Answered => Ok(()),
+    }
+}
+
+/// Forgets the reply an earlier run was interrupted in, leaving the conversation as it is.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn discard_pending_generation(
+    generation_journal: State<'_, Arc<GenerationJournal>>,
+    conversation_id: &str,
+) -> Result<(), MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    generation_journal
+        .take_interrupted(conversation_id)
+        .map_err(|_| MyError::GenerationJournalFail)?
+        .ok_or(MyError::PendingGenerationNotFoundFail)?;
+    Ok(())
+}
+
+/// Typing events go to every window, so each can show activity for generations it didn't start.
+/// With `continue_partial`, the conversation's pending partial reply is continued instead of
+/// generating a new one. The generation is journaled while it runs, so a crash leaves it to be
+/// resumed with `resume_pending_generation`.
+async fn respond<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    conversation_id: uuid::Uuid,
+    continue_partial: bool,
+) -> Result<(), MyError> {
+    events::emit_all(
+        app_handle,
+        "assistant_typing_started",
+        AssistantTypingEventPayload { conversation_id },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    let journal = app_handle.state::<Arc<GenerationJournal>>();
+    // Read-only chats aren't saved, so there'd be nothing to resume.
+    let journaled = !app_handle
+        .state::<RwLock<crate::config::Config>>()
+        .read()
+        .await
+        .read_only;
+    if journaled {
+        let pending = app_handle
+            .state::<RwLock<ConversationManager>>()
+            .read()
+            .await
+            .conversation(&conversation_id)
+            .map(|conv| PendingGeneration::new(&conv, continue_partial));
+        // Without the conversation, generation fails straight away anyway.
+        if let Ok(pending) = pending {
+            if let Err(e) = journal.begin(pending) {
+                eprintln!("Failed to journal the generation: {}", e);
+            }
+        }
+    }
+    let result = generate_assistant_message(app_handle, conversation_id, continue_partial).await;
+    if journaled {
+        if let Err(e) = journal.finish(conversation_id) {
+            eprintln!("Failed to journal the end of the generation: {}", e);
+        }
+    }
+    if result.is_ok() {
+        background::schedule_auto_tagging(app_handle.clone(), conversation_id);
+    }
+    // Stop even when generation failed, or indicators would spin forever.
+    events::emit_all(
+        app_handle,
+        "assistant_typing_stopped",
+        AssistantTypingEventPayload { conversation_id },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    result
+}
+
+async fn generate_assistant_message<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    conversation_id: uuid::Uuid,
+    continue_partial: bool,
+) -> Result<(), MyError> {
+    let config = app_handle.state::<RwLock<crate::config::Config>>();
+    let profile_clients = app_handle.state::<RwLock<ProfileClients>>();
+    let conversation_manager = app_handle.state::<RwLock<ConversationManager>>();
+    let usage_log = app_handle.state::<Arc<UsageLog>>();
+    let (active_profile, models, model_pricing, system_prompt, read_only, guardrails) = {
+        let config = config.read().await;
+        (
+            config.active_profile.clone(),
+            config.model_chain(),
+            config.model_pricing.clone(),
+            config.system_prompt.clone(),
+            config.read_only,
+            Guardrails::new(&config.guardrails),
+        )
+    };
+    let catalog_path =
+        crate::config::Config::get_model_catalog_path().map_err(|_| MyError::NoConfigDirFail)?;
+    let catalog = ModelCatalog::open(&catalog_path, &model_pricing);
+    // Searched before the request is built, as it waits for the provider too.
+    let retrieved = match read_only {
+        true => None,
+        false => retrieve_context(app_handle, conversation_id, &active_profile).await,
+    };
+
+    // The history is only held while building the request and adding the reply, not while
+    // waiting on the provider, so other commands and background tasks carry on meanwhile.
+    let (preferred_profile, models, history, reply_start, partial_id, stop, sampling) = {
+        let mgr = conversation_manager.read().await;
+        let conv = mgr.conversation(&conversation_id)?;
+
+        let preferred_profile = conv
+            .get_pinned_profile()
+            .unwrap_or(&active_profile)
+            .to_string();
+        let models = conv.model_chain(models);
+        // Budgeted for the preferred model; a fallback with a smaller window may refuse it.
+        let limit = catalog
+            .info(&models[0], &model_pricing)
+            .context_window
+            .map(|context_window| ContextLimit {
+                model: &models[0],
+                context_window,
+            });
+        if conv.message_count() == 0 {
+            return Err(MyError::ConversationEmptyFail);
+        }
+        // The text the provider continues from: the partial reply, or the assistant prefix.
+        let (mut history, reply_start, partial_id) = if continue_partial {
+            let (partial_id, partial) = conv
+                .pending_partial()
+                .map(|(id, partial)| (id, partial.content.clone()))
+                .ok_or(MyError::MessageNotFoundFail)?;
+            let history = conv
+                .to_continuation_messages(&system_prompt, limit.as_ref())
+                .ok_or(MyError::MessageNotFoundFail)?;
+            (history, partial, Some(partial_id))
+        } else {
+            (
+                conv.to_request_messages(&system_prompt, limit.as_ref()),
+                conv.assistant_prefix(),
+                None,
+            )
+        };
+        if let Some(context) = retrieved {
+            knowledge::add_context(&mut history, context);
+        }
+        // Checked before the request so a locked conversation doesn't cost anything.
+        conv.ensure_unlocked()?;
+        (
+            preferred_profile,
+            models,
+            history,
+            reply_start,
+            partial_id,
+            conv.stop_sequences(),
+            conv.sampling(),
+        )
+    };
+
+    let started = std::time::Instant::now();
+    let result = if read_only {
+        Ok(provider::mock_completion())
+    } else {
+        profile_clients
+            .read()
+            .await
+            .stream_with_failover(&preferred_profile, &models, &history, &stop, sampling)
+            .await
+    };
+    let duration = started.elapsed();
+    if let Ok(ai_response) = &result {
+        if let Some(usage) = UsageRecord::from_response(conversation_id, ai_response) {
+            usage_log.record(usage);
+        }
+    }
+
+    let (message_id, response, filtered, unread_count, model, token_usage) = {
+        let mut mgr = conversation_manager.write().await;
+        let conv = mgr.conversation_mut(&conversation_id)?;
+        // Either may have changed while waiting on the provider.
+        conv.ensure_unlocked()?;
+        if continue_partial && conv.pending_partial().map(|(id, _)| id) != partial_id {
+            return Err(MyError::MessageNotFoundFail);
+        }
+        let ai_response = match result {
+            Ok(response) => response,
+            Err(ProviderError::Interrupted {
+                partial,
+                model,
+                error,
+            }) => {
+                let partial = ConversationMessagePartialEvent {
+                    content: format!("{}{}", reply_start, partial),
+                    model: Some(model).filter(|model| !model.is_empty()),
+                    error: error.to_string(),
+                };
+                let message_id = conv.add_event(partial.clone()).id;
+                drop(mgr);
+                return keep_partial_reply(app_handle, conversation_id, message_id, partial).await;
+            }
+            Err(e) => {
+                let failure = ConversationGenerationFailedEvent {
+                    error: recorder::redact_body(&e.to_string()),
+                };
+                conv.add_event(failure.clone());
+                drop(mgr);
+                return keep_failed_generation(app_handle, conversation_id, failure).await;
+            }
+        };
+        let filtered = guardrails.filter(&format!(
+            "{}{}",
+            reply_start,
+            ai_response
+                .message()
+                .ok_or(MyError::ConversationAIResponseFail)?
+                .content
+        ));
+        let response = filtered.content;
+
+        let message_id = conv
+            .try_add_event(ConversationMessageAddedEvent {
+                author: chatgpt::types::Role::Assistant,
+                content: response.clone(),
+                model: Some(ai_response.model.clone()),
+                language: None,
+                citations: Vec::new(),
+            })?
+            .id;
+        // Recorded beside the message rather than in it, so the hits outlive edits to the reply.
+        let filtered = (!filtered.hits.is_empty()).then_some(ConversationMessageFilteredEvent {
+            message_id,
+            hits: filtered.hits,
+            redacted: filtered.redacted,
+        });
+        if let Some(filtered) = &filtered {
+            conv.add_event(filtered.clone());
+        }
+        (
+            message_id,
+            response,
+            filtered,
+            conv.unread_count(),
+            ai_response.model,
+            ai_response.usage,
+        )
+    };
+
+    save_history(&*conversation_manager.read().await, &config).await?;
+
+    events::emit_all(
+        app_handle,
+        "conversation_message_added",
+        ConversationMessageAddedEventPayload {
+            conversation_id,
+            message_id,
+            author: chatgpt::types::Role::Assistant,
+            content: response,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    if let Some(filtered) = filtered {
+        events::emit_all(
+            app_handle,
+            "message_filtered",
+            MessageFilteredEventPayload {
+                conversation_id,
+                message_id,
+                hits: filtered.hits.iter().map(Into::into).collect(),
+                redacted: filtered.redacted,
+            },
+        )
+        .map_err(|_| MyError::EmitFail)?;
+    }
+    // Windows showing the conversation mark it read again, so only unfocused chats keep a count.
+    events::emit_all(
+        app_handle,
+        "unread_count_changed",
+        UnreadCountChangedEventPayload {
+            conversation_id,
+            unread_count,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    events::emit_all(
+        app_handle,
+        "generation_usage",
+        GenerationUsageEventPayload {
+            conversation_id,
+            message_id,
+            prompt_tokens: token_usage.map(|usage| usage.prompt_tokens),
+            completion_tokens: token_usage.map(|usage| usage.completion_tokens),
+            cost_usd: token_usage.and_then(|usage| {
+                pricing::estimate_cost(
+                    &model_pricing,
+                    &model,
+                    usage.prompt_tokens,
+                    usage.completion_tokens,
+                )
+            }),
+            model,
+            duration_ms: duration.as_millis() as u64,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+
+    Ok(())
+}
+
+/// Code of the conversation's linked repository relevant to its latest question, for the
+/// system prompt. When it can't be searched the reply goes without it.
+async fn retrieve_context<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    conversation_id: uuid::Uuid,
+    active_profile: &str,
+) -> Option<String> {
+    let (repository_id, query, profile) = {
+        let mgr = app_handle.state::<RwLock<ConversationManager>>();
+        let mgr = mgr.read().await;
+        let conv = mgr.conversation(&conversation_id).ok()?;
+        (
+            conv.repository()?,
+            knowledge::retrieval_query(&conv)?,
+            conv.get_pinned_profile()
+                .unwrap_or(active_profile)
+                .to_string(),
+        )
+    };
+    let dir = crate::config::Config::get_knowledge_dir().ok()?;
+    let profile_clients = app_handle.state::<RwLock<ProfileClients>>();
+    let profile_clients = profile_clients.read().await;
+    let context =
+        knowledge::retrieve_context(&dir, repository_id, &query, &profile_clients, &profile).await;
+    match context {
+        Ok(context) => context,
+        Err(e) => {
+            eprintln!("Failed to search the linked repository: {}", e);
+            None
+        }
+    }
+}
+
+/// Saves the failed attempt so it shows up in the usage report and can be retried with
+/// `retry_failed_generation`. Fails with the generation error once the attempt is kept.
+async fn keep_failed_generation<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    conversation_id: uuid::Uuid,
+    failure: ConversationGenerationFailedEvent,
+) -> Result<(), MyError> {
+    let config = app_handle.state::<RwLock<crate::config::Config>>();
+    let conversation_manager = app_handle.state::<RwLock<ConversationManager>>();
+    save_history(&*conversation_manager.read().await, &config).await?;
+    events::emit_all(
+        app_handle,
+        "generation_failed",
+        GenerationFailedEventPayload {
+            conversation_id,
+            error: failure.error,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Err(MyError::ConversationAIResponseFail)
+}
+
+/// Saves the partial reply of an interrupted generation and shows it, so it can be continued
+/// with `continue_generation`. Fails with the interruption once the partial reply is kept.
+async fn keep_partial_reply<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    conversation_id: uuid::Uuid,
+    message_id: uuid::Uuid,
+    partial: ConversationMessagePartialEvent,
+) -> Result<(), MyError> {
+    let config = app_handle.state::<RwLock<crate::config::Config>>();
+    let conversation_manager = app_handle.state::<RwLock<ConversationManager>>();
+    save_history(&*conversation_manager.read().await, &config).await?;
+    events::emit_all(
+        app_handle,
+        "conversation_message_partial",
+        ConversationMessagePartialEventPayload {
+            conversation_id,
+            message_id,
+            content: partial.content,
+            error: partial.error,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Err(MyError::GenerationInterruptedFail)
+}
+
+
+fn workspace_error(e: WorkspaceError) -> MyError {
+    match e {
+        WorkspaceError::InvalidName => MyError::WorkspaceNameInvalidFail,
+        WorkspaceError::Exists => MyError::WorkspaceExistsFail,
+        WorkspaceError::NotFound => MyError::WorkspaceNotFoundFail,
+        WorkspaceError::Io(_) => MyError::WorkspaceSwitchFail,
+    }
+}
+
+/// Workspace profiles, each with its own config, API keys and history.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_profiles() -> Result<Vec<WorkspaceProfilePayload>, MyError> {
+    let base = workspaces::base_dir().ok_or(MyError::NoConfigDirFail)?;
+    let active = workspaces::active();
+    Ok(workspaces::list(&base)
+        .into_iter()
+        .map(|name| WorkspaceProfilePayload {
+            active: name == active,
+            name,
+        })
+        .collect())
+}
+
+/// Creates an empty workspace profile; an empty `openai_api_key` leaves it without API keys.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn create_profile(
+    config: State<'_, RwLock<crate::config::Config>>,
+    name: &str,
+    openai_api_key: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let base = workspaces::base_dir().ok_or(MyError::NoConfigDirFail)?;
+    let dir = workspaces::create(&base, name).map_err(workspace_error)?;
+    crate::config::Config::for_workspace(&dir, openai_api_key)
+        .write_to(&dir.join("config.json"))
+        .map_err(|_| MyError::ConfigWriteToDiskFail)
+}
+
+/// Switches to another workspace profile, reloading the config, history and logs in place.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command(rename_all = "snake_case")]
+pub async fn switch_profile<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    profile_clients: State<'_, RwLock<ProfileClients>>,
+    recorder: State<'_, Arc<RequestRecorder>>,
+    usage_log: State<'_, Arc<UsageLog>>,
+    draft_store: State<'_, Arc<DraftStore>>,
+    sync_conflicts: State<'_, Arc<SyncConflicts>>,
+    window_layout: State<'_, Arc<WindowLayout>>,
+    generation_journal: State<'_, Arc<GenerationJournal>>,
+    app_handle: tauri::AppHandle<R>,
+    name: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let base = workspaces::base_dir().ok_or(MyError::NoConfigDirFail)?;
+    let previous = workspaces::active();
+    if previous == name {
+        return Ok(());
+    }
+    {
+        let mut mgr = conversation_manager.write().await;
+        // The loader would go on adding this profile's conversations to the next one's.
+        if mgr.loading {
+            return Err(MyError::HistoryLoadingFail);
+        }
+        let mut config = config.write().await;
+        let mut profile_clients = profile_clients.write().await;
+        workspaces::switch(&base, name).map_err(workspace_error)?;
+        let loaded = crate::config::Config::from_disk()
+            .and_then(|loaded| loaded.with_overrides(config.overrides.clone()))
+            .and_then(|loaded| {
+                let clients = ProfileClients::from_config(&loaded, (*recorder).clone())?;
+                Ok((loaded, clients))
+            });
+        let (mut new_config, new_clients) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                eprintln!("Failed to load profile {}: {}", name, e);
+                if let Err(e) = workspaces::switch(&base, &previous) {
+                    eprintln!("Failed to return to profile {}: {}", previous, e);
+                }
+                return Err(MyError::WorkspaceSwitchFail);
+            }
+        };
+
+        // The profile's directory was just read from, so these can't fail.
+        let path = |path: Result<std::path::PathBuf, Box<dyn std::error::Error>>| {
+            path.map_err(|_| MyError::NoConfigDirFail)
+        };
+        recorder.reopen(
+            path(crate::config::Config::get_request_log_path())?,
+            new_config.record_requests,
+        );
+        usage_log.reopen(path(crate::config::Config::get_usage_log_path())?);
+        draft_store.reopen(path(crate::config::Config::get_drafts_path())?);
+        sync_conflicts.reopen(path(crate::config::Config::get_sync_conflicts_path())?);
+        window_layout.reopen(path(crate::config::Config::get_window_layout_path())?);
+        generation_journal.reopen(path(crate::config::Config::get_pending_generations_path())?);
+        *profile_clients = new_clients;
+
+        // Only a missing history starts the profile empty; a damaged one is recovered as on
+        // startup, since the next save would otherwise write over it.
+        let attachments_dir = path(crate::config::Config::get_attachments_dir())?;
+        let (recovered, recovery) = integrity::load_or_recover(&mut new_config, &attachments_dir);
+        *mgr = recovered;
+        *app_handle.state::<LastHistoryRecovery>().0.lock().unwrap() = recovery;
+        mgr.compressed = new_config.compress_history;
+        if let Some(watcher) = app_handle.try_state::<HistoryWatcher>() {
+            if let Err(e) = watcher.set_path(new_config.conversation_history_save_path.clone().into())
+            {
+                eprintln!("Failed to watch profile history: {}", e);
+            }
+        }
+        new_config.apply_locale();
+        *config = new_config;
+    }
+
+    events::emit_all(&app_handle, "profile_switched", name.to_string())
+        .map_err(|_| MyError::EmitFail)?;
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_api_key_profiles(
+    profile_clients: State<'_, RwLock<ProfileClients>>,
+) -> Result<Vec<String>, MyError> {
+    Ok(profile_clients.read().await.names())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_active_profile(
+    config: State<'_, RwLock<crate::config::Config>>,
+    profile_clients: State<'_, RwLock<ProfileClients>>,
+    profile_name: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    if !profile_clients.read().await.contains(profile_name) {
+        return Err(MyError::ProfileNotFoundFail);
+    }
+    let mut config = config.write().await;
+    config.active_profile = profile_name.to_string();
+    config
+        .write_to_disk()
+        .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn pin_conversation_profile(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    profile_clients: State<'_, RwLock<ProfileClients>>,
+    conversation_id: &str,
+    profile_name: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    if !profile_clients.read().await.contains(profile_name) {
+        return Err(MyError::ProfileNotFoundFail);
+    }
+    set_pinned_profile(
+        &conversation_manager,
+        &config,
+        conversation_id,
+        Some(profile_name.to_string()),
+    )
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn unpin_conversation_profile(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    conversation_id: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    set_pinned_profile(&conversation_manager, &config, conversation_id, None).await
+}
+
+async fn set_pinned_profile(
+    conversation_manager: &RwLock<ConversationManager>,
+    config: &RwLock<crate::config::Config>,
+    conversation_id: &str,
+    profile: Option<String>,
+) -> Result<(), MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let mut mgr = conversation_manager.write().await;
+    let conv = mgr.conversation_mut(&conversation_id)?;
+    if conv.get_pinned_profile() == profile.as_deref() {
+        return Ok(());
+    }
+    conv.add_event(ConversationProfilePinnedEvent { profile });
+    mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+        .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn test_proxy(
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<ProxyTestResultPayload, MyError> {
+    let proxy = config.read().await.proxy.clone();
+    provider::build_http_client(&proxy).map_err(|_| MyError::ProxyConfigFail)?;
+    let started = std::time::Instant::now();
+    let result = provider::test_connection(&proxy).await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    Ok(match result {
+        Ok(status) => ProxyTestResultPayload {
+            reachable: true,
+            status: Some(status),
+            elapsed_ms,
+            error: None,
+        },
+        Err(e) => ProxyTestResultPayload {
+            reachable: false,
+            status: None,
+            elapsed_ms,
+            error: Some(e.to_string()),
+        },
+    })
+}
+
+/// Compares the running version with the latest GitHub release.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn check_for_updates<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<UpdateInfoPayload, MyError> {
+    let proxy = config.read().await.proxy.clone();
+    let current_version = app_handle.package_info().version.to_string();
+    updates::check(&proxy, &current_version)
+        .await
+        .map(UpdateInfoPayload::from)
+        .map_err(|e| {
+            eprintln!("Failed to check for updates: {}", e);
+            MyError::UpdateCheckFail
+        })
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn enable_request_recording(
+    config: State<'_, RwLock<crate::config::Config>>,
+    recorder: State<'_, Arc<RequestRecorder>>,
+    enabled: bool,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    recorder.set_enabled(enabled);
+    let mut config = config.write().await;
+    config.record_requests = enabled;
+    config
+        .write_to_disk()
+        .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+    Ok(())
+}
+
+/// Opts in to (or out of) the model tagging conversations by topic.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_auto_tagging(
+    config: State<'_, RwLock<crate::config::Config>>,
+    enabled: bool,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let mut config = config.write().await;
+    config.auto_tag = enabled;
+    config
+        .write_to_disk()
+        .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+    Ok(())
+}
+
+/// Sets the model replies come from and the models to fall back on, in order, when it's
+/// unavailable or a conversation outgrows its context. Returns the models as they'll be tried.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_models(
+    config: State<'_, RwLock<crate::config::Config>>,
+    model: &str,
+    fallback_models: Vec<String>,
+) -> Result<Vec<String>, MyError> {
+    ensure_writable(&config).await?;
+    let mut config = config.write().await;
+    config.model = model.trim().to_string();
+    config.fallback_models = fallback_models;
+    config
+        .write_to_disk()
+        .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+    Ok(config.model_chain())
+}
+
+/// The prices cost estimates and usage reports are computed with, in USD per million tokens
+/// by model name prefix, marking the ones the config overrides.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_model_pricing(
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<Vec<ModelPricingPayload>, MyError> {
+    let config = config.read().await;
+    Ok(pricing::table(&config.model_pricing)
+        .into_iter()
+        .map(ModelPricingPayload::from)
+        .collect())
+}
+
+/// Overrides the price of the models starting with `model`, for when the provider's prices
+/// changed or it isn't OpenAI; without a price it goes back to the built-in one. Returns the
+/// new table.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_model_pricing(
+    config: State<'_, RwLock<crate::config::Config>>,
+    model: &str,
+    price: Option<ModelPricePayload>,
+) -> Result<Vec<ModelPricingPayload>, MyError> {
+    ensure_writable(&config).await?;
+    let model = validation::not_empty("model", model)?;
+    let price = price
+        .map(|price| {
+            Ok::<_, MyError>(pricing::ModelPrice {
+                prompt: validation::non_negative("prompt", price.prompt)?,
+                completion: validation::non_negative("completion", price.completion)?,
+            })
+        })
+        .transpose()?;
+    let mut config = config.write().await;
+    match price {
+        Some(price) => config.model_pricing.insert(model, price),
+        None => config.model_pricing.remove(&model),
+    };
+    config
+        .write_to_disk()
+        .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+    Ok(pricing::table(&config.model_pricing)
+        .into_iter()
+        .map(ModelPricingPayload::from)
+        .collect())
+}
+
+/// Turns the startup and daily update checks on or off; see `check_for_updates`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_auto_update_check(
+    config: State<'_, RwLock<crate::config::Config>>,
+    enabled: bool,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let mut config = config.write().await;
+    config.auto_update_check = enabled;
+    config
+        .write_to_disk()
+        .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_guardrails(
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<GuardrailsPayload, MyError> {
+    Ok(config.read().await.guardrails.clone().into())
+}
+
+/// Sets the phrases and patterns replies are checked for once generated, and whether they're
+/// redacted or only flagged. Blank entries are dropped; a pattern that isn't a valid regular
+/// expression is refused.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_guardrails(
+    config: State<'_, RwLock<crate::config::Config>>,
+    guardrails: GuardrailsPayload,
+) -> Result<GuardrailsPayload, MyError> {
+    ensure_writable(&config).await?;
+    let keep = |entries: Vec<String>| -> Vec<String> {
+        entries
+            .into_iter()
+            .map(|entry| entry.trim().to_string())
+            .filter(|entry| !entry.is_empty())
+            .collect()
+    };
+    let guardrails = GuardrailConfig {
+        banned_phrases: keep(guardrails.banned_phrases),
+        banned_patterns: keep(guardrails.banned_patterns),
+        action: guardrails.action,
+    };
+    if let Some(e) = guardrails
+        .banned_patterns
+        .iter()
+        .find_map(|pattern| guardrails::check_pattern(pattern).err())
+    {
+        eprintln!("Refusing banned pattern: {}", e);
+        return Err(MyError::GuardrailPatternInvalidFail);
+    }
+    let mut config = config.write().await;
+    config.guardrails = guardrails.clone();
+    config
+        .write_to_disk()
+        .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+    Ok(guardrails.into())
+}
+
+/// Stores the history and attachments zstd-compressed, or plainly again, rewriting what's
+/// already stored so the whole profile uses one format.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_history_compression<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    enabled: bool,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let attachments_dir =
+        crate::config::Config::get_attachments_dir().map_err(|_| MyError::NoConfigDirFail)?;
+    let mut mgr = conversation_manager.write().await;
+    let mut config = config.write().await;
+    let progress = Progress::start(&app_handle, ProgressKind::Migration);
+    let previous = std::mem::replace(&mut mgr.compressed, enabled);
+    if mgr
+        .write_to_disk(&config.conversation_history_save_path)
+        .is_err()
+    {
+        mgr.compressed = previous;
+        return Err(MyError::HistoryCompressionFail);
+    }
+    compression::migrate_attachments(&attachments_dir, enabled)
+        .map_err(|_| MyError::HistoryCompressionFail)?;
+    progress.finish();
+
+    config.compress_history = enabled;
+    config
+        .write_to_disk()
+        .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+    Ok(())
+}
+
+/// Turns read-only (guest/demo) mode on or off, announced as `read_only_changed`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_read_only<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    enabled: bool,
+) -> Result<(), MyError> {
+    {
+        let mut config = config.write().await;
+        // The file value would be written back, so the change wouldn't survive a restart.
+        if config.overrides.source_of("read_only").is_some() {
+            return Err(MyError::ReadOnlyModeFail);
+        }
+        config.read_only = enabled;
+        config
+            .write_to_disk()
+            .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+    }
+
+    events::emit_all(&app_handle, "read_only_changed", enabled).map_err(|_| MyError::EmitFail)?;
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_request_log(
+    recorder: State<'_, Arc<RequestRecorder>>,
+    target_path: &str,
+) -> Result<usize, MyError> {
+    validation::path("target_path", target_path, &["json", "jsonl"])?;
+    recorder
+        .export(target_path)
+        .map_err(|_| MyError::RequestLogExportFail)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn bookmark_message<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    message_id: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    set_message_bookmarked(
+        &conversation_manager,
+        &config,
+        &app_handle,
+        conversation_id,
+        message_id,
+        true,
+    )
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn unbookmark_message<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    message_id: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    set_message_bookmarked(
+        &conversation_manager,
+        &config,
+        &app_handle,
+        conversation_id,
+        message_id,
+        false,
+    )
+    .await
+}
+
+async fn set_message_bookmarked<R: Runtime>(
+    conversation_manager: &RwLock<ConversationManager>,
+    config: &RwLock<crate::config::Config>,
+    app_handle: &tauri::AppHandle<R>,
+    conversation_id: &str,
+    message_id: &str,
+    bookmarked: bool,
+) -> Result<(), MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let message_id = validation::id("message_id", message_id)?;
+    {
+        let mut mgr = conversation_manager.write().await;
+        let conv = mgr.conversation_mut(&conversation_id)?;
+        if conv.get_message(message_id).is_none() {
+            return Err(MyError::MessageNotFoundFail);
+        }
+        if conv.message_bookmarked_at(message_id).is_some() == bookmarked {
+            return Ok(());
+        }
+        conv.add_event(ConversationMessageBookmarkedEvent {
+            message_id,
+            bookmarked,
+        });
+        mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+            .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    }
+
+    events::emit_all(
+        app_handle,
+        "message_bookmark_changed",
+        MessageBookmarkChangedEventPayload {
+            conversation_id,
+            message_id,
+            bookmarked,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_bookmarks(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+) -> Result<Vec<BookmarkPayload>, MyError> {
+    let mgr = conversation_manager.read().await;
+    Ok(bookmarks::list_bookmarks(&mgr)
+        .into_iter()
+        .map(BookmarkPayload::from)
+        .collect())
+}
+
+/// Pinned context is sent with every request instead of being left out with the oldest
+/// messages once the conversation outgrows the model's context window.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_message_pinned_context<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    message_id: &str,
+    pinned_context: bool,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let message_id = validation::id("message_id", message_id)?;
+    let conversation_id = {
+        let mut mgr = conversation_manager.write().await;
+        let conv = mgr
+            .find_message_conversation_mut(message_id)
+            .ok_or(MyError::MessageNotFoundFail)?;
+        if conv.is_message_pinned(message_id) == pinned_context {
+            return Ok(());
+        }
+        conv.add_event(ConversationMessagePinnedEvent {
+            message_id,
+            pinned: pinned_context,
+        });
+        let conversation_id = conv.id;
+        mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+            .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+        conversation_id
+    };
+
+    events::emit_all(
+        &app_handle,
+        "message_pinned_context_changed",
+        MessagePinnedContextChangedEventPayload {
+            conversation_id,
+            message_id,
+            pinned_context,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)
+}
+
+/// Attaches a private note to a message; an empty note removes it.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn annotate_message<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    message_id: &str,
+    note: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let message_id = validation::id("message_id", message_id)?;
+    let note = Some(note.trim().to_string()).filter(|note| !note.is_empty());
+    let conversation_id = {
+        let mut mgr = conversation_manager.write().await;
+        let conv = mgr
+            .find_message_conversation_mut(message_id)
+            .ok_or(MyError::MessageNotFoundFail)?;
+        conv.add_event(ConversationMessageAnnotatedEvent {
+            message_id,
+            note: note.clone(),
+        });
+        let conversation_id = conv.id;
+        mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+            .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+        conversation_id
+    };
+
+    events::emit_all(
+        &app_handle,
+        "message_annotated",
+        MessageAnnotatedEventPayload {
+            conversation_id,
+            message_id,
+            note,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)
+}
+
+/// The sources a message cites. With `fetch_titles`, each page's title is looked up as well,
+/// which takes a request per source; read-only mode skips it.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_message_citations(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    message_id: &str,
+    fetch_titles: bool,
+) -> Result<Vec<CitationPayload>, MyError> {
+    let message_id = validation::id("message_id", message_id)?;
+    let citations = {
+        let mgr = conversation_manager.read().await;
+        mgr.conversations
+            .values()
+            .find_map(|conv| conv.get_message(message_id).map(|msg| msg.detected_citations()))
+            .ok_or(MyError::MessageNotFoundFail)?
+    };
+    let previews = match fetch_titles {
+        true => {
+            let urls: Vec<&str> = citations.iter().map(|citation| citation.url.as_str()).collect();
+            link_previews(&config, &urls).await?
+        }
+        false => vec![None; citations.len()],
+    };
+    Ok(citations
+        .into_iter()
+        .zip(previews)
+        .map(|(citation, preview)| CitationPayload {
+            url: citation.url,
+            label: citation.label,
+            title: preview.and_then(|preview| preview.title),
+        })
+        .collect())
+}
+
+/// Previews of `urls`, from the cache when it has a fresh one and otherwise fetched, except
+/// in read-only mode. None for the pages that couldn't be fetched.
+async fn link_previews(
+    config: &RwLock<crate::config::Config>,
+    urls: &[&str],
+) -> Result<Vec<Option<LinkPreview>>, MyError> {
+    let (proxy, read_only) = {
+        let config = config.read().await;
+        (config.proxy.clone(), config.read_only)
+    };
+    let cache_path =
+        crate::config::Config::get_link_previews_path().map_err(|_| MyError::NoConfigDirFail)?;
+    let mut cache = PreviewCache::open(&cache_path);
+    let now = background::unix_now();
+    let mut http = None;
+    let mut fetched = false;
+    let mut previews = Vec::with_capacity(urls.len());
+    for url in urls {
+        if let Some(preview) = cache.get(url, now) {
+            previews.push(Some(preview.clone()));
+            continue;
+        }
+        if read_only {
+            previews.push(None);
+            continue;
+        }
+        if http.is_none() {
+            http = Some(provider::build_http_client(&proxy).map_err(|_| MyError::ProxyConfigFail)?);
+        }
+        match link_preview::fetch(http.as_ref().unwrap(), url, now).await {
+            Ok(preview) => {
+                cache.insert(preview.clone());
+                fetched = true;
+                previews.push(Some(preview));
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch {} for its preview: {}", url, e);
+                previews.push(None);
+            }
+        }
+    }
+    // Only a cache, so failing to save it fails nothing.
+    if fetched {
+        if let Err(e) = cache.save(&cache_path) {
+            eprintln!("Failed to save the link previews: {}", e);
+        }
+    }
+    Ok(previews)
+}
+
+/// The OpenGraph or Twitter card preview of the page at `url`, for rendering a link card.
+/// Previews are cached for a week; read-only mode only has the cached ones.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_link_preview(
+    config: State<'_, RwLock<crate::config::Config>>,
+    url: &str,
+) -> Result<LinkPreviewPayload, MyError> {
+    let url = validation::url("url", url)?;
+    match link_previews(&config, &[&url]).await?.remove(0) {
+        Some(preview) => Ok(LinkPreviewPayload::from(preview)),
+        None if config.read().await.read_only => Err(MyError::ReadOnlyModeFail),
+        None => Err(MyError::LinkPreviewFail),
+    }
+}
+
+/// Translates a message into `target_lang`, e.g. "German", storing the translation next to
+/// the original. Announced as `message_translated`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn translate_message<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    profile_clients: State<'_, RwLock<ProfileClients>>,
+    usage_log: State<'_, Arc<UsageLog>>,
+    app_handle: tauri::AppHandle<R>,
+    message_id: &str,
+    target_lang: &str,
+) -> Result<String, MyError> {
+    ensure_writable(&config).await?;
+    let message_id = validation::id("message_id", message_id)?;
+    let language = target_lang.trim();
+    if language.is_empty() {
+        return Err(MyError::LanguageInvalidFail);
+    }
+    let (conversation_id, preferred_profile, request) = {
+        let mgr = conversation_manager.read().await;
+        let conv = mgr
+            .conversations
+            .values()
+            .find(|conv| conv.get_message(message_id).is_some())
+            .ok_or(MyError::MessageNotFoundFail)?;
+        let content = &conv
+            .get_message(message_id)
+            .ok_or(MyError::MessageNotFoundFail)?
+            .content;
+        let preferred_profile = match conv.get_pinned_profile() {
+            Some(profile) => profile.to_string(),
+            None => config.read().await.active_profile.clone(),
+        };
+        (
+            conv.id,
+            preferred_profile,
+            translation::translation_messages(content, language),
+        )
+    };
+
+    // The conversation isn't held while waiting for the provider.
+    let models = config.read().await.model_chain();
+    let ai_response = profile_clients
+        .read()
+        .await
+        .send_with_failover(&preferred_profile, &models, &request, &[], Sampling::default())
+        .await
+        .map_err(|_| MyError::ConversationAIResponseFail)?;
+    if let Some(usage) = UsageRecord::from_response(conversation_id, &ai_response) {
+        usage_log.record(usage);
+    }
+    let translated = ai_response
+        .message()
+        .ok_or(MyError::ConversationAIResponseFail)?
+        .content
+        .trim()
+        .to_string();
+
+    {
+        let mut mgr = conversation_manager.write().await;
+        let conv = mgr.conversation_mut(&conversation_id)?;
+        conv.add_event(ConversationMessageTranslatedEvent {
+            message_id,
+            language: language.to_string(),
+            content: translated.clone(),
+        });
+        mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+            .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    }
+
+    events::emit_all(
+        &app_handle,
+        "message_translated",
+        MessageTranslatedEventPayload {
+            conversation_id,
+            message_id,
+            language: language.to_string(),
+            content: translated.clone(),
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Ok(translated)
+}
+
+fn open_action_items() -> Result<(std::path::PathBuf, ActionItems), MyError> {
+    let path =
+        crate::config::Config::get_action_items_path().map_err(|_| MyError::NoConfigDirFail)?;
+    let items = ActionItems::open(&path).map_err(|_| MyError::ActionItemsFail)?;
+    Ok((path, items))
+}
+
+/// Asks the model for the conversation's action items and adds the ones it doesn't have yet
+/// to the task list, returning those.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn extract_action_items(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    profile_clients: State<'_, RwLock<ProfileClients>>,
+    usage_log: State<'_, Arc<UsageLog>>,
+    conversation_id: &str,
+) -> Result<Vec<ActionItemPayload>, MyError> {
+    ensure_writable(&config).await?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let (preferred_profile, models, request) = {
+        let mgr = conversation_manager.read().await;
+        let conv = mgr.conversation(&conversation_id)?;
+        let config = config.read().await;
+        (
+            conv.get_pinned_profile()
+                .unwrap_or(&config.active_profile)
+                .to_string(),
+            conv.model_chain(config.model_chain()),
+            action_items::extraction_messages(&conv),
+        )
+    };
+
+    // The conversation isn't held while waiting for the provider.
+    let ai_response = profile_clients
+        .read()
+        .await
+        .send_with_failover(&preferred_profile, &models, &request, &[], Sampling::default())
+        .await
+        .map_err(|_| MyError::ConversationAIResponseFail)?;
+    if let Some(usage) = UsageRecord::from_response(conversation_id, &ai_response) {
+        usage_log.record(usage);
+    }
+    let texts = action_items::parse_action_items(
+        &ai_response
+            .message()
+            .ok_or(MyError::ConversationAIResponseFail)?
+            .content,
+    );
+
+    let (path, mut items) = open_action_items()?;
+    let added = items.add(conversation_id, texts, background::unix_now());
+    items
+        .save(&path, false)
+        .map_err(|_| MyError::ActionItemsFail)?;
+    Ok(added.into_iter().map(ActionItemPayload::from).collect())
+}
+
+/// Action items of every conversation in the order they were extracted, those done only
+/// with `include_done`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_tasks(include_done: bool) -> Result<Vec<ActionItemPayload>, MyError> {
+    let (_, items) = open_action_items()?;
+    Ok(items
+        .items
+        .into_iter()
+        .filter(|item| include_done || item.done_at.is_none())
+        .map(ActionItemPayload::from)
+        .collect())
+}
+
+/// Marks an action item done, or with `done` false, to do again.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn complete_task(
+    config: State<'_, RwLock<crate::config::Config>>,
+    task_id: &str,
+    done: bool,
+) -> Result<ActionItemPayload, MyError> {
+    ensure_writable(&config).await?;
+    let task_id = validation::id("task_id", task_id)?;
+    let (path, mut items) = open_action_items()?;
+    let item = items
+        .complete(&task_id, done, background::unix_now())
+        .ok_or(MyError::ActionItemNotFoundFail)?
+        .clone();
+    items
+        .save(&path, false)
+        .map_err(|_| MyError::ActionItemsFail)?;
+    Ok(item.into())
+}
+
+async fn change_task_reminder(
+    config: &RwLock<crate::config::Config>,
+    task_id: &str,
+    remind_at: Option<i64>,
+) -> Result<ActionItemPayload, MyError> {
+    ensure_writable(config).await?;
+    let task_id = validation::id("task_id", task_id)?;
+    let (path, mut items) = open_action_items()?;
+    let item = items
+        .set_reminder(&task_id, remind_at)
+        .ok_or(MyError::ActionItemNotFoundFail)?
+        .clone();
+    items
+        .save(&path, false)
+        .map_err(|_| MyError::ActionItemsFail)?;
+    Ok(item.into())
+}
+
+/// Reminds the user of an action item at `remind_at`, in Unix seconds, announced as
+/// `task_reminder_due` for the frontend to show as a system notification. Replaces any
+/// earlier reminder of the item.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_task_reminder(
+    config: State<'_, RwLock<crate::config::Config>>,
+    task_id: &str,
+    remind_at: i64,
+) -> Result<ActionItemPayload, MyError> {
+    change_task_reminder(&config, task_id, Some(remind_at)).await
+}
+
+/// Reminds the user of an action item again in `minutes`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn snooze_task(
+    config: State<'_, RwLock<crate::config::Config>>,
+    task_id: &str,
+    minutes: u32,
+) -> Result<ActionItemPayload, MyError> {
+    let remind_at = background::unix_now() + i64::from(minutes) * 60;
+    change_task_reminder(&config, task_id, Some(remind_at)).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cancel_task_reminder(
+    config: State<'_, RwLock<crate::config::Config>>,
+    task_id: &str,
+) -> Result<ActionItemPayload, MyError> {
+    change_task_reminder(&config, task_id, None).await
+}
+
+/// `rating` is "up", "down", or "none" to withdraw it; an empty comment is dropped.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn rate_message<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    message_id: &str,
+    rating: &str,
+    comment: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let message_id = validation::id("message_id", message_id)?;
+    let rating = match rating {
+        "up" => Some(MessageRating::Up),
+        "down" => Some(MessageRating::Down),
+        "none" => None,
+        _ => return Err(MyError::MessageRatingFail),
+    };
+    let comment = Some(comment.trim().to_string()).filter(|comment| !comment.is_empty());
+    let conversation_id = {
+        let mut mgr = conversation_manager.write().await;
+        let conv = mgr
+            .find_message_conversation_mut(message_id)
+            .ok_or(MyError::MessageNotFoundFail)?;
+        conv.add_event(ConversationMessageRatedEvent {
+            message_id,
+            rating,
+            comment: comment.clone(),
+        });
+        let conversation_id = conv.id;
+        mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+            .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+        conversation_id
+    };
+
+    events::emit_all(
+        &app_handle,
+        "message_rated",
+        MessageRatedEventPayload {
+            conversation_id,
+            message_id,
+            rating,
+            comment,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_feedback_report(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+) -> Result<Vec<ModelFeedbackPayload>, MyError> {
+    let mgr = conversation_manager.read().await;
+    Ok(feedback::feedback_report(&mgr)
+        .into_iter()
+        .map(ModelFeedbackPayload::from)
+        .collect())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn search_messages(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    query: &str,
+    language: &str,
+) -> Result<Vec<SearchHitPayload>, MyError> {
+    let mgr = conversation_manager.read().await;
+    Ok(search::search_messages(&mgr, query, language)
+        .into_iter()
+        .map(SearchHitPayload::from)
+        .collect())
+}
+
+/// Quick-switcher results: conversations whose title or tags fuzzily match `query`, best first.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn fuzzy_find_conversations(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<FuzzyConversationMatchPayload>, MyError> {
+    let mgr = conversation_manager.read().await;
+    Ok(fuzzy::find_conversations(&mgr, query, limit)
+        .into_iter()
+        .map(FuzzyConversationMatchPayload::from)
+        .collect())
+}
+
+/// Find-in-page for one conversation: the messages containing `query`, in order, with the byte
+/// offsets of each match.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn search_in_conversation(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    conversation_id: &str,
+    query: &str,
+    regex: bool,
+) -> Result<Vec<MessageMatchesPayload>, MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let mgr = conversation_manager.read().await;
+    let conv = mgr.conversation(&conversation_id)?;
+    Ok(search::search_in_conversation(&conv, query, regex)
+        .map_err(|_| MyError::SearchPatternInvalidFail)?
+        .into_iter()
+        .map(MessageMatchesPayload::from)
+        .collect())
+}
+
+/// The search overlay's results for `query`: conversation titles, message text, bookmarks,
+/// templates and commands, ranked together best first.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn quick_search(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    query: &str,
+) -> Result<Vec<QuickSearchResultPayload>, MyError> {
+    let (_, templates) = open_templates()?;
+    let actions = actions::app_actions(&keybindings::effective(&config.read().await.keybindings));
+    let commands: Vec<(&str, &str)> = actions
+        .iter()
+        .map(|action| (action.name.as_str(), action.description.as_str()))
+        .collect();
+    let mgr = conversation_manager.read().await;
+    Ok(
+        quick_search::quick_search(&mgr, &templates.templates, &commands, query)
+            .into_iter()
+            .map(QuickSearchResultPayload::from)
+            .collect(),
+    )
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_conversation_archived(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    conversation_id: &str,
+    archived: bool,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let mut mgr = conversation_manager.write().await;
+    let conv = mgr.conversation_mut(&conversation_id)?;
+    if conv.is_archived() != archived {
+        conv.add_event(ConversationArchivedEvent { archived });
+        mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+            .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    }
+    Ok(())
+}
+
+/// The conversations a bulk command acts on, each once. Every id is checked before any
+/// conversation is changed, so one bad id leaves them all as they were.
+fn bulk_conversation_ids(
+    mgr: &ConversationManager,
+    conversation_ids: &[String],
+) -> Result<Vec<uuid::Uuid>, MyError> {
+    let mut ids = Vec::new();
+    for id in conversation_ids {
+        let id = validation::id("conversation_ids", id)?;
+        mgr.conversation(&id)?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    Ok(ids)
+}
+
+/// Moves the conversations to the trash and deletes their drafts, all or none, returning how
+/// many were deleted. With `trash_days` at 0 they're deleted for good right away. Refused
+/// when any of them is locked.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn bulk_delete<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    draft_store: State<'_, Arc<DraftStore>>,
+    tasks: State<'_, TaskSupervisor>,
+    conversation_ids: Vec<String>,
+) -> Result<usize, MyError> {
+    ensure_writable(&config).await?;
+    let conversation_ids = {
+        let mut mgr = conversation_manager.write().await;
+        let ids = bulk_conversation_ids(&mgr, &conversation_ids)?;
+        for id in &ids {
+            mgr.conversation(id)?.ensure_unlocked()?;
+        }
+        let config = config.read().await;
+        let removed = ids
+            .iter()
+            .map(|id| mgr.conversation(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut trashed = None;
+        if config.retention.trash_days > 0 {
+            // Saved to the trash before they leave the history, so a failure loses nothing.
+            let trash_path =
+                crate::config::Config::get_trash_path().map_err(|_| MyError::NoConfigDirFail)?;
+            let mut trash = Trash::open(&trash_path).map_err(|_| MyError::TrashFail)?;
+            let now = background::unix_now();
+            for conv in &removed {
+                trash.add((**conv).clone(), now);
+            }
+            trash
+                .save(&trash_path, config.compress_history)
+                .map_err(|_| MyError::TrashFail)?;
+            trashed = Some((trash_path, trash));
+        }
+        for id in &ids {
+            mgr.conversations.remove(id);
+        }
+        if mgr
+            .write_to_disk(&config.conversation_history_save_path)
+            .is_err()
+        {
+            // Put back as they were, so a delete that can't be saved changes nothing.
+            for conv in removed {
+                mgr.conversations.insert(conv.id, (*conv).clone());
+            }
+            if let Some((trash_path, mut trash)) = trashed {
+                for id in &ids {
+                    trash.take(id);
+                }
+                if let Err(e) = trash.save(&trash_path, config.compress_history) {
+                    eprintln!("Failed to take undeleted conversations out of the trash: {}", e);
+                }
+            }
+            return Err(MyError::ConversationWriteToDiskFail);
+        }
+        ids
+    };
+    let mut revision = None;
+    for id in &conversation_ids {
+        revision = Some(draft_store.set(*id, ""));
+    }
+    if let Some(revision) = revision {
+        schedule_draft_flush(&tasks, draft_store.inner().clone(), revision);
+    }
+
+    let deleted = conversation_ids.len();
+    events::emit_all(
+        &app_handle,
+        "conversations_removed",
+        ConversationsRemovedEventPayload { conversation_ids },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Ok(deleted)
+}
+
+/// Deleted conversations that can still be restored, most recently deleted first.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_trashed_conversations(
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<Vec<TrashedConversationPayload>, MyError> {
+    let trash_path =
+        crate::config::Config::get_trash_path().map_err(|_| MyError::NoConfigDirFail)?;
+    let trash = Trash::open(&trash_path).map_err(|_| MyError::TrashFail)?;
+    let trash_days = config.read().await.retention.trash_days;
+    Ok(trash
+        .conversations
+        .iter()
+        .rev()
+        .map(|trashed| TrashedConversationPayload::new(trashed, trash_days))
+        .collect())
+}
+
+/// Moves a deleted conversation from the trash back into the history.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn restore_conversation<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    conversation_id: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let trash_path =
+        crate::config::Config::get_trash_path().map_err(|_| MyError::NoConfigDirFail)?;
+    let mut mgr = conversation_manager.write().await;
+    let config = config.read().await;
+    let mut trash = Trash::open(&trash_path).map_err(|_| MyError::TrashFail)?;
+    let conv = trash
+        .take(&conversation_id)
+        .ok_or(MyError::ConversationNotFoundFail)?;
+    let added = ConversationAddedEvent {
+        conversation_id,
+        title: conv.get_title().into_owned(),
+    };
+    // Back in the history before it leaves the trash, so a failure loses nothing.
+    mgr.conversations.insert(conversation_id, conv);
+    mgr.write_to_disk(&config.conversation_history_save_path)
+        .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    trash
+        .save(&trash_path, config.compress_history)
+        .map_err(|_| MyError::TrashFail)?;
+    drop(config);
+    drop(mgr);
+
+    events::emit_all(&app_handle, "new_conversation", added).map_err(|_| MyError::EmitFail)?;
+    Ok(())
+}
+
+/// Deletes everything in the trash for good, returning how many conversations that was.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn empty_trash(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<usize, MyError> {
+    ensure_writable(&config).await?;
+    let trash_path =
+        crate::config::Config::get_trash_path().map_err(|_| MyError::NoConfigDirFail)?;
+    // The history lock guards the trash too, so a delete can't land in between.
+    let _mgr = conversation_manager.write().await;
+    let mut trash = Trash::open(&trash_path).map_err(|_| MyError::TrashFail)?;
+    let emptied = std::mem::take(&mut trash.conversations).len();
+    trash
+        .save(&trash_path, false)
+        .map_err(|_| MyError::TrashFail)?;
+    Ok(emptied)
+}
+
+fn open_templates() -> Result<(std::path::PathBuf, Templates), MyError> {
+    let path =
+        crate::config::Config::get_templates_path().map_err(|_| MyError::NoConfigDirFail)?;
+    let templates = Templates::open(&path).map_err(|_| MyError::TemplateFail)?;
+    Ok((path, templates))
+}
+
+/// Checks a template the way the commands changing the same settings of a conversation do.
+fn validated_template(
+    id: uuid::Uuid,
+    template: ConversationTemplatePayload,
+) -> Result<ConversationTemplate, MyError> {
+    let name = validation::not_empty("name", &template.name)?;
+    validation::max_length("name", &name, validation::MAX_TITLE_LENGTH)?;
+    let title_pattern = validation::title("title_pattern", &template.title_pattern)?;
+    check_stop_sequences(&template.stop_sequences)?;
+    let starter_messages = template
+        .starter_messages
+        .into_iter()
+        .map(|message| {
+            let content = validation::not_empty("starter_messages", &message.content)?;
+            validation::max_length("starter_messages", &content, validation::MAX_MESSAGE_LENGTH)?;
+            Ok(StarterMessage {
+                author: message.author,
+                content,
+            })
+        })
+        .collect::<Result<_, MyError>>()?;
+    Ok(ConversationTemplate {
+        id,
+        name,
+        title_pattern,
+        system_prompt: template.system_prompt.trim().to_string(),
+        model: template.model.trim().to_string(),
+        auto_respond: template.auto_respond,
+        stop_sequences: template.stop_sequences,
+        assistant_prefix: template.assistant_prefix,
+        language: template.language.trim().to_string(),
+        starter_messages,
+    })
+}
+
+/// Conversation templates, in the order they were created.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_templates() -> Result<Vec<ConversationTemplateEntryPayload>, MyError> {
+    let (_, templates) = open_templates()?;
+    Ok(templates
+        .templates
+        .iter()
+        .map(ConversationTemplateEntryPayload::from)
+        .collect())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn create_template(
+    config: State<'_, RwLock<crate::config::Config>>,
+    template: ConversationTemplatePayload,
+) -> Result<ConversationTemplateEntryPayload, MyError> {
+    ensure_writable(&config).await?;
+    let template = validated_template(uuid::Uuid::new_v4(), template)?;
+    let (path, mut templates) = open_templates()?;
+    let created = ConversationTemplateEntryPayload::from(&template);
+    templates.templates.push(template);
+    templates
+        .save(&path, false)
+        .map_err(|_| MyError::TemplateFail)?;
+    Ok(created)
+}
+
+/// Replaces a template; conversations already made from it stay as they are.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn update_template(
+    config: State<'_, RwLock<crate::config::Config>>,
+    template_id: &str,
+    template: ConversationTemplatePayload,
+) -> Result<ConversationTemplateEntryPayload, MyError> {
+    ensure_writable(&config).await?;
+    let template_id = validation::id("template_id", template_id)?;
+    let template = validated_template(template_id, template)?;
+    let (path, mut templates) = open_templates()?;
+    let updated = ConversationTemplateEntryPayload::from(&template);
+    if !templates.replace(template) {
+        return Err(MyError::TemplateNotFoundFail);
+    }
+    templates
+        .save(&path, false)
+        .map_err(|_| MyError::TemplateFail)?;
+    Ok(updated)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn delete_template(
+    config: State<'_, RwLock<crate::config::Config>>,
+    template_id: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let template_id = validation::id("template_id", template_id)?;
+    let (path, mut templates) = open_templates()?;
+    templates
+        .remove(&template_id)
+        .ok_or(MyError::TemplateNotFoundFail)?;
+    templates
+        .save(&path, false)
+        .map_err(|_| MyError::TemplateFail)?;
+    Ok(())
+}
+
+/// A new conversation with the template's title, settings and starter messages, announced as
+/// `new_conversation`. Nothing is sent until the user sends a message.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn new_conversation_from_template<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    template_id: &str,
+) -> Result<Conversation, MyError> {
+    ensure_writable(&config).await?;
+    let template_id = validation::id("template_id", template_id)?;
+    let (_, templates) = open_templates()?;
+    let template = templates
+        .get(&template_id)
+        .ok_or(MyError::TemplateNotFoundFail)?;
+    add_new_conversation(&app_handle, template.instantiate()).await
+}
+
+/// Archives or unarchives the conversations together, returning how many changed.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn bulk_archive<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    conversation_ids: Vec<String>,
+    archived: bool,
+) -> Result<usize, MyError> {
+    ensure_writable(&config).await?;
+    let changed = {
+        let mut mgr = conversation_manager.write().await;
+        let mut changed = Vec::new();
+        let mut previous = Vec::new();
+        for id in bulk_conversation_ids(&mgr, &conversation_ids)? {
+            let before = mgr.conversation(&id)?;
+            if before.is_archived() != archived {
+                mgr.conversation_mut(&id)?
+                    .add_event(ConversationArchivedEvent { archived });
+                changed.push(id);
+                previous.push(before);
+            }
+        }
+        if !changed.is_empty()
+            && mgr
+                .write_to_disk(&config.read().await.conversation_history_save_path)
+                .is_err()
+        {
+            // Put back as they were, like bulk_delete, so a change that can't be saved
+            // isn't shown as made.
+            for conv in previous {
+                mgr.conversations.insert(conv.id, (*conv).clone());
+            }
+            return Err(MyError::ConversationWriteToDiskFail);
+        }
+        changed
+    };
+
+    let count = changed.len();
+    events::emit_all(
+        &app_handle,
+        "conversations_archived",
+        ConversationsArchivedEventPayload {
+            conversation_ids: changed,
+            archived,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Ok(count)
+}
+
+/// Adds the tags in `add` to every conversation and takes those in `remove` off, normalized
+/// and capped at `tagging::MAX_TAGS` like `set_conversation_tags`. Returns the tags of each
+/// conversation afterwards.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn bulk_tag<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    conversation_ids: Vec<String>,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<HashMap<String, Vec<String>>, MyError> {
+    ensure_writable(&config).await?;
+    let add = tagging::normalize_tags(add.iter().map(String::as_str));
+    let remove = tagging::normalize_tags(remove.iter().map(String::as_str));
+    let mut tags_by_id = HashMap::new();
+    let mut changes = Vec::new();
+    {
+        let mut mgr = conversation_manager.write().await;
+        let mut previous = Vec::new();
+        for id in bulk_conversation_ids(&mgr, &conversation_ids)? {
+            let before = mgr.conversation(&id)?;
+            let tags = tagging::normalize_tags(
+                before
+                    .tags()
+                    .iter()
+                    .chain(&add)
+                    .filter(|tag| !remove.contains(tag))
+                    .map(String::as_str),
+            );
+            if tags != before.tags() {
+                mgr.conversation_mut(&id)?
+                    .add_event(ConversationTagsChangedEvent {
+                        tags: tags.clone(),
+                        automatic: false,
+                    });
+                previous.push(before);
+                changes.push(ConversationTagsChangedEventPayload {
+                    conversation_id: id,
+                    tags: tags.clone(),
+                    automatic: false,
+                });
+            }
+            tags_by_id.insert(id.to_string(), tags);
+        }
+        if !changes.is_empty()
+            && mgr
+                .write_to_disk(&config.read().await.conversation_history_save_path)
+                .is_err()
+        {
+            // Put back as they were, like bulk_archive.
+            for conv in previous {
+                mgr.conversations.insert(conv.id, (*conv).clone());
+            }
+            return Err(MyError::ConversationWriteToDiskFail);
+        }
+    }
+
+    events::emit_all(
+        &app_handle,
+        "conversations_tags_changed",
+        ConversationsTagsChangedEventPayload { changes },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Ok(tags_by_id)
+}
+
+/// Writes the conversations into one zip of `.ehya` archives, each importable with
+/// `import_conversation_archive` once unpacked. Returns how many were exported.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn bulk_export<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    conversation_ids: Vec<String>,
+    path: &str,
+) -> Result<usize, MyError> {
+    let path = validation::path("path", path, &[archive::BUNDLE_EXTENSION])?;
+    let attachments_dir =
+        crate::config::Config::get_attachments_dir().map_err(|_| MyError::NoConfigDirFail)?;
+    let conversations = {
+        let mgr = conversation_manager.read().await;
+        bulk_conversation_ids(&mgr, &conversation_ids)?
+            .iter()
+            .map(|id| mgr.conversation(id))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    let mut progress = Progress::start(&app_handle, ProgressKind::Export);
+    let manifests = archive::write_bundle(
+        &conversations,
+        &attachments_dir,
+        &path,
+        background::unix_now(),
+        |done, total| progress.update(done, total),
+    )
+    .map_err(|e| {
+        eprintln!("Failed to export archives: {}", e);
+        MyError::ArchiveExportFail
+    })?;
+    progress.finish();
+    Ok(manifests.len())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_conversation_settings(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    conversation_id: &str,
+) -> Result<ConversationSettingsPayload, MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let mgr = conversation_manager.read().await;
+    let conv = mgr.conversation(&conversation_id)?;
+    Ok(ConversationSettingsPayload::from(&*conv))
+}
+
+/// Reading time and how dense the conversation is, for a "long read" indicator.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_conversation_stats(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    conversation_id: &str,
+) -> Result<ConversationStatsPayload, MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let mgr = conversation_manager.read().await;
+    let conv = mgr.conversation(&conversation_id)?;
+    Ok(ConversationStats::measure(&conv).into())
+}
+
+/// With auto-respond off, sent messages wait for `generate_response`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_conversation_auto_respond<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    enabled: bool,
+) -> Result<(), MyError> {
+    change_conversation_setting(
+        &conversation_manager,
+        &config,
+        &app_handle,
+        conversation_id,
+        ConversationSetting::AutoRespond(enabled),
+    )
+    .await
+}
+
+/// Replies end before any of the sequences; an empty list removes them.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_conversation_stop_sequences<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    stop_sequences: Vec<String>,
+) -> Result<(), MyError> {
+    check_stop_sequences(&stop_sequences)?;
+    change_conversation_setting(
+        &conversation_manager,
+        &config,
+        &app_handle,
+        conversation_id,
+        ConversationSetting::StopSequences(stop_sequences),
+    )
+    .await
+}
+
+/// Replies start with `prefix`; an empty prefix removes it.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_conversation_assistant_prefix<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    prefix: &str,
+) -> Result<(), MyError> {
+    change_conversation_setting(
+        &conversation_manager,
+        &config,
+        &app_handle,
+        conversation_id,
+        ConversationSetting::AssistantPrefix(prefix.to_string()),
+    )
+    .await
+}
+
+/// Replies are written in `language`, e.g. "German"; an empty language removes the preference.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_conversation_language<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    language: &str,
+) -> Result<(), MyError> {
+    change_conversation_setting(
+        &conversation_manager,
+        &config,
+        &app_handle,
+        conversation_id,
+        ConversationSetting::Language(language.trim().to_string()),
+    )
+    .await
+}
+
+/// Replaces the system prompt from the config for this conversation; an empty prompt goes
+/// back to the config's.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_conversation_system_prompt<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    system_prompt: &str,
+) -> Result<(), MyError> {
+    change_conversation_setting(
+        &conversation_manager,
+        &config,
+        &app_handle,
+        conversation_id,
+        ConversationSetting::SystemPrompt(system_prompt.trim().to_string()),
+    )
+    .await
+}
+
+/// Replies come from `model`, with the config's models as fallbacks; an empty model goes back
+/// to the config's alone.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_conversation_model<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    model: &str,
+) -> Result<(), MyError> {
+    change_conversation_setting(
+        &conversation_manager,
+        &config,
+        &app_handle,
+        conversation_id,
+        ConversationSetting::Model(model.trim().to_string()),
+    )
+    .await
+}
+
+fn open_knowledge() -> Result<KnowledgeBase, MyError> {
+    let dir =
+        crate::config::Config::get_knowledge_dir().map_err(|_| MyError::NoConfigDirFail)?;
+    KnowledgeBase::open(&dir).map_err(|_| MyError::KnowledgeFail)
+}
+
+/// Indexes the git repository at `path` for questions about its code: the files it doesn't
+/// ignore are chunked and embedded, and conversations linked to it with
+/// `link_conversation_repository` get the chunks relevant to each question. Indexing it again
+/// updates it, embedding only what changed.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn index_repository<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    profile_clients: State<'_, RwLock<ProfileClients>>,
+    path: &str,
+) -> Result<RepositoryPayload, MyError> {
+    ensure_writable(&config).await?;
+    let root = std::fs::canonicalize(validation::not_empty("path", path)?)
+        .map_err(|_| MyError::RepositoryInvalidFail)?;
+    if !root.is_dir() || !knowledge::is_git_repository(&root) {
+        return Err(MyError::RepositoryInvalidFail);
+    }
+    let (active_profile, compress) = {
+        let config = config.read().await;
+        (config.active_profile.clone(), config.compress_history)
+    };
+    let mut progress = Progress::start(&app_handle, ProgressKind::Indexing);
+    let collected = knowledge::collect_chunks(&root);
+    let mut knowledge = open_knowledge()?;
+    let path = root.to_string_lossy().into_owned();
+    let existing = knowledge.find_by_path(&path).cloned();
+    let known = existing
+        .as_ref()
+        .and_then(|info| knowledge.read_index(info.id).ok())
+        .map(|index| index.vectors_by_text())
+        .unwrap_or_default();
+
+    let mut chunks = collected.chunks;
+    let mut missing = Vec::new();
+    for (i, chunk) in chunks.iter_mut().enumerate() {
+        match known.get(&chunk.embedding_text()) {
+            Some(vector) => chunk.vector = vector.clone(),
+            None => missing.push(i),
+        }
+    }
+    let profile_clients = profile_clients.read().await;
+    for (batch_index, batch) in missing.chunks(EMBEDDING_BATCH_SIZE).enumerate() {
+        progress.update(batch_index * EMBEDDING_BATCH_SIZE, missing.len());
+        let inputs: Vec<String> = batch.iter().map(|&i| chunks[i].embedding_text()).collect();
+        let embedded = profile_clients
+            .embed_with_failover(&active_profile, &inputs)
+            .await
+            .map_err(|_| MyError::ConversationAIResponseFail)?;
+        for (&i, vector) in batch.iter().zip(embedded) {
+            chunks[i].vector = vector;
+        }
+    }
+
+    let info = RepositoryInfo {
+        // Kept when indexing again, so linked conversations stay linked.
+        id: existing.map_or_else(uuid::Uuid::new_v4, |info| info.id),
+        name: root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.clone()),
+        path,
+        indexed_at: background::unix_now(),
+        file_count: collected.file_count,
+        chunk_count: chunks.len(),
+        truncated: collected.truncated,
+    };
+    knowledge
+        .put(info.clone(), &RepositoryIndex { chunks }, compress)
+        .map_err(|_| MyError::KnowledgeFail)?;
+    progress.finish();
+    Ok(info.into())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_repositories() -> Result<Vec<RepositoryPayload>, MyError> {
+    Ok(open_knowledge()?
+        .repositories
+        .into_iter()
+        .map(RepositoryPayload::from)
+        .collect())
+}
+
+/// Forgets the indexed repository. Conversations linked to it stop getting its code.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn remove_repository(
+    config: State<'_, RwLock<crate::config::Config>>,
+    repository_id: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let repository_id = validation::id("repository_id", repository_id)?;
+    let compress = config.read().await.compress_history;
+    open_knowledge()?
+        .remove(repository_id, compress)
+        .map_err(|_| MyError::KnowledgeFail)?
+        .ok_or(MyError::RepositoryNotFoundFail)?;
+    Ok(())
+}
+
+/// Links the conversation to an indexed repository, whose code relevant to each question is
+/// then added to the system prompt; without a repository the link is removed.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn link_conversation_repository<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    repository_id: Option<&str>,
+) -> Result<(), MyError> {
+    let repository_id = repository_id
+        .map(|repository_id| validation::id("repository_id", repository_id))
+        .transpose()?;
+    if let Some(repository_id) = repository_id {
+        open_knowledge()?
+            .get(repository_id)
+            .ok_or(MyError::RepositoryNotFoundFail)?;
+    }
+    change_conversation_setting(
+        &conversation_manager,
+        &config,
+        &app_handle,
+        conversation_id,
+        ConversationSetting::Repository(repository_id),
+    )
+    .await
+}
+
+/// Replies use the glossary's terms as defined; an empty glossary removes it.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_conversation_glossary<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    glossary: Vec<GlossaryEntryPayload>,
+) -> Result<(), MyError> {
+    let glossary = glossary
+        .into_iter()
+        .map(|entry| {
+            let term = validation::not_empty("term", &entry.term)?;
+            let term = validation::title("term", &term)?;
+            let definition = validation::not_empty("definition", &entry.definition)?;
+            validation::max_length("definition", &definition, validation::MAX_TITLE_LENGTH)?;
+            Ok(GlossaryEntry { term, definition })
+        })
+        .collect::<Result<Vec<_>, MyError>>()?;
+    if glossary.len() > glossary::MAX_GLOSSARY_TERMS || glossary::has_duplicate_terms(&glossary) {
+        return Err(MyError::GlossaryInvalidFail);
+    }
+    change_conversation_setting(
+        &conversation_manager,
+        &config,
+        &app_handle,
+        conversation_id,
+        ConversationSetting::Glossary(glossary),
+    )
+    .await
+}
+
+/// Replies follow the style preset named `preset`, as it is now; an empty name removes the
+/// conversation's preset.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn apply_preset<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    preset: &str,
+) -> Result<(), MyError> {
+    let preset = match preset.trim() {
+        "" => None,
+        name => {
+            let (_, presets) = open_style_presets()?;
+            Some(
+                presets
+                    .get(name)
+                    .cloned()
+                    .ok_or(MyError::StylePresetNotFoundFail)?,
+            )
+        }
+    };
+    change_conversation_setting(
+        &conversation_manager,
+        &config,
+        &app_handle,
+        conversation_id,
+        ConversationSetting::StylePreset(preset),
+    )
+    .await
+}
+
+fn open_style_presets() -> Result<(std::path::PathBuf, StylePresets), MyError> {
+    let path =
+        crate::config::Config::get_style_presets_path().map_err(|_| MyError::NoConfigDirFail)?;
+    let presets = StylePresets::open(&path).map_err(|_| MyError::StylePresetFail)?;
+    Ok((path, presets))
+}
+
+fn validated_style_preset(preset: StylePresetPayload) -> Result<StylePreset, MyError> {
+    let name = validation::not_empty("name", &preset.name)?;
+    let name = validation::title("name", &name)?;
+    let instruction = validation::not_empty("instruction", &preset.instruction)?;
+    validation::max_length("instruction", &instruction, validation::MAX_MESSAGE_LENGTH)?;
+    let temperature_valid = preset
+        .temperature
+        .is_none_or(|temperature| (0.0..=presets::MAX_TEMPERATURE).contains(&temperature));
+    if !temperature_valid || preset.max_tokens == Some(0) {
+        return Err(MyError::StylePresetInvalidFail);
+    }
+    Ok(StylePreset {
+        name,
+        instruction,
+        temperature: preset.temperature,
+        max_tokens: preset.max_tokens,
+    })
+}
+
+/// Response style presets, the built-in ones first.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_style_presets() -> Result<Vec<StylePresetPayload>, MyError> {
+    let (_, presets) = open_style_presets()?;
+    Ok(presets
+        .presets
+        .iter()
+        .map(StylePresetPayload::from)
+        .collect())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn create_style_preset(
+    config: State<'_, RwLock<crate::config::Config>>,
+    preset: StylePresetPayload,
+) -> Result<StylePresetPayload, MyError> {
+    ensure_writable(&config).await?;
+    let preset = validated_style_preset(preset)?;
+    let (path, mut presets) = open_style_presets()?;
+    if presets.get(&preset.name).is_some() {
+        return Err(MyError::StylePresetExistsFail);
+    }
+    let created = StylePresetPayload::from(&preset);
+    presets.presets.push(preset);
+    presets
+        .save(&path, false)
+        .map_err(|_| MyError::StylePresetFail)?;
+    Ok(created)
+}
+
+/// Replaces the preset named `name`, which `preset` may rename. Conversations the preset was
+/// applied to keep it as it was.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn update_style_preset(
+    config: State<'_, RwLock<crate::config::Config>>,
+    name: &str,
+    preset: StylePresetPayload,
+) -> Result<StylePresetPayload, MyError> {
+    ensure_writable(&config).await?;
+    let preset = validated_style_preset(preset)?;
+    let (path, mut presets) = open_style_presets()?;
+    if !preset.name.eq_ignore_ascii_case(name.trim()) && presets.get(&preset.name).is_some() {
+        return Err(MyError::StylePresetExistsFail);
+    }
+    let updated = StylePresetPayload::from(&preset);
+    if !presets.replace(name.trim(), preset) {
+        return Err(MyError::StylePresetNotFoundFail);
+    }
+    presets
+        .save(&path, false)
+        .map_err(|_| MyError::StylePresetFail)?;
+    Ok(updated)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn delete_style_preset(
+    config: State<'_, RwLock<crate::config::Config>>,
+    name: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let (path, mut presets) = open_style_presets()?;
+    presets
+        .remove(name.trim())
+        .ok_or(MyError::StylePresetNotFoundFail)?;
+    presets
+        .save(&path, false)
+        .map_err(|_| MyError::StylePresetFail)?;
+    Ok(())
+}
+
+fn check_stop_sequences(stop_sequences: &[String]) -> Result<(), MyError> {
+    match stop_sequences.len() > provider::MAX_STOP_SEQUENCES
+        || stop_sequences.iter().any(String::is_empty)
+    {
+        true => Err(MyError::ConversationSettingInvalidFail),
+        false => Ok(()),
+    }
+}
+
+/// Records the setting and announces the conversation's settings as `conversation_settings_changed`.
+async fn change_conversation_setting<R: Runtime>(
+    conversation_manager: &RwLock<ConversationManager>,
+    config: &RwLock<crate::config::Config>,
+    app_handle: &tauri::AppHandle<R>,
+    conversation_id: &str,
+    setting: ConversationSetting,
+) -> Result<(), MyError> {
+    ensure_writable(config).await?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let settings = {
+        let mut mgr = conversation_manager.write().await;
+        let conv = mgr.conversation_mut(&conversation_id)?;
+        conv.try_add_event(ConversationSettingChangedEvent { setting })?;
+        let settings = ConversationSettingsPayload::from(&*conv);
+        mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+            .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+        settings
+    };
+
+    events::emit_all(
+        app_handle,
+        "conversation_settings_changed",
+        ConversationSettingsChangedEventPayload {
+            conversation_id,
+            settings,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Ok(())
+}
+
+/// Protects a finished conversation from accidental new messages, renames and setting changes.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn lock_conversation<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+) -> Result<(), MyError> {
+    set_conversation_locked(&conversation_manager, &config, &app_handle, conversation_id, true)
+        .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn unlock_conversation<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+) -> Result<(), MyError> {
+    set_conversation_locked(&conversation_manager, &config, &app_handle, conversation_id, false)
+        .await
+}
+
+async fn set_conversation_locked<R: Runtime>(
+    conversation_manager: &RwLock<ConversationManager>,
+    config: &RwLock<crate::config::Config>,
+    app_handle: &tauri::AppHandle<R>,
+    conversation_id: &str,
+    locked: bool,
+) -> Result<(), MyError> {
+    ensure_writable(config).await?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    {
+        let mut mgr = conversation_manager.write().await;
+        let conv = mgr.conversation_mut(&conversation_id)?;
+        if conv.is_locked() == locked {
+            return Ok(());
+        }
+        conv.add_event(ConversationLockedEvent { locked });
+        mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+            .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    }
+
+    events::emit_all(
+        app_handle,
+        "conversation_lock_changed",
+        ConversationLockChangedEventPayload {
+            conversation_id,
+            locked,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Ok(())
+}
+
+/// Dry run of the retention rules: what the next background pass would delete.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn preview_retention(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<Vec<RetentionCandidatePayload>, MyError> {
+    let mgr = conversation_manager.read().await;
+    let config = config.read().await;
+    Ok(retention::plan(&mgr, &config.retention, background::unix_now())
+        .into_iter()
+        .map(RetentionCandidatePayload::from)
+        .collect())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn enforce_retention<R: Runtime>(
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+) -> Result<usize, MyError> {
+    ensure_writable(&config).await?;
+    background::enforce_retention(&app_handle)
+        .await
+        .map(|removed| removed.len())
+        .map_err(|_| MyError::RetentionFail)
+}
+
+/// `period` is one of "week", "month", "year" or "all".
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_usage_report(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    usage_log: State<'_, Arc<UsageLog>>,
+    period: &str,
+) -> Result<UsageReportPayload, MyError> {
+    let period = ReportPeriod::parse(period).ok_or(MyError::UsageReportPeriodFail)?;
+    let mgr = conversation_manager.read().await;
+    let prices = &config.read().await.model_pricing;
+    Ok(usage_log
+        .report(&mgr, period, background::unix_now(), prices)
+        .into())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_conversation_pdf(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    conversation_id: &str,
+    path: &str,
+) -> Result<(), MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    validation::path("path", path, &["pdf"])?;
+    let mgr = conversation_manager.read().await;
+    let conv = mgr.conversation(&conversation_id)?;
+    pdf::conversation_to_pdf(&conv, path).map_err(|_| MyError::PdfExportFail)
+}
+
+/// Writes a single self-contained HTML file for sharing outside the app.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn publish_conversation_html(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    conversation_id: &str,
+    path: &str,
+) -> Result<(), MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    validation::path("path", path, &["html", "htm"])?;
+    let attachments_dir =
+        crate::config::Config::get_attachments_dir().map_err(|_| MyError::NoConfigDirFail)?;
+    let mgr = conversation_manager.read().await;
+    let conv = mgr.conversation(&conversation_id)?;
+    std::fs::write(path, html::conversation_to_html(&conv, &attachments_dir))
+        .map_err(|_| MyError::HtmlExportFail)
+}
+
+/// Reports which importer would handle the file, so one import button can cover every format.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn detect_import_format(path: &str) -> Result<String, MyError> {
+    validation::path("path", path, &["json"])?;
+    let format = import::detect_import_format(path).map_err(|_| MyError::ImportFail)?;
+    serde_json::to_value(format)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .ok_or(MyError::ImportFail)
+}
+
+/// Adds every conversation found in the file, returning the new conversation ids.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn import_conversations<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    path: &str,
+) -> Result<Vec<String>, MyError> {
+    ensure_writable(&config).await?;
+    validation::path("path", path, &["json"])?;
+    let progress = Progress::start(&app_handle, ProgressKind::Import);
+    let imported = import::import_conversations(path).map_err(|_| MyError::ImportFail)?;
+    let mut mgr = conversation_manager.write().await;
+    let added: Vec<ConversationAddedEvent> = imported
+        .iter()
+        .map(|conv| ConversationAddedEvent {
+            conversation_id: conv.id,
+            title: conv.get_title().into_owned(),
+        })
+        .collect();
+    for conv in imported {
+        mgr.conversations.insert(conv.id, conv);
+    }
+    mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+        .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    drop(mgr);
+    progress.finish();
+
+    let ids = added
+        .iter()
+        .map(|event| event.conversation_id.to_string())
+        .collect();
+    for event in added {
+        events::emit_all(&app_handle, "new_conversation", event).map_err(|_| MyError::EmitFail)?;
+    }
+    Ok(ids)
+}
+
+/// Writes the conversation with every event and the attachments it shows as a `.ehya`
+/// archive, to be imported on another machine with `import_conversation_archive`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_conversation_archive(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    conversation_id: &str,
+    path: &str,
+) -> Result<(), MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let path = validation::path("path", path, &[archive::ARCHIVE_EXTENSION])?;
+    let attachments_dir =
+        crate::config::Config::get_attachments_dir().map_err(|_| MyError::NoConfigDirFail)?;
+    let conv = conversation_manager.read().await.conversation(&conversation_id)?;
+    archive::write(&conv, &attachments_dir, &path, background::unix_now()).map_err(|e| {
+        eprintln!("Failed to export archive: {}", e);
+        MyError::ArchiveExportFail
+    })?;
+    Ok(())
+}
+
+/// Adds the conversation in a `.ehya` archive as it was exported, ids and timestamps included,
+/// returning its id. Refused when the conversation is already in the history.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn import_conversation_archive<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    path: &str,
+) -> Result<String, MyError> {
+    ensure_writable(&config).await?;
+    let path = validation::path("path", path, &[archive::ARCHIVE_EXTENSION])?;
+    let archive = archive::read(&path).map_err(|e| match e {
+        archive::ArchiveError::UnsupportedVersion(_) => MyError::ArchiveVersionFail,
+        e => {
+            eprintln!("Failed to import archive: {}", e);
+            MyError::ArchiveImportFail
+        }
+    })?;
+    add_archived_conversation(&app_handle, &conversation_manager, &config, archive)
+        .await
+        .map(|conversation_id| conversation_id.to_string())
+}
+
+/// Adds the conversation read from an archive, refused when it's already in the history.
+pub async fn add_archived_conversation<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    conversation_manager: &RwLock<ConversationManager>,
+    config: &RwLock<crate::config::Config>,
+    archive: archive::Archive,
+) -> Result<uuid::Uuid, MyError> {
+    let conversation_id = archive.conversation.id;
+    let mut mgr = conversation_manager.write().await;
+    if mgr.conversations.get(&conversation_id).is_some() {
+        return Err(MyError::ArchiveConversationExistsFail);
+    }
+    let attachments_dir =
+        crate::config::Config::get_attachments_dir().map_err(|_| MyError::NoConfigDirFail)?;
+    let config = config.read().await;
+    archive::restore_attachments(&archive, &attachments_dir, config.compress_history)
+        .map_err(|_| MyError::ArchiveImportFail)?;
+    let added = ConversationAddedEvent {
+        conversation_id,
+        title: archive.conversation.get_title().into_owned(),
+    };
+    mgr.conversations.insert(conversation_id, archive.conversation);
+    mgr.write_to_disk(&config.conversation_history_save_path)
+        .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    drop(mgr);
+
+    events::emit_all(app_handle, "new_conversation", added).map_err(|_| MyError::EmitFail)?;
+    Ok(conversation_id)
+}
+
+/// Writes the conversation like `export_conversation_archive` does, encrypted with
+/// `passphrase`, as a `.ehyas` bundle in the shared bundles folder. Returns the bundle's path,
+/// for sending it on; it's opened with `import_shared_bundle` and the same passphrase.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn share_conversation_encrypted(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    conversation_id: &str,
+    passphrase: &str,
+) -> Result<String, MyError> {
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    validation::not_empty("passphrase", passphrase)?;
+    let attachments_dir =
+        crate::config::Config::get_attachments_dir().map_err(|_| MyError::NoConfigDirFail)?;
+    let shared_dir =
+        crate::config::Config::get_shared_bundles_dir().map_err(|_| MyError::NoConfigDirFail)?;
+    std::fs::create_dir_all(&shared_dir).map_err(|_| MyError::ShareFail)?;
+    let path = shared_dir.join(format!("{}.{}", conversation_id, sharing::SHARED_EXTENSION));
+    let conv = conversation_manager.read().await.conversation(&conversation_id)?;
+    // Deriving the key takes a while on purpose, so it's kept off the async runtime.
+    let passphrase = passphrase.to_string();
+    let bundle_path = path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        sharing::write(
+            &conv,
+            &attachments_dir,
+            &bundle_path,
+            &passphrase,
+            background::unix_now(),
+        )
+    })
+    .await
+    .map_err(|_| MyError::ShareFail)?
+    .map_err(|e| {
+        eprintln!("Failed to share conversation: {}", e);
+        MyError::ShareFail
+    })?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Adds the conversation in a `.ehyas` bundle from `share_conversation_encrypted`, decrypted
+/// with `passphrase`, returning its id. Refused when the conversation is already in the
+/// history.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn import_shared_bundle<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    path: &str,
+    passphrase: &str,
+) -> Result<String, MyError> {
+    ensure_writable(&config).await?;
+    let path = validation::path("path", path, &[sharing::SHARED_EXTENSION])?;
+    // Deriving the key takes a while on purpose, so it's kept off the async runtime.
+    let passphrase = passphrase.to_string();
+    let read = tauri::async_runtime::spawn_blocking(move || sharing::read(&path, &passphrase))
+        .await
+        .map_err(|_| MyError::SharedBundleFail)?;
+    let archive = read.map_err(|e| match e {
+        sharing::ShareError::UnsupportedVersion(_)
+        | sharing::ShareError::Archive(archive::ArchiveError::UnsupportedVersion(_)) => {
+            MyError::ArchiveVersionFail
+        }
+        sharing::ShareError::WrongPassphrase => MyError::SharedBundlePassphraseFail,
+        e => {
+            eprintln!("Failed to import shared bundle: {}", e);
+            MyError::SharedBundleFail
+        }
+    })?;
+    add_archived_conversation(&app_handle, &conversation_manager, &config, archive)
+        .await
+        .map(|conversation_id| conversation_id.to_string())
+}
+
+/// Writes the selected conversations as chat-format JSONL, returning the number of examples.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_finetune_dataset<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    conversation_ids: Vec<String>,
+    path: &str,
+    options: FinetuneExportOptionsPayload,
+) -> Result<usize, MyError> {
+    validation::path("path", path, &["jsonl"])?;
+    let mgr = conversation_manager.read().await;
+    let conversations = conversation_ids
+        .iter()
+        .map(|id| {
+            let id = validation::id("conversation_ids", id)?;
+            mgr.conversation(&id)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut file = std::io::BufWriter::new(
+        std::fs::File::create(path).map_err(|_| MyError::FinetuneExportFail)?,
+    );
+    let mut progress = Progress::start(&app_handle, ProgressKind::Export);
+    let total = conversations.len();
+    let written = finetune::write_dataset(
+        &mut file,
+        conversations
+            .iter()
+            .enumerate()
+            .map(|(done, conversation)| {
+                progress.update(done, total);
+                &**conversation
+            }),
+        &options.into(),
+    )
+    .map_err(|_| MyError::FinetuneExportFail)?;
+    progress.finish();
+    Ok(written)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_all_data<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    target_path: &str,
+) -> Result<usize, MyError> {
+    validation::path("target_path", target_path, &["zip"])?;
+    let mgr = conversation_manager.read().await;
+    let config = config.read().await;
+    let mut progress = Progress::start(&app_handle, ProgressKind::Export);
+    let exported = takeout::export_all_data(&mgr, &config, target_path, |done, total| {
+        progress.update(done, total)
+    })
+    .map_err(|_| MyError::DataExportFail)?;
+    progress.finish();
+    Ok(exported)
+}
+
+/// Moves the conversation history to `new_path` and switches to it without a restart.
+/// The old file is kept as a backup, so nothing is lost if the new location turns out to be
+/// wrong; `clean_storage` removes it.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_history_location<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    new_path: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    {
+        let mut mgr = conversation_manager.write().await;
+        // Only the part loaded so far would be moved.
+        if mgr.loading {
+            return Err(MyError::HistoryLoadingFail);
+        }
+        let mut config = config.write().await;
+        // The file value would be written back in place of the new path.
+        if config
+            .overrides
+            .source_of("conversation_history_save_path")
+            .is_some()
+        {
+            return Err(MyError::HistoryLocationInvalidFail);
+        }
+        let target = std::path::Path::new(new_path);
+        relocate::validate_target(&config.conversation_history_save_path, target)
+            .map_err(|_| MyError::HistoryLocationInvalidFail)?;
+        let progress = Progress::start(&app_handle, ProgressKind::Migration);
+        let moved = relocate::move_history(&mgr, target).map_err(|_| MyError::HistoryMoveFail)?;
+        progress.finish();
+
+        let old_path =
+            std::mem::replace(&mut config.conversation_history_save_path, new_path.to_string());
+        config.history_backups.push(old_path.clone());
+        if config.write_to_disk().is_err() {
+            config.conversation_history_save_path = old_path;
+            config.history_backups.pop();
+            return Err(MyError::ConfigWriteToDiskFail);
+        }
+        *mgr = moved;
+    }
+    if let Some(watcher) = app_handle.try_state::<HistoryWatcher>() {
+        if let Err(e) = watcher.set_path(new_path.into()) {
+            eprintln!("Failed to watch moved history: {}", e);
+        }
+    }
+
+    events::emit_all(&app_handle, "history_location_changed", new_path.to_string())
+        .map_err(|_| MyError::EmitFail)?;
+    Ok(())
+}
+
+fn emit_sync_status<R: Runtime>(app_handle: &tauri::AppHandle<R>, status: SyncStatusPayload) {
+    if let Err(e) = events::emit_all(app_handle, "sync_status", status) {
+        eprintln!("Failed to announce sync status: {}", e);
+    }
+}
+
+/// Pushes local conversation changes to the configured sync backend and applies remote ones,
+/// reporting progress through `sync_status` events.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn sync_now<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    sync_conflicts: State<'_, Arc<SyncConflicts>>,
+    app_handle: tauri::AppHandle<R>,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let backend = {
+        let config = config.read().await;
+        let Some(backend_config) = &config.sync.backend else {
+            return Err(MyError::SyncNotConfiguredFail);
+        };
+        sync::backend(backend_config, &config.proxy).map_err(|_| MyError::SyncFail)?
+    };
+    emit_sync_status(
+        &app_handle,
+        SyncStatusPayload {
+            state: "syncing".to_string(),
+            pushed: 0,
+            pulled: 0,
+            conflicts: 0,
+            error: None,
+        },
+    );
+
+    // Sync a snapshot so the history stays usable while waiting on the network.
+    let snapshot = conversation_manager.read().await.conversations.clone();
+    let outcome = match sync::sync(backend.as_ref(), &snapshot).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            emit_sync_status(
+                &app_handle,
+                SyncStatusPayload {
+                    state: "failed".to_string(),
+                    pushed: 0,
+                    pulled: 0,
+                    conflicts: 0,
+                    error: Some(e.to_string()),
+                },
+            );
+            return Err(MyError::SyncFail);
+        }
+    };
+
+    let mut pulled = Vec::new();
+    {
+        let mut mgr = conversation_manager.write().await;
+        for remote in outcome.pulled {
+            // Conversations changed during the sync are left for the next one.
+            let unchanged = match (mgr.conversations.get(&remote.id), snapshot.get(&remote.id)) {
+                (Some(current), Some(before)) => current.history.len() == before.history.len(),
+                (None, None) => true,
+                _ => false,
+            };
+            if unchanged {
+                pulled.push(remote.id);
+                mgr.conversations.insert(remote.id, remote);
+            }
+        }
+        if !pulled.is_empty() {
+            mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+                .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+        }
+    }
+
+    for conversation_id in &pulled {
+        events::emit_all(
+            &app_handle,
+            "conversation_reloaded",
+            ConversationReloadedEventPayload {
+                conversation_id: conversation_id.to_string(),
+                removed: false,
+            },
+        )
+        .map_err(|_| MyError::EmitFail)?;
+    }
+    let conflicts = outcome.conflicts.len();
+    for remote in outcome.conflicts {
+        let local_events = snapshot
+            .get(&remote.id)
+            .map_or(0, |local| local.history.len());
+        let payload = SyncConflictEventPayload {
+            conversation_id: remote.id.to_string(),
+            local_events,
+            remote_events: remote.history.len(),
+        };
+        sync_conflicts
+            .insert(remote)
+            .map_err(|_| MyError::SyncFail)?;
+        events::emit_all(&app_handle, "sync_conflict", payload).map_err(|_| MyError::EmitFail)?;
+    }
+    emit_sync_status(
+        &app_handle,
+        SyncStatusPayload {
+            state: "synced".to_string(),
+            pushed: outcome.pushed,
+            pulled: pulled.len(),
+            conflicts,
+            error: None,
+        },
+    );
+    Ok(())
+}
+
+/// Ids of conversations whose local and remote histories diverged and await resolution.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_sync_conflicts(
+    sync_conflicts: State<'_, Arc<SyncConflicts>>,
+) -> Result<Vec<String>, MyError> {
+    Ok(sync_conflicts
+        .conversation_ids()
+        .into_iter()
+        .map(|id| id.to_string())
+        .collect())
+}
+
+/// Settles a sync conflict with `merge_by_timestamp`, `keep_local` or `keep_remote`, and
+/// uploads the result so other devices pick it up.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn resolve_sync_conflict<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    sync_conflicts: State<'_, Arc<SyncConflicts>>,
+    app_handle: tauri::AppHandle<R>,
+    conversation_id: &str,
+    strategy: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    let conversation_id = validation::id("conversation_id", conversation_id)?;
+    let strategy = ConflictStrategy::parse(strategy).ok_or(MyError::SyncConflictStrategyFail)?;
+    let remote = sync_conflicts
+        .get(conversation_id)
+        .ok_or(MyError::SyncConflictNotFoundFail)?;
+    let backend = {
+        let config = config.read().await;
+        let Some(backend_config) = &config.sync.backend else {
+            return Err(MyError::SyncNotConfiguredFail);
+        };
+        sync::backend(backend_config, &config.proxy).map_err(|_| MyError::SyncFail)?
+    };
+
+    {
+        // Held across the upload so no message lands between resolving and saving.
+        let mut mgr = conversation_manager.write().await;
+        let local = mgr.conversation(&conversation_id)?;
+        let resolved = sync::resolve_conflict(&local, remote, strategy);
+        sync::push_resolved(backend.as_ref(), &resolved)
+            .await
+            .map_err(|_| MyError::SyncFail)?;
+        mgr.conversations.insert(conversation_id, resolved);
+        mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+            .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+    }
+    sync_conflicts
+        .remove(conversation_id)
+        .map_err(|_| MyError::SyncFail)?;
+
+    events::emit_all(
+        &app_handle,
+        "conversation_reloaded",
+        ConversationReloadedEventPayload {
+            conversation_id: conversation_id.to_string(),
+            removed: false,
+        },
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    events::emit_all(
+        &app_handle,
+        "sync_conflict_resolved",
+        conversation_id.to_string(),
+    )
+    .map_err(|_| MyError::EmitFail)?;
+    Ok(())
+}
+
+/// Erases conversations, attachments, the request log and usage analytics. Settings and API keys are kept.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command(rename_all = "snake_case")]
+pub async fn delete_all_data<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    recorder: State<'_, Arc<RequestRecorder>>,
+    usage_log: State<'_, Arc<UsageLog>>,
+    draft_store: State<'_, Arc<DraftStore>>,
+    sync_conflicts: State<'_, Arc<SyncConflicts>>,
+    app_handle: tauri::AppHandle<R>,
+    confirmation: &str,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    if confirmation != takeout::DELETE_ALL_DATA_CONFIRMATION {
+        return Err(MyError::DeleteConfirmationFail);
+    }
+    {
+        let mut mgr = conversation_manager.write().await;
+        mgr.conversations.clear();
+        mgr.write_to_disk(&config.read().await.conversation_history_save_path)
+            .map_err(|_| MyError::ConversationWriteToDiskFail)?;
+        Trash::default()
+            .save(
+                &crate::config::Config::get_trash_path().map_err(|_| MyError::NoConfigDirFail)?,
+                false,
+            )
+            .map_err(|_| MyError::DataDeleteFail)?;
+    }
+    takeout::delete_attachments().map_err(|_| MyError::DataDeleteFail)?;
+    recorder.clear().map_err(|_| MyError::DataDeleteFail)?;
+    usage_log.clear().map_err(|_| MyError::DataDeleteFail)?;
+    draft_store.clear().map_err(|_| MyError::DataDeleteFail)?;
+    embeddings::delete(
+        &crate::config::Config::get_embeddings_path().map_err(|_| MyError::NoConfigDirFail)?,
+    )
+    .map_err(|_| MyError::DataDeleteFail)?;
+    knowledge::delete(
+        &crate::config::Config::get_knowledge_dir().map_err(|_| MyError::NoConfigDirFail)?,
+    )
+    .map_err(|_| MyError::DataDeleteFail)?;
+    sync_conflicts.clear().map_err(|_| MyError::DataDeleteFail)?;
+    {
+        let mut config = config.write().await;
+        storage::delete_backups(&mut config).map_err(|_| MyError::DataDeleteFail)?;
+        config
+            .write_to_disk()
+            .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+    }
+
+    events::emit_all(&app_handle, "all_data_deleted", ()).map_err(|_| MyError::EmitFail)?;
+    Ok(())
+}
+
+/// Disk usage by category, with which categories are worth cleaning.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_storage_report(
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<Vec<StorageUsagePayload>, MyError> {
+    let report = storage::report(&*config.read().await).map_err(|_| MyError::NoConfigDirFail)?;
+    Ok(report.into_iter().map(StorageUsagePayload::from).collect())
+}
+
+/// Deletes the data of the given cleanable categories, returning the bytes reclaimed.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn clean_storage(
+    config: State<'_, RwLock<crate::config::Config>>,
+    recorder: State<'_, Arc<RequestRecorder>>,
+    usage_log: State<'_, Arc<UsageLog>>,
+    categories: Vec<String>,
+) -> Result<u64, MyError> {
+    ensure_writable(&config).await?;
+    let categories = categories
+        .iter()
+        .map(|category| StorageCategory::parse(category).filter(|c| c.is_cleanable()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(MyError::StorageCategoryInvalidFail)?;
+    let mut config = config.write().await;
+    let mut reclaimed = 0;
+    for category in categories {
+        let before = storage::usage(&config, category).map_err(|_| MyError::NoConfigDirFail)?;
+        match category {
+            StorageCategory::Embeddings => embeddings::delete(
+                &crate::config::Config::get_embeddings_path()
+                    .map_err(|_| MyError::NoConfigDirFail)?,
+            )
+            .map_err(|_| MyError::DataDeleteFail)?,
+            StorageCategory::Logs => {
+                recorder.clear().map_err(|_| MyError::DataDeleteFail)?;
+                usage_log.clear().map_err(|_| MyError::DataDeleteFail)?;
+                let crash_report_path = crate::config::Config::get_crash_report_path()
+                    .map_err(|_| MyError::NoConfigDirFail)?;
+                if crash_report_path.exists() {
+                    std::fs::remove_file(crash_report_path).map_err(|_| MyError::DataDeleteFail)?;
+                }
+            }
+            StorageCategory::Backups => {
+                storage::delete_backups(&mut config).map_err(|_| MyError::DataDeleteFail)?;
+                config
+                    .write_to_disk()
+                    .map_err(|_| MyError::ConfigWriteToDiskFail)?;
+            }
+            StorageCategory::Conversations | StorageCategory::Attachments => {
+                return Err(MyError::StorageCategoryInvalidFail)
+            }
+        }
+        let after = storage::usage(&config, category).map_err(|_| MyError::NoConfigDirFail)?;
+        reclaimed += before.bytes.saturating_sub(after.bytes);
+    }
+    Ok(reclaimed)
+}
+
+/// Size and hit rate of the embeddings cache.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_embedding_cache_stats(
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<EmbeddingCacheStatsPayload, MyError> {
+    let path =
+        crate::config::Config::get_embeddings_path().map_err(|_| MyError::NoConfigDirFail)?;
+    let stats = EmbeddingCache::open(&path).stats();
+    let max_size_mb = config.read().await.embedding_cache.max_size_mb;
+    Ok(EmbeddingCacheStatsPayload {
+        entries: stats.entries,
+        size_bytes: stats.size_bytes,
+        max_size_bytes: max_size_mb.saturating_mul(1024 * 1024),
+        hits: stats.hits,
+        misses: stats.misses,
+    })
+}
+
+/// Empties the embeddings cache, so topic grouping embeds every conversation again.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn clear_embedding_cache(
+    config: State<'_, RwLock<crate::config::Config>>,
+) -> Result<(), MyError> {
+    ensure_writable(&config).await?;
+    embeddings::delete(
+        &crate::config::Config::get_embeddings_path().map_err(|_| MyError::NoConfigDirFail)?,
+    )
+    .map_err(|_| MyError::DataDeleteFail)
+}
+
+/// Background work currently running, oldest first.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_background_tasks(
+    tasks: State<'_, TaskSupervisor>,
+) -> Result<Vec<BackgroundTaskPayload>, MyError> {
+    Ok(tasks.list().into_iter().map(BackgroundTaskPayload::from).collect())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cancel_task(tasks: State<'_, TaskSupervisor>, id: &str) -> Result<(), MyError> {
+    let id = validation::id("id", id)?;
+    if !tasks.cancel(id) {
+        return Err(MyError::TaskNotFoundFail);
+    }
+    Ok(())
+}
+
+/// The last panic of the previous run, if any; empty when it ran without one.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_last_crash_report(
+    last_crash: State<'_, LastCrashReport>,
+) -> Result<Vec<CrashReportPayload>, MyError> {
+    Ok(last_crash.0.iter().cloned().map(CrashReportPayload::from).collect())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn open_deep_link<R: Runtime>(app_handle: tauri::AppHandle<R>, url: &str) -> Result<(), MyError> {
+    let link = deep_link::parse(url)?;
+    deep_link::route(&app_handle, link).await
+}
+
+/// Opens a `.ehya` archive, Markdown or text file handed over by the OS or dropped on the
+/// window: archives and Markdown transcripts are imported, other files start a new chat with
+/// them as its draft. Announced as `file_opened`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn handle_opened_file<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    path: &str,
+) -> Result<FileOpenedEventPayload, MyError> {
+    ensure_writable(&config).await?;
+    file_open::open(&app_handle, path).await
+}
+
+/// Returns the files the app was launched with; the frontend opens them once it's listening.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn take_pending_opened_files(
+    pending: State<'_, file_open::PendingOpenedFiles>,
+) -> Result<Vec<String>, MyError> {
+    Ok(std::mem::take(&mut *pending.0.lock().unwrap()))
+}
+
+/// Returns the links the app was launched with; the frontend opens them once it's listening.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn take_pending_deep_links(
+    pending: State<'_, deep_link::PendingDeepLinks>,
+) -> Result<Vec<String>, MyError> {
+    Ok(std::mem::take(&mut *pending.0.lock().unwrap()))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_files() -> Result<Vec<String>, MyError> {
     let res = std::fs::read_dir("./").map_err(|_| MyError::DirListFail)?
     .map(|res| res.map(|e| e.path().display().to_string()))
     .collect::<Result<Vec<String>, std::io::Error>>().map_err(|_| MyError::DirListFail)?;
     
     Ok(res)
-}
\ No newline at end of file
+}
+/// Checks the stored history for damage such as unreadable events or missing attachments.
+/// With `repair`, fixes what can be fixed after keeping a backup of the file, which
+/// `backup_path` names.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn verify_history_integrity<R: Runtime>(
+    conversation_manager: State<'_, RwLock<ConversationManager>>,
+    config: State<'_, RwLock<crate::config::Config>>,
+    app_handle: tauri::AppHandle<R>,
+    repair: bool,
+) -> Result<IntegrityReportPayload, MyError> {
+    if repair {
+        ensure_writable(&config).await?;
+    }
+    let attachments_dir =
+        crate::config::Config::get_attachments_dir().map_err(|_| MyError::NoConfigDirFail)?;
+    let (report, backup_path) = {
+        // Held so no save lands between reading the file and writing the repaired one.
+        let _mgr = conversation_manager.write().await;
+        let mut config = config.write().await;
+        let path = config.conversation_history_save_path.clone();
+        let json = match compression::read_to_string(std::path::Path::new(&path)) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => "{}".to_string(),
+            Err(_) => return Err(MyError::IntegrityCheckFail),
+        };
+        let integrity::Audit { report, repaired } =
+            integrity::audit(&json, &attachments_dir).map_err(|_| MyError::IntegrityCheckFail)?;
+        let mut backup_path = String::new();
+        if repair && report.needs_repair() {
+            let repaired = ConversationManager {
+                conversations: repaired.into_iter().collect(),
+                compressed: config.compress_history,
+                loading: false,
+            };
+            backup_path = integrity::repair(&mut config, &repaired, background::unix_now())
+                .map_err(|_| MyError::IntegrityRepairFail)?;
+        }
+        (report, backup_path)
+    };
+    if !backup_path.is_empty() {
+        // Without waiting for the watcher, so the result reflects the repaired history.
+        history_watcher::reload(&app_handle).await;
+    }
+    Ok(integrity_report_payload(report, backup_path))
+}
+
+fn integrity_report_payload(
+    report: integrity::IntegrityReport,
+    backup_path: String,
+) -> IntegrityReportPayload {
+    IntegrityReportPayload {
+        conversations: report.conversations,
+        events: report.events,
+        issues: report
+            .issues
+            .into_iter()
+            .map(|issue| IntegrityIssuePayload {
+                repairable: issue.kind.is_repairable(),
+                conversation_id: issue.conversation_id,
+                event_index: issue.event_index,
+                kind: issue.kind,
+                detail: issue.detail,
+            })
+            .collect(),
+        repaired: !backup_path.is_empty(),
+        backup_path,
+    }
+}
+
+/// How the history was recovered on startup or on switching profiles because it was damaged;
+/// see `integrity::load_or_recover`.
+/// Empty until the history has loaded; see `history_loader`.
+pub struct LastHistoryRecovery(pub std::sync::Mutex<Option<integrity::HistoryRecovery>>);
+
+/// What was wrong with the history when it was loaded on startup or on switching profiles;
+/// empty when it loaded as it was.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_history_recovery_report(
+    recovery: State<'_, LastHistoryRecovery>,
+) -> Result<Vec<IntegrityReportPayload>, MyError> {
+    Ok(recovery
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .cloned()
+        .map(|recovery| integrity_report_payload(recovery.report, recovery.backup_path))
+        .collect())
+}
@@ -0,0 +1,181 @@
+//! Reloads the config when it's edited outside the app.
+//!
+//! Settings the app reads on every use (appearance, keybindings, locale, request recording,
+//! retention, ...) apply immediately. Settings only read at startup are stored but flagged as
+//! requiring a restart. An edited history path is kept for the next start, while the history
+//! in use goes on being saved where it was loaded from; see `Config::defer_history_path`.
+//!
+//! The whole config directory is watched so the config of whichever workspace profile is
+//! active at the time of an edit is the one reloaded.
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+use notify::{RecursiveMode, Watcher};
+use tauri::{async_runtime::RwLock, AppHandle, Manager, Runtime};
+use tokio::sync::mpsc;
+
+use crate::{
+    config::Config,
+    events, i18n, keybindings,
+    payloads::{AppearancePayload, ConfigChangePayload},
+    recorder::RequestRecorder,
+    workspaces,
+};
+
+/// Editors often write a file in several steps; wait for them to settle before reading.
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(300);
+
+pub async fn watch_config<R: Runtime>(app_handle: AppHandle<R>) {
+    let Some(base) = workspaces::base_dir() else {
+        eprintln!("Failed to locate config for watching");
+        return;
+    };
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        // Looked up per event since switching profiles moves the config.
+        let Ok(watched_path) = Config::get_config_path() else {
+            return;
+        };
+        if (event.kind.is_modify() || event.kind.is_create())
+            && event.paths.iter().any(|path| path == &watched_path)
+        {
+            // Only fails once the receiving loop has stopped.
+            let _ = sender.send(());
+        }
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("Failed to watch config: {}", e);
+            return;
+        }
+    };
+    // Watch the directory since editors often replace the file instead of writing in place.
+    if let Err(e) = std::fs::create_dir_all(&base) {
+        eprintln!("Failed to watch config: {}", e);
+        return;
+    }
+    if let Err(e) = watcher.watch(&base, RecursiveMode::Recursive) {
+        eprintln!("Failed to watch config: {}", e);
+        return;
+    }
+
+    while receiver.recv().await.is_some() {
+        tokio::time::sleep(RELOAD_DEBOUNCE).await;
+        while receiver.try_recv().is_ok() {}
+        let Ok(path) = Config::get_config_path() else {
+            eprintln!("Failed to locate config for reloading");
+            continue;
+        };
+        reload(&app_handle, &path).await;
+    }
+}
+
+fn read_config(path: &Path) -> Option<Config> {
+    match Config::read_from(path) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            eprintln!("Ignoring invalid config edit: {}", e);
+            None
+        }
+    }
+}
+
+/// Reverts settings that fail validation to their current values.
+fn keep_valid(current: &Config, new: &mut Config) {
+    if !new.appearance.is_valid() {
+        eprintln!("Ignoring invalid appearance in edited config");
+        new.appearance = current.appearance.clone();
+    }
+    if new
+        .keybindings
+        .values()
+        .any(|shortcut| !shortcut.is_empty() && keybindings::normalize_shortcut(shortcut).is_none())
+    {
+        eprintln!("Ignoring invalid keybindings in edited config");
+        new.keybindings = current.keybindings.clone();
+    }
+    if !i18n::list_locales()
+        .iter()
+        .any(|(code, _)| *code == new.locale)
+    {
+        eprintln!("Ignoring unknown locale in edited config");
+        new.locale = current.locale.clone();
+    }
+}
+
+async fn reload<R: Runtime>(app_handle: &AppHandle<R>, path: &Path) {
+    let Some(mut new) = read_config(path) else {
+        return;
+    };
+    let config = app_handle.state::<RwLock<Config>>();
+    let mut config = config.write().await;
+    // Environment and flag overrides still win over the edited file.
+    new = match new.with_overrides(config.overrides.clone()) {
+        Ok(new) => new,
+        Err(e) => {
+            eprintln!("Ignoring config edit: {}", e);
+            return;
+        }
+    };
+    keep_valid(&config, &mut new);
+    let in_use = config.conversation_history_save_path.clone();
+    if config.deferred_history_path() == Some(new.conversation_history_save_path.as_str()) {
+        // Already reported, so the edit only counts once.
+        new.defer_history_path(&in_use);
+    }
+    let changes = config.diff(&new);
+    new.defer_history_path(&in_use);
+    if changes.is_empty() {
+        // Usually our own write_to_disk.
+        return;
+    }
+
+    app_handle
+        .state::<Arc<RequestRecorder>>()
+        .set_enabled(new.record_requests);
+    new.apply_locale();
+    let appearance = new.appearance.clone();
+    let effective_keybindings = keybindings::effective(&new.keybindings);
+    let locale = new.locale.clone();
+    let read_only = new.read_only;
+    *config = new;
+    drop(config);
+
+    let changed = |key: &str| changes.iter().any(|change| change.key == key);
+    let mut emitted = Vec::new();
+    if changed("appearance") {
+        emitted.push(events::emit_all(
+            app_handle,
+            "appearance_changed",
+            AppearancePayload::from(appearance),
+        ));
+    }
+    if changed("keybindings") {
+        emitted.push(events::emit_all(
+            app_handle,
+            "keybindings_changed",
+            effective_keybindings,
+        ));
+    }
+    if changed("locale") {
+        emitted.push(events::emit_all(app_handle, "locale_changed", locale));
+    }
+    if changed("read_only") {
+        emitted.push(events::emit_all(app_handle, "read_only_changed", read_only));
+    }
+    emitted.push(events::emit_all(
+        app_handle,
+        "config_changed",
+        changes
+            .into_iter()
+            .map(ConfigChangePayload::from)
+            .collect::<Vec<ConfigChangePayload>>(),
+    ));
+    if let Some(Err(e)) = emitted.into_iter().find(Result::is_err) {
+        eprintln!("Failed to announce config change: {}", e);
+    }
+}
@@ -0,0 +1,137 @@
+//! Saves what it can when the app panics and keeps a report of the panic for the next launch.
+//!
+//! The hook runs on the panicking thread, possibly while it holds locks, so state is only saved
+//! when its lock can be taken without waiting.
+
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    panic::PanicHookInfo,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{async_runtime::RwLock, AppHandle, Manager};
+
+use crate::{background, config::Config, drafts::DraftStore, models::ConversationManager, tasks};
+
+/// How many of the latest commands a report lists.
+const RECENT_COMMAND_COUNT: usize = 20;
+
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+static RECENT_COMMANDS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+/// Set while a panic is being handled, so panics on other threads meanwhile are only printed.
+static HANDLING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub occurred_at: i64,
+    pub version: String,
+    pub message: String,
+    pub location: String,
+    pub backtrace: String,
+    /// Oldest first.
+    pub recent_commands: Vec<String>,
+}
+
+/// The report left by the previous run, if it panicked.
+pub struct LastCrashReport(pub Option<CrashReport>);
+
+/// Reads and removes the report at `path`, so each crash is only surfaced once.
+pub fn take_last_report(path: &Path) -> Option<CrashReport> {
+    let json = std::fs::read_to_string(path).ok()?;
+    if let Err(e) = std::fs::remove_file(path) {
+        eprintln!("Failed to remove crash report: {}", e);
+    }
+    match serde_json::from_str(&json) {
+        Ok(report) => Some(report),
+        Err(e) => {
+            eprintln!("Ignoring unreadable crash report: {}", e);
+            None
+        }
+    }
+}
+
+/// Installs the hook, keeping the default one so panics are still printed.
+pub fn install(report_path: PathBuf) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if HANDLING.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        save_state();
+        if let Err(e) = write_report(&report_path, info) {
+            eprintln!("Failed to write crash report: {}", e);
+        }
+        HANDLING.store(false, Ordering::SeqCst);
+    }));
+}
+
+/// Gives the hook access to the app's state once it's running.
+pub fn attach(app_handle: AppHandle) {
+    let _ = APP_HANDLE.set(app_handle);
+}
+
+pub fn record_command(command: &str) {
+    let Ok(mut commands) = RECENT_COMMANDS.lock() else {
+        return;
+    };
+    if commands.len() == RECENT_COMMAND_COUNT {
+        commands.pop_front();
+    }
+    commands.push_back(command.to_string());
+}
+
+fn save_state() {
+    let Some(app_handle) = APP_HANDLE.get() else {
+        return;
+    };
+    if let Some(draft_store) = app_handle.try_state::<Arc<DraftStore>>() {
+        match draft_store.try_flush() {
+            Ok(true) => {}
+            Ok(false) => eprintln!("Drafts were busy, not saving them"),
+            Err(e) => eprintln!("Failed to save drafts: {}", e),
+        }
+    }
+    let (Some(config), Some(mgr)) = (
+        app_handle.try_state::<RwLock<Config>>(),
+        app_handle.try_state::<RwLock<ConversationManager>>(),
+    ) else {
+        return;
+    };
+    let (Ok(config), Ok(mgr)) = (config.try_read(), mgr.try_read()) else {
+        eprintln!("Conversations were busy, not saving them");
+        return;
+    };
+    if config.read_only {
+        return;
+    }
+    if let Err(e) = mgr.write_to_disk(&config.conversation_history_save_path) {
+        eprintln!("Failed to save conversations: {}", e);
+    }
+}
+
+fn write_report(path: &Path, info: &PanicHookInfo) -> Result<(), Box<dyn std::error::Error>> {
+    let recent_commands = RECENT_COMMANDS
+        .try_lock()
+        .map(|commands| commands.iter().cloned().collect())
+        .unwrap_or_default();
+    let report = CrashReport {
+        occurred_at: background::unix_now(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        message: tasks::panic_message(info.payload()).to_string(),
+        location: info.location().map(ToString::to_string).unwrap_or_default(),
+        backtrace: Backtrace::force_capture().to_string(),
+        recent_commands,
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+    Ok(())
+}
@@ -0,0 +1,306 @@
+//! `ehyaioess://` URL handling.
+//!
+//! The OS launches a new process with the link as a command line argument. If another
+//! instance is already running, the link is forwarded to it over a loopback socket and the
+//! new process exits. macOS delivers links through Apple Events, which tauri 1 doesn't expose,
+//! so only Windows and Linux are registered.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpStream},
+    path::PathBuf,
+    sync::Mutex,
+    time::Duration,
+};
+
+use tauri::{async_runtime::RwLock, AppHandle, Manager, Runtime};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::{
+    commands,
+    config::Config,
+    events, file_open,
+    models::{ConversationManager, MyError},
+    payloads::{DeepLinkNewConversationEventPayload, DeepLinkOpenConversationEventPayload},
+    tasks::TaskSupervisor,
+};
+
+pub const SCHEME: &str = "ehyaioess";
+const MAX_PROMPT_LENGTH: usize = 16 * 1024;
+/// Sent by the running instance on connect so a stale port file can't make us talk to a stranger.
+const INSTANCE_GREETING: &str = "ehyaioess-instance";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeepLink {
+    OpenConversation(Uuid),
+    NewConversation { prompt: Option<String> },
+}
+
+pub fn parse(link: &str) -> Result<DeepLink, MyError> {
+    let url = url::Url::parse(link).map_err(|_| MyError::DeepLinkInvalidFail)?;
+    if url.scheme() != SCHEME {
+        return Err(MyError::DeepLinkInvalidFail);
+    }
+    match url.host_str() {
+        Some("conversation") => {
+            let conversation_id = url.path().trim_matches('/');
+            let conversation_id =
+                Uuid::parse_str(conversation_id).map_err(|_| MyError::DeepLinkInvalidFail)?;
+            Ok(DeepLink::OpenConversation(conversation_id))
+        }
+        Some("new") => {
+            let prompt = url
+                .query_pairs()
+                .find(|(key, _)| key == "prompt")
+                .map(|(_, value)| value.into_owned())
+                .filter(|prompt| !prompt.trim().is_empty());
+            if prompt.as_ref().is_some_and(|p| p.len() > MAX_PROMPT_LENGTH) {
+                return Err(MyError::DeepLinkInvalidFail);
+            }
+            Ok(DeepLink::NewConversation { prompt })
+        }
+        _ => Err(MyError::DeepLinkInvalidFail),
+    }
+}
+
+pub fn links_from_args(args: impl Iterator<Item = String>) -> Vec<String> {
+    let prefix = format!("{}://", SCHEME);
+    args.filter(|arg| arg.starts_with(&prefix)).collect()
+}
+
+/// Links the app was launched with, held until the frontend is ready to receive their events.
+#[derive(Default)]
+pub struct PendingDeepLinks(pub Mutex<Vec<String>>);
+
+pub async fn route<R: Runtime>(app_handle: &AppHandle<R>, link: DeepLink) -> Result<(), MyError> {
+    match link {
+        DeepLink::OpenConversation(conversation_id) => {
+            let exists = app_handle
+                .state::<RwLock<ConversationManager>>()
+                .read()
+                .await
+                .conversations
+                .contains_key(&conversation_id);
+            if !exists {
+                return Err(MyError::ConversationNotFoundFail);
+            }
+            focus_main_window(app_handle);
+            events::emit_all(
+                app_handle,
+                "deep_link_open_conversation",
+                DeepLinkOpenConversationEventPayload { conversation_id },
+            )
+            .map_err(|_| MyError::EmitFail)
+        }
+        DeepLink::NewConversation { prompt } => {
+            let conv = commands::create_conversation(app_handle).await?;
+            focus_main_window(app_handle);
+            events::emit_all(
+                app_handle,
+                "deep_link_new_conversation",
+                DeepLinkNewConversationEventPayload {
+                    conversation_id: conv.id,
+                    prompt,
+                },
+            )
+            .map_err(|_| MyError::EmitFail)
+        }
+    }
+}
+
+pub fn focus_main_window<R: Runtime>(app_handle: &AppHandle<R>) {
+    if let Some(window) = app_handle.get_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn instance_port_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(Config::get_config_dir()?.join("instance.port"))
+}
+
+/// Hands the links, and the paths of files opened with the app, to an already running
+/// instance, returning false if none is listening.
+pub fn forward_to_running_instance(links: &[String]) -> bool {
+    let Some(port) = instance_port_path()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|port| port.trim().parse::<u16>().ok())
+    else {
+        return false;
+    };
+    let address = SocketAddr::from(([127, 0, 0, 1], port));
+    let Ok(mut stream) = TcpStream::connect_timeout(&address, Duration::from_millis(500)) else {
+        return false;
+    };
+    let _ = stream.set_read_timeout(Some(Duration::from_millis(500)));
+    let mut greeting = String::new();
+    if BufReader::new(&stream).read_line(&mut greeting).is_err()
+        || greeting.trim() != INSTANCE_GREETING
+    {
+        return false;
+    }
+    links
+        .iter()
+        .all(|link| writeln!(stream, "{}", link).is_ok())
+}
+
+fn write_instance_port(
+    listener: &tokio::net::TcpListener,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let port = listener.local_addr()?.port();
+    std::fs::write(instance_port_path()?, port.to_string())?;
+    Ok(())
+}
+
+/// Accepts links and opened files forwarded by later launches and routes them as they arrive.
+pub async fn listen_for_forwarded_links<R: Runtime>(app_handle: AppHandle<R>) {
+    let listener = match tokio::net::TcpListener::bind("127.0.0.1:0").await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to listen for forwarded deep links: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = write_instance_port(&listener) {
+        eprintln!("Failed to record instance port: {}", e);
+        return;
+    }
+    let tasks = app_handle.state::<TaskSupervisor>().inner().clone();
+    loop {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let app_handle = app_handle.clone();
+        tasks.spawn("forwarded_link", async move {
+            if stream
+                .write_all(format!("{}\n", INSTANCE_GREETING).as_bytes())
+                .await
+                .is_err()
+            {
+                return;
+            }
+            let mut lines = tokio::io::BufReader::new(stream).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let line = line.trim();
+                match line.starts_with(&format!("{}://", SCHEME)) {
+                    true => handle_link(&app_handle, line).await,
+                    false => file_open::handle_file(&app_handle, line).await,
+                }
+            }
+        });
+    }
+}
+
+pub async fn handle_link<R: Runtime>(app_handle: &AppHandle<R>, link: &str) {
+    let result = match parse(link) {
+        Ok(deep_link) => route(app_handle, deep_link).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("Ignoring deep link {}: {}", link, e);
+    }
+}
+
+/// Registers this executable as the `ehyaioess://` handler for the current user.
+/// Skipped in debug builds so development binaries don't replace the installed handler.
+pub fn register_url_scheme() {
+    if cfg!(debug_assertions) {
+        return;
+    }
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            eprintln!("Failed to locate executable for URL scheme registration: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = register_url_scheme_for(&exe) {
+        eprintln!("Failed to register {}:// URL scheme: {}", SCHEME, e);
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn register_url_scheme_for(exe: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    let key = format!("HKCU\\Software\\Classes\\{}", SCHEME);
+    let command = format!("\"{}\" \"%1\"", exe.display());
+    let entries: [(&str, Option<&str>, &str); 3] = [
+        (&key, None, "URL:ehyaioess protocol"),
+        (&key, Some("URL Protocol"), ""),
+        (&format!("{}\\shell\\open\\command", key), None, &command),
+    ];
+    for (key, value_name, data) in entries {
+        let mut reg = std::process::Command::new("reg");
+        reg.creation_flags(CREATE_NO_WINDOW).args(["add", key]);
+        match value_name {
+            Some(name) => reg.args(["/v", name]),
+            None => reg.arg("/ve"),
+        };
+        let status = reg.args(["/d", data, "/f"]).status()?;
+        if !status.success() {
+            return Err(format!("reg add {} failed", key).into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn register_url_scheme_for(exe: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+    let desktop_file_name = format!("{}-url-handler.desktop", SCHEME);
+    let applications_dir = tauri::api::path::data_dir()
+        .ok_or(MyError::NoConfigDirFail)?
+        .join("applications");
+    std::fs::create_dir_all(&applications_dir)?;
+    std::fs::write(
+        applications_dir.join(&desktop_file_name),
+        format!(
+            "[Desktop Entry]\nType=Application\nName=ehyaioess\nExec=\"{}\" %u\nNoDisplay=true\nMimeType=x-scheme-handler/{};\n",
+            exe.display(),
+            SCHEME
+        ),
+    )?;
+    std::process::Command::new("xdg-mime")
+        .args([
+            "default",
+            &desktop_file_name,
+            &format!("x-scheme-handler/{}", SCHEME),
+        ])
+        .status()?;
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn register_url_scheme_for(_exe: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let id = Uuid::new_v4();
+        assert_eq!(
+            parse(&format!("ehyaioess://conversation/{}", id)).unwrap(),
+            DeepLink::OpenConversation(id)
+        );
+        assert_eq!(
+            parse("ehyaioess://new?prompt=hello%20there").unwrap(),
+            DeepLink::NewConversation {
+                prompt: Some("hello there".to_string())
+            }
+        );
+        assert_eq!(
+            parse("ehyaioess://new").unwrap(),
+            DeepLink::NewConversation { prompt: None }
+        );
+        assert!(parse("ehyaioess://conversation/not-a-uuid").is_err());
+        assert!(parse("https://conversation/abc").is_err());
+        assert!(parse("ehyaioess://delete_everything").is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::broadcast,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Emits an event to every window and, when the event bridge is running, to its WebSocket clients.
+pub fn emit_all<R: Runtime, S: Serialize + Clone>(
+    app_handle: &AppHandle<R>,
+    event: &str,
+    payload: S,
+) -> tauri::Result<()> {
+    if let Some(bridge) = app_handle.try_state::<EventBridge>() {
+        bridge.publish(event, &payload);
+    }
+    app_handle.emit_all(event, payload)
+}
+
+#[derive(Serialize)]
+struct BridgedEvent<'a, S: Serialize> {
+    event: &'a str,
+    payload: &'a S,
+}
+
+/// Re-broadcasts frontend events to external WebSocket consumers (overlays, loggers, ...).
+#[derive(Clone)]
+pub struct EventBridge {
+    sender: broadcast::Sender<String>,
+}
+
+impl EventBridge {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(256);
+        Self { sender }
+    }
+
+    fn publish<S: Serialize>(&self, event: &str, payload: &S) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        match serde_json::to_string(&BridgedEvent { event, payload }) {
+            Ok(message) => {
+                // Sending only fails when every client has disconnected in the meantime.
+                let _ = self.sender.send(message);
+            }
+            Err(e) => eprintln!("Failed to serialize bridged event {}: {}", event, e),
+        }
+    }
+
+    pub async fn serve(self, address: String) {
+        let listener = match TcpListener::bind(&address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("Failed to start event bridge on {}: {}", address, e);
+                return;
+            }
+        };
+        println!("Event bridge listening on ws://{}", address);
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tauri::async_runtime::spawn(forward_events(stream, self.sender.subscribe()));
+                }
+                Err(e) => eprintln!("Event bridge failed to accept connection: {}", e),
+            }
+        }
+    }
+}
+
+async fn forward_events(stream: TcpStream, mut receiver: broadcast::Receiver<String>) {
+    let Ok(websocket) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let (mut sink, mut incoming) = websocket.split();
+    loop {
+        tokio::select! {
+            event = receiver.recv() => match event {
+                Ok(message) => {
+                    if sink.send(Message::Text(message)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("Event bridge client lagged, dropped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = incoming.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            },
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! Files opened with the app: `.ehya` archives are imported, Markdown transcripts become
+//! conversations and other Markdown or text files start a new chat with them in the composer.
+//!
+//! The OS passes a file opened through a file association or "Open with" as a command line
+//! argument, which is forwarded to a running instance the way deep links are. Files dropped on
+//! the window reach `handle_opened_file` from the frontend.
+
+use std::{path::PathBuf, sync::Mutex};
+
+use tauri::{async_runtime::RwLock, AppHandle, Manager, Runtime};
+
+use crate::{
+    archive, commands,
+    config::Config,
+    deep_link,
+    drafts::DraftStore,
+    events, import,
+    models::{Conversation, ConversationManager, ConversationTitleChangedEvent, MyError},
+    payloads::FileOpenedEventPayload,
+    validation,
+};
+
+pub const OPENABLE_EXTENSIONS: [&str; 3] = [archive::ARCHIVE_EXTENSION, "md", "txt"];
+const ARCHIVE_MIME_TYPE: &str = "application/x-ehyaioess-archive";
+
+/// The arguments naming files the app can open, as absolute paths so a running instance
+/// they're forwarded to finds them too.
+pub fn files_from_args(args: impl Iterator<Item = String>) -> Vec<String> {
+    args.filter(|arg| !arg.starts_with('-') && !arg.contains("://"))
+        .map(PathBuf::from)
+        .filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    OPENABLE_EXTENSIONS
+                        .iter()
+                        .any(|openable| openable.eq_ignore_ascii_case(extension))
+                })
+        })
+        .filter_map(|path| path.canonicalize().ok())
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Files the app was launched with, held until the frontend is ready to receive their events.
+#[derive(Default)]
+pub struct PendingOpenedFiles(pub Mutex<Vec<String>>);
+
+/// Opens the file at `path` and shows the conversation it became, announced as `file_opened`.
+pub async fn open<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    path: &str,
+) -> Result<FileOpenedEventPayload, MyError> {
+    let path = validation::path("path", path, &OPENABLE_EXTENSIONS)?;
+    let is_archive = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(archive::ARCHIVE_EXTENSION));
+    let opened: FileOpenedEventPayload = match is_archive {
+        true => {
+            let archive = archive::read(&path).map_err(|e| match e {
+                archive::ArchiveError::UnsupportedVersion(_) => MyError::ArchiveVersionFail,
+                e => {
+                    eprintln!("Failed to import archive: {}", e);
+                    MyError::ArchiveImportFail
+                }
+            })?;
+            let conversation_id = commands::add_archived_conversation(
+                app_handle,
+                &app_handle.state::<RwLock<ConversationManager>>(),
+                &app_handle.state::<RwLock<Config>>(),
+                archive,
+            )
+            .await?;
+            FileOpenedEventPayload {
+                conversation_id,
+                imported: true,
+            }
+        }
+        false => {
+            let content = std::fs::read_to_string(&path).map_err(|_| MyError::OpenedFileFail)?;
+            open_text(app_handle, &path, &content).await?
+        }
+    };
+    deep_link::focus_main_window(app_handle);
+    events::emit_all(app_handle, "file_opened", opened.clone()).map_err(|_| MyError::EmitFail)?;
+    Ok(opened)
+}
+
+/// Imports a Markdown transcript, or starts a chat titled after the file with `content` as
+/// its draft.
+async fn open_text<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    path: &std::path::Path,
+    content: &str,
+) -> Result<FileOpenedEventPayload, MyError> {
+    if let Some(conv) = import::markdown_transcript(content) {
+        let conv = commands::add_new_conversation(app_handle, conv).await?;
+        return Ok(FileOpenedEventPayload {
+            conversation_id: conv.id,
+            imported: true,
+        });
+    }
+    validation::max_length("content", content, validation::MAX_MESSAGE_LENGTH)?;
+    let mut conv = Conversation::new();
+    let title = path
+        .file_stem()
+        .map(|stem| validation::imported_title(&stem.to_string_lossy()))
+        .unwrap_or_default();
+    if !title.is_empty() {
+        conv.add_event(ConversationTitleChangedEvent { new_title: title });
+    }
+    let conv = commands::add_new_conversation(app_handle, conv).await?;
+    let draft_store = app_handle.state::<std::sync::Arc<DraftStore>>();
+    draft_store.set(conv.id, content);
+    if let Err(e) = draft_store.try_flush() {
+        eprintln!("Failed to save drafts: {}", e);
+    }
+    Ok(FileOpenedEventPayload {
+        conversation_id: conv.id,
+        imported: false,
+    })
+}
+
+/// Opens a file forwarded by a later launch, logging why when it can't be.
+pub async fn handle_file<R: Runtime>(app_handle: &AppHandle<R>, path: &str) {
+    if let Err(e) = open(app_handle, path).await {
+        eprintln!("Ignoring opened file {}: {}", path, e);
+    }
+}
+
+/// Registers this executable as the handler of `.ehya` archives and as an "Open with" choice
+/// for Markdown and text files, for the current user. Skipped in debug builds like
+/// `deep_link::register_url_scheme`.
+pub fn register_file_associations() {
+    if cfg!(debug_assertions) {
+        return;
+    }
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            eprintln!(
+                "Failed to locate executable for file association registration: {}",
+                e
+            );
+            return;
+        }
+    };
+    if let Err(e) = register_file_associations_for(&exe) {
+        eprintln!("Failed to register file associations: {}", e);
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn register_file_associations_for(
+    exe: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    let classes = "HKCU\\Software\\Classes";
+    let prog_id = format!("{}.file", deep_link::SCHEME);
+    let command = format!("\"{}\" \"%1\"", exe.display());
+    let mut entries: Vec<(String, Option<String>, String)> = vec![
+        (
+            format!("{}\\{}", classes, prog_id),
+            None,
+            "ehyaioess conversation".to_string(),
+        ),
+        (
+            format!("{}\\{}\\shell\\open\\command", classes, prog_id),
+            None,
+            command,
+        ),
+        // Archives open with the app by default; Markdown and text files only offer it.
+        (
+            format!("{}\\.{}", classes, archive::ARCHIVE_EXTENSION),
+            None,
+            prog_id.clone(),
+        ),
+    ];
+    for extension in &OPENABLE_EXTENSIONS {
+        entries.push((
+            format!("{}\\.{}\\OpenWithProgids", classes, extension),
+            Some(prog_id.clone()),
+            String::new(),
+        ));
+    }
+    for (key, value_name, data) in entries {
+        let mut reg = std::process::Command::new("reg");
+        reg.creation_flags(CREATE_NO_WINDOW).args(["add", &key]);
+        match &value_name {
+            Some(name) => reg.args(["/v", name]),
+            None => reg.arg("/ve"),
+        };
+        let status = reg.args(["/d", &data, "/f"]).status()?;
+        if !status.success() {
+            return Err(format!("reg add {} failed", key).into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn register_file_associations_for(
+    exe: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let data_dir = tauri::api::path::data_dir().ok_or(MyError::NoConfigDirFail)?;
+    // `.ehya` needs a MIME type of its own before it can be associated.
+    let packages_dir = data_dir.join("mime").join("packages");
+    std::fs::create_dir_all(&packages_dir)?;
+    std::fs::write(
+        packages_dir.join(format!("{}.xml", deep_link::SCHEME)),
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<mime-info xmlns=\"http://www.freedesktop.org/standards/shared-mime-info\">\n  <mime-type type=\"{}\">\n    <comment>ehyaioess conversation archive</comment>\n    <glob pattern=\"*.{}\"/>\n  </mime-type>\n</mime-info>\n",
+            ARCHIVE_MIME_TYPE,
+            archive::ARCHIVE_EXTENSION
+        ),
+    )?;
+    std::process::Command::new("update-mime-database")
+        .arg(data_dir.join("mime"))
+        .status()?;
+
+    let desktop_file_name = format!("{}-file-handler.desktop", deep_link::SCHEME);
+    let applications_dir = data_dir.join("applications");
+    std::fs::create_dir_all(&applications_dir)?;
+    std::fs::write(
+        applications_dir.join(&desktop_file_name),
+        format!(
+            "[Desktop Entry]\nType=Application\nName=ehyaioess\nExec=\"{}\" %f\nNoDisplay=true\nMimeType={};text/markdown;text/plain;\n",
+            exe.display(),
+            ARCHIVE_MIME_TYPE
+        ),
+    )?;
+    // Archives open with the app by default; Markdown and text files only offer it.
+    std::process::Command::new("xdg-mime")
+        .args(["default", &desktop_file_name, ARCHIVE_MIME_TYPE])
+        .status()?;
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn register_file_associations_for(
+    _exe: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_files_from_args() {
+        let dir = std::env::temp_dir().join(format!("file_open-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let notes = dir.join("Notes.MD");
+        std::fs::write(&notes, "# Notes").unwrap();
+        std::fs::write(dir.join("photo.png"), "").unwrap();
+        let args = [
+            "--flag".to_string(),
+            "ehyaioess://new".to_string(),
+            notes.to_string_lossy().into_owned(),
+            dir.join("photo.png").to_string_lossy().into_owned(),
+            dir.join("missing.txt").to_string_lossy().into_owned(),
+        ];
+        assert_eq!(
+            files_from_args(args.into_iter()),
+            [notes.canonicalize().unwrap().to_string_lossy()]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
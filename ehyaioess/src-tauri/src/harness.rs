@@ -0,0 +1,1465 @@
+//! Runs the commands end to end without a real window: the state `main` manages, on Tauri's
+//! mock runtime, with every profile in a temp dir and completions answered by a local mock
+//! provider. Commands go through the same IPC handler as the frontend's `invoke`, so their
+//! arguments and results are checked as they cross the wire.
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tauri::{
+    api::ipc::CallbackFn,
+    test::{MockRuntime, INVOKE_KEY},
+    InvokePayload, Manager,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::{
+    analytics::UsageLog,
+    config::Config,
+    drafts::DraftStore,
+    generation_journal::{GenerationJournal, PendingGeneration},
+    history_loader,
+    profiles::ProfileClients,
+    recorder::RequestRecorder,
+    sync::SyncConflicts,
+    window_layout::WindowLayout,
+    workspaces, ManagedState,
+};
+
+/// Harnesses share the process-wide profile directory and locale, so they take turns.
+static EXCLUSIVE: Mutex<()> = Mutex::new(());
+
+pub const MOCK_MODEL: &str = "mock-model";
+pub const MOCK_REPLY: &str = "Mock reply";
+
+/// How the mock provider answers a completion.
+#[derive(Debug, Clone, Copy)]
+pub enum MockReply {
+    Complete,
+    /// A server error, which isn't retried with another profile.
+    Fail,
+    /// The first word of the reply, then the connection drops.
+    Interrupt,
+    /// The requested model doesn't exist, so the next configured model is tried.
+    ModelNotFound,
+}
+
+/// An OpenAI-compatible server answering every completion with `MOCK_REPLY`, unless other
+/// replies are queued, embedding each input as a vector of its length and word count, and
+/// listing `MOCK_MODEL` and `gpt-4o` as its models.
+#[derive(Clone, Default)]
+pub struct MockProvider {
+    queued: Arc<Mutex<VecDeque<MockReply>>>,
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+impl MockProvider {
+    /// Starts serving on a free local port, returning the API base to point clients at.
+    fn start(&self) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let provider = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            while let Ok((stream, _)) = listener.accept().await {
+                tauri::async_runtime::spawn(provider.clone().answer(stream));
+            }
+        });
+        format!("http://{}/v1", address)
+    }
+
+    /// Answers the next completion with `reply` instead of `MOCK_REPLY`.
+    pub fn queue(&self, reply: MockReply) {
+        self.queued.lock().unwrap().push_back(reply);
+    }
+
+    /// The bodies of the requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    async fn answer(self, mut stream: TcpStream) {
+        let Some((path, request)) = read_request(&mut stream).await else {
+            return;
+        };
+        self.requests.lock().unwrap().push(request.clone());
+        let response = if path.ends_with("/models") {
+            let data = json!([
+                { "id": MOCK_MODEL, "owned_by": "harness" },
+                { "id": "gpt-4o", "owned_by": "system" },
+            ]);
+            http_response("200 OK", "application/json", &json!({ "data": data }).to_string())
+        } else if path.ends_with("/embeddings") {
+            let data: Vec<Value> = request["input"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .enumerate()
+                .map(|(index, input)| {
+                    let text = input.as_str().unwrap_or_default();
+                    let words = text.split_whitespace().count();
+                    json!({ "index": index, "embedding": [text.len() as f32, words as f32, 1.0] })
+                })
+                .collect();
+            http_response("200 OK", "application/json", &json!({ "data": data }).to_string())
+        } else {
+            let reply = self.queued.lock().unwrap().pop_front();
+            let streamed = request["stream"].as_bool().unwrap_or_default();
+            completion(reply.unwrap_or(MockReply::Complete), streamed)
+        };
+        // The client finds out on its own when the connection is gone.
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+}
+
+/// The path and JSON body of the request on `stream`.
+async fn read_request(stream: &mut TcpStream) -> Option<(String, Value)> {
+    let mut received = Vec::new();
+    let mut buffer = [0; 4096];
+    let header_end = loop {
+        let read = stream.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        received.extend_from_slice(&buffer[..read]);
+        if let Some(end) = received.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&received[..header_end]).to_string();
+    let path = head.split_whitespace().nth(1)?.to_string();
+    let length: usize = head
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse().ok())
+                .flatten()
+        })
+        .unwrap_or_default();
+    while received.len() < header_end + length {
+        let read = stream.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        received.extend_from_slice(&buffer[..read]);
+    }
+    let body = serde_json::from_slice(&received[header_end..]).unwrap_or_default();
+    Some((path, body))
+}
+
+fn http_response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+fn completion(reply: MockReply, streamed: bool) -> String {
+    let usage = json!({ "prompt_tokens": 12, "completion_tokens": 2 });
+    match reply {
+        MockReply::Fail => http_response(
+            "500 Internal Server Error",
+            "application/json",
+            &json!({ "error": { "message": "Mock failure", "type": "server_error" } }).to_string(),
+        ),
+        MockReply::ModelNotFound => http_response(
+            "404 Not Found",
+            "application/json",
+            &json!({
+                "error": {
+                    "message": "Mock model not found",
+                    "type": "invalid_request_error",
+                    "code": "model_not_found",
+                }
+            })
+            .to_string(),
+        ),
+        MockReply::Complete if !streamed => http_response(
+            "200 OK",
+            "application/json",
+            &json!({
+                "model": MOCK_MODEL,
+                "choices": [{ "message": { "role": "assistant", "content": MOCK_REPLY } }],
+                "usage": usage,
+            })
+            .to_string(),
+        ),
+        MockReply::Complete | MockReply::Interrupt => {
+            let chunk = |content: &str| {
+                let chunk = json!({ "model": MOCK_MODEL, "choices": [{ "delta": { "content": content } }] });
+                format!("data: {}\n\n", chunk)
+            };
+            let mut events = String::new();
+            for word in MOCK_REPLY.split_inclusive(' ') {
+                events.push_str(&chunk(word));
+                if matches!(reply, MockReply::Interrupt) {
+                    break;
+                }
+            }
+            if matches!(reply, MockReply::Complete) {
+                let chunk = json!({ "model": MOCK_MODEL, "choices": [], "usage": usage });
+                events.push_str(&format!("data: {}\n\ndata: [DONE]\n\n", chunk));
+            }
+            // Without a length, the body ends when the connection closes.
+            format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nconnection: close\r\n\r\n{}",
+                events
+            )
+        }
+    }
+}
+
+/// The app as a test sees it. Dropping it removes its directory.
+pub struct Harness {
+    app: tauri::App<MockRuntime>,
+    window: tauri::Window<MockRuntime>,
+    pub provider: MockProvider,
+    dir: TempDir,
+    invoked: Mutex<BTreeSet<String>>,
+    _exclusive: MutexGuard<'static, ()>,
+}
+
+struct TempDir(PathBuf);
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+impl Harness {
+    /// A fresh install with one API key profile, its history still to be created.
+    pub fn new() -> Self {
+        let exclusive = EXCLUSIVE.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join(format!("harness-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self::start(TempDir(dir), exclusive)
+    }
+
+    /// Starts the app again on the same directory, as after quitting it.
+    pub fn restart(self) -> Self {
+        let Harness {
+            app,
+            dir,
+            _exclusive,
+            ..
+        } = self;
+        drop(app);
+        Self::start(dir, _exclusive)
+    }
+
+    fn start(dir: TempDir, exclusive: MutexGuard<'static, ()>) -> Self {
+        workspaces::set_base_dir(Some(dir.0.clone()));
+        // A profile left open by an earlier harness doesn't exist here.
+        workspaces::select(&dir.0, workspaces::DEFAULT_WORKSPACE).unwrap();
+        if !Config::get_config_path().unwrap().exists() {
+            Config::for_workspace(&dir.0, "sk-test").write_to_disk().unwrap();
+        }
+        let config = Config::from_disk().unwrap();
+        config.apply_locale();
+
+        let provider = MockProvider::default();
+        let api_base = provider.start();
+        let recorder = Arc::new(RequestRecorder::new(
+            Config::get_request_log_path().unwrap(),
+            config.record_requests,
+        ));
+        let state = ManagedState {
+            profile_clients: ProfileClients::from_config(&config, recorder.clone())
+                .unwrap()
+                .with_api_base(&api_base),
+            config,
+            recorder,
+            usage_log: Arc::new(UsageLog::new(Config::get_usage_log_path().unwrap())),
+            draft_store: Arc::new(DraftStore::new(Config::get_drafts_path().unwrap())),
+            sync_conflicts: Arc::new(SyncConflicts::new(
+                Config::get_sync_conflicts_path().unwrap(),
+            )),
+            window_layout: Arc::new(WindowLayout::new(
+                Config::get_window_layout_path().unwrap(),
+            )),
+            generation_journal: Arc::new(GenerationJournal::new(
+                Config::get_pending_generations_path().unwrap(),
+            )),
+            last_crash_report: None,
+            startup_links: Vec::new(),
+            startup_files: Vec::new(),
+        };
+        let app = state
+            .manage(tauri::test::mock_builder())
+            .invoke_handler(crate::invoke_handler())
+            .build(tauri::test::mock_context(tauri::test::noop_assets()))
+            .unwrap();
+        tauri::async_runtime::block_on(history_loader::load(app.handle()));
+        let window = app.get_window("main").unwrap();
+        Self {
+            app,
+            window,
+            provider,
+            dir,
+            invoked: Mutex::default(),
+            _exclusive: exclusive,
+        }
+    }
+
+    /// Where the default profile keeps its config and history.
+    pub fn dir(&self) -> &Path {
+        &self.dir.0
+    }
+
+    pub fn state<T: Send + Sync + 'static>(&self) -> tauri::State<'_, T> {
+        self.app.state::<T>()
+    }
+
+    /// Invokes `command` as the frontend would, returning its result or serialized error.
+    pub fn invoke(&self, command: &str, args: Value) -> Result<Value, Value> {
+        self.invoked.lock().unwrap().insert(command.to_string());
+        tauri::test::get_ipc_response(
+            &self.window,
+            InvokePayload {
+                cmd: command.to_string(),
+                tauri_module: None,
+                callback: CallbackFn(0),
+                error: CallbackFn(1),
+                inner: args,
+                invoke_key: Some(INVOKE_KEY.to_string()),
+            },
+        )
+    }
+
+    /// Invokes `command`, which is expected to succeed.
+    pub fn call<T: DeserializeOwned>(&self, command: &str, args: Value) -> T {
+        match self.invoke(command, args) {
+            Ok(value) => serde_json::from_value(value).unwrap(),
+            Err(error) => panic!("{} failed: {}", command, error),
+        }
+    }
+
+    /// Invokes `command`, which is expected to fail, returning the error's code.
+    pub fn fail(&self, command: &str, args: Value) -> String {
+        match self.invoke(command, args) {
+            Ok(value) => panic!("{} succeeded with {}", command, value),
+            Err(error) => error["code"].as_str().unwrap_or_default().to_string(),
+        }
+    }
+
+    /// The commands invoked through this harness so far.
+    pub fn invoked(&self) -> BTreeSet<String> {
+        self.invoked.lock().unwrap().clone()
+    }
+}
+
+/// Every command `main` registers.
+fn registered_commands() -> BTreeSet<String> {
+    let main = std::fs::read_to_string("src/main.rs").unwrap();
+    let handler = main.split("generate_handler![").nth(1).unwrap();
+    let handler = &handler[..handler.find(']').unwrap()];
+    handler
+        .split(',')
+        .filter_map(|item| item.trim().strip_prefix("commands::"))
+        .map(str::to_string)
+        .collect()
+}
+
+mod test {
+    use super::*;
+    use crate::{compression, tasks::TaskSupervisor, takeout};
+    use tauri::async_runtime::RwLock;
+
+    /// The id of the conversation's latest event of `kind`, e.g. "MessagePartial".
+    fn latest_event(conversation: &Value, kind: &str) -> String {
+        conversation["history"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|record| record["event"].get(kind).is_some())
+            .map(|record| record["id"].as_str().unwrap().to_string())
+            .unwrap()
+    }
+
+    fn contents(messages: &[Value]) -> Vec<&str> {
+        messages
+            .iter()
+            .map(|message| message["content"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_every_command() {
+        let harness = Harness::new();
+        let path = |name: &str| harness.dir().join(name).display().to_string();
+
+        // Settings
+        let actions: Vec<Value> = harness.call("list_app_actions", json!({}));
+        assert!(!actions.is_empty());
+        let appearance: Value = harness.call("get_appearance", json!({}));
+        harness.call::<()>("set_appearance", json!({ "appearance": appearance }));
+        let themes: Vec<String> = harness.call("list_code_themes", json!({}));
+        assert!(!themes.is_empty());
+        let locales: Vec<Value> = harness.call("list_locales", json!({}));
+        assert!(locales.iter().any(|locale| locale["code"] == "de"));
+        harness.call::<()>("set_locale", json!({ "locale": "de" }));
+        harness.call::<()>("set_locale", json!({ "locale": "en" }));
+        assert_eq!(harness.fail("set_locale", json!({ "locale": "xx" })), "LocaleUnknownFail");
+        let effective: Vec<Value> = harness.call("get_effective_config", json!({}));
+        assert!(!effective.is_empty());
+        let info: Value = harness.call("get_app_info", json!({}));
+        assert_eq!(info["version"], "0.1.0");
+        let keybindings: std::collections::HashMap<String, String> =
+            harness.call("get_keybindings", json!({}));
+        let action = keybindings.keys().next().unwrap().clone();
+        harness.call::<()>(
+            "set_keybinding",
+            json!({ "action": action, "shortcut": "Ctrl+Alt+Shift+K" }),
+        );
+        let keybindings: std::collections::HashMap<String, String> =
+            harness.call("get_keybindings", json!({}));
+        assert_eq!(keybindings[&action], "Ctrl+Alt+Shift+K");
+        harness.call::<()>("set_auto_tagging", json!({ "enabled": false }));
+        let models: Vec<String> = harness.call(
+            "set_models",
+            json!({ "model": " gpt-4o ", "fallback_models": ["gpt-4o-mini", "gpt-4o"] }),
+        );
+        assert_eq!(models, ["gpt-4o", "gpt-4o-mini"]);
+        let info: Value = harness.call("get_app_info", json!({}));
+        assert_eq!(info["model"], "gpt-4o");
+        harness.call::<()>("set_auto_update_check", json!({ "enabled": false }));
+        harness.call::<()>("enable_request_recording", json!({ "enabled": true }));
+
+        // Conversations and replies
+        let conversation: Value = harness.call("new_conversation", json!({}));
+        let id = conversation["id"].as_str().unwrap().to_string();
+        let stored: String = harness.call(
+            "set_conversation_title",
+            json!({ "conversation_id": id, "new_title": " Groceries\n\u{7}" }),
+        );
+        assert_eq!(stored, "Groceries");
+        let title: String = harness.call("get_conversation_title", json!({ "conversation_id": id }));
+        assert_eq!(title, "Groceries");
+        let error = harness
+            .invoke(
+                "set_conversation_title",
+                json!({ "conversation_id": id, "new_title": "a".repeat(201) }),
+            )
+            .unwrap_err();
+        assert_eq!(error["code"], "ArgumentInvalidFail");
+        assert_eq!(error["field"], "new_title");
+        assert_eq!(error["problem"], json!({ "kind": "too_long", "max_length": 200 }));
+        assert_eq!(
+            harness.fail("get_draft", json!({ "conversation_id": "not-an-id" })),
+            "ArgumentInvalidFail"
+        );
+        harness.call::<()>("save_draft", json!({ "conversation_id": id, "content": "Half a" }));
+        let draft: String = harness.call("get_draft", json!({ "conversation_id": id }));
+        assert_eq!(draft, "Half a");
+        let length: Value = harness.call(
+            "get_message_length_info",
+            json!({ "content": "What do we need?", "model": "gpt-4o" }),
+        );
+        assert_eq!(length["characters"], 16);
+        assert_eq!(length["words"], 4);
+        assert!(length["tokens"].as_u64().unwrap() > 0);
+        assert!(length["cost_usd"].as_f64().unwrap() > 0.0);
+        assert_eq!(length["context_window"], 128_000);
+        let catalog: Value = harness.call("get_model_catalog", json!({}));
+        assert_eq!(catalog, json!({ "fetched_at": null, "models": [] }));
+        let catalog: Value = harness.call("refresh_model_catalog", json!({}));
+        assert!(catalog["fetched_at"].is_number());
+        assert_eq!(catalog["models"][0]["id"], "gpt-4o");
+        assert_eq!(catalog["models"][0]["vision"], true);
+        assert_eq!(catalog["models"][1]["id"], MOCK_MODEL);
+        assert_eq!(catalog["models"][1]["context_window"], Value::Null);
+        let cached: Value = harness.call("get_model_catalog", json!({}));
+        assert_eq!(cached, catalog);
+        harness.call::<()>(
+            "new_conversation_user_message",
+            json!({ "conversation_id": id, "content": "What do we need?" }),
+        );
+        assert_eq!(
+            harness.fail(
+                "new_conversation_user_message",
+                json!({ "conversation_id": uuid::Uuid::new_v4(), "content": "Anyone there?" }),
+            ),
+            "ConversationNotFoundFail"
+        );
+        let messages: Vec<Value> =
+            harness.call("get_conversation_messages", json!({ "conversation_id": id }));
+        assert_eq!(contents(&messages), ["What do we need?", MOCK_REPLY]);
+        let draft: String = harness.call("get_draft", json!({ "conversation_id": id }));
+        assert_eq!(draft, "");
+        let unread: std::collections::HashMap<String, usize> =
+            harness.call("list_unread_counts", json!({}));
+        assert_eq!(unread[&id], 1);
+        harness.call::<()>("mark_conversation_read", json!({ "conversation_id": id }));
+        let unread: std::collections::HashMap<String, usize> =
+            harness.call("list_unread_counts", json!({}));
+        assert_eq!(unread[&id], 0);
+
+        harness.call::<()>(
+            "set_conversation_auto_respond",
+            json!({ "conversation_id": id, "enabled": false }),
+        );
+        harness.call::<()>(
+            "new_conversation_user_messages",
+            json!({ "conversation_id": id, "contents": ["Milk", "Eggs"] }),
+        );
+        harness.call::<()>("generate_response", json!({ "conversation_id": id }));
+        harness.provider.queue(MockReply::Fail);
+        assert_eq!(
+            harness.fail("generate_response", json!({ "conversation_id": id })),
+            "ConversationAIResponseFail"
+        );
+        harness.call::<()>("retry_failed_generation", json!({ "conversation_id": id }));
+        harness.provider.queue(MockReply::Interrupt);
+        assert_eq!(
+            harness.fail("generate_response", json!({ "conversation_id": id })),
+            "GenerationInterruptedFail"
+        );
+        let conversation: Value = harness.call("get_conversation", json!({ "conversation_id": id }));
+        let partial = latest_event(&conversation, "MessagePartial");
+        harness.call::<()>("continue_generation", json!({ "message_id": partial }));
+        let messages: Vec<Value> =
+            harness.call("get_conversation_messages", json!({ "conversation_id": id }));
+        assert_eq!(
+            contents(&messages),
+            [
+                "What do we need?",
+                MOCK_REPLY,
+                "Milk",
+                "Eggs",
+                MOCK_REPLY,
+                MOCK_REPLY,
+                &format!("Mock {}", MOCK_REPLY),
+            ]
+        );
+        let titles: std::collections::HashMap<String, String> =
+            harness.call("list_conversation_titles", json!({}));
+        assert_eq!(titles[&id], "Groceries");
+
+        // Generations interrupted by a crash
+        let pending: Vec<Value> = harness.call("list_pending_generations", json!({}));
+        assert!(pending.is_empty());
+        let interrupt = |prompt: &str| {
+            let journal = harness.state::<Arc<GenerationJournal>>();
+            journal
+                .begin(PendingGeneration {
+                    conversation_id: id.parse().unwrap(),
+                    prompt: prompt.to_string(),
+                    continue_partial: false,
+                    started_at: 0,
+                })
+                .unwrap();
+            // as though the app went down mid-reply and started again
+            journal.reopen(Config::get_pending_generations_path().unwrap());
+        };
+        interrupt("Eggs");
+        let pending: Vec<Value> = harness.call("list_pending_generations", json!({}));
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0]["conversation_id"], id.as_str());
+        assert_eq!(pending[0]["prompt"], "Eggs");
+        harness.call::<()>("discard_pending_generation", json!({ "conversation_id": id }));
+        assert_eq!(
+            harness.fail("discard_pending_generation", json!({ "conversation_id": id })),
+            "PendingGenerationNotFoundFail"
+        );
+        interrupt("Eggs");
+        let requests = harness.provider.requests().len();
+        // The reply to "Eggs" was saved before the crash, so there's nothing to generate.
+        harness.call::<()>("resume_pending_generation", json!({ "conversation_id": id }));
+        assert_eq!(harness.provider.requests().len(), requests);
+        assert_eq!(
+            harness.fail("resume_pending_generation", json!({ "conversation_id": id })),
+            "PendingGenerationNotFoundFail"
+        );
+        let pending: Vec<Value> = harness.call("list_pending_generations", json!({}));
+        assert!(pending.is_empty());
+
+        // Guardrails
+        assert_eq!(
+            harness.fail(
+                "set_guardrails",
+                json!({ "guardrails": { "banned_phrases": [], "banned_patterns": ["("], "action": "flag" } }),
+            ),
+            "GuardrailPatternInvalidFail"
+        );
+        let guardrails: Value = harness.call(
+            "set_guardrails",
+            json!({ "guardrails": { "banned_phrases": ["REPLY", " "], "banned_patterns": [], "action": "redact" } }),
+        );
+        assert_eq!(guardrails["banned_phrases"], json!(["REPLY"]));
+        harness.call::<()>("generate_response", json!({ "conversation_id": id }));
+        let messages: Vec<Value> =
+            harness.call("get_conversation_messages", json!({ "conversation_id": id }));
+        let filtered = messages.last().unwrap();
+        assert_eq!(filtered["content"], "Mock [redacted]");
+        assert_eq!(filtered["redacted"], true);
+        assert_eq!(
+            filtered["guardrail_hits"],
+            json!([{ "rule": "REPLY", "count": 1 }])
+        );
+        assert_eq!(messages[1]["guardrail_hits"], json!([]));
+        harness.call::<Value>(
+            "set_guardrails",
+            json!({ "guardrails": { "banned_phrases": [], "banned_patterns": [], "action": "flag" } }),
+        );
+        let guardrails: Value = harness.call("get_guardrails", json!({}));
+        assert_eq!(guardrails["banned_phrases"], json!([]));
+
+        // Conversation settings
+        harness.call::<()>(
+            "set_conversation_stop_sequences",
+            json!({ "conversation_id": id, "stop_sequences": ["END"] }),
+        );
+        harness.call::<()>(
+            "set_conversation_assistant_prefix",
+            json!({ "conversation_id": id, "prefix": "Sure: " }),
+        );
+        harness.call::<()>(
+            "set_conversation_language",
+            json!({ "conversation_id": id, "language": "German" }),
+        );
+        harness.call::<()>(
+            "set_conversation_system_prompt",
+            json!({ "conversation_id": id, "system_prompt": " Be brief. " }),
+        );
+        harness.call::<()>(
+            "set_conversation_model",
+            json!({ "conversation_id": id, "model": "" }),
+        );
+        let presets: Vec<Value> = harness.call("list_style_presets", json!({}));
+        assert_eq!(presets.len(), 4);
+        let pirate = json!({
+            "name": "Pirate",
+            "instruction": "Talk like a pirate.",
+            "temperature": 1.0,
+            "max_tokens": null,
+        });
+        harness.call::<Value>("create_style_preset", json!({ "preset": pirate }));
+        assert_eq!(
+            harness.fail("create_style_preset", json!({ "preset": pirate })),
+            "StylePresetExistsFail"
+        );
+        let too_hot = json!({ "name": "Pirate", "instruction": "Arr.", "temperature": 3.0, "max_tokens": null });
+        assert_eq!(
+            harness.fail("update_style_preset", json!({ "name": "pirate", "preset": too_hot })),
+            "StylePresetInvalidFail"
+        );
+        harness.call::<()>("delete_style_preset", json!({ "name": "pirate" }));
+        assert_eq!(
+            harness.fail("delete_style_preset", json!({ "name": "pirate" })),
+            "StylePresetNotFoundFail"
+        );
+        assert_eq!(
+            harness.fail("apply_preset", json!({ "conversation_id": id, "preset": "pirate" })),
+            "StylePresetNotFoundFail"
+        );
+        harness.call::<()>("apply_preset", json!({ "conversation_id": id, "preset": "Concise" }));
+        let ledger = json!({ "term": "Ledger", "definition": "the balances service" });
+        assert_eq!(
+            harness.fail(
+                "set_conversation_glossary",
+                json!({ "conversation_id": id, "glossary": [ledger, ledger] }),
+            ),
+            "GlossaryInvalidFail"
+        );
+        harness.call::<()>(
+            "set_conversation_glossary",
+            json!({ "conversation_id": id, "glossary": [ledger] }),
+        );
+        let settings: Value =
+            harness.call("get_conversation_settings", json!({ "conversation_id": id }));
+        assert_eq!(
+            settings,
+            json!({
+                "auto_respond": false,
+                "stop_sequences": ["END"],
+                "assistant_prefix": "Sure: ",
+                "language": "German",
+                "system_prompt": "Be brief.",
+                "model": "",
+                "style_preset": "concise",
+                "glossary": [{ "term": "Ledger", "definition": "the balances service" }],
+                "repository_id": null,
+            })
+        );
+
+        // Repositories
+        let repo = harness.dir().join("repo");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(repo.join("src")).unwrap();
+        std::fs::write(repo.join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(repo.join("src/main.rs"), "fn main() { restock(); }\n").unwrap();
+        std::fs::write(repo.join("build.log"), "ignored").unwrap();
+        assert_eq!(
+            harness.fail("index_repository", json!({ "path": path("") })),
+            "RepositoryInvalidFail"
+        );
+        let repository: Value =
+            harness.call("index_repository", json!({ "path": repo.display().to_string() }));
+        assert_eq!(repository["name"], "repo");
+        // build.log is ignored and .gitignore hidden
+        assert_eq!(repository["file_count"], 1);
+        let reindexed: Value =
+            harness.call("index_repository", json!({ "path": repo.display().to_string() }));
+        assert_eq!(reindexed["id"], repository["id"]);
+        let repositories: Vec<Value> = harness.call("list_repositories", json!({}));
+        assert_eq!(repositories.len(), 1);
+        harness.call::<()>(
+            "link_conversation_repository",
+            json!({ "conversation_id": id, "repository_id": repository["id"] }),
+        );
+        harness.call::<()>("generate_response", json!({ "conversation_id": id }));
+        let requests = harness.provider.requests();
+        let reply_request = requests.iter().rev().find(|request| request["messages"].is_array());
+        let system = reply_request.unwrap()["messages"][0]["content"].as_str().unwrap();
+        assert!(system.contains("src/main.rs (lines 1-1):\n```\nfn main() { restock(); }\n```"));
+        assert!(!system.contains("build.log"));
+        harness.call::<()>("remove_repository", json!({ "repository_id": repository["id"] }));
+        assert_eq!(
+            harness.fail(
+                "link_conversation_repository",
+                json!({ "conversation_id": id, "repository_id": repository["id"] }),
+            ),
+            "RepositoryNotFoundFail"
+        );
+        harness.call::<()>(
+            "link_conversation_repository",
+            json!({ "conversation_id": id, "repository_id": null }),
+        );
+        let stats: Value =
+            harness.call("get_conversation_stats", json!({ "conversation_id": id }));
+        assert_eq!(stats["long_read"], false);
+        let tags: Vec<String> = harness.call(
+            "set_conversation_tags",
+            json!({ "conversation_id": id, "tags": ["Food", "food"] }),
+        );
+        let tags_by_id: std::collections::HashMap<String, Vec<String>> =
+            harness.call("list_conversation_tags", json!({}));
+        assert_eq!(tags_by_id[&id], tags);
+        harness.call::<()>("lock_conversation", json!({ "conversation_id": id }));
+        assert_eq!(
+            harness.fail(
+                "new_conversation_user_message",
+                json!({ "conversation_id": id, "content": "Bread" }),
+            ),
+            "ConversationLockedFail"
+        );
+        assert_eq!(
+            harness.fail(
+                "set_conversation_system_prompt",
+                json!({ "conversation_id": id, "system_prompt": "Talk like a pirate." }),
+            ),
+            "ConversationLockedFail"
+        );
+        harness.call::<()>("unlock_conversation", json!({ "conversation_id": id }));
+        harness.call::<()>(
+            "set_conversation_archived",
+            json!({ "conversation_id": id, "archived": true }),
+        );
+        harness.call::<()>(
+            "set_conversation_archived",
+            json!({ "conversation_id": id, "archived": false }),
+        );
+
+        // Messages
+        let message = messages[1]["id"].as_str().unwrap().to_string();
+        harness.call::<()>(
+            "bookmark_message",
+            json!({ "conversation_id": id, "message_id": message }),
+        );
+        let bookmarks: Vec<Value> = harness.call("list_bookmarks", json!({}));
+        assert_eq!(bookmarks.len(), 1);
+        harness.call::<()>(
+            "unbookmark_message",
+            json!({ "conversation_id": id, "message_id": message }),
+        );
+        let bookmarks: Vec<Value> = harness.call("list_bookmarks", json!({}));
+        assert!(bookmarks.is_empty());
+        harness.call::<()>(
+            "set_message_pinned_context",
+            json!({ "message_id": message, "pinned_context": true }),
+        );
+        let pinned: Vec<Value> =
+            harness.call("get_conversation_messages", json!({ "conversation_id": id }));
+        assert_eq!(pinned[1]["pinned_context"], true);
+        assert_eq!(pinned[0]["pinned_context"], false);
+        let citations: Vec<Value> = harness.call(
+            "get_message_citations",
+            json!({ "message_id": message, "fetch_titles": false }),
+        );
+        assert!(citations.is_empty());
+        assert_eq!(
+            harness.fail(
+                "get_message_citations",
+                json!({ "message_id": uuid::Uuid::new_v4(), "fetch_titles": false }),
+            ),
+            "MessageNotFoundFail"
+        );
+        assert_eq!(
+            harness.fail("get_link_preview", json!({ "url": "ftp://example.com" })),
+            "ArgumentInvalidFail"
+        );
+        harness.call::<()>(
+            "annotate_message",
+            json!({ "message_id": message, "note": "Check the fridge" }),
+        );
+        harness.call::<()>(
+            "rate_message",
+            json!({ "message_id": message, "rating": "up", "comment": "Helpful" }),
+        );
+        let feedback: Vec<Value> = harness.call("get_feedback_report", json!({}));
+        assert_eq!(feedback[0]["model"], MOCK_MODEL);
+        let translated: String = harness.call(
+            "translate_message",
+            json!({ "message_id": message, "target_lang": "de" }),
+        );
+        assert_eq!(translated, MOCK_REPLY);
+        let extracted: Vec<Value> =
+            harness.call("extract_action_items", json!({ "conversation_id": id }));
+        assert_eq!(extracted[0]["text"], MOCK_REPLY);
+        let task_id = extracted[0]["id"].as_str().unwrap().to_string();
+        let task: Value =
+            harness.call("complete_task", json!({ "task_id": task_id, "done": true }));
+        assert!(task["done_at"].is_number());
+        let tasks: Vec<Value> = harness.call("list_tasks", json!({ "include_done": false }));
+        assert!(tasks.is_empty());
+        let tasks: Vec<Value> = harness.call("list_tasks", json!({ "include_done": true }));
+        assert_eq!(tasks.len(), 1);
+        let task: Value = harness.call(
+            "set_task_reminder",
+            json!({ "task_id": task_id, "remind_at": 4_102_444_800i64 }),
+        );
+        assert_eq!(task["remind_at"], 4_102_444_800i64);
+        let task: Value =
+            harness.call("snooze_task", json!({ "task_id": task_id, "minutes": 10 }));
+        assert!(task["remind_at"].as_i64().unwrap() < 4_102_444_800);
+        let task: Value = harness.call("cancel_task_reminder", json!({ "task_id": task_id }));
+        assert!(task["remind_at"].is_null());
+        assert_eq!(
+            harness.fail(
+                "complete_task",
+                json!({ "task_id": uuid::Uuid::new_v4(), "done": true }),
+            ),
+            "ActionItemNotFoundFail"
+        );
+        assert_eq!(
+            harness.fail(
+                "explain_code",
+                json!({ "conversation_id": id, "content": " ", "language": "Rust" }),
+            ),
+            "ArgumentInvalidFail"
+        );
+        let before: Vec<Value> =
+            harness.call("get_conversation_messages", json!({ "conversation_id": id }));
+        let explained: String = harness.call(
+            "explain_code",
+            json!({ "conversation_id": id, "content": "x = 1\n".repeat(3000), "language": "Python" }),
+        );
+        assert_eq!(explained, id);
+        let messages: Vec<Value> =
+            harness.call("get_conversation_messages", json!({ "conversation_id": id }));
+        // two parts, each asked and answered in turn
+        assert_eq!(messages.len(), before.len() + 4);
+        assert!(messages[before.len() + 2]["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("This is part 2 of 2 of some Python code."));
+        assert_eq!(messages.last().unwrap()["author"], "assistant");
+        let diff = "diff --git a/shopping.txt b/shopping.txt\n--- a/shopping.txt\n\
+                    +++ b/shopping.txt\n@@ -1 +1,2 @@\n milk\n+eggs\n";
+        let summary: Value = harness.call("summarize_diff", json!({ "path_or_text": diff }));
+        assert_eq!(summary["summary"], MOCK_REPLY);
+        assert_eq!(summary["files"][0]["path"], "shopping.txt");
+        assert_eq!(summary["files"][0]["additions"], 1);
+        let requests = harness.provider.requests();
+        let summary_request = requests.last().unwrap()["messages"][1]["content"].clone();
+        assert!(summary_request
+            .as_str()
+            .unwrap()
+            .starts_with("File: shopping.txt (modified, +1 -0)"));
+        std::fs::write(harness.dir().join("change.patch"), diff).unwrap();
+        let summary: Value =
+            harness.call("summarize_diff", json!({ "path_or_text": path("change.patch") }));
+        assert_eq!(summary["files"].as_array().unwrap().len(), 1);
+        std::fs::write(harness.dir().join("empty.diff"), "").unwrap();
+        assert_eq!(
+            harness.fail("summarize_diff", json!({ "path_or_text": path("empty.diff") })),
+            "DiffInvalidFail"
+        );
+        assert_eq!(
+            harness.fail("summarize_diff", json!({ "path_or_text": "change.txt" })),
+            "ArgumentInvalidFail"
+        );
+        // The conversation is checked before the microphone is opened.
+        assert_eq!(
+            harness.fail(
+                "start_dictation",
+                json!({ "conversation_id": uuid::Uuid::new_v4(), "language": null }),
+            ),
+            "ConversationNotFoundFail"
+        );
+        assert!(!harness.call::<bool>("stop_dictation", json!({})));
+        // Test machines may have no display to list.
+        match harness.invoke("list_capture_targets", json!({})) {
+            Ok(targets) => assert!(targets.is_array()),
+            Err(error) => assert_eq!(error["code"], "ScreenshotFail"),
+        }
+        let playback: Value = harness.call("get_playback_state", json!({}));
+        assert_eq!(playback["state"], "stopped");
+        assert_eq!(playback["speed"], 1.0);
+        for command in ["pause_playback", "skip_message", "stop_playback"] {
+            let playback: Value = harness.call(command, json!({}));
+            assert_eq!(playback["state"], "stopped");
+        }
+        assert_eq!(
+            harness.fail("set_playback_speed", json!({ "speed": 10.0 })),
+            "PlaybackSpeedInvalidFail"
+        );
+        let playback: Value = harness.call("set_playback_speed", json!({ "speed": 1.5 }));
+        assert_eq!(playback["speed"], 1.5);
+        harness.call::<()>("set_window_conversation", json!({ "conversation_id": id }));
+        let window: Value = harness.call("get_window_state", json!({}));
+        assert_eq!(window["label"], "main");
+        assert_eq!(window["conversation_id"], id.as_str());
+        let label: String = harness.call("open_window", json!({ "conversation_id": id }));
+        let layout = harness.state::<Arc<WindowLayout>>().windows();
+        assert_eq!(layout[1].label, label);
+        assert_eq!(layout[1].conversation_id.unwrap().to_string(), id);
+        assert_eq!(
+            harness.fail("open_window", json!({ "conversation_id": uuid::Uuid::new_v4() })),
+            "ConversationNotFoundFail"
+        );
+        assert_eq!(messages[1]["note"], "Check the fridge");
+        assert_eq!(messages[1]["rating"], "up");
+        assert_eq!(messages[1]["translations"].as_array().unwrap().len(), 1);
+
+        // Search
+        let hits: Vec<Value> =
+            harness.call("search_messages", json!({ "query": "fridge", "language": "" }));
+        assert_eq!(hits[0]["message_id"], message.as_str());
+        let matches: Vec<Value> = harness.call(
+            "search_in_conversation",
+            json!({ "conversation_id": id, "query": "M.lk", "regex": true }),
+        );
+        assert_eq!(matches.len(), 1);
+        let found: Vec<Value> =
+            harness.call("fuzzy_find_conversations", json!({ "query": "Grocer", "limit": 5 }));
+        assert_eq!(found[0]["conversation_id"], id.as_str());
+        let results: Vec<Value> = harness.call("quick_search", json!({ "query": "Grocer" }));
+        assert_eq!(results[0]["kind"], "conversation");
+        assert_eq!(results[0]["id"], id.as_str());
+        let results: Vec<Value> = harness.call("quick_search", json!({ "query": "new conversation" }));
+        assert!(results
+            .iter()
+            .any(|result| result["kind"] == "command" && result["id"] == "new_conversation"));
+
+        // API key profiles
+        let api_key_profiles: Vec<String> = harness.call("list_api_key_profiles", json!({}));
+        assert_eq!(api_key_profiles, ["default"]);
+        harness.call::<()>("set_active_profile", json!({ "profile_name": "default" }));
+        harness.call::<()>(
+            "pin_conversation_profile",
+            json!({ "conversation_id": id, "profile_name": "default" }),
+        );
+        harness.call::<()>("unpin_conversation_profile", json!({ "conversation_id": id }));
+
+        // Topics, usage and retention
+        let clusters: Vec<Value> = harness.call("cluster_conversations", json!({ "k": 1 }));
+        assert_eq!(clusters.len(), 1);
+        let cache: Value = harness.call("get_embedding_cache_stats", json!({}));
+        assert_eq!(cache["entries"], 1);
+        harness.call::<()>("clear_embedding_cache", json!({}));
+        let report: Value = harness.call("get_usage_report", json!({ "period": "all" }));
+        assert_eq!(report["tokens_per_model"][0]["model"], MOCK_MODEL);
+        assert_eq!(report["tokens_per_model"][0]["cost_usd"], 0.0);
+        let pricing: Vec<Value> = harness.call(
+            "set_model_pricing",
+            json!({ "model": MOCK_MODEL, "price": { "prompt": 1000.0, "completion": 2000.0 } }),
+        );
+        let mock = pricing.iter().find(|row| row["prefix"] == MOCK_MODEL).unwrap();
+        assert_eq!(mock["overridden"], true);
+        let report: Value = harness.call("get_usage_report", json!({ "period": "all" }));
+        assert!(report["tokens_per_model"][0]["cost_usd"].as_f64().unwrap() > 0.0);
+        let error = harness
+            .invoke(
+                "set_model_pricing",
+                json!({ "model": "gpt-4o", "price": { "prompt": -1.0, "completion": 10.0 } }),
+            )
+            .unwrap_err();
+        assert_eq!(error["field"], "prompt");
+        assert_eq!(error["problem"], json!({ "kind": "negative" }));
+        harness.call::<Vec<Value>>(
+            "set_model_pricing",
+            json!({ "model": MOCK_MODEL, "price": null }),
+        );
+        let pricing: Vec<Value> = harness.call("get_model_pricing", json!({}));
+        assert!(pricing.iter().all(|row| row["overridden"] == false));
+        assert_eq!(report["failed_generations"][0]["recovered"], true);
+        let candidates: Vec<Value> = harness.call("preview_retention", json!({}));
+        assert!(candidates.is_empty());
+        let removed: usize = harness.call("enforce_retention", json!({}));
+        assert_eq!(removed, 0);
+
+        // Exports and imports
+        harness.call::<()>(
+            "export_conversation_pdf",
+            json!({ "conversation_id": id, "path": path("groceries.pdf") }),
+        );
+        assert!(harness.dir().join("groceries.pdf").exists());
+        assert_eq!(
+            harness.fail(
+                "export_conversation_pdf",
+                json!({ "conversation_id": id, "path": path("groceries.exe") }),
+            ),
+            "ArgumentInvalidFail"
+        );
+        harness.call::<()>(
+            "publish_conversation_html",
+            json!({ "conversation_id": id, "path": path("groceries.html") }),
+        );
+        assert!(harness.dir().join("groceries.html").exists());
+        let examples: usize = harness.call(
+            "export_finetune_dataset",
+            json!({
+                "conversation_ids": [id],
+                "path": path("finetune.jsonl"),
+                "options": {
+                    "include_system_messages": false,
+                    "system_prompt": null,
+                    "swap_user_assistant": false,
+                },
+            }),
+        );
+        assert_eq!(examples, 1);
+        let exported: usize = harness.call("export_all_data", json!({ "target_path": path("takeout.zip") }));
+        assert!(exported > 0);
+        let recorded: usize =
+            harness.call("export_request_log", json!({ "target_path": path("requests.jsonl") }));
+        assert_eq!(recorded, harness.provider.requests().len());
+        std::fs::write(
+            harness.dir().join("import.json"),
+            json!([{ "role": "user", "content": "Imported" }]).to_string(),
+        )
+        .unwrap();
+        let format: String = harness.call("detect_import_format", json!({ "path": path("import.json") }));
+        assert_eq!(format, "role_content");
+        let imported: Vec<String> =
+            harness.call("import_conversations", json!({ "path": path("import.json") }));
+        assert_eq!(imported.len(), 1);
+        harness.call::<()>(
+            "export_conversation_archive",
+            json!({ "conversation_id": id, "path": path("groceries.ehya") }),
+        );
+        assert_eq!(
+            harness.fail("import_conversation_archive", json!({ "path": path("groceries.ehya") })),
+            "ArchiveConversationExistsFail"
+        );
+        let bundle: String = harness.call(
+            "share_conversation_encrypted",
+            json!({ "conversation_id": id, "passphrase": "correct horse" }),
+        );
+        assert!(bundle.ends_with(".ehyas"));
+        assert_eq!(
+            harness.fail(
+                "import_shared_bundle",
+                json!({ "path": bundle, "passphrase": "wrong horse" }),
+            ),
+            "SharedBundlePassphraseFail"
+        );
+        assert_eq!(
+            harness.fail(
+                "import_shared_bundle",
+                json!({ "path": bundle, "passphrase": "correct horse" }),
+            ),
+            "ArchiveConversationExistsFail"
+        );
+
+        // Bulk operations
+        let scratch: Value = harness.call("new_conversation", json!({}));
+        let scratch = scratch["id"].as_str().unwrap().to_string();
+        let tags_by_id: std::collections::HashMap<String, Vec<String>> = harness.call(
+            "bulk_tag",
+            json!({ "conversation_ids": [id, scratch], "add": ["Errands"], "remove": [] }),
+        );
+        assert!(tags_by_id[&id].contains(&"errands".to_string()));
+        assert_eq!(tags_by_id[&scratch], ["errands"]);
+        let tags_by_id: std::collections::HashMap<String, Vec<String>> = harness.call(
+            "bulk_tag",
+            json!({ "conversation_ids": [id], "add": [], "remove": ["errands"] }),
+        );
+        assert!(!tags_by_id[&id].contains(&"errands".to_string()));
+        let archived: usize = harness.call(
+            "bulk_archive",
+            json!({ "conversation_ids": [scratch, scratch], "archived": true }),
+        );
+        assert_eq!(archived, 1);
+        let archived: usize = harness.call(
+            "bulk_archive",
+            json!({ "conversation_ids": [scratch], "archived": true }),
+        );
+        assert_eq!(archived, 0);
+        // Having no messages, it fails before the speakers are opened.
+        assert_eq!(
+            harness.fail(
+                "play_conversation",
+                json!({ "conversation_id": scratch, "from_message_id": null }),
+            ),
+            "PlaybackEmptyFail"
+        );
+        let exported: usize = harness.call(
+            "bulk_export",
+            json!({ "conversation_ids": [id, scratch], "path": path("bundle.zip") }),
+        );
+        assert_eq!(exported, 2);
+        assert_eq!(
+            harness.fail(
+                "bulk_delete",
+                json!({ "conversation_ids": [scratch, "not-an-id"] }),
+            ),
+            "ArgumentInvalidFail"
+        );
+        harness.call::<()>("lock_conversation", json!({ "conversation_id": scratch }));
+        assert_eq!(
+            harness.fail("bulk_delete", json!({ "conversation_ids": [scratch] })),
+            "ConversationLockedFail"
+        );
+        harness.call::<()>("unlock_conversation", json!({ "conversation_id": scratch }));
+        let titles: std::collections::HashMap<String, String> =
+            harness.call("list_conversation_titles", json!({}));
+        assert!(titles.contains_key(&scratch));
+        let deleted: usize = harness.call("bulk_delete", json!({ "conversation_ids": [scratch] }));
+        assert_eq!(deleted, 1);
+        let titles: std::collections::HashMap<String, String> =
+            harness.call("list_conversation_titles", json!({}));
+        assert!(!titles.contains_key(&scratch));
+
+        // Trash
+        let trashed: Vec<Value> = harness.call("list_trashed_conversations", json!({}));
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0]["conversation_id"], scratch);
+        assert_eq!(
+            trashed[0]["purge_at"].as_i64().unwrap() - trashed[0]["deleted_at"].as_i64().unwrap(),
+            30 * 24 * 60 * 60
+        );
+        harness.call::<()>("restore_conversation", json!({ "conversation_id": scratch }));
+        let tags_by_id: std::collections::HashMap<String, Vec<String>> =
+            harness.call("list_conversation_tags", json!({}));
+        assert_eq!(tags_by_id[&scratch], ["errands"]);
+        assert_eq!(
+            harness.fail("restore_conversation", json!({ "conversation_id": scratch })),
+            "ConversationNotFoundFail"
+        );
+        harness.call::<usize>("bulk_delete", json!({ "conversation_ids": [scratch] }));
+        let emptied: usize = harness.call("empty_trash", json!({}));
+        assert_eq!(emptied, 1);
+        let trashed: Vec<Value> = harness.call("list_trashed_conversations", json!({}));
+        assert!(trashed.is_empty());
+
+        // Templates
+        let template = json!({
+            "name": "Standup",
+            "title_pattern": "Standup {date}",
+            "system_prompt": "",
+            "model": "",
+            "auto_respond": false,
+            "stop_sequences": [],
+            "assistant_prefix": "",
+            "language": "",
+            "starter_messages": [{ "author": "user", "content": "Yesterday I" }],
+        });
+        let created: Value = harness.call("create_template", json!({ "template": template }));
+        let template_id = created["id"].as_str().unwrap().to_string();
+        let mut renamed = template.clone();
+        renamed["name"] = json!("Daily standup");
+        harness.call::<Value>(
+            "update_template",
+            json!({ "template_id": template_id, "template": renamed }),
+        );
+        let templates: Vec<Value> = harness.call("list_templates", json!({}));
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0]["template"]["name"], "Daily standup");
+        let mut unnamed = template.clone();
+        unnamed["name"] = json!(" ");
+        assert_eq!(
+            harness.fail("create_template", json!({ "template": unnamed })),
+            "ArgumentInvalidFail"
+        );
+        let from_template: Value = harness.call(
+            "new_conversation_from_template",
+            json!({ "template_id": template_id }),
+        );
+        let from_template = from_template["id"].as_str().unwrap().to_string();
+        let messages: Vec<Value> = harness.call(
+            "get_conversation_messages",
+            json!({ "conversation_id": from_template }),
+        );
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["content"], "Yesterday I");
+        let settings: Value = harness.call(
+            "get_conversation_settings",
+            json!({ "conversation_id": from_template }),
+        );
+        assert_eq!(settings["auto_respond"], false);
+        harness.call::<()>("delete_template", json!({ "template_id": template_id }));
+        assert_eq!(
+            harness.fail(
+                "new_conversation_from_template",
+                json!({ "template_id": template_id })
+            ),
+            "TemplateNotFoundFail"
+        );
+        harness.call::<usize>("bulk_delete", json!({ "conversation_ids": [from_template] }));
+        harness.call::<usize>("empty_trash", json!({}));
+
+        // Storage and integrity
+        harness.call::<()>("set_history_compression", json!({ "enabled": true }));
+        let history = harness.dir().join("conversations.json");
+        assert!(compression::is_compressed(&history).unwrap());
+        harness.call::<()>("set_history_compression", json!({ "enabled": false }));
+        assert!(!compression::is_compressed(&history).unwrap());
+        let usage: Vec<Value> = harness.call("get_storage_report", json!({}));
+        assert!(usage.iter().any(|usage| usage["category"] == "conversations"));
+        harness.call::<u64>("clean_storage", json!({ "categories": ["logs"] }));
+        assert_eq!(
+            harness.fail("clean_storage", json!({ "categories": ["conversations"] })),
+            "StorageCategoryInvalidFail"
+        );
+        let integrity: Value = harness.call("verify_history_integrity", json!({ "repair": false }));
+        assert_eq!(integrity["issues"], json!([]));
+        assert_eq!(integrity["conversations"], 2);
+        let recoveries: Vec<Value> = harness.call("get_history_recovery_report", json!({}));
+        assert!(recoveries.is_empty());
+        std::fs::create_dir(harness.dir().join("moved")).unwrap();
+        harness.call::<()>(
+            "set_history_location",
+            json!({ "new_path": path("moved/conversations.json") }),
+        );
+        assert!(harness.dir().join("moved/conversations.json").exists());
+
+        // Sync
+        assert_eq!(harness.fail("sync_now", json!({})), "SyncNotConfiguredFail");
+        tauri::async_runtime::block_on(async {
+            harness.state::<RwLock<Config>>().write().await.sync.backend =
+                Some(crate::sync::SyncBackendConfig::Folder { path: path("remote") });
+        });
+        harness.call::<()>("sync_now", json!({}));
+        assert!(harness.dir().join("remote").exists());
+        let conflicts: Vec<String> = harness.call("list_sync_conflicts", json!({}));
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            harness.fail(
+                "resolve_sync_conflict",
+                json!({ "conversation_id": id, "strategy": "keep_local" }),
+            ),
+            "SyncConflictNotFoundFail"
+        );
+
+        // Network checks, through a proxy that refuses connections
+        tauri::async_runtime::block_on(async {
+            harness.state::<RwLock<Config>>().write().await.proxy.url =
+                Some("http://127.0.0.1:9".to_string());
+        });
+        let proxy: Value = harness.call("test_proxy", json!({}));
+        assert_eq!(proxy["reachable"], false);
+        assert!(harness.invoke("check_for_updates", json!({})).is_err());
+
+        // Background tasks, crashes and deep links
+        let task = harness
+            .state::<TaskSupervisor>()
+            .spawn("idle", std::future::pending::<()>());
+        let tasks: Vec<Value> = harness.call("list_background_tasks", json!({}));
+        assert!(tasks.iter().any(|listed| listed["id"] == task.to_string()));
+        harness.call::<()>("cancel_task", json!({ "id": task.to_string() }));
+        assert_eq!(harness.fail("cancel_task", json!({ "id": task.to_string() })), "TaskNotFoundFail");
+        let crashes: Vec<Value> = harness.call("get_last_crash_report", json!({}));
+        assert!(crashes.is_empty());
+        harness.call::<()>("open_deep_link", json!({ "url": "ehyaioess://new" }));
+        let titles: std::collections::HashMap<String, String> =
+            harness.call("list_conversation_titles", json!({}));
+        assert_eq!(titles.len(), 3);
+        let pending: Vec<String> = harness.call("take_pending_deep_links", json!({}));
+        assert!(pending.is_empty());
+        let pending: Vec<String> = harness.call("take_pending_opened_files", json!({}));
+        assert!(pending.is_empty());
+        std::fs::write(harness.dir().join("notes.txt"), "Pick up the dry cleaning").unwrap();
+        let opened: Value = harness.call("handle_opened_file", json!({ "path": path("notes.txt") }));
+        assert_eq!(opened["imported"], false);
+        let draft: String =
+            harness.call("get_draft", json!({ "conversation_id": opened["conversation_id"] }));
+        assert_eq!(draft, "Pick up the dry cleaning");
+        std::fs::write(harness.dir().join("chat.md"), "# Trip\n\n## User\n\nWhere to?\n").unwrap();
+        let opened: Value = harness.call("handle_opened_file", json!({ "path": path("chat.md") }));
+        assert_eq!(opened["imported"], true);
+        assert_eq!(
+            harness.fail("handle_opened_file", json!({ "path": path("photo.png") })),
+            "ArgumentInvalidFail"
+        );
+        let titles: std::collections::HashMap<String, String> =
+            harness.call("list_conversation_titles", json!({}));
+        assert_eq!(titles.len(), 5);
+
+        // Workspace profiles
+        harness.call::<()>("create_profile", json!({ "name": "work", "openai_api_key": "sk-work" }));
+        let profiles: Vec<Value> = harness.call("list_profiles", json!({}));
+        assert_eq!(profiles.len(), 2);
+        harness.call::<()>("switch_profile", json!({ "name": "work" }));
+        let titles: std::collections::HashMap<String, String> =
+            harness.call("list_conversation_titles", json!({}));
+        assert!(titles.is_empty());
+        harness.call::<()>("switch_profile", json!({ "name": "default" }));
+        let titles: std::collections::HashMap<String, String> =
+            harness.call("list_conversation_titles", json!({}));
+        assert_eq!(titles[&id], "Groceries");
+
+        // Read-only mode and deleting everything
+        harness.call::<()>("set_read_only", json!({ "enabled": true }));
+        assert_eq!(
+            harness.fail(
+                "set_conversation_title",
+                json!({ "conversation_id": id, "new_title": "Shopping" }),
+            ),
+            "ReadOnlyModeFail"
+        );
+        assert_eq!(
+            harness.fail(
+                "capture_screenshot",
+                json!({ "region": { "kind": "screen", "id": null } }),
+            ),
+            "ReadOnlyModeFail"
+        );
+        assert_eq!(
+            harness.fail("start_dictation", json!({ "conversation_id": id, "language": "en" })),
+            "ReadOnlyModeFail"
+        );
+        assert_eq!(
+            harness.fail(
+                "play_conversation",
+                json!({ "conversation_id": id, "from_message_id": null }),
+            ),
+            "ReadOnlyModeFail"
+        );
+        assert_eq!(
+            harness.fail("resume_pending_generation", json!({ "conversation_id": id })),
+            "ReadOnlyModeFail"
+        );
+        harness.call::<()>("set_read_only", json!({ "enabled": false }));
+        assert_eq!(
+            harness.fail("delete_all_data", json!({ "confirmation": "yes" })),
+            "DeleteConfirmationFail"
+        );
+        harness.call::<()>(
+            "delete_all_data",
+            json!({ "confirmation": takeout::DELETE_ALL_DATA_CONFIRMATION }),
+        );
+        let titles: std::collections::HashMap<String, String> =
+            harness.call("list_conversation_titles", json!({}));
+        assert!(titles.is_empty());
+
+        let missed: Vec<_> = registered_commands()
+            .difference(&harness.invoked())
+            .cloned()
+            .collect();
+        assert!(missed.is_empty(), "Commands not exercised: {:?}", missed);
+    }
+
+    #[test]
+    fn test_model_fallback() {
+        let harness = Harness::new();
+        harness.call::<Vec<String>>(
+            "set_models",
+            json!({ "model": "gpt-5", "fallback_models": ["gpt-4o", "gpt-4o-mini"] }),
+        );
+        let conversation: Value = harness.call("new_conversation", json!({}));
+        let id = conversation["id"].as_str().unwrap().to_string();
+        harness.provider.queue(MockReply::ModelNotFound);
+        harness.call::<()>(
+            "new_conversation_user_message",
+            json!({ "conversation_id": id, "content": "Anyone there?" }),
+        );
+        let requested: Vec<Value> = harness
+            .provider
+            .requests()
+            .iter()
+            .map(|request| request["model"].clone())
+            .collect();
+        assert_eq!(requested, ["gpt-5", "gpt-4o"]);
+        // The model the provider says answered is kept with the reply.
+        let conversation: Value = harness.call("get_conversation", json!({ "conversation_id": id }));
+        let reply = conversation["history"].as_array().unwrap().last().unwrap();
+        assert_eq!(reply["event"]["MessageAdded"]["model"], MOCK_MODEL);
+    }
+
+    #[test]
+    fn test_history_persists_across_restarts() {
+        let harness = Harness::new();
+        let conversation: Value = harness.call("new_conversation", json!({}));
+        let id = conversation["id"].as_str().unwrap().to_string();
+        harness.call::<()>(
+            "new_conversation_user_message",
+            json!({ "conversation_id": id, "content": "Remember this" }),
+        );
+        harness.call::<()>("set_history_compression", json!({ "enabled": true }));
+        let before: Vec<Value> =
+            harness.call("get_conversation_messages", json!({ "conversation_id": id }));
+
+        let harness = harness.restart();
+        let after: Vec<Value> =
+            harness.call("get_conversation_messages", json!({ "conversation_id": id }));
+        assert_eq!(before, after);
+        assert_eq!(contents(&after), ["Remember this", MOCK_REPLY]);
+        let integrity: Value = harness.call("verify_history_integrity", json!({ "repair": false }));
+        assert_eq!(integrity["issues"], json!([]));
+    }
+
+    #[test]
+    fn test_archive_moves_conversation_between_profiles() {
+        let archive = std::env::temp_dir().join(format!("moved-{}.ehya", uuid::Uuid::new_v4()));
+        let archive_path = archive.display().to_string();
+        let (id, before) = {
+            let harness = Harness::new();
+            let conversation: Value = harness.call("new_conversation", json!({}));
+            let id = conversation["id"].as_str().unwrap().to_string();
+            harness.call::<String>(
+                "set_conversation_title",
+                json!({ "conversation_id": id, "new_title": "Moving" }),
+            );
+            harness.call::<()>(
+                "new_conversation_user_message",
+                json!({ "conversation_id": id, "content": "Take this along" }),
+            );
+            harness.call::<()>(
+                "export_conversation_archive",
+                json!({ "conversation_id": id, "path": archive_path }),
+            );
+            let before: Vec<Value> =
+                harness.call("get_conversation_messages", json!({ "conversation_id": id }));
+            (id, before)
+        };
+
+        let harness = Harness::new();
+        let imported: String =
+            harness.call("import_conversation_archive", json!({ "path": archive_path }));
+        assert_eq!(imported, id);
+        let after: Vec<Value> =
+            harness.call("get_conversation_messages", json!({ "conversation_id": id }));
+        assert_eq!(before, after);
+        let title: String = harness.call("get_conversation_title", json!({ "conversation_id": id }));
+        assert_eq!(title, "Moving");
+        std::fs::remove_file(&archive).unwrap();
+    }
+}
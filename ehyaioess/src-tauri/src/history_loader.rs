@@ -0,0 +1,98 @@
+//! Loads the conversation history in the background at startup, so the window opens straight
+//! away and lists conversations as they're decoded. Saving waits for the whole history; see
+//! `ConversationManager::loading`.
+
+use std::{path::PathBuf, sync::Arc};
+
+use tauri::{async_runtime::RwLock, AppHandle, Manager, Runtime};
+use tokio::sync::mpsc;
+
+use crate::{
+    commands::LastHistoryRecovery,
+    config::Config,
+    events,
+    generation_journal::GenerationJournal,
+    integrity,
+    models::ConversationManager,
+    payloads::{HistoryLoadProgressEventPayload, PendingGenerationPayload},
+};
+
+fn emit_progress<R: Runtime>(app_handle: &AppHandle<R>, loaded: usize, total: usize, done: bool) {
+    let payload = HistoryLoadProgressEventPayload {
+        loaded,
+        total,
+        done,
+    };
+    if let Err(e) = events::emit_all(app_handle, "history_load_progress", payload) {
+        eprintln!("Failed to announce history loading: {}", e);
+    }
+}
+
+pub async fn load<R: Runtime>(app_handle: AppHandle<R>) {
+    let conversation_manager = app_handle.state::<RwLock<ConversationManager>>();
+    let config = app_handle.state::<RwLock<Config>>();
+    let path = config.read().await.conversation_history_save_path.clone();
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let loading = tauri::async_runtime::spawn_blocking(move || {
+        ConversationManager::load_batches(&path, |batch, loaded, total| {
+            // Only fails once loading was abandoned.
+            let _ = sender.send((batch, loaded, total));
+        })
+    });
+    while let Some((batch, loaded, total)) = receiver.recv().await {
+        conversation_manager
+            .write()
+            .await
+            .conversations
+            .insert_stored(batch);
+        emit_progress(&app_handle, loaded, total, false);
+    }
+
+    let loaded = match loading.await {
+        Ok(loaded) => loaded,
+        Err(e) => Err(std::io::Error::other(e)),
+    };
+    let mut mgr = conversation_manager.write().await;
+    let mut config = config.write().await;
+    match loaded {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => {
+            eprintln!("Failed to load history: {}", e);
+            // Without the config directory, attachments are reported as missing rather than
+            // leaving the history loading, and saves refused, for the rest of the session.
+            let attachments_dir = Config::get_attachments_dir().unwrap_or_else(|e| {
+                eprintln!("Failed to locate config directory: {}", e);
+                PathBuf::new()
+            });
+            // Reads the file again to tell what's damaged, which only a damaged file costs.
+            let (recovered, recovery) = integrity::load_or_recover(&mut config, &attachments_dir);
+            *mgr = recovered;
+            *app_handle.state::<LastHistoryRecovery>().0.lock().unwrap() = recovery;
+        }
+    }
+    // A setting changed in the file takes effect with the next save.
+    mgr.compressed = config.compress_history;
+    mgr.loading = false;
+    let count = mgr.conversations.len();
+    let journal = app_handle.state::<Arc<GenerationJournal>>();
+    let (pending, gone): (Vec<_>, Vec<_>) = journal
+        .interrupted()
+        .into_iter()
+        .partition(|pending| mgr.conversations.contains_key(&pending.conversation_id));
+    // Nothing to resume in a conversation that's since been deleted.
+    for gone in gone {
+        if let Err(e) = journal.take_interrupted(gone.conversation_id) {
+            eprintln!("Failed to forget an interrupted generation: {}", e);
+        }
+    }
+    drop((mgr, config));
+    emit_progress(&app_handle, count, count, true);
+    for pending in pending {
+        let payload = PendingGenerationPayload::from(pending);
+        if let Err(e) = events::emit_all(&app_handle, "pending_generation_found", payload) {
+            eprintln!("Failed to announce an interrupted generation: {}", e);
+        }
+    }
+}
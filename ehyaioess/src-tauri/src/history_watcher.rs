@@ -0,0 +1,121 @@
+//! Reloads conversations when the history file is changed by another instance or a sync tool,
+//! so the next save doesn't write over the external changes.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::{async_runtime::RwLock, AppHandle, Manager, Runtime};
+use tokio::sync::mpsc;
+
+use crate::{
+    config::Config, events, models::ConversationManager, payloads::ConversationReloadedEventPayload,
+};
+
+/// Sync tools often write a file in several steps; wait for them to settle before reading.
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
+pub struct HistoryWatcher {
+    watcher: Mutex<RecommendedWatcher>,
+    path: Arc<Mutex<PathBuf>>,
+}
+
+impl HistoryWatcher {
+    /// Starts watching `path`; the receiver gets a message whenever the file changes.
+    pub fn new(path: PathBuf) -> notify::Result<(Self, mpsc::UnboundedReceiver<()>)> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let watched_path = Arc::new(Mutex::new(path.clone()));
+        let event_path = watched_path.clone();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            let path = event_path.lock().unwrap();
+            if (event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove())
+                && event.paths.iter().any(|changed| changed == &*path)
+            {
+                // Only fails once the reload loop has stopped.
+                let _ = sender.send(());
+            }
+        })?;
+        let history_watcher = Self {
+            watcher: Mutex::new(watcher),
+            path: watched_path,
+        };
+        history_watcher.watch_dir(&path)?;
+        Ok((history_watcher, receiver))
+    }
+
+    // Watch the directory since sync tools often replace the file instead of writing in place.
+    fn watch_dir(&self, path: &Path) -> notify::Result<()> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        self.watcher
+            .lock()
+            .unwrap()
+            .watch(dir, RecursiveMode::NonRecursive)
+    }
+
+    /// Follows the history to a new location.
+    pub fn set_path(&self, path: PathBuf) -> notify::Result<()> {
+        let old = std::mem::replace(&mut *self.path.lock().unwrap(), path.clone());
+        if old.parent() != path.parent() {
+            if let Some(dir) = old.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                // The folder may be gone already, which is fine.
+                let _ = self.watcher.lock().unwrap().unwatch(dir);
+            }
+            self.watch_dir(&path)?;
+        }
+        Ok(())
+    }
+}
+
+pub async fn reload_loop<R: Runtime>(app_handle: AppHandle<R>, mut receiver: mpsc::UnboundedReceiver<()>) {
+    while receiver.recv().await.is_some() {
+        tokio::time::sleep(RELOAD_DEBOUNCE).await;
+        while receiver.try_recv().is_ok() {}
+        reload(&app_handle).await;
+    }
+}
+
+/// Takes over the conversations that changed on disk and announces them.
+pub async fn reload<R: Runtime>(app_handle: &AppHandle<R>) {
+    let conversation_manager = app_handle.state::<RwLock<ConversationManager>>();
+    // Holding the lock keeps our own saves from landing while the file is read.
+    let mut mgr = conversation_manager.write().await;
+    let path = app_handle
+        .state::<RwLock<Config>>()
+        .read()
+        .await
+        .conversation_history_save_path
+        .clone();
+    let external = match ConversationManager::from_disk(&path) {
+        Ok(external) => external,
+        Err(e) => {
+            // Half-written or removed; a later change brings it back.
+            eprintln!("Ignoring unreadable history change: {}", e);
+            return;
+        }
+    };
+    // Empty when the change was our own save.
+    let changed = mgr.reload_from(external);
+    drop(mgr);
+
+    for (conversation_id, removed) in changed {
+        if let Err(e) = events::emit_all(
+            app_handle,
+            "conversation_reloaded",
+            ConversationReloadedEventPayload {
+                conversation_id: conversation_id.to_string(),
+                removed,
+            },
+        ) {
+            eprintln!("Failed to announce reloaded conversation: {}", e);
+        }
+    }
+}
@@ -1,18 +1,60 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod config;
+use ehyaioess_core::{
+    action_items, analytics, appearance, archive, bookmarks, catalog, clustering, compression,
+    config, context, dictation, diff, drafts, embeddings, explain, feedback, finetune, fuzzy,
+    generation_journal, glossary, guardrails, html, i18n, import, integrity, keybindings,
+    knowledge, link_preview, models, overrides, pdf, presets, pricing, profiles, provider,
+    quick_search, read_aloud, recorder, relocate, retention, search, sharing, stats, storage, sync,
+    tagging, takeout, templates, tokens, translation, trash, updates, validation, window_layout,
+    workspaces,
+};
 use config::Config;
 use models::ConversationManager;
-use std::time::{Duration, Instant};
-use tauri::{async_runtime::RwLock, Manager};
+use overrides::ConfigOverrides;
+use profiles::ProfileClients;
+use recorder::RequestRecorder;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tauri::{async_runtime::RwLock, Manager, Runtime};
 use tauri_plugin_window_state::{AppHandleExt, StateFlags};
 
+mod actions;
+mod background;
 mod commands;
-mod models;
+mod config_watcher;
+mod crash;
+mod deep_link;
+mod events;
+mod file_open;
+#[cfg(test)]
+mod harness;
+mod history_loader;
+mod history_watcher;
+mod microphone;
 mod payloads;
+mod playback;
+mod progress;
+mod screenshot;
+mod tasks;
+mod windows;
 
 fn main() {
+    let startup_links = deep_link::links_from_args(std::env::args().skip(1));
+    let startup_files = file_open::files_from_args(std::env::args().skip(1));
+    let forwarded: Vec<String> = startup_links.iter().chain(&startup_files).cloned().collect();
+    if !forwarded.is_empty() && deep_link::forward_to_running_instance(&forwarded) {
+        return;
+    }
+    deep_link::register_url_scheme();
+    file_open::register_file_associations();
+
+    if let Some(base) = workspaces::base_dir() {
+        workspaces::load_active(&base);
+    }
     let config = match Config::from_disk() {
         Ok(conf) => conf,
         Err(e) => {
@@ -20,33 +62,133 @@ fn main() {
             std::process::exit(1);
         }
     };
-    let chatgpt = match config.create_chatgpt_client() {
-        Ok(client) => client,
+    let config = match config.with_overrides(ConfigOverrides::from_process()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid config override: {}", e);
+            std::process::exit(1);
+        }
+    };
+    config.apply_locale();
+    let request_log_path = match Config::get_request_log_path() {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Failed to locate config directory: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let recorder = Arc::new(RequestRecorder::new(request_log_path, config.record_requests));
+    let usage_log = match Config::get_usage_log_path() {
+        Ok(path) => Arc::new(analytics::UsageLog::new(path)),
         Err(e) => {
-            eprintln!("Failed to create ChatGPT client: {}", e);
+            eprintln!("Failed to locate config directory: {}", e);
             std::process::exit(1);
         }
     };
-    let conversation_manager =
-        ConversationManager::from_disk(&config.conversation_history_save_path)
-            .unwrap_or_else(|_| ConversationManager::new());
+    let draft_store = match Config::get_drafts_path() {
+        Ok(path) => Arc::new(drafts::DraftStore::new(path)),
+        Err(e) => {
+            eprintln!("Failed to locate config directory: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let sync_conflicts = match Config::get_sync_conflicts_path() {
+        Ok(path) => Arc::new(sync::SyncConflicts::new(path)),
+        Err(e) => {
+            eprintln!("Failed to locate config directory: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let window_layout = match Config::get_window_layout_path() {
+        Ok(path) => Arc::new(window_layout::WindowLayout::new(path)),
+        Err(e) => {
+            eprintln!("Failed to locate config directory: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let generation_journal = match Config::get_pending_generations_path() {
+        Ok(path) => Arc::new(generation_journal::GenerationJournal::new(path)),
+        Err(e) => {
+            eprintln!("Failed to locate config directory: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let last_crash_report = match Config::get_crash_report_path() {
+        Ok(path) => {
+            let report = crash::take_last_report(&path);
+            crash::install(path);
+            report
+        }
+        Err(e) => {
+            eprintln!("Failed to locate config directory: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let profile_clients = match ProfileClients::from_config(&config, recorder.clone()) {
+        Ok(clients) => clients,
+        Err(e) => {
+            eprintln!("Failed to create ChatGPT clients: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let event_bridge_config = config.event_bridge.clone();
+    let history_path = std::path::PathBuf::from(&config.conversation_history_save_path);
 
-    tauri::Builder::default()
-        .manage(config)
-        .manage(chatgpt)
-        .manage(RwLock::new(conversation_manager))
+    let invoke_handler = invoke_handler();
+
+    let state = ManagedState {
+        config,
+        profile_clients,
+        recorder,
+        usage_log,
+        draft_store,
+        sync_conflicts,
+        window_layout,
+        generation_journal,
+        last_crash_report,
+        startup_links,
+        startup_files,
+    };
+    state
+        .manage(tauri::Builder::default())
         .plugin(tauri_plugin_window_state::Builder::default().build())
-        .invoke_handler(tauri::generate_handler![
-            commands::list_conversation_titles,
-            commands::get_conversation_messages,
-            commands::get_conversation_title,
-            commands::get_conversation,
-            commands::new_conversation,
-            commands::set_conversation_title,
-            commands::new_conversation_user_message,
-            commands::new_conversation_assistant_message,
-        ])
-        .setup(|app| {
+        .invoke_handler(move |invoke| {
+            crash::record_command(invoke.message.command());
+            invoke_handler(invoke)
+        })
+        .on_page_load(|window, _| windows::announce(&window))
+        .setup(move |app| {
+            crash::attach(app.handle());
+            let tasks = app.state::<tasks::TaskSupervisor>();
+            if event_bridge_config.enabled {
+                let bridge = events::EventBridge::new();
+                app.manage(bridge.clone());
+                tasks.spawn(
+                    "event_bridge",
+                    bridge.serve(event_bridge_config.address.clone()),
+                );
+            }
+            // Not a supervised task, since cancelling it would leave saves refused.
+            tauri::async_runtime::spawn(history_loader::load(app.handle()));
+            tasks.spawn(
+                "deep_link_listener",
+                deep_link::listen_for_forwarded_links(app.handle()),
+            );
+            tasks.spawn("retention", background::retention_loop(app.handle()));
+            tasks.spawn("update_check", background::update_check_loop(app.handle()));
+            tasks.spawn("task_reminders", background::reminder_loop(app.handle()));
+            tasks.spawn("config_watcher", config_watcher::watch_config(app.handle()));
+            match history_watcher::HistoryWatcher::new(history_path) {
+                Ok((watcher, receiver)) => {
+                    app.manage(watcher);
+                    tasks.spawn(
+                        "history_watcher",
+                        history_watcher::reload_loop(app.handle(), receiver),
+                    );
+                }
+                Err(e) => eprintln!("Failed to watch conversation history: {}", e),
+            }
+            windows::restore(&app.handle());
             let window = app.get_window("main").unwrap();
             {
                 // save window state on move
@@ -76,3 +218,204 @@ fn main() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// Every command the frontend can invoke.
+fn invoke_handler<R: Runtime>() -> fn(tauri::Invoke<R>) {
+    tauri::generate_handler![
+        commands::list_app_actions,
+        commands::get_appearance,
+        commands::set_appearance,
+        commands::list_code_themes,
+        commands::list_locales,
+        commands::set_locale,
+        commands::get_effective_config,
+        commands::get_app_info,
+        commands::get_keybindings,
+        commands::set_keybinding,
+        commands::list_conversation_titles,
+        commands::list_conversation_tags,
+        commands::set_conversation_tags,
+        commands::cluster_conversations,
+        commands::list_unread_counts,
+        commands::mark_conversation_read,
+        commands::get_conversation_messages,
+        commands::get_conversation_title,
+        commands::get_conversation,
+        commands::new_conversation,
+        commands::set_conversation_title,
+        commands::new_conversation_user_message,
+        commands::new_conversation_user_messages,
+        commands::explain_code,
+        commands::summarize_diff,
+        commands::list_capture_targets,
+        commands::capture_screenshot,
+        commands::start_dictation,
+        commands::stop_dictation,
+        commands::play_conversation,
+        commands::pause_playback,
+        commands::skip_message,
+        commands::stop_playback,
+        commands::set_playback_speed,
+        commands::get_playback_state,
+        commands::open_window,
+        commands::set_window_conversation,
+        commands::get_window_state,
+        commands::generate_response,
+        commands::continue_generation,
+        commands::retry_failed_generation,
+        commands::list_pending_generations,
+        commands::resume_pending_generation,
+        commands::discard_pending_generation,
+        commands::list_profiles,
+        commands::create_profile,
+        commands::switch_profile,
+        commands::list_api_key_profiles,
+        commands::set_active_profile,
+        commands::pin_conversation_profile,
+        commands::unpin_conversation_profile,
+        commands::test_proxy,
+        commands::check_for_updates,
+        commands::enable_request_recording,
+        commands::set_auto_tagging,
+        commands::set_models,
+        commands::get_model_pricing,
+        commands::set_model_pricing,
+        commands::set_auto_update_check,
+        commands::get_guardrails,
+        commands::set_guardrails,
+        commands::set_history_compression,
+        commands::set_read_only,
+        commands::export_request_log,
+        commands::bookmark_message,
+        commands::unbookmark_message,
+        commands::list_bookmarks,
+        commands::set_message_pinned_context,
+        commands::annotate_message,
+        commands::get_message_citations,
+        commands::get_link_preview,
+        commands::translate_message,
+        commands::extract_action_items,
+        commands::list_tasks,
+        commands::complete_task,
+        commands::set_task_reminder,
+        commands::snooze_task,
+        commands::cancel_task_reminder,
+        commands::rate_message,
+        commands::get_feedback_report,
+        commands::search_messages,
+        commands::search_in_conversation,
+        commands::quick_search,
+        commands::fuzzy_find_conversations,
+        commands::set_conversation_archived,
+        commands::lock_conversation,
+        commands::unlock_conversation,
+        commands::get_conversation_settings,
+        commands::get_conversation_stats,
+        commands::set_conversation_auto_respond,
+        commands::set_conversation_stop_sequences,
+        commands::set_conversation_assistant_prefix,
+        commands::set_conversation_language,
+        commands::set_conversation_system_prompt,
+        commands::set_conversation_model,
+        commands::set_conversation_glossary,
+        commands::index_repository,
+        commands::list_repositories,
+        commands::remove_repository,
+        commands::link_conversation_repository,
+        commands::apply_preset,
+        commands::list_style_presets,
+        commands::create_style_preset,
+        commands::update_style_preset,
+        commands::delete_style_preset,
+        commands::preview_retention,
+        commands::enforce_retention,
+        commands::get_usage_report,
+        commands::export_conversation_pdf,
+        commands::publish_conversation_html,
+        commands::detect_import_format,
+        commands::import_conversations,
+        commands::export_conversation_archive,
+        commands::import_conversation_archive,
+        commands::share_conversation_encrypted,
+        commands::import_shared_bundle,
+        commands::bulk_delete,
+        commands::bulk_archive,
+        commands::bulk_tag,
+        commands::bulk_export,
+        commands::list_trashed_conversations,
+        commands::restore_conversation,
+        commands::empty_trash,
+        commands::list_templates,
+        commands::create_template,
+        commands::update_template,
+        commands::delete_template,
+        commands::new_conversation_from_template,
+        commands::save_draft,
+        commands::get_draft,
+        commands::get_message_length_info,
+        commands::refresh_model_catalog,
+        commands::get_model_catalog,
+        commands::export_finetune_dataset,
+        commands::export_all_data,
+        commands::set_history_location,
+        commands::sync_now,
+        commands::list_sync_conflicts,
+        commands::resolve_sync_conflict,
+        commands::get_embedding_cache_stats,
+        commands::clear_embedding_cache,
+        commands::get_storage_report,
+        commands::clean_storage,
+        commands::verify_history_integrity,
+        commands::get_history_recovery_report,
+        commands::delete_all_data,
+        commands::list_background_tasks,
+        commands::cancel_task,
+        commands::get_last_crash_report,
+        commands::open_deep_link,
+        commands::take_pending_deep_links,
+        commands::handle_opened_file,
+        commands::take_pending_opened_files,
+    ]
+}
+
+/// The state commands expect to find managed, shared by the app and the test harness. The
+/// history starts out loading; see `history_loader`.
+struct ManagedState {
+    config: Config,
+    profile_clients: ProfileClients,
+    recorder: Arc<RequestRecorder>,
+    usage_log: Arc<analytics::UsageLog>,
+    draft_store: Arc<drafts::DraftStore>,
+    sync_conflicts: Arc<sync::SyncConflicts>,
+    window_layout: Arc<window_layout::WindowLayout>,
+    generation_journal: Arc<generation_journal::GenerationJournal>,
+    last_crash_report: Option<crash::CrashReport>,
+    startup_links: Vec<String>,
+    startup_files: Vec<String>,
+}
+
+impl ManagedState {
+    fn manage<R: Runtime>(self, builder: tauri::Builder<R>) -> tauri::Builder<R> {
+        builder
+            .manage(RwLock::new(self.config))
+            .manage(RwLock::new(self.profile_clients))
+            .manage(self.recorder)
+            .manage(self.usage_log)
+            .manage(self.draft_store)
+            .manage(self.sync_conflicts)
+            .manage(self.window_layout)
+            .manage(self.generation_journal)
+            .manage(tasks::TaskSupervisor::new())
+            .manage(crash::LastCrashReport(self.last_crash_report))
+            .manage(commands::LastHistoryRecovery(Default::default()))
+            .manage(RwLock::new(ConversationManager::loading()))
+            .manage(deep_link::PendingDeepLinks(std::sync::Mutex::new(
+                self.startup_links,
+            )))
+            .manage(file_open::PendingOpenedFiles(std::sync::Mutex::new(
+                self.startup_files,
+            )))
+            .manage(microphone::ActiveDictation::default())
+            .manage(playback::ReadAloud::default())
+    }
+}
@@ -0,0 +1,242 @@
+//! Dictation from the microphone into the composer. The microphone is recorded on a thread of its own, since an
+//! audio stream can't move between threads, and a task cuts the recording into chunks for
+//! transcription: the transcript is announced as `dictation_partial` while the user speaks and
+//! as `dictation_finished` once they fall silent or stop it, or `dictation_failed`.
+
+use std::sync::{mpsc as std_mpsc, Mutex};
+
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    SampleFormat, SizedSample, StreamConfig,
+};
+use tauri::{async_runtime::RwLock, AppHandle, Manager, Runtime};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    dictation::{self, VoiceActivity},
+    events,
+    models::MyError,
+    payloads::{DictationFailedEventPayload, DictationTranscriptEventPayload},
+    profiles::ProfileClients,
+};
+
+/// The dictation running, if any; there's one microphone to go round.
+#[derive(Default)]
+pub struct ActiveDictation(pub Mutex<Option<DictationHandle>>);
+
+pub struct DictationHandle {
+    pub id: Uuid,
+    /// Recording stops once this is sent to or dropped.
+    pub stop: std_mpsc::Sender<()>,
+}
+
+impl DictationHandle {
+    /// Stops recording; what was recorded is still transcribed.
+    pub fn stop(&self) {
+        let _ = self.stop.send(());
+    }
+}
+
+/// Microphone audio as it arrives, interleaved over `channels` at `rate`. Ends when recording
+/// stops.
+pub struct Recording {
+    samples: mpsc::UnboundedReceiver<Vec<f32>>,
+    channels: u16,
+    rate: u32,
+}
+
+fn build_stream<T: SizedSample>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    sender: mpsc::UnboundedSender<Vec<f32>>,
+    to_f32: fn(T) -> f32,
+) -> Result<cpal::Stream, MyError> {
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let _ = sender.send(data.iter().map(|&sample| to_f32(sample)).collect());
+            },
+            |e| eprintln!("Microphone stream failed: {}", e),
+            None,
+        )
+        .map_err(|_| MyError::MicrophoneFail)
+}
+
+fn open_microphone(
+    sender: mpsc::UnboundedSender<Vec<f32>>,
+) -> Result<(cpal::Stream, StreamConfig), MyError> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or(MyError::MicrophoneFail)?;
+    let supported = device
+        .default_input_config()
+        .map_err(|_| MyError::MicrophoneFail)?;
+    let config = supported.config();
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build_stream(&device, &config, sender, |sample: f32| sample),
+        SampleFormat::I16 => build_stream(&device, &config, sender, |sample: i16| {
+            sample as f32 / i16::MAX as f32
+        }),
+        SampleFormat::U16 => build_stream(&device, &config, sender, |sample: u16| {
+            (sample as f32 - 32_768.0) / 32_768.0
+        }),
+        _ => Err(MyError::MicrophoneFail),
+    }?;
+    stream.play().map_err(|_| MyError::MicrophoneFail)?;
+    Ok((stream, config))
+}
+
+/// Starts recording the default microphone until `stop` is sent to or dropped.
+pub fn record(stop: std_mpsc::Receiver<()>) -> Result<Recording, MyError> {
+    let (opened_sender, opened) = std_mpsc::channel();
+    std::thread::spawn(move || {
+        let (sender, samples) = mpsc::unbounded_channel();
+        // The stream records until it's dropped as the thread ends.
+        let _stream = match open_microphone(sender) {
+            Ok((stream, config)) => {
+                let _ = opened_sender.send(Ok(Recording {
+                    samples,
+                    channels: config.channels,
+                    rate: config.sample_rate.0,
+                }));
+                stream
+            }
+            Err(e) => {
+                let _ = opened_sender.send(Err(e));
+                return;
+            }
+        };
+        let _ = stop.recv();
+    });
+    opened.recv().map_err(|_| MyError::MicrophoneFail)?
+}
+
+/// `transcript` with `chunk` transcribed and added on.
+async fn transcribe_chunk<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    profile: &str,
+    chunk: &[f32],
+    transcript: &str,
+    language: Option<&str>,
+) -> Result<String, MyError> {
+    let text = app_handle
+        .state::<RwLock<ProfileClients>>()
+        .read()
+        .await
+        .transcribe_with_failover(profile, &dictation::wav(chunk), transcript, language)
+        .await
+        .map_err(|_| MyError::TranscriptionFail)?;
+    Ok(dictation::append_transcript(transcript, &text))
+}
+
+/// The transcript of `recording`, or what there was of it when transcription failed.
+async fn transcribe_recording<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    dictation_id: Uuid,
+    conversation_id: Option<Uuid>,
+    language: Option<&str>,
+    recording: &mut Recording,
+) -> Result<String, (String, MyError)> {
+    let profile = app_handle
+        .state::<RwLock<Config>>()
+        .read()
+        .await
+        .active_profile
+        .clone();
+    let max_samples = dictation::samples_in(dictation::MAX_DICTATION_SECS * 1000);
+    let mut activity = VoiceActivity::new();
+    let mut chunk: Vec<f32> = Vec::new();
+    let mut recorded = 0;
+    let mut transcript = String::new();
+    loop {
+        let samples = recording.samples.recv().await;
+        let stopped = samples.is_none();
+        if let Some(samples) = samples {
+            let samples = dictation::to_mono(&samples, recording.channels, recording.rate);
+            activity.push(&samples);
+            recorded += samples.len();
+            chunk.extend(samples);
+        }
+        let finished = stopped || activity.finished() || recorded >= max_samples;
+        if !finished && chunk.len() < dictation::samples_in(dictation::CHUNK_MS) {
+            continue;
+        }
+        // Nothing is sent until the user says something; transcribing silence makes words up.
+        if activity.speech_heard() && !chunk.is_empty() {
+            transcript = transcribe_chunk(app_handle, &profile, &chunk, &transcript, language)
+                .await
+                .map_err(|e| (transcript.clone(), e))?;
+            if !finished {
+                let partial = DictationTranscriptEventPayload {
+                    dictation_id,
+                    conversation_id,
+                    text: transcript.clone(),
+                };
+                if events::emit_all(app_handle, "dictation_partial", partial).is_err() {
+                    eprintln!("Failed to announce the partial dictation");
+                }
+            }
+        }
+        chunk.clear();
+        if finished {
+            return Ok(transcript);
+        }
+    }
+}
+
+/// Transcribes `recording` until the user falls silent or stops it, then announces the
+/// transcript.
+pub async fn transcribe<R: Runtime>(
+    app_handle: AppHandle<R>,
+    dictation_id: Uuid,
+    conversation_id: Option<Uuid>,
+    language: Option<String>,
+    mut recording: Recording,
+) {
+    let result = transcribe_recording(
+        &app_handle,
+        dictation_id,
+        conversation_id,
+        language.as_deref(),
+        &mut recording,
+    )
+    .await;
+    // Dropping the handle stops recording when it was silence that ended the dictation.
+    {
+        let active = app_handle.state::<ActiveDictation>();
+        let mut active = active.0.lock().unwrap();
+        if active
+            .as_ref()
+            .is_some_and(|handle| handle.id == dictation_id)
+        {
+            *active = None;
+        }
+    }
+    let emitted = match result {
+        Ok(text) => events::emit_all(
+            &app_handle,
+            "dictation_finished",
+            DictationTranscriptEventPayload {
+                dictation_id,
+                conversation_id,
+                text,
+            },
+        ),
+        Err((text, error)) => events::emit_all(
+            &app_handle,
+            "dictation_failed",
+            DictationFailedEventPayload {
+                dictation_id,
+                conversation_id,
+                text,
+                error: error.to_string(),
+            },
+        ),
+    };
+    if emitted.is_err() {
+        eprintln!("Failed to announce the end of dictation {}", dictation_id);
+    }
+}
@@ -14,9 +14,43 @@ pub struct ConversationTitleChangedEventPayload {
 #[derive(Debug, TS, Serialize, Deserialize, Clone)]
 #[ts(export, export_to = "../src/lib/bindings/")]
 pub struct ConversationMessagePayload {
+    #[ts(type="string")]
+    pub id: uuid::Uuid,
     #[ts(type="\"system\" | \"user\" | \"assistant\"")]
     pub author: chatgpt::types::Role,
     pub content: String,
+    pub bookmarked: bool,
+    /// Sent with every request, however long the conversation grows.
+    pub pinned_context: bool,
+    pub note: Option<String>,
+    #[ts(type="\"up\" | \"down\" | null")]
+    pub rating: Option<crate::models::MessageRating>,
+    pub translations: Vec<MessageTranslationPayload>,
+    pub language: Option<String>,
+    /// What arrived of a reply that broke off; see `continue_generation`.
+    pub partial: bool,
+    /// Banned phrases and patterns the guardrails found in the reply.
+    pub guardrail_hits: Vec<GuardrailHitPayload>,
+    /// Whether they were replaced with `[redacted]` rather than only flagged.
+    pub redacted: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct MessageTranslationPayload {
+    pub language: String,
+    pub content: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct MessageTranslatedEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    #[ts(type="string")]
+    pub message_id: uuid::Uuid,
+    pub language: String,
+    pub content: String,
 }
 
 #[derive(Debug, TS, Serialize, Deserialize, Clone)]
@@ -24,7 +58,1418 @@ pub struct ConversationMessagePayload {
 pub struct ConversationMessageAddedEventPayload {
     #[ts(type="string")]
     pub conversation_id: uuid::Uuid,
+    #[ts(type="string")]
+    pub message_id: uuid::Uuid,
+    #[ts(type="\"system\" | \"user\" | \"assistant\"")]
+    pub author: chatgpt::types::Role,
+    pub content: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ProxyTestResultPayload {
+    pub reachable: bool,
+    pub status: Option<u16>,
+    #[ts(type="number")]
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct DeepLinkOpenConversationEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct DeepLinkNewConversationEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    pub prompt: Option<String>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct FileOpenedEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    /// False when the file started a new chat as its draft rather than being imported.
+    pub imported: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct RetentionCandidatePayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    pub title: String,
+    #[ts(type="\"archived_expired\" | \"history_size_cap\"")]
+    pub reason: crate::retention::RetentionReason,
+    #[ts(type="number")]
+    pub size_bytes: u64,
+}
+
+impl From<crate::retention::RetentionCandidate> for RetentionCandidatePayload {
+    fn from(candidate: crate::retention::RetentionCandidate) -> Self {
+        Self {
+            conversation_id: candidate.conversation_id,
+            title: candidate.title,
+            reason: candidate.reason,
+            size_bytes: candidate.size_bytes,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct BackgroundTaskPayload {
+    #[ts(type="string")]
+    pub id: uuid::Uuid,
+    pub name: String,
+    #[ts(type="number")]
+    pub started_at: i64,
+}
+
+impl From<crate::tasks::TaskInfo> for BackgroundTaskPayload {
+    fn from(task: crate::tasks::TaskInfo) -> Self {
+        Self {
+            id: task.id,
+            name: task.name,
+            started_at: task.started_at,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct CrashReportPayload {
+    #[ts(type="number")]
+    pub occurred_at: i64,
+    pub version: String,
+    pub message: String,
+    pub location: String,
+    pub backtrace: String,
+    pub recent_commands: Vec<String>,
+}
+
+impl From<crate::crash::CrashReport> for CrashReportPayload {
+    fn from(report: crate::crash::CrashReport) -> Self {
+        Self {
+            occurred_at: report.occurred_at,
+            version: report.version,
+            message: report.message,
+            location: report.location,
+            backtrace: report.backtrace,
+            recent_commands: report.recent_commands,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct AppInfoPayload {
+    pub version: String,
+    /// Short git commit hash of the build, or `unknown`.
+    pub build_hash: String,
+    pub os: String,
+    pub arch: String,
+    /// The active workspace profile.
+    pub workspace: String,
+    pub config_dir: String,
+    pub history_path: String,
+    pub storage_backend: String,
+    /// `none`, `webdav` or `folder`.
+    pub sync_backend: String,
+    pub provider: String,
+    pub model: String,
+    pub api_key_profile: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct StorageUsagePayload {
+    #[ts(type="\"conversations\" | \"attachments\" | \"embeddings\" | \"logs\" | \"backups\"")]
+    pub category: crate::storage::StorageCategory,
+    #[ts(type="number")]
+    pub bytes: u64,
+    #[ts(type="number")]
+    pub files: usize,
+    /// Whether `clean_storage` accepts the category.
+    pub cleanable: bool,
+    /// Whether cleaning the category is worth suggesting.
+    pub suggested: bool,
+}
+
+impl From<crate::storage::CategoryUsage> for StorageUsagePayload {
+    fn from(usage: crate::storage::CategoryUsage) -> Self {
+        Self {
+            category: usage.category,
+            bytes: usage.bytes,
+            files: usage.files,
+            cleanable: usage.cleanable,
+            suggested: usage.suggested,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct EmbeddingCacheStatsPayload {
+    #[ts(type="number")]
+    pub entries: usize,
+    #[ts(type="number")]
+    pub size_bytes: u64,
+    #[ts(type="number")]
+    pub max_size_bytes: u64,
+    #[ts(type="number")]
+    pub hits: u64,
+    #[ts(type="number")]
+    pub misses: u64,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct UpdateInfoPayload {
+    pub current_version: String,
+    pub latest_version: String,
+    pub update_available: bool,
+    pub release_notes: String,
+    pub download_url: String,
+}
+
+impl From<crate::updates::UpdateInfo> for UpdateInfoPayload {
+    fn from(info: crate::updates::UpdateInfo) -> Self {
+        Self {
+            current_version: info.current_version,
+            latest_version: info.latest_version,
+            update_available: info.update_available,
+            release_notes: info.release_notes,
+            download_url: info.download_url,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ProgressEventPayload {
+    #[ts(type="string")]
+    pub operation_id: uuid::Uuid,
+    #[ts(type="\"import\" | \"export\" | \"embedding\" | \"migration\" | \"indexing\"")]
+    pub kind: crate::progress::ProgressKind,
+    /// Null while the operation can't tell how far along it is.
+    pub percent: Option<u8>,
+    pub message: String,
+    pub done: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationsRemovedEventPayload {
+    #[ts(type="Array<string>")]
+    pub conversation_ids: Vec<uuid::Uuid>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct TrashedConversationPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    pub title: String,
+    #[ts(type="number")]
+    pub message_count: usize,
+    /// Unix seconds.
+    #[ts(type="number")]
+    pub deleted_at: i64,
+    /// When retention deletes it for good, in unix seconds.
+    #[ts(type="number")]
+    pub purge_at: i64,
+}
+
+impl TrashedConversationPayload {
+    pub fn new(trashed: &crate::trash::TrashedConversation, trash_days: u32) -> Self {
+        Self {
+            conversation_id: trashed.conversation.id,
+            title: trashed.conversation.get_title().into_owned(),
+            message_count: trashed.conversation.message_count(),
+            deleted_at: trashed.deleted_at,
+            purge_at: trashed.purge_at(trash_days),
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct StarterMessagePayload {
     #[ts(type="\"system\" | \"user\" | \"assistant\"")]
     pub author: chatgpt::types::Role,
     pub content: String,
-}
\ No newline at end of file
+}
+
+/// A conversation template as it's created or edited; see `templates::ConversationTemplate`.
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationTemplatePayload {
+    pub name: String,
+    pub title_pattern: String,
+    pub system_prompt: String,
+    pub model: String,
+    pub auto_respond: bool,
+    pub stop_sequences: Vec<String>,
+    pub assistant_prefix: String,
+    pub language: String,
+    pub starter_messages: Vec<StarterMessagePayload>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationTemplateEntryPayload {
+    #[ts(type="string")]
+    pub id: uuid::Uuid,
+    pub template: ConversationTemplatePayload,
+}
+
+impl From<&crate::templates::ConversationTemplate> for ConversationTemplateEntryPayload {
+    fn from(template: &crate::templates::ConversationTemplate) -> Self {
+        Self {
+            id: template.id,
+            template: ConversationTemplatePayload {
+                name: template.name.clone(),
+                title_pattern: template.title_pattern.clone(),
+                system_prompt: template.system_prompt.clone(),
+                model: template.model.clone(),
+                auto_respond: template.auto_respond,
+                stop_sequences: template.stop_sequences.clone(),
+                assistant_prefix: template.assistant_prefix.clone(),
+                language: template.language.clone(),
+                starter_messages: template
+                    .starter_messages
+                    .iter()
+                    .map(|message| StarterMessagePayload {
+                        author: message.author,
+                        content: message.content.clone(),
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationsArchivedEventPayload {
+    /// Only the conversations that weren't archived (or unarchived) already.
+    #[ts(type="Array<string>")]
+    pub conversation_ids: Vec<uuid::Uuid>,
+    pub archived: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationsTagsChangedEventPayload {
+    /// One per conversation whose tags changed.
+    pub changes: Vec<ConversationTagsChangedEventPayload>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct WeeklyChatCountPayload {
+    #[ts(type="number")]
+    pub week_start: i64,
+    #[ts(type="number")]
+    pub chats: usize,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ModelTokenUsagePayload {
+    pub model: String,
+    #[ts(type="number")]
+    pub requests: usize,
+    #[ts(type="number")]
+    pub prompt_tokens: u64,
+    #[ts(type="number")]
+    pub completion_tokens: u64,
+    /// Estimated from the current prices; null for a model without a known price.
+    pub cost_usd: Option<f64>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct UsageReportPayload {
+    #[ts(type="number | null")]
+    pub period_start: Option<i64>,
+    pub chats_per_week: Vec<WeeklyChatCountPayload>,
+    pub tokens_per_model: Vec<ModelTokenUsagePayload>,
+    pub top_templates: Vec<NamedUsagePayload>,
+    pub top_style_presets: Vec<NamedUsagePayload>,
+    pub failed_generations: Vec<FailedGenerationPayload>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct NamedUsagePayload {
+    pub name: String,
+    #[ts(type="number")]
+    pub conversations: usize,
+}
+
+impl From<crate::analytics::NamedUsage> for NamedUsagePayload {
+    fn from(usage: crate::analytics::NamedUsage) -> Self {
+        Self {
+            name: usage.name,
+            conversations: usage.conversations,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct FailedGenerationPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    #[ts(type="number")]
+    pub timestamp: i64,
+    pub error: String,
+    pub recovered: bool,
+}
+
+impl From<crate::analytics::UsageReport> for UsageReportPayload {
+    fn from(report: crate::analytics::UsageReport) -> Self {
+        Self {
+            period_start: report.period_start,
+            chats_per_week: report
+                .chats_per_week
+                .into_iter()
+                .map(|week| WeeklyChatCountPayload {
+                    week_start: week.week_start,
+                    chats: week.chats,
+                })
+                .collect(),
+            tokens_per_model: report
+                .tokens_per_model
+                .into_iter()
+                .map(|usage| ModelTokenUsagePayload {
+                    model: usage.model,
+                    requests: usage.requests,
+                    prompt_tokens: usage.prompt_tokens,
+                    completion_tokens: usage.completion_tokens,
+                    cost_usd: usage.cost_usd,
+                })
+                .collect(),
+            top_templates: report.top_templates.into_iter().map(Into::into).collect(),
+            top_style_presets: report
+                .top_style_presets
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            failed_generations: report
+                .failed_generations
+                .into_iter()
+                .map(|failure| FailedGenerationPayload {
+                    conversation_id: failure.conversation_id,
+                    timestamp: failure.timestamp,
+                    error: failure.error,
+                    recovered: failure.recovered,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct MessageBookmarkChangedEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    #[ts(type="string")]
+    pub message_id: uuid::Uuid,
+    pub bookmarked: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct MessagePinnedContextChangedEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    #[ts(type="string")]
+    pub message_id: uuid::Uuid,
+    pub pinned_context: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct CitationPayload {
+    pub url: String,
+    /// The footnote marker or link text it was cited with; null for a bare URL.
+    pub label: Option<String>,
+    /// Only when asked to fetch titles, and null when the page couldn't be read.
+    pub title: Option<String>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ActionItemPayload {
+    #[ts(type="string")]
+    pub id: uuid::Uuid,
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    pub text: String,
+    #[ts(type="number")]
+    pub created_at: i64,
+    /// Null while it's still to do.
+    #[ts(type="number | null")]
+    pub done_at: Option<i64>,
+    /// Null without a pending reminder.
+    #[ts(type="number | null")]
+    pub remind_at: Option<i64>,
+}
+
+impl From<crate::action_items::ActionItem> for ActionItemPayload {
+    fn from(item: crate::action_items::ActionItem) -> Self {
+        Self {
+            id: item.id,
+            conversation_id: item.conversation_id,
+            text: item.text,
+            created_at: item.created_at,
+            done_at: item.done_at,
+            remind_at: item.remind_at,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct LinkPreviewPayload {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Absolute URL of the preview image.
+    pub image: Option<String>,
+    pub site_name: Option<String>,
+    /// Unix seconds.
+    #[ts(type="number")]
+    pub fetched_at: i64,
+}
+
+impl From<crate::link_preview::LinkPreview> for LinkPreviewPayload {
+    fn from(preview: crate::link_preview::LinkPreview) -> Self {
+        Self {
+            url: preview.url,
+            title: preview.title,
+            description: preview.description,
+            image: preview.image,
+            site_name: preview.site_name,
+            fetched_at: preview.fetched_at,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct BookmarkPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    pub conversation_title: String,
+    #[ts(type="string")]
+    pub message_id: uuid::Uuid,
+    #[ts(type="\"system\" | \"user\" | \"assistant\"")]
+    pub author: chatgpt::types::Role,
+    pub snippet: String,
+    pub context: Option<String>,
+    #[ts(type="number")]
+    pub bookmarked_at: i64,
+}
+
+impl From<crate::bookmarks::Bookmark> for BookmarkPayload {
+    fn from(bookmark: crate::bookmarks::Bookmark) -> Self {
+        Self {
+            conversation_id: bookmark.conversation_id,
+            conversation_title: bookmark.conversation_title,
+            message_id: bookmark.message_id,
+            author: bookmark.author,
+            snippet: bookmark.snippet,
+            context: bookmark.context,
+            bookmarked_at: bookmark.bookmarked_at,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct MessageAnnotatedEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    #[ts(type="string")]
+    pub message_id: uuid::Uuid,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct FuzzyConversationMatchPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    pub title: String,
+    /// The tag that matched, or null when the title did.
+    pub tag: Option<String>,
+    /// Character indices into the tag when set, otherwise into the title.
+    pub indices: Vec<usize>,
+    pub score: i32,
+}
+
+impl From<crate::fuzzy::ConversationMatch> for FuzzyConversationMatchPayload {
+    fn from(found: crate::fuzzy::ConversationMatch) -> Self {
+        Self {
+            conversation_id: found.conversation_id,
+            title: found.title,
+            tag: found.tag,
+            indices: found.matched.indices,
+            score: found.matched.score,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct MatchRangePayload {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct MessageMatchesPayload {
+    #[ts(type="string")]
+    pub message_id: uuid::Uuid,
+    /// Byte offsets into the message's content.
+    pub ranges: Vec<MatchRangePayload>,
+}
+
+impl From<crate::search::MessageMatches> for MessageMatchesPayload {
+    fn from(matches: crate::search::MessageMatches) -> Self {
+        Self {
+            message_id: matches.message_id,
+            ranges: matches
+                .ranges
+                .into_iter()
+                .map(|(start, end)| MatchRangePayload { start, end })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct SearchHitPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    pub conversation_title: String,
+    #[ts(type="string")]
+    pub message_id: uuid::Uuid,
+    #[ts(type="\"content\" | \"note\"")]
+    pub field: crate::search::SearchField,
+    pub snippet: String,
+    #[ts(type="number")]
+    pub timestamp: i64,
+    pub language: Option<String>,
+}
+
+impl From<crate::search::SearchHit> for SearchHitPayload {
+    fn from(hit: crate::search::SearchHit) -> Self {
+        Self {
+            conversation_id: hit.conversation_id,
+            conversation_title: hit.conversation_title,
+            message_id: hit.message_id,
+            field: hit.field,
+            snippet: hit.snippet,
+            timestamp: hit.timestamp,
+            language: hit.language,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct QuickSearchResultPayload {
+    #[ts(type="\"conversation\" | \"template\" | \"command\" | \"bookmark\" | \"message\"")]
+    pub kind: crate::quick_search::QuickSearchKind,
+    /// The conversation, message or template id, or the command name.
+    pub id: String,
+    #[ts(type="string | null")]
+    pub conversation_id: Option<uuid::Uuid>,
+    pub title: String,
+    pub detail: String,
+    /// Character indices into the title that matched.
+    pub indices: Vec<usize>,
+    pub score: i32,
+}
+
+impl From<crate::quick_search::QuickSearchResult> for QuickSearchResultPayload {
+    fn from(result: crate::quick_search::QuickSearchResult) -> Self {
+        Self {
+            kind: result.kind,
+            id: result.id,
+            conversation_id: result.conversation_id,
+            title: result.title,
+            detail: result.detail,
+            indices: result.indices,
+            score: result.score,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct MessageRatedEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    #[ts(type="string")]
+    pub message_id: uuid::Uuid,
+    #[ts(type="\"up\" | \"down\" | null")]
+    pub rating: Option<crate::models::MessageRating>,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ModelFeedbackPayload {
+    pub model: String,
+    #[ts(type="number")]
+    pub thumbs_up: usize,
+    #[ts(type="number")]
+    pub thumbs_down: usize,
+    pub approval: f64,
+    pub comments: Vec<String>,
+}
+
+impl From<crate::feedback::ModelFeedback> for ModelFeedbackPayload {
+    fn from(feedback: crate::feedback::ModelFeedback) -> Self {
+        Self {
+            approval: feedback.approval(),
+            model: feedback.model,
+            thumbs_up: feedback.thumbs_up,
+            thumbs_down: feedback.thumbs_down,
+            comments: feedback.comments,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct FinetuneExportOptionsPayload {
+    pub include_system_messages: bool,
+    pub system_prompt: Option<String>,
+    pub swap_user_assistant: bool,
+}
+
+impl From<FinetuneExportOptionsPayload> for crate::finetune::FinetuneOptions {
+    fn from(options: FinetuneExportOptionsPayload) -> Self {
+        Self {
+            include_system_messages: options.include_system_messages,
+            system_prompt: options.system_prompt,
+            swap_user_assistant: options.swap_user_assistant,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationLockChangedEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    pub locked: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct UnreadCountChangedEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    #[ts(type="number")]
+    pub unread_count: usize,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct AssistantTypingEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct GenerationUsageEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    #[ts(type="string")]
+    pub message_id: uuid::Uuid,
+    pub model: String,
+    /// Unset when the provider didn't report usage.
+    pub prompt_tokens: Option<u32>,
+    pub completion_tokens: Option<u32>,
+    /// Estimated from list prices; unset for models without a known price.
+    pub cost_usd: Option<f64>,
+    #[ts(type="number")]
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct AppActionArgPayload {
+    pub name: String,
+    /// TypeScript type of the argument.
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct AppActionPayload {
+    pub name: String,
+    pub description: String,
+    pub args: Vec<AppActionArgPayload>,
+    pub returns: String,
+    #[serde(default)]
+    pub keybinding: Option<String>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct AppearancePayload {
+    #[ts(type="\"system\" | \"light\" | \"dark\"")]
+    pub theme: crate::appearance::Theme,
+    pub font_size: u8,
+    #[ts(type="\"compact\" | \"comfortable\"")]
+    pub density: crate::appearance::Density,
+    pub code_theme: String,
+}
+
+impl From<crate::appearance::AppearanceConfig> for AppearancePayload {
+    fn from(appearance: crate::appearance::AppearanceConfig) -> Self {
+        Self {
+            theme: appearance.theme,
+            font_size: appearance.font_size,
+            density: appearance.density,
+            code_theme: appearance.code_theme,
+        }
+    }
+}
+
+impl From<AppearancePayload> for crate::appearance::AppearanceConfig {
+    fn from(appearance: AppearancePayload) -> Self {
+        Self {
+            theme: appearance.theme,
+            font_size: appearance.font_size,
+            density: appearance.density,
+            code_theme: appearance.code_theme,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct LocalePayload {
+    pub code: String,
+    pub name: String,
+    pub active: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConfigChangePayload {
+    pub key: String,
+    #[ts(type="unknown")]
+    pub old_value: serde_json::Value,
+    #[ts(type="unknown")]
+    pub new_value: serde_json::Value,
+    pub requires_restart: bool,
+}
+
+impl From<crate::config::ConfigChange> for ConfigChangePayload {
+    fn from(change: crate::config::ConfigChange) -> Self {
+        Self {
+            key: change.key,
+            old_value: change.old_value,
+            new_value: change.new_value,
+            requires_restart: change.requires_restart,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct EffectiveConfigValuePayload {
+    /// Dotted path of the setting, e.g. `proxy.url`.
+    pub key: String,
+    #[ts(type="unknown")]
+    pub value: serde_json::Value,
+    #[ts(type="\"default\" | \"file\" | \"env\" | \"flag\"")]
+    pub source: crate::overrides::ConfigSource,
+}
+
+impl From<crate::overrides::EffectiveValue> for EffectiveConfigValuePayload {
+    fn from(value: crate::overrides::EffectiveValue) -> Self {
+        Self {
+            key: value.key,
+            value: value.value,
+            source: value.source,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationReloadedEventPayload {
+    pub conversation_id: String,
+    /// The conversation was deleted from the history file.
+    pub removed: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct SyncStatusPayload {
+    #[ts(type="\"syncing\" | \"synced\" | \"failed\"")]
+    pub state: String,
+    /// Conversations uploaded by the finished sync.
+    pub pushed: usize,
+    /// Conversations updated from the remote by the finished sync.
+    pub pulled: usize,
+    /// Conversations that diverged and now wait for `resolve_sync_conflict`.
+    pub conflicts: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct SyncConflictEventPayload {
+    pub conversation_id: String,
+    pub local_events: usize,
+    pub remote_events: usize,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct WorkspaceProfilePayload {
+    pub name: String,
+    pub active: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationSettingsPayload {
+    pub auto_respond: bool,
+    pub stop_sequences: Vec<String>,
+    pub assistant_prefix: String,
+    pub language: String,
+    /// Empty when the config's system prompt is used.
+    pub system_prompt: String,
+    /// Empty when the config's models are used.
+    pub model: String,
+    /// The name of the response style preset, null for none.
+    pub style_preset: Option<String>,
+    pub glossary: Vec<GlossaryEntryPayload>,
+    /// The linked repository, null for none.
+    #[ts(type="string | null")]
+    pub repository_id: Option<uuid::Uuid>,
+}
+
+impl From<&crate::models::Conversation> for ConversationSettingsPayload {
+    fn from(conversation: &crate::models::Conversation) -> Self {
+        Self {
+            auto_respond: conversation.auto_respond(),
+            stop_sequences: conversation.stop_sequences(),
+            assistant_prefix: conversation.assistant_prefix(),
+            language: conversation.language(),
+            system_prompt: conversation.system_prompt(),
+            model: conversation.model(),
+            style_preset: conversation.style_preset().map(|preset| preset.name),
+            glossary: conversation
+                .glossary()
+                .into_iter()
+                .map(GlossaryEntryPayload::from)
+                .collect(),
+            repository_id: conversation.repository(),
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct GlossaryEntryPayload {
+    pub term: String,
+    pub definition: String,
+}
+
+impl From<crate::glossary::GlossaryEntry> for GlossaryEntryPayload {
+    fn from(entry: crate::glossary::GlossaryEntry) -> Self {
+        Self {
+            term: entry.term,
+            definition: entry.definition,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct StylePresetPayload {
+    pub name: String,
+    /// Added to the system prompt.
+    pub instruction: String,
+    #[ts(type="number | null")]
+    pub temperature: Option<f32>,
+    #[ts(type="number | null")]
+    pub max_tokens: Option<u32>,
+}
+
+impl From<&crate::presets::StylePreset> for StylePresetPayload {
+    fn from(preset: &crate::presets::StylePreset) -> Self {
+        Self {
+            name: preset.name.clone(),
+            instruction: preset.instruction.clone(),
+            temperature: preset.temperature,
+            max_tokens: preset.max_tokens,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationStatsPayload {
+    #[ts(type="number")]
+    pub message_count: usize,
+    #[ts(type="number")]
+    pub word_count: usize,
+    /// Words inside code blocks, part of `word_count`.
+    #[ts(type="number")]
+    pub code_word_count: usize,
+    /// Estimated, with code read more slowly than prose.
+    #[ts(type="number")]
+    pub reading_seconds: u64,
+    pub long_read: bool,
+    /// Prose words per sentence.
+    pub average_sentence_length: f64,
+    /// Share of the words that are code, from 0 to 1.
+    pub code_ratio: f64,
+}
+
+impl From<crate::stats::ConversationStats> for ConversationStatsPayload {
+    fn from(stats: crate::stats::ConversationStats) -> Self {
+        Self {
+            message_count: stats.messages,
+            word_count: stats.prose_words + stats.code_words,
+            code_word_count: stats.code_words,
+            reading_seconds: stats.reading_seconds(),
+            long_read: stats.is_long_read(),
+            average_sentence_length: stats.average_sentence_words(),
+            code_ratio: stats.code_ratio(),
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationSettingsChangedEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    pub settings: ConversationSettingsPayload,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationTagsChangedEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    pub tags: Vec<String>,
+    pub automatic: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationClusterPayload {
+    pub label: String,
+    #[ts(type="Array<string>")]
+    pub conversation_ids: Vec<uuid::Uuid>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ConversationMessagePartialEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    #[ts(type="string")]
+    pub message_id: uuid::Uuid,
+    pub content: String,
+    /// Why the reply broke off.
+    pub error: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct GenerationFailedEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    pub error: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct IntegrityIssuePayload {
+    /// The key the conversation is stored under, which may not be a valid id.
+    pub conversation_id: String,
+    #[ts(type="number | null")]
+    pub event_index: Option<usize>,
+    #[ts(type="\"undecodable_file\" | \"undecodable_conversation\" | \"checksum_mismatch\" | \"conversation_id_mismatch\" | \"undecodable_event\" | \"foreign_event\" | \"duplicate_event\" | \"out_of_order\" | \"dangling_message_reference\" | \"missing_attachment\"")]
+    pub kind: crate::integrity::IssueKind,
+    pub detail: String,
+    pub repairable: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct IntegrityReportPayload {
+    #[ts(type="number")]
+    pub conversations: usize,
+    #[ts(type="number")]
+    pub events: usize,
+    pub issues: Vec<IntegrityIssuePayload>,
+    /// Whether the history was rewritten with the repairable issues fixed.
+    pub repaired: bool,
+    /// The copy kept of the history from before the repair; empty when not repaired.
+    pub backup_path: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct HistoryLoadProgressEventPayload {
+    /// Conversations loaded so far; listed as soon as they are.
+    #[ts(type="number")]
+    pub loaded: usize,
+    #[ts(type="number")]
+    pub total: usize,
+    /// Set once the whole history has loaded and can be saved.
+    pub done: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct MessageLengthInfoPayload {
+    #[ts(type="number")]
+    pub characters: usize,
+    #[ts(type="number")]
+    pub words: usize,
+    #[ts(type="number")]
+    pub tokens: usize,
+    /// Estimated cost of sending it as a prompt; null for a model without a known price.
+    pub cost_usd: Option<f64>,
+    #[ts(type="number | null")]
+    pub context_window: Option<usize>,
+}
+
+impl From<crate::tokens::LengthInfo> for MessageLengthInfoPayload {
+    fn from(info: crate::tokens::LengthInfo) -> Self {
+        Self {
+            characters: info.characters,
+            words: info.words,
+            tokens: info.tokens,
+            cost_usd: info.cost_usd,
+            context_window: info.context_window,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ModelInfoPayload {
+    pub id: String,
+    pub owned_by: String,
+    /// Tokens of prompt and reply together; null for a model nothing is known about.
+    #[ts(type="number | null")]
+    pub context_window: Option<usize>,
+    pub vision: bool,
+    /// USD per million tokens; null without a known price.
+    pub prompt_price: Option<f64>,
+    pub completion_price: Option<f64>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ModelCatalogPayload {
+    /// Unix seconds of the last refresh; null before the first.
+    #[ts(type="number | null")]
+    pub fetched_at: Option<i64>,
+    pub models: Vec<ModelInfoPayload>,
+}
+
+/// USD per million tokens.
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ModelPricePayload {
+    pub prompt: f64,
+    pub completion: f64,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct ModelPricingPayload {
+    /// Applies to every model whose name starts with it that no longer prefix covers.
+    pub prefix: String,
+    pub prompt: f64,
+    pub completion: f64,
+    /// Set in the config rather than built in.
+    pub overridden: bool,
+}
+
+impl From<crate::pricing::PriceEntry> for ModelPricingPayload {
+    fn from(entry: crate::pricing::PriceEntry) -> Self {
+        Self {
+            prefix: entry.prefix,
+            prompt: entry.price.prompt,
+            completion: entry.price.completion,
+            overridden: entry.overridden,
+        }
+    }
+}
+
+impl From<crate::catalog::ModelCatalog> for ModelCatalogPayload {
+    fn from(catalog: crate::catalog::ModelCatalog) -> Self {
+        Self {
+            fetched_at: catalog.fetched_at,
+            models: catalog
+                .models
+                .into_iter()
+                .map(|model| ModelInfoPayload {
+                    id: model.id,
+                    owned_by: model.owned_by,
+                    context_window: model.context_window,
+                    vision: model.vision,
+                    prompt_price: model.prompt_price,
+                    completion_price: model.completion_price,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct GuardrailHitPayload {
+    /// The phrase or pattern, as configured.
+    pub rule: String,
+    #[ts(type="number")]
+    pub count: usize,
+}
+
+impl From<&crate::guardrails::GuardrailHit> for GuardrailHitPayload {
+    fn from(hit: &crate::guardrails::GuardrailHit) -> Self {
+        Self {
+            rule: hit.rule.clone(),
+            count: hit.count,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct GuardrailsPayload {
+    /// Matched ignoring case.
+    pub banned_phrases: Vec<String>,
+    /// Regular expressions.
+    pub banned_patterns: Vec<String>,
+    #[ts(type="\"flag\" | \"redact\"")]
+    pub action: crate::guardrails::GuardrailAction,
+}
+
+impl From<crate::guardrails::GuardrailConfig> for GuardrailsPayload {
+    fn from(guardrails: crate::guardrails::GuardrailConfig) -> Self {
+        Self {
+            banned_phrases: guardrails.banned_phrases,
+            banned_patterns: guardrails.banned_patterns,
+            action: guardrails.action,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct MessageFilteredEventPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    #[ts(type="string")]
+    pub message_id: uuid::Uuid,
+    pub hits: Vec<GuardrailHitPayload>,
+    pub redacted: bool,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct RepositoryPayload {
+    #[ts(type="string")]
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub path: String,
+    /// Unix seconds.
+    #[ts(type="number")]
+    pub indexed_at: i64,
+    #[ts(type="number")]
+    pub file_count: usize,
+    #[ts(type="number")]
+    pub chunk_count: usize,
+    /// Whether files were left out for the repository being too large.
+    pub truncated: bool,
+}
+
+impl From<crate::knowledge::RepositoryInfo> for RepositoryPayload {
+    fn from(info: crate::knowledge::RepositoryInfo) -> Self {
+        Self {
+            id: info.id,
+            name: info.name,
+            path: info.path,
+            indexed_at: info.indexed_at,
+            file_count: info.file_count,
+            chunk_count: info.chunk_count,
+            truncated: info.truncated,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct FileDiffSummaryPayload {
+    pub path: String,
+    /// The path before a rename.
+    pub old_path: Option<String>,
+    #[ts(type="\"added\" | \"deleted\" | \"modified\" | \"renamed\"")]
+    pub change: crate::diff::FileChange,
+    #[ts(type="number")]
+    pub additions: usize,
+    #[ts(type="number")]
+    pub deletions: usize,
+    /// Empty when the model didn't summarize the file.
+    pub summary: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct DiffSummaryPayload {
+    /// A commit-message-style summary line of the whole diff.
+    pub summary: String,
+    pub files: Vec<FileDiffSummaryPayload>,
+}
+
+impl From<crate::diff::DiffSummary> for DiffSummaryPayload {
+    fn from(summary: crate::diff::DiffSummary) -> Self {
+        Self {
+            summary: summary.summary,
+            files: summary
+                .files
+                .into_iter()
+                .map(|file| FileDiffSummaryPayload {
+                    path: file.path,
+                    old_path: file.old_path,
+                    change: file.change,
+                    additions: file.additions,
+                    deletions: file.deletions,
+                    summary: file.summary,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct CaptureTargetPayload {
+    #[ts(type="\"screen\" | \"window\"")]
+    pub kind: crate::screenshot::CaptureKind,
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct CaptureRegionPayload {
+    #[ts(type="\"screen\" | \"window\"")]
+    pub kind: crate::screenshot::CaptureKind,
+    /// A target's id from `list_capture_targets`; a screen may leave it out for the primary one.
+    pub id: Option<u32>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct DictationTranscriptEventPayload {
+    #[ts(type="string")]
+    pub dictation_id: uuid::Uuid,
+    /// The conversation whose composer the dictation is for.
+    #[ts(type="string | null")]
+    pub conversation_id: Option<uuid::Uuid>,
+    /// The whole transcript so far.
+    pub text: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct DictationFailedEventPayload {
+    #[ts(type="string")]
+    pub dictation_id: uuid::Uuid,
+    #[ts(type="string | null")]
+    pub conversation_id: Option<uuid::Uuid>,
+    /// What was transcribed before it failed.
+    pub text: String,
+    pub error: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct PlaybackStatePayload {
+    /// The conversation being read aloud, none when stopped.
+    #[ts(type="string | null")]
+    pub conversation_id: Option<uuid::Uuid>,
+    /// The message being read.
+    #[ts(type="string | null")]
+    pub message_id: Option<uuid::Uuid>,
+    #[ts(type="\"playing\" | \"paused\" | \"stopped\"")]
+    pub state: crate::read_aloud::PlaybackState,
+    /// The index of the message being read among the `message_count` queued.
+    #[ts(type="number")]
+    pub position: usize,
+    #[ts(type="number")]
+    pub message_count: usize,
+    pub speed: f32,
+    /// Why playback stopped, when it was because of an error.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct WindowStatePayload {
+    pub label: String,
+    /// The conversation the window showed when the app was last closed.
+    #[ts(type="string | null")]
+    pub conversation_id: Option<uuid::Uuid>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, Clone)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct PendingGenerationPayload {
+    #[ts(type="string")]
+    pub conversation_id: uuid::Uuid,
+    /// The message that was being replied to.
+    pub prompt: String,
+    /// Whether a partial reply was being continued.
+    pub continue_partial: bool,
+    #[ts(type="number")]
+    pub started_at: i64,
+}
+
+impl From<crate::generation_journal::PendingGeneration> for PendingGenerationPayload {
+    fn from(pending: crate::generation_journal::PendingGeneration) -> Self {
+        Self {
+            conversation_id: pending.conversation_id,
+            prompt: pending.prompt,
+            continue_partial: pending.continue_partial,
+            started_at: pending.started_at,
+        }
+    }
+}
@@ -0,0 +1,332 @@
+//! Reading a conversation aloud. The speakers are played on a thread of its own, since an audio
+//! stream can't move between threads, from audio a task synthesizes a part of a message at a
+//! time; every change of playback is announced as `playback_state_changed`.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc as std_mpsc, Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    SampleFormat, SizedSample, StreamConfig,
+};
+use tauri::{async_runtime::RwLock, AppHandle, Manager, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    dictation, events,
+    models::MyError,
+    payloads::PlaybackStatePayload,
+    profiles::ProfileClients,
+    provider,
+    read_aloud::{self, PlaybackQueue, PlaybackState},
+};
+
+/// How often the task checks whether a part has finished playing or was skipped.
+const PLAYBACK_POLL: Duration = Duration::from_millis(50);
+
+/// Reading aloud, if anything is being read, and the speed it's read at.
+#[derive(Default)]
+pub struct ReadAloud(pub Mutex<Player>);
+
+pub struct Player {
+    pub speed: f32,
+    pub session: Option<Session>,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            session: None,
+        }
+    }
+}
+
+impl Player {
+    /// The state to announce, with `error` when playback stopped because of one.
+    pub fn payload(&self, error: Option<&MyError>) -> PlaybackStatePayload {
+        let queue = self.session.as_ref().map(|session| &session.queue);
+        PlaybackStatePayload {
+            conversation_id: queue.map(|queue| queue.conversation_id),
+            message_id: queue
+                .and_then(|queue| queue.current())
+                .map(|message| message.message_id),
+            state: queue.map_or(PlaybackState::Stopped, |queue| queue.state),
+            position: queue.map_or(0, |queue| queue.position()),
+            message_count: queue.map_or(0, |queue| queue.len()),
+            speed: self.speed,
+            error: error.map(|error| error.to_string()),
+        }
+    }
+}
+
+pub struct Session {
+    pub id: Uuid,
+    pub queue: PlaybackQueue,
+    pub speaker: Arc<Speaker>,
+    /// Set to move on to the next message; the task clears it once it has.
+    pub skip: Arc<AtomicBool>,
+    /// The speakers close once this is sent to or dropped.
+    pub stop: std_mpsc::Sender<()>,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+    }
+}
+
+impl Session {
+    pub fn pause(&mut self) {
+        self.queue.state = PlaybackState::Paused;
+        self.speaker.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&mut self) {
+        self.queue.state = PlaybackState::Playing;
+        self.speaker.paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Cuts the message being read short.
+    pub fn skip(&self) {
+        self.skip.store(true, Ordering::SeqCst);
+        self.speaker.audio.lock().unwrap().clear();
+    }
+}
+
+/// Audio waiting to be played, mono at `rate`, and whether it's held back.
+pub struct Speaker {
+    paused: AtomicBool,
+    audio: Mutex<VecDeque<f32>>,
+    rate: u32,
+}
+
+impl Speaker {
+    fn queue(&self, samples: Vec<f32>) {
+        self.audio.lock().unwrap().extend(samples);
+    }
+
+    fn is_idle(&self) -> bool {
+        self.audio.lock().unwrap().is_empty()
+    }
+
+    /// Fills `data`, interleaved over `channels`, with what's next or with silence.
+    fn fill<T: SizedSample>(&self, data: &mut [T], channels: usize, from_f32: fn(f32) -> T) {
+        let paused = self.paused.load(Ordering::SeqCst);
+        let mut audio = self.audio.lock().unwrap();
+        for frame in data.chunks_mut(channels) {
+            let sample = match paused {
+                true => 0.0,
+                false => audio.pop_front().unwrap_or(0.0),
+            };
+            frame.fill(from_f32(sample));
+        }
+    }
+}
+
+fn build_stream<T: SizedSample>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    speaker: Arc<Speaker>,
+    from_f32: fn(f32) -> T,
+) -> Result<cpal::Stream, MyError> {
+    let channels = config.channels.max(1) as usize;
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                speaker.fill(data, channels, from_f32)
+            },
+            |e| eprintln!("Speaker stream failed: {}", e),
+            None,
+        )
+        .map_err(|_| MyError::SpeakerFail)
+}
+
+fn open_speakers() -> Result<(cpal::Stream, Arc<Speaker>), MyError> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or(MyError::SpeakerFail)?;
+    let supported = device
+        .default_output_config()
+        .map_err(|_| MyError::SpeakerFail)?;
+    let config = supported.config();
+    let speaker = Arc::new(Speaker {
+        paused: AtomicBool::new(false),
+        audio: Mutex::new(VecDeque::new()),
+        rate: config.sample_rate.0,
+    });
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build_stream(&device, &config, speaker.clone(), |sample| sample),
+        SampleFormat::I16 => build_stream(&device, &config, speaker.clone(), |sample| {
+            (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+        }),
+        SampleFormat::U16 => build_stream(&device, &config, speaker.clone(), |sample| {
+            (sample.clamp(-1.0, 1.0) * 32_767.0 + 32_768.0) as u16
+        }),
+        _ => Err(MyError::SpeakerFail),
+    }?;
+    stream.play().map_err(|_| MyError::SpeakerFail)?;
+    Ok((stream, speaker))
+}
+
+/// Opens the default speakers until `stop` is sent to or dropped.
+pub fn speakers(stop: std_mpsc::Receiver<()>) -> Result<Arc<Speaker>, MyError> {
+    let (opened_sender, opened) = std_mpsc::channel();
+    std::thread::spawn(move || {
+        // The stream plays until it's dropped as the thread ends.
+        let _stream = match open_speakers() {
+            Ok((stream, speaker)) => {
+                let _ = opened_sender.send(Ok(speaker));
+                stream
+            }
+            Err(e) => {
+                let _ = opened_sender.send(Err(e));
+                return;
+            }
+        };
+        let _ = stop.recv();
+    });
+    opened.recv().map_err(|_| MyError::SpeakerFail)?
+}
+
+pub fn announce<R: Runtime>(app_handle: &AppHandle<R>, state: PlaybackStatePayload) {
+    if events::emit_all(app_handle, "playback_state_changed", state).is_err() {
+        eprintln!("Failed to announce the playback state");
+    }
+}
+
+enum Next {
+    /// A part of the current message to speak, at the speed chosen.
+    Speak {
+        text: String,
+        speed: f32,
+        speaker: Arc<Speaker>,
+        skip: Arc<AtomicBool>,
+    },
+    /// Playback was stopped, or another conversation started playing.
+    Ended,
+}
+
+/// The part `part` of the current message, or None once the message has no more parts.
+fn next_part(read_aloud: &ReadAloud, session_id: Uuid, part: usize) -> Option<Next> {
+    let player = read_aloud.0.lock().unwrap();
+    let session = match player.session.as_ref() {
+        Some(session) if session.id == session_id => session,
+        _ => return Some(Next::Ended),
+    };
+    let message = match session.queue.current() {
+        Some(message) => message,
+        None => return Some(Next::Ended),
+    };
+    let text = read_aloud::speech_parts(&message.text)
+        .into_iter()
+        .nth(part)?;
+    Some(Next::Speak {
+        text,
+        speed: player.speed,
+        speaker: session.speaker.clone(),
+        skip: session.skip.clone(),
+    })
+}
+
+/// Whether `session_id` is still the session playing.
+fn is_current(read_aloud: &ReadAloud, session_id: Uuid) -> bool {
+    let player = read_aloud.0.lock().unwrap();
+    player
+        .session
+        .as_ref()
+        .is_some_and(|session| session.id == session_id)
+}
+
+/// Ends the session `session_id` if it's still the one playing, announcing why.
+fn end<R: Runtime>(app_handle: &AppHandle<R>, session_id: Uuid, error: Option<MyError>) {
+    let read_aloud = app_handle.state::<ReadAloud>();
+    let mut player = read_aloud.0.lock().unwrap();
+    match player.session.as_ref() {
+        Some(session) if session.id == session_id => player.session = None,
+        _ => return,
+    }
+    announce(app_handle, player.payload(error.as_ref()));
+}
+
+/// Reads the queue of the session `session_id` aloud, part by part, until it ends, is stopped
+/// or another conversation starts playing.
+pub async fn read<R: Runtime>(app_handle: AppHandle<R>, session_id: Uuid) {
+    let profile = app_handle
+        .state::<RwLock<Config>>()
+        .read()
+        .await
+        .active_profile
+        .clone();
+    let read_aloud = app_handle.state::<ReadAloud>();
+    let mut part = 0;
+    loop {
+        let (text, speed, speaker, skip) = match next_part(&read_aloud, session_id, part) {
+            Some(Next::Speak {
+                text,
+                speed,
+                speaker,
+                skip,
+            }) => (text, speed, speaker, skip),
+            Some(Next::Ended) => break,
+            None => {
+                // The message was read to the end.
+                let mut player = read_aloud.0.lock().unwrap();
+                let Some(session) = player
+                    .session
+                    .as_mut()
+                    .filter(|session| session.id == session_id)
+                else {
+                    return;
+                };
+                session.skip.store(false, Ordering::SeqCst);
+                if !session.queue.advance() {
+                    break;
+                }
+                part = 0;
+                announce(&app_handle, player.payload(None));
+                continue;
+            }
+        };
+        let pcm = app_handle
+            .state::<RwLock<ProfileClients>>()
+            .read()
+            .await
+            .speak_with_failover(&profile, &text, speed)
+            .await;
+        let pcm = match pcm {
+            Ok(pcm) => pcm,
+            Err(_) => return end(&app_handle, session_id, Some(MyError::SpeechFail)),
+        };
+        part += 1;
+        if skip.load(Ordering::SeqCst) {
+            // Skipped while the part was synthesized: none of the message is played.
+            part = usize::MAX;
+            continue;
+        }
+        let samples = read_aloud::pcm_samples(&pcm);
+        speaker.queue(dictation::resample(
+            &samples,
+            provider::SPEECH_SAMPLE_RATE,
+            speaker.rate,
+        ));
+        while !speaker.is_idle() && !skip.load(Ordering::SeqCst) {
+            tokio::time::sleep(PLAYBACK_POLL).await;
+            if !is_current(&read_aloud, session_id) {
+                return;
+            }
+        }
+        if skip.load(Ordering::SeqCst) {
+            part = usize::MAX;
+        }
+    }
+    end(&app_handle, session_id, None);
+}
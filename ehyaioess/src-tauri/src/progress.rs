@@ -0,0 +1,92 @@
+//! The `progress` event channel: slow operations (imports, exports, embedding jobs, moving the
+//! history, ...) report through it so the UI can show a progress bar for any of them.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Runtime};
+
+use crate::{events, i18n, payloads::ProgressEventPayload};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressKind {
+    Import,
+    Export,
+    Embedding,
+    Migration,
+    Indexing,
+}
+impl ProgressKind {
+    fn message_key(self) -> &'static str {
+        match self {
+            ProgressKind::Import => "progress.import",
+            ProgressKind::Export => "progress.export",
+            ProgressKind::Embedding => "progress.embedding",
+            ProgressKind::Migration => "progress.migration",
+            ProgressKind::Indexing => "progress.indexing",
+        }
+    }
+}
+
+/// One running operation. Dropping it before `finish` (e.g. on an error) still announces the
+/// operation as done, so progress bars don't hang.
+pub struct Progress<R: Runtime> {
+    app_handle: AppHandle<R>,
+    operation_id: uuid::Uuid,
+    kind: ProgressKind,
+    /// Whole percent last announced; None while indeterminate.
+    percent: Option<u8>,
+    finished: bool,
+}
+
+impl<R: Runtime> Progress<R> {
+    /// Announces the operation as started, with no estimate yet.
+    pub fn start(app_handle: &AppHandle<R>, kind: ProgressKind) -> Self {
+        let progress = Self {
+            app_handle: app_handle.clone(),
+            operation_id: uuid::Uuid::new_v4(),
+            kind,
+            percent: None,
+            finished: false,
+        };
+        progress.emit(false);
+        progress
+    }
+
+    /// Reports `done` of `total` steps, skipping updates that wouldn't move the bar.
+    pub fn update(&mut self, done: usize, total: usize) {
+        // Nothing to do counts as done.
+        let percent = (done.min(total) * 100).checked_div(total).unwrap_or(100) as u8;
+        if self.percent == Some(percent) {
+            return;
+        }
+        self.percent = Some(percent);
+        self.emit(false);
+    }
+
+    pub fn finish(mut self) {
+        self.percent = Some(100);
+        self.finished = true;
+        self.emit(true);
+    }
+
+    fn emit(&self, done: bool) {
+        let payload = ProgressEventPayload {
+            operation_id: self.operation_id,
+            kind: self.kind,
+            percent: self.percent,
+            message: i18n::t(self.kind.message_key()),
+            done,
+        };
+        if let Err(e) = events::emit_all(&self.app_handle, "progress", payload) {
+            eprintln!("Failed to announce progress: {}", e);
+        }
+    }
+}
+
+impl<R: Runtime> Drop for Progress<R> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.emit(true);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Screenshots to ask the model about what's on screen: a whole screen or a single window,
+//! captured as a PNG for the attachment store.
+
+use std::io::Cursor;
+
+use serde::{Deserialize, Serialize};
+use xcap::{image::ImageFormat, Monitor, Window};
+
+use crate::{
+    models::MyError,
+    payloads::{CaptureRegionPayload, CaptureTargetPayload},
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureKind {
+    Screen,
+    Window,
+}
+
+/// How a window is listed: its title, with the app it belongs to when that's known.
+fn window_name(window: &Window) -> String {
+    match (window.title(), window.app_name()) {
+        (title, "") => title.to_string(),
+        ("", app_name) => app_name.to_string(),
+        (title, app_name) => format!("{} ({})", title, app_name),
+    }
+}
+
+/// The screens, the primary one first, then the windows that aren't minimized.
+pub fn capture_targets() -> Result<Vec<CaptureTargetPayload>, MyError> {
+    let mut monitors = Monitor::all().map_err(|_| MyError::ScreenshotFail)?;
+    monitors.sort_by_key(|monitor| !monitor.is_primary());
+    let windows = Window::all().map_err(|_| MyError::ScreenshotFail)?;
+    let screens = monitors.iter().map(|monitor| CaptureTargetPayload {
+        kind: CaptureKind::Screen,
+        id: monitor.id(),
+        name: monitor.name().to_string(),
+    });
+    let windows = windows
+        .iter()
+        .filter(|window| !window.is_minimized())
+        .map(|window| CaptureTargetPayload {
+            kind: CaptureKind::Window,
+            id: window.id(),
+            name: window_name(window),
+        });
+    Ok(screens.chain(windows).collect())
+}
+
+/// `region` captured as a PNG. This blocks until the capture is done.
+pub fn capture_png(region: &CaptureRegionPayload) -> Result<Vec<u8>, MyError> {
+    let image = match (region.kind, region.id) {
+        (CaptureKind::Screen, id) => Monitor::all()
+            .map_err(|_| MyError::ScreenshotFail)?
+            .into_iter()
+            .find(|monitor| match id {
+                Some(id) => monitor.id() == id,
+                None => monitor.is_primary(),
+            })
+            .ok_or(MyError::ScreenshotTargetNotFoundFail)?
+            .capture_image(),
+        (CaptureKind::Window, Some(id)) => Window::all()
+            .map_err(|_| MyError::ScreenshotFail)?
+            .into_iter()
+            .find(|window| window.id() == id)
+            .ok_or(MyError::ScreenshotTargetNotFoundFail)?
+            .capture_image(),
+        (CaptureKind::Window, None) => return Err(MyError::ScreenshotTargetNotFoundFail),
+    }
+    .map_err(|_| MyError::ScreenshotFail)?;
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|_| MyError::ScreenshotFail)?;
+    Ok(png.into_inner())
+}
@@ -0,0 +1,107 @@
+//! Owns the app's background work (saves, watchers, schedules, tagging, ...) so it can be listed
+//! and cancelled by name, and so a panicking task is logged and dropped instead of lost silently.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::FutureExt;
+use tauri::async_runtime::JoinHandle;
+
+use crate::background;
+
+struct TaskEntry {
+    name: String,
+    started_at: i64,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub started_at: i64,
+}
+
+#[derive(Clone, Default)]
+pub struct TaskSupervisor {
+    tasks: Arc<Mutex<HashMap<uuid::Uuid, TaskEntry>>>,
+}
+
+pub fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause")
+}
+
+impl TaskSupervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `task` in the background under `name` until it ends or is cancelled.
+    pub fn spawn<F>(&self, name: &str, task: F) -> uuid::Uuid
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let id = uuid::Uuid::new_v4();
+        let tasks = self.tasks.clone();
+        let task_name = name.to_string();
+        // Held while spawning, so a task that ends right away can't deregister before it's
+        // registered.
+        let mut registry = self.tasks.lock().unwrap();
+        let handle = tauri::async_runtime::spawn(async move {
+            if let Err(panic) = AssertUnwindSafe(task).catch_unwind().await {
+                eprintln!(
+                    "Background task {} panicked: {}",
+                    task_name,
+                    panic_message(&*panic)
+                );
+            }
+            tasks.lock().unwrap().remove(&id);
+        });
+        registry.insert(
+            id,
+            TaskEntry {
+                name: name.to_string(),
+                started_at: background::unix_now(),
+                handle,
+            },
+        );
+        id
+    }
+
+    /// The running tasks, oldest first.
+    pub fn list(&self) -> Vec<TaskInfo> {
+        let mut tasks: Vec<TaskInfo> = self
+            .tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, entry)| TaskInfo {
+                id: *id,
+                name: entry.name.clone(),
+                started_at: entry.started_at,
+            })
+            .collect();
+        tasks.sort_by_key(|task| task.started_at);
+        tasks
+    }
+
+    /// Stops the task at its next await point; false when no such task is running.
+    pub fn cancel(&self, id: uuid::Uuid) -> bool {
+        match self.tasks.lock().unwrap().remove(&id) {
+            Some(entry) => {
+                entry.handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
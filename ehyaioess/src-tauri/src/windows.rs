@@ -0,0 +1,169 @@
+//! Windows beyond the main one, and the layout of them all kept for the next start: each
+//! window is reopened where it was and told, as `restore_window_state`, which conversation it
+//! showed.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tauri::{
+    AppHandle, LogicalPosition, LogicalSize, Manager, Runtime, Window, WindowBuilder, WindowEvent,
+    WindowUrl,
+};
+use uuid::Uuid;
+
+use crate::{
+    models::MyError,
+    payloads::WindowStatePayload,
+    window_layout::{WindowBounds, WindowLayout, WindowState, MAIN_WINDOW},
+};
+
+/// Moving or resizing a window records where it is at most this often.
+const BOUNDS_SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A label for a new window, unique among those ever opened.
+pub fn new_label() -> String {
+    format!("window-{}", Uuid::new_v4().simple())
+}
+
+pub fn payload(state: &WindowState) -> WindowStatePayload {
+    WindowStatePayload {
+        label: state.label.clone(),
+        conversation_id: state.conversation_id,
+    }
+}
+
+/// Where `window` is now, or None while it's minimized.
+fn bounds<R: Runtime>(window: &Window<R>) -> Option<WindowBounds> {
+    if window.is_minimized().unwrap_or(false) {
+        return None;
+    }
+    let scale = window.scale_factor().ok()?;
+    let position = window.outer_position().ok()?.to_logical::<f64>(scale);
+    let size = window.inner_size().ok()?.to_logical::<f64>(scale);
+    Some(WindowBounds {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized: window.is_maximized().unwrap_or(false),
+    })
+}
+
+/// The screens `window` could be shown on.
+fn screens<R: Runtime>(window: &Window<R>) -> Vec<WindowBounds> {
+    window
+        .available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|monitor| {
+            let position = monitor.position().to_logical::<f64>(monitor.scale_factor());
+            let size = monitor.size().to_logical::<f64>(monitor.scale_factor());
+            WindowBounds {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                maximized: false,
+            }
+        })
+        .collect()
+}
+
+fn save_bounds<R: Runtime>(window: &Window<R>, layout: &WindowLayout) {
+    if let Some(bounds) = bounds(window) {
+        if let Err(e) = layout.set_bounds(window.label(), bounds) {
+            eprintln!(
+                "Failed to save the layout of window {}: {}",
+                window.label(),
+                e
+            );
+        }
+    }
+}
+
+/// Keeps the layout up to date as `window` is moved, resized and closed.
+pub fn track<R: Runtime>(window: &Window<R>) {
+    let layout = window.state::<Arc<WindowLayout>>().inner().clone();
+    let last_save_time = std::cell::Cell::new(Instant::now() - BOUNDS_SAVE_INTERVAL);
+    let tracked = window.clone();
+    window.on_window_event(move |e| match e {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+            let now = Instant::now();
+            if now - last_save_time.get() >= BOUNDS_SAVE_INTERVAL {
+                save_bounds(&tracked, &layout);
+                last_save_time.set(now);
+            }
+        }
+        WindowEvent::CloseRequested { .. } => {
+            // Closing the last window quits, and the windows open then are the ones to restore.
+            if tracked.app_handle().windows().len() > 1 {
+                if let Err(e) = layout.remove(tracked.label()) {
+                    eprintln!("Failed to forget window {}: {}", tracked.label(), e);
+                }
+            } else {
+                save_bounds(&tracked, &layout);
+            }
+        }
+        _ => {}
+    });
+}
+
+/// Opens a window for `state`, where it last was when that's still on screen.
+pub fn open<R: Runtime>(app_handle: &AppHandle<R>, state: &WindowState) -> Result<(), MyError> {
+    let window = WindowBuilder::new(
+        app_handle,
+        &state.label,
+        WindowUrl::App("index.html".into()),
+    )
+    .title("ehyaioess")
+    .visible(false)
+    .build()
+    .map_err(|_| MyError::WindowFail)?;
+    place(&window, state);
+    window.show().map_err(|_| MyError::WindowFail)?;
+    track(&window);
+    Ok(())
+}
+
+/// Moves `window` to where `state` last had it, unless that's no longer on any screen.
+fn place<R: Runtime>(window: &Window<R>, state: &WindowState) {
+    let Some(bounds) = state
+        .bounds
+        .filter(|bounds| bounds.is_visible_on(&screens(window)))
+    else {
+        return;
+    };
+    let _ = window.set_size(LogicalSize::new(bounds.width, bounds.height));
+    let _ = window.set_position(LogicalPosition::new(bounds.x, bounds.y));
+    if bounds.maximized {
+        let _ = window.maximize();
+    }
+}
+
+/// Tells `window` which conversation to show, e.g. once its page has loaded.
+pub fn announce<R: Runtime>(window: &Window<R>) {
+    let layout = window.state::<Arc<WindowLayout>>();
+    let state = payload(&layout.get(window.label()));
+    if window.emit("restore_window_state", state).is_err() {
+        eprintln!("Failed to restore the state of window {}", window.label());
+    }
+}
+
+/// Reopens the windows of the last session, the main one where it was.
+pub fn restore<R: Runtime>(app_handle: &AppHandle<R>) {
+    let layout = app_handle.state::<Arc<WindowLayout>>();
+    if let Some(window) = app_handle.get_window(MAIN_WINDOW) {
+        place(&window, &layout.get(MAIN_WINDOW));
+        track(&window);
+    }
+    for state in layout.windows() {
+        if state.label == MAIN_WINDOW {
+            continue;
+        }
+        if let Err(e) = open(app_handle, &state) {
+            eprintln!("Failed to reopen window {}: {}", state.label, e);
+        }
+    }
+}
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface ActionItemPayload { id: string, conversation_id: string, text: string, created_at: number, done_at: number | null, remind_at: number | null, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+import type { AppActionArgPayload } from "./AppActionArgPayload";
+
+export interface AppActionPayload { name: string, description: string, args: Array<AppActionArgPayload>, returns: string, keybinding: string | null, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface AppInfoPayload { version: string, build_hash: string, os: string, arch: string, workspace: string, config_dir: string, history_path: string, storage_backend: string, sync_backend: string, provider: string, model: string, api_key_profile: string, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface AppearancePayload { theme: "system" | "light" | "dark", font_size: number, density: "compact" | "comfortable", code_theme: string, }
\ No newline at end of file
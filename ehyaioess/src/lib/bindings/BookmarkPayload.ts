@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface BookmarkPayload { conversation_id: string, conversation_title: string, message_id: string, author: "system" | "user" | "assistant", snippet: string, context: string | null, bookmarked_at: number, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface ConfigChangePayload { key: string, old_value: unknown, new_value: unknown, requires_restart: boolean, }
\ No newline at end of file
@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export interface ConversationMessageAddedEventPayload { conversation_id: string, author: "system" | "user" | "assistant", content: string, }
\ No newline at end of file
+export interface ConversationMessageAddedEventPayload { conversation_id: string, message_id: string, author: "system" | "user" | "assistant", content: string, }
\ No newline at end of file
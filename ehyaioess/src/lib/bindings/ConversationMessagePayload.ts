@@ -1,3 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export interface ConversationMessagePayload { author: "system" | "user" | "assistant", content: string, }
\ No newline at end of file
+import type { GuardrailHitPayload } from "./GuardrailHitPayload";
+import type { MessageTranslationPayload } from "./MessageTranslationPayload";
+
+export interface ConversationMessagePayload { id: string, author: "system" | "user" | "assistant", content: string, bookmarked: boolean, pinned_context: boolean, note: string | null, rating: "up" | "down" | null, translations: Array<MessageTranslationPayload>, language: string | null, partial: boolean, guardrail_hits: Array<GuardrailHitPayload>, redacted: boolean, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+import type { ConversationSettingsPayload } from "./ConversationSettingsPayload";
+
+export interface ConversationSettingsChangedEventPayload { conversation_id: string, settings: ConversationSettingsPayload, }
\ No newline at end of file
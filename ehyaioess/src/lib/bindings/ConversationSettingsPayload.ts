@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+import type { GlossaryEntryPayload } from "./GlossaryEntryPayload";
+
+export interface ConversationSettingsPayload { auto_respond: boolean, stop_sequences: Array<string>, assistant_prefix: string, language: string, system_prompt: string, model: string, style_preset: string | null, glossary: Array<GlossaryEntryPayload>, repository_id: string | null, }
\ No newline at end of file
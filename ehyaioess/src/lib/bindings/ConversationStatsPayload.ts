@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface ConversationStatsPayload { message_count: number, word_count: number, code_word_count: number, reading_seconds: number, long_read: boolean, average_sentence_length: number, code_ratio: number, }
\ No newline at end of file
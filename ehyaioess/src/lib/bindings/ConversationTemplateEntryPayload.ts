@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+import type { ConversationTemplatePayload } from "./ConversationTemplatePayload";
+
+export interface ConversationTemplateEntryPayload { id: string, template: ConversationTemplatePayload, }
\ No newline at end of file
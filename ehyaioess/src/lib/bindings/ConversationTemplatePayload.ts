@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+import type { StarterMessagePayload } from "./StarterMessagePayload";
+
+export interface ConversationTemplatePayload { name: string, title_pattern: string, system_prompt: string, model: string, auto_respond: boolean, stop_sequences: Array<string>, assistant_prefix: string, language: string, starter_messages: Array<StarterMessagePayload>, }
\ No newline at end of file
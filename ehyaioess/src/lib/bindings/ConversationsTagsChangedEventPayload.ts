@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+import type { ConversationTagsChangedEventPayload } from "./ConversationTagsChangedEventPayload";
+
+export interface ConversationsTagsChangedEventPayload { changes: Array<ConversationTagsChangedEventPayload>, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface CrashReportPayload { occurred_at: number, version: string, message: string, location: string, backtrace: string, recent_commands: Array<string>, }
\ No newline at end of file
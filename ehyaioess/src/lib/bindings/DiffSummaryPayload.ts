@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+import type { FileDiffSummaryPayload } from "./FileDiffSummaryPayload";
+
+export interface DiffSummaryPayload { summary: string, files: Array<FileDiffSummaryPayload>, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface EffectiveConfigValuePayload { key: string, value: unknown, source: "default" | "file" | "env" | "flag", }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface EmbeddingCacheStatsPayload { entries: number, size_bytes: number, max_size_bytes: number, hits: number, misses: number, }
\ No newline at end of file
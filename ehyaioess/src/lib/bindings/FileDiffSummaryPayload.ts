@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface FileDiffSummaryPayload { path: string, old_path: string | null, change: "added" | "deleted" | "modified" | "renamed", additions: number, deletions: number, summary: string, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface FinetuneExportOptionsPayload { include_system_messages: boolean, system_prompt: string | null, swap_user_assistant: boolean, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface FuzzyConversationMatchPayload { conversation_id: string, title: string, tag: string | null, indices: Array<number>, score: number, }
\ No newline at end of file
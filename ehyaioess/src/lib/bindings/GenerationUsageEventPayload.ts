@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface GenerationUsageEventPayload { conversation_id: string, message_id: string, model: string, prompt_tokens: number | null, completion_tokens: number | null, cost_usd: number | null, duration_ms: number, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface GuardrailsPayload { banned_phrases: Array<string>, banned_patterns: Array<string>, action: "flag" | "redact", }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface IntegrityIssuePayload { conversation_id: string, event_index: number | null, kind: "undecodable_file" | "undecodable_conversation" | "checksum_mismatch" | "conversation_id_mismatch" | "undecodable_event" | "foreign_event" | "duplicate_event" | "out_of_order" | "dangling_message_reference" | "missing_attachment", detail: string, repairable: boolean, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+import type { IntegrityIssuePayload } from "./IntegrityIssuePayload";
+
+export interface IntegrityReportPayload { conversations: number, events: number, issues: Array<IntegrityIssuePayload>, repaired: boolean, backup_path: string, }
\ No newline at end of file
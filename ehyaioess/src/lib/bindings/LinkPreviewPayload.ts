@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface LinkPreviewPayload { url: string, title: string | null, description: string | null, image: string | null, site_name: string | null, fetched_at: number, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+import type { GuardrailHitPayload } from "./GuardrailHitPayload";
+
+export interface MessageFilteredEventPayload { conversation_id: string, message_id: string, hits: Array<GuardrailHitPayload>, redacted: boolean, }
\ No newline at end of file
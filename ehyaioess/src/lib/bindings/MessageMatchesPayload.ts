@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+import type { MatchRangePayload } from "./MatchRangePayload";
+
+export interface MessageMatchesPayload { message_id: string, ranges: Array<MatchRangePayload>, }
\ No newline at end of file
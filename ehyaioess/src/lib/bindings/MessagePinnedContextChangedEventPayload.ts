@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface MessagePinnedContextChangedEventPayload { conversation_id: string, message_id: string, pinned_context: boolean, }
\ No newline at end of file
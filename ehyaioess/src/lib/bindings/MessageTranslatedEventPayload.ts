@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface MessageTranslatedEventPayload { conversation_id: string, message_id: string, language: string, content: string, }
\ No newline at end of file
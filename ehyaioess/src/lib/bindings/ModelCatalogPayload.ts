@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+import type { ModelInfoPayload } from "./ModelInfoPayload";
+
+export interface ModelCatalogPayload { fetched_at: number | null, models: Array<ModelInfoPayload>, }
\ No newline at end of file
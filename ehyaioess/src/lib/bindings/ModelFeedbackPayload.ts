@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface ModelFeedbackPayload { model: string, thumbs_up: number, thumbs_down: number, approval: number, comments: Array<string>, }
\ No newline at end of file
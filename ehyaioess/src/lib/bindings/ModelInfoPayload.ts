@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface ModelInfoPayload { id: string, owned_by: string, context_window: number | null, vision: boolean, prompt_price: number | null, completion_price: number | null, }
\ No newline at end of file
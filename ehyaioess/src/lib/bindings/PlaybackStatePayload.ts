@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface PlaybackStatePayload { conversation_id: string | null, message_id: string | null, state: "playing" | "paused" | "stopped", position: number, message_count: number, speed: number, error: string | null, }
\ No newline at end of file
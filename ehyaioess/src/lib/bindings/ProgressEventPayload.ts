@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface ProgressEventPayload { operation_id: string, kind: "import" | "export" | "embedding" | "migration" | "indexing", percent: number | null, message: string, done: boolean, }
\ No newline at end of file
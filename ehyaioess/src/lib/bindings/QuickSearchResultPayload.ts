@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface QuickSearchResultPayload { kind: "conversation" | "template" | "command" | "bookmark" | "message", id: string, conversation_id: string | null, title: string, detail: string, indices: Array<number>, score: number, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface RepositoryPayload { id: string, name: string, path: string, indexed_at: number, file_count: number, chunk_count: number, truncated: boolean, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface RetentionCandidatePayload { conversation_id: string, title: string, reason: "archived_expired" | "history_size_cap", size_bytes: number, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface SearchHitPayload { conversation_id: string, conversation_title: string, message_id: string, field: "content" | "note", snippet: string, timestamp: number, language: string | null, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface StylePresetPayload { name: string, instruction: string, temperature: number | null, max_tokens: number | null, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface SyncStatusPayload { state: "syncing" | "synced" | "failed", pushed: number, pulled: number, conflicts: number, error: string | null, }
\ No newline at end of file
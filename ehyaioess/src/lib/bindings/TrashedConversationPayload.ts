@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface TrashedConversationPayload { conversation_id: string, title: string, message_count: number, deleted_at: number, purge_at: number, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface UpdateInfoPayload { current_version: string, latest_version: string, update_available: boolean, release_notes: string, download_url: string, }
\ No newline at end of file
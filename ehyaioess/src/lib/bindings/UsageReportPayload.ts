@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+import type { FailedGenerationPayload } from "./FailedGenerationPayload";
+import type { ModelTokenUsagePayload } from "./ModelTokenUsagePayload";
+import type { NamedUsagePayload } from "./NamedUsagePayload";
+import type { WeeklyChatCountPayload } from "./WeeklyChatCountPayload";
+
+export interface UsageReportPayload { period_start: number | null, chats_per_week: Array<WeeklyChatCountPayload>, tokens_per_model: Array<ModelTokenUsagePayload>, top_templates: Array<NamedUsagePayload>, top_style_presets: Array<NamedUsagePayload>, failed_generations: Array<FailedGenerationPayload>, }
\ No newline at end of file
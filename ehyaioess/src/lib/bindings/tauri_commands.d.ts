@@ -1,46 +1,2113 @@
-// THIS FILE IS AUTO-GENERATED BY CARGO TESTS! DO NOT EDIT!
+// THIS FILE IS AUTO-GENERATED BY ts-bindgen! DO NOT EDIT!
 
 import { invoke as invokeRaw } from "@tauri-apps/api";
+import { listen as listenRaw, type EventCallback, type UnlistenFn } from "@tauri-apps/api/event";
+
+export interface ActionItemPayload {
+    id: string,
+    conversation_id: string,
+    text: string,
+    created_at: number,
+    /** Null while it's still to do. */
+    done_at: number | null,
+    /** Null without a pending reminder. */
+    remind_at: number | null,
+}
+
+export interface AppActionArgPayload {
+    name: string,
+    /** TypeScript type of the argument. */
+    type: string,
+}
+
+export interface AppActionPayload {
+    name: string,
+    description: string,
+    args: Array<AppActionArgPayload>,
+    returns: string,
+    keybinding: string | null,
+}
+
+export interface AppInfoPayload {
+    version: string,
+    /** Short git commit hash of the build, or `unknown`. */
+    build_hash: string,
+    os: string,
+    arch: string,
+    /** The active workspace profile. */
+    workspace: string,
+    config_dir: string,
+    history_path: string,
+    storage_backend: string,
+    /** `none`, `webdav` or `folder`. */
+    sync_backend: string,
+    provider: string,
+    model: string,
+    api_key_profile: string,
+}
+
+export interface AppearancePayload {
+    theme: Theme,
+    font_size: number,
+    density: Density,
+    code_theme: string,
+}
+
+/** What was wrong with an argument. */
+export type ArgumentProblem = { kind: "too_long", max_length: number } | { kind: "invalid_id" } | { kind: "path_not_allowed", extensions: Array<string> } | { kind: "empty" } | { kind: "negative" } | { kind: "invalid_url" };
+
+export interface AssistantTypingEventPayload {
+    conversation_id: string,
+}
+
+export interface BackgroundTaskPayload {
+    id: string,
+    name: string,
+    started_at: number,
+}
+
+export interface BookmarkPayload {
+    conversation_id: string,
+    conversation_title: string,
+    message_id: string,
+    author: "system" | "user" | "assistant",
+    snippet: string,
+    context: string | null,
+    bookmarked_at: number,
+}
+
+export type CaptureKind = "screen" | "window";
+
+export interface CaptureRegionPayload {
+    kind: CaptureKind,
+    /** A target's id from `list_capture_targets`; a screen may leave it out for the primary one. */
+    id: number | null,
+}
+
+export interface CaptureTargetPayload {
+    kind: CaptureKind,
+    id: number,
+    name: string,
+}
+
+export interface Citation {
+    url: string,
+    /**
+     * The footnote marker, e.g. "1" for `[1]` or `[^1]`, or the text of a markdown link.
+     * None for a bare URL.
+     */
+    label?: string | null,
+}
+
+export interface CitationPayload {
+    url: string,
+    /** The footnote marker or link text it was cited with; null for a bare URL. */
+    label: string | null,
+    /** Only when asked to fetch titles, and null when the page couldn't be read. */
+    title: string | null,
+}
+
+export interface ConfigChangePayload {
+    key: string,
+    old_value: unknown,
+    new_value: unknown,
+    requires_restart: boolean,
+}
+
+export type ConfigSource = "default" | "file" | "env" | "flag";
+
+export interface Conversation {
+    id: string,
+    history: Array<ConversationEventRecord>,
+}
+
+export interface ConversationAddedEvent {
+    conversation_id: string,
+    title: string,
+}
+
+export interface ConversationArchivedEvent {
+    archived: boolean,
+}
+
+export interface ConversationClusterPayload {
+    label: string,
+    conversation_ids: Array<string>,
+}
+
+export interface ConversationCreatedEvent {
+    /** Name of the template the conversation was started from, as it was then. */
+    template?: string | null,
+}
+
+export type ConversationEvent = { MessageAdded: ConversationMessageAddedEvent } | { TitleChange: ConversationTitleChangedEvent } | { Created: ConversationCreatedEvent } | { ProfilePinned: ConversationProfilePinnedEvent } | { Archived: ConversationArchivedEvent } | { Locked: ConversationLockedEvent } | { MessageBookmarked: ConversationMessageBookmarkedEvent } | { MessageAnnotated: ConversationMessageAnnotatedEvent } | { MessageRated: ConversationMessageRatedEvent } | { MessageTranslated: ConversationMessageTranslatedEvent } | { Read: ConversationReadEvent } | { SettingChanged: ConversationSettingChangedEvent } | { TagsChanged: ConversationTagsChangedEvent } | { MessagePartial: ConversationMessagePartialEvent } | { GenerationFailed: ConversationGenerationFailedEvent } | { MessagePinned: ConversationMessagePinnedEvent } | { MessageFiltered: ConversationMessageFilteredEvent };
+
+export interface ConversationEventRecord {
+    id: string,
+    conversation_id: string,
+    timestamp: number,
+    event: ConversationEvent,
+}
+
+/**
+ * An attempt at generating a reply that failed without any of it arriving. It stays pending
+ * until a message is added after it; see `Conversation::pending_failure`.
+ */
+export interface ConversationGenerationFailedEvent {
+    /** Redacted of API keys. */
+    error: string,
+}
+
+export interface ConversationLockChangedEventPayload {
+    conversation_id: string,
+    locked: boolean,
+}
+
+/**
+ * A locked conversation refuses new messages, title changes and setting changes; see
+ * `Conversation::try_add_event`.
+ */
+export interface ConversationLockedEvent {
+    locked: boolean,
+}
+
+export interface ConversationMessageAddedEvent {
+    author: "system" | "user" | "assistant",
+    content: string,
+    /** Model that generated an assistant message. */
+    model?: string | null,
+    /** ISO 639-3 code of the detected language, filled in when the message is added. */
+    language?: string | null,
+    /** Sources an assistant message cites, filled in when the message is added. */
+    citations?: Array<Citation>,
+}
+
+export interface ConversationMessageAddedEventPayload {
+    conversation_id: string,
+    message_id: string,
+    author: "system" | "user" | "assistant",
+    content: string,
+}
+
+export interface ConversationMessageAnnotatedEvent {
+    message_id: string,
+    /** None clears the note. */
+    note: string | null,
+}
+
+export interface ConversationMessageBookmarkedEvent {
+    message_id: string,
+    bookmarked: boolean,
+}
+
+/** Banned content the guardrails found in a reply; see `guardrails`. */
+export interface ConversationMessageFilteredEvent {
+    message_id: string,
+    hits: Array<GuardrailHit>,
+    /** Whether the stored reply has the banned content redacted, rather than only flagged. */
+    redacted: boolean,
+}
+
+/**
+ * What arrived of an assistant reply before generating it failed. It stays pending until a
+ * message is added after it, either its continuation or a new reply.
+ */
+export interface ConversationMessagePartialEvent {
+    content: string,
+    model?: string | null,
+    /** Why the reply broke off. */
+    error: string,
+}
+
+export interface ConversationMessagePartialEventPayload {
+    conversation_id: string,
+    message_id: string,
+    content: string,
+    /** Why the reply broke off. */
+    error: string,
+}
+
+export interface ConversationMessagePayload {
+    id: string,
+    author: "system" | "user" | "assistant",
+    content: string,
+    bookmarked: boolean,
+    /** Sent with every request, however long the conversation grows. */
+    pinned_context: boolean,
+    note: string | null,
+    rating: MessageRating | null,
+    translations: Array<MessageTranslationPayload>,
+    language: string | null,
+    /** What arrived of a reply that broke off; see `continue_generation`. */
+    partial: boolean,
+    /** Banned phrases and patterns the guardrails found in the reply. */
+    guardrail_hits: Array<GuardrailHitPayload>,
+    /** Whether they were replaced with `[redacted]` rather than only flagged. */
+    redacted: boolean,
+}
+
+/**
+ * Pinned context is sent with every request, however long the conversation grows; see
+ * `context::fit`.
+ */
+export interface ConversationMessagePinnedEvent {
+    message_id: string,
+    pinned: boolean,
+}
+
+export interface ConversationMessageRatedEvent {
+    message_id: string,
+    /** None withdraws the rating. */
+    rating: MessageRating | null,
+    comment: string | null,
+}
+
+/** An alternate rendition of a message in another language, kept alongside the original. */
+export interface ConversationMessageTranslatedEvent {
+    message_id: string,
+    language: string,
+    content: string,
+}
+
+export interface ConversationProfilePinnedEvent {
+    profile: string | null,
+}
+
+export interface ConversationReadEvent {
+    /** Number of messages the user had seen, counting from the start of the conversation. */
+    read_through: number,
+}
+
+export interface ConversationReloadedEventPayload {
+    conversation_id: string,
+    /** The conversation was deleted from the history file. */
+    removed: boolean,
+}
+
+/** A per-conversation setting. Each kind is changed independently and the latest change wins. */
+export type ConversationSetting = { auto_respond: boolean } | { stop_sequences: Array<string> } | { assistant_prefix: string } | { language: string } | { system_prompt: string } | { model: string } | { style_preset: StylePreset | null } | { glossary: Array<GlossaryEntry> } | { repository: string | null };
+
+export interface ConversationSettingChangedEvent {
+    setting: ConversationSetting,
+}
+
+export interface ConversationSettingsChangedEventPayload {
+    conversation_id: string,
+    settings: ConversationSettingsPayload,
+}
+
+export interface ConversationSettingsPayload {
+    auto_respond: boolean,
+    stop_sequences: Array<string>,
+    assistant_prefix: string,
+    language: string,
+    /** Empty when the config's system prompt is used. */
+    system_prompt: string,
+    /** Empty when the config's models are used. */
+    model: string,
+    /** The name of the response style preset, null for none. */
+    style_preset: string | null,
+    glossary: Array<GlossaryEntryPayload>,
+    /** The linked repository, null for none. */
+    repository_id: string | null,
+}
+
+export interface ConversationStatsPayload {
+    message_count: number,
+    word_count: number,
+    /** Words inside code blocks, part of `word_count`. */
+    code_word_count: number,
+    /** Estimated, with code read more slowly than prose. */
+    reading_seconds: number,
+    long_read: boolean,
+    /** Prose words per sentence. */
+    average_sentence_length: number,
+    /** Share of the words that are code, from 0 to 1. */
+    code_ratio: number,
+}
+
+/** Replaces the conversation's topic tags; see `tagging`. */
+export interface ConversationTagsChangedEvent {
+    tags: Array<string>,
+    /** Assigned by the model rather than the user. */
+    automatic: boolean,
+}
+
+export interface ConversationTagsChangedEventPayload {
+    conversation_id: string,
+    tags: Array<string>,
+    automatic: boolean,
+}
+
+export interface ConversationTemplateEntryPayload {
+    id: string,
+    template: ConversationTemplatePayload,
+}
+
+/** A conversation template as it's created or edited; see `templates::ConversationTemplate`. */
+export interface ConversationTemplatePayload {
+    name: string,
+    title_pattern: string,
+    system_prompt: string,
+    model: string,
+    auto_respond: boolean,
+    stop_sequences: Array<string>,
+    assistant_prefix: string,
+    language: string,
+    starter_messages: Array<StarterMessagePayload>,
+}
+
+export interface ConversationTitleChangedEvent {
+    new_title: string,
+}
+
+export interface ConversationTitleChangedEventPayload {
+    conversation_id: string,
+    new_title: string,
+}
+
+export interface ConversationsArchivedEventPayload {
+    /** Only the conversations that weren't archived (or unarchived) already. */
+    conversation_ids: Array<string>,
+    archived: boolean,
+}
+
+export interface ConversationsRemovedEventPayload {
+    conversation_ids: Array<string>,
+}
+
+export interface ConversationsTagsChangedEventPayload {
+    /** One per conversation whose tags changed. */
+    changes: Array<ConversationTagsChangedEventPayload>,
+}
+
+export interface CrashReportPayload {
+    occurred_at: number,
+    version: string,
+    message: string,
+    location: string,
+    backtrace: string,
+    recent_commands: Array<string>,
+}
+
+export interface DeepLinkNewConversationEventPayload {
+    conversation_id: string,
+    prompt: string | null,
+}
+
+export interface DeepLinkOpenConversationEventPayload {
+    conversation_id: string,
+}
+
+export type Density = "compact" | "comfortable";
+
+export interface DictationFailedEventPayload {
+    dictation_id: string,
+    conversation_id: string | null,
+    /** What was transcribed before it failed. */
+    text: string,
+    error: string,
+}
+
+export interface DictationTranscriptEventPayload {
+    dictation_id: string,
+    /** The conversation whose composer the dictation is for. */
+    conversation_id: string | null,
+    /** The whole transcript so far. */
+    text: string,
+}
+
+export interface DiffSummaryPayload {
+    /** A commit-message-style summary line of the whole diff. */
+    summary: string,
+    files: Array<FileDiffSummaryPayload>,
+}
+
+export interface EffectiveConfigValuePayload {
+    /** Dotted path of the setting, e.g. `proxy.url`. */
+    key: string,
+    value: unknown,
+    source: ConfigSource,
+}
+
+export interface EmbeddingCacheStatsPayload {
+    entries: number,
+    size_bytes: number,
+    max_size_bytes: number,
+    hits: number,
+    misses: number,
+}
+
+export interface FailedGenerationPayload {
+    conversation_id: string,
+    timestamp: number,
+    error: string,
+    recovered: boolean,
+}
+
+export type FileChange = "added" | "deleted" | "modified" | "renamed";
+
+export interface FileDiffSummaryPayload {
+    path: string,
+    /** The path before a rename. */
+    old_path: string | null,
+    change: FileChange,
+    additions: number,
+    deletions: number,
+    /** Empty when the model didn't summarize the file. */
+    summary: string,
+}
+
+export interface FileOpenedEventPayload {
+    conversation_id: string,
+    /** False when the file started a new chat as its draft rather than being imported. */
+    imported: boolean,
+}
+
+export interface FinetuneExportOptionsPayload {
+    include_system_messages: boolean,
+    system_prompt: string | null,
+    swap_user_assistant: boolean,
+}
+
+export interface FuzzyConversationMatchPayload {
+    conversation_id: string,
+    title: string,
+    /** The tag that matched, or null when the title did. */
+    tag: string | null,
+    /** Character indices into the tag when set, otherwise into the title. */
+    indices: Array<number>,
+    score: number,
+}
+
+export interface GenerationFailedEventPayload {
+    conversation_id: string,
+    error: string,
+}
+
+export interface GenerationUsageEventPayload {
+    conversation_id: string,
+    message_id: string,
+    model: string,
+    /** Unset when the provider didn't report usage. */
+    prompt_tokens: number | null,
+    completion_tokens: number | null,
+    /** Estimated from list prices; unset for models without a known price. */
+    cost_usd: number | null,
+    duration_ms: number,
+}
+
+export interface GlossaryEntry {
+    term: string,
+    definition: string,
+}
+
+export interface GlossaryEntryPayload {
+    term: string,
+    definition: string,
+}
+
+export type GuardrailAction = "flag" | "redact";
+
+export interface GuardrailHit {
+    /** The phrase or pattern that matched, as configured. */
+    rule: string,
+    count: number,
+}
+
+export interface GuardrailHitPayload {
+    /** The phrase or pattern, as configured. */
+    rule: string,
+    count: number,
+}
+
+export interface GuardrailsPayload {
+    /** Matched ignoring case. */
+    banned_phrases: Array<string>,
+    /** Regular expressions. */
+    banned_patterns: Array<string>,
+    action: GuardrailAction,
+}
+
+export interface HistoryLoadProgressEventPayload {
+    /** Conversations loaded so far; listed as soon as they are. */
+    loaded: number,
+    total: number,
+    /** Set once the whole history has loaded and can be saved. */
+    done: boolean,
+}
+
+export interface IntegrityIssuePayload {
+    /** The key the conversation is stored under, which may not be a valid id. */
+    conversation_id: string,
+    event_index: number | null,
+    kind: IssueKind,
+    detail: string,
+    repairable: boolean,
+}
+
+export interface IntegrityReportPayload {
+    conversations: number,
+    events: number,
+    issues: Array<IntegrityIssuePayload>,
+    /** Whether the history was rewritten with the repairable issues fixed. */
+    repaired: boolean,
+    /** The copy kept of the history from before the repair; empty when not repaired. */
+    backup_path: string,
+}
+
+export type IssueKind = "undecodable_file" | "undecodable_conversation" | "checksum_mismatch" | "conversation_id_mismatch" | "undecodable_event" | "foreign_event" | "duplicate_event" | "out_of_order" | "dangling_message_reference" | "missing_attachment";
+
+/** Action name to shortcut, in the accelerator format tauri uses (`CmdOrCtrl+Shift+F`). */
+export type Keybindings = Record<string, string>;
+
+export interface LinkPreviewPayload {
+    url: string,
+    title: string | null,
+    description: string | null,
+    /** Absolute URL of the preview image. */
+    image: string | null,
+    site_name: string | null,
+    /** Unix seconds. */
+    fetched_at: number,
+}
+
+export interface LocalePayload {
+    code: string,
+    name: string,
+    active: boolean,
+}
+
+export interface MatchRangePayload {
+    start: number,
+    end: number,
+}
+
+export interface MessageAnnotatedEventPayload {
+    conversation_id: string,
+    message_id: string,
+    note: string | null,
+}
+
+export interface MessageBookmarkChangedEventPayload {
+    conversation_id: string,
+    message_id: string,
+    bookmarked: boolean,
+}
+
+export interface MessageFilteredEventPayload {
+    conversation_id: string,
+    message_id: string,
+    hits: Array<GuardrailHitPayload>,
+    redacted: boolean,
+}
+
+export interface MessageLengthInfoPayload {
+    characters: number,
+    words: number,
+    tokens: number,
+    /** Estimated cost of sending it as a prompt; null for a model without a known price. */
+    cost_usd: number | null,
+    context_window: number | null,
+}
+
+export interface MessageMatchesPayload {
+    message_id: string,
+    /** Byte offsets into the message's content. */
+    ranges: Array<MatchRangePayload>,
+}
+
+export interface MessagePinnedContextChangedEventPayload {
+    conversation_id: string,
+    message_id: string,
+    pinned_context: boolean,
+}
+
+export interface MessageRatedEventPayload {
+    conversation_id: string,
+    message_id: string,
+    rating: MessageRating | null,
+    comment: string | null,
+}
+
+export type MessageRating = "up" | "down";
+
+export interface MessageTranslatedEventPayload {
+    conversation_id: string,
+    message_id: string,
+    language: string,
+    content: string,
+}
+
+export interface MessageTranslationPayload {
+    language: string,
+    content: string,
+}
+
+export interface ModelCatalogPayload {
+    /** Unix seconds of the last refresh; null before the first. */
+    fetched_at: number | null,
+    models: Array<ModelInfoPayload>,
+}
+
+export interface ModelFeedbackPayload {
+    model: string,
+    thumbs_up: number,
+    thumbs_down: number,
+    approval: number,
+    comments: Array<string>,
+}
+
+export interface ModelInfoPayload {
+    id: string,
+    owned_by: string,
+    /** Tokens of prompt and reply together; null for a model nothing is known about. */
+    context_window: number | null,
+    vision: boolean,
+    /** USD per million tokens; null without a known price. */
+    prompt_price: number | null,
+    completion_price: number | null,
+}
+
+/** USD per million tokens. */
+export interface ModelPricePayload {
+    prompt: number,
+    completion: number,
+}
+
+export interface ModelPricingPayload {
+    /** Applies to every model whose name starts with it that no longer prefix covers. */
+    prefix: string,
+    prompt: number,
+    completion: number,
+    /** Set in the config rather than built in. */
+    overridden: boolean,
+}
+
+export interface ModelTokenUsagePayload {
+    model: string,
+    requests: number,
+    prompt_tokens: number,
+    completion_tokens: number,
+    /** Estimated from the current prices; null for a model without a known price. */
+    cost_usd: number | null,
+}
+
+export interface NamedUsagePayload {
+    name: string,
+    conversations: number,
+}
+
+export interface PendingGenerationPayload {
+    conversation_id: string,
+    /** The message that was being replied to. */
+    prompt: string,
+    /** Whether a partial reply was being continued. */
+    continue_partial: boolean,
+    started_at: number,
+}
+
+export type PlaybackState = "playing" | "paused" | "stopped";
+
+export interface PlaybackStatePayload {
+    /** The conversation being read aloud, none when stopped. */
+    conversation_id: string | null,
+    /** The message being read. */
+    message_id: string | null,
+    state: PlaybackState,
+    /** The index of the message being read among the `message_count` queued. */
+    position: number,
+    message_count: number,
+    speed: number,
+    /** Why playback stopped, when it was because of an error. */
+    error: string | null,
+}
+
+export interface ProgressEventPayload {
+    operation_id: string,
+    kind: ProgressKind,
+    /** Null while the operation can't tell how far along it is. */
+    percent: number | null,
+    message: string,
+    done: boolean,
+}
+
+export type ProgressKind = "import" | "export" | "embedding" | "migration" | "indexing";
+
+export interface ProxyTestResultPayload {
+    reachable: boolean,
+    status: number | null,
+    elapsed_ms: number,
+    error: string | null,
+}
+
+export type QuickSearchKind = "conversation" | "template" | "command" | "bookmark" | "message";
+
+export interface QuickSearchResultPayload {
+    kind: QuickSearchKind,
+    /** The conversation, message or template id, or the command name. */
+    id: string,
+    conversation_id: string | null,
+    title: string,
+    detail: string,
+    /** Character indices into the title that matched. */
+    indices: Array<number>,
+    score: number,
+}
+
+export interface RepositoryPayload {
+    id: string,
+    name: string,
+    path: string,
+    /** Unix seconds. */
+    indexed_at: number,
+    file_count: number,
+    chunk_count: number,
+    /** Whether files were left out for the repository being too large. */
+    truncated: boolean,
+}
+
+export interface RetentionCandidatePayload {
+    conversation_id: string,
+    title: string,
+    reason: RetentionReason,
+    size_bytes: number,
+}
+
+export type RetentionReason = "archived_expired" | "history_size_cap";
+
+export type SearchField = "content" | "note";
+
+export interface SearchHitPayload {
+    conversation_id: string,
+    conversation_title: string,
+    message_id: string,
+    field: SearchField,
+    snippet: string,
+    timestamp: number,
+    language: string | null,
+}
+
+export interface StarterMessagePayload {
+    author: "system" | "user" | "assistant",
+    content: string,
+}
+
+export type StorageCategory = "conversations" | "attachments" | "embeddings" | "logs" | "backups";
+
+export interface StorageUsagePayload {
+    category: StorageCategory,
+    bytes: number,
+    files: number,
+    /** Whether `clean_storage` accepts the category. */
+    cleanable: boolean,
+    /** Whether cleaning the category is worth suggesting. */
+    suggested: boolean,
+}
+
+export interface StylePreset {
+    /** Identifies the preset, compared ignoring case. */
+    name: string,
+    /** Added to the system prompt of conversations using the preset. */
+    instruction: string,
+    temperature?: number | null,
+    /** Caps the length of replies, in tokens. */
+    max_tokens?: number | null,
+}
+
+export interface StylePresetPayload {
+    name: string,
+    /** Added to the system prompt. */
+    instruction: string,
+    temperature: number | null,
+    max_tokens: number | null,
+}
+
+export interface SyncConflictEventPayload {
+    conversation_id: string,
+    local_events: number,
+    remote_events: number,
+}
+
+export interface SyncStatusPayload {
+    state: string,
+    /** Conversations uploaded by the finished sync. */
+    pushed: number,
+    /** Conversations updated from the remote by the finished sync. */
+    pulled: number,
+    /** Conversations that diverged and now wait for `resolve_sync_conflict`. */
+    conflicts: number,
+    error: string | null,
+}
+
+export type Theme = "system" | "light" | "dark";
+
+export interface TrashedConversationPayload {
+    conversation_id: string,
+    title: string,
+    message_count: number,
+    /** Unix seconds. */
+    deleted_at: number,
+    /** When retention deletes it for good, in unix seconds. */
+    purge_at: number,
+}
+
+export interface UnreadCountChangedEventPayload {
+    conversation_id: string,
+    unread_count: number,
+}
+
+export interface UpdateInfoPayload {
+    current_version: string,
+    latest_version: string,
+    update_available: boolean,
+    release_notes: string,
+    download_url: string,
+}
+
+export interface UsageReportPayload {
+    period_start: number | null,
+    chats_per_week: Array<WeeklyChatCountPayload>,
+    tokens_per_model: Array<ModelTokenUsagePayload>,
+    top_templates: Array<NamedUsagePayload>,
+    top_style_presets: Array<NamedUsagePayload>,
+    failed_generations: Array<FailedGenerationPayload>,
+}
+
+export interface WeeklyChatCountPayload {
+    week_start: number,
+    chats: number,
+}
+
+export interface WindowStatePayload {
+    label: string,
+    /** The conversation the window showed when the app was last closed. */
+    conversation_id: string | null,
+}
+
+export interface WorkspaceProfilePayload {
+    name: string,
+    active: boolean,
+}
+
+export type AppError =
+    | { code: "ConversationNotFoundFail", message: string, hint: string | null }
+    | { code: "EmitFail", message: string, hint: string | null }
+    | { code: "ConversationWriteToDiskFail", message: string, hint: string | null }
+    | { code: "NoConfigDirFail", message: string, hint: string | null }
+    | { code: "UserNotLatestAuthorInConversationFail", message: string, hint: string | null }
+    | { code: "ConversationEmptyFail", message: string, hint: string | null }
+    | { code: "ConversationAIResponseFail", message: string, hint: string | null }
+    | { code: "DirListFail", message: string, hint: string | null }
+    | { code: "ProfileNotFoundFail", message: string, hint: string | null }
+    | { code: "ConfigWriteToDiskFail", message: string, hint: string | null }
+    | { code: "ProxyConfigFail", message: string, hint: string | null }
+    | { code: "RequestLogExportFail", message: string, hint: string | null }
+    | { code: "DeepLinkInvalidFail", message: string, hint: string | null }
+    | { code: "DataExportFail", message: string, hint: string | null }
+    | { code: "DeleteConfirmationFail", message: string, hint: string | null }
+    | { code: "DataDeleteFail", message: string, hint: string | null }
+    | { code: "RetentionFail", message: string, hint: string | null }
+    | { code: "UsageReportPeriodFail", message: string, hint: string | null }
+    | { code: "MessageNotFoundFail", message: string, hint: string | null }
+    | { code: "MessageRatingFail", message: string, hint: string | null }
+    | { code: "FinetuneExportFail", message: string, hint: string | null }
+    | { code: "PdfExportFail", message: string, hint: string | null }
+    | { code: "HtmlExportFail", message: string, hint: string | null }
+    | { code: "ImportFail", message: string, hint: string | null }
+    | { code: "KeybindingInvalidFail", message: string, hint: string | null }
+    | { code: "KeybindingConflictFail", message: string, hint: string | null }
+    | { code: "AppearanceInvalidFail", message: string, hint: string | null }
+    | { code: "LocaleUnknownFail", message: string, hint: string | null }
+    | { code: "HistoryLocationInvalidFail", message: string, hint: string | null }
+    | { code: "HistoryMoveFail", message: string, hint: string | null }
+    | { code: "SyncNotConfiguredFail", message: string, hint: string | null }
+    | { code: "SyncFail", message: string, hint: string | null }
+    | { code: "SyncConflictNotFoundFail", message: string, hint: string | null }
+    | { code: "SyncConflictStrategyFail", message: string, hint: string | null }
+    | { code: "WorkspaceNameInvalidFail", message: string, hint: string | null }
+    | { code: "WorkspaceExistsFail", message: string, hint: string | null }
+    | { code: "WorkspaceNotFoundFail", message: string, hint: string | null }
+    | { code: "WorkspaceSwitchFail", message: string, hint: string | null }
+    | { code: "ReadOnlyModeFail", message: string, hint: string | null }
+    | { code: "ConversationLockedFail", message: string, hint: string | null }
+    | { code: "ConversationSettingInvalidFail", message: string, hint: string | null }
+    | { code: "LanguageInvalidFail", message: string, hint: string | null }
+    | { code: "ClusterCountInvalidFail", message: string, hint: string | null }
+    | { code: "SearchPatternInvalidFail", message: string, hint: string | null }
+    | { code: "TaskNotFoundFail", message: string, hint: string | null }
+    | { code: "UpdateCheckFail", message: string, hint: string | null }
+    | { code: "StorageCategoryInvalidFail", message: string, hint: string | null }
+    | { code: "GenerationInterruptedFail", message: string, hint: string | null }
+    | { code: "NoFailedGenerationFail", message: string, hint: string | null }
+    | { code: "IntegrityCheckFail", message: string, hint: string | null }
+    | { code: "IntegrityRepairFail", message: string, hint: string | null }
+    | { code: "HistoryCompressionFail", message: string, hint: string | null }
+    | { code: "ModelCatalogFail", message: string, hint: string | null }
+    | { code: "ArchiveExportFail", message: string, hint: string | null }
+    | { code: "ArchiveImportFail", message: string, hint: string | null }
+    | { code: "ArchiveVersionFail", message: string, hint: string | null }
+    | { code: "ArchiveConversationExistsFail", message: string, hint: string | null }
+    | { code: "TrashFail", message: string, hint: string | null }
+    | { code: "TemplateFail", message: string, hint: string | null }
+    | { code: "TemplateNotFoundFail", message: string, hint: string | null }
+    | { code: "LinkPreviewFail", message: string, hint: string | null }
+    | { code: "ActionItemsFail", message: string, hint: string | null }
+    | { code: "ActionItemNotFoundFail", message: string, hint: string | null }
+    | { code: "ShareFail", message: string, hint: string | null }
+    | { code: "SharedBundleFail", message: string, hint: string | null }
+    | { code: "SharedBundlePassphraseFail", message: string, hint: string | null }
+    | { code: "OpenedFileFail", message: string, hint: string | null }
+    | { code: "StylePresetFail", message: string, hint: string | null }
+    | { code: "StylePresetNotFoundFail", message: string, hint: string | null }
+    | { code: "StylePresetExistsFail", message: string, hint: string | null }
+    | { code: "StylePresetInvalidFail", message: string, hint: string | null }
+    | { code: "GlossaryInvalidFail", message: string, hint: string | null }
+    | { code: "GuardrailPatternInvalidFail", message: string, hint: string | null }
+    | { code: "RepositoryInvalidFail", message: string, hint: string | null }
+    | { code: "RepositoryNotFoundFail", message: string, hint: string | null }
+    | { code: "KnowledgeFail", message: string, hint: string | null }
+    | { code: "DiffInvalidFail", message: string, hint: string | null }
+    | { code: "DiffReadFail", message: string, hint: string | null }
+    | { code: "ScreenshotFail", message: string, hint: string | null }
+    | { code: "ScreenshotTargetNotFoundFail", message: string, hint: string | null }
+    | { code: "AttachmentWriteFail", message: string, hint: string | null }
+    | { code: "MicrophoneFail", message: string, hint: string | null }
+    | { code: "DictationActiveFail", message: string, hint: string | null }
+    | { code: "TranscriptionFail", message: string, hint: string | null }
+    | { code: "SpeakerFail", message: string, hint: string | null }
+    | { code: "SpeechFail", message: string, hint: string | null }
+    | { code: "PlaybackEmptyFail", message: string, hint: string | null }
+    | { code: "PlaybackSpeedInvalidFail", message: string, hint: string | null }
+    | { code: "WindowFail", message: string, hint: string | null }
+    | { code: "WindowLayoutWriteFail", message: string, hint: string | null }
+    | { code: "PendingGenerationNotFoundFail", message: string, hint: string | null }
+    | { code: "GenerationJournalFail", message: string, hint: string | null }
+    | { code: "HistoryLoadingFail", message: string, hint: string | null }
+    | { code: "ArgumentInvalidFail", message: string, hint: string | null, field: string, problem: ArgumentProblem };
+
+export function isAppError(error: unknown): error is AppError {
+    return typeof error === "object" && error !== null && "code" in error && "message" in error;
+}
 
 type TauriCommands = {
+    /** Lists every command with its arguments and keybinding, for the command palette. */
+    list_app_actions: {
+        returns: Array<AppActionPayload>,
+        args: {  },
+        error: AppError
+    },
+    get_appearance: {
+        returns: AppearancePayload,
+        args: {  },
+        error: AppError
+    },
+    /** Persists the appearance and broadcasts it as `appearance_changed` so every window restyles. */
+    set_appearance: {
+        returns: void,
+        args: { appearance: AppearancePayload },
+        error: AppError
+    },
+    list_code_themes: {
+        returns: Array<string>,
+        args: {  },
+        error: AppError
+    },
+    list_locales: {
+        returns: Array<LocalePayload>,
+        args: {  },
+        error: AppError
+    },
+    /** Changes the language of backend strings such as error messages and default titles. */
+    set_locale: {
+        returns: void,
+        args: { locale: string },
+        error: AppError
+    },
+    /**
+     * Every setting as the app currently uses it, secrets masked, with whether it came from
+     * the defaults, the config file, an environment variable or a command line flag.
+     */
+    get_effective_config: {
+        returns: Array<EffectiveConfigValuePayload>,
+        args: {  },
+        error: AppError
+    },
+    /** Version, platform, data locations and provider, for the About dialog and bug reports. */
+    get_app_info: {
+        returns: AppInfoPayload,
+        args: {  },
+        error: AppError
+    },
+    /** Effective shortcuts by action name, defaults included. */
+    get_keybindings: {
+        returns: Record<string, string>,
+        args: {  },
+        error: AppError
+    },
+    /** An empty `shortcut` unbinds the action; binding it to its default again drops the override. */
+    set_keybinding: {
+        returns: void,
+        args: { action: string, shortcut: string },
+        error: AppError
+    },
     list_conversation_titles: {
         returns: Record<string, string>,
-        args: {  }
+        args: {  },
+        error: AppError
+    },
+    list_conversation_tags: {
+        returns: Record<string, Array<string>>,
+        args: {  },
+        error: AppError
+    },
+    /**
+     * Replaces the conversation's tags, normalized and capped at `tagging::MAX_TAGS`; an empty
+     * list clears them and keeps automatic tagging from adding new ones.
+     */
+    set_conversation_tags: {
+        returns: Array<string>,
+        args: { conversation_id: string, tags: Array<string> },
+        error: AppError
+    },
+    /**
+     * Groups the conversations with messages into at most `k` topics, each labeled by the model,
+     * for the sidebar's "by topic" view. Embeddings are cached, so only new or changed
+     * conversations are embedded again.
+     */
+    cluster_conversations: {
+        returns: Array<ConversationClusterPayload>,
+        args: { k: number },
+        error: AppError
+    },
+    list_unread_counts: {
+        returns: Record<string, number>,
+        args: {  },
+        error: AppError
+    },
+    mark_conversation_read: {
+        returns: void,
+        args: { conversation_id: string },
+        error: AppError
     },
     get_conversation: {
         returns: Conversation,
-        args: { conversation_id: string }
+        args: { conversation_id: string },
+        error: AppError
     },
     get_conversation_title: {
         returns: string,
-        args: { conversation_id: string }
+        args: { conversation_id: string },
+        error: AppError
     },
     get_conversation_messages: {
         returns: Array<ConversationMessagePayload>,
-        args: { conversation_id: string }
+        args: { conversation_id: string },
+        error: AppError
     },
     new_conversation: {
         returns: Conversation,
-        args: {  }
+        args: {  },
+        error: AppError
     },
+    /** Renames the conversation, returning the title as stored after `validation::title` normalized it. */
     set_conversation_title: {
+        returns: string,
+        args: { conversation_id: string, new_title: string },
+        error: AppError
+    },
+    /** Called on every keystroke; the disk write is debounced. */
+    save_draft: {
         returns: void,
-        args: { conversation_id: string, new_title: string }
+        args: { conversation_id: string, content: string },
+        error: AppError
+    },
+    /** Empty when the conversation has no draft. */
+    get_draft: {
+        returns: string,
+        args: { conversation_id: string },
+        error: AppError
+    },
+    /** Characters, words and tokens of `content` for `model`, for a live estimate while composing. */
+    get_message_length_info: {
+        returns: MessageLengthInfoPayload,
+        args: { content: string, model: string },
+        error: AppError
+    },
+    /**
+     * Asks the provider which models the active profile's key may use and caches them, with
+     * their context windows, vision support and prices, for the model pickers and token budgets.
+     */
+    refresh_model_catalog: {
+        returns: ModelCatalogPayload,
+        args: {  },
+        error: AppError
+    },
+    /** The models as of the last `refresh_model_catalog`; none before the first. */
+    get_model_catalog: {
+        returns: ModelCatalogPayload,
+        args: {  },
+        error: AppError
     },
     new_conversation_user_message: {
         returns: void,
-        args: { conversation_id: string, content: string }
+        args: { conversation_id: string, content: string },
+        error: AppError
     },
-    new_conversation_assistant_message: {
+    /**
+     * Sends several messages as separate turns, e.g. a long email split into parts or a file
+     * followed by instructions, with at most one reply after the last of them.
+     */
+    new_conversation_user_messages: {
         returns: void,
-        args: { conversation_id: string }
+        args: { conversation_id: string, contents: Array<string> },
+        error: AppError
+    },
+    /**
+     * Asks for an explanation of pasted code in `conversation_id`, or in a new conversation
+     * titled after the language without one. Code too long for one message is explained part by
+     * part, each part a question with its own reply. Returns the conversation's id.
+     */
+    explain_code: {
+        returns: string,
+        args: { conversation_id: string | null, content: string, language: string },
+        error: AppError
+    },
+    /**
+     * Summarizes a unified diff, pasted or read from a .diff or .patch file, the way a commit
+     * message would, with a line for each file changed so review tools can show it by the file.
+     */
+    summarize_diff: {
+        returns: DiffSummaryPayload,
+        args: { path_or_text: string },
+        error: AppError
+    },
+    /** The screens and windows `capture_screenshot` can capture. */
+    list_capture_targets: {
+        returns: Array<CaptureTargetPayload>,
+        args: {  },
+        error: AppError
+    },
+    /**
+     * Captures a screen or window into the attachment store and returns the attachment's name,
+     * which a message shows the image by as `![screenshot](attachment:<name>)`.
+     */
+    capture_screenshot: {
+        returns: string,
+        args: { region: CaptureRegionPayload },
+        error: AppError
+    },
+    /**
+     * Starts dictating into the composer of `conversation_id`, or of whichever is open without
+     * one, transcribing in `language` (an ISO 639-1 code) or whatever is heard. Dictation ends
+     * after a pause or with `stop_dictation`; returns the id its events are sent with.
+     */
+    start_dictation: {
+        returns: string,
+        args: { conversation_id: string | null, language: string | null },
+        error: AppError
+    },
+    /**
+     * Stops recording the dictation; what was said is still transcribed and announced as
+     * `dictation_finished`. False when no dictation was running.
+     */
+    stop_dictation: {
+        returns: boolean,
+        args: {  },
+        error: AppError
+    },
+    /**
+     * Reads the conversation aloud from `from_message_id`, or from the start, announcing every
+     * change as `playback_state_changed`. Resumes instead when the conversation is paused.
+     */
+    play_conversation: {
+        returns: PlaybackStatePayload,
+        args: { conversation_id: string, from_message_id: string | null },
+        error: AppError
+    },
+    /** Pauses reading aloud where it is, to carry on with `play_conversation`. */
+    pause_playback: {
+        returns: PlaybackStatePayload,
+        args: {  },
+        error: AppError
+    },
+    /** Moves on to the next message, stopping after the last one. */
+    skip_message: {
+        returns: PlaybackStatePayload,
+        args: {  },
+        error: AppError
+    },
+    /** Stops reading aloud; `play_conversation` starts over. */
+    stop_playback: {
+        returns: PlaybackStatePayload,
+        args: {  },
+        error: AppError
+    },
+    /** Sets how fast messages are read, from the next part read on. */
+    set_playback_speed: {
+        returns: PlaybackStatePayload,
+        args: { speed: number },
+        error: AppError
+    },
+    /** What is being read aloud and how far along it is. */
+    get_playback_state: {
+        returns: PlaybackStatePayload,
+        args: {  },
+        error: AppError
+    },
+    /** Opens another window, showing `conversation_id` when given; returns the window's label. */
+    open_window: {
+        returns: string,
+        args: { conversation_id: string | null },
+        error: AppError
+    },
+    /** Records the conversation the calling window shows, to show it again on the next start. */
+    set_window_conversation: {
+        returns: void,
+        args: { conversation_id: string | null },
+        error: AppError
+    },
+    /**
+     * What the calling window showed when the app was last closed, as `restore_window_state`
+     * announces once its page has loaded.
+     */
+    get_window_state: {
+        returns: WindowStatePayload,
+        args: {  },
+        error: AppError
+    },
+    /**
+     * Replies to every message since the last reply at once, e.g. after sending several
+     * messages with auto-respond off.
+     */
+    generate_response: {
+        returns: void,
+        args: { conversation_id: string },
+        error: AppError
+    },
+    /**
+     * Continues the partial reply `message_id`, left by a generation that broke off, from where
+     * it stopped. The finished reply replaces the partial one.
+     */
+    continue_generation: {
+        returns: void,
+        args: { message_id: string },
+        error: AppError
+    },
+    /** Generates the reply whose last attempt failed, sending the same messages again. */
+    retry_failed_generation: {
+        returns: void,
+        args: { conversation_id: string },
+        error: AppError
+    },
+    /**
+     * Replies an earlier run was generating when it crashed or was closed, as announced by
+     * `pending_generation_found` once the history has loaded. Oldest first.
+     */
+    list_pending_generations: {
+        returns: Array<PendingGenerationPayload>,
+        args: {  },
+        error: AppError
+    },
+    /**
+     * Generates the reply an earlier run was interrupted in, first sending its prompt again if
+     * the history lost it. Nothing is generated when the reply turns out to have been saved.
+     */
+    resume_pending_generation: {
+        returns: void,
+        args: { conversation_id: string },
+        error: AppError
+    },
+    /** Forgets the reply an earlier run was interrupted in, leaving the conversation as it is. */
+    discard_pending_generation: {
+        returns: void,
+        args: { conversation_id: string },
+        error: AppError
+    },
+    /** Workspace profiles, each with its own config, API keys and history. */
+    list_profiles: {
+        returns: Array<WorkspaceProfilePayload>,
+        args: {  },
+        error: AppError
+    },
+    /** Creates an empty workspace profile; an empty `openai_api_key` leaves it without API keys. */
+    create_profile: {
+        returns: void,
+        args: { name: string, openai_api_key: string },
+        error: AppError
+    },
+    /** Switches to another workspace profile, reloading the config, history and logs in place. */
+    switch_profile: {
+        returns: void,
+        args: { name: string },
+        error: AppError
+    },
+    list_api_key_profiles: {
+        returns: Array<string>,
+        args: {  },
+        error: AppError
+    },
+    set_active_profile: {
+        returns: void,
+        args: { profile_name: string },
+        error: AppError
+    },
+    pin_conversation_profile: {
+        returns: void,
+        args: { conversation_id: string, profile_name: string },
+        error: AppError
+    },
+    unpin_conversation_profile: {
+        returns: void,
+        args: { conversation_id: string },
+        error: AppError
+    },
+    test_proxy: {
+        returns: ProxyTestResultPayload,
+        args: {  },
+        error: AppError
+    },
+    /** Compares the running version with the latest GitHub release. */
+    check_for_updates: {
+        returns: UpdateInfoPayload,
+        args: {  },
+        error: AppError
+    },
+    enable_request_recording: {
+        returns: void,
+        args: { enabled: boolean },
+        error: AppError
+    },
+    /** Opts in to (or out of) the model tagging conversations by topic. */
+    set_auto_tagging: {
+        returns: void,
+        args: { enabled: boolean },
+        error: AppError
+    },
+    /**
+     * Sets the model replies come from and the models to fall back on, in order, when it's
+     * unavailable or a conversation outgrows its context. Returns the models as they'll be tried.
+     */
+    set_models: {
+        returns: Array<string>,
+        args: { model: string, fallback_models: Array<string> },
+        error: AppError
+    },
+    /**
+     * The prices cost estimates and usage reports are computed with, in USD per million tokens
+     * by model name prefix, marking the ones the config overrides.
+     */
+    get_model_pricing: {
+        returns: Array<ModelPricingPayload>,
+        args: {  },
+        error: AppError
+    },
+    /**
+     * Overrides the price of the models starting with `model`, for when the provider's prices
+     * changed or it isn't OpenAI; without a price it goes back to the built-in one. Returns the
+     * new table.
+     */
+    set_model_pricing: {
+        returns: Array<ModelPricingPayload>,
+        args: { model: string, price: ModelPricePayload | null },
+        error: AppError
+    },
+    /** Turns the startup and daily update checks on or off; see `check_for_updates`. */
+    set_auto_update_check: {
+        returns: void,
+        args: { enabled: boolean },
+        error: AppError
+    },
+    get_guardrails: {
+        returns: GuardrailsPayload,
+        args: {  },
+        error: AppError
+    },
+    /**
+     * Sets the phrases and patterns replies are checked for once generated, and whether they're
+     * redacted or only flagged. Blank entries are dropped; a pattern that isn't a valid regular
+     * expression is refused.
+     */
+    set_guardrails: {
+        returns: GuardrailsPayload,
+        args: { guardrails: GuardrailsPayload },
+        error: AppError
+    },
+    /**
+     * Stores the history and attachments zstd-compressed, or plainly again, rewriting what's
+     * already stored so the whole profile uses one format.
+     */
+    set_history_compression: {
+        returns: void,
+        args: { enabled: boolean },
+        error: AppError
+    },
+    /** Turns read-only (guest/demo) mode on or off, announced as `read_only_changed`. */
+    set_read_only: {
+        returns: void,
+        args: { enabled: boolean },
+        error: AppError
+    },
+    export_request_log: {
+        returns: number,
+        args: { target_path: string },
+        error: AppError
+    },
+    bookmark_message: {
+        returns: void,
+        args: { conversation_id: string, message_id: string },
+        error: AppError
+    },
+    unbookmark_message: {
+        returns: void,
+        args: { conversation_id: string, message_id: string },
+        error: AppError
+    },
+    list_bookmarks: {
+        returns: Array<BookmarkPayload>,
+        args: {  },
+        error: AppError
+    },
+    /**
+     * Pinned context is sent with every request instead of being left out with the oldest
+     * messages once the conversation outgrows the model's context window.
+     */
+    set_message_pinned_context: {
+        returns: void,
+        args: { message_id: string, pinned_context: boolean },
+        error: AppError
+    },
+    /** Attaches a private note to a message; an empty note removes it. */
+    annotate_message: {
+        returns: void,
+        args: { message_id: string, note: string },
+        error: AppError
+    },
+    /**
+     * The sources a message cites. With `fetch_titles`, each page's title is looked up as well,
+     * which takes a request per source; read-only mode skips it.
+     */
+    get_message_citations: {
+        returns: Array<CitationPayload>,
+        args: { message_id: string, fetch_titles: boolean },
+        error: AppError
+    },
+    /**
+     * The OpenGraph or Twitter card preview of the page at `url`, for rendering a link card.
+     * Previews are cached for a week; read-only mode only has the cached ones.
+     */
+    get_link_preview: {
+        returns: LinkPreviewPayload,
+        args: { url: string },
+        error: AppError
+    },
+    /**
+     * Translates a message into `target_lang`, e.g. "German", storing the translation next to
+     * the original. Announced as `message_translated`.
+     */
+    translate_message: {
+        returns: string,
+        args: { message_id: string, target_lang: string },
+        error: AppError
+    },
+    /**
+     * Asks the model for the conversation's action items and adds the ones it doesn't have yet
+     * to the task list, returning those.
+     */
+    extract_action_items: {
+        returns: Array<ActionItemPayload>,
+        args: { conversation_id: string },
+        error: AppError
+    },
+    /**
+     * Action items of every conversation in the order they were extracted, those done only
+     * with `include_done`.
+     */
+    list_tasks: {
+        returns: Array<ActionItemPayload>,
+        args: { include_done: boolean },
+        error: AppError
+    },
+    /** Marks an action item done, or with `done` false, to do again. */
+    complete_task: {
+        returns: ActionItemPayload,
+        args: { task_id: string, done: boolean },
+        error: AppError
+    },
+    /**
+     * Reminds the user of an action item at `remind_at`, in Unix seconds, announced as
+     * `task_reminder_due` for the frontend to show as a system notification. Replaces any
+     * earlier reminder of the item.
+     */
+    set_task_reminder: {
+        returns: ActionItemPayload,
+        args: { task_id: string, remind_at: number },
+        error: AppError
+    },
+    /** Reminds the user of an action item again in `minutes`. */
+    snooze_task: {
+        returns: ActionItemPayload,
+        args: { task_id: string, minutes: number },
+        error: AppError
+    },
+    cancel_task_reminder: {
+        returns: ActionItemPayload,
+        args: { task_id: string },
+        error: AppError
+    },
+    /** `rating` is "up", "down", or "none" to withdraw it; an empty comment is dropped. */
+    rate_message: {
+        returns: void,
+        args: { message_id: string, rating: string, comment: string },
+        error: AppError
+    },
+    get_feedback_report: {
+        returns: Array<ModelFeedbackPayload>,
+        args: {  },
+        error: AppError
+    },
+    search_messages: {
+        returns: Array<SearchHitPayload>,
+        args: { query: string, language: string },
+        error: AppError
+    },
+    /** Quick-switcher results: conversations whose title or tags fuzzily match `query`, best first. */
+    fuzzy_find_conversations: {
+        returns: Array<FuzzyConversationMatchPayload>,
+        args: { query: string, limit: number },
+        error: AppError
+    },
+    /**
+     * Find-in-page for one conversation: the messages containing `query`, in order, with the byte
+     * offsets of each match.
+     */
+    search_in_conversation: {
+        returns: Array<MessageMatchesPayload>,
+        args: { conversation_id: string, query: string, regex: boolean },
+        error: AppError
+    },
+    /**
+     * The search overlay's results for `query`: conversation titles, message text, bookmarks,
+     * templates and commands, ranked together best first.
+     */
+    quick_search: {
+        returns: Array<QuickSearchResultPayload>,
+        args: { query: string },
+        error: AppError
+    },
+    set_conversation_archived: {
+        returns: void,
+        args: { conversation_id: string, archived: boolean },
+        error: AppError
+    },
+    /**
+     * Moves the conversations to the trash and deletes their drafts, all or none, returning how
+     * many were deleted. With `trash_days` at 0 they're deleted for good right away. Refused
+     * when any of them is locked.
+     */
+    bulk_delete: {
+        returns: number,
+        args: { conversation_ids: Array<string> },
+        error: AppError
+    },
+    /** Deleted conversations that can still be restored, most recently deleted first. */
+    list_trashed_conversations: {
+        returns: Array<TrashedConversationPayload>,
+        args: {  },
+        error: AppError
+    },
+    /** Moves a deleted conversation from the trash back into the history. */
+    restore_conversation: {
+        returns: void,
+        args: { conversation_id: string },
+        error: AppError
+    },
+    /** Deletes everything in the trash for good, returning how many conversations that was. */
+    empty_trash: {
+        returns: number,
+        args: {  },
+        error: AppError
+    },
+    /** Conversation templates, in the order they were created. */
+    list_templates: {
+        returns: Array<ConversationTemplateEntryPayload>,
+        args: {  },
+        error: AppError
+    },
+    create_template: {
+        returns: ConversationTemplateEntryPayload,
+        args: { template: ConversationTemplatePayload },
+        error: AppError
+    },
+    /** Replaces a template; conversations already made from it stay as they are. */
+    update_template: {
+        returns: ConversationTemplateEntryPayload,
+        args: { template_id: string, template: ConversationTemplatePayload },
+        error: AppError
+    },
+    delete_template: {
+        returns: void,
+        args: { template_id: string },
+        error: AppError
+    },
+    /**
+     * A new conversation with the template's title, settings and starter messages, announced as
+     * `new_conversation`. Nothing is sent until the user sends a message.
+     */
+    new_conversation_from_template: {
+        returns: Conversation,
+        args: { template_id: string },
+        error: AppError
+    },
+    /** Archives or unarchives the conversations together, returning how many changed. */
+    bulk_archive: {
+        returns: number,
+        args: { conversation_ids: Array<string>, archived: boolean },
+        error: AppError
+    },
+    /**
+     * Adds the tags in `add` to every conversation and takes those in `remove` off, normalized
+     * and capped at `tagging::MAX_TAGS` like `set_conversation_tags`. Returns the tags of each
+     * conversation afterwards.
+     */
+    bulk_tag: {
+        returns: Record<string, Array<string>>,
+        args: { conversation_ids: Array<string>, add: Array<string>, remove: Array<string> },
+        error: AppError
+    },
+    /**
+     * Writes the conversations into one zip of `.ehya` archives, each importable with
+     * `import_conversation_archive` once unpacked. Returns how many were exported.
+     */
+    bulk_export: {
+        returns: number,
+        args: { conversation_ids: Array<string>, path: string },
+        error: AppError
+    },
+    get_conversation_settings: {
+        returns: ConversationSettingsPayload,
+        args: { conversation_id: string },
+        error: AppError
+    },
+    /** Reading time and how dense the conversation is, for a "long read" indicator. */
+    get_conversation_stats: {
+        returns: ConversationStatsPayload,
+        args: { conversation_id: string },
+        error: AppError
+    },
+    /** With auto-respond off, sent messages wait for `generate_response`. */
+    set_conversation_auto_respond: {
+        returns: void,
+        args: { conversation_id: string, enabled: boolean },
+        error: AppError
+    },
+    /** Replies end before any of the sequences; an empty list removes them. */
+    set_conversation_stop_sequences: {
+        returns: void,
+        args: { conversation_id: string, stop_sequences: Array<string> },
+        error: AppError
+    },
+    /** Replies start with `prefix`; an empty prefix removes it. */
+    set_conversation_assistant_prefix: {
+        returns: void,
+        args: { conversation_id: string, prefix: string },
+        error: AppError
+    },
+    /** Replies are written in `language`, e.g. "German"; an empty language removes the preference. */
+    set_conversation_language: {
+        returns: void,
+        args: { conversation_id: string, language: string },
+        error: AppError
+    },
+    /**
+     * Replaces the system prompt from the config for this conversation; an empty prompt goes
+     * back to the config's.
+     */
+    set_conversation_system_prompt: {
+        returns: void,
+        args: { conversation_id: string, system_prompt: string },
+        error: AppError
+    },
+    /**
+     * Replies come from `model`, with the config's models as fallbacks; an empty model goes back
+     * to the config's alone.
+     */
+    set_conversation_model: {
+        returns: void,
+        args: { conversation_id: string, model: string },
+        error: AppError
+    },
+    /**
+     * Indexes the git repository at `path` for questions about its code: the files it doesn't
+     * ignore are chunked and embedded, and conversations linked to it with
+     * `link_conversation_repository` get the chunks relevant to each question. Indexing it again
+     * updates it, embedding only what changed.
+     */
+    index_repository: {
+        returns: RepositoryPayload,
+        args: { path: string },
+        error: AppError
+    },
+    list_repositories: {
+        returns: Array<RepositoryPayload>,
+        args: {  },
+        error: AppError
+    },
+    /** Forgets the indexed repository. Conversations linked to it stop getting its code. */
+    remove_repository: {
+        returns: void,
+        args: { repository_id: string },
+        error: AppError
+    },
+    /**
+     * Links the conversation to an indexed repository, whose code relevant to each question is
+     * then added to the system prompt; without a repository the link is removed.
+     */
+    link_conversation_repository: {
+        returns: void,
+        args: { conversation_id: string, repository_id: string | null },
+        error: AppError
+    },
+    /** Replies use the glossary's terms as defined; an empty glossary removes it. */
+    set_conversation_glossary: {
+        returns: void,
+        args: { conversation_id: string, glossary: Array<GlossaryEntryPayload> },
+        error: AppError
+    },
+    /**
+     * Replies follow the style preset named `preset`, as it is now; an empty name removes the
+     * conversation's preset.
+     */
+    apply_preset: {
+        returns: void,
+        args: { conversation_id: string, preset: string },
+        error: AppError
+    },
+    /** Response style presets, the built-in ones first. */
+    list_style_presets: {
+        returns: Array<StylePresetPayload>,
+        args: {  },
+        error: AppError
+    },
+    create_style_preset: {
+        returns: StylePresetPayload,
+        args: { preset: StylePresetPayload },
+        error: AppError
+    },
+    /**
+     * Replaces the preset named `name`, which `preset` may rename. Conversations the preset was
+     * applied to keep it as it was.
+     */
+    update_style_preset: {
+        returns: StylePresetPayload,
+        args: { name: string, preset: StylePresetPayload },
+        error: AppError
+    },
+    delete_style_preset: {
+        returns: void,
+        args: { name: string },
+        error: AppError
+    },
+    /** Protects a finished conversation from accidental new messages, renames and setting changes. */
+    lock_conversation: {
+        returns: void,
+        args: { conversation_id: string },
+        error: AppError
+    },
+    unlock_conversation: {
+        returns: void,
+        args: { conversation_id: string },
+        error: AppError
+    },
+    /** Dry run of the retention rules: what the next background pass would delete. */
+    preview_retention: {
+        returns: Array<RetentionCandidatePayload>,
+        args: {  },
+        error: AppError
+    },
+    enforce_retention: {
+        returns: number,
+        args: {  },
+        error: AppError
+    },
+    /** `period` is one of "week", "month", "year" or "all". */
+    get_usage_report: {
+        returns: UsageReportPayload,
+        args: { period: string },
+        error: AppError
+    },
+    export_conversation_pdf: {
+        returns: void,
+        args: { conversation_id: string, path: string },
+        error: AppError
+    },
+    /** Writes a single self-contained HTML file for sharing outside the app. */
+    publish_conversation_html: {
+        returns: void,
+        args: { conversation_id: string, path: string },
+        error: AppError
+    },
+    /** Reports which importer would handle the file, so one import button can cover every format. */
+    detect_import_format: {
+        returns: string,
+        args: { path: string },
+        error: AppError
+    },
+    /** Adds every conversation found in the file, returning the new conversation ids. */
+    import_conversations: {
+        returns: Array<string>,
+        args: { path: string },
+        error: AppError
+    },
+    /**
+     * Writes the conversation with every event and the attachments it shows as a `.ehya`
+     * archive, to be imported on another machine with `import_conversation_archive`.
+     */
+    export_conversation_archive: {
+        returns: void,
+        args: { conversation_id: string, path: string },
+        error: AppError
+    },
+    /**
+     * Adds the conversation in a `.ehya` archive as it was exported, ids and timestamps included,
+     * returning its id. Refused when the conversation is already in the history.
+     */
+    import_conversation_archive: {
+        returns: string,
+        args: { path: string },
+        error: AppError
+    },
+    /**
+     * Writes the conversation like `export_conversation_archive` does, encrypted with
+     * `passphrase`, as a `.ehyas` bundle in the shared bundles folder. Returns the bundle's path,
+     * for sending it on; it's opened with `import_shared_bundle` and the same passphrase.
+     */
+    share_conversation_encrypted: {
+        returns: string,
+        args: { conversation_id: string, passphrase: string },
+        error: AppError
+    },
+    /**
+     * Adds the conversation in a `.ehyas` bundle from `share_conversation_encrypted`, decrypted
+     * with `passphrase`, returning its id. Refused when the conversation is already in the
+     * history.
+     */
+    import_shared_bundle: {
+        returns: string,
+        args: { path: string, passphrase: string },
+        error: AppError
+    },
+    /** Writes the selected conversations as chat-format JSONL, returning the number of examples. */
+    export_finetune_dataset: {
+        returns: number,
+        args: { conversation_ids: Array<string>, path: string, options: FinetuneExportOptionsPayload },
+        error: AppError
+    },
+    export_all_data: {
+        returns: number,
+        args: { target_path: string },
+        error: AppError
+    },
+    /**
+     * Moves the conversation history to `new_path` and switches to it without a restart.
+     * The old file is kept as a backup, so nothing is lost if the new location turns out to be
+     * wrong; `clean_storage` removes it.
+     */
+    set_history_location: {
+        returns: void,
+        args: { new_path: string },
+        error: AppError
+    },
+    /**
+     * Pushes local conversation changes to the configured sync backend and applies remote ones,
+     * reporting progress through `sync_status` events.
+     */
+    sync_now: {
+        returns: void,
+        args: {  },
+        error: AppError
+    },
+    /** Ids of conversations whose local and remote histories diverged and await resolution. */
+    list_sync_conflicts: {
+        returns: Array<string>,
+        args: {  },
+        error: AppError
+    },
+    /**
+     * Settles a sync conflict with `merge_by_timestamp`, `keep_local` or `keep_remote`, and
+     * uploads the result so other devices pick it up.
+     */
+    resolve_sync_conflict: {
+        returns: void,
+        args: { conversation_id: string, strategy: string },
+        error: AppError
+    },
+    /** Erases conversations, attachments, the request log and usage analytics. Settings and API keys are kept. */
+    delete_all_data: {
+        returns: void,
+        args: { confirmation: string },
+        error: AppError
+    },
+    /** Disk usage by category, with which categories are worth cleaning. */
+    get_storage_report: {
+        returns: Array<StorageUsagePayload>,
+        args: {  },
+        error: AppError
+    },
+    /** Deletes the data of the given cleanable categories, returning the bytes reclaimed. */
+    clean_storage: {
+        returns: number,
+        args: { categories: Array<string> },
+        error: AppError
+    },
+    /** Size and hit rate of the embeddings cache. */
+    get_embedding_cache_stats: {
+        returns: EmbeddingCacheStatsPayload,
+        args: {  },
+        error: AppError
+    },
+    /** Empties the embeddings cache, so topic grouping embeds every conversation again. */
+    clear_embedding_cache: {
+        returns: void,
+        args: {  },
+        error: AppError
+    },
+    /** Background work currently running, oldest first. */
+    list_background_tasks: {
+        returns: Array<BackgroundTaskPayload>,
+        args: {  },
+        error: AppError
+    },
+    cancel_task: {
+        returns: void,
+        args: { id: string },
+        error: AppError
+    },
+    /** The last panic of the previous run, if any; empty when it ran without one. */
+    get_last_crash_report: {
+        returns: Array<CrashReportPayload>,
+        args: {  },
+        error: AppError
+    },
+    open_deep_link: {
+        returns: void,
+        args: { url: string },
+        error: AppError
+    },
+    /**
+     * Opens a `.ehya` archive, Markdown or text file handed over by the OS or dropped on the
+     * window: archives and Markdown transcripts are imported, other files start a new chat with
+     * them as its draft. Announced as `file_opened`.
+     */
+    handle_opened_file: {
+        returns: FileOpenedEventPayload,
+        args: { path: string },
+        error: AppError
+    },
+    /** Returns the files the app was launched with; the frontend opens them once it's listening. */
+    take_pending_opened_files: {
+        returns: Array<string>,
+        args: {  },
+        error: AppError
+    },
+    /** Returns the links the app was launched with; the frontend opens them once it's listening. */
+    take_pending_deep_links: {
+        returns: Array<string>,
+        args: {  },
+        error: AppError
     },
     list_files: {
         returns: Array<string>,
-        args: {  }
+        args: {  },
+        error: AppError
+    },
+    /**
+     * Checks the stored history for damage such as unreadable events or missing attachments.
+     * With `repair`, fixes what can be fixed after keeping a backup of the file, which
+     * `backup_path` names.
+     */
+    verify_history_integrity: {
+        returns: IntegrityReportPayload,
+        args: { repair: boolean },
+        error: AppError
+    },
+    /**
+     * What was wrong with the history when it was loaded on startup or on switching profiles;
+     * empty when it loaded as it was.
+     */
+    get_history_recovery_report: {
+        returns: Array<IntegrityReportPayload>,
+        args: {  },
+        error: AppError
     }
 };
 
+/** Rejects with the command's `error`. */
 export function invoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T]["args"]): Promise<TauriCommands[T]["returns"]> {
     return invokeRaw(cmd, args);
-}
\ No newline at end of file
+}
+
+export type InvokeResult<T extends keyof TauriCommands> =
+    | { ok: true, value: TauriCommands[T]["returns"] }
+    | { ok: false, error: TauriCommands[T]["error"] };
+
+/** Resolves with the command's result or its `error`, so callers have to handle both. */
+export async function tryInvoke<T extends keyof TauriCommands>(cmd: T, args: TauriCommands[T]["args"]): Promise<InvokeResult<T>> {
+    try {
+        return { ok: true, value: await invokeRaw(cmd, args) };
+    } catch (error) {
+        return { ok: false, error: error as TauriCommands[T]["error"] };
+    }
+}
+
+type TauriEvents = {
+    all_data_deleted: null,
+    appearance_changed: AppearancePayload,
+    assistant_typing_started: AssistantTypingEventPayload,
+    assistant_typing_stopped: AssistantTypingEventPayload,
+    config_changed: Array<ConfigChangePayload>,
+    conversation_lock_changed: ConversationLockChangedEventPayload,
+    conversation_message_added: ConversationMessageAddedEventPayload,
+    conversation_message_partial: ConversationMessagePartialEventPayload,
+    conversation_reloaded: ConversationReloadedEventPayload,
+    conversation_settings_changed: ConversationSettingsChangedEventPayload,
+    conversation_tags_changed: ConversationTagsChangedEventPayload,
+    conversation_title_changed: ConversationTitleChangedEventPayload,
+    conversations_archived: ConversationsArchivedEventPayload,
+    conversations_removed: ConversationsRemovedEventPayload,
+    conversations_tags_changed: ConversationsTagsChangedEventPayload,
+    deep_link_new_conversation: DeepLinkNewConversationEventPayload,
+    deep_link_open_conversation: DeepLinkOpenConversationEventPayload,
+    dictation_failed: DictationFailedEventPayload,
+    dictation_finished: DictationTranscriptEventPayload,
+    dictation_partial: DictationTranscriptEventPayload,
+    file_opened: FileOpenedEventPayload,
+    generation_failed: GenerationFailedEventPayload,
+    generation_usage: GenerationUsageEventPayload,
+    history_load_progress: HistoryLoadProgressEventPayload,
+    history_location_changed: string,
+    keybindings_changed: Keybindings,
+    locale_changed: string,
+    message_annotated: MessageAnnotatedEventPayload,
+    message_bookmark_changed: MessageBookmarkChangedEventPayload,
+    message_filtered: MessageFilteredEventPayload,
+    message_pinned_context_changed: MessagePinnedContextChangedEventPayload,
+    message_rated: MessageRatedEventPayload,
+    message_translated: MessageTranslatedEventPayload,
+    new_conversation: ConversationAddedEvent,
+    pending_generation_found: PendingGenerationPayload,
+    playback_state_changed: PlaybackStatePayload,
+    profile_switched: string,
+    progress: ProgressEventPayload,
+    read_only_changed: boolean,
+    sync_conflict: SyncConflictEventPayload,
+    sync_conflict_resolved: string,
+    sync_status: SyncStatusPayload,
+    task_reminder_due: ActionItemPayload,
+    unread_count_changed: UnreadCountChangedEventPayload,
+    update_available: UpdateInfoPayload
+};
+
+export function listen<E extends keyof TauriEvents>(event: E, handler: EventCallback<TauriEvents[E]>): Promise<UnlistenFn> {
+    return listenRaw(event, handler);
+}